@@ -1,33 +1,495 @@
-use clap::Parser;
-use plainsight;
-use std::path::PathBuf;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum, parser::ValueSource};
+use std::{collections::BTreeMap, path::PathBuf, sync::Arc, time::Duration};
+
+mod progress;
+
+const AUTHORIZATION_ENV: &str = "PLAINSIGHT_OLLAMA_AUTHORIZATION";
 
 #[derive(Debug, Parser)]
 #[command(name = "plainsight")]
-#[command(about = "Generate source documentation with local Ollama models")]
+#[command(about = "Generate source documentation with local LLMs")]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Project root directory to scan.
     #[arg(value_name = "PROJECT_ROOT", default_value = ".")]
     project_root: PathBuf,
 
     /// Docs output root directory.
-    #[arg(long, value_name = "DOCS_ROOT", default_value = "docs")]
+    #[arg(long, value_name = "DOCS_ROOT", default_value = "docs", global = true)]
     docs_root: PathBuf,
 
+    /// Leave the docs root as it is and write the files a run generates under this directory
+    /// instead, laid out as under the docs root. Previous outputs are still read from the docs
+    /// root.
+    #[arg(long, value_name = "DIR", global = true)]
+    docs_out: Option<PathBuf>,
+
     /// Project name used under docs root (defaults to project root folder name).
-    #[arg(long, value_name = "NAME")]
+    #[arg(long, value_name = "NAME", global = true)]
     project_name: Option<String>,
+
+    /// Only document files under this directory (relative to the project root).
+    #[arg(long, value_name = "PATH")]
+    subtree: Option<PathBuf>,
+
+    /// Treat the project root as a workspace: document each Cargo workspace member, pnpm or
+    /// Yarn workspace package and Maven module as its own project, and link them from
+    /// `workspace.md` at the docs root.
+    #[arg(long, conflicts_with_all = ["subtree", "dry_run", "project_name"])]
+    workspace: bool,
+
+    /// Only regenerate files git lists as changed since this commit, branch or range (e.g.
+    /// `origin/main...HEAD`), whether or not their content hash changed.
+    #[arg(long, value_name = "REV")]
+    changed_since: Option<String>,
+
+    /// Continue a run that stopped before finishing, keeping the file summaries and docs it
+    /// wrote for files that did not change since.
+    #[arg(long)]
+    resume: bool,
+
+    /// Roll the file summaries up into a summary per source directory and write the project
+    /// summary and architecture docs from them.
+    #[arg(long)]
+    directory_summaries: bool,
+
+    /// What the architecture docs are written from besides the module tree.
+    #[arg(long, value_enum, default_value = "chunk-previews")]
+    architecture_context: ArchitectureContext,
+
+    /// Keep the docs of source files that were deleted or renamed instead of removing them.
+    #[arg(long)]
+    keep_stale: bool,
+
+    /// Regenerate every file summary, file's docs and project-level page, changed or not.
+    #[arg(long)]
+    force: bool,
+
+    /// Regenerate every file summary, changed or not.
+    #[arg(long)]
+    force_summaries: bool,
+
+    /// Regenerate every file's docs, changed or not.
+    #[arg(long)]
+    force_docs: bool,
+
+    /// Regenerate the project summary, architecture and directory summaries, changed or not.
+    #[arg(long)]
+    force_project_level: bool,
+
+    /// Print the files a run would regenerate and reuse and the requests it would send, without
+    /// contacting the LLM server or writing to the docs root.
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Do not show a progress bar of the files generated in each phase.
+    #[arg(long)]
+    no_progress: bool,
+
+    /// API of the LLM server: `ollama`, or `openai` for OpenAI-compatible servers such as
+    /// llama.cpp, vLLM or LM Studio.
+    #[arg(long, value_name = "KIND", default_value = "ollama", value_parser = ["ollama", "openai"])]
+    backend: String,
+
+    /// LLM server to use (`http[s]://host[:port]`); defaults to http://127.0.0.1:11434.
+    ///
+    /// An `Authorization` header (an API key for OpenAI-compatible servers, or for servers
+    /// behind a proxy) is read from `PLAINSIGHT_OLLAMA_AUTHORIZATION`.
+    #[arg(long = "llm-url", alias = "ollama-url", value_name = "URL")]
+    llm_url: Option<String>,
+
+    /// Number of files generated at once; raise it for servers that handle parallel requests.
+    #[arg(long, value_name = "N", default_value_t = 1)]
+    max_concurrency: usize,
+
+    /// Send at most this many requests per minute, retries included, to leave room for other
+    /// users of a shared server.
+    #[arg(long, value_name = "N")]
+    requests_per_minute: Option<u32>,
+
+    /// When models are unloaded: as each phase finishes, so that one model is loaded at a time,
+    /// or only when the server's keep-alive runs out.
+    #[arg(long, value_enum, default_value = "after-phase")]
+    model_unloading: ModelUnloading,
+
+    /// GPU memory the task models may fill together, in GiB. Models that do not fit together
+    /// are scheduled so that each is loaded once; by default the server's reports are used.
+    #[arg(long, value_name = "GIB")]
+    gpu_memory: Option<f64>,
+
+    /// Pull task models the Ollama server does not have before generating, instead of failing.
+    #[arg(long)]
+    pull_missing_models: bool,
+
+    /// Skip a file whose summary or docs take longer than this, and add it to the project's
+    /// `.skip.json` so later runs leave it out until it is removed from there.
+    #[arg(long, value_name = "SECS")]
+    file_timeout: Option<u64>,
+
+    /// Once this many requests have failed, retries included, or files have run over
+    /// `--file-timeout`, start the remaining files from the compact context.
+    #[arg(long, value_name = "N")]
+    retry_budget: Option<usize>,
+
+    /// When the retry budget is spent, pause for this long and count again instead of
+    /// switching to the compact context.
+    #[arg(long, value_name = "SECS", requires = "retry_budget")]
+    retry_cooldown: Option<u64>,
+
+    /// Summarize files of up to 30 lines several to a request, one section per file, instead
+    /// of one request each. Ignored with `--structured-output`.
+    #[arg(long)]
+    batch_small_files: bool,
+
+    /// Do not offer the source and project memory tools, for models without tool calling.
+    #[arg(long)]
+    no_tools: bool,
+
+    /// Have the model reply with JSON sections (Ollama 0.5+ `format`, or `response_format` on
+    /// OpenAI-compatible servers) and render the Markdown from them.
+    #[arg(long)]
+    structured_output: bool,
+
+    /// Send source to the model as it is, without replacing instruction-like lines or escaping
+    /// headings that look like the model's output.
+    #[arg(long)]
+    no_injection_guard: bool,
+
+    /// Have the model check each file's docs against the file's symbols and remove or correct
+    /// functions, types and fields the code does not define. Doubles the docs requests.
+    #[arg(long)]
+    review_pass: bool,
+
+    /// Whether reasoning models think before replying, for every task. Thinking left in a
+    /// reply is stripped either way.
+    #[arg(long, value_enum, default_value = "model")]
+    thinking: Thinking,
+
+    /// How replies are checked for the model declining the task; file summaries and docs judged
+    /// a refusal are retried with a smaller prompt.
+    #[arg(long, value_enum, default_value = "lenient")]
+    refusal_detection: RefusalDetection,
+
+    /// Save every prompt and raw model reply to a timestamped file in this directory, to see
+    /// why a file's docs came out wrong.
+    #[arg(long, value_name = "DIR")]
+    dump_prompts: Option<PathBuf>,
+
+    /// What to do when the project name's docs were generated from a different source root.
+    #[arg(long, value_enum, default_value = "refuse")]
+    on_name_collision: NameCollision,
+
+    /// The order files are summarized and documented in, so a run stopped early has covered
+    /// the most useful ones.
+    #[arg(long, value_enum, default_value = "priority")]
+    generation_order: GenerationOrder,
+
+    /// How test files (`tests/`, `*_test.go`, `*.spec.ts`, ...) and test-only symbols
+    /// (`#[cfg(test)]`, `#[test]`, ...) are documented.
+    #[arg(long, value_enum, default_value = "include")]
+    test_code: TestCode,
+
+    /// Which outputs to generate. A run that leaves summaries or docs out keeps its files marked
+    /// for generation; a later `--resume` run reuses what it wrote.
+    #[arg(long, value_enum, default_value = "full")]
+    mode: Mode,
+
+    /// What to do when the task models changed since the last full run. Every mode but `guard`
+    /// switches the project over to the new models.
+    #[arg(long, value_enum, default_value = "guard")]
+    model_upgrade: ModelUpgrade,
+
+    /// Post a short digest of each run to this Slack or Teams incoming webhook.
+    #[arg(long, value_name = "URL")]
+    digest_webhook: Option<String>,
+
+    /// Message format of the digest.
+    #[arg(long, value_enum, default_value = "slack")]
+    digest_format: DigestFormat,
+
+    /// Compare the extracted public API with rustdoc JSON and pass rustdoc's signatures to the
+    /// docs prompt (Rust projects; needs a nightly toolchain).
+    #[arg(long)]
+    rustdoc: bool,
+
+    /// Do not contact the LLM server; only refresh the source index, project memory and other
+    /// artifacts built from parsing.
+    #[arg(long)]
+    offline: bool,
+
+    /// After the run, rebuild the index of symbols of every project under the docs root that
+    /// `symbols --all-projects` searches.
+    #[arg(long)]
+    org_index: bool,
+
+    /// Drop entries of hand-edited `.annotations.json`, `.skip.json` or `.memory.json` that do
+    /// not match their schema instead of failing; the original is kept as `<file>.bak`.
+    #[arg(long)]
+    repair: bool,
+
+    /// Split the project summary, architecture and file docs longer than this many lines into
+    /// one linked sub-page per section.
+    #[arg(long, value_name = "LINES")]
+    split_pages_over: Option<usize>,
+
+    /// Record which models succeed or fail at which tasks, and how long they take, in
+    /// `<DOCS_ROOT>/.model_stats.json`. Only model names, task names, counts and latencies are
+    /// kept; see the `model-stats` command.
+    #[arg(long)]
+    model_stats: bool,
+
+    /// After each run, post `.model_stats.json` to this URL.
+    #[arg(long, value_name = "URL", requires = "model_stats")]
+    model_stats_upload: Option<String>,
+
+    /// Stop the run at the first file whose summary or docs fail, instead of carrying on and
+    /// listing it in the run report.
+    #[arg(long)]
+    fail_fast: bool,
+
+    /// Stop the run once more than this many files failed.
+    #[arg(long, value_name = "N")]
+    max_file_failures: Option<usize>,
+
+    /// Fail the run when fewer than this share (0.0 to 1.0) of the regenerated files
+    /// succeeded. Its outputs are still written.
+    #[arg(long, value_name = "RATIO")]
+    min_success_ratio: Option<f64>,
+
+    /// Fail the run when the docs it regenerated score below this average heuristic quality
+    /// (0.0 to 1.0, listed in `run_report.json`). Its outputs are still written.
+    #[arg(long, value_name = "SCORE")]
+    min_quality: Option<f64>,
+
+    /// Print the effective configuration with the source of each value (`default`, a CLI flag
+    /// or an environment variable) and exit.
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        default_missing_value = "json"
+    )]
+    explain_config: Option<ConfigFormat>,
+
+    /// Also publish artifacts to an object store (`s3://bucket/prefix` or `gs://bucket/prefix`).
+    #[cfg(feature = "object-store")]
+    #[arg(long, value_name = "URL", conflicts_with = "docs_out")]
+    publish_url: Option<String>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Parse the project and save the project memory, source index and `.ingest.json` without
+    /// contacting the LLM server, for `generate --from-index` to pick up later.
+    Index,
+    /// Regenerate only the docs of the files these paths select.
+    Generate {
+        /// Files, directories or globs relative to the project root, such as `src/parser/**`
+        /// (quote globs so the shell leaves them to PlainSight).
+        #[arg(value_name = "PATH", required_unless_present = "from_index")]
+        paths: Vec<PathBuf>,
+
+        /// Generate from the `.ingest.json` saved by `index` instead of reading the project
+        /// root, which need not exist on this machine. Without paths, the whole project.
+        #[arg(long)]
+        from_index: bool,
+    },
+    /// Merge the generated docs into one self-contained file for sharing, or lay them out as a
+    /// static site or an mdBook.
+    Export {
+        #[arg(long, value_enum, default_value = "single-html")]
+        format: ExportFormat,
+
+        /// Output file, or directory for `html` and `mdbook` (defaults to
+        /// `<DOCS_ROOT>/<NAME>/<NAME>.html|pdf`, `<DOCS_ROOT>/<NAME>/site` or
+        /// `<DOCS_ROOT>/<NAME>/book`).
+        #[arg(long, short, value_name = "PATH")]
+        output: Option<PathBuf>,
+    },
+    /// Explain a line range of a file and print the explanation to stdout.
+    Explain {
+        /// File containing the selection (absolute, or relative to the working directory).
+        file: PathBuf,
+
+        /// Selected lines, 1-based and inclusive (`40-90` or `40`).
+        #[arg(long, value_name = "START-END")]
+        lines: String,
+    },
+    /// Search the symbols recorded in the project memory of the last run.
+    Symbols {
+        /// Glob (`*Config`, `parse_?`) or fuzzy pattern, case-insensitive.
+        pattern: String,
+
+        /// Most matches to print.
+        #[arg(long, value_name = "N", default_value_t = 20)]
+        limit: usize,
+
+        /// Search every project under the docs root instead of only this one.
+        #[arg(long)]
+        all_projects: bool,
+    },
+    /// Move every project of a docs root to another one without regenerating anything.
+    MigrateDocs {
+        /// Docs root the projects are in now.
+        #[arg(long, value_name = "OLD_ROOT")]
+        from: PathBuf,
+
+        /// New docs root; must not exist yet or be empty.
+        #[arg(long, value_name = "NEW_ROOT")]
+        to: PathBuf,
+    },
+    /// Print the model statistics recorded with `--model-stats`.
+    ModelStats,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    /// HTML with inline CSS and a linked table of contents.
+    SingleHtml,
+    /// PDF printed by headless Chromium/Chrome or wkhtmltopdf.
+    Pdf,
+    /// Static site, one page per document, with search over files, summaries and symbols.
+    Html,
+    /// mdBook source directory with `book.toml` and `src/SUMMARY.md`, for `mdbook serve`.
+    #[value(name = "mdbook")]
+    MdBook,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum ModelUnloading {
+    /// Unload each phase's models before the next phase loads its own.
+    AfterPhase,
+    /// Leave models loaded until their keep-alive expires.
+    KeepAlive,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Thinking {
+    /// Send no toggle; required for models without thinking support.
+    Model,
+    /// Ask the model not to think.
+    Off,
+    /// Ask the model to think.
+    On,
+    /// Low thinking effort, for models that take one.
+    Low,
+    /// Medium thinking effort.
+    Medium,
+    /// High thinking effort.
+    High,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum RefusalDetection {
+    /// Never treat a reply as a refusal.
+    Off,
+    /// A refusal phrase in the first line, or two in the first paragraph.
+    Lenient,
+    /// Any refusal phrase anywhere in the reply.
+    Strict,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum NameCollision {
+    /// Stop with an error.
+    Refuse,
+    /// Use the first free `<name>_2`, `<name>_3`, ...
+    Suffix,
+    /// Ask for another name when run from a terminal.
+    Prompt,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum GenerationOrder {
+    /// Entry points first, then the files most linked to, then those with the largest public API.
+    Priority,
+    /// Path order.
+    Path,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum TestCode {
+    /// Like any other source.
+    Include,
+    /// Leave test files and test-only symbols out.
+    Exclude,
+    /// In a `## Testing` section of each file's docs, apart from the rest.
+    Separate,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum ArchitectureContext {
+    /// The start of each file's source.
+    ChunkPreviews,
+    /// The file summaries and the project memory's cross-file links and global symbols.
+    Summaries,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Mode {
+    /// File summaries and docs, the project summary and the architecture docs.
+    Full,
+    /// File summaries and the project summary only.
+    SummariesOnly,
+    /// File docs and the architecture docs only.
+    DocsOnly,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum ModelUpgrade {
+    /// Regenerate without comparing.
+    Off,
+    /// Compare a sample of files with their previous outputs in `model_upgrade.md`.
+    ReportOnly,
+    /// Compare like `report-only`, and stop the run if a sample regressed or could not be
+    /// compared.
+    Guard,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum ConfigFormat {
+    Json,
+    Toml,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum DigestFormat {
+    Slack,
+    Teams,
 }
 
 #[tokio::main]
 async fn main() {
-    let cli = Cli::parse();
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|why| why.exit());
+
+    if let Some(format) = cli.explain_config {
+        match explain_config(&cli, &matches, format) {
+            Ok(report) => println!("{report}"),
+            Err(why) => {
+                eprintln!("Explaining the configuration failed: {why}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
     let project_name = cli
         .project_name
+        .clone()
         .unwrap_or_else(|| infer_project_name(&cli.project_root));
 
-    let app = match plainsight::PlainSight::new(&cli.docs_root) {
-        Ok(app) => app,
+    let cancel = plainsight::CancellationToken::new();
+    let docs_out_store = cli.docs_out.as_ref().map(|_| {
+        Arc::new(plainsight::docs_store::DryRunDocsStore::new(Arc::new(
+            plainsight::docs_store::LocalDocsStore,
+        )))
+    });
+    let app = match init_app(&cli, docs_out_store.clone()) {
+        Ok(app) => app.with_cancellation(cancel.clone()),
         Err(why) => {
             tracing::error!(error = %why, "initialization failed");
             eprintln!("Initialization failed. See logs for details.");
@@ -35,13 +497,744 @@ async fn main() {
         }
     };
 
-    if let Err(why) = app.run_project(&project_name, &cli.project_root).await {
+    if let Some(Command::Export { format, output }) = &cli.command {
+        let format = match format {
+            ExportFormat::SingleHtml => plainsight::export::ExportFormat::SingleHtml,
+            ExportFormat::Pdf => plainsight::export::ExportFormat::Pdf,
+            ExportFormat::Html => plainsight::export::ExportFormat::Html,
+            ExportFormat::MdBook => plainsight::export::ExportFormat::MdBook,
+        };
+        match app.export_bundle(&project_name, format, output.as_deref()) {
+            Ok(path) => println!("{}", path.display()),
+            Err(why) => {
+                tracing::error!(error = %why, "export failed");
+                eprintln!("Export failed: {why}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if let Some(Command::Explain { file, lines }) = &cli.command {
+        tokio::spawn(cancel_on_ctrl_c(cancel.clone()));
+        let explained = match plainsight::explain::Selection::parse(file, lines) {
+            Ok(selection) => {
+                app.explain_selection(&project_name, &cli.project_root, &selection)
+                    .await
+            }
+            Err(why) => Err(why),
+        };
+        match explained {
+            Ok(explanation) => {
+                tracing::info!(
+                    model = %explanation.model,
+                    prompt_eval_count = explanation.prompt_eval_count,
+                    eval_count = explanation.eval_count,
+                    total_duration_ms = explanation.total_duration.as_millis() as u64,
+                    "selection explained"
+                );
+                println!("{}", explanation.text);
+            }
+            Err(plainsight::error::PlainSightError::Cancelled) => std::process::exit(130),
+            Err(why) => {
+                tracing::error!(error = %why, "explain failed");
+                eprintln!("Explain failed: {why}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if let Some(Command::Symbols {
+        pattern,
+        limit,
+        all_projects,
+    }) = &cli.command
+    {
+        let printed = if *all_projects {
+            app.search_org_symbols(pattern, *limit).map(|matches| {
+                print_org_symbols(&matches);
+                !matches.is_empty()
+            })
+        } else {
+            app.search_symbols(&project_name, pattern, *limit)
+                .map(|matches| {
+                    print_symbols(&matches);
+                    !matches.is_empty()
+                })
+        };
+        match printed {
+            Ok(false) => println!("No symbols match '{pattern}'."),
+            Ok(true) => {}
+            Err(why) => {
+                tracing::error!(error = %why, "symbol search failed");
+                eprintln!("Symbol search failed: {why}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if let Some(Command::MigrateDocs { from, to }) = &cli.command {
+        match plainsight::migrate::migrate_docs(from, to) {
+            Ok(report) => {
+                for project in &report.projects {
+                    println!(
+                        "{}: {} files, {} paths rewritten",
+                        project.name, project.files, project.paths_rewritten
+                    );
+                    for file in &project.missing_summaries {
+                        println!("  no summary yet: {file}");
+                    }
+                }
+                println!(
+                    "Moved {} projects to {}",
+                    report.projects.len(),
+                    report.to.display()
+                );
+            }
+            Err(why) => {
+                tracing::error!(error = %why, "docs migration failed");
+                eprintln!("Migration failed: {why}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if let Some(Command::ModelStats) = &cli.command {
+        match app.model_stats() {
+            Ok(stats) if stats.models.is_empty() => {
+                println!("No model statistics yet; run with --model-stats to record them.")
+            }
+            Ok(stats) => print_model_stats(&stats),
+            Err(why) => {
+                tracing::error!(error = %why, "reading model stats failed");
+                eprintln!("Reading model statistics failed: {why}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if cli.dry_run {
+        match app
+            .plan_project(&project_name, &cli.project_root, cli.subtree.as_deref())
+            .await
+        {
+            Ok(plan) => print_run_plan(&plan),
+            Err(why) => {
+                tracing::error!(error = %why, "planning failed");
+                eprintln!("Planning failed: {why}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    tokio::spawn(cancel_on_ctrl_c(cancel.clone()));
+    let app = if cli.no_progress {
+        app
+    } else {
+        app.with_progress(Arc::new(progress::ProgressBarSink::new()))
+    };
+    let result = match &cli.subtree {
+        _ if cli.workspace => app.run_workspace(&cli.project_root).await,
+        Some(subtree) => {
+            app.run_project_subtree(&project_name, &cli.project_root, subtree)
+                .await
+        }
+        None => app.run_project(&project_name, &cli.project_root).await,
+    };
+    if let (Some(docs_out), Some(store)) = (&cli.docs_out, &docs_out_store) {
+        match write_docs_out(&cli.docs_root, docs_out, store) {
+            Ok(written) => tracing::info!(
+                docs_out = %docs_out.display(),
+                written,
+                "docs written outside the docs root"
+            ),
+            Err(why) => {
+                tracing::error!(error = %why, "writing --docs-out failed");
+                eprintln!("Writing to '{}' failed: {why}", docs_out.display());
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Err(plainsight::error::PlainSightError::Cancelled) = result {
+        eprintln!(
+            "Generation cancelled. Docs finished so far are kept; the next run generates the rest."
+        );
+        std::process::exit(130);
+    }
+    if let Err(why) = result {
         tracing::error!(error = %why, "generation failed");
-        eprintln!("Generation failed. See logs for details.");
+        if let plainsight::error::PlainSightError::Ollama(
+            unreachable @ plainsight::ollama::OllamaError::Unreachable { .. },
+        ) = &why
+        {
+            eprintln!(
+                "{unreachable}. Start the server, or pass --offline to refresh only the parse artifacts."
+            );
+        } else if let plainsight::error::PlainSightError::ProjectLocked { .. }
+        | plainsight::error::PlainSightError::TooManyFailures { .. } = &why
+        {
+            eprintln!("Generation failed: {why}.");
+        } else if let plainsight::error::PlainSightError::Io { source, .. } = &why
+            && matches!(
+                source.kind(),
+                std::io::ErrorKind::PermissionDenied | std::io::ErrorKind::ReadOnlyFilesystem
+            )
+        {
+            eprintln!(
+                "{why}. The docs root is not writable; pass --docs-out DIR to write the generated files elsewhere."
+            );
+        } else {
+            eprintln!("Generation failed. See logs for details.");
+        }
         std::process::exit(1);
     }
 }
 
+fn print_symbols(matches: &[plainsight::symbol_search::SymbolMatch]) {
+    for symbol in matches {
+        println!("{} ({})", symbol.name, symbol.kind);
+        print_symbol_details(symbol);
+    }
+}
+
+fn print_run_plan(plan: &plainsight::run_plan::RunPlan) {
+    println!(
+        "{}: {} files to regenerate, {} reused",
+        plan.project_name,
+        plan.regenerate.len(),
+        plan.reused.len()
+    );
+    if plan.models_changed {
+        println!("The task models changed since the last run, so every file is regenerated.");
+    }
+    for file in &plan.regenerate {
+        println!(
+            "  regenerate {}: summary {}, docs {}",
+            file.path,
+            planned_task(&file.summary),
+            planned_task(&file.docs)
+        );
+    }
+    for path in &plan.reused {
+        println!("  reuse {path}");
+    }
+    if plan.project_docs {
+        println!("The project summary and architecture are rewritten.");
+    }
+    println!(
+        "At least {} requests; ~{} prompt tokens for the file summaries and docs.",
+        plan.request_count(),
+        plan.file_prompt_tokens()
+    );
+}
+
+fn planned_task(task: &plainsight::run_plan::PlannedTask) -> String {
+    let requests = match task.requests {
+        0 => return "kept from the unfinished run".to_string(),
+        1 => "1 request".to_string(),
+        count => format!("{count} requests"),
+    };
+    format!(
+        "{requests} (~{} prompt tokens, at most {} per request)",
+        task.prompt_tokens, task.token_limit
+    )
+}
+
+fn print_model_stats(stats: &plainsight::model_stats::ModelStats) {
+    println!("{} runs", stats.runs);
+    for (model, tasks) in &stats.models {
+        println!("{model}");
+        for (task, task_stats) in tasks {
+            println!(
+                "  {task}: {} requests, {:.0}% succeeded, {:.0}% refused, {:.0}% passed validation, {} ms average",
+                task_stats.requests,
+                task_stats.success_rate() * 100.0,
+                task_stats.refusal_rate() * 100.0,
+                task_stats.validation_pass_rate() * 100.0,
+                task_stats.mean_latency_ms()
+            );
+        }
+    }
+}
+
+/// Cancel the run on the first Ctrl-C so it can save its progress; exit at once on the second.
+async fn cancel_on_ctrl_c(cancel: plainsight::CancellationToken) {
+    if tokio::signal::ctrl_c().await.is_err() {
+        return;
+    }
+    eprintln!("Cancelling; press Ctrl-C again to exit at once.");
+    cancel.cancel();
+    if tokio::signal::ctrl_c().await.is_ok() {
+        std::process::exit(130);
+    }
+}
+
+fn print_org_symbols(matches: &[plainsight::org_index::OrgSymbolMatch]) {
+    for found in matches {
+        let symbol = &found.symbol;
+        match &found.source_root {
+            Some(root) => println!(
+                "{} ({}) in {} [{}]",
+                symbol.name,
+                symbol.kind,
+                found.project,
+                root.display()
+            ),
+            None => println!("{} ({}) in {}", symbol.name, symbol.kind, found.project),
+        }
+        print_symbol_details(symbol);
+    }
+}
+
+fn print_symbol_details(symbol: &plainsight::symbol_search::SymbolMatch) {
+    for location in &symbol.locations {
+        println!("  {}:{}", location.file, location.line);
+    }
+    for link in &symbol.links {
+        println!("  {} -> {} ({})", link.from_file, link.to_file, link.reason);
+    }
+}
+
+fn init_app(
+    cli: &Cli,
+    docs_out_store: Option<Arc<plainsight::docs_store::DryRunDocsStore>>,
+) -> plainsight::error::Result<plainsight::PlainSight> {
+    let config = build_config(cli)?;
+
+    if let Some(store) = docs_out_store {
+        return plainsight::PlainSight::with_store(&cli.docs_root, config, store);
+    }
+
+    #[cfg(feature = "object-store")]
+    if let Some(url) = &cli.publish_url {
+        let store = plainsight::docs_store::ObjectDocsStore::from_url(url, &cli.docs_root)?;
+        return plainsight::PlainSight::with_store(
+            &cli.docs_root,
+            config,
+            std::sync::Arc::new(store),
+        );
+    }
+
+    plainsight::PlainSight::with_config(&cli.docs_root, config)
+}
+
+/// Write the files `store` kept from the run under `docs_out`, at their path relative to the
+/// docs root. Returns how many were written.
+fn write_docs_out(
+    docs_root: &std::path::Path,
+    docs_out: &std::path::Path,
+    store: &plainsight::docs_store::DryRunDocsStore,
+) -> std::io::Result<usize> {
+    let written = store.written_files();
+    for (path, contents) in &written {
+        let relative = path.strip_prefix(docs_root).unwrap_or(path);
+        let target = docs_out.join(relative);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&target, contents)?;
+    }
+    Ok(written.len())
+}
+
+fn build_config(cli: &Cli) -> plainsight::error::Result<plainsight::config::PlainSightConfig> {
+    let mut config = plainsight::config::PlainSightConfig::default();
+    if cli.rustdoc {
+        config.rustdoc = plainsight::config::RustdocMode::Auto;
+    }
+    config.org_index = cli.org_index;
+    config.offline = cli.offline;
+    config.changed_since = cli.changed_since.clone();
+    match &cli.command {
+        Some(Command::Index) => config.offline = true,
+        Some(Command::Generate { paths, from_index }) => {
+            config.paths = paths.clone();
+            config.from_index = *from_index;
+        }
+        _ => {}
+    }
+    config.resume = cli.resume;
+    config.directory_summaries = cli.directory_summaries;
+    config.architecture_context = match cli.architecture_context {
+        ArchitectureContext::ChunkPreviews => {
+            plainsight::config::ArchitectureContext::ChunkPreviews
+        }
+        ArchitectureContext::Summaries => plainsight::config::ArchitectureContext::Summaries,
+    };
+    config.keep_stale = cli.keep_stale;
+    config.force = if cli.force {
+        plainsight::config::ForceConfig::all()
+    } else {
+        plainsight::config::ForceConfig {
+            summaries: cli.force_summaries,
+            docs: cli.force_docs,
+            project_level: cli.force_project_level,
+        }
+    };
+    config.repair_state_files = cli.repair;
+    config.page_split.max_lines = cli.split_pages_over;
+    config.model_stats.enabled = cli.model_stats;
+    config.model_stats.upload_url = cli.model_stats_upload.clone();
+    config.error_policy.fail_fast = cli.fail_fast;
+    config.error_policy.max_file_failures = cli.max_file_failures;
+    if let Some(ratio) = cli.min_success_ratio
+        && !(0.0..=1.0).contains(&ratio)
+    {
+        return Err(plainsight::error::PlainSightError::InvalidState(format!(
+            "--min-success-ratio must be between 0.0 and 1.0, got {ratio}"
+        )));
+    }
+    config.error_policy.min_success_ratio = cli.min_success_ratio;
+    if let Some(score) = cli.min_quality
+        && !(0.0..=1.0).contains(&score)
+    {
+        return Err(plainsight::error::PlainSightError::InvalidState(format!(
+            "--min-quality must be between 0.0 and 1.0, got {score}"
+        )));
+    }
+    config.error_policy.min_quality = cli.min_quality;
+    if cli.backend == "openai" {
+        config.ollama.backend = plainsight::ollama::BackendKind::OpenAiCompatible;
+    }
+    if let Some(url) = &cli.llm_url {
+        config.ollama = config.ollama.with_url(url)?;
+    }
+    config.ollama.max_concurrency = cli.max_concurrency;
+    config.ollama.requests_per_minute = cli.requests_per_minute;
+    config.ollama.model_unloading = match cli.model_unloading {
+        ModelUnloading::AfterPhase => plainsight::ollama::ModelUnloading::AfterPhase,
+        ModelUnloading::KeepAlive => plainsight::ollama::ModelUnloading::KeepAlive,
+    };
+    config.ollama.gpu_memory = cli
+        .gpu_memory
+        .map(|gib| (gib * 1024.0 * 1024.0 * 1024.0) as u64);
+    config.ollama.pull_missing_models = cli.pull_missing_models;
+    config.ollama.file_timeout = cli.file_timeout.map(Duration::from_secs);
+    config.ollama.retry_budget.max_failures = cli.retry_budget;
+    config.ollama.retry_budget.cooldown = cli.retry_cooldown.map(Duration::from_secs);
+    config.ollama.summary_batching.enabled = cli.batch_small_files;
+    config.ollama.use_tools = !cli.no_tools;
+    config.ollama.structured_output = cli.structured_output;
+    config.ollama.enable_review_pass = cli.review_pass;
+    if cli.no_injection_guard {
+        config.injection_guard = plainsight::config::InjectionGuard::Off;
+    }
+    let thinking = match cli.thinking {
+        Thinking::Model => plainsight::ollama::Thinking::Model,
+        Thinking::Off => plainsight::ollama::Thinking::Off,
+        Thinking::On => plainsight::ollama::Thinking::On,
+        Thinking::Low => plainsight::ollama::Thinking::Low,
+        Thinking::Medium => plainsight::ollama::Thinking::Medium,
+        Thinking::High => plainsight::ollama::Thinking::High,
+    };
+    config.ollama.tasks.set_thinking_for_all(thinking);
+    config.ollama.refusal_detection = match cli.refusal_detection {
+        RefusalDetection::Off => plainsight::ollama::RefusalDetection::Off,
+        RefusalDetection::Lenient => plainsight::ollama::RefusalDetection::Lenient,
+        RefusalDetection::Strict => plainsight::ollama::RefusalDetection::Strict,
+    };
+    config.ollama.dump_prompts_dir = cli.dump_prompts.clone();
+    config.name_collision = match cli.on_name_collision {
+        NameCollision::Refuse => plainsight::config::NameCollisionPolicy::Refuse,
+        NameCollision::Suffix => plainsight::config::NameCollisionPolicy::Suffix,
+        NameCollision::Prompt => plainsight::config::NameCollisionPolicy::Prompt,
+    };
+    config.generation_order = match cli.generation_order {
+        GenerationOrder::Priority => plainsight::config::GenerationOrder::Priority,
+        GenerationOrder::Path => plainsight::config::GenerationOrder::Path,
+    };
+    config.test_code = match cli.test_code {
+        TestCode::Include => plainsight::config::TestCodeMode::Include,
+        TestCode::Exclude => plainsight::config::TestCodeMode::Exclude,
+        TestCode::Separate => plainsight::config::TestCodeMode::Separate,
+    };
+    config.mode = match cli.mode {
+        Mode::Full => plainsight::config::GenerationMode::Full,
+        Mode::SummariesOnly => plainsight::config::GenerationMode::SummariesOnly,
+        Mode::DocsOnly => plainsight::config::GenerationMode::DocsOnly,
+    };
+    config.model_upgrade = match cli.model_upgrade {
+        ModelUpgrade::Off => plainsight::config::ModelUpgradeMode::Off,
+        ModelUpgrade::ReportOnly => plainsight::config::ModelUpgradeMode::ReportOnly,
+        ModelUpgrade::Guard => plainsight::config::ModelUpgradeMode::Guard,
+    };
+    config.digest.webhook_url = cli.digest_webhook.clone();
+    config.digest.format = match cli.digest_format {
+        DigestFormat::Slack => plainsight::config::DigestFormat::Slack,
+        DigestFormat::Teams => plainsight::config::DigestFormat::Teams,
+    };
+    config.ollama.authorization = std::env::var(AUTHORIZATION_ENV)
+        .ok()
+        .filter(|value| !value.is_empty());
+    Ok(config)
+}
+
+/// Config keys each flag sets in `build_config`.
+const FLAG_KEYS: &[(&str, &str, &[&str])] = &[
+    ("rustdoc", "--rustdoc", &["rustdoc"]),
+    ("org_index", "--org-index", &["org_index"]),
+    ("offline", "--offline", &["offline"]),
+    ("changed_since", "--changed-since", &["changed_since"]),
+    ("resume", "--resume", &["resume"]),
+    (
+        "directory_summaries",
+        "--directory-summaries",
+        &["directory_summaries"],
+    ),
+    (
+        "architecture_context",
+        "--architecture-context",
+        &["architecture_context"],
+    ),
+    ("keep_stale", "--keep-stale", &["keep_stale"]),
+    (
+        "force",
+        "--force",
+        &["force.summaries", "force.docs", "force.project_level"],
+    ),
+    ("force_summaries", "--force-summaries", &["force.summaries"]),
+    ("force_docs", "--force-docs", &["force.docs"]),
+    (
+        "force_project_level",
+        "--force-project-level",
+        &["force.project_level"],
+    ),
+    ("repair", "--repair", &["repair_state_files"]),
+    (
+        "split_pages_over",
+        "--split-pages-over",
+        &["page_split.max_lines"],
+    ),
+    ("model_stats", "--model-stats", &["model_stats.enabled"]),
+    (
+        "model_stats_upload",
+        "--model-stats-upload",
+        &["model_stats.upload_url"],
+    ),
+    ("fail_fast", "--fail-fast", &["error_policy.fail_fast"]),
+    (
+        "max_file_failures",
+        "--max-file-failures",
+        &["error_policy.max_file_failures"],
+    ),
+    (
+        "min_success_ratio",
+        "--min-success-ratio",
+        &["error_policy.min_success_ratio"],
+    ),
+    (
+        "min_quality",
+        "--min-quality",
+        &["error_policy.min_quality"],
+    ),
+    ("backend", "--backend", &["ollama.backend"]),
+    ("llm_url", "--llm-url", &["ollama.host", "ollama.port"]),
+    (
+        "max_concurrency",
+        "--max-concurrency",
+        &["ollama.max_concurrency"],
+    ),
+    (
+        "model_unloading",
+        "--model-unloading",
+        &["ollama.model_unloading"],
+    ),
+    ("gpu_memory", "--gpu-memory", &["ollama.gpu_memory"]),
+    (
+        "requests_per_minute",
+        "--requests-per-minute",
+        &["ollama.requests_per_minute"],
+    ),
+    (
+        "pull_missing_models",
+        "--pull-missing-models",
+        &["ollama.pull_missing_models"],
+    ),
+    ("file_timeout", "--file-timeout", &["ollama.file_timeout"]),
+    (
+        "retry_budget",
+        "--retry-budget",
+        &["ollama.retry_budget.max_failures"],
+    ),
+    (
+        "retry_cooldown",
+        "--retry-cooldown",
+        &["ollama.retry_budget.cooldown"],
+    ),
+    (
+        "batch_small_files",
+        "--batch-small-files",
+        &["ollama.summary_batching.enabled"],
+    ),
+    ("no_tools", "--no-tools", &["ollama.use_tools"]),
+    (
+        "structured_output",
+        "--structured-output",
+        &["ollama.structured_output"],
+    ),
+    (
+        "no_injection_guard",
+        "--no-injection-guard",
+        &["injection_guard"],
+    ),
+    (
+        "review_pass",
+        "--review-pass",
+        &["ollama.enable_review_pass"],
+    ),
+    (
+        "thinking",
+        "--thinking",
+        &[
+            "ollama.tasks.documentation.thinking",
+            "ollama.tasks.project_summary.thinking",
+            "ollama.tasks.architecture.thinking",
+            "ollama.tasks.summarize.thinking",
+            "ollama.tasks.explain.thinking",
+            "ollama.tasks.review.thinking",
+            "ollama.tasks.directory_summary.thinking",
+            "ollama.tasks.change_summary.thinking",
+        ],
+    ),
+    (
+        "refusal_detection",
+        "--refusal-detection",
+        &["ollama.refusal_detection"],
+    ),
+    (
+        "dump_prompts",
+        "--dump-prompts",
+        &["ollama.dump_prompts_dir"],
+    ),
+    (
+        "on_name_collision",
+        "--on-name-collision",
+        &["name_collision"],
+    ),
+    (
+        "generation_order",
+        "--generation-order",
+        &["generation_order"],
+    ),
+    ("test_code", "--test-code", &["test_code"]),
+    ("mode", "--mode", &["mode"]),
+    ("model_upgrade", "--model-upgrade", &["model_upgrade"]),
+    (
+        "digest_webhook",
+        "--digest-webhook",
+        &["digest.webhook_url"],
+    ),
+    ("digest_format", "--digest-format", &["digest.format"]),
+];
+
+fn explain_config(
+    cli: &Cli,
+    matches: &clap::ArgMatches,
+    format: ConfigFormat,
+) -> plainsight::error::Result<String> {
+    let mut config = build_config(cli)?;
+    let from_cli = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+
+    let mut sources = BTreeMap::new();
+    for (id, flag, keys) in FLAG_KEYS {
+        if from_cli(id) {
+            for key in *keys {
+                sources.insert(key.to_string(), flag.to_string());
+            }
+        }
+    }
+    match &cli.command {
+        Some(Command::Index) => {
+            sources.insert("offline".to_string(), "index".to_string());
+        }
+        Some(Command::Generate { paths, from_index }) => {
+            if !paths.is_empty() {
+                sources.insert("paths".to_string(), "generate PATH".to_string());
+            }
+            if *from_index {
+                sources.insert(
+                    "from_index".to_string(),
+                    "generate --from-index".to_string(),
+                );
+            }
+        }
+        _ => {}
+    }
+    for (task, template) in plainsight::ollama::read_prompt_overrides(&cli.project_root)? {
+        sources.insert(
+            format!("ollama.prompt_overrides.{task}"),
+            format!(
+                "file {}/{task}.md",
+                plainsight::ollama::PROMPT_OVERRIDES_DIR
+            ),
+        );
+        config.ollama.prompt_overrides.insert(task, template);
+    }
+    if config.ollama.authorization.is_some() {
+        sources.insert(
+            "ollama.authorization".to_string(),
+            format!("env {AUTHORIZATION_ENV}"),
+        );
+    }
+
+    let run_source = |id: &str, flag: &str| {
+        if from_cli(id) {
+            flag.to_string()
+        } else {
+            plainsight::config_report::DEFAULT_SOURCE.to_string()
+        }
+    };
+    let (project_name, project_name_source) = match &cli.project_name {
+        Some(name) => (name.clone(), "--project-name".to_string()),
+        None => (
+            infer_project_name(&cli.project_root),
+            "inferred from PROJECT_ROOT".to_string(),
+        ),
+    };
+    let report = plainsight::config_report::ConfigReport::new(&config, &sources)?
+        .with_value(
+            "run.project_root",
+            &cli.project_root,
+            &run_source("project_root", "PROJECT_ROOT"),
+        )?
+        .with_value(
+            "run.docs_root",
+            &cli.docs_root,
+            &run_source("docs_root", "--docs-root"),
+        )?
+        .with_value(
+            "run.docs_out",
+            &cli.docs_out,
+            &run_source("docs_out", "--docs-out"),
+        )?
+        .with_value("run.project_name", project_name, &project_name_source)?
+        .with_value(
+            "run.subtree",
+            &cli.subtree,
+            &run_source("subtree", "--subtree"),
+        )?
+        .with_value(
+            "run.workspace",
+            cli.workspace,
+            &run_source("workspace", "--workspace"),
+        )?
+        .with_value(
+            "run.dry_run",
+            cli.dry_run,
+            &run_source("dry_run", "--dry-run"),
+        )?;
+
+    match format {
+        ConfigFormat::Json => report.to_json(),
+        ConfigFormat::Toml => report.to_toml(),
+    }
+}
+
 fn infer_project_name(project_root: &std::path::Path) -> String {
     project_root
         .file_name()
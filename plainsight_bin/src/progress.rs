@@ -0,0 +1,42 @@
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use plainsight::{ProgressEvent, ProgressSink};
+
+/// Shows the files of each summary and docs phase as a progress bar on stderr. Hidden when
+/// stderr is not a terminal.
+pub(crate) struct ProgressBarSink {
+    bar: ProgressBar,
+}
+
+impl ProgressBarSink {
+    pub(crate) fn new() -> Self {
+        let bar = ProgressBar::with_draw_target(None, ProgressDrawTarget::stderr());
+        bar.set_style(
+            ProgressStyle::with_template("{prefix:>18} [{bar:30}] {pos}/{len} {wide_msg}")
+                .expect("progress template is valid")
+                .progress_chars("=> "),
+        );
+        Self { bar }
+    }
+}
+
+impl ProgressSink for ProgressBarSink {
+    fn event(&self, event: ProgressEvent<'_>) {
+        match event {
+            ProgressEvent::PhaseStarted { file_tasks: 0, .. } => self.bar.finish_and_clear(),
+            ProgressEvent::PhaseStarted { phase, file_tasks } => {
+                self.bar.reset();
+                self.bar.set_length(file_tasks as u64);
+                self.bar.set_prefix(phase.to_string());
+                self.bar.set_message("");
+            }
+            ProgressEvent::FileStarted { path, .. } => self.bar.set_message(path.to_string()),
+            ProgressEvent::FileFinished { path, .. }
+            | ProgressEvent::FileReused { path, .. }
+            | ProgressEvent::FileSkipped { path, .. } => {
+                self.bar.set_message(path.to_string());
+                self.bar.inc(1);
+            }
+            _ => {}
+        }
+    }
+}
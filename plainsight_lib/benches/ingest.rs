@@ -0,0 +1,109 @@
+//! Ingestion of a generated project of several thousand files, on one thread and on one per
+//! CPU. Run with `cargo bench -p plainsight --bench ingest`.
+//!
+//! Each pass is an offline run (`PlainSightConfig::offline`), which parses the project and
+//! writes the parse artifacts without contacting a server. Ingestion takes most of it; the
+//! `ingest_complete` lines on stderr give that phase alone.
+
+use std::{
+    fs,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use plainsight::{PlainSight, config::PlainSightConfig};
+
+const FILES: usize = 4000;
+const FUNCTIONS_PER_FILE: usize = 12;
+const PASSES: usize = 3;
+
+fn main() {
+    tracing_subscriber::fmt()
+        .with_env_filter("warn,plainsight::workflow::ingest=info")
+        .with_writer(std::io::stderr)
+        .init();
+
+    let root = std::env::temp_dir().join("plainsight-ingest-bench");
+    let project_root = root.join("project");
+    let docs_root = root.join("docs");
+    let _ = fs::remove_dir_all(&root);
+    write_project(&project_root).expect("writing the bench project");
+
+    let runtime = tokio::runtime::Runtime::new().expect("starting the runtime");
+    let cpus = std::thread::available_parallelism().map_or(1, |n| n.get());
+    // The first pass only warms the page cache.
+    run(&runtime, &project_root, &docs_root, None);
+    let serial = best_of(&runtime, &project_root, &docs_root, Some(1));
+    let parallel = best_of(&runtime, &project_root, &docs_root, None);
+
+    println!("{FILES} files, best of {PASSES} passes");
+    println!(
+        "  1 thread:        {:>8.1} ms",
+        serial.as_secs_f64() * 1000.0
+    );
+    println!(
+        "  {cpus:>2} threads:      {:>8.1} ms",
+        parallel.as_secs_f64() * 1000.0
+    );
+    println!(
+        "  speedup:         {:>8.2}x",
+        serial.as_secs_f64() / parallel.as_secs_f64()
+    );
+    let _ = fs::remove_dir_all(&root);
+}
+
+fn best_of(
+    runtime: &tokio::runtime::Runtime,
+    project_root: &Path,
+    docs_root: &Path,
+    threads: Option<usize>,
+) -> Duration {
+    (0..PASSES)
+        .map(|_| run(runtime, project_root, docs_root, threads))
+        .min()
+        .unwrap_or_default()
+}
+
+fn run(
+    runtime: &tokio::runtime::Runtime,
+    project_root: &Path,
+    docs_root: &Path,
+    threads: Option<usize>,
+) -> Duration {
+    let mut config = PlainSightConfig {
+        offline: true,
+        ..PlainSightConfig::default()
+    };
+    config.source_discovery.ingest_threads = threads;
+    let app = PlainSight::with_config(docs_root, config).expect("building the app");
+    let start = Instant::now();
+    runtime
+        .block_on(app.run_project("bench", project_root))
+        .expect("ingesting the bench project");
+    start.elapsed()
+}
+
+fn write_project(project_root: &Path) -> std::io::Result<()> {
+    for file in 0..FILES {
+        let dir = project_root.join(format!("src/module_{}", file / 100));
+        fs::create_dir_all(&dir)?;
+        let mut source =
+            format!("//! Generated module {file}.\n\nuse std::collections::BTreeMap;\n\n");
+        for function in 0..FUNCTIONS_PER_FILE {
+            source.push_str(&format!(
+                "/// Sums the entries of `values` above {function}.\n\
+                 pub fn total_{file}_{function}(values: &BTreeMap<String, u64>) -> u64 {{\n\
+                 \x20   let mut total = 0;\n\
+                 \x20   for (key, value) in values {{\n\
+                 \x20       if *value > {function} && !key.is_empty() {{\n\
+                 \x20           total += value;\n\
+                 \x20       }}\n\
+                 \x20   }}\n\
+                 \x20   total\n\
+                 }}\n\n"
+            ));
+        }
+        fs::write(dir.join(format!("file_{file}.rs")), source)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,319 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::memory::ProjectMemory;
+
+/// Lines after a benchmark definition searched for the code it calls.
+const MAX_BODY_LINES: usize = 15;
+
+/// Calls inside benchmark bodies that belong to the harness, not to the code under test.
+const HARNESS_CALLS: &[&str] = &[
+    "b",
+    "bench_function",
+    "bench_with_input",
+    "benchmark_group",
+    "black_box",
+    "criterion_group",
+    "criterion_main",
+    "finish",
+    "from_parameter",
+    "iter",
+    "iter_batched",
+    "iter_batched_ref",
+    "iter_custom",
+    "iter_with_large_drop",
+    "measurement_time",
+    "new",
+    "ResetTimer",
+    "sample_size",
+    "throughput",
+    "to_async",
+    "BenchmarkId",
+    "Bencher",
+    "Criterion",
+    "Duration",
+    "Some",
+    "Ok",
+    "Err",
+    "Throughput",
+    "Vec",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkDef {
+    /// Criterion benchmark ID, or the function name for `#[bench]` and Go benchmarks.
+    pub id: String,
+    pub line: usize,
+    /// Functions and types called in the benchmark body.
+    pub calls: Vec<String>,
+}
+
+/// The latest criterion measurement of one benchmark, in nanoseconds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkResult {
+    pub id: String,
+    pub mean_ns: f64,
+    pub lower_ns: f64,
+    pub upper_ns: f64,
+    /// Project-relative path of the `estimates.json` the numbers come from.
+    pub source: String,
+    /// Modification time of `source`, in seconds since the Unix epoch.
+    pub measured_at: u64,
+}
+
+/// A benchmark together with the project symbols it exercises and its recorded results.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PerformanceNote {
+    pub benchmark: String,
+    /// `path:line` of the benchmark definition.
+    pub defined_in: String,
+    pub exercises: Vec<String>,
+    pub results: Vec<BenchmarkResult>,
+}
+
+/// Find benchmark definitions: criterion `bench_function`/`bench_with_input` and `#[bench]`
+/// functions in Rust files under `benches/`, and `func BenchmarkX(b *testing.B)` in Go.
+pub fn detect_benchmarks(language: &str, relative_path: &str, source: &str) -> Vec<BenchmarkDef> {
+    let lines = source.lines().map(str::trim).collect::<Vec<_>>();
+    let mut defs = match language {
+        "rust" if is_bench_path(relative_path) || source.contains("#[bench]") => {
+            rust_benchmarks(&lines)
+        }
+        "go" if relative_path.ends_with("_test.go") => go_benchmarks(&lines),
+        _ => return Vec::new(),
+    };
+
+    let starts = defs.iter().map(|def| def.line).collect::<Vec<_>>();
+    for (idx, def) in defs.iter_mut().enumerate() {
+        let next_function = lines
+            .iter()
+            .enumerate()
+            .skip(def.line)
+            .find(|(_, line)| {
+                line.starts_with("fn ") || line.starts_with("pub fn ") || line.starts_with("func ")
+            })
+            .map_or(usize::MAX, |(idx, _)| idx + 1);
+        let end = starts
+            .get(idx + 1)
+            .copied()
+            .unwrap_or(usize::MAX)
+            .min(next_function)
+            .min(def.line + MAX_BODY_LINES);
+        def.calls = called_names(&lines[def.line - 1..end.saturating_sub(1).min(lines.len())]);
+    }
+    defs
+}
+
+/// Parse criterion's `new/benchmark.json` and `new/estimates.json` for one benchmark.
+pub fn parse_criterion_result(
+    benchmark_json: &str,
+    estimates_json: &str,
+    source: String,
+    measured_at: u64,
+) -> Option<BenchmarkResult> {
+    let benchmark: Value = serde_json::from_str(benchmark_json).ok()?;
+    let estimates: Value = serde_json::from_str(estimates_json).ok()?;
+    let mean = estimates.get("mean")?;
+    let interval = mean.get("confidence_interval")?;
+
+    Some(BenchmarkResult {
+        id: benchmark.get("full_id")?.as_str()?.to_string(),
+        mean_ns: mean.get("point_estimate")?.as_f64()?,
+        lower_ns: interval.get("lower_bound")?.as_f64()?,
+        upper_ns: interval.get("upper_bound")?.as_f64()?,
+        source,
+        measured_at,
+    })
+}
+
+/// Attach results to benchmark definitions and group them by the files defining the symbols
+/// each benchmark calls. `defs` holds `(path, definition)` pairs.
+pub fn link_benchmarks(
+    defs: &[(String, BenchmarkDef)],
+    results: &[BenchmarkResult],
+    memory: &ProjectMemory,
+) -> BTreeMap<String, Vec<PerformanceNote>> {
+    let defined_in = memory
+        .global_symbols
+        .iter()
+        .map(|sym| (sym.name.as_str(), sym.defined_in.as_slice()))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut by_file: BTreeMap<String, Vec<PerformanceNote>> = BTreeMap::new();
+    for (path, def) in defs {
+        let prefix = format!("{}/", def.id);
+        let matching = results
+            .iter()
+            .filter(|result| result.id == def.id || result.id.starts_with(&prefix))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let mut targets: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+        for call in &def.calls {
+            for file in defined_in.get(call.as_str()).copied().unwrap_or_default() {
+                if file != path && !is_bench_path(file) {
+                    targets.entry(file).or_default().push(call.clone());
+                }
+            }
+        }
+
+        for (file, exercises) in targets {
+            by_file
+                .entry(file.to_string())
+                .or_default()
+                .push(PerformanceNote {
+                    benchmark: def.id.clone(),
+                    defined_in: format!("{path}:{}", def.line),
+                    exercises,
+                    results: matching.clone(),
+                });
+        }
+    }
+    by_file
+}
+
+/// `1234.5` -> `1.23 µs`.
+pub fn format_nanos(nanos: f64) -> String {
+    let (value, unit) = if nanos >= 1e9 {
+        (nanos / 1e9, "s")
+    } else if nanos >= 1e6 {
+        (nanos / 1e6, "ms")
+    } else if nanos >= 1e3 {
+        (nanos / 1e3, "µs")
+    } else {
+        (nanos, "ns")
+    };
+    format!("{value:.2} {unit}")
+}
+
+fn is_bench_path(path: &str) -> bool {
+    path.split('/').any(|segment| segment == "benches")
+}
+
+fn rust_benchmarks(lines: &[&str]) -> Vec<BenchmarkDef> {
+    let mut defs = Vec::new();
+    // `let mut group = c.benchmark_group("parse");` -> ("group", "parse")
+    let mut group: Option<(String, String)> = None;
+    let mut pending_bench_attr = false;
+
+    for (idx, line) in lines.iter().enumerate() {
+        if line.starts_with("//") {
+            continue;
+        }
+        if line.starts_with("fn ") || line.starts_with("pub fn ") {
+            if pending_bench_attr {
+                let name = line
+                    .trim_start_matches("pub ")
+                    .trim_start_matches("fn ")
+                    .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .next()
+                    .unwrap_or_default();
+                defs.push(def(name.to_string(), idx));
+            }
+            pending_bench_attr = false;
+            group = None;
+            continue;
+        }
+        if *line == "#[bench]" {
+            pending_bench_attr = true;
+            continue;
+        }
+
+        if let Some(pos) = line.find(".benchmark_group(")
+            && let Some(name) = first_string_literal(&line[pos..])
+        {
+            let var = line[..pos]
+                .trim_start_matches("let ")
+                .trim_start_matches("mut ")
+                .split('=')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_string();
+            group = Some((var, name));
+            continue;
+        }
+
+        for call in [".bench_function(", ".bench_with_input("] {
+            let Some(pos) = line.find(call) else {
+                continue;
+            };
+            let receiver = line[..pos]
+                .rsplit(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .next()
+                .unwrap_or_default();
+            let args = &line[pos + call.len()..];
+            let name = if args.trim_start().starts_with("BenchmarkId::from_parameter") {
+                String::new()
+            } else {
+                first_string_literal(args).unwrap_or_default()
+            };
+            let id = match &group {
+                Some((var, group_name)) if var == receiver && name.is_empty() => group_name.clone(),
+                Some((var, group_name)) if var == receiver => format!("{group_name}/{name}"),
+                _ if name.is_empty() => continue,
+                _ => name,
+            };
+            defs.push(def(id, idx));
+        }
+    }
+    defs
+}
+
+fn go_benchmarks(lines: &[&str]) -> Vec<BenchmarkDef> {
+    lines
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, line)| {
+            let rest = line.strip_prefix("func Benchmark")?;
+            rest.contains("*testing.B").then(|| {
+                let name = rest
+                    .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .next()
+                    .unwrap_or_default();
+                def(format!("Benchmark{name}"), idx)
+            })
+        })
+        .collect()
+}
+
+fn def(id: String, idx: usize) -> BenchmarkDef {
+    BenchmarkDef {
+        id,
+        line: idx + 1,
+        calls: Vec::new(),
+    }
+}
+
+fn first_string_literal(text: &str) -> Option<String> {
+    let start = text.find('"')? + 1;
+    let end = text[start..].find('"')?;
+    Some(text[start..start + end].to_string())
+}
+
+/// Identifiers followed by `(` or `::`, minus harness calls.
+fn called_names(lines: &[&str]) -> Vec<String> {
+    let mut names = BTreeSet::new();
+    for line in lines {
+        let mut ident = String::new();
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                ident.push(c);
+                continue;
+            }
+            let is_call = c == '(' || (c == ':' && chars.peek() == Some(&':'));
+            if is_call
+                && !ident.is_empty()
+                && !ident.starts_with(|c: char| c.is_ascii_digit())
+                && !HARNESS_CALLS.contains(&ident.as_str())
+            {
+                names.insert(std::mem::take(&mut ident));
+            }
+            ident.clear();
+        }
+    }
+    names.into_iter().collect()
+}
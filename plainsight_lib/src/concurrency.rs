@@ -0,0 +1,216 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+/// `(marker, kind, primitive)` triples: a line containing `marker` uses `primitive`.
+type Marker = (&'static str, &'static str, &'static str);
+
+const RUST_MARKERS: &[Marker] = &[
+    ("tokio::spawn(", "task", "tokio::spawn"),
+    ("task::spawn(", "task", "task::spawn"),
+    ("spawn_blocking(", "task", "spawn_blocking"),
+    ("spawn_local(", "task", "spawn_local"),
+    ("JoinSet::new(", "task", "JoinSet"),
+    ("thread::spawn(", "thread", "thread::spawn"),
+    ("thread::scope(", "thread", "thread::scope"),
+    ("Builder::new().name(", "thread", "thread::Builder"),
+    (".par_iter(", "thread", "rayon par_iter"),
+    ("mpsc::channel", "channel", "mpsc::channel"),
+    (
+        "mpsc::unbounded_channel",
+        "channel",
+        "mpsc::unbounded_channel",
+    ),
+    ("mpsc::sync_channel", "channel", "mpsc::sync_channel"),
+    ("oneshot::channel", "channel", "oneshot::channel"),
+    ("broadcast::channel", "channel", "broadcast::channel"),
+    ("watch::channel", "channel", "watch::channel"),
+    ("crossbeam_channel::", "channel", "crossbeam_channel"),
+    ("flume::", "channel", "flume"),
+    ("Arc<Mutex<", "lock", "Arc<Mutex>"),
+    ("Arc<RwLock<", "lock", "Arc<RwLock>"),
+    ("Mutex::new(", "lock", "Mutex"),
+    ("RwLock::new(", "lock", "RwLock"),
+    ("Semaphore::new(", "semaphore", "Semaphore"),
+    ("Arc<Semaphore>", "semaphore", "Semaphore"),
+    ("Notify::new(", "signal", "Notify"),
+    ("Condvar::new(", "signal", "Condvar"),
+    ("Barrier::new(", "signal", "Barrier"),
+    ("AtomicBool", "atomic", "AtomicBool"),
+    ("AtomicUsize", "atomic", "AtomicUsize"),
+    ("AtomicU64", "atomic", "AtomicU64"),
+    ("AtomicI64", "atomic", "AtomicI64"),
+];
+
+const PYTHON_MARKERS: &[Marker] = &[
+    ("asyncio.create_task(", "task", "asyncio.create_task"),
+    ("asyncio.gather(", "task", "asyncio.gather"),
+    ("loop.run_in_executor(", "task", "run_in_executor"),
+    ("threading.Thread(", "thread", "threading.Thread"),
+    ("ThreadPoolExecutor(", "thread", "ThreadPoolExecutor"),
+    ("ProcessPoolExecutor(", "process", "ProcessPoolExecutor"),
+    (
+        "multiprocessing.Process(",
+        "process",
+        "multiprocessing.Process",
+    ),
+    ("multiprocessing.Pool(", "process", "multiprocessing.Pool"),
+    ("asyncio.Queue(", "channel", "asyncio.Queue"),
+    ("queue.Queue(", "channel", "queue.Queue"),
+    ("multiprocessing.Queue(", "channel", "multiprocessing.Queue"),
+    ("threading.Lock(", "lock", "threading.Lock"),
+    ("threading.RLock(", "lock", "threading.RLock"),
+    ("asyncio.Lock(", "lock", "asyncio.Lock"),
+    ("threading.Semaphore(", "semaphore", "threading.Semaphore"),
+    ("asyncio.Semaphore(", "semaphore", "asyncio.Semaphore"),
+    ("threading.Event(", "signal", "threading.Event"),
+    ("asyncio.Event(", "signal", "asyncio.Event"),
+];
+
+const JS_MARKERS: &[Marker] = &[
+    ("new Worker(", "thread", "Worker"),
+    ("new SharedWorker(", "thread", "SharedWorker"),
+    ("cluster.fork(", "process", "cluster.fork"),
+    ("child_process.fork(", "process", "child_process.fork"),
+    ("Promise.all(", "task", "Promise.all"),
+    ("Promise.allSettled(", "task", "Promise.allSettled"),
+    ("Promise.race(", "task", "Promise.race"),
+    ("new MessageChannel(", "channel", "MessageChannel"),
+    ("new BroadcastChannel(", "channel", "BroadcastChannel"),
+    ("new SharedArrayBuffer(", "atomic", "SharedArrayBuffer"),
+    ("Atomics.", "atomic", "Atomics"),
+];
+
+const GO_MARKERS: &[Marker] = &[
+    ("make(chan ", "channel", "chan"),
+    ("sync.Mutex", "lock", "sync.Mutex"),
+    ("sync.RWMutex", "lock", "sync.RWMutex"),
+    ("sync.WaitGroup", "signal", "sync.WaitGroup"),
+    ("sync.Once", "signal", "sync.Once"),
+    ("sync.Cond", "signal", "sync.Cond"),
+    ("semaphore.NewWeighted(", "semaphore", "semaphore.Weighted"),
+    ("errgroup.WithContext(", "task", "errgroup"),
+    ("errgroup.Group", "task", "errgroup"),
+    ("atomic.", "atomic", "sync/atomic"),
+];
+
+const JVM_MARKERS: &[Marker] = &[
+    ("new Thread(", "thread", "Thread"),
+    ("Executors.new", "thread", "ExecutorService"),
+    ("CompletableFuture.", "task", "CompletableFuture"),
+    ("Task.Run(", "task", "Task.Run"),
+    ("Task.WhenAll(", "task", "Task.WhenAll"),
+    ("Parallel.For", "thread", "Parallel.For"),
+    ("GlobalScope.launch", "task", "GlobalScope.launch"),
+    ("launch {", "task", "launch"),
+    ("async {", "task", "async"),
+    ("Channel<", "channel", "Channel"),
+    ("BlockingQueue<", "channel", "BlockingQueue"),
+    ("ReentrantLock(", "lock", "ReentrantLock"),
+    ("ReentrantReadWriteLock(", "lock", "ReentrantReadWriteLock"),
+    ("synchronized", "lock", "synchronized"),
+    ("lock (", "lock", "lock"),
+    ("Mutex()", "lock", "Mutex"),
+    ("Semaphore(", "semaphore", "Semaphore"),
+    ("SemaphoreSlim(", "semaphore", "SemaphoreSlim"),
+    ("CountDownLatch(", "signal", "CountDownLatch"),
+    ("AtomicInteger", "atomic", "AtomicInteger"),
+    ("AtomicLong", "atomic", "AtomicLong"),
+    ("Interlocked.", "atomic", "Interlocked"),
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ConcurrencyFact {
+    /// `task`, `thread`, `process`, `channel`, `lock`, `semaphore`, `signal` or `atomic`.
+    pub kind: String,
+    pub primitive: String,
+    pub line: usize,
+}
+
+/// One primitive across the project with every place it is used.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConcurrencyUsage {
+    pub kind: String,
+    pub primitive: String,
+    /// `path:line` entries.
+    pub locations: Vec<String>,
+}
+
+/// Find spawned tasks, threads, channels, locks and other synchronization primitives in one
+/// source file.
+pub fn detect_concurrency(language: &str, source: &str) -> Vec<ConcurrencyFact> {
+    let markers = match language {
+        "rust" => RUST_MARKERS,
+        "python" => PYTHON_MARKERS,
+        "javascript" | "typescript" => JS_MARKERS,
+        "go" => GO_MARKERS,
+        "java" | "kotlin" | "csharp" => JVM_MARKERS,
+        _ => return Vec::new(),
+    };
+
+    let mut facts = Vec::new();
+    let mut seen = BTreeSet::new();
+    for (idx, raw_line) in source.lines().enumerate() {
+        let line = raw_line.trim();
+        let first_word = line.split_whitespace().next().unwrap_or_default();
+        if line.starts_with("//")
+            || line.starts_with('#')
+            || matches!(first_word, "use" | "import" | "from")
+        {
+            continue;
+        }
+
+        let mut found = markers
+            .iter()
+            .filter(|(marker, _, _)| contains_marker(line, marker))
+            .map(|(_, kind, primitive)| (*kind, *primitive))
+            .collect::<Vec<_>>();
+        // `go worker(ch)` / `go func() { ... }()`
+        if language == "go" && first_word == "go" && line.contains('(') {
+            found.push(("task", "goroutine"));
+        }
+
+        for (kind, primitive) in found {
+            if seen.insert((idx, primitive)) {
+                facts.push(ConcurrencyFact {
+                    kind: kind.to_string(),
+                    primitive: primitive.to_string(),
+                    line: idx + 1,
+                });
+            }
+        }
+    }
+    facts
+}
+
+/// Group per-file facts into one entry per primitive, sorted by kind then primitive.
+pub fn compile_inventory(facts: &[(String, ConcurrencyFact)]) -> Vec<ConcurrencyUsage> {
+    let mut grouped: BTreeMap<(&str, &str), ConcurrencyUsage> = BTreeMap::new();
+    for (path, fact) in facts {
+        grouped
+            .entry((fact.kind.as_str(), fact.primitive.as_str()))
+            .or_insert_with(|| ConcurrencyUsage {
+                kind: fact.kind.clone(),
+                primitive: fact.primitive.clone(),
+                locations: Vec::new(),
+            })
+            .locations
+            .push(format!("{path}:{}", fact.line));
+    }
+
+    grouped.into_values().collect()
+}
+
+/// `marker` not preceded by an identifier character, so `Mutex::new(` skips `MyMutex::new(`.
+fn contains_marker(line: &str, marker: &str) -> bool {
+    let mut search = 0usize;
+    while let Some(pos) = line[search..].find(marker) {
+        let start = search + pos;
+        search = start + marker.len();
+        let before = line[..start].chars().next_back();
+        if !before.is_some_and(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return true;
+        }
+    }
+    false
+}
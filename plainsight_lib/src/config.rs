@@ -1,9 +1,26 @@
-use crate::ollama::OllamaConfig;
+use std::path::PathBuf;
 
-#[derive(Debug, Clone)]
+use serde::Serialize;
+
+use crate::{file_walker::WalkLimits, ollama::OllamaConfig};
+
+#[derive(Debug, Clone, Serialize)]
 pub struct SourceDiscoveryConfig {
     pub extensions: Vec<String>,
     pub exclude_directories: Vec<String>,
+    /// Absolute directories never walked. The docs root is added for each run when it lies
+    /// inside the project root.
+    pub exclude_paths: Vec<PathBuf>,
+    pub walk_limits: WalkLimits,
+    /// Also pick up extensionless files (e.g. `bin/deploy`) whose shebang, modeline or content
+    /// identifies a language.
+    pub detect_extensionless: bool,
+    /// Files larger than this are streamed line by line during ingestion instead of being
+    /// read into memory whole.
+    pub stream_threshold_bytes: u64,
+    /// Threads that hash, read and index source files during ingestion; `None` uses one per
+    /// available CPU.
+    pub ingest_threads: Option<usize>,
 }
 
 impl Default for SourceDiscoveryConfig {
@@ -11,7 +28,7 @@ impl Default for SourceDiscoveryConfig {
         Self {
             extensions: vec![
                 "rs", "py", "js", "jsx", "ts", "tsx", "go", "java", "kt", "c", "h", "cc", "cpp",
-                "hpp", "cs",
+                "hpp", "cs", "rb", "php", "zig", "sql", "prisma",
             ]
             .into_iter()
             .map(str::to_string)
@@ -20,12 +37,320 @@ impl Default for SourceDiscoveryConfig {
                 .into_iter()
                 .map(str::to_string)
                 .collect(),
+            exclude_paths: Vec::new(),
+            walk_limits: WalkLimits::default(),
+            detect_extensionless: true,
+            stream_threshold_bytes: 1024 * 1024,
+            ingest_threads: None,
         }
     }
 }
 
-#[derive(Debug, Clone, Default)]
+/// Human-written files passed to the project summary as a "project metadata" block.
+///
+/// They are never documented on their own.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectMetadataConfig {
+    pub enabled: bool,
+    /// Files read relative to the project root when present.
+    pub files: Vec<String>,
+    /// Package manifests picked up anywhere in the project, matched by file name.
+    pub manifests: Vec<String>,
+    /// Directories whose YAML files are CI pipeline definitions.
+    pub ci_directories: Vec<String>,
+    /// Per-file character cap; longer files are truncated.
+    pub max_file_chars: usize,
+    pub max_manifests: usize,
+}
+
+impl Default for ProjectMetadataConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            files: vec![
+                "README.md",
+                ".gitlab-ci.yml",
+                ".circleci/config.yml",
+                "azure-pipelines.yml",
+                "Jenkinsfile",
+            ]
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+            manifests: vec!["Cargo.toml", "package.json"]
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+            ci_directories: vec![".github/workflows".to_string()],
+            max_file_chars: 4000,
+            max_manifests: 12,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TestCodeMode {
+    /// Document test code like any other source.
+    #[default]
+    Include,
+    /// Drop test files and test-only symbols before generation.
+    Exclude,
+    /// Keep test code out of the main sections and describe it under `## Testing`.
+    Separate,
+}
+
+/// Prompt-injection attempts in source; see [`crate::injection_guard`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InjectionGuard {
+    /// Replace instruction-like lines and escape output-like headings in the source sent to
+    /// the model, and list them in `redactions.md`.
+    #[default]
+    Neutralize,
+    /// Send source as it is, relying on the prompts' instructions alone.
+    Off,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelUpgradeMode {
+    /// Compare a sample against the previous outputs and stop the run on regressions or samples
+    /// that could not be compared; a run in another mode switches over.
+    #[default]
+    Guard,
+    /// Compare a sample and write the report, but switch over regardless.
+    ReportOnly,
+    /// Switch to the new models without comparing.
+    Off,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RustdocMode {
+    /// Rely on the line-based extractor alone.
+    #[default]
+    Off,
+    /// Run `cargo +nightly doc` with JSON output for Rust projects, compare its public items
+    /// with the extracted ones and pass its signatures to the docs prompt. Falls back to
+    /// `Off` with a warning when nightly or the build is unavailable.
+    Auto,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DigestFormat {
+    /// Slack `mrkdwn`.
+    #[default]
+    Slack,
+    /// Markdown as rendered by Microsoft Teams incoming webhooks.
+    Teams,
+}
+
+/// What to do when the docs directory for a project name already belongs to a different
+/// source root, e.g. two checkouts that share a folder name.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NameCollisionPolicy {
+    /// Stop the run with `PlainSightError::ProjectNameCollision`.
+    #[default]
+    Refuse,
+    /// Use the first free `<name>_2`, `<name>_3`, ... instead.
+    Suffix,
+    /// Ask for another name on the terminal, offering the suffixed one. Refuses when stdin is
+    /// not a terminal.
+    Prompt,
+}
+
+/// The order files are summarized and documented in, so that a run stopped early has covered
+/// the most useful ones.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GenerationOrder {
+    /// Entry points (`main.rs`, `lib.rs`, `index.ts`, ...) first, then files by how many other
+    /// files link to them, then by the size of their public API.
+    #[default]
+    Priority,
+    /// Path order.
+    Path,
+}
+
+/// Which outputs a run generates. A run that leaves one out keeps the files it regenerated
+/// marked for generation, and keeps its outputs in `.checkpoint.json` for a `resume` run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GenerationMode {
+    /// File summaries and docs, the project summary and the architecture docs.
+    #[default]
+    Full,
+    /// File summaries and the project summary only.
+    SummariesOnly,
+    /// File docs and the architecture docs only, next to the summaries already written.
+    DocsOnly,
+}
+
+impl GenerationMode {
+    /// Whether file summaries and the project summary are generated.
+    pub fn summaries(self) -> bool {
+        self != Self::DocsOnly
+    }
+
+    /// Whether file docs and the architecture docs are generated.
+    pub fn docs(self) -> bool {
+        self != Self::SummariesOnly
+    }
+}
+
+/// What the architecture docs are written from, besides the module tree and the other
+/// project-wide outlines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchitectureContext {
+    /// The start of each file's first source chunk.
+    #[default]
+    ChunkPreviews,
+    /// Each file's summary, falling back to its chunk preview when it has none, and the
+    /// project memory's cross-file links and global symbols. Smaller and more telling for
+    /// large projects.
+    Summaries,
+}
+
+/// Short chat message summarizing each full run, written to `digest.txt`.
+#[derive(Debug, Clone, Serialize)]
+pub struct DigestConfig {
+    pub enabled: bool,
+    pub format: DigestFormat,
+    /// Incoming webhook the digest is posted to after the run.
+    pub webhook_url: Option<String>,
+    /// Upper bound on the message length; file lists are cut short to fit.
+    pub max_chars: usize,
+}
+
+impl Default for DigestConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            format: DigestFormat::default(),
+            webhook_url: None,
+            max_chars: 3000,
+        }
+    }
+}
+
+/// Opt-in statistics of how each model fares at each task; see [`crate::model_stats`].
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ModelStatsConfig {
+    /// Add each run's request outcomes and latencies to `.model_stats.json` at the docs root.
+    pub enabled: bool,
+    /// Post the whole file as JSON here after each run that added to it.
+    pub upload_url: Option<String>,
+}
+
+/// How many failed files a run tolerates. A file fails when its summary or docs request still
+/// fails after the smaller-context retries, or when it runs over `OllamaConfig::file_timeout`;
+/// errors that concern the whole run, e.g. an unreachable server, stop it regardless. The
+/// default keeps going and lists the failed files in the run report.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ErrorPolicy {
+    /// Stop the run at the first failed file.
+    pub fail_fast: bool,
+    /// Stop the run once more than this many files failed.
+    pub max_file_failures: Option<usize>,
+    /// Fail the run, after writing its outputs, when fewer than this share (0.0 to 1.0) of the
+    /// files it regenerated succeeded.
+    pub min_success_ratio: Option<f64>,
+    /// Fail the run, after writing its outputs, when the average heuristic quality score (0.0
+    /// to 1.0) of the docs it regenerated, as listed in `run_report.json`, is below this.
+    pub min_quality: Option<f64>,
+}
+
+/// Outputs to regenerate even when their inputs did not change, e.g. after editing a prompt
+/// override or switching to a model whose name stayed the same.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct ForceConfig {
+    /// Every file summary.
+    pub summaries: bool,
+    /// Every file's docs.
+    pub docs: bool,
+    /// The project summary and architecture docs, and the directory summaries with
+    /// `PlainSightConfig::directory_summaries`.
+    pub project_level: bool,
+}
+
+impl ForceConfig {
+    /// Everything.
+    pub fn all() -> Self {
+        Self {
+            summaries: true,
+            docs: true,
+            project_level: true,
+        }
+    }
+}
+
+/// Oversized generated pages; see [`crate::page_split`].
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PageSplitConfig {
+    /// Split the project summary, architecture and file docs longer than this many lines into
+    /// one linked sub-page per section. `None` writes every page whole.
+    pub max_lines: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct PlainSightConfig {
     pub source_discovery: SourceDiscoveryConfig,
+    pub project_metadata: ProjectMetadataConfig,
     pub ollama: OllamaConfig,
+    pub test_code: TestCodeMode,
+    pub injection_guard: InjectionGuard,
+    pub model_upgrade: ModelUpgradeMode,
+    pub rustdoc: RustdocMode,
+    pub digest: DigestConfig,
+    pub name_collision: NameCollisionPolicy,
+    pub generation_order: GenerationOrder,
+    pub mode: GenerationMode,
+    /// Rebuild `.org_index.json` at the docs root after each full run, so symbols can be
+    /// searched across every project documented there.
+    pub org_index: bool,
+    /// Skip the server health check and all generation; only the source index, project memory
+    /// and other artifacts built from parsing are refreshed, and everything read from the source
+    /// tree is saved to `.ingest.json` for `from_index`. Files stay marked for generation.
+    pub offline: bool,
+    /// Generate from the `.ingest.json` an earlier `offline` run saved instead of discovering and
+    /// parsing the source tree, which need not be present. Runs with it cannot be scoped or use
+    /// `changed_since`.
+    pub from_index: bool,
+    /// Regenerate only the files `git diff --name-only` lists against this revision (a commit,
+    /// branch, tag or range), whatever the hash cache says; see [`crate::git_changes`]. Other
+    /// changed files keep their cache entries, so a later run without it still regenerates them.
+    pub changed_since: Option<String>,
+    /// Regenerate only the files these paths select, whatever the hash cache says: files,
+    /// directories or globs relative to the project root; see [`crate::path_selection`]. Like
+    /// `changed_since`, other changed files keep their cache entries; with both set a file must
+    /// be selected by each.
+    pub paths: Vec<PathBuf>,
+    /// Continue a run that stopped before finishing: the file summaries and docs it wrote, as
+    /// listed in `.checkpoint.json`, are kept for files whose content and task models are
+    /// unchanged instead of being generated again. Without it an unfinished run's checkpoint is
+    /// discarded.
+    pub resume: bool,
+    /// Roll the file summaries up into a `summary.md` per source directory, under
+    /// `files/<dir>/`, deepest directories first. The project summary is then written from the
+    /// top-level directories' summaries, and the architecture docs get every directory's.
+    pub directory_summaries: bool,
+    pub architecture_context: ArchitectureContext,
+    /// Keep the docs directories and `.meta.json` entries of source files that no longer exist.
+    /// Without it a finished run removes them, so deleted and renamed files leave no docs
+    /// behind.
+    pub keep_stale: bool,
+    /// In a run limited with `changed_since` or `paths`, the selected files are regenerated
+    /// whole and the rest is not forced.
+    pub force: ForceConfig,
+    /// Drop entries of hand-edited `.annotations.json`, `.skip.json` and `.memory.json` that do
+    /// not match their schema, keeping the original as `<file>.bak`, instead of failing with a
+    /// list of them.
+    pub repair_state_files: bool,
+    pub model_stats: ModelStatsConfig,
+    pub page_split: PageSplitConfig,
+    pub error_policy: ErrorPolicy,
 }
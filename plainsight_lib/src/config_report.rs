@@ -0,0 +1,136 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use crate::{
+    config::PlainSightConfig,
+    error::{PlainSightError, Result},
+};
+
+/// Source of values equal to `PlainSightConfig::default()` with no recorded override.
+pub const DEFAULT_SOURCE: &str = "default";
+/// Source of values that differ from the defaults with no recorded override, i.e. set by the
+/// program embedding the library.
+pub const CODE_SOURCE: &str = "code";
+
+/// The effective configuration of a run, with where each value came from.
+///
+/// Every leaf becomes `{ "value": ..., "source": ... }`; lists are single leaves. Secrets are
+/// redacted by the config types' serialization.
+#[derive(Debug, Clone)]
+pub struct ConfigReport {
+    root: Value,
+}
+
+impl ConfigReport {
+    /// `sources` maps dotted keys to where they were set, e.g. `ollama.host` to `--llm-url`.
+    /// A key also covers everything below it, so `ollama.tasks` can stand for every task model.
+    pub fn new(config: &PlainSightConfig, sources: &BTreeMap<String, String>) -> Result<Self> {
+        let effective = to_value(config)?;
+        let defaults = to_value(&PlainSightConfig::default())?;
+        Ok(Self {
+            root: annotate(effective, Some(&defaults), "", sources),
+        })
+    }
+
+    /// Add a setting that lives outside `PlainSightConfig`, such as the project root, under
+    /// the dotted `key`.
+    pub fn with_value(mut self, key: &str, value: impl Serialize, source: &str) -> Result<Self> {
+        let leaf = json!({ "value": to_value(&value)?, "source": source });
+        let mut node = &mut self.root;
+        for segment in key.split('.') {
+            if !node.is_object() {
+                *node = Value::Null;
+            }
+            node = &mut node[segment];
+        }
+        *node = leaf;
+        Ok(self)
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(&self.root).map_err(|e| {
+            PlainSightError::InvalidState(format!("serializing configuration as JSON: {e}"))
+        })
+    }
+
+    /// TOML has no null, so unset values keep only their `source`.
+    pub fn to_toml(&self) -> Result<String> {
+        toml::to_string(&without_nulls(self.root.clone())).map_err(|e| {
+            PlainSightError::InvalidState(format!("serializing configuration as TOML: {e}"))
+        })
+    }
+}
+
+fn to_value(value: &impl Serialize) -> Result<Value> {
+    serde_json::to_value(value)
+        .map_err(|e| PlainSightError::InvalidState(format!("serializing configuration: {e}")))
+}
+
+fn annotate(
+    value: Value,
+    default: Option<&Value>,
+    path: &str,
+    sources: &BTreeMap<String, String>,
+) -> Value {
+    match value {
+        Value::Object(fields) => Value::Object(
+            fields
+                .into_iter()
+                .map(|(key, field)| {
+                    let field_path = if path.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{path}.{key}")
+                    };
+                    let field_default = default.and_then(|default| default.get(&key));
+                    let field = annotate(field, field_default, &field_path, sources);
+                    (key, field)
+                })
+                .collect(),
+        ),
+        leaf => {
+            let source = recorded_source(path, sources).unwrap_or(if default == Some(&leaf) {
+                DEFAULT_SOURCE
+            } else {
+                CODE_SOURCE
+            });
+            json!({ "value": leaf, "source": source })
+        }
+    }
+}
+
+/// The source recorded for `path` or the closest key above it.
+fn recorded_source<'a>(path: &str, sources: &'a BTreeMap<String, String>) -> Option<&'a str> {
+    sources
+        .iter()
+        .filter(|(key, _)| {
+            path == key.as_str()
+                || path
+                    .strip_prefix(key.as_str())
+                    .is_some_and(|rest| rest.starts_with('.'))
+        })
+        .max_by_key(|(key, _)| key.len())
+        .map(|(_, source)| source.as_str())
+}
+
+fn without_nulls(value: Value) -> Value {
+    match value {
+        Value::Object(fields) => Value::Object(
+            fields
+                .into_iter()
+                .filter(|(_, field)| !field.is_null())
+                .map(|(key, field)| (key, without_nulls(field)))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .filter(|item| !item.is_null())
+                .map(without_nulls)
+                .collect(),
+        ),
+        other => other,
+    }
+}
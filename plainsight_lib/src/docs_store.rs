@@ -0,0 +1,382 @@
+use std::{
+    collections::{BTreeMap, btree_map::Entry},
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+/// Destination for generated documentation artifacts.
+///
+/// Paths passed to a store are the same paths `ProjectContext` computes under the docs root,
+/// so implementations decide how to map them onto their own namespace.
+pub trait DocsStore: Send + Sync + std::fmt::Debug {
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()>;
+    fn read_to_string(&self, path: &Path) -> io::Result<String>;
+    fn exists(&self, path: &Path) -> bool;
+    fn create_dir_all(&self, path: &Path) -> io::Result<()>;
+    /// Remove the directory at `path` and everything under it.
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()>;
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+
+    /// Write `path` only if nothing is there yet, failing with `ErrorKind::AlreadyExists`
+    /// otherwise. Runs take their project's lock with it, so stores shared between processes
+    /// should make the check and the write one step.
+    fn create_new(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        if self.exists(path) {
+            return Err(already_exists(path));
+        }
+        self.write(path, contents)
+    }
+}
+
+fn already_exists(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        format!("'{}' already exists", path.display()),
+    )
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LocalDocsStore;
+
+impl DocsStore for LocalDocsStore {
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        fs::write(path, contents)
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        fs::read_to_string(path)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        fs::create_dir_all(path)
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        fs::remove_dir_all(path)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+
+    fn create_new(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)?
+            .write_all(contents)
+    }
+}
+
+/// Keeps writes in memory on top of another store, for dry runs that must leave the docs
+/// root as it was. Reads see the kept writes first.
+#[derive(Debug)]
+pub struct DryRunDocsStore {
+    inner: Arc<dyn DocsStore>,
+    written: Mutex<BTreeMap<PathBuf, Vec<u8>>>,
+}
+
+impl DryRunDocsStore {
+    pub fn new(inner: Arc<dyn DocsStore>) -> Self {
+        Self {
+            inner,
+            written: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// The writes kept so far, by the path they were written to.
+    pub fn written_files(&self) -> BTreeMap<PathBuf, Vec<u8>> {
+        self.written().clone()
+    }
+
+    fn written(&self) -> MutexGuard<'_, BTreeMap<PathBuf, Vec<u8>>> {
+        self.written.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl DocsStore for DryRunDocsStore {
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        self.written().insert(path.to_path_buf(), contents.to_vec());
+        Ok(())
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        match self.written().get(path) {
+            Some(contents) => String::from_utf8(contents.clone())
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            None => self.inner.read_to_string(path),
+        }
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.written().contains_key(path) || self.inner.exists(path)
+    }
+
+    fn create_dir_all(&self, _path: &Path) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        self.written()
+            .retain(|written, _| !written.starts_with(path));
+        Ok(())
+    }
+
+    /// Drops a kept write; a file of the inner store is left in place.
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        self.written().remove(path);
+        Ok(())
+    }
+}
+
+/// Keeps every artifact in memory and never touches the filesystem, for runs whose outputs
+/// are uploaded or inspected by the embedding program. Each run starts from nothing, so it
+/// regenerates every file.
+///
+/// The model tools still read `.memory.json` and `.source_index.json` from the docs root on
+/// disk; without them there, tool calls answer with an error and the prompt context is used
+/// alone.
+#[derive(Debug, Default)]
+pub struct MemoryDocsStore {
+    files: Mutex<BTreeMap<PathBuf, Vec<u8>>>,
+}
+
+impl MemoryDocsStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The artifacts written so far, by the path they were written to.
+    pub fn files(&self) -> BTreeMap<PathBuf, Vec<u8>> {
+        self.lock().clone()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<PathBuf, Vec<u8>>> {
+        self.files.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl DocsStore for MemoryDocsStore {
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        self.lock().insert(path.to_path_buf(), contents.to_vec());
+        Ok(())
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        match self.lock().get(path) {
+            Some(contents) => String::from_utf8(contents.clone())
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("'{}' was not written", path.display()),
+            )),
+        }
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.lock().keys().any(|written| written.starts_with(path))
+    }
+
+    fn create_dir_all(&self, _path: &Path) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        self.lock().retain(|written, _| !written.starts_with(path));
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        self.lock()
+            .remove(path)
+            .map(|_| ())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "not written"))
+    }
+
+    fn create_new(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        match self.lock().entry(path.to_path_buf()) {
+            Entry::Occupied(_) => Err(already_exists(path)),
+            Entry::Vacant(entry) => {
+                entry.insert(contents.to_vec());
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(feature = "object-store")]
+pub use object::ObjectDocsStore;
+
+#[cfg(feature = "object-store")]
+mod object {
+    use std::{
+        io,
+        path::{Path, PathBuf},
+        sync::Arc,
+    };
+
+    use futures::{StreamExt, TryStreamExt};
+    use object_store::{
+        ObjectStore, PutPayload, aws::AmazonS3Builder, gcp::GoogleCloudStorageBuilder,
+        path::Path as ObjectPath,
+    };
+
+    use super::{DocsStore, LocalDocsStore};
+    use crate::error::{PlainSightError, Result};
+
+    /// Publishes artifacts to an S3 or GCS bucket as they are written.
+    ///
+    /// The local docs root stays the working copy: incremental runs read the previous
+    /// outputs from it and the model tools load `.memory.json`/`.source_index.json` from disk.
+    /// Every write lands locally first and is then uploaded under `prefix`.
+    ///
+    /// Uploads block the calling worker thread, so this store requires a multi-threaded
+    /// Tokio runtime.
+    #[derive(Debug)]
+    pub struct ObjectDocsStore {
+        store: Arc<dyn ObjectStore>,
+        docs_root: PathBuf,
+        prefix: String,
+        local: LocalDocsStore,
+    }
+
+    impl ObjectDocsStore {
+        pub fn new(
+            store: Arc<dyn ObjectStore>,
+            docs_root: impl Into<PathBuf>,
+            prefix: impl Into<String>,
+        ) -> Self {
+            Self {
+                store,
+                docs_root: docs_root.into(),
+                prefix: prefix.into().trim_matches('/').to_string(),
+                local: LocalDocsStore,
+            }
+        }
+
+        /// Build a store from a `s3://bucket/prefix` or `gs://bucket/prefix` URL.
+        ///
+        /// Credentials and region are read from the standard `AWS_*`/`GOOGLE_*` environment
+        /// variables.
+        pub fn from_url(url: &str, docs_root: impl Into<PathBuf>) -> Result<Self> {
+            let (scheme, rest) = url.split_once("://").ok_or_else(|| {
+                PlainSightError::InvalidState(format!("invalid docs store url '{url}'"))
+            })?;
+            let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+            if bucket.is_empty() {
+                return Err(PlainSightError::InvalidState(format!(
+                    "docs store url '{url}' is missing a bucket name"
+                )));
+            }
+
+            let store: Arc<dyn ObjectStore> = match scheme {
+                "s3" => Arc::new(
+                    AmazonS3Builder::from_env()
+                        .with_bucket_name(bucket)
+                        .build()
+                        .map_err(|e| {
+                            PlainSightError::InvalidState(format!("configuring S3 store: {e}"))
+                        })?,
+                ),
+                "gs" | "gcs" => Arc::new(
+                    GoogleCloudStorageBuilder::from_env()
+                        .with_bucket_name(bucket)
+                        .build()
+                        .map_err(|e| {
+                            PlainSightError::InvalidState(format!("configuring GCS store: {e}"))
+                        })?,
+                ),
+                other => {
+                    return Err(PlainSightError::InvalidState(format!(
+                        "unsupported docs store scheme '{other}'"
+                    )));
+                }
+            };
+
+            Ok(Self::new(store, docs_root, prefix))
+        }
+
+        fn object_path(&self, path: &Path) -> io::Result<ObjectPath> {
+            let relative = path.strip_prefix(&self.docs_root).map_err(|_| {
+                io::Error::other(format!(
+                    "'{}' is outside docs root '{}'",
+                    path.display(),
+                    self.docs_root.display()
+                ))
+            })?;
+
+            let mut key = self.prefix.clone();
+            for component in relative.components() {
+                if !key.is_empty() {
+                    key.push('/');
+                }
+                key.push_str(&component.as_os_str().to_string_lossy());
+            }
+
+            ObjectPath::parse(key).map_err(io::Error::other)
+        }
+    }
+
+    impl DocsStore for ObjectDocsStore {
+        fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+            self.local.write(path, contents)?;
+
+            let location = self.object_path(path)?;
+            let payload = PutPayload::from(contents.to_vec());
+            let handle = tokio::runtime::Handle::try_current().map_err(io::Error::other)?;
+            tokio::task::block_in_place(|| handle.block_on(self.store.put(&location, payload)))
+                .map(|_| ())
+                .map_err(io::Error::other)
+        }
+
+        fn read_to_string(&self, path: &Path) -> io::Result<String> {
+            self.local.read_to_string(path)
+        }
+
+        fn exists(&self, path: &Path) -> bool {
+            self.local.exists(path)
+        }
+
+        fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+            self.local.create_dir_all(path)
+        }
+
+        /// Only the run lock is written this way, and it is not published.
+        fn create_new(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+            self.local.create_new(path, contents)
+        }
+
+        /// Files removed one by one are run locks, which are never published.
+        fn remove_file(&self, path: &Path) -> io::Result<()> {
+            self.local.remove_file(path)
+        }
+
+        fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+            self.local.remove_dir_all(path)?;
+
+            let prefix = self.object_path(path)?;
+            let handle = tokio::runtime::Handle::try_current().map_err(io::Error::other)?;
+            tokio::task::block_in_place(|| {
+                handle.block_on(async {
+                    let locations = self
+                        .store
+                        .list(Some(&prefix))
+                        .map_ok(|object| object.location)
+                        .boxed();
+                    self.store
+                        .delete_stream(locations)
+                        .try_collect::<Vec<_>>()
+                        .await
+                })
+            })
+            .map(|_| ())
+            .map_err(io::Error::other)
+        }
+    }
+}
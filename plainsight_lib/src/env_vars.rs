@@ -0,0 +1,328 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+/// Calls whose first argument is the variable name, per language.
+const RUST_READS: &[&str] = &[
+    "env::var(",
+    "env::var_os(",
+    "option_env!(",
+    "env!(",
+    "dotenvy::var(",
+    "dotenv::var(",
+];
+const PYTHON_READS: &[&str] = &[
+    "os.environ[",
+    "os.environ.get(",
+    "os.getenv(",
+    "environ.get(",
+    "environ[",
+];
+const JS_READS: &[&str] = &["process.env["];
+const GO_READS: &[&str] = &["os.Getenv(", "os.LookupEnv(", "syscall.Getenv("];
+const JVM_READS: &[&str] = &["System.getenv(", "Environment.GetEnvironmentVariable("];
+
+/// Markers that introduce a default after the read on the same line.
+const RUST_DEFAULTS: &[&str] = &["unwrap_or(", "unwrap_or_else("];
+const JS_DEFAULTS: &[&str] = &["||", "??"];
+const JVM_DEFAULTS: &[&str] = &["?:", "??"];
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EnvVarRead {
+    pub name: String,
+    pub line: usize,
+    /// The call or attribute the read was found through, e.g. `env::var` or `env attribute`.
+    pub via: String,
+    #[serde(default)]
+    pub default: Option<String>,
+}
+
+/// One variable across the project: every default seen and every place it is read.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvVarUsage {
+    pub name: String,
+    pub defaults: Vec<String>,
+    pub via: Vec<String>,
+    /// `path:line` entries.
+    pub locations: Vec<String>,
+}
+
+/// Find environment variable reads and env-backed config attributes in one source file.
+pub fn detect_env_reads(language: &str, source: &str) -> Vec<EnvVarRead> {
+    let mut reads = Vec::new();
+
+    for (idx, raw_line) in source.lines().enumerate() {
+        let line = raw_line.trim();
+        let line_no = idx + 1;
+        let found = match language {
+            "rust" => {
+                let mut found = call_reads(line, RUST_READS, RUST_DEFAULTS);
+                if line.starts_with("#[") {
+                    found.extend(attribute_read(
+                        line,
+                        "env attribute",
+                        &["default_value_t", "default_value", "default"],
+                    ));
+                }
+                found
+            }
+            "python" => {
+                let mut found = call_reads(line, PYTHON_READS, &[]);
+                found.extend(attribute_read(line, "settings field", &["default"]));
+                found
+            }
+            "javascript" | "typescript" => {
+                let mut found = call_reads(line, JS_READS, JS_DEFAULTS);
+                found.extend(js_member_reads(line));
+                found
+            }
+            "go" => {
+                let mut found = call_reads(line, GO_READS, &[]);
+                found.extend(go_tag_read(line));
+                found
+            }
+            "java" | "kotlin" | "csharp" => call_reads(line, JVM_READS, JVM_DEFAULTS),
+            _ => Vec::new(),
+        };
+        reads.extend(found.into_iter().map(|(name, via, default)| EnvVarRead {
+            name,
+            line: line_no,
+            via,
+            default,
+        }));
+    }
+
+    // `env!(` also matches inside `option_env!(`; keep the first hit per line.
+    let mut seen = BTreeSet::new();
+    reads.retain(|read| seen.insert((read.name.clone(), read.line)));
+    reads
+}
+
+/// Merge per-file reads into one entry per variable, sorted by name.
+pub fn compile_inventory(reads: &[(String, EnvVarRead)]) -> Vec<EnvVarUsage> {
+    let mut grouped: BTreeMap<&str, EnvVarUsage> = BTreeMap::new();
+    for (path, read) in reads {
+        let usage = grouped
+            .entry(read.name.as_str())
+            .or_insert_with(|| EnvVarUsage {
+                name: read.name.clone(),
+                defaults: Vec::new(),
+                via: Vec::new(),
+                locations: Vec::new(),
+            });
+        if let Some(default) = &read.default
+            && !usage.defaults.contains(default)
+        {
+            usage.defaults.push(default.clone());
+        }
+        if !usage.via.contains(&read.via) {
+            usage.via.push(read.via.clone());
+        }
+        usage.locations.push(format!("{path}:{}", read.line));
+    }
+
+    grouped.into_values().collect()
+}
+
+/// Render `configuration.md` from a compiled inventory.
+pub fn render_inventory(project_name: &str, inventory: &[EnvVarUsage]) -> String {
+    let mut out = format!("# {project_name} Configuration\n\n");
+    if inventory.is_empty() {
+        out.push_str("No environment variable reads were found.\n");
+        return out;
+    }
+
+    out.push_str(&format!(
+        "{} environment variable(s) are read by the project.\n\n",
+        inventory.len()
+    ));
+    out.push_str("| Variable | Default | Read via | Locations |\n");
+    out.push_str("|---|---|---|---|\n");
+    for usage in inventory {
+        let defaults = if usage.defaults.is_empty() {
+            "-".to_string()
+        } else {
+            usage
+                .defaults
+                .iter()
+                .map(|d| format!("`{d}`"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let via = usage
+            .via
+            .iter()
+            .map(|v| format!("`{v}`"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let locations = usage
+            .locations
+            .iter()
+            .map(|l| format!("`{l}`"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        out.push_str(&format!(
+            "| `{}` | {defaults} | {via} | {locations} |\n",
+            usage.name
+        ));
+    }
+
+    let conflicting = inventory
+        .iter()
+        .filter(|usage| usage.defaults.len() > 1)
+        .collect::<Vec<_>>();
+    if !conflicting.is_empty() {
+        out.push_str("\n## Conflicting Defaults\n\n");
+        for usage in conflicting {
+            out.push_str(&format!(
+                "- `{}` falls back to different values: {}\n",
+                usage.name,
+                usage.defaults.join(", ")
+            ));
+        }
+    }
+
+    out
+}
+
+type Found = (String, String, Option<String>);
+
+fn call_reads(line: &str, markers: &[&str], default_markers: &[&str]) -> Vec<Found> {
+    let mut found = Vec::new();
+    for marker in markers {
+        let mut search = line;
+        while let Some(pos) = search.find(marker) {
+            let after = &search[pos + marker.len()..];
+            let before = &search[..pos];
+            search = after;
+            // `environ[` / `environ.get(` are the `from os import environ` forms; the
+            // `os.`-qualified calls have their own markers.
+            if marker.starts_with("environ") && before.ends_with("os.") {
+                continue;
+            }
+            let Some((name, rest)) = string_literal(after) else {
+                continue;
+            };
+            if !is_env_name(&name) {
+                continue;
+            }
+            let default = second_argument(rest).or_else(|| default_after(rest, default_markers));
+            let via = marker.trim_end_matches(['(', '[']).to_string();
+            found.push((name, via, default));
+        }
+    }
+    found
+}
+
+/// `env = "NAME"` inside a config attribute or field declaration.
+fn attribute_read(line: &str, via: &str, default_keys: &[&str]) -> Option<Found> {
+    let name = keyword_value(line, "env")?;
+    if !is_env_name(&name) {
+        return None;
+    }
+    let default = default_keys.iter().find_map(|key| keyword_value(line, key));
+    Some((name, via.to_string(), default))
+}
+
+/// `process.env.NAME`, with an optional `|| default` / `?? default`.
+fn js_member_reads(line: &str) -> Vec<Found> {
+    let mut found = Vec::new();
+    let mut search = line;
+    while let Some(pos) = search.find("process.env.") {
+        let after = &search[pos + "process.env.".len()..];
+        search = after;
+        let end = after
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(after.len());
+        let name = &after[..end];
+        if !is_env_name(name) {
+            continue;
+        }
+        let default = default_after(&after[end..], JS_DEFAULTS);
+        found.push((name.to_string(), "process.env".to_string(), default));
+    }
+    found
+}
+
+/// Struct tags like `env:"NAME" envDefault:"value"` (caarlos0/env, envconfig).
+fn go_tag_read(line: &str) -> Option<Found> {
+    let start = line.find("env:\"")? + "env:\"".len();
+    let end = line[start..].find('"')?;
+    let name = line[start..start + end].split(',').next()?.to_string();
+    if !is_env_name(&name) {
+        return None;
+    }
+    let default = line.find("envDefault:\"").and_then(|pos| {
+        let rest = &line[pos + "envDefault:\"".len()..];
+        rest.find('"').map(|end| rest[..end].to_string())
+    });
+    Some((name, "env struct tag".to_string(), default))
+}
+
+/// Value of `key = "value"` / `key="value"` / `key = value` as a whole word in `line`.
+fn keyword_value(line: &str, key: &str) -> Option<String> {
+    let mut search = line;
+    while let Some(pos) = search.find(key) {
+        let before = search[..pos].chars().next_back();
+        let after = search[pos + key.len()..].trim_start();
+        search = &search[pos + key.len()..];
+        if before.is_some_and(|c| c.is_ascii_alphanumeric() || c == '_') {
+            continue;
+        }
+        let Some(value) = after.strip_prefix('=') else {
+            continue;
+        };
+        if value.starts_with('=') {
+            continue;
+        }
+        return literal(value.trim_start()).map(|(value, _)| value);
+    }
+    None
+}
+
+fn second_argument(rest: &str) -> Option<String> {
+    let rest = rest.trim_start().strip_prefix(',')?;
+    literal(rest.trim_start()).map(|(value, _)| value)
+}
+
+fn default_after(rest: &str, markers: &[&str]) -> Option<String> {
+    markers.iter().find_map(|marker| {
+        let pos = rest.find(marker)?;
+        let after = rest[pos + marker.len()..].trim_start();
+        // `unwrap_or_else(|_| "x".to_string())`
+        let after = after
+            .strip_prefix("|_|")
+            .or_else(|| after.strip_prefix("||"))
+            .unwrap_or(after)
+            .trim_start();
+        literal(after).map(|(value, _)| value)
+    })
+}
+
+/// A leading string, number or boolean literal.
+fn literal(text: &str) -> Option<(String, &str)> {
+    if let Some(found) = string_literal(text) {
+        return Some(found);
+    }
+    let end = text
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '_'))
+        .unwrap_or(text.len());
+    let token = &text[..end];
+    let is_literal =
+        token.parse::<f64>().is_ok() || matches!(token, "true" | "false" | "True" | "False");
+    is_literal.then(|| (token.to_string(), &text[end..]))
+}
+
+fn string_literal(text: &str) -> Option<(String, &str)> {
+    let text = text.trim_start();
+    let quote = text.chars().next().filter(|c| matches!(c, '"' | '\''))?;
+    let rest = &text[1..];
+    let end = rest.find(quote)?;
+    Some((rest[..end].to_string(), &rest[end + 1..]))
+}
+
+fn is_env_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+}
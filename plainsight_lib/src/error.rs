@@ -2,6 +2,8 @@ use std::path::PathBuf;
 
 use thiserror::Error;
 
+use crate::ollama::OllamaError;
+
 #[derive(Debug, Error)]
 pub enum PlainSightError {
     #[error("I/O error while {context}: {source}")]
@@ -12,7 +14,7 @@ pub enum PlainSightError {
     },
 
     #[error("ollama error: {0}")]
-    Ollama(String),
+    Ollama(#[from] OllamaError),
 
     #[error("file path '{path}' is outside project root '{project_root}'")]
     PathOutsideProject {
@@ -20,8 +22,43 @@ pub enum PlainSightError {
         project_root: PathBuf,
     },
 
+    #[error(
+        "project name '{project_name}' already holds docs for '{existing_root}', not '{project_root}'; pass a different project name"
+    )]
+    ProjectNameCollision {
+        project_name: String,
+        existing_root: PathBuf,
+        project_root: PathBuf,
+    },
+
+    #[error(
+        "another run ({holder}) holds the lock '{}'; wait for it to finish, or delete the file if no run is active",
+        lock_path.display()
+    )]
+    ProjectLocked { lock_path: PathBuf, holder: String },
+
+    /// The run failed more files, or wrote lower-quality docs, than `ErrorPolicy` allows.
+    #[error("stopped by the error policy: {reason}")]
+    TooManyFailures {
+        failed_files: Vec<String>,
+        reason: String,
+    },
+
     #[error("invalid state: {0}")]
     InvalidState(String),
+
+    /// A `PipelineHook` failed, which stops the run.
+    #[error("pipeline hook failed {when} the {stage} stage: {source}")]
+    Hook {
+        when: &'static str,
+        stage: &'static str,
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    /// The run's `CancellationToken` was cancelled.
+    #[error("cancelled")]
+    Cancelled,
 }
 
 impl PlainSightError {
@@ -31,6 +68,11 @@ impl PlainSightError {
             source,
         }
     }
+
+    /// Whether the LLM request failed in a way that a repeated or smaller request may avoid.
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Self::Ollama(err) if err.is_transient())
+    }
 }
 
 pub type Result<T> = std::result::Result<T, PlainSightError>;
@@ -0,0 +1,702 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::Range,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::rust_source;
+
+/// Construction sites listed per variant before the rest are summarized as a count.
+const MAX_LISTED_SITES: usize = 10;
+
+/// Base classes whose subclasses are treated as error types.
+const EXCEPTION_BASES: &[&str] = &["Error", "Exception", "Throwable", "BaseException"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorType {
+    pub name: String,
+    /// `enum`, `struct`, `class` or `sentinel` (Go `var ErrX = errors.New(..)`).
+    pub kind: String,
+    pub line: usize,
+    /// How the type was recognized, e.g. `derive(Error)`, `impl Error`, `extends Exception`.
+    pub marker: String,
+    #[serde(default)]
+    pub message: String,
+    #[serde(default)]
+    pub variants: Vec<ErrorVariant>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorVariant {
+    pub name: String,
+    /// The `#[error("...")]` display message when present.
+    #[serde(default)]
+    pub message: String,
+}
+
+/// An error type with the file it is defined in and where its variants are constructed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CatalogEntry {
+    pub path: String,
+    pub error: ErrorType,
+    /// `path:line` sites keyed by variant name; the empty key holds sites of the type itself.
+    pub constructed_at: BTreeMap<String, Vec<String>>,
+}
+
+/// Find error enums, error structs, exception classes and sentinel errors in one file.
+pub fn detect_error_types(language: &str, source: &str) -> Vec<ErrorType> {
+    match language {
+        "rust" => rust_error_types(source),
+        "go" => go_error_types(source),
+        "python" | "javascript" | "typescript" | "java" | "kotlin" | "csharp" | "ruby" | "php" => {
+            class_error_types(source)
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Build the catalog and attach construction sites found in `sources` — `(path, start_line,
+/// text)` chunks that may overlap.
+pub fn build_catalog<'a>(
+    errors: Vec<(String, ErrorType)>,
+    sources: impl IntoIterator<Item = (&'a str, usize, &'a str)>,
+) -> Vec<CatalogEntry> {
+    let mut entries = errors
+        .into_iter()
+        .map(|(path, error)| CatalogEntry {
+            path,
+            error,
+            constructed_at: BTreeMap::new(),
+        })
+        .collect::<Vec<_>>();
+    let mut seen: Vec<BTreeMap<String, BTreeSet<(String, usize)>>> =
+        vec![BTreeMap::new(); entries.len()];
+
+    for (path, start_line, text) in sources {
+        let patterns = if path.ends_with(".rs") {
+            rust_pattern_spans(text)
+        } else {
+            Vec::new()
+        };
+        let mut line_start = 0usize;
+        for (offset, raw_line) in text.split_inclusive('\n').enumerate() {
+            let line_no = start_line + offset;
+            let line = raw_line.trim();
+            // Pattern spans on this line, relative to the trimmed `line`.
+            let indent = line_start + raw_line.len() - raw_line.trim_start().len();
+            let line_patterns = patterns
+                .iter()
+                .filter(|span| span.start < indent + line.len() && span.end > indent)
+                .map(|span| span.start.saturating_sub(indent)..span.end - indent)
+                .collect::<Vec<_>>();
+            line_start += raw_line.len();
+            if line.is_empty() || line.starts_with("//") || line.starts_with('#') {
+                continue;
+            }
+            for (entry, sites) in entries.iter().zip(seen.iter_mut()) {
+                for key in constructions(entry, path, line_no, line, &line_patterns) {
+                    sites
+                        .entry(key)
+                        .or_default()
+                        .insert((path.to_string(), line_no));
+                }
+            }
+        }
+    }
+
+    for (entry, sites) in entries.iter_mut().zip(seen) {
+        entry.constructed_at = sites
+            .into_iter()
+            .map(|(key, sites)| {
+                let sites = sites
+                    .into_iter()
+                    .map(|(path, line)| format!("{path}:{line}"))
+                    .collect();
+                (key, sites)
+            })
+            .collect();
+    }
+    entries.sort_by(|a, b| a.error.name.cmp(&b.error.name).then(a.path.cmp(&b.path)));
+    entries
+}
+
+/// Render `errors.md`.
+pub fn render_catalog(project_name: &str, catalog: &[CatalogEntry]) -> String {
+    let mut out = format!("# {project_name} Error Reference\n\n");
+    if catalog.is_empty() {
+        out.push_str("No error types were found.\n");
+        return out;
+    }
+    out.push_str(&format!("{} error type(s).\n", catalog.len()));
+
+    for entry in catalog {
+        let error = &entry.error;
+        out.push_str(&format!(
+            "\n## {}\n\n{} defined in `{}:{}` ({}).\n",
+            error.name, error.kind, entry.path, error.line, error.marker
+        ));
+        if !error.message.is_empty() {
+            out.push_str(&format!("\nMessage: `{}`\n", error.message));
+        }
+
+        if error.variants.is_empty() {
+            out.push_str(&format!(
+                "\nConstructed at: {}\n",
+                format_sites(entry.constructed_at.get(""))
+            ));
+            continue;
+        }
+
+        out.push_str("\n| Variant | Message | Constructed at |\n");
+        out.push_str("|---|---|---|\n");
+        for variant in &error.variants {
+            let message = if variant.message.is_empty() {
+                "-".to_string()
+            } else {
+                format!("`{}`", variant.message.replace('|', "\\|"))
+            };
+            out.push_str(&format!(
+                "| `{}` | {message} | {} |\n",
+                variant.name,
+                format_sites(entry.constructed_at.get(&variant.name))
+            ));
+        }
+    }
+
+    out
+}
+
+fn format_sites(sites: Option<&Vec<String>>) -> String {
+    let Some(sites) = sites.filter(|sites| !sites.is_empty()) else {
+        return "not constructed in scanned code".to_string();
+    };
+    let mut listed = sites
+        .iter()
+        .take(MAX_LISTED_SITES)
+        .map(|site| format!("`{site}`"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    if sites.len() > MAX_LISTED_SITES {
+        listed.push_str(&format!(" and {} more", sites.len() - MAX_LISTED_SITES));
+    }
+    listed
+}
+
+/// Which variants of `entry` (or the type itself, as `""`) `line` constructs, leaving out
+/// mentions inside the `patterns` spans of the line.
+fn constructions(
+    entry: &CatalogEntry,
+    path: &str,
+    line_no: usize,
+    line: &str,
+    patterns: &[Range<usize>],
+) -> Vec<String> {
+    let error = &entry.error;
+    let in_definition_file = path == entry.path;
+    if in_definition_file && line_no == error.line {
+        return Vec::new();
+    }
+    // Declarations and match arms mention the type without constructing it.
+    let first_word = line.split_whitespace().next().unwrap_or_default();
+    if matches!(
+        first_word,
+        "impl" | "class" | "struct" | "enum" | "type" | "use" | "import" | "from" | "func"
+    ) {
+        return Vec::new();
+    }
+
+    if !error.variants.is_empty() {
+        return error
+            .variants
+            .iter()
+            .filter(|variant| {
+                let qualified = format!("{}::{}", error.name, variant.name);
+                let own = format!("Self::{}", variant.name);
+                find_construction(line, &qualified, patterns)
+                    || (in_definition_file && find_construction(line, &own, patterns))
+            })
+            .map(|variant| variant.name.clone())
+            .collect();
+    }
+
+    let constructs = match error.kind.as_str() {
+        "sentinel" => contains_word(line, &error.name) && !line.contains(" = errors.New("),
+        _ => [
+            format!("{}(", error.name),
+            format!("{} {{", error.name),
+            format!("{}{{", error.name),
+            format!("{}::new(", error.name),
+        ]
+        .iter()
+        .any(|pattern| find_construction(line, pattern, patterns)),
+    };
+    if constructs {
+        vec![String::new()]
+    } else {
+        Vec::new()
+    }
+}
+
+/// Whether `pattern` appears as a whole identifier outside the `patterns` spans and not as a
+/// match arm pattern.
+fn find_construction(line: &str, pattern: &str, patterns: &[Range<usize>]) -> bool {
+    let mut search = 0usize;
+    while let Some(pos) = line[search..].find(pattern) {
+        let start = search + pos;
+        let end = start + pattern.len();
+        search = end;
+
+        if patterns.iter().any(|span| span.contains(&start)) {
+            continue;
+        }
+        let before = line[..start].chars().next_back();
+        if before.is_some_and(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':') {
+            continue;
+        }
+        let after = line[end..].chars().next();
+        let pattern_ends_in_ident = pattern
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_');
+        if pattern_ends_in_ident && after.is_some_and(|c| c.is_ascii_alphanumeric() || c == '_') {
+            continue;
+        }
+        if line[end..].contains("=>") && !line[..start].contains("=>") {
+            continue;
+        }
+        return true;
+    }
+    false
+}
+
+/// Byte ranges of `source`, Rust code, where a path names a pattern rather than a value:
+/// `matches!` arguments, match arm patterns up to their `=>`, and `let` patterns (including
+/// `if let`, `while let` and `let ... else`) up to their `=`.
+fn rust_pattern_spans(source: &str) -> Vec<Range<usize>> {
+    let code = rust_source::mask_comments_and_literals(source);
+    let bytes = code.as_bytes();
+    let mut spans = Vec::new();
+
+    for (start, _) in code.match_indices("matches!(") {
+        if !keyword_at(bytes, start, "matches!(") {
+            continue;
+        }
+        let open = start + "matches!".len();
+        if let Some(close) = closing_bracket(bytes, open) {
+            spans.push(open + 1..close);
+        }
+    }
+
+    for (start, _) in code.match_indices("let") {
+        if !keyword_at(bytes, start, "let") {
+            continue;
+        }
+        let from = start + "let".len();
+        if let Some(end) = scan_top_level(bytes, from, bytes.len(), |idx| {
+            binding_equals(bytes, idx) || bytes[idx] == b';'
+        }) {
+            spans.push(from..end);
+        }
+    }
+
+    // Match arm patterns, found from their `=>` so that arms of a `match` begun before the
+    // start of `source` count too.
+    for (arrow, _) in code.match_indices("=>") {
+        spans.push(arm_start(bytes, arrow)..arrow);
+    }
+
+    spans
+}
+
+/// Where the match arm whose `=>` is at `arrow` begins: after the previous `,`, `;`, opening
+/// bracket or arm block body, going back over nested brackets.
+fn arm_start(bytes: &[u8], arrow: usize) -> usize {
+    let mut closers = Vec::new();
+    for idx in (0..arrow).rev() {
+        match bytes[idx] {
+            b')' | b']' | b'}' => closers.push(idx),
+            b'(' | b'[' | b'{' => {
+                let Some(close) = closers.pop() else {
+                    return idx + 1;
+                };
+                let before = bytes[..idx].trim_ascii_end();
+                if closers.is_empty() && bytes[idx] == b'{' && before.ends_with(b"=>") {
+                    return close + 1;
+                }
+            }
+            b',' | b';' if closers.is_empty() => return idx + 1,
+            _ => {}
+        }
+    }
+    0
+}
+
+/// Whether `word` starts at `start` and is not part of a longer identifier.
+fn keyword_at(bytes: &[u8], start: usize, word: &str) -> bool {
+    let is_ident = |byte: &u8| byte.is_ascii_alphanumeric() || *byte == b'_';
+    let before = start.checked_sub(1).map(|idx| &bytes[idx]);
+    let after = bytes.get(start + word.len());
+    !before.is_some_and(is_ident) && (word.ends_with('(') || !after.is_some_and(is_ident))
+}
+
+/// Whether `bytes[idx]` is the `=` of a binding, not part of `==`, `=>`, `<=`, `>=` or `!=`.
+fn binding_equals(bytes: &[u8], idx: usize) -> bool {
+    bytes[idx] == b'='
+        && !matches!(bytes.get(idx + 1), Some(b'=' | b'>'))
+        && !idx
+            .checked_sub(1)
+            .is_some_and(|prev| matches!(bytes[prev], b'=' | b'<' | b'>' | b'!'))
+}
+
+/// The first index in `from..to` outside nested brackets where `stop` holds.
+fn scan_top_level(
+    bytes: &[u8],
+    from: usize,
+    to: usize,
+    stop: impl Fn(usize) -> bool,
+) -> Option<usize> {
+    let mut depth = 0usize;
+    for (idx, byte) in bytes.iter().enumerate().take(to).skip(from) {
+        if depth == 0 && stop(idx) {
+            return Some(idx);
+        }
+        match byte {
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => {
+                if depth == 0 {
+                    return None;
+                }
+                depth -= 1;
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The index of the bracket closing the one at `open`.
+fn closing_bracket(bytes: &[u8], open: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    for (idx, byte) in bytes.iter().enumerate().skip(open) {
+        match byte {
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    return Some(idx);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn contains_word(line: &str, word: &str) -> bool {
+    line.split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .any(|token| token == word)
+}
+
+fn rust_error_types(source: &str) -> Vec<ErrorType> {
+    let lines = source
+        .lines()
+        .map(|line| {
+            line.split_once("//")
+                .map(|(left, _)| left)
+                .unwrap_or(line)
+                .trim()
+        })
+        .collect::<Vec<_>>();
+
+    let implemented = lines
+        .iter()
+        .filter_map(|line| {
+            let rest = line.strip_prefix("impl")?;
+            let (trait_name, target) = rest.split_once(" for ")?;
+            let trait_name = trait_name.trim().rsplit("::").next()?.trim();
+            (trait_name == "Error").then(|| identifier(target.trim()))
+        })
+        .filter(|name| !name.is_empty())
+        .collect::<BTreeSet<_>>();
+
+    let mut errors = Vec::new();
+    let mut derives_error = false;
+    let mut message = String::new();
+    let mut attribute_end = 0;
+    for (idx, line) in lines.iter().enumerate() {
+        if idx < attribute_end {
+            continue;
+        }
+        if line.starts_with("#[") {
+            let (attribute, len) = attribute_text(&lines[idx..]);
+            attribute_end = idx + len;
+            if attribute.starts_with("#[derive(") {
+                derives_error |= attribute
+                    .trim_start_matches("#[derive(")
+                    .split([',', ')'])
+                    .any(|derive| derive.trim().rsplit("::").next() == Some("Error"));
+            } else if let Some(found) = error_attribute_message(&attribute) {
+                message = found;
+            }
+            continue;
+        }
+
+        let item = line
+            .strip_prefix("pub(crate) ")
+            .or_else(|| line.strip_prefix("pub(super) "))
+            .or_else(|| line.strip_prefix("pub "))
+            .unwrap_or(line);
+        let kind = if item.starts_with("enum ") {
+            Some("enum")
+        } else if item.starts_with("struct ") {
+            Some("struct")
+        } else {
+            None
+        };
+
+        if let Some(kind) = kind {
+            let name = identifier(item[kind.len()..].trim_start());
+            let marker = if derives_error {
+                Some("derive(Error)")
+            } else if implemented.contains(&name) {
+                Some("impl Error")
+            } else {
+                None
+            };
+            if let Some(marker) = marker {
+                let variants = if kind == "enum" {
+                    rust_enum_variants(&lines[idx..])
+                } else {
+                    Vec::new()
+                };
+                errors.push(ErrorType {
+                    message: std::mem::take(&mut message),
+                    name,
+                    kind: kind.to_string(),
+                    line: idx + 1,
+                    marker: marker.to_string(),
+                    variants,
+                });
+            }
+        }
+        derives_error = false;
+        message.clear();
+    }
+    errors
+}
+
+/// Variants of the enum whose declaration starts at `lines[0]`, with their `#[error]` messages.
+fn rust_enum_variants(lines: &[&str]) -> Vec<ErrorVariant> {
+    let mut variants = Vec::new();
+    let mut depth = 0i64;
+    let mut message = String::new();
+    let mut attribute_end = 0;
+
+    for (idx, line) in lines.iter().enumerate() {
+        if idx < attribute_end {
+            continue;
+        }
+        if idx > 0 && depth == 1 {
+            if line.starts_with("#[") {
+                let (attribute, len) = attribute_text(&lines[idx..]);
+                attribute_end = idx + len;
+                if let Some(found) = error_attribute_message(&attribute) {
+                    message = found;
+                }
+                continue;
+            } else if line.starts_with(|c: char| c.is_ascii_uppercase()) {
+                variants.push(ErrorVariant {
+                    name: identifier(line),
+                    message: std::mem::take(&mut message),
+                });
+            }
+        }
+        for ch in line.chars() {
+            match ch {
+                '{' | '(' => depth += 1,
+                '}' | ')' => depth -= 1,
+                _ => {}
+            }
+        }
+        if idx > 0 && depth <= 0 {
+            break;
+        }
+    }
+    variants
+}
+
+/// The attribute starting at `lines[0]`, joined across lines until its brackets balance, and
+/// the number of lines it spans. Brackets inside string literals are not counted; an attribute
+/// that never closes is taken to be its first line.
+fn attribute_text(lines: &[&str]) -> (String, usize) {
+    let mut text = String::new();
+    let mut depth = 0i64;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (idx, line) in lines.iter().enumerate() {
+        // A string continued with a trailing `\` picks up again after the next line's
+        // indentation; any other line break becomes a space.
+        if in_string && text.ends_with('\\') {
+            text.pop();
+            escaped = false;
+        } else if idx > 0 {
+            text.push(' ');
+        }
+        text.push_str(line);
+        for ch in line.chars() {
+            if in_string {
+                match ch {
+                    _ if escaped => escaped = false,
+                    '\\' => escaped = true,
+                    '"' => in_string = false,
+                    _ => {}
+                }
+                continue;
+            }
+            match ch {
+                '"' => in_string = true,
+                '[' | '(' | '{' => depth += 1,
+                ']' | ')' | '}' => depth -= 1,
+                _ => {}
+            }
+        }
+        if depth <= 0 && !in_string {
+            return (text, idx + 1);
+        }
+    }
+    (lines.first().copied().unwrap_or_default().to_string(), 1)
+}
+
+/// The format string of an `#[error("...", ...)]` attribute, escapes left as written.
+fn error_attribute_message(attribute: &str) -> Option<String> {
+    let literal = attribute
+        .strip_prefix("#[error(")?
+        .trim_start()
+        .strip_prefix('"')?;
+    let mut escaped = false;
+    let end = literal.char_indices().find_map(|(idx, ch)| {
+        match ch {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return Some(idx),
+            _ => {}
+        }
+        None
+    })?;
+    Some(literal[..end].to_string())
+}
+
+fn go_error_types(source: &str) -> Vec<ErrorType> {
+    let lines = source.lines().map(str::trim).collect::<Vec<_>>();
+    let implemented = lines
+        .iter()
+        .filter_map(|line| {
+            let rest = line.strip_prefix("func (")?;
+            let (receiver, method) = rest.split_once(')')?;
+            if !method.trim_start().starts_with("Error() string") {
+                return None;
+            }
+            let receiver_type = receiver.split_whitespace().last()?;
+            Some(identifier(receiver_type.trim_start_matches('*')))
+        })
+        .collect::<BTreeSet<_>>();
+
+    let mut errors = Vec::new();
+    for (idx, line) in lines.iter().enumerate() {
+        if let Some(rest) = line.strip_prefix("type ") {
+            let name = identifier(rest);
+            if implemented.contains(&name) {
+                errors.push(ErrorType {
+                    name,
+                    kind: "struct".to_string(),
+                    line: idx + 1,
+                    marker: "Error() string".to_string(),
+                    message: String::new(),
+                    variants: Vec::new(),
+                });
+            }
+            continue;
+        }
+
+        let declaration = line.strip_prefix("var ").unwrap_or(line);
+        let Some((name, value)) = declaration.split_once('=') else {
+            continue;
+        };
+        let name = name.trim();
+        let value = value.trim();
+        let is_sentinel = name.starts_with("Err")
+            && (value.starts_with("errors.New(") || value.starts_with("fmt.Errorf("));
+        if is_sentinel && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            let message = value
+                .split('"')
+                .nth(1)
+                .map(str::to_string)
+                .unwrap_or_default();
+            errors.push(ErrorType {
+                name: name.to_string(),
+                kind: "sentinel".to_string(),
+                line: idx + 1,
+                marker: "errors.New".to_string(),
+                message,
+                variants: Vec::new(),
+            });
+        }
+    }
+    errors
+}
+
+/// `class X(Exception)`, `class X extends Error`, `class X : Exception`, `class X < StandardError`.
+fn class_error_types(source: &str) -> Vec<ErrorType> {
+    let mut errors = Vec::new();
+    for (idx, raw_line) in source.lines().enumerate() {
+        let line = raw_line.trim();
+        let Some(class_pos) = line.find("class ") else {
+            continue;
+        };
+        if class_pos > 0
+            && line[..class_pos]
+                .chars()
+                .next_back()
+                .is_some_and(|c| !c.is_whitespace())
+        {
+            continue;
+        }
+        let rest = &line[class_pos + "class ".len()..];
+        let name = identifier(rest);
+        if name.is_empty() {
+            continue;
+        }
+        let after_name = &rest[name.len()..];
+        let base = ["extends ", "(", ": ", ":", "< "]
+            .iter()
+            .find_map(|marker| after_name.trim_start().strip_prefix(marker))
+            .map(|base| identifier(base.trim_start()))
+            .unwrap_or_default();
+        let is_error = EXCEPTION_BASES.iter().any(|suffix| base.ends_with(suffix));
+        if is_error {
+            errors.push(ErrorType {
+                marker: format!("extends {base}"),
+                name,
+                kind: "class".to_string(),
+                line: idx + 1,
+                message: String::new(),
+                variants: Vec::new(),
+            });
+        }
+    }
+    errors
+}
+
+/// Leading identifier, dropping generics and qualifiers (`a::B<T>` → `B`).
+fn identifier(text: &str) -> String {
+    let token = text
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == ':' || c == '.'))
+        .next()
+        .unwrap_or_default();
+    token
+        .rsplit(['.', ':'])
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
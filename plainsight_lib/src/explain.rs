@@ -0,0 +1,234 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde_json::json;
+use tracing::warn;
+
+use crate::{
+    config::InjectionGuard,
+    error::{PlainSightError, Result},
+    injection_guard,
+    language::LanguageDetector,
+    memory::{self, ProjectMemory, SymbolFact},
+    project_manager::ProjectContext,
+};
+
+/// Lines of the file shown around the selection so the model sees where it sits.
+const SURROUNDING_LINES: usize = 8;
+const MAX_SELECTION_LINES: usize = 400;
+const MAX_RELATED_SYMBOLS: usize = 25;
+
+/// A line range of one source file, 1-based and inclusive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selection {
+    pub file: PathBuf,
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+impl Selection {
+    /// Parse `40-90` or a single line such as `40`.
+    pub fn parse(file: impl Into<PathBuf>, lines: &str) -> Result<Self> {
+        let invalid = || {
+            PlainSightError::InvalidState(format!(
+                "invalid line range '{lines}': expected START-END with 1-based line numbers"
+            ))
+        };
+        let (start, end) = match lines.split_once('-') {
+            Some((start, end)) => (start.trim(), end.trim()),
+            None => (lines.trim(), lines.trim()),
+        };
+        let start_line: usize = start.parse().map_err(|_| invalid())?;
+        let end_line: usize = end.parse().map_err(|_| invalid())?;
+        if start_line == 0 || end_line < start_line {
+            return Err(invalid());
+        }
+        Ok(Self {
+            file: file.into(),
+            start_line,
+            end_line,
+        })
+    }
+}
+
+/// Build the explain prompt context for `selection`: the selected lines with a few lines around
+/// them, the symbols declared in and just before the range, the file's generated summary, and
+/// the project memory relevant to the file.
+///
+/// Memory and summaries come from an earlier run when present; without one the context holds
+/// the source alone. `guard` decides whether instruction-like lines of the source are
+/// neutralized first.
+pub fn build_explain_context(
+    project: &ProjectContext,
+    project_root: &Path,
+    selection: &Selection,
+    guard: InjectionGuard,
+) -> Result<String> {
+    let relative = relative_to_root(project_root, &selection.file)?;
+    let relative_path = relative.to_string_lossy().replace('\\', "/");
+    let absolute = project_root.join(&relative);
+    let source = fs::read_to_string(&absolute).map_err(|e| {
+        PlainSightError::io(format!("reading selection source '{}'", absolute.display()), e)
+    })?;
+    let source = match guard {
+        InjectionGuard::Neutralize => {
+            let (source, redactions) = injection_guard::sanitize(&source, 1);
+            if let Some(first) = redactions.first() {
+                warn!(
+                    target_file = %relative_path,
+                    redacted_lines = redactions.len(),
+                    first_line = first.line,
+                    "instruction-like text in source neutralized before prompting"
+                );
+            }
+            source
+        }
+        InjectionGuard::Off => source,
+    };
+    let lines: Vec<&str> = source.lines().collect();
+    if selection.start_line > lines.len() {
+        return Err(PlainSightError::InvalidState(format!(
+            "line {} is past the end of '{relative_path}' ({} lines)",
+            selection.start_line,
+            lines.len()
+        )));
+    }
+    let end_line = selection
+        .end_line
+        .min(lines.len())
+        .min(selection.start_line + MAX_SELECTION_LINES - 1);
+
+    let numbered = |from: usize, to: usize| -> String {
+        (from..=to)
+            .filter(|line| *line >= 1 && *line <= lines.len())
+            .map(|line| format!("{line:>5} | {}", lines[line - 1]))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    let selected = numbered(selection.start_line, end_line);
+    let before = numbered(
+        selection.start_line.saturating_sub(SURROUNDING_LINES).max(1),
+        selection.start_line - 1,
+    );
+    let after = numbered(end_line + 1, end_line + SURROUNDING_LINES);
+
+    let project_memory = load_project_memory(project);
+    let file_memory = project_memory
+        .as_ref()
+        .and_then(|memory| memory.files.iter().find(|file| file.path == relative_path));
+    let symbols = file_memory
+        .map(|file| selection_symbols(&file.symbols, selection.start_line, end_line))
+        .unwrap_or_default();
+
+    let related = project_memory
+        .as_ref()
+        .map(|memory| {
+            let relevant = memory::get_relevant_memory_for_file(memory, &relative_path);
+            let selected_text = lines[selection.start_line - 1..end_line].join("\n");
+            let symbols = relevant
+                .global_symbols
+                .into_iter()
+                .filter(|symbol| contains_identifier(&selected_text, &symbol.name))
+                .take(MAX_RELATED_SYMBOLS)
+                .map(|symbol| {
+                    json!({
+                        "name": symbol.name,
+                        "kind": symbol.kind,
+                        "defined_in": symbol.defined_in,
+                    })
+                })
+                .collect::<Vec<_>>();
+            let links = relevant
+                .links
+                .into_iter()
+                .filter(|link| contains_identifier(&selected_text, &link.symbol))
+                .map(|link| {
+                    json!({
+                        "from": link.from_file,
+                        "to": link.to_file,
+                        "symbol": link.symbol,
+                        "reason": link.reason,
+                    })
+                })
+                .collect::<Vec<_>>();
+            json!({ "symbols": symbols, "links": links })
+        })
+        .unwrap_or_else(|| json!({}));
+
+    let summary = project
+        .store()
+        .read_to_string(&project.file_summary_path(&relative)?)
+        .ok()
+        .filter(|summary| !summary.trim().is_empty());
+
+    let context = json!({
+        "path": relative_path,
+        "language": LanguageDetector::new().detect(&relative, &source),
+        "lines": format!("{}-{end_line}", selection.start_line),
+        "selection": selected,
+        "before_selection": before,
+        "after_selection": after,
+        "enclosing_symbols": symbols,
+        "file_summary": summary,
+        "related_memory": related,
+    });
+    serde_json::to_string_pretty(&context)
+        .map_err(|e| PlainSightError::InvalidState(format!("serializing explain context: {e}")))
+}
+
+/// `file` relative to `project_root`; relative paths are taken from the working directory,
+/// the way an editor passes them.
+fn relative_to_root(project_root: &Path, file: &Path) -> Result<PathBuf> {
+    let canonical = |path: &Path| {
+        path.canonicalize()
+            .map_err(|e| PlainSightError::io(format!("resolving '{}'", path.display()), e))
+    };
+    let root = canonical(project_root)?;
+    let file = canonical(file)?;
+    file.strip_prefix(&root)
+        .map(Path::to_path_buf)
+        .map_err(|_| PlainSightError::PathOutsideProject {
+            path: file.clone(),
+            project_root: root.clone(),
+        })
+}
+
+fn load_project_memory(project: &ProjectContext) -> Option<ProjectMemory> {
+    project.load_memory().ok()
+}
+
+/// Symbols declared inside the range, plus the last one declared before it, which usually
+/// encloses the selection.
+fn selection_symbols(
+    symbols: &[SymbolFact],
+    start_line: usize,
+    end_line: usize,
+) -> Vec<serde_json::Value> {
+    let enclosing = symbols
+        .iter()
+        .filter(|symbol| symbol.line < start_line)
+        .max_by_key(|symbol| symbol.line);
+    enclosing
+        .into_iter()
+        .chain(
+            symbols
+                .iter()
+                .filter(|symbol| (start_line..=end_line).contains(&symbol.line)),
+        )
+        .map(|symbol| {
+            json!({
+                "name": symbol.name,
+                "kind": symbol.kind,
+                "line": symbol.line,
+                "signature": symbol.details.signature,
+            })
+        })
+        .collect()
+}
+
+fn contains_identifier(text: &str, name: &str) -> bool {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .any(|word| word == name)
+}
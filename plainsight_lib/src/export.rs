@@ -0,0 +1,602 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use pulldown_cmark::{Event, Options, Parser, html};
+use serde::Serialize;
+use tracing::{debug, info};
+
+use crate::{
+    error::{PlainSightError, Result},
+    freshness::{FreshnessBadge, FreshnessCheck, HtmlBadge, PageStatus},
+    ownership, page_split,
+    project_manager::ProjectContext,
+};
+
+/// Headless renderers tried in order for PDF output; each is given the bundle's HTML file.
+const PDF_RENDERERS: [&str; 5] = [
+    "chromium",
+    "chromium-browser",
+    "google-chrome",
+    "google-chrome-stable",
+    "wkhtmltopdf",
+];
+
+const BUNDLE_CSS: &str = "\
+body{font-family:-apple-system,'Segoe UI',Helvetica,Arial,sans-serif;line-height:1.5;color:#1f2328;max-width:60rem;margin:0 auto;padding:2rem}\
+h1,h2,h3{line-height:1.25}\
+section{border-top:1px solid #d0d7de;margin-top:2.5rem;padding-top:1rem}\
+section>header{font-size:.85rem;color:#59636e}\
+nav ol{padding-left:1.5rem}\
+pre{background:#f6f8fa;padding:.75rem;overflow-x:auto;border-radius:6px}\
+code{font-family:ui-monospace,SFMono-Regular,Menlo,monospace;font-size:.9em}\
+table{border-collapse:collapse}\
+th,td{border:1px solid #d0d7de;padding:.3rem .6rem}\
+blockquote{margin-left:0;padding-left:1rem;border-left:.25rem solid #d0d7de;color:#59636e}\
+.freshness{border-radius:1rem;padding:.05rem .5rem;margin-left:.5rem;background:#eef1f4}\
+.freshness-current{background:#dafbe1;color:#1a7f37}\
+.freshness-stale{background:#fff8c5;color:#9a6700;font-weight:600}\
+@media print{section{page-break-before:always}nav{page-break-after:always}}";
+
+/// Styles a static site adds to [`BUNDLE_CSS`]: the header with the search box and its results.
+const SITE_CSS: &str = "\
+.site-header{display:flex;gap:1rem;align-items:center;position:relative;border-bottom:1px solid #d0d7de;padding-bottom:.75rem}\
+.site-header input{flex:1;max-width:24rem;padding:.3rem .5rem;font:inherit}\
+#search-results{position:absolute;top:100%;right:0;z-index:1;list-style:none;margin:0;padding:0;background:#fff;border:1px solid #d0d7de;max-width:32rem}\
+#search-results:empty{display:none}\
+#search-results li{padding:.3rem .6rem}\
+#search-results small{color:#59636e}";
+
+/// Filters `window.PLAINSIGHT_SEARCH`, written to `search_index.js`, as the search box changes;
+/// every term must occur in a page's path, symbol names or summary.
+const SITE_SEARCH_JS: &str = r#"(function () {
+  var input = document.getElementById("search");
+  var results = document.getElementById("search-results");
+  var root = document.body.getAttribute("data-root");
+  var entries = window.PLAINSIGHT_SEARCH || [];
+  input.addEventListener("input", function () {
+    var terms = input.value.toLowerCase().split(/\s+/).filter(Boolean);
+    results.innerHTML = "";
+    if (!terms.length) {
+      return;
+    }
+    entries
+      .filter(function (entry) {
+        var text = [entry.title, entry.symbols.join(" "), entry.summary].join(" ").toLowerCase();
+        return terms.every(function (term) {
+          return text.indexOf(term) >= 0;
+        });
+      })
+      .slice(0, 20)
+      .forEach(function (entry) {
+        var item = document.createElement("li");
+        var link = document.createElement("a");
+        link.href = root + entry.url;
+        link.textContent = entry.title;
+        item.appendChild(link);
+        var symbols = entry.symbols.filter(function (symbol) {
+          return terms.some(function (term) {
+            return symbol.toLowerCase().indexOf(term) >= 0;
+          });
+        });
+        if (symbols.length) {
+          var matched = document.createElement("small");
+          matched.textContent = " " + symbols.slice(0, 5).join(", ");
+          item.appendChild(matched);
+        }
+        results.appendChild(item);
+      });
+  });
+})();
+"#;
+
+/// Characters of a file summary kept in the search index.
+const SEARCH_SUMMARY_CHARS: usize = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// One HTML file with inline CSS and anchors for every section.
+    SingleHtml,
+    /// The same document printed to PDF by a headless browser or `wkhtmltopdf`.
+    Pdf,
+    /// A static site: one HTML page per document with a client-side search over file paths,
+    /// summaries and symbol names. Needs no server; the pages can be opened from disk.
+    Html,
+    /// An mdBook source directory: `book.toml`, and under `src/` a `SUMMARY.md`, the project
+    /// summary as the introduction, and one chapter per source directory with its files'
+    /// docs as sub-chapters. `mdbook serve` on it gives a navigable site.
+    MdBook,
+}
+
+impl ExportFormat {
+    /// What the export is written to under the project docs directory without an `output`.
+    pub fn default_output_name(self, project_name: &str) -> String {
+        match self {
+            Self::SingleHtml => format!("{project_name}.html"),
+            Self::Pdf => format!("{project_name}.pdf"),
+            Self::Html => "site".to_string(),
+            Self::MdBook => "book".to_string(),
+        }
+    }
+}
+
+/// One merged document of the bundle.
+#[derive(Debug, Clone)]
+pub struct BundleSection {
+    pub anchor: String,
+    pub title: String,
+    pub markdown: String,
+    /// Checked while collecting; `None` for pages without freshness front-matter.
+    pub freshness: Option<PageStatus>,
+}
+
+/// Merge the project summary, architecture and every file's docs into a single file, or lay
+/// them out as a static site or an mdBook.
+///
+/// Files are the ones recorded in `.meta.json` by the last run, in path order; empty
+/// documents are left out. Returns the path written, `output` or
+/// [`ExportFormat::default_output_name`] under the project docs directory.
+pub fn export_bundle(
+    project: &ProjectContext,
+    project_name: &str,
+    format: ExportFormat,
+    output: Option<&Path>,
+) -> Result<PathBuf> {
+    let output = output.map(Path::to_path_buf).unwrap_or_else(|| {
+        project
+            .project_docs_path()
+            .join(format.default_output_name(project_name))
+    });
+    let sections = collect_sections(project)?;
+    if sections.is_empty() {
+        return Err(PlainSightError::InvalidState(format!(
+            "no generated docs found under '{}'; run a generation first",
+            project.project_docs_path().display()
+        )));
+    }
+
+    match format {
+        ExportFormat::SingleHtml => {
+            let document = render_html(project_name, &sections, &HtmlBadge);
+            fs::write(&output, document.as_bytes()).map_err(|e| {
+                PlainSightError::io(format!("writing docs bundle '{}'", output.display()), e)
+            })?
+        }
+        ExportFormat::Pdf => {
+            let document = render_html(project_name, &sections, &HtmlBadge);
+            let html_path = output.with_extension("bundle.html");
+            fs::write(&html_path, document.as_bytes()).map_err(|e| {
+                PlainSightError::io(format!("writing docs bundle '{}'", html_path.display()), e)
+            })?;
+            let rendered = print_to_pdf(&html_path, &output);
+            let _ = fs::remove_file(&html_path);
+            rendered?;
+        }
+        ExportFormat::Html => write_site(project, project_name, &sections, &output)?,
+        ExportFormat::MdBook => write_mdbook(project, project_name, &sections, &output)?,
+    }
+
+    info!(
+        project_name,
+        format = ?format,
+        sections = sections.len(),
+        output = %output.display(),
+        "docs bundle exported"
+    );
+    Ok(output)
+}
+
+pub fn collect_sections(project: &ProjectContext) -> Result<Vec<BundleSection>> {
+    let check = FreshnessCheck::new(project)?;
+    let mut sections = Vec::new();
+    let mut push = |anchor: String, title: String, path: &Path| {
+        if let Ok(markdown) = page_split::read_page(project.store(), path)
+            && !markdown.trim().is_empty()
+        {
+            sections.push(BundleSection {
+                anchor,
+                title,
+                freshness: check.status(&markdown),
+                markdown,
+            });
+        }
+    };
+
+    push(
+        "project-summary".to_string(),
+        "Project summary".to_string(),
+        &project.summary_path(),
+    );
+    push(
+        "architecture".to_string(),
+        "Architecture".to_string(),
+        &project.architecture_path(),
+    );
+
+    let meta = project.load_meta()?;
+    for relative in meta.files.keys() {
+        push(
+            format!("file-{}", slugify(relative)),
+            relative.clone(),
+            &project.file_docs_path(relative)?,
+        );
+    }
+    Ok(sections)
+}
+
+/// The bundle as one HTML page; `badge` renders each section's freshness next to its title.
+pub fn render_html(
+    project_name: &str,
+    sections: &[BundleSection],
+    badge: &dyn FreshnessBadge,
+) -> String {
+    let title = escape_html(project_name);
+    let mut out = String::new();
+    out.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str(&format!("<title>{title} documentation</title>\n"));
+    out.push_str(&format!("<style>{BUNDLE_CSS}</style>\n</head>\n<body id=\"top\">\n"));
+    out.push_str(&format!("<h1>{title} documentation</h1>\n"));
+
+    out.push_str("<nav>\n<h2>Contents</h2>\n<ol>\n");
+    for section in sections {
+        out.push_str(&format!(
+            "<li><a href=\"#{}\">{}</a></li>\n",
+            section.anchor,
+            escape_html(&section.title)
+        ));
+    }
+    out.push_str("</ol>\n</nav>\n");
+
+    for section in sections {
+        let freshness = section
+            .freshness
+            .as_ref()
+            .map(|status| badge.render(status))
+            .unwrap_or_default();
+        out.push_str(&format!(
+            "<section id=\"{}\">\n<header>{} · <a href=\"#top\">top</a>{freshness}</header>\n",
+            section.anchor,
+            escape_html(&section.title)
+        ));
+        push_markdown_html(&mut out, &section.markdown);
+        out.push_str("</section>\n");
+    }
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn push_markdown_html(out: &mut String, markdown: &str) {
+    // Docs pages may open with ownership front-matter, which is left out of the HTML.
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS;
+    // Pages are written by the model from the project's own source, so HTML in them is shown
+    // as text rather than run in the reader's browser.
+    let events = Parser::new_ext(markdown, options).map(|event| match event {
+        Event::Html(html) | Event::InlineHtml(html) => Event::Text(html),
+        event => event,
+    });
+    html::push_html(out, events);
+}
+
+/// One page of a static site in its search index.
+#[derive(Debug, Serialize)]
+struct SearchEntry<'a> {
+    title: &'a str,
+    /// Relative to the site root.
+    url: String,
+    summary: String,
+    symbols: Vec<&'a str>,
+}
+
+/// Write `sections` as a static site under `output`: `index.html` with the project summary and
+/// a list of every page, `architecture.html`, and `files/<path>.html` for each file's docs.
+/// `search_index.js` holds each file's path, summary and symbol names, from the file summaries
+/// and the project memory, for the search box on every page. Pages of files no longer
+/// documented are left in place but no longer linked.
+fn write_site(
+    project: &ProjectContext,
+    project_name: &str,
+    sections: &[BundleSection],
+    output: &Path,
+) -> Result<()> {
+    let memory = project.load_memory().ok();
+    let mut contents = String::from("<h2>Contents</h2>\n<ul>\n");
+    let mut intro = String::new();
+    let mut search = Vec::new();
+    for section in sections {
+        let (page, title) = match section.anchor.as_str() {
+            "project-summary" => {
+                push_markdown_html(&mut intro, &section.markdown);
+                continue;
+            }
+            "architecture" => ("architecture.html".to_string(), "Architecture"),
+            _ => (
+                format!("files/{}.html", section.title),
+                section.title.as_str(),
+            ),
+        };
+        contents.push_str(&format!(
+            "<li><a href=\"{}\">{}</a></li>\n",
+            escape_html(&page),
+            escape_html(title)
+        ));
+        let root = "../".repeat(page.matches('/').count());
+        let mut body = section
+            .freshness
+            .as_ref()
+            .map(|status| format!("<p>{}</p>\n", HtmlBadge.render(status)))
+            .unwrap_or_default();
+        push_markdown_html(&mut body, &section.markdown);
+        write_export_file(
+            &output.join(&page),
+            &render_site_page(project_name, title, &root, &body),
+        )?;
+
+        if section.anchor == "architecture" {
+            continue;
+        }
+        let summary = project
+            .file_summary_path(title)
+            .ok()
+            .and_then(|path| project.store().read_to_string(&path).ok())
+            .map(|summary| search_summary(&summary))
+            .unwrap_or_default();
+        let symbols = memory
+            .iter()
+            .flat_map(|memory| &memory.files)
+            .filter(|file| file.path == title)
+            .flat_map(|file| &file.symbols)
+            .filter(|symbol| !symbol.is_test)
+            .map(|symbol| symbol.name.as_str())
+            .collect::<BTreeSet<_>>();
+        search.push(SearchEntry {
+            title,
+            url: page,
+            summary,
+            symbols: symbols.into_iter().collect(),
+        });
+    }
+    contents.push_str("</ul>\n");
+    intro.push_str(&contents);
+
+    write_export_file(
+        &output.join("index.html"),
+        &render_site_page(project_name, project_name, "", &intro),
+    )?;
+    write_export_file(
+        &output.join("style.css"),
+        &format!("{BUNDLE_CSS}{SITE_CSS}"),
+    )?;
+    write_export_file(&output.join("search.js"), SITE_SEARCH_JS)?;
+    let index = serde_json::to_string(&search)
+        .map_err(|e| PlainSightError::InvalidState(format!("serializing search index: {e}")))?;
+    write_export_file(
+        &output.join("search_index.js"),
+        &format!("window.PLAINSIGHT_SEARCH = {index};\n"),
+    )
+}
+
+/// One site page around `body`, with links relative to the site root `root`.
+fn render_site_page(project_name: &str, title: &str, root: &str, body: &str) -> String {
+    let project_name = escape_html(project_name);
+    let title = escape_html(title);
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{title} · {project_name} documentation</title>\n\
+         <link rel=\"stylesheet\" href=\"{root}style.css\">\n</head>\n\
+         <body data-root=\"{root}\">\n<header class=\"site-header\">\n\
+         <a href=\"{root}index.html\">{project_name} documentation</a>\n\
+         <input id=\"search\" type=\"search\" placeholder=\"Search files and symbols\" autocomplete=\"off\">\n\
+         <ol id=\"search-results\"></ol>\n</header>\n\
+         <main>\n<h1>{title}</h1>\n{body}</main>\n\
+         <script src=\"{root}search_index.js\"></script>\n\
+         <script src=\"{root}search.js\"></script>\n</body>\n</html>\n"
+    )
+}
+
+/// A file summary on one line for the search index, without front-matter, the disclaimer and
+/// headings, cut at [`SEARCH_SUMMARY_CHARS`].
+fn search_summary(summary: &str) -> String {
+    ownership::strip_front_matter(summary)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('>') && !line.starts_with('#'))
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(SEARCH_SUMMARY_CHARS)
+        .collect()
+}
+
+/// Lay `sections` out as an mdBook under `output`. The project summary is the introduction;
+/// each source directory is a chapter, opening with its directory summary when there is one,
+/// and each file's docs are a sub-chapter of its directory. Pages of files no longer
+/// documented are left in place but no longer linked.
+fn write_mdbook(
+    project: &ProjectContext,
+    project_name: &str,
+    sections: &[BundleSection],
+    output: &Path,
+) -> Result<()> {
+    let src = output.join("src");
+    let title = toml::Value::String(format!("{project_name} documentation"));
+    write_export_file(
+        &output.join("book.toml"),
+        &format!("[book]\ntitle = {title}\nsrc = \"src\"\n"),
+    )?;
+
+    let mut intro = format!("# {project_name}\n");
+    let mut summary = String::from("# Summary\n\n[Introduction](README.md)\n\n");
+    let mut dirs: BTreeMap<&str, (BTreeSet<&str>, Vec<&str>)> = BTreeMap::new();
+    for section in sections {
+        let body = ownership::strip_front_matter(&section.markdown);
+        match section.anchor.as_str() {
+            "project-summary" => intro = format!("# {project_name}\n\n{body}"),
+            "architecture" => {
+                write_export_file(
+                    &src.join("architecture.md"),
+                    &format!("# Architecture\n\n{body}"),
+                )?;
+                summary.push_str("- [Architecture](architecture.md)\n");
+            }
+            _ => {
+                let path = section.title.as_str();
+                write_export_file(
+                    &src.join("files").join(format!("{path}.md")),
+                    &format!("# {path}\n\n{body}"),
+                )?;
+                let (mut parent, _) = path.rsplit_once('/').unwrap_or(("", path));
+                dirs.entry(parent).or_default().1.push(path);
+                while !parent.is_empty() {
+                    let grandparent = parent.rsplit_once('/').map_or("", |(dir, _)| dir);
+                    dirs.entry(grandparent).or_default().0.insert(parent);
+                    parent = grandparent;
+                }
+            }
+        }
+    }
+    write_export_file(&src.join("README.md"), &intro)?;
+
+    for (dir, (subdirs, files)) in dirs.iter().filter(|(dir, _)| !dir.is_empty()) {
+        let mut chapter = format!("# {dir}/\n\n");
+        if let Ok(dir_summary) = project
+            .store()
+            .read_to_string(&project.directory_summary_path(dir))
+        {
+            chapter.push_str(ownership::strip_front_matter(&dir_summary).trim_end());
+            chapter.push_str("\n\n");
+        }
+        for subdir in subdirs {
+            let name = last_component(subdir);
+            let link = book_link(&format!("{name}/README.md"));
+            chapter.push_str(&format!("- [{name}/]({link})\n"));
+        }
+        for file in files {
+            let name = last_component(file);
+            let link = book_link(&format!("{name}.md"));
+            chapter.push_str(&format!("- [{name}]({link})\n"));
+        }
+        write_export_file(&src.join("files").join(dir).join("README.md"), &chapter)?;
+    }
+    if !dirs.is_empty() {
+        summary.push_str("\n# Files\n\n");
+        push_book_chapters(&mut summary, "", 0, &dirs);
+    }
+    write_export_file(&src.join("SUMMARY.md"), &summary)
+}
+
+/// Add the chapters of `dir`'s subdirectories, each followed by its own, then its files to
+/// `SUMMARY.md`, indented by `depth`.
+fn push_book_chapters(
+    summary: &mut String,
+    dir: &str,
+    depth: usize,
+    dirs: &BTreeMap<&str, (BTreeSet<&str>, Vec<&str>)>,
+) {
+    let Some((subdirs, files)) = dirs.get(dir) else {
+        return;
+    };
+    let indent = "  ".repeat(depth);
+    for subdir in subdirs {
+        let name = last_component(subdir);
+        let link = book_link(&format!("files/{subdir}/README.md"));
+        summary.push_str(&format!("{indent}- [{name}/]({link})\n"));
+        push_book_chapters(summary, subdir, depth + 1, dirs);
+    }
+    for file in files {
+        let name = last_component(file);
+        let link = book_link(&format!("files/{file}.md"));
+        summary.push_str(&format!("{indent}- [{name}]({link})\n"));
+    }
+}
+
+fn write_export_file(path: &Path, content: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| {
+            PlainSightError::io(
+                format!("creating export directory '{}'", parent.display()),
+                e,
+            )
+        })?;
+    }
+    fs::write(path, content.as_bytes())
+        .map_err(|e| PlainSightError::io(format!("writing export file '{}'", path.display()), e))
+}
+
+fn last_component(path: &str) -> &str {
+    path.rsplit_once('/').map_or(path, |(_, name)| name)
+}
+
+/// `path` as a Markdown link destination, in angle brackets when it has spaces.
+fn book_link(path: &str) -> String {
+    if path.contains(' ') {
+        format!("<{path}>")
+    } else {
+        path.to_string()
+    }
+}
+
+fn print_to_pdf(html_path: &Path, output: &Path) -> Result<()> {
+    let html_path = html_path.canonicalize().map_err(|e| {
+        PlainSightError::io(format!("resolving '{}'", html_path.display()), e)
+    })?;
+    for renderer in PDF_RENDERERS {
+        let mut command = Command::new(renderer);
+        if renderer == "wkhtmltopdf" {
+            command.args(["--quiet", "--enable-local-file-access"]);
+            command.arg(&html_path).arg(output);
+        } else {
+            command.args(["--headless", "--disable-gpu", "--no-pdf-header-footer"]);
+            command.arg(format!("--print-to-pdf={}", output.display()));
+            command.arg(format!("file://{}", html_path.display()));
+        }
+
+        let result = match command.output() {
+            Ok(result) => result,
+            Err(err) => {
+                debug!(renderer, error = %err, "pdf_renderer_unavailable");
+                continue;
+            }
+        };
+        if !result.status.success() {
+            let stderr = String::from_utf8_lossy(&result.stderr);
+            return Err(PlainSightError::InvalidState(format!(
+                "`{renderer}` failed to print the docs bundle: {}",
+                stderr.lines().last().unwrap_or("no output").trim()
+            )));
+        }
+        debug!(renderer, output = %output.display(), "pdf_rendered");
+        return Ok(());
+    }
+
+    Err(PlainSightError::InvalidState(format!(
+        "PDF export needs one of {} on PATH",
+        PDF_RENDERERS.join(", ")
+    )))
+}
+
+fn slugify(value: &str) -> String {
+    let mut slug = String::with_capacity(value.len());
+    for ch in value.chars() {
+        if ch.is_ascii_alphanumeric() {
+            slug.push(ch.to_ascii_lowercase());
+        } else if !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_matches('-').to_string()
+}
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
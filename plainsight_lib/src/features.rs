@@ -0,0 +1,442 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+const ITEM_KEYWORDS: &[&str] = &[
+    "fn",
+    "struct",
+    "enum",
+    "mod",
+    "impl",
+    "trait",
+    "const",
+    "static",
+    "type",
+    "use",
+    "union",
+    "macro_rules!",
+];
+
+/// Cargo features declared by one `Cargo.toml`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrateFeatures {
+    pub manifest: String,
+    pub crate_name: String,
+    pub features: Vec<FeatureDefinition>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeatureDefinition {
+    pub name: String,
+    /// Entries of the feature's array: other features, `dep:` crates, `crate/feature` pairs.
+    pub enables: Vec<String>,
+    pub default: bool,
+    /// Declared implicitly by an optional dependency without a `dep:` reference.
+    pub implicit: bool,
+}
+
+/// A `cfg` predicate that mentions at least one feature, and what it applies to.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FeatureGate {
+    pub features: Vec<String>,
+    /// The predicate as written, e.g. `all(feature = "a", not(feature = "b"))`.
+    pub condition: String,
+    pub line: usize,
+    /// `fn`, `struct`, `mod`, … for gated items, `file` for inner attributes and `expression`
+    /// for `cfg!` checks.
+    pub kind: String,
+    pub item: String,
+}
+
+/// Parse the `[package]` name, `[features]` table and optional dependencies of a manifest.
+///
+/// Returns `None` for manifests without a package (virtual workspace roots).
+pub fn parse_manifest(relative_path: &str, content: &str) -> Option<CrateFeatures> {
+    let mut crate_name = None;
+    let mut section = String::new();
+    let mut declared: Vec<(String, Vec<String>)> = Vec::new();
+    let mut optional_deps = Vec::new();
+    let mut pending: Option<(String, String)> = None;
+
+    for raw_line in content.lines() {
+        let line = strip_toml_comment(raw_line).trim();
+        if let Some((name, buffer)) = pending.as_mut() {
+            buffer.push_str(line);
+            if buffer.contains(']') {
+                declared.push((name.clone(), string_items(buffer)));
+                pending = None;
+            }
+            continue;
+        }
+        if line.is_empty() {
+            continue;
+        }
+        if line.starts_with('[') {
+            section = line.trim_matches(['[', ']']).trim().to_string();
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim().trim_matches('"');
+        let value = value.trim();
+
+        match section.as_str() {
+            "package" if key == "name" => crate_name = Some(value.trim_matches('"').to_string()),
+            "features" if value.starts_with('[') => {
+                if value.contains(']') {
+                    declared.push((key.to_string(), string_items(value)));
+                } else {
+                    pending = Some((key.to_string(), value.to_string()));
+                }
+            }
+            "dependencies" | "build-dependencies" if is_optional(value) => {
+                optional_deps.push(key.to_string());
+            }
+            _ if is_dependency_table(&section) && key == "optional" && value == "true" => {
+                if let Some(dep) = section.rsplit('.').next() {
+                    optional_deps.push(dep.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let crate_name = crate_name?;
+    let defaults = declared
+        .iter()
+        .find(|(name, _)| name == "default")
+        .map(|(_, enables)| enables.clone())
+        .unwrap_or_default();
+    let explicit_deps = declared
+        .iter()
+        .flat_map(|(_, enables)| enables.iter())
+        .filter_map(|entry| entry.strip_prefix("dep:"))
+        .map(str::to_string)
+        .collect::<BTreeSet<_>>();
+
+    let mut features = declared
+        .iter()
+        .filter(|(name, _)| name != "default")
+        .map(|(name, enables)| FeatureDefinition {
+            name: name.clone(),
+            enables: enables.clone(),
+            default: defaults.contains(name),
+            implicit: false,
+        })
+        .collect::<Vec<_>>();
+    // Optional dependencies never named with `dep:` still act as features of the same name.
+    for dep in optional_deps {
+        if !explicit_deps.contains(&dep) && !features.iter().any(|f| f.name == dep) {
+            features.push(FeatureDefinition {
+                default: defaults.contains(&dep),
+                name: dep,
+                enables: Vec::new(),
+                implicit: true,
+            });
+        }
+    }
+
+    Some(CrateFeatures {
+        manifest: relative_path.to_string(),
+        crate_name,
+        features,
+    })
+}
+
+/// Find `#[cfg(...)]`, `#![cfg(...)]`, `#[cfg_attr(...)]` and `cfg!(...)` uses that depend on a
+/// feature in Rust source.
+pub fn detect_feature_gates(source: &str) -> Vec<FeatureGate> {
+    let mut gates = Vec::new();
+    let mut pending: Vec<(String, usize)> = Vec::new();
+
+    for (idx, raw_line) in source.lines().enumerate() {
+        let line_no = idx + 1;
+        let line = raw_line
+            .split_once("//")
+            .map(|(left, _)| left)
+            .unwrap_or(raw_line)
+            .trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(inner) = line.strip_prefix("#![cfg(") {
+            push_gate(&mut gates, balanced_prefix(inner), line_no, "file", "");
+            continue;
+        }
+        if line.starts_with("#[") {
+            if let Some(condition) = cfg_condition(line) {
+                pending.push((condition.to_string(), line_no));
+            }
+            continue;
+        }
+
+        if !pending.is_empty() {
+            let (kind, item) = item_name(line);
+            for (condition, gate_line) in pending.drain(..) {
+                push_gate(&mut gates, &condition, gate_line, kind, &item);
+            }
+        }
+
+        let mut search = line;
+        while let Some(pos) = search.find("cfg!(") {
+            let rest = &search[pos + "cfg!(".len()..];
+            let condition = balanced_prefix(rest);
+            push_gate(&mut gates, condition, line_no, "expression", "");
+            search = &rest[condition.len()..];
+        }
+    }
+
+    gates
+}
+
+/// Render `features.md`: per-crate feature matrix, gated items per feature, and features that
+/// are used but never declared (or declared but never used).
+pub fn render_matrix(
+    project_name: &str,
+    crates: &[CrateFeatures],
+    gates: &[(String, FeatureGate)],
+) -> String {
+    let mut out = format!("# {project_name} Feature Matrix\n\n");
+    if crates.iter().all(|krate| krate.features.is_empty()) && gates.is_empty() {
+        out.push_str("No cargo features are declared or used.\n");
+        return out;
+    }
+
+    let mut by_feature: BTreeMap<&str, Vec<(&str, &FeatureGate)>> = BTreeMap::new();
+    for (path, gate) in gates {
+        for feature in &gate.features {
+            by_feature
+                .entry(feature.as_str())
+                .or_default()
+                .push((path.as_str(), gate));
+        }
+    }
+
+    let mut declared = BTreeSet::new();
+    for krate in crates.iter().filter(|krate| !krate.features.is_empty()) {
+        let crate_dir = krate
+            .manifest
+            .rsplit_once('/')
+            .map(|(dir, _)| format!("{dir}/"))
+            .unwrap_or_default();
+        out.push_str(&format!(
+            "## `{}` (`{}`)\n\n",
+            krate.crate_name, krate.manifest
+        ));
+        out.push_str("| Feature | Default | Enables | Gated items |\n");
+        out.push_str("|---|---|---|---|\n");
+        for feature in &krate.features {
+            declared.insert(feature.name.as_str());
+            let enables = if feature.implicit {
+                format!("optional dependency `{}`", feature.name)
+            } else if feature.enables.is_empty() {
+                "-".to_string()
+            } else {
+                feature
+                    .enables
+                    .iter()
+                    .map(|entry| format!("`{entry}`"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            let gated = by_feature
+                .get(feature.name.as_str())
+                .map(|uses| {
+                    uses.iter()
+                        .filter(|(path, _)| path.starts_with(&crate_dir))
+                        .count()
+                })
+                .unwrap_or(0);
+            out.push_str(&format!(
+                "| `{}` | {} | {enables} | {gated} |\n",
+                feature.name,
+                if feature.default { "yes" } else { "no" }
+            ));
+        }
+        out.push('\n');
+    }
+
+    if !by_feature.is_empty() {
+        out.push_str("## Gated Code\n");
+        for (feature, uses) in &by_feature {
+            out.push_str(&format!("\n### `{feature}`\n\n"));
+            for (path, gate) in uses {
+                let target = match gate.kind.as_str() {
+                    "file" => "whole file".to_string(),
+                    "expression" => "`cfg!` check".to_string(),
+                    kind if gate.item.is_empty() => kind.to_string(),
+                    kind => format!("{kind} `{}`", gate.item),
+                };
+                out.push_str(&format!(
+                    "- `{path}:{}` {target} — `{}`\n",
+                    gate.line, gate.condition
+                ));
+            }
+        }
+    }
+
+    let undeclared = by_feature
+        .keys()
+        .filter(|feature| !declared.contains(*feature))
+        .collect::<Vec<_>>();
+    let unused = declared
+        .iter()
+        .filter(|feature| !by_feature.contains_key(*feature))
+        .collect::<Vec<_>>();
+    if !undeclared.is_empty() || !unused.is_empty() {
+        out.push_str("\n## Consistency\n\n");
+        for feature in undeclared {
+            out.push_str(&format!(
+                "- `{feature}` is checked in code but not declared in any `Cargo.toml`.\n"
+            ));
+        }
+        for feature in unused {
+            out.push_str(&format!(
+                "- `{feature}` is declared but gates no code directly (it may only enable dependencies).\n"
+            ));
+        }
+    }
+
+    out
+}
+
+fn push_gate(gates: &mut Vec<FeatureGate>, condition: &str, line: usize, kind: &str, item: &str) {
+    let features = feature_names(condition);
+    if features.is_empty() {
+        return;
+    }
+    gates.push(FeatureGate {
+        features,
+        condition: condition.trim().to_string(),
+        line,
+        kind: kind.to_string(),
+        item: item.to_string(),
+    });
+}
+
+/// The predicate of `#[cfg(...)]`, or the condition part of `#[cfg_attr(cond, attr)]`.
+fn cfg_condition(attribute: &str) -> Option<&str> {
+    if let Some(rest) = attribute.strip_prefix("#[cfg(") {
+        return Some(balanced_prefix(rest));
+    }
+    let rest = attribute.strip_prefix("#[cfg_attr(")?;
+    let body = balanced_prefix(rest);
+    let mut depth = 0i64;
+    for (idx, ch) in body.char_indices() {
+        match ch {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => return Some(&body[..idx]),
+            _ => {}
+        }
+    }
+    Some(body)
+}
+
+/// Text up to the `)` closing an already opened parenthesis.
+fn balanced_prefix(text: &str) -> &str {
+    let mut depth = 1i64;
+    for (idx, ch) in text.char_indices() {
+        match ch {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return &text[..idx];
+                }
+            }
+            _ => {}
+        }
+    }
+    text
+}
+
+fn feature_names(condition: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut search = condition;
+    while let Some(pos) = search.find("feature") {
+        let rest = search[pos + "feature".len()..].trim_start();
+        search = &search[pos + "feature".len()..];
+        let Some(rest) = rest.strip_prefix('=') else {
+            continue;
+        };
+        let rest = rest.trim_start();
+        if let Some(rest) = rest.strip_prefix('"')
+            && let Some(end) = rest.find('"')
+            && !names.iter().any(|name| name == &rest[..end])
+        {
+            names.push(rest[..end].to_string());
+        }
+    }
+    names
+}
+
+fn item_name(line: &str) -> (&'static str, String) {
+    let mut words = line.split_whitespace().skip_while(|word| {
+        word.starts_with("pub")
+            || matches!(*word, "async" | "unsafe" | "extern" | "default" | "\"C\"")
+    });
+    let Some(keyword) = words.next() else {
+        return ("item", String::new());
+    };
+    // `impl<T>` and `fn name<T>(` keep generics attached to the keyword or name.
+    let keyword = keyword.split('<').next().unwrap_or_default();
+    let Some(kind) = ITEM_KEYWORDS.iter().find(|kw| **kw == keyword) else {
+        // Struct fields and enum variants: `name: Type,` / `Variant(..),`.
+        if let Some((name, rest)) = keyword.split_once(':')
+            && !rest.starts_with(':')
+            && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+        {
+            return ("field", name.to_string());
+        }
+        return ("statement", String::new());
+    };
+    let name = words
+        .next()
+        .unwrap_or_default()
+        .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':'))
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    let kind = if *kind == "macro_rules!" {
+        "macro"
+    } else {
+        kind
+    };
+    (kind, name)
+}
+
+fn string_items(array: &str) -> Vec<String> {
+    array
+        .split('"')
+        .skip(1)
+        .step_by(2)
+        .map(str::to_string)
+        .collect()
+}
+
+fn is_optional(value: &str) -> bool {
+    value.starts_with('{')
+        && value
+            .split([',', '{', '}'])
+            .filter_map(|pair| pair.split_once('='))
+            .any(|(key, value)| key.trim() == "optional" && value.trim() == "true")
+}
+
+fn is_dependency_table(section: &str) -> bool {
+    section.starts_with("dependencies.") || section.starts_with("build-dependencies.")
+}
+
+fn strip_toml_comment(line: &str) -> &str {
+    let mut in_string = false;
+    for (idx, ch) in line.char_indices() {
+        match ch {
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..idx],
+            _ => {}
+        }
+    }
+    line
+}
@@ -4,7 +4,13 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use crate::error::{PlainSightError, Result};
+use serde::Serialize;
+use tracing::{debug, warn};
+
+use crate::{
+    error::{PlainSightError, Result},
+    project_manager::OUTPUT_MARKER_FILE,
+};
 
 #[derive(Debug)]
 pub struct FileInfo {
@@ -14,6 +20,33 @@ pub struct FileInfo {
 pub struct FilterOptions {
     pub extensions: Vec<String>,
     pub exclude_directories: Vec<String>,
+    /// Absolute directories skipped with everything below them, matched by path rather than
+    /// by name.
+    pub exclude_paths: Vec<PathBuf>,
+    pub limits: WalkLimits,
+}
+
+/// Bounds on a walk, so that a tree such as an included `node_modules`, a symlink loop or a
+/// FUSE mount with endless listings cannot stall discovery. Each limit logs a warning when hit;
+/// `None` disables it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct WalkLimits {
+    /// Directories nested deeper than this below the walk root are not read.
+    pub max_depth: Option<usize>,
+    /// Entries read from one directory listing; the rest of that directory is skipped.
+    pub max_files_per_directory: Option<usize>,
+    /// Matching files returned; the walk stops once it has found this many.
+    pub max_files: Option<usize>,
+}
+
+impl Default for WalkLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: Some(32),
+            max_files_per_directory: Some(10_000),
+            max_files: Some(100_000),
+        }
+    }
 }
 
 pub struct FileWalker {
@@ -41,20 +74,58 @@ impl FileWalker {
         false
     }
 
+    /// Configured output directories, and any directory PlainSight has marked as its own
+    /// output, are never walked.
+    fn is_output_directory(&self, path: &Path) -> bool {
+        self.filter_options
+            .exclude_paths
+            .iter()
+            .any(|excluded| path.starts_with(excluded))
+            || path.join(OUTPUT_MARKER_FILE).is_file()
+    }
+
     pub fn walk(&self, path: PathBuf) -> Result<Vec<FileInfo>> {
-        let mut directory_stack: VecDeque<PathBuf> = VecDeque::from([path]);
+        // Canonical, so that `exclude_paths` compare against real locations.
+        let path = path
+            .canonicalize()
+            .map_err(|e| PlainSightError::io(format!("canonicalizing '{}'", path.display()), e))?;
+        let limits = self.filter_options.limits;
+        let mut directory_stack: VecDeque<(PathBuf, usize)> = VecDeque::from([(path, 0)]);
         let mut files: Vec<FileInfo> = Vec::new();
 
-        while let Some(current_path) = directory_stack.pop_front() {
+        while let Some((current_path, depth)) = directory_stack.pop_front() {
             if self.is_directory_excluded(&current_path) {
                 continue;
             }
+            if self.is_output_directory(&current_path) {
+                debug!(directory = %current_path.display(), "skipping_generated_output");
+                continue;
+            }
+            if limits.max_depth.is_some_and(|max_depth| depth > max_depth) {
+                warn!(
+                    directory = %current_path.display(),
+                    max_depth = limits.max_depth,
+                    "directory nested deeper than the walk depth limit; skipping it"
+                );
+                continue;
+            }
 
             let entries = fs::read_dir(&current_path).map_err(|e| {
                 PlainSightError::io(format!("reading directory '{}'", current_path.display()), e)
             })?;
 
-            for entry in entries {
+            for (read, entry) in entries.enumerate() {
+                if limits
+                    .max_files_per_directory
+                    .is_some_and(|max_entries| read >= max_entries)
+                {
+                    warn!(
+                        directory = %current_path.display(),
+                        max_files_per_directory = limits.max_files_per_directory,
+                        "directory has more entries than the per-directory limit; skipping the rest"
+                    );
+                    break;
+                }
                 let entry = entry.map_err(|e| {
                     PlainSightError::io(
                         format!("reading entry in directory '{}'", current_path.display()),
@@ -65,7 +136,7 @@ impl FileWalker {
                 let path = entry.path();
 
                 if path.is_dir() {
-                    directory_stack.push_back(path);
+                    directory_stack.push_back((path, depth + 1));
                 } else if !self.filter_options.extensions.is_empty()
                     && self.filter_options.extensions.iter().any(|ext| {
                         ext == path
@@ -81,6 +152,14 @@ impl FileWalker {
                         })?,
                     };
                     files.push(file_info);
+                    if limits.max_files.is_some_and(|max_files| files.len() >= max_files) {
+                        warn!(
+                            directory = %current_path.display(),
+                            max_files = limits.max_files,
+                            "walk reached the file limit; remaining files are not discovered"
+                        );
+                        return Ok(files);
+                    }
                 }
             }
         }
@@ -0,0 +1,196 @@
+//! Front-matter of generated file docs, and how fresh a page is when it is rendered.
+//!
+//! Every `docs.md` opens with the source file it documents, the hash that file had and when
+//! the page was generated. Whether the page is still current is left to render time: the
+//! source is hashed again under the recorded source root, so an export or a published site
+//! flags pages whose file changed since, however long ago the last run was.
+
+use std::{
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{error::Result, ownership::FileOwners, project_manager::ProjectContext};
+
+/// What a page's front-matter records about its source when it is written.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageFreshness {
+    /// `FileMeta::hash` of the source the page was generated from.
+    pub source_hash: String,
+    /// Unix seconds.
+    pub generated_at: u64,
+}
+
+impl PageFreshness {
+    /// Generated now from a source with `source_hash`.
+    pub fn now(source_hash: impl Into<String>) -> Self {
+        Self {
+            source_hash: source_hash.into(),
+            generated_at: unix_now(),
+        }
+    }
+}
+
+/// The front-matter of a file docs page.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageFrontMatter {
+    /// Source path relative to the project root.
+    pub source: String,
+    /// Missing on pages written before freshness was recorded.
+    #[serde(flatten, default, skip_serializing_if = "Option::is_none")]
+    pub freshness: Option<PageFreshness>,
+    /// `generated_at` as `YYYY-MM-DD`, for readers of the raw Markdown.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generated_on: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub owners: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owners_from: Option<String>,
+}
+
+/// YAML front-matter for the docs page of `relative_path`.
+pub fn render_front_matter(
+    relative_path: &str,
+    freshness: &PageFreshness,
+    owners: Option<&FileOwners>,
+) -> String {
+    let front_matter = PageFrontMatter {
+        source: relative_path.to_string(),
+        freshness: Some(freshness.clone()),
+        generated_on: Some(format_date(freshness.generated_at)),
+        owners: owners
+            .map(|owners| owners.owners.clone())
+            .unwrap_or_default(),
+        owners_from: owners.map(|owners| owners.source.clone()),
+    };
+    let yaml = serde_yaml::to_string(&front_matter).unwrap_or_default();
+    format!("---\n{yaml}---\n\n")
+}
+
+/// The front-matter `markdown` opens with, if any.
+pub fn parse_front_matter(markdown: &str) -> Option<PageFrontMatter> {
+    let rest = markdown.strip_prefix("---\n")?;
+    let end = rest.find("\n---\n")?;
+    serde_yaml::from_str(&rest[..end]).ok()
+}
+
+/// Whether a page still matches its source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FreshnessStatus {
+    /// The source has the hash the page was generated from.
+    Current,
+    /// The source changed since the page was generated.
+    Stale,
+    /// The source root is unknown or the file cannot be read, or the page records no hash.
+    Unknown,
+}
+
+/// A page's freshness as of the moment it is rendered.
+#[derive(Debug, Clone, Serialize)]
+pub struct PageStatus {
+    pub source: String,
+    pub status: FreshnessStatus,
+    /// Unix seconds; `None` for pages without freshness front-matter.
+    pub generated_at: Option<u64>,
+    pub days_since_generated: Option<u64>,
+}
+
+/// Computes `PageStatus` for the pages of one project, hashing sources under its recorded
+/// source root.
+pub struct FreshnessCheck<'a> {
+    project: &'a ProjectContext,
+    source_root: Option<PathBuf>,
+    now: u64,
+}
+
+impl<'a> FreshnessCheck<'a> {
+    pub fn new(project: &'a ProjectContext) -> Result<Self> {
+        Ok(Self {
+            project,
+            source_root: project.load_source_root()?.map(|record| record.source_root),
+            now: unix_now(),
+        })
+    }
+
+    /// The status of the docs page `markdown`; `None` when it has no front-matter.
+    pub fn status(&self, markdown: &str) -> Option<PageStatus> {
+        let front_matter = parse_front_matter(markdown)?;
+        let freshness = front_matter.freshness.as_ref();
+        let current_hash = self
+            .source_root
+            .as_ref()
+            .and_then(|root| self.project.hash_file(root.join(&front_matter.source)).ok());
+        let status = match (freshness, current_hash) {
+            (Some(freshness), Some(hash)) if freshness.source_hash == hash => {
+                FreshnessStatus::Current
+            }
+            (Some(_), Some(_)) => FreshnessStatus::Stale,
+            _ => FreshnessStatus::Unknown,
+        };
+        let generated_at = freshness.map(|freshness| freshness.generated_at);
+        Some(PageStatus {
+            source: front_matter.source,
+            status,
+            generated_at,
+            days_since_generated: generated_at.map(|at| self.now.saturating_sub(at) / 86_400),
+        })
+    }
+}
+
+/// Turns a page's status into markup shown with the page; implement it to style the
+/// indicator of a published site.
+pub trait FreshnessBadge {
+    fn render(&self, status: &PageStatus) -> String;
+}
+
+/// An HTML `<span>` with class `freshness freshness-<status>`, e.g. "Stale · generated 12
+/// days ago".
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HtmlBadge;
+
+impl FreshnessBadge for HtmlBadge {
+    fn render(&self, status: &PageStatus) -> String {
+        let (class, label) = match status.status {
+            FreshnessStatus::Current => ("current", "Current"),
+            FreshnessStatus::Stale => ("stale", "Stale: source changed"),
+            FreshnessStatus::Unknown => ("unknown", "Freshness unknown"),
+        };
+        let age = match status.days_since_generated {
+            Some(0) => " · generated today".to_string(),
+            Some(1) => " · generated 1 day ago".to_string(),
+            Some(days) => format!(" · generated {days} days ago"),
+            None => String::new(),
+        };
+        let title = status
+            .generated_at
+            .map(|at| format!(" title=\"generated {}\"", format_date(at)))
+            .unwrap_or_default();
+        format!("<span class=\"freshness freshness-{class}\"{title}>{label}{age}</span>")
+    }
+}
+
+pub fn format_date(timestamp: u64) -> String {
+    // Civil-from-days conversion (proleptic Gregorian calendar, UTC).
+    let days = (timestamp / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
@@ -0,0 +1,37 @@
+//! Files changed since a git revision, for runs limited with `PlainSightConfig::changed_since`.
+
+use std::{collections::BTreeSet, path::Path, process::Command};
+
+use crate::error::{PlainSightError, Result};
+
+/// Paths of the files `git diff --name-only <rev>` lists under `project_root`: changed between
+/// `rev` and the working tree, staged or not. Paths are relative to `project_root`, also when it
+/// is a subdirectory of the repository. `rev` may be a range such as `origin/main...HEAD`.
+pub fn changed_files(project_root: &Path, rev: &str) -> Result<BTreeSet<String>> {
+    if rev.is_empty() || rev.starts_with('-') {
+        return Err(PlainSightError::InvalidState(format!(
+            "'{rev}' is not a git revision"
+        )));
+    }
+    let output = Command::new("git")
+        .args(["diff", "--name-only", "-z", "--relative", rev, "--"])
+        .current_dir(project_root)
+        .output()
+        .map_err(|e| PlainSightError::io("running `git diff`", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = stderr
+            .lines()
+            .find(|line| !line.trim().is_empty())
+            .unwrap_or("no output");
+        return Err(PlainSightError::InvalidState(format!(
+            "`git diff {rev}` failed: {}",
+            reason.trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .split('\0')
+        .filter(|path| !path.is_empty())
+        .map(str::to_string)
+        .collect())
+}
@@ -0,0 +1,235 @@
+use std::path::Path;
+
+/// Lines at the start and end of a file searched for Emacs/Vim modelines.
+const MODELINE_LINES: usize = 5;
+/// Minimum classifier score before content is trusted over `text`.
+const MIN_CLASSIFIER_SCORE: usize = 3;
+
+/// Interpreter names from shebang lines, mapped to languages.
+const INTERPRETERS: &[(&str, &str)] = &[
+    ("python", "python"),
+    ("python2", "python"),
+    ("python3", "python"),
+    ("node", "javascript"),
+    ("nodejs", "javascript"),
+    ("bun", "javascript"),
+    ("deno", "typescript"),
+    ("ts-node", "typescript"),
+    ("tsx", "typescript"),
+    ("sh", "shell"),
+    ("bash", "shell"),
+    ("zsh", "shell"),
+    ("ksh", "shell"),
+    ("dash", "shell"),
+    ("ruby", "ruby"),
+    ("perl", "perl"),
+    ("php", "php"),
+    ("kotlin", "kotlin"),
+    ("run-cargo-script", "rust"),
+    ("rust-script", "rust"),
+];
+
+/// Mode/filetype names used in modelines, mapped to languages.
+const MODE_NAMES: &[(&str, &str)] = &[
+    ("rust", "rust"),
+    ("python", "python"),
+    ("js", "javascript"),
+    ("javascript", "javascript"),
+    ("typescript", "typescript"),
+    ("go", "go"),
+    ("java", "java"),
+    ("kotlin", "kotlin"),
+    ("cs", "csharp"),
+    ("csharp", "csharp"),
+    ("c", "c"),
+    ("cpp", "cpp"),
+    ("c++", "cpp"),
+    ("sh", "shell"),
+    ("bash", "shell"),
+    ("shell-script", "shell"),
+    ("zsh", "shell"),
+    ("ruby", "ruby"),
+    ("perl", "perl"),
+    ("php", "php"),
+    ("zig", "zig"),
+    ("sql", "sql"),
+];
+
+/// Tokens that hint at a language when a file has neither extension, shebang nor modeline.
+const CLASSIFIER_HINTS: &[(&str, &[&str])] = &[
+    (
+        "rust",
+        &[
+            "fn ",
+            "let mut ",
+            "impl ",
+            "pub fn ",
+            "use std::",
+            "-> ",
+            "::",
+        ],
+    ),
+    (
+        "python",
+        &["def ", "import ", "self.", "elif ", "__name__", "print("],
+    ),
+    (
+        "javascript",
+        &[
+            "function ",
+            "const ",
+            "=> ",
+            "require(",
+            "module.exports",
+            "console.log",
+        ],
+    ),
+    ("go", &["package ", "func ", ":= ", "fmt."]),
+    (
+        "shell",
+        &["fi\n", "then\n", "esac", "$(", "echo ", "set -e", "${"],
+    ),
+    ("ruby", &["end\n", "puts ", "require '", "def ", "do |"]),
+];
+
+/// Pick a language for a source file from its extension, falling back to the content for
+/// extensionless or unknown files: shebang line, Emacs/Vim modeline, then a keyword classifier.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LanguageDetector;
+
+impl LanguageDetector {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Detect the language of `path`, using `content` when the extension says nothing.
+    ///
+    /// Returns `text` when no signal is found.
+    pub fn detect(&self, path: &Path, content: &str) -> &'static str {
+        self.from_extension(path)
+            .or_else(|| self.from_shebang(content))
+            .or_else(|| self.from_modeline(content))
+            .or_else(|| self.classify(content))
+            .unwrap_or("text")
+    }
+
+    pub fn from_extension(&self, path: &Path) -> Option<&'static str> {
+        let language = match path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "rs" => "rust",
+            "py" => "python",
+            "js" | "jsx" => "javascript",
+            "ts" | "tsx" => "typescript",
+            "go" => "go",
+            "java" => "java",
+            "kt" => "kotlin",
+            "cs" => "csharp",
+            "c" | "h" => "c",
+            "cc" | "cpp" | "hpp" => "cpp",
+            "sql" => "sql",
+            "prisma" => "prisma",
+            "sh" | "bash" | "zsh" => "shell",
+            "rb" => "ruby",
+            "php" => "php",
+            "zig" => "zig",
+            "pl" => "perl",
+            _ => return None,
+        };
+        Some(language)
+    }
+
+    /// `#!/usr/bin/env python3`, `#!/bin/bash -e`, `#!/usr/bin/env -S deno run`.
+    pub fn from_shebang(&self, content: &str) -> Option<&'static str> {
+        let line = content.lines().next()?.strip_prefix("#!")?;
+        let mut words = line.split_whitespace();
+        let mut program = words.next()?.rsplit('/').next()?;
+        if program == "env" {
+            program = words.find(|word| !word.starts_with('-'))?;
+        }
+        interpreter_language(program)
+    }
+
+    /// Emacs `-*- mode: python -*-` / `-*- python -*-` and Vim `vim: set ft=python:` modelines
+    /// in the first or last few lines.
+    pub fn from_modeline(&self, content: &str) -> Option<&'static str> {
+        let lines = content.lines().collect::<Vec<_>>();
+        let head = lines.iter().take(MODELINE_LINES);
+        let tail = lines.iter().skip(
+            lines
+                .len()
+                .saturating_sub(MODELINE_LINES)
+                .max(MODELINE_LINES),
+        );
+        head.chain(tail)
+            .find_map(|line| emacs_mode(line).or_else(|| vim_filetype(line)))
+            .and_then(mode_language)
+    }
+
+    /// Score the content against per-language keyword hints and take the best match.
+    pub fn classify(&self, content: &str) -> Option<&'static str> {
+        let mut best: Option<(&'static str, usize)> = None;
+        for (language, hints) in CLASSIFIER_HINTS {
+            let score = hints
+                .iter()
+                .map(|hint| content.matches(hint).count().min(3))
+                .sum::<usize>();
+            if score >= MIN_CLASSIFIER_SCORE && best.is_none_or(|(_, top)| score > top) {
+                best = Some((language, score));
+            }
+        }
+        best.map(|(language, _)| language)
+    }
+}
+
+fn interpreter_language(program: &str) -> Option<&'static str> {
+    // `python3.12` and friends.
+    let program = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    INTERPRETERS
+        .iter()
+        .find(|(name, _)| name.trim_end_matches(|c: char| c.is_ascii_digit()) == program)
+        .map(|(_, language)| *language)
+}
+
+fn mode_language(mode: &str) -> Option<&'static str> {
+    let mode = mode.to_ascii_lowercase();
+    let mode = mode.trim_end_matches("-mode");
+    MODE_NAMES
+        .iter()
+        .find(|(name, _)| *name == mode)
+        .map(|(_, language)| *language)
+}
+
+fn emacs_mode(line: &str) -> Option<&str> {
+    let start = line.find("-*-")? + 3;
+    let end = line[start..].find("-*-")? + start;
+    let body = line[start..end].trim();
+    if !body.contains(':') {
+        return Some(body);
+    }
+    body.split(';').find_map(|setting| {
+        let (key, value) = setting.split_once(':')?;
+        key.trim()
+            .eq_ignore_ascii_case("mode")
+            .then(|| value.trim())
+    })
+}
+
+fn vim_filetype(line: &str) -> Option<&str> {
+    let start = ["vim:", "vi:", "ex:"]
+        .iter()
+        .find_map(|marker| line.find(marker).map(|pos| pos + marker.len()))?;
+    line[start..]
+        .split([' ', ':'])
+        .find_map(|setting| {
+            setting
+                .strip_prefix("ft=")
+                .or_else(|| setting.strip_prefix("filetype="))
+                .or_else(|| setting.strip_prefix("syntax="))
+        })
+        .filter(|value| !value.is_empty())
+}
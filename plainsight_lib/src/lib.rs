@@ -1,25 +1,79 @@
-use std::path::Path;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use tracing_subscriber::EnvFilter;
 
+pub use tokio_util::sync::CancellationToken;
+pub use workflow::{
+    hooks::{PipelineHook, PipelineStage, StageContext},
+    progress::{ProgressEvent, ProgressSink},
+};
+
 use crate::{
     config::PlainSightConfig,
+    docs_store::{DocsStore, LocalDocsStore},
     error::{PlainSightError, Result},
+    explain::Selection,
+    export::ExportFormat,
+    model_stats::ModelStats,
+    ollama::{GenerationResult, LlmBackend, OllamaWrapper, Task},
+    org_index::OrgSymbolMatch,
     project_manager::ProjectManager,
+    retrieval::{ContextBundle, RetrievalOptions},
+    run_plan::RunPlan,
+    symbol_search::SymbolMatch,
+    workflow::{RunControl, progress::NoProgress},
 };
 
+pub mod benchmarks;
+pub mod concurrency;
 pub mod config;
+pub mod config_report;
+pub mod docs_store;
+pub mod env_vars;
 pub mod error;
+pub mod error_catalog;
+pub mod explain;
+pub mod export;
+pub mod features;
 pub mod file_walker;
+pub mod freshness;
+pub mod git_changes;
+pub mod injection_guard;
+pub mod language;
 pub mod memory;
+pub mod migrate;
+pub mod model_stats;
+pub mod module_tree;
 pub mod ollama;
+pub mod org_index;
+pub mod openapi;
+pub mod ownership;
+pub mod page_split;
+pub mod path_selection;
 pub mod project_manager;
+pub mod prompt_context;
+pub mod proto;
+pub mod retrieval;
+pub mod run_plan;
+mod rust_source;
+pub mod rustdoc;
 pub mod source_indexer;
+mod state_files;
+pub mod symbol_search;
+pub mod unsafe_code;
 mod workflow;
+pub mod workspace;
 
 pub struct PlainSight {
     config: PlainSightConfig,
     manager: ProjectManager,
+    backend: Option<Arc<dyn LlmBackend>>,
+    cancel: CancellationToken,
+    progress: Arc<dyn ProgressSink>,
+    hooks: Vec<Arc<dyn PipelineHook>>,
 }
 
 impl PlainSight {
@@ -30,32 +84,255 @@ impl PlainSight {
     pub fn with_config(
         docs_root: impl AsRef<Path>,
         config: PlainSightConfig,
+    ) -> Result<Self> {
+        Self::with_store(docs_root, config, Arc::new(LocalDocsStore))
+    }
+
+    pub fn with_store(
+        docs_root: impl AsRef<Path>,
+        config: PlainSightConfig,
+        store: Arc<dyn DocsStore>,
     ) -> Result<Self> {
         let env_filter =
             EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
-        tracing_subscriber::fmt()
+        // Kept when the embedding program, or an earlier instance, already installed one.
+        let _ = tracing_subscriber::fmt()
             .with_env_filter(env_filter)
             .with_target(true)
             .with_file(false)
             .with_line_number(false)
-            .init();
+            .with_writer(std::io::stderr)
+            .try_init();
 
         let docs_root = docs_root.as_ref().to_str().ok_or_else(|| {
             PlainSightError::InvalidState("docs_root contains non-utf8 characters".to_string())
         })?;
 
         Ok(Self {
+            manager: ProjectManager::with_store(docs_root, store)
+                .with_state_repair(config.repair_state_files),
             config,
-            manager: ProjectManager::new(docs_root),
+            backend: None,
+            cancel: CancellationToken::new(),
+            progress: Arc::new(NoProgress),
+            hooks: Vec::new(),
         })
     }
 
+    /// Generate with `backend` instead of the server selected by `config.ollama.backend`.
+    pub fn with_backend(mut self, backend: Arc<dyn LlmBackend>) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    /// Stop runs and explanations when `cancel` is cancelled, e.g. from a Ctrl-C handler.
+    ///
+    /// A cancelled run drops its requests in flight, unloads the models and records the files
+    /// whose docs were finished in the meta cache, so the next run picks up the rest. It then
+    /// fails with `PlainSightError::Cancelled`.
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Report the phases of runs and each file's summary and docs to `progress` as they go;
+    /// see [`ProgressEvent`].
+    pub fn with_progress(mut self, progress: Arc<dyn ProgressSink>) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// Call `hook` before and after each stage of runs, after the hooks added before it; see
+    /// [`PipelineStage`].
+    pub fn with_hook(mut self, hook: Arc<dyn PipelineHook>) -> Self {
+        self.hooks.push(hook);
+        self
+    }
+
+    fn control(&self) -> RunControl<'_> {
+        RunControl {
+            cancel: &self.cancel,
+            progress: self.progress.as_ref(),
+            hooks: &self.hooks,
+        }
+    }
+
     pub async fn run_project(
         &self,
         project_name: &str,
         project_root: &Path,
     ) -> Result<()> {
-        workflow::run_with_manager(&self.manager, &self.config, project_name, project_root).await
+        workflow::run_with_manager(
+            &self.manager,
+            &self.config,
+            project_name,
+            project_root,
+            None,
+            self.backend.as_ref(),
+            self.control(),
+        )
+        .await
+    }
+
+    /// Document only the files under `subtree` (relative to `project_root`).
+    ///
+    /// Memory persisted by earlier full runs is reused for cross-file context. The project
+    /// summary and architecture for the subtree go to `scopes/<subtree>/` under the project docs.
+    pub async fn run_project_subtree(
+        &self,
+        project_name: &str,
+        project_root: &Path,
+        subtree: &Path,
+    ) -> Result<()> {
+        workflow::run_with_manager(
+            &self.manager,
+            &self.config,
+            project_name,
+            project_root,
+            Some(subtree),
+            self.backend.as_ref(),
+            self.control(),
+        )
+        .await
+    }
+
+    /// Regenerate only the files `paths` select: files, directories or globs such as
+    /// `src/parser/**`, relative to `project_root`; see [`path_selection`]. The rest of the
+    /// project is still parsed for cross-file context, and its changed files are left for a
+    /// later run.
+    pub async fn run_paths(
+        &self,
+        project_name: &str,
+        project_root: &Path,
+        paths: &[PathBuf],
+    ) -> Result<()> {
+        let config = PlainSightConfig {
+            paths: paths.to_vec(),
+            ..self.config.clone()
+        };
+        workflow::run_with_manager(
+            &self.manager,
+            &config,
+            project_name,
+            project_root,
+            None,
+            self.backend.as_ref(),
+            self.control(),
+        )
+        .await
+    }
+
+    /// Document every sub-project of the workspace at `workspace_root` (Cargo workspace
+    /// members, pnpm or Yarn workspaces, Maven modules) as its own project under the docs root,
+    /// then write `workspace.md` there linking them; see [`workspace`].
+    pub async fn run_workspace(&self, workspace_root: &Path) -> Result<()> {
+        workspace::run_workspace(
+            &self.manager,
+            &self.config,
+            workspace_root,
+            self.backend.as_ref(),
+            self.control(),
+        )
+        .await
+    }
+
+    /// What `run_project`, or `run_project_subtree` with `subtree`, would do: the files it would
+    /// regenerate and reuse and the requests it would send, worked out without contacting the
+    /// LLM server or writing to the docs root.
+    pub async fn plan_project(
+        &self,
+        project_name: &str,
+        project_root: &Path,
+        subtree: Option<&Path>,
+    ) -> Result<RunPlan> {
+        workflow::plan_with_manager(
+            &self.manager,
+            &self.config,
+            project_name,
+            project_root,
+            subtree,
+            self.backend.as_ref(),
+        )
+        .await
+    }
+
+    pub fn get_context_for_question(
+        &self,
+        project_name: &str,
+        question: &str,
+    ) -> Result<ContextBundle> {
+        let project = self.manager.new_project(project_name, std::path::PathBuf::new());
+        retrieval::get_context_for_question(&project, question, &RetrievalOptions::default())
+    }
+
+    /// Search the symbols of `project_name`'s project memory; see
+    /// [`symbol_search::search_symbols`].
+    pub fn search_symbols(
+        &self,
+        project_name: &str,
+        pattern: &str,
+        limit: usize,
+    ) -> Result<Vec<SymbolMatch>> {
+        let project = self.manager.new_project(project_name, std::path::PathBuf::new());
+        symbol_search::search_symbols(&project, pattern, limit)
+    }
+
+    /// Search the symbols of every project under the docs root; see
+    /// [`org_index::search_org_symbols`].
+    pub fn search_org_symbols(&self, pattern: &str, limit: usize) -> Result<Vec<OrgSymbolMatch>> {
+        org_index::search_org_symbols(&self.manager, pattern, limit)
+    }
+
+    /// Model statistics collected under the docs root; see [`model_stats`].
+    pub fn model_stats(&self) -> Result<ModelStats> {
+        model_stats::load_model_stats(&self.manager)
+    }
+
+    /// Explain the selected lines of a file under `project_root`, using the project memory and
+    /// summaries of earlier runs of `project_name` when present. The explanation comes with the
+    /// model that wrote it and the server's token counts.
+    pub async fn explain_selection(
+        &self,
+        project_name: &str,
+        project_root: &Path,
+        selection: &Selection,
+    ) -> Result<GenerationResult> {
+        let project_name = self.manager.bind_project_name(
+            project_name,
+            project_root,
+            self.config.name_collision,
+        )?;
+        let project = self.manager.new_project(project_name.as_str(), project_root);
+        let context = explain::build_explain_context(
+            &project,
+            project_root,
+            selection,
+            self.config.injection_guard,
+        )?;
+        let ollama_config = self
+            .config
+            .ollama
+            .clone()
+            .with_prompt_overrides_from(project_root, &project_name)?;
+        let wrapper = match &self.backend {
+            Some(backend) => OllamaWrapper::with_backend(ollama_config, Arc::clone(backend)),
+            None => OllamaWrapper::with_config(ollama_config)?,
+        }
+        .with_cancellation(self.cancel.clone());
+        wrapper.ensure_models(&[Task::Explain]).await?;
+        wrapper.explain(&context).await
+    }
+
+    /// Merge the generated docs of `project_name` into one HTML or PDF file, a static site or an
+    /// mdBook; see [`export::export_bundle`].
+    pub fn export_bundle(
+        &self,
+        project_name: &str,
+        format: ExportFormat,
+        output: Option<&Path>,
+    ) -> Result<std::path::PathBuf> {
+        let project = self.manager.new_project(project_name, std::path::PathBuf::new());
+        export::export_bundle(&project, project_name, format, output)
     }
 
     pub fn manager(&self) -> &ProjectManager {
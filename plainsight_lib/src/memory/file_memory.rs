@@ -1,13 +1,34 @@
-use std::collections::BTreeSet;
+use std::{
+    collections::{BTreeSet, hash_map::DefaultHasher},
+    hash::{Hash, Hasher},
+};
 
-use super::{ConfidenceLevel, FileMemory, SymbolDetails, SymbolFact};
+use super::{
+    ConfidenceLevel, FileMemory, SymbolDetails, SymbolFact, SymbolFingerprint, metrics, schema,
+};
 
 const MAX_FILE_SYMBOLS: usize = 200;
 const MAX_FILE_IMPORTS: usize = 200;
+const MAX_IMPORT_CHARS: usize = 180;
+const MAX_REEXPORT_CHARS: usize = 1000;
+/// An attribute still open after this many lines is most likely a misread bracket or quote; it
+/// is dropped so the items after it are still recorded.
+const MAX_ATTRIBUTE_LINES: usize = 8;
+/// Bodies shorter than this (accessors, one-line constructors) are too generic to fingerprint.
+const MIN_FINGERPRINT_LINES: usize = 3;
 
 pub fn build_file_memory(relative_path: &str, language: &str, source: &str) -> FileMemory {
+    if schema::is_schema_language(language) {
+        return schema_file_memory(relative_path, language, source);
+    }
+
     let mut symbols = Vec::new();
     let mut imports = Vec::new();
+    let mut reexports = Vec::new();
+    let mut pending_use: Option<String> = None;
+    let is_test_file = is_test_path(relative_path);
+    let mut test_scope = TestScope::default();
+    let mut attributes = PendingAttributes::default();
 
     for (idx, raw_line) in source.lines().enumerate() {
         let line_no = idx + 1;
@@ -17,18 +38,50 @@ pub fn build_file_memory(relative_path: &str, language: &str, source: &str) -> F
             continue;
         }
 
-        if let Some(import) = parse_import(trimmed, language) {
+        let in_test_scope = language == "rust" && test_scope.observe(trimmed);
+
+        if language == "rust" {
+            match rust_use_statement(&mut pending_use, trimmed) {
+                UseStatement::Pending => continue,
+                UseStatement::ReExport(statement) => {
+                    reexports.push(statement);
+                    continue;
+                }
+                UseStatement::Import(statement) => {
+                    imports.push(truncate_import(statement, MAX_IMPORT_CHARS));
+                    continue;
+                }
+                UseStatement::None => {}
+            }
+        } else if let Some(import) = parse_import(trimmed, language) {
             imports.push(import);
         }
 
-        if let Some(sym) = parse_symbol(trimmed, line_no, language) {
+        let Some(item) = attributes.observe(trimmed, language) else {
+            continue;
+        };
+
+        if let Some(mut sym) = parse_symbol(item, line_no, language) {
+            sym.is_test = is_test_file || in_test_scope || is_test_symbol_name(&sym, language);
+            sym.details.attributes = attributes.take();
             symbols.push(sym);
+        } else {
+            attributes.take();
         }
     }
 
     dedup_imports(&mut imports);
+    dedup_imports(&mut reexports);
     dedup_symbols(&mut symbols);
 
+    let code_lines = source
+        .lines()
+        .map(|line| strip_comments(line, language))
+        .collect::<Vec<_>>();
+    for sym in symbols.iter_mut().filter(|sym| sym.kind == "function") {
+        sym.metrics = Some(metrics::function_metrics(&code_lines, sym.line, language));
+    }
+
     if symbols.len() > MAX_FILE_SYMBOLS {
         symbols.truncate(MAX_FILE_SYMBOLS);
     }
@@ -39,16 +92,310 @@ pub fn build_file_memory(relative_path: &str, language: &str, source: &str) -> F
     FileMemory {
         path: relative_path.to_string(),
         language: language.to_string(),
+        is_test_file,
         symbol_count: symbols.len(),
         import_count: imports.len(),
         symbols,
         imports,
+        reexports,
+    }
+}
+
+/// Fingerprint the body of every symbol in `memory` that spans at least
+/// `MIN_FINGERPRINT_LINES` non-blank lines.
+///
+/// Bodies are hashed with comments, blank lines and indentation removed and runs of whitespace
+/// collapsed, so reformatting or moving a symbol keeps its fingerprint.
+pub fn symbol_fingerprints(memory: &FileMemory, source: &str) -> Vec<SymbolFingerprint> {
+    let code_lines = source
+        .lines()
+        .map(|line| strip_comments(line, &memory.language))
+        .collect::<Vec<_>>();
+
+    memory
+        .symbols
+        .iter()
+        .filter_map(|sym| {
+            let normalized = metrics::symbol_body(&code_lines, sym.line, &memory.language)
+                .iter()
+                .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+                .filter(|line| !line.is_empty())
+                .collect::<Vec<_>>();
+            if normalized.len() < MIN_FINGERPRINT_LINES {
+                return None;
+            }
+
+            let mut hasher = DefaultHasher::new();
+            normalized.hash(&mut hasher);
+            Some(SymbolFingerprint {
+                name: sym.name.clone(),
+                kind: sym.kind.clone(),
+                line: sym.line,
+                fingerprint: format!("{:x}", hasher.finish()),
+            })
+        })
+        .collect()
+}
+
+/// Fold the memory of a later segment of the same file into `memory`; `line_offset` is the
+/// number of lines before the segment.
+pub fn merge_file_memory(memory: &mut FileMemory, segment: FileMemory, line_offset: usize) {
+    memory
+        .symbols
+        .extend(segment.symbols.into_iter().map(|mut sym| {
+            sym.line += line_offset;
+            sym
+        }));
+    memory.imports.extend(segment.imports);
+    memory.reexports.extend(segment.reexports);
+
+    dedup_imports(&mut memory.imports);
+    dedup_imports(&mut memory.reexports);
+    memory.symbols.truncate(MAX_FILE_SYMBOLS);
+    memory.imports.truncate(MAX_FILE_IMPORTS);
+    memory.symbol_count = memory.symbols.len();
+    memory.import_count = memory.imports.len();
+}
+
+/// Data-model files carry tables instead of code symbols and have no imports.
+fn schema_file_memory(relative_path: &str, language: &str, source: &str) -> FileMemory {
+    let mut symbols = schema::schema_symbols(language, source);
+    symbols.truncate(MAX_FILE_SYMBOLS);
+
+    FileMemory {
+        path: relative_path.to_string(),
+        language: language.to_string(),
+        is_test_file: is_test_path(relative_path),
+        symbol_count: symbols.len(),
+        import_count: 0,
+        symbols,
+        imports: Vec::new(),
+        reexports: Vec::new(),
+    }
+}
+
+/// Path conventions for test code: `tests/` directories, `*_test.go`, `test_*.py` and the
+/// usual JS/JVM suffixes.
+fn is_test_path(relative_path: &str) -> bool {
+    let path = std::path::Path::new(relative_path);
+    let in_test_dir = path.parent().is_some_and(|parent| {
+        parent.components().any(|component| {
+            matches!(
+                component.as_os_str().to_str(),
+                Some("tests" | "test" | "__tests__" | "spec")
+            )
+        })
+    });
+    if in_test_dir {
+        return true;
+    }
+
+    let file_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+    file_name.ends_with("_test.go")
+        || (file_name.starts_with("test_") && file_name.ends_with(".py"))
+        || file_name.ends_with("_test.py")
+        || file_name.contains(".test.")
+        || file_name.contains(".spec.")
+        || file_name.ends_with("Test.java")
+        || file_name.ends_with("Tests.java")
+        || file_name.ends_with("Test.kt")
+        || file_name.ends_with("Tests.cs")
+}
+
+fn is_test_symbol_name(symbol: &SymbolFact, language: &str) -> bool {
+    match language {
+        "python" => {
+            (symbol.kind == "function" && symbol.name.starts_with("test_"))
+                || (symbol.kind == "class" && symbol.name.starts_with("Test"))
+        }
+        "go" => symbol.kind == "function" && symbol.name.starts_with("Test"),
+        "ruby" => symbol.kind == "function" && symbol.name.starts_with("test_"),
+        "php" => symbol.kind == "function" && symbol.name.starts_with("test"),
+        "zig" => symbol.kind == "test",
+        _ => false,
+    }
+}
+
+/// Tracks `#[cfg(test)]` modules and `#[test]` items while scanning Rust source line by line.
+#[derive(Debug, Default)]
+struct TestScope {
+    depth: i64,
+    test_module_depth: Option<i64>,
+    pending_cfg_test: bool,
+    pending_test_item: bool,
+}
+
+impl TestScope {
+    /// Returns whether `line` belongs to test code.
+    fn observe(&mut self, line: &str) -> bool {
+        let mut in_test = self.test_module_depth.is_some();
+
+        let item = if line.starts_with("#[") {
+            let (attribute, rest) = match line.find(']') {
+                Some(end) => (&line[..=end], line[end + 1..].trim_start()),
+                None => (line, ""),
+            };
+            if attribute.starts_with("#[cfg(test)") {
+                self.pending_cfg_test = true;
+            } else if attribute == "#[test]" || attribute.ends_with("::test]") {
+                self.pending_test_item = true;
+            }
+            rest
+        } else {
+            line
+        };
+
+        if !item.is_empty() && !item.starts_with("#[") {
+            if self.pending_cfg_test || self.pending_test_item {
+                in_test = true;
+                if self.pending_cfg_test && item.contains("mod ") && item.contains('{') {
+                    self.test_module_depth.get_or_insert(self.depth);
+                }
+            }
+            self.pending_cfg_test = false;
+            self.pending_test_item = false;
+        }
+
+        self.depth += line.matches('{').count() as i64 - line.matches('}').count() as i64;
+        if self
+            .test_module_depth
+            .is_some_and(|module_depth| self.depth <= module_depth)
+        {
+            self.test_module_depth = None;
+        }
+
+        in_test
     }
 }
 
+/// Collects Rust attributes and Java/Kotlin annotations so they can be attached to the item
+/// that follows them.
+#[derive(Default)]
+struct PendingAttributes {
+    attributes: Vec<String>,
+    partial: Option<String>,
+    partial_lines: usize,
+}
+
+impl PendingAttributes {
+    /// Consumes leading attributes on `line` and returns the remaining item text, or `None` when
+    /// the line holds nothing but attributes.
+    fn observe<'a>(&mut self, line: &'a str, language: &str) -> Option<&'a str> {
+        let (open, close) = match language {
+            "rust" => ('[', ']'),
+            "java" | "kotlin" => ('(', ')'),
+            _ => return Some(line),
+        };
+
+        let mut rest = line;
+        if let Some(partial) = self.partial.as_mut() {
+            self.partial_lines += 1;
+            partial.push(' ');
+            match closing_index(partial, rest, open, close) {
+                Some(end) => {
+                    partial.push_str(&rest[..=end]);
+                    let attribute = self.partial.take().unwrap_or_default();
+                    if !attribute.starts_with("#!") {
+                        self.attributes.push(attribute);
+                    }
+                    rest = rest[end + 1..].trim_start();
+                }
+                None if self.partial_lines > MAX_ATTRIBUTE_LINES => self.partial = None,
+                None => {
+                    partial.push_str(rest);
+                    return None;
+                }
+            }
+        }
+
+        loop {
+            let is_attribute = match language {
+                "rust" => rest.starts_with("#[") || rest.starts_with("#!["),
+                _ => rest.starts_with('@') && !rest.starts_with("@interface"),
+            };
+            if !is_attribute {
+                break;
+            }
+
+            let (head, args_start) = if language == "rust" {
+                (rest.find('[').unwrap_or(0), None)
+            } else {
+                let name_end = rest[1..]
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | ':')))
+                    .map(|idx| idx + 1)
+                    .unwrap_or(rest.len());
+                if !rest[name_end..].starts_with('(') {
+                    self.attributes.push(rest[..name_end].to_string());
+                    rest = rest[name_end..].trim_start();
+                    continue;
+                }
+                (name_end, Some(name_end))
+            };
+            let start = args_start.unwrap_or(head);
+
+            match closing_index("", &rest[start..], open, close) {
+                Some(end) => {
+                    let attribute = &rest[..=start + end];
+                    // Inner attributes (`#![...]`) describe the enclosing module, not the next item.
+                    if !attribute.starts_with("#!") {
+                        self.attributes.push(attribute.to_string());
+                    }
+                    rest = rest[start + end + 1..].trim_start();
+                }
+                None => {
+                    self.partial = Some(rest.to_string());
+                    self.partial_lines = 1;
+                    return None;
+                }
+            }
+        }
+
+        if rest.is_empty() { None } else { Some(rest) }
+    }
+
+    fn take(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.attributes)
+    }
+}
+
+/// Index in `text` of the bracket that balances everything opened in `prefix` and `text`.
+/// Brackets inside string literals are not counted.
+fn closing_index(prefix: &str, text: &str, open: char, close: char) -> Option<usize> {
+    let mut depth = 0i64;
+    let mut in_string = false;
+    let mut escaped = false;
+    let chars = prefix
+        .chars()
+        .map(|ch| (None, ch))
+        .chain(text.char_indices().map(|(idx, ch)| (Some(idx), ch)));
+    for (idx, ch) in chars {
+        if in_string {
+            match ch {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+        } else if ch == '"' {
+            in_string = true;
+        } else if ch == open {
+            depth += 1;
+        } else if ch == close {
+            depth -= 1;
+            if depth == 0 && idx.is_some() {
+                return idx;
+            }
+        }
+    }
+    None
+}
+
 fn strip_comments<'a>(line: &'a str, language: &str) -> &'a str {
     let marker = match language {
-        "python" => "#",
+        "python" | "shell" | "ruby" | "perl" => "#",
         _ => "//",
     };
     line.split_once(marker)
@@ -64,6 +411,16 @@ fn parse_import(line: &str, language: &str) -> Option<String> {
         "go" => line.starts_with("import "),
         "java" | "kotlin" | "csharp" => line.starts_with("import ") || line.starts_with("using "),
         "c" | "cpp" => line.starts_with("#include "),
+        "ruby" => line.starts_with("require ") || line.starts_with("require_relative "),
+        "php" => {
+            line.starts_with("use ")
+                || ["require", "require_once", "include", "include_once"]
+                    .iter()
+                    .any(|kw| {
+                        line.starts_with(&format!("{kw} ")) || line.starts_with(&format!("{kw}("))
+                    })
+        }
+        "zig" => line.contains("@import("),
         _ => {
             line.starts_with("import ") || line.starts_with("use ") || line.starts_with("#include ")
         }
@@ -73,12 +430,85 @@ fn parse_import(line: &str, language: &str) -> Option<String> {
         return None;
     }
 
-    let mut normalized = line.trim_end_matches(';').to_string();
-    if normalized.len() > 180 {
-        normalized.truncate(180);
-        normalized.push_str("...");
+    Some(truncate_import(
+        line.trim_end_matches(';').to_string(),
+        MAX_IMPORT_CHARS,
+    ))
+}
+
+fn truncate_import(mut import: String, max_chars: usize) -> String {
+    if import.len() > max_chars {
+        let mut end = max_chars;
+        while !import.is_char_boundary(end) {
+            end -= 1;
+        }
+        import.truncate(end);
+        import.push_str("...");
+    }
+    import
+}
+
+enum UseStatement {
+    None,
+    Pending,
+    Import(String),
+    ReExport(String),
+}
+
+/// Classify a Rust `use` item, joining statements that span several lines.
+///
+/// `pub use` (with any visibility) is a re-export and is tracked apart from plain imports.
+fn rust_use_statement(pending: &mut Option<String>, line: &str) -> UseStatement {
+    let statement = match pending.as_mut() {
+        Some(buffer) => {
+            buffer.push(' ');
+            buffer.push_str(line);
+            if !line.contains(';') {
+                return UseStatement::Pending;
+            }
+            pending.take().unwrap_or_default()
+        }
+        None => {
+            let item = strip_visibility(line);
+            if !item.starts_with("use ") {
+                return UseStatement::None;
+            }
+            if !line.contains(';') {
+                *pending = Some(line.to_string());
+                return UseStatement::Pending;
+            }
+            line.to_string()
+        }
+    };
+
+    let statement = statement.split(';').next().unwrap_or_default();
+    let is_reexport = strip_visibility(statement).len() != statement.len();
+    let normalized = normalize_use(strip_visibility(statement));
+
+    if is_reexport {
+        UseStatement::ReExport(truncate_import(normalized, MAX_REEXPORT_CHARS))
+    } else {
+        UseStatement::Import(normalized)
+    }
+}
+
+fn strip_visibility(line: &str) -> &str {
+    if let Some(rest) = line.strip_prefix("pub(")
+        && let Some(end) = rest.find(')')
+    {
+        return rest[end + 1..].trim_start();
     }
-    Some(normalized)
+    line.strip_prefix("pub ")
+        .map(str::trim_start)
+        .unwrap_or(line)
+}
+
+fn normalize_use(statement: &str) -> String {
+    let collapsed = statement.split_whitespace().collect::<Vec<_>>().join(" ");
+    collapsed
+        .replace("{ ", "{")
+        .replace(" }", "}")
+        .replace(",}", "}")
 }
 
 fn parse_symbol(line: &str, line_no: usize, language: &str) -> Option<SymbolFact> {
@@ -89,6 +519,9 @@ fn parse_symbol(line: &str, line_no: usize, language: &str) -> Option<SymbolFact
         "go" => parse_go_symbol(line),
         "java" | "kotlin" | "csharp" => parse_jvm_or_csharp_symbol(line),
         "c" | "cpp" => parse_c_family_symbol(line),
+        "ruby" => parse_ruby_symbol(line),
+        "php" => parse_php_symbol(line),
+        "zig" => parse_zig_symbol(line),
         _ => parse_fallback_symbol(line),
     }?;
 
@@ -98,6 +531,8 @@ fn parse_symbol(line: &str, line_no: usize, language: &str) -> Option<SymbolFact
         line: line_no,
         confidence: parsed.2,
         details: parsed.3,
+        is_test: false,
+        metrics: None,
     })
 }
 
@@ -129,7 +564,11 @@ fn extract_identifier_after_keyword(line: &str, keyword: &str) -> Option<String>
 }
 
 fn parse_rust_symbol(line: &str) -> Option<(String, &'static str, ConfidenceLevel, SymbolDetails)> {
-    let details = SymbolDetails::default();
+    let visibility = &line[..line.len() - strip_visibility(line).len()];
+    let details = SymbolDetails {
+        visibility: visibility.trim().to_string(),
+        ..SymbolDetails::default()
+    };
     let candidates = [
         ("fn", "function"),
         ("struct", "struct"),
@@ -183,12 +622,11 @@ fn parse_js_ts_symbol(
         }
     }
 
-    if line.contains("=>") || (line.contains('(') && line.contains(')') && line.contains('{')) {
-        if let Some(name) = extract_identifier_before_char(line, '(')
-            && !is_control_keyword(&name)
-        {
-            return Some((name, "function", ConfidenceLevel::Medium, details));
-        }
+    if (line.contains("=>") || (line.contains('(') && line.contains(')') && line.contains('{')))
+        && let Some(name) = extract_identifier_before_char(line, '(')
+        && !is_control_keyword(&name)
+    {
+        return Some((name, "function", ConfidenceLevel::Medium, details));
     }
 
     None
@@ -232,12 +670,13 @@ fn parse_jvm_or_csharp_symbol(
         }
     }
 
-    if line.contains('(') && line.contains(')') && line.ends_with('{') {
-        if let Some(name) = extract_identifier_before_char(line, '(')
-            && !is_control_keyword(&name)
-        {
-            return Some((name, "function", ConfidenceLevel::Medium, details));
-        }
+    if line.contains('(')
+        && line.contains(')')
+        && line.ends_with('{')
+        && let Some(name) = extract_identifier_before_char(line, '(')
+        && !is_control_keyword(&name)
+    {
+        return Some((name, "function", ConfidenceLevel::Medium, details));
     }
 
     None
@@ -262,11 +701,143 @@ fn parse_c_family_symbol(
         }
     }
 
-    if line.contains('(') && line.contains(')') && line.ends_with('{') {
-        if let Some(name) = extract_identifier_before_char(line, '(')
-            && !is_control_keyword(&name)
+    if line.contains('(')
+        && line.contains(')')
+        && line.ends_with('{')
+        && let Some(name) = extract_identifier_before_char(line, '(')
+        && !is_control_keyword(&name)
+    {
+        return Some((name, "function", ConfidenceLevel::Medium, details));
+    }
+
+    None
+}
+
+fn parse_ruby_symbol(line: &str) -> Option<(String, &'static str, ConfidenceLevel, SymbolDetails)> {
+    let details = SymbolDetails::default();
+
+    if let Some(rest) = line.strip_prefix("def ") {
+        // `def self.build` defines a class method; `valid?`, `save!` and `name=` keep their suffix.
+        let rest = rest.trim_start();
+        let rest = rest.strip_prefix("self.").unwrap_or(rest);
+        let end = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        let suffix = rest[end..]
+            .chars()
+            .next()
+            .filter(|c| matches!(c, '?' | '!' | '='))
+            .map(String::from)
+            .unwrap_or_default();
+        let name = format!("{}{suffix}", &rest[..end]);
+        if end > 0 {
+            return Some((name, "function", ConfidenceLevel::High, details));
+        }
+        return None;
+    }
+
+    for (keyword, kind) in [("class", "class"), ("module", "module")] {
+        if line.starts_with(&format!("{keyword} "))
+            && let Some(name) = extract_identifier_after_keyword(line, keyword)
+        {
+            return Some((name, kind, ConfidenceLevel::High, details));
+        }
+    }
+
+    if let Some((name, _)) = line.split_once(" = ")
+        && name.starts_with(|c: char| c.is_ascii_uppercase())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        return Some((name.to_string(), "const", ConfidenceLevel::Medium, details));
+    }
+
+    None
+}
+
+fn parse_php_symbol(line: &str) -> Option<(String, &'static str, ConfidenceLevel, SymbolDetails)> {
+    let details = SymbolDetails::default();
+
+    for (keyword, kind) in [
+        ("class", "class"),
+        ("interface", "interface"),
+        ("trait", "trait"),
+        ("enum", "enum"),
+        ("function", "function"),
+    ] {
+        // `new class(...)` and `function (` closures have no name and are skipped.
+        if let Some(name) = extract_identifier_after_keyword(line, keyword)
+            && !line.contains("new class")
         {
-            return Some((name, "function", ConfidenceLevel::Medium, details));
+            return Some((name, kind, ConfidenceLevel::High, details));
+        }
+    }
+
+    if let Some(name) = extract_identifier_after_keyword(line, "namespace") {
+        let namespace = line
+            .split_once("namespace ")
+            .map(|(_, rest)| rest.trim_end_matches([';', '{']).trim())
+            .filter(|ns| !ns.is_empty())
+            .unwrap_or(&name)
+            .to_string();
+        return Some((namespace, "namespace", ConfidenceLevel::High, details));
+    }
+
+    if let Some(name) = extract_identifier_after_keyword(line, "const") {
+        return Some((name, "const", ConfidenceLevel::High, details));
+    }
+
+    None
+}
+
+fn parse_zig_symbol(line: &str) -> Option<(String, &'static str, ConfidenceLevel, SymbolDetails)> {
+    let details = SymbolDetails::default();
+
+    if let Some(rest) = line.strip_prefix("test ") {
+        let name = rest
+            .trim()
+            .trim_end_matches('{')
+            .trim()
+            .trim_matches('"')
+            .to_string();
+        let name = if name.is_empty() {
+            "test".to_string()
+        } else {
+            name
+        };
+        return Some((name, "test", ConfidenceLevel::High, details));
+    }
+
+    if let Some(name) = extract_identifier_after_keyword(line, "fn") {
+        return Some((name, "function", ConfidenceLevel::High, details));
+    }
+
+    // Zig declares containers and imports as constants: `const Point = struct {`.
+    let declaration = line
+        .strip_prefix("pub ")
+        .unwrap_or(line)
+        .trim_start_matches("extern ")
+        .trim_start_matches("export ");
+    for keyword in ["const", "var"] {
+        if declaration.starts_with(&format!("{keyword} "))
+            && let Some(name) = extract_identifier_after_keyword(declaration, keyword)
+        {
+            if line.contains("@import(") {
+                return None;
+            }
+            let value = declaration
+                .split_once('=')
+                .map(|(_, value)| value.trim())
+                .unwrap_or_default();
+            let kind = ["struct", "enum", "union", "error", "opaque"]
+                .into_iter()
+                .find(|container| {
+                    value
+                        .trim_start_matches("packed ")
+                        .trim_start_matches("extern ")
+                        .starts_with(container)
+                })
+                .unwrap_or(keyword);
+            return Some((name, kind, ConfidenceLevel::High, details));
         }
     }
 
@@ -0,0 +1,233 @@
+use super::FunctionMetrics;
+
+const MAX_SIGNATURE_LINES: usize = 20;
+const MAX_BODY_LINES: usize = 2000;
+
+/// Measure the function whose declaration starts on `line` (1-based).
+///
+/// `lines` must already have comments stripped. Bodies are delimited by braces, or by
+/// indentation for Python; declarations without a body (trait or interface methods) only
+/// span their signature.
+pub(crate) fn function_metrics(lines: &[&str], line: usize, language: &str) -> FunctionMetrics {
+    let start = line.saturating_sub(1).min(lines.len());
+    let body = symbol_body(lines, line, language);
+
+    FunctionMetrics {
+        line_count: body.len(),
+        parameter_count: parameter_count(&lines[start..], language),
+        complexity: 1 + body
+            .iter()
+            .map(|line| decision_points(line, language))
+            .sum::<usize>(),
+    }
+}
+
+/// Lines of the item declared on `line` (1-based), from its declaration to the end of its body.
+pub(crate) fn symbol_body<'a>(lines: &'a [&'a str], line: usize, language: &str) -> &'a [&'a str] {
+    let start = line.saturating_sub(1).min(lines.len());
+    match language {
+        "python" => python_body(lines, start),
+        "ruby" => keyword_body(lines, start),
+        _ => braced_body(lines, start),
+    }
+}
+
+fn braced_body<'a>(lines: &'a [&'a str], start: usize) -> &'a [&'a str] {
+    let mut depth = 0i64;
+    let mut opened = false;
+
+    for (offset, line) in lines[start..].iter().take(MAX_BODY_LINES).enumerate() {
+        for ch in line.chars() {
+            match ch {
+                '{' => {
+                    depth += 1;
+                    opened = true;
+                }
+                '}' => depth -= 1,
+                ';' if !opened => return &lines[start..=start + offset],
+                _ => {}
+            }
+        }
+        if opened && depth <= 0 {
+            return &lines[start..=start + offset];
+        }
+    }
+
+    let end = (start + MAX_BODY_LINES).min(lines.len());
+    &lines[start..end]
+}
+
+fn python_body<'a>(lines: &'a [&'a str], start: usize) -> &'a [&'a str] {
+    let Some(def_line) = lines.get(start) else {
+        return &[];
+    };
+    let def_indent = indentation(def_line);
+    let mut end = start + 1;
+
+    for (offset, line) in lines[start + 1..].iter().take(MAX_BODY_LINES).enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if indentation(line) <= def_indent {
+            break;
+        }
+        end = start + 1 + offset + 1;
+    }
+
+    &lines[start..end]
+}
+
+/// Ruby bodies close with `end`; every block opener on the way nests one level deeper.
+fn keyword_body<'a>(lines: &'a [&'a str], start: usize) -> &'a [&'a str] {
+    const OPENERS: &[&str] = &[
+        "def", "class", "module", "if", "unless", "while", "until", "case", "begin", "for",
+    ];
+    let mut depth = 0i64;
+
+    for (offset, line) in lines[start..].iter().take(MAX_BODY_LINES).enumerate() {
+        let trimmed = line.trim();
+        let first = trimmed
+            .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .next()
+            .unwrap_or_default();
+        // One-line `def x = expr` (endless methods) has no `end`.
+        if offset == 0 && first == "def" && trimmed.contains(") =") {
+            return &lines[start..=start];
+        }
+        if OPENERS.contains(&first) {
+            depth += 1;
+        }
+        if trimmed.ends_with(" do") || trimmed.contains(" do |") {
+            depth += 1;
+        }
+        if first == "end" {
+            depth -= 1;
+        }
+        if depth <= 0 {
+            return &lines[start..=start + offset];
+        }
+    }
+
+    let end = (start + MAX_BODY_LINES).min(lines.len());
+    &lines[start..end]
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Count declared parameters, excluding receivers such as `self`, `&mut self` and `cls`.
+fn parameter_count(lines: &[&str], language: &str) -> usize {
+    let signature = lines
+        .iter()
+        .take(MAX_SIGNATURE_LINES)
+        .copied()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let Some(open) = signature.find('(') else {
+        return 0;
+    };
+
+    // Go methods declare their receiver in a leading parameter list: `func (s *Server) Run(...)`.
+    let mut rest = &signature[open..];
+    if language == "go" && signature[..open].trim() == "func" {
+        let Some(close) = matching_paren(rest) else {
+            return 0;
+        };
+        let Some(next) = rest[close + 1..].find('(') else {
+            return 0;
+        };
+        rest = &rest[close + 1 + next..];
+    }
+
+    let Some(close) = matching_paren(rest) else {
+        return 0;
+    };
+
+    split_top_level(&rest[1..close])
+        .into_iter()
+        .map(str::trim)
+        .filter(|param| !param.is_empty() && !is_receiver(param))
+        .count()
+}
+
+fn matching_paren(text: &str) -> Option<usize> {
+    let mut depth = 0i64;
+    for (idx, ch) in text.char_indices() {
+        match ch {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(idx);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn split_top_level(params: &str) -> Vec<&str> {
+    let mut out = Vec::new();
+    let mut depth = 0i64;
+    let mut last = 0usize;
+    let mut prev = ' ';
+
+    for (idx, ch) in params.char_indices() {
+        match ch {
+            '(' | '[' | '{' | '<' => depth += 1,
+            // `->` in closure or fn-pointer types is not a closing angle bracket.
+            '>' if prev == '-' => {}
+            ')' | ']' | '}' | '>' => depth -= 1,
+            ',' if depth == 0 => {
+                out.push(&params[last..idx]);
+                last = idx + 1;
+            }
+            _ => {}
+        }
+        prev = ch;
+    }
+    out.push(&params[last..]);
+    out
+}
+
+fn is_receiver(param: &str) -> bool {
+    let name = param.split(':').next().unwrap_or(param).trim();
+    let name = name.trim_start_matches('&').trim_start();
+    let name = name.strip_prefix("mut ").unwrap_or(name).trim();
+    let name = name
+        .strip_prefix('\'')
+        .and_then(|rest| rest.split_once(' '))
+        .map(|(_, rest)| rest.trim())
+        .unwrap_or(name);
+    let name = name.strip_prefix("mut ").unwrap_or(name).trim();
+    matches!(name, "self" | "cls" | "this")
+}
+
+/// Branching constructs on one line: conditionals, loops, match/switch arms, handlers and
+/// short-circuit operators.
+fn decision_points(line: &str, language: &str) -> usize {
+    let keywords: &[&str] = match language {
+        "rust" => &["if", "while", "for"],
+        "python" => &["if", "elif", "while", "for", "except", "and", "or"],
+        "go" => &["if", "for", "case", "select"],
+        "ruby" => &[
+            "if", "elsif", "unless", "while", "until", "when", "rescue", "and", "or",
+        ],
+        _ => &["if", "while", "for", "case", "catch", "foreach"],
+    };
+
+    let words = line
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .filter(|word| keywords.contains(word))
+        .count();
+    let operators = line.matches("&&").count() + line.matches("||").count();
+    let arms = if language == "rust" {
+        line.matches("=>").count()
+    } else {
+        0
+    };
+
+    words + operators + arms
+}
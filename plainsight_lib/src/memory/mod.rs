@@ -1,12 +1,15 @@
 mod file_memory;
+mod metrics;
 mod project_memory;
 mod relevance;
+mod schema;
 mod types;
 
-pub use file_memory::build_file_memory;
+pub use file_memory::{build_file_memory, merge_file_memory, symbol_fingerprints};
 pub use project_memory::build_project_memory;
 pub use relevance::{RelevantMemory, SmartMemory, get_relevant_memory_for_file};
 pub use types::{
-    ConfidenceLevel, CrossFileLink, FieldInfo, FileMemory, GlobalSymbol, OpenItem, ParameterInfo,
-    ProjectMemory, SymbolDetails, SymbolFact, VariantInfo,
+    ConfidenceLevel, CrossFileLink, FieldInfo, FileMemory, FunctionMetrics, GlobalSymbol, OpenItem,
+    ParameterInfo, ProjectMemory, ReExport, SymbolDetails, SymbolFact, SymbolFingerprint,
+    VariantInfo,
 };
@@ -1,17 +1,24 @@
-use std::collections::{BTreeMap, BTreeSet};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
 
-use super::{CrossFileLink, FileMemory, GlobalSymbol, OpenItem, ProjectMemory};
+use super::{CrossFileLink, FileMemory, GlobalSymbol, OpenItem, ProjectMemory, ReExport};
 use crate::memory::file_memory::is_valid_identifier;
 
 const MAX_GLOBAL_SYMBOLS: usize = 300;
 const MAX_OPEN_ITEMS: usize = 120;
 const MAX_PROJECT_LINKS: usize = 400;
 
+/// The memory of the project made of `files`. Every list in it is sorted, whatever the order of
+/// `files`, so that `.memory.json` only changes where the code did.
 pub fn build_project_memory(files: &[FileMemory]) -> ProjectMemory {
+    let mut files = files.to_vec();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
     let mut by_symbol: BTreeMap<(String, String), BTreeSet<String>> = BTreeMap::new();
     let mut by_name: BTreeMap<String, BTreeMap<String, BTreeSet<String>>> = BTreeMap::new();
 
-    for file in files {
+    for file in &files {
         for sym in &file.symbols {
             by_symbol
                 .entry((sym.name.clone(), sym.kind.clone()))
@@ -27,7 +34,8 @@ pub fn build_project_memory(files: &[FileMemory]) -> ProjectMemory {
     }
 
     let unique_symbol_count = by_symbol.len();
-    let links = build_links(files, &by_symbol);
+    let reexports = build_reexports(&files, &by_symbol);
+    let links = build_links(&files, &by_symbol, &reexports);
     let mut global_symbols = by_symbol
         .into_iter()
         .map(|((name, kind), paths)| GlobalSymbol {
@@ -42,6 +50,7 @@ pub fn build_project_memory(files: &[FileMemory]) -> ProjectMemory {
             .len()
             .cmp(&a.defined_in.len())
             .then_with(|| a.name.cmp(&b.name))
+            .then_with(|| a.kind.cmp(&b.kind))
     });
     if global_symbols.len() > MAX_GLOBAL_SYMBOLS {
         global_symbols.truncate(MAX_GLOBAL_SYMBOLS);
@@ -52,10 +61,11 @@ pub fn build_project_memory(files: &[FileMemory]) -> ProjectMemory {
     ProjectMemory {
         file_count: files.len(),
         unique_symbol_count,
-        files: files.to_vec(),
+        files,
         global_symbols,
         open_items,
         links,
+        reexports,
     }
 }
 
@@ -100,6 +110,7 @@ fn build_open_items(
 fn build_links(
     files: &[FileMemory],
     by_symbol: &BTreeMap<(String, String), BTreeSet<String>>,
+    reexports: &[ReExport],
 ) -> Vec<CrossFileLink> {
     let mut by_name: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
     for ((name, _kind), locations) in by_symbol {
@@ -146,11 +157,33 @@ fn build_links(
         }
     }
 
+    for reexport in reexports {
+        for to_file in &reexport.defined_in {
+            let key = (
+                reexport.exported_from.clone(),
+                to_file.clone(),
+                reexport.symbol.clone(),
+                "reexport".to_string(),
+            );
+            if !seen.insert(key) {
+                continue;
+            }
+
+            links.push(CrossFileLink {
+                from_file: reexport.exported_from.clone(),
+                to_file: to_file.clone(),
+                symbol: reexport.symbol.clone(),
+                reason: "reexport".to_string(),
+            });
+        }
+    }
+
     links.sort_by(|a, b| {
         a.from_file
             .cmp(&b.from_file)
             .then_with(|| a.symbol.cmp(&b.symbol))
             .then_with(|| a.to_file.cmp(&b.to_file))
+            .then_with(|| a.reason.cmp(&b.reason))
     });
     if links.len() > MAX_PROJECT_LINKS {
         links.truncate(MAX_PROJECT_LINKS);
@@ -158,6 +191,178 @@ fn build_links(
     links
 }
 
+/// Resolve Rust `pub use` items to the files that define the re-exported symbols.
+///
+/// When a name is defined in several files, definitions whose file or directory matches a
+/// segment of the `use` path win, then those next to the re-exporting file. Glob re-exports
+/// resolve to the module file itself.
+fn build_reexports(
+    files: &[FileMemory],
+    by_symbol: &BTreeMap<(String, String), BTreeSet<String>>,
+) -> Vec<ReExport> {
+    let mut by_name: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    for ((name, _kind), locations) in by_symbol {
+        by_name
+            .entry(name.as_str())
+            .or_default()
+            .extend(locations.iter().map(String::as_str));
+    }
+    let all_files = files.iter().map(|f| f.path.as_str()).collect::<Vec<_>>();
+
+    let mut out = Vec::new();
+    for file in files.iter().filter(|f| !f.reexports.is_empty()) {
+        let exporter_dir = Path::new(&file.path).parent().unwrap_or(Path::new(""));
+
+        for statement in &file.reexports {
+            let tree = statement.strip_prefix("use ").unwrap_or(statement);
+            for path in expand_use_tree(tree) {
+                let (path, alias) = match path.split_once(" as ") {
+                    Some((path, alias)) => (path.trim().to_string(), Some(alias.trim())),
+                    None => (path, None),
+                };
+                let segments = path
+                    .split("::")
+                    .map(str::trim)
+                    .filter(|seg| !matches!(*seg, "" | "crate" | "self" | "super"))
+                    .collect::<Vec<_>>();
+                let Some((leaf, modules)) = segments.split_last() else {
+                    continue;
+                };
+
+                let (symbol, candidates) = if *leaf == "*" {
+                    let Some(module) = modules.last() else {
+                        continue;
+                    };
+                    let module_files = all_files
+                        .iter()
+                        .copied()
+                        .filter(|f| is_module_file(f, module))
+                        .collect::<BTreeSet<_>>();
+                    (format!("{module}::*"), module_files)
+                } else {
+                    let Some(definitions) = by_name.get(leaf) else {
+                        continue;
+                    };
+                    (
+                        alias.unwrap_or(leaf).to_string(),
+                        definitions.iter().copied().collect(),
+                    )
+                };
+
+                let candidates = candidates
+                    .into_iter()
+                    .filter(|f| *f != file.path)
+                    .collect::<Vec<_>>();
+                let defined_in = narrow_definitions(&candidates, modules, exporter_dir);
+                if defined_in.is_empty() {
+                    continue;
+                }
+
+                out.push(ReExport {
+                    symbol,
+                    exported_from: file.path.clone(),
+                    defined_in,
+                });
+            }
+        }
+    }
+
+    out.sort_by(|a, b| {
+        a.exported_from
+            .cmp(&b.exported_from)
+            .then_with(|| a.symbol.cmp(&b.symbol))
+            .then_with(|| a.defined_in.cmp(&b.defined_in))
+    });
+    out
+}
+
+fn narrow_definitions(candidates: &[&str], modules: &[&str], exporter_dir: &Path) -> Vec<String> {
+    let by_module = candidates
+        .iter()
+        .copied()
+        .filter(|f| modules.iter().any(|module| path_mentions_module(f, module)))
+        .collect::<Vec<_>>();
+    let pool = if by_module.is_empty() {
+        candidates.to_vec()
+    } else {
+        by_module
+    };
+
+    let nearby = pool
+        .iter()
+        .copied()
+        .filter(|f| Path::new(f).starts_with(exporter_dir))
+        .collect::<Vec<_>>();
+    let chosen = if nearby.is_empty() { pool } else { nearby };
+
+    chosen.into_iter().map(str::to_string).collect()
+}
+
+fn is_module_file(file: &str, module: &str) -> bool {
+    let path = Path::new(file);
+    match path.file_stem().and_then(|stem| stem.to_str()) {
+        Some("mod") => path
+            .parent()
+            .and_then(Path::file_name)
+            .is_some_and(|dir| dir == module),
+        Some(stem) => stem == module,
+        None => false,
+    }
+}
+
+fn path_mentions_module(file: &str, module: &str) -> bool {
+    let path = Path::new(file).with_extension("");
+    path.components()
+        .any(|component| component.as_os_str() == module)
+}
+
+/// Flatten a `use` tree such as `a::{b::C, D as E}` into `a::b::C` and `a::D as E`.
+fn expand_use_tree(tree: &str) -> Vec<String> {
+    let tree = tree.trim();
+    let Some(open) = tree.find('{') else {
+        return vec![tree.to_string()];
+    };
+    let Some(close) = tree.rfind('}') else {
+        return vec![tree.to_string()];
+    };
+    if close < open {
+        return vec![tree.to_string()];
+    }
+
+    let prefix = &tree[..open];
+    let inner = &tree[open + 1..close];
+    let mut out = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0usize;
+    for (idx, ch) in inner.char_indices() {
+        match ch {
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                push_use_items(&mut out, prefix, &inner[start..idx]);
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    push_use_items(&mut out, prefix, &inner[start..]);
+    out
+}
+
+fn push_use_items(out: &mut Vec<String>, prefix: &str, item: &str) {
+    let item = item.trim();
+    if item.is_empty() {
+        return;
+    }
+    if item == "self" {
+        out.push(prefix.trim_end_matches("::").to_string());
+        return;
+    }
+    for path in expand_use_tree(item) {
+        out.push(format!("{prefix}{path}"));
+    }
+}
+
 pub(crate) fn import_symbol_candidates(import: &str, language: &str) -> Vec<String> {
     match language {
         "rust" => rust_import_candidates(import),
@@ -0,0 +1,395 @@
+use super::{ConfidenceLevel, FieldInfo, SymbolDetails, SymbolFact, VariantInfo};
+
+/// Words that open a table-level constraint rather than a column definition.
+const SQL_CONSTRAINT_KEYWORDS: &[&str] = &[
+    "CONSTRAINT",
+    "PRIMARY",
+    "FOREIGN",
+    "UNIQUE",
+    "CHECK",
+    "INDEX",
+    "KEY",
+    "EXCLUDE",
+    "LIKE",
+    "PERIOD",
+    "FULLTEXT",
+    "SPATIAL",
+];
+
+/// Words that end the type part of a column definition.
+const SQL_COLUMN_MODIFIERS: &[&str] = &[
+    "NOT",
+    "NULL",
+    "PRIMARY",
+    "UNIQUE",
+    "DEFAULT",
+    "REFERENCES",
+    "CHECK",
+    "CONSTRAINT",
+    "GENERATED",
+    "COLLATE",
+    "AUTO_INCREMENT",
+    "AUTOINCREMENT",
+    "IDENTITY",
+    "COMMENT",
+    "ON",
+];
+
+/// Languages handled by [`schema_symbols`] instead of the line-based symbol scanner.
+pub(crate) fn is_schema_language(language: &str) -> bool {
+    matches!(language, "sql" | "prisma")
+}
+
+/// Extract data-model definitions from a `.sql` or `schema.prisma` file.
+///
+/// Tables (`CREATE TABLE`, `ALTER TABLE … ADD COLUMN`, Prisma `model`/`view` blocks) become
+/// symbols of kind `table` with their columns in `details.fields`; Prisma enums become `enum`
+/// symbols. Anything else in the file is ignored.
+pub(crate) fn schema_symbols(language: &str, source: &str) -> Vec<SymbolFact> {
+    match language {
+        "sql" => sql_symbols(source),
+        "prisma" => prisma_symbols(source),
+        _ => Vec::new(),
+    }
+}
+
+fn sql_symbols(source: &str) -> Vec<SymbolFact> {
+    let text = strip_sql_comments(source);
+    let mut symbols = Vec::new();
+    let mut line_no = 1usize;
+    let mut consumed = 0usize;
+
+    for (offset, statement) in split_sql_statements(&text) {
+        let leading = statement.len() - statement.trim_start().len();
+        let start = offset + leading;
+        line_no += text[consumed..start].matches('\n').count();
+        consumed = start;
+
+        let statement = statement.trim();
+        if let Some(symbol) = create_table(statement, line_no) {
+            symbols.push(symbol);
+        } else if let Some(symbol) = alter_table(statement, line_no) {
+            symbols.push(symbol);
+        }
+    }
+
+    symbols
+}
+
+/// Drop `--` line comments and `/* */` block comments, keeping line breaks so offsets still map
+/// to source lines.
+fn strip_sql_comments(source: &str) -> String {
+    let mut out = String::with_capacity(source.len());
+    let mut in_block = false;
+
+    for line in source.lines() {
+        let mut rest = line;
+        loop {
+            if in_block {
+                match rest.find("*/") {
+                    Some(end) => {
+                        rest = &rest[end + 2..];
+                        in_block = false;
+                    }
+                    None => break,
+                }
+            } else {
+                let block = rest.find("/*");
+                let dashes = rest.find("--");
+                match (block, dashes) {
+                    (Some(b), d) if d.is_none_or(|d| b < d) => {
+                        out.push_str(&rest[..b]);
+                        rest = &rest[b + 2..];
+                        in_block = true;
+                    }
+                    (_, Some(d)) => {
+                        out.push_str(&rest[..d]);
+                        break;
+                    }
+                    _ => {
+                        out.push_str(rest);
+                        break;
+                    }
+                }
+            }
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Split on `;` outside quoted strings and `$$` bodies, returning each statement with its
+/// byte offset.
+fn split_sql_statements(text: &str) -> Vec<(usize, &str)> {
+    let mut statements = Vec::new();
+    let mut start = 0usize;
+    let mut in_quote = false;
+    let mut in_dollar = false;
+    let bytes = text.as_bytes();
+    let mut idx = 0usize;
+
+    while idx < bytes.len() {
+        match bytes[idx] {
+            b'\'' if !in_dollar => in_quote = !in_quote,
+            b'$' if !in_quote && bytes.get(idx + 1) == Some(&b'$') => {
+                in_dollar = !in_dollar;
+                idx += 1;
+            }
+            b';' if !in_quote && !in_dollar => {
+                statements.push((start, &text[start..idx]));
+                start = idx + 1;
+            }
+            _ => {}
+        }
+        idx += 1;
+    }
+    if !text[start..].trim().is_empty() {
+        statements.push((start, &text[start..]));
+    }
+
+    statements
+}
+
+fn create_table(statement: &str, line_no: usize) -> Option<SymbolFact> {
+    let open = statement.find('(')?;
+    let head = statement[..open].split_whitespace().collect::<Vec<_>>();
+    if !head
+        .first()
+        .is_some_and(|word| word.eq_ignore_ascii_case("create"))
+    {
+        return None;
+    }
+    let table_pos = head
+        .iter()
+        .position(|word| word.eq_ignore_ascii_case("table"))?;
+    let name = head[table_pos + 1..]
+        .iter()
+        .rfind(|word| !is_sql_keyword(word, &["IF", "NOT", "EXISTS"]))
+        .map(|word| unquote_identifier(word))?;
+
+    let body = balanced_body(&statement[open..])?;
+    let fields = split_top_level(body, ',')
+        .into_iter()
+        .filter_map(sql_column)
+        .collect::<Vec<_>>();
+
+    Some(table_symbol(
+        name,
+        line_no,
+        ConfidenceLevel::High,
+        head.join(" "),
+        fields,
+    ))
+}
+
+fn alter_table(statement: &str, line_no: usize) -> Option<SymbolFact> {
+    let words = statement.split_whitespace().collect::<Vec<_>>();
+    if words.len() < 4
+        || !words[0].eq_ignore_ascii_case("alter")
+        || !words[1].eq_ignore_ascii_case("table")
+    {
+        return None;
+    }
+    let mut pos = 2;
+    while words
+        .get(pos)
+        .is_some_and(|word| is_sql_keyword(word, &["IF", "EXISTS", "ONLY"]))
+    {
+        pos += 1;
+    }
+    let raw_name = words.get(pos)?;
+    let name = unquote_identifier(raw_name);
+
+    let actions_start = statement.find(raw_name)? + raw_name.len();
+    let fields = split_top_level(&statement[actions_start..], ',')
+        .into_iter()
+        .filter_map(|action| {
+            let action = action.trim();
+            let rest = strip_keyword(action, "ADD")?;
+            let rest = strip_keyword(rest, "COLUMN").unwrap_or(rest);
+            let rest = strip_keyword(rest, "IF")
+                .and_then(|rest| strip_keyword(rest, "NOT"))
+                .and_then(|rest| strip_keyword(rest, "EXISTS"))
+                .unwrap_or(rest);
+            sql_column(rest)
+        })
+        .collect::<Vec<_>>();
+    if fields.is_empty() {
+        return None;
+    }
+
+    Some(table_symbol(
+        name.clone(),
+        line_no,
+        ConfidenceLevel::Medium,
+        format!("ALTER TABLE {name}"),
+        fields,
+    ))
+}
+
+fn sql_column(definition: &str) -> Option<FieldInfo> {
+    let mut words = definition.split_whitespace();
+    let first = words.next()?;
+    if is_sql_keyword(first, SQL_CONSTRAINT_KEYWORDS) {
+        return None;
+    }
+
+    let type_name = words
+        .take_while(|word| !is_sql_keyword(word, SQL_COLUMN_MODIFIERS))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    Some(FieldInfo {
+        name: unquote_identifier(first),
+        type_name,
+        visibility: String::new(),
+    })
+}
+
+fn prisma_symbols(source: &str) -> Vec<SymbolFact> {
+    let mut symbols = Vec::new();
+    let mut current: Option<SymbolFact> = None;
+
+    for (idx, raw_line) in source.lines().enumerate() {
+        let line = raw_line
+            .split_once("//")
+            .map(|(left, _)| left)
+            .unwrap_or(raw_line)
+            .trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(symbol) = current.as_mut() {
+            if line.starts_with('}') {
+                symbols.extend(current.take());
+                continue;
+            }
+            if line.starts_with("@@") {
+                continue;
+            }
+            let mut words = line.split_whitespace();
+            let Some(name) = words.next() else {
+                continue;
+            };
+            if symbol.kind == "enum" {
+                symbol.details.variants.push(VariantInfo {
+                    name: name.to_string(),
+                    data: String::new(),
+                });
+            } else {
+                symbol.details.fields.push(FieldInfo {
+                    name: name.to_string(),
+                    type_name: words.next().unwrap_or_default().to_string(),
+                    visibility: String::new(),
+                });
+            }
+            continue;
+        }
+
+        let mut words = line.split_whitespace();
+        let (Some(keyword), Some(name)) = (words.next(), words.next()) else {
+            continue;
+        };
+        if !line.ends_with('{') {
+            continue;
+        }
+        let name = name.trim_end_matches('{').to_string();
+        let kind = match keyword {
+            "model" | "view" => "table",
+            "enum" => "enum",
+            _ => continue,
+        };
+        let mut symbol = table_symbol(
+            name,
+            idx + 1,
+            ConfidenceLevel::High,
+            line.trim_end_matches('{').trim_end().to_string(),
+            Vec::new(),
+        );
+        symbol.kind = kind.to_string();
+        current = Some(symbol);
+    }
+
+    symbols
+}
+
+fn table_symbol(
+    name: String,
+    line: usize,
+    confidence: ConfidenceLevel,
+    signature: String,
+    fields: Vec<FieldInfo>,
+) -> SymbolFact {
+    SymbolFact {
+        name,
+        kind: "table".to_string(),
+        line,
+        confidence,
+        details: SymbolDetails {
+            signature,
+            fields,
+            ..SymbolDetails::default()
+        },
+        is_test: false,
+        metrics: None,
+    }
+}
+
+/// Contents between the leading `(` of `text` and its matching `)`.
+fn balanced_body(text: &str) -> Option<&str> {
+    let mut depth = 0i64;
+    for (idx, ch) in text.char_indices() {
+        match ch {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(&text[1..idx]);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn split_top_level(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0i64;
+    let mut start = 0usize;
+    for (idx, ch) in text.char_indices() {
+        match ch {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            c if c == separator && depth == 0 => {
+                parts.push(&text[start..idx]);
+                start = idx + ch.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+fn strip_keyword<'a>(text: &'a str, keyword: &str) -> Option<&'a str> {
+    let (word, rest) = text.split_once(char::is_whitespace)?;
+    word.eq_ignore_ascii_case(keyword)
+        .then(|| rest.trim_start())
+}
+
+fn is_sql_keyword(word: &str, keywords: &[&str]) -> bool {
+    let word = word.trim_end_matches(',');
+    keywords
+        .iter()
+        .any(|keyword| word.eq_ignore_ascii_case(keyword))
+}
+
+fn unquote_identifier(word: &str) -> String {
+    word.split('.')
+        .map(|part| part.trim_matches(|c| matches!(c, '"' | '`' | '[' | ']')))
+        .collect::<Vec<_>>()
+        .join(".")
+}
@@ -18,6 +18,17 @@ pub struct SymbolFact {
     pub confidence: ConfidenceLevel,
     #[serde(default)]
     pub details: SymbolDetails,
+    #[serde(default)]
+    pub is_test: bool,
+    #[serde(default)]
+    pub metrics: Option<FunctionMetrics>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct FunctionMetrics {
+    pub line_count: usize,
+    pub parameter_count: usize,
+    pub complexity: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -27,6 +38,8 @@ pub struct SymbolDetails {
     #[serde(default)]
     pub modifiers: Vec<String>,
     #[serde(default)]
+    pub attributes: Vec<String>,
+    #[serde(default)]
     pub signature: String,
     #[serde(default)]
     pub fields: Vec<FieldInfo>,
@@ -61,15 +74,28 @@ pub struct ParameterInfo {
     pub type_name: String,
 }
 
+/// Content hash of one symbol's normalized body, used to recognize it after it moves.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SymbolFingerprint {
+    pub name: String,
+    pub kind: String,
+    pub line: usize,
+    pub fingerprint: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileMemory {
     pub path: String,
     #[serde(default)]
     pub language: String,
+    #[serde(default)]
+    pub is_test_file: bool,
     pub symbol_count: usize,
     pub import_count: usize,
     pub symbols: Vec<SymbolFact>,
     pub imports: Vec<String>,
+    #[serde(default)]
+    pub reexports: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -95,6 +121,13 @@ pub struct CrossFileLink {
     pub reason: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReExport {
+    pub symbol: String,
+    pub exported_from: String,
+    pub defined_in: Vec<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProjectMemory {
     pub file_count: usize,
@@ -105,4 +138,6 @@ pub struct ProjectMemory {
     pub open_items: Vec<OpenItem>,
     #[serde(default)]
     pub links: Vec<CrossFileLink>,
+    #[serde(default)]
+    pub reexports: Vec<ReExport>,
 }
@@ -0,0 +1,300 @@
+//! Moving a docs root without regenerating it.
+//!
+//! Projects are copied to the new root first. Absolute paths under the old root in their JSON
+//! artifacts are rewritten, and each copy is checked against the original before the original is
+//! removed, so a failed migration leaves the old root as it was.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+use serde_json::Value;
+use tracing::{info, warn};
+
+use crate::{
+    error::{PlainSightError, Result},
+    memory::ProjectMemory,
+    project_manager::{
+        MetaCache, ORG_INDEX_FILE, ProjectManager, SOURCE_ROOT_FILE, SourceRootRecord,
+    },
+};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationReport {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    pub projects: Vec<MigratedProject>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MigratedProject {
+    pub name: String,
+    pub files: usize,
+    /// Absolute paths under the old root rewritten to the new one.
+    pub paths_rewritten: usize,
+    /// Files listed in `.meta.json` without a `summary.md`; they were like that before the move
+    /// and are regenerated by the next run.
+    pub missing_summaries: Vec<String>,
+}
+
+/// Move every project under `from` to `to`, with `.org_index.json` when present.
+///
+/// `to` must not exist yet or be empty, and neither root may contain the other. Files under
+/// `from` that belong to no project are left where they are; `from` is removed once empty.
+pub fn migrate_docs(from: &Path, to: &Path) -> Result<MigrationReport> {
+    let from = from
+        .canonicalize()
+        .map_err(|e| io_error("resolving", from, e))?;
+    let names = ProjectManager::new(&from).project_names()?;
+    if names.is_empty() {
+        return Err(PlainSightError::InvalidState(format!(
+            "no projects found under '{}'",
+            from.display()
+        )));
+    }
+    let to_is_empty = !to.exists()
+        || fs::read_dir(to)
+            .map_err(|e| io_error("listing", to, e))?
+            .next()
+            .is_none();
+    if !to_is_empty {
+        return Err(PlainSightError::InvalidState(format!(
+            "target docs root '{}' is not empty",
+            to.display()
+        )));
+    }
+    let to = resolve_new_path(to)?;
+    if to.starts_with(&from) || from.starts_with(&to) {
+        return Err(PlainSightError::InvalidState(format!(
+            "docs roots '{}' and '{}' overlap",
+            from.display(),
+            to.display()
+        )));
+    }
+    fs::create_dir_all(&to).map_err(|e| io_error("creating", &to, e))?;
+
+    let mut projects = Vec::with_capacity(names.len());
+    for name in &names {
+        match copy_project(&from, &to, name) {
+            Ok(project) => projects.push(project),
+            Err(e) => {
+                // Leave the target as it was found; the old root has not been touched yet.
+                for copied in names.iter().take(projects.len() + 1) {
+                    let _ = fs::remove_dir_all(to.join(copied));
+                }
+                return Err(e);
+            }
+        }
+    }
+    let org_index = from.join(ORG_INDEX_FILE);
+    if org_index.is_file() {
+        let target = to.join(ORG_INDEX_FILE);
+        fs::copy(&org_index, &target).map_err(|e| io_error("copying", &org_index, e))?;
+        rewrite_json_paths(&target, &from, &to)?;
+        fs::remove_file(&org_index).map_err(|e| io_error("removing", &org_index, e))?;
+    }
+
+    for name in &names {
+        let old = from.join(name);
+        fs::remove_dir_all(&old).map_err(|e| io_error("removing", &old, e))?;
+    }
+    if fs::read_dir(&from)
+        .map(|mut entries| entries.next().is_none())
+        .unwrap_or(false)
+    {
+        let _ = fs::remove_dir(&from);
+    }
+
+    info!(
+        from = %from.display(),
+        to = %to.display(),
+        project_count = projects.len(),
+        "docs_root_migrated"
+    );
+    Ok(MigrationReport { from, to, projects })
+}
+
+/// Copy one project, rewrite its JSON artifacts and check the copy.
+fn copy_project(from: &Path, to: &Path, name: &str) -> Result<MigratedProject> {
+    let source = from.join(name);
+    let target = to.join(name);
+    let files = copy_dir(&source, &target)?;
+
+    let mut paths_rewritten = 0;
+    for entry in fs::read_dir(&target).map_err(|e| io_error("listing", &target, e))? {
+        let path = entry.map_err(|e| io_error("listing", &target, e))?.path();
+        if path.is_file() && is_json(&path) {
+            paths_rewritten += rewrite_json_paths(&path, from, to)?;
+        }
+    }
+
+    let missing_summaries = validate_project(&source, &target)?;
+    if !missing_summaries.is_empty() {
+        warn!(
+            project = %name,
+            count = missing_summaries.len(),
+            "documented files without a summary"
+        );
+    }
+    info!(project = %name, files, paths_rewritten, "project_migrated");
+    Ok(MigratedProject {
+        name: name.to_string(),
+        files,
+        paths_rewritten,
+        missing_summaries,
+    })
+}
+
+/// Copy `source` into `target` recursively; returns the number of files copied.
+fn copy_dir(source: &Path, target: &Path) -> Result<usize> {
+    fs::create_dir_all(target).map_err(|e| io_error("creating", target, e))?;
+    let mut files = 0;
+    for entry in fs::read_dir(source).map_err(|e| io_error("listing", source, e))? {
+        let entry = entry.map_err(|e| io_error("listing", source, e))?;
+        let path = entry.path();
+        let destination = target.join(entry.file_name());
+        if path.is_dir() {
+            files += copy_dir(&path, &destination)?;
+        } else {
+            fs::copy(&path, &destination).map_err(|e| io_error("copying", &path, e))?;
+            files += 1;
+        }
+    }
+    Ok(files)
+}
+
+/// Replace `from` with `to` at the start of every string in the JSON file at `path` that is a
+/// path under `from`. The file is only rewritten when something changed.
+fn rewrite_json_paths(path: &Path, from: &Path, to: &Path) -> Result<usize> {
+    let content = fs::read_to_string(path).map_err(|e| io_error("reading", path, e))?;
+    let mut value: Value = serde_json::from_str(&content).map_err(|e| {
+        PlainSightError::InvalidState(format!("failed to parse '{}': {e}", path.display()))
+    })?;
+    let count = rewrite_value(&mut value, from, to);
+    if count > 0 {
+        let content = serde_json::to_string_pretty(&value).map_err(|e| {
+            PlainSightError::InvalidState(format!("serializing '{}': {e}", path.display()))
+        })?;
+        fs::write(path, content).map_err(|e| io_error("writing", path, e))?;
+    }
+    Ok(count)
+}
+
+fn rewrite_value(value: &mut Value, from: &Path, to: &Path) -> usize {
+    match value {
+        Value::String(text) => match Path::new(text.as_str()).strip_prefix(from) {
+            Ok(rest) => {
+                *text = to.join(rest).display().to_string();
+                1
+            }
+            Err(_) => 0,
+        },
+        Value::Array(items) => items
+            .iter_mut()
+            .map(|item| rewrite_value(item, from, to))
+            .sum(),
+        Value::Object(fields) => fields
+            .values_mut()
+            .map(|field| rewrite_value(field, from, to))
+            .sum(),
+        _ => 0,
+    }
+}
+
+/// Check that the copy at `target` holds every file of `source` and that its artifacts load.
+/// Returns the documented files that have no summary.
+fn validate_project(source: &Path, target: &Path) -> Result<Vec<String>> {
+    for relative in list_files(source, Path::new(""))? {
+        let copied = target.join(&relative);
+        let original_len = fs::metadata(source.join(&relative))
+            .map_err(|e| io_error("checking", &source.join(&relative), e))?
+            .len();
+        match fs::metadata(&copied) {
+            Ok(metadata) if is_json(&relative) || metadata.len() == original_len => {}
+            _ => {
+                return Err(PlainSightError::InvalidState(format!(
+                    "migrated copy of '{}' is missing or incomplete",
+                    source.join(&relative).display()
+                )));
+            }
+        }
+    }
+
+    let meta: Option<MetaCache> = load_artifact(&target.join(".meta.json"))?;
+    load_artifact::<ProjectMemory>(&target.join(".memory.json"))?;
+    load_artifact::<SourceRootRecord>(&target.join(SOURCE_ROOT_FILE))?;
+
+    Ok(meta
+        .map(|meta| {
+            meta.files
+                .into_keys()
+                .filter(|file| !target.join("files").join(file).join("summary.md").is_file())
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+/// Parse the artifact at `path`, or `None` when the project has none.
+fn load_artifact<T: serde::de::DeserializeOwned>(path: &Path) -> Result<Option<T>> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(path).map_err(|e| io_error("reading", path, e))?;
+    serde_json::from_str(&content).map(Some).map_err(|e| {
+        PlainSightError::InvalidState(format!(
+            "migrated artifact '{}' does not load: {e}",
+            path.display()
+        ))
+    })
+}
+
+/// Files under `dir`, relative to the directory listing started from.
+fn list_files(dir: &Path, relative: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let current = dir.join(relative);
+    for entry in fs::read_dir(&current).map_err(|e| io_error("listing", &current, e))? {
+        let entry = entry.map_err(|e| io_error("listing", &current, e))?;
+        let path = relative.join(entry.file_name());
+        if entry.path().is_dir() {
+            files.extend(list_files(dir, &path)?);
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+/// `path` with its deepest existing ancestor canonicalized; the rest need not exist yet.
+fn resolve_new_path(path: &Path) -> Result<PathBuf> {
+    let absolute = std::path::absolute(path).map_err(|e| io_error("resolving", path, e))?;
+    let mut existing = absolute.as_path();
+    let mut missing = Vec::new();
+    loop {
+        match existing.canonicalize() {
+            Ok(resolved) => {
+                return Ok(missing
+                    .into_iter()
+                    .rev()
+                    .fold(resolved, |path, part| path.join(part)));
+            }
+            Err(_) => match (existing.parent(), existing.file_name()) {
+                (Some(parent), Some(name)) => {
+                    missing.push(name);
+                    existing = parent;
+                }
+                _ => return Ok(absolute),
+            },
+        }
+    }
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension == "json")
+}
+
+fn io_error(action: &str, path: &Path, source: std::io::Error) -> PlainSightError {
+    PlainSightError::io(format!("{action} '{}'", path.display()), source)
+}
@@ -0,0 +1,178 @@
+//! Opt-in statistics of how each model fares at each task, kept at the docs root.
+//!
+//! Only model names, task names, outcome counts and latencies are recorded: no project names,
+//! file paths, prompts or replies. The file is plain JSON that can be read before anything is
+//! shared, and it is only sent anywhere when `ModelStatsConfig::upload_url` is set.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::{
+    config::ModelStatsConfig,
+    error::{PlainSightError, Result},
+    ollama::{RequestOutcome, TaskOutcome},
+    project_manager::ProjectManager,
+};
+
+/// Bumped when the layout of `.model_stats.json` changes.
+const MODEL_STATS_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelStats {
+    pub version: u32,
+    /// Runs that sent at least one task request.
+    pub runs: u64,
+    /// By model name, then `Task::name`.
+    pub models: BTreeMap<String, BTreeMap<String, TaskStats>>,
+}
+
+impl Default for ModelStats {
+    fn default() -> Self {
+        Self {
+            version: MODEL_STATS_VERSION,
+            runs: 0,
+            models: BTreeMap::new(),
+        }
+    }
+}
+
+/// Task requests of one model, counted after retries.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct TaskStats {
+    pub requests: u64,
+    pub succeeded: u64,
+    pub refused: u64,
+    /// Replies that were JSON instead of Markdown, or structured output missing its sections.
+    pub invalid_output: u64,
+    pub failed: u64,
+    pub total_latency_ms: u64,
+}
+
+impl TaskStats {
+    fn add(&mut self, outcome: &TaskOutcome) {
+        self.requests += 1;
+        self.total_latency_ms += outcome.duration_ms;
+        match outcome.outcome {
+            RequestOutcome::Succeeded => self.succeeded += 1,
+            RequestOutcome::Refused => self.refused += 1,
+            RequestOutcome::InvalidOutput => self.invalid_output += 1,
+            RequestOutcome::Failed => self.failed += 1,
+        }
+    }
+
+    pub fn success_rate(&self) -> f64 {
+        self.share(self.succeeded)
+    }
+
+    pub fn refusal_rate(&self) -> f64 {
+        self.share(self.refused)
+    }
+
+    /// Share of replies that passed the output checks, refusals aside.
+    pub fn validation_pass_rate(&self) -> f64 {
+        1.0 - self.share(self.invalid_output)
+    }
+
+    pub fn mean_latency_ms(&self) -> u64 {
+        self.total_latency_ms
+            .checked_div(self.requests)
+            .unwrap_or(0)
+    }
+
+    fn share(&self, count: u64) -> f64 {
+        if self.requests == 0 {
+            0.0
+        } else {
+            count as f64 / self.requests as f64
+        }
+    }
+}
+
+/// The statistics collected under the docs root so far; empty when there are none.
+pub fn load_model_stats(manager: &ProjectManager) -> Result<ModelStats> {
+    let path = manager.model_stats_path();
+    if !manager.store().exists(&path) {
+        return Ok(ModelStats::default());
+    }
+    let content = manager
+        .store()
+        .read_to_string(&path)
+        .map_err(|e| PlainSightError::io(format!("reading model stats '{}'", path.display()), e))?;
+    serde_json::from_str(&content).map_err(|e| {
+        PlainSightError::InvalidState(format!(
+            "failed to parse model stats '{}': {e}",
+            path.display()
+        ))
+    })
+}
+
+/// Add a run's `outcomes` to `.model_stats.json` and upload the file when configured. Failures
+/// are logged; statistics never fail a run.
+pub(crate) async fn record_run(
+    manager: &ProjectManager,
+    config: &ModelStatsConfig,
+    outcomes: &[TaskOutcome],
+) {
+    if outcomes.is_empty() {
+        return;
+    }
+    let stats = match add_outcomes(manager, outcomes) {
+        Ok(stats) => stats,
+        Err(e) => {
+            warn!(error = %e, "failed recording model stats");
+            return;
+        }
+    };
+    if let Some(url) = &config.upload_url {
+        match upload(url, &stats).await {
+            Ok(()) => info!(url = %url, "model_stats_uploaded"),
+            Err(e) => warn!(url = %url, error = %e, "failed uploading model stats"),
+        }
+    }
+}
+
+fn add_outcomes(manager: &ProjectManager, outcomes: &[TaskOutcome]) -> Result<ModelStats> {
+    let mut stats = load_model_stats(manager)?;
+    stats.version = MODEL_STATS_VERSION;
+    stats.runs += 1;
+    for outcome in outcomes {
+        stats
+            .models
+            .entry(outcome.model.clone())
+            .or_default()
+            .entry(outcome.task.clone())
+            .or_default()
+            .add(outcome);
+    }
+
+    let path = manager.model_stats_path();
+    let content = serde_json::to_string_pretty(&stats)
+        .map_err(|e| PlainSightError::InvalidState(format!("serializing model stats: {e}")))?;
+    manager
+        .store()
+        .write(&path, content.as_bytes())
+        .map_err(|e| PlainSightError::io(format!("writing model stats '{}'", path.display()), e))?;
+    info!(
+        model_stats_path = %path.display(),
+        requests = outcomes.len(),
+        "model_stats_written"
+    );
+    Ok(stats)
+}
+
+async fn upload(url: &str, stats: &ModelStats) -> std::result::Result<(), String> {
+    let response = reqwest::Client::new()
+        .post(url)
+        .json(stats)
+        .send()
+        .await
+        .map_err(|e| format!("request failed: {e}"))?;
+    let status = response.status();
+    if !status.is_success() {
+        let detail = response.text().await.unwrap_or_default();
+        return Err(format!("server returned {status}: {}", detail.trim()));
+    }
+    Ok(())
+}
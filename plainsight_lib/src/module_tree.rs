@@ -0,0 +1,352 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::rust_source;
+
+const MAX_MODULE_DEPTH: usize = 32;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModDeclaration {
+    pub name: String,
+    pub visibility: String,
+    pub inline: bool,
+    pub line: usize,
+    /// Declarations inside an inline module's body.
+    #[serde(default)]
+    pub children: Vec<ModDeclaration>,
+}
+
+impl ModDeclaration {
+    /// Move this declaration and those nested in it `offset` lines down.
+    pub fn shift_lines(&mut self, offset: usize) {
+        self.line += offset;
+        for child in &mut self.children {
+            child.shift_lines(offset);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModuleNode {
+    pub name: String,
+    pub module_path: String,
+    pub file: Option<String>,
+    pub visibility: String,
+    pub inline: bool,
+    pub children: Vec<ModuleNode>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CrateModuleTree {
+    pub crate_root: String,
+    pub root: ModuleNode,
+}
+
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct ModuleTreeIndex {
+    pub crates: Vec<CrateModuleTree>,
+    pub unlinked_files: Vec<String>,
+    pub missing_modules: Vec<String>,
+}
+
+/// The `mod` items of a Rust file, with those declared inside an inline module nested under it.
+pub fn parse_mod_declarations(source: &str) -> Vec<ModDeclaration> {
+    let mut out = Vec::new();
+    // Inline modules whose body is still open, innermost last, with the brace depth inside it.
+    let mut open: Vec<(ModDeclaration, usize)> = Vec::new();
+    let mut depth = 0usize;
+
+    let code = rust_source::mask_comments_and_literals(source);
+    for (idx, line) in code.lines().enumerate() {
+        let line = line.trim();
+        if let Some(decl) = mod_declaration(line, idx + 1) {
+            if decl.inline {
+                open.push((decl, depth + 1));
+            } else {
+                push_declaration(&mut out, &mut open, decl);
+            }
+        }
+
+        for ch in line.chars() {
+            match ch {
+                '{' => depth += 1,
+                '}' => {
+                    depth = depth.saturating_sub(1);
+                    while open.last().is_some_and(|(_, body)| *body > depth)
+                        && let Some((decl, _)) = open.pop()
+                    {
+                        push_declaration(&mut out, &mut open, decl);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    while let Some((decl, _)) = open.pop() {
+        push_declaration(&mut out, &mut open, decl);
+    }
+    out
+}
+
+/// Add `decl` to the innermost inline module still open, or to `out` outside of any.
+fn push_declaration(
+    out: &mut Vec<ModDeclaration>,
+    open: &mut [(ModDeclaration, usize)],
+    decl: ModDeclaration,
+) {
+    match open.last_mut() {
+        Some((parent, _)) => parent.children.push(decl),
+        None => out.push(decl),
+    }
+}
+
+/// The `mod` item declared on `line`, if any.
+fn mod_declaration(line: &str, line_no: usize) -> Option<ModDeclaration> {
+    // Attributes may share a line with the declaration, e.g. `#[cfg(test)] mod tests {`.
+    let line = match line.rfind(']') {
+        Some(end) if line.starts_with("#[") => line[end + 1..].trim_start(),
+        _ => line,
+    };
+
+    let (visibility, rest) = split_visibility(line);
+    let rest = rest.strip_prefix("mod ")?.trim_start();
+    let name: String = rest
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '_')
+        .collect();
+    if name.is_empty() {
+        return None;
+    }
+
+    let tail = rest[name.len()..].trim_start();
+    let inline = if tail.starts_with(';') {
+        false
+    } else if tail.starts_with('{') || tail.is_empty() {
+        true
+    } else {
+        return None;
+    };
+
+    Some(ModDeclaration {
+        name,
+        visibility: visibility.to_string(),
+        inline,
+        line: line_no,
+        children: Vec::new(),
+    })
+}
+
+/// Reconstruct the module hierarchy of every Rust crate found in the project.
+///
+/// `declarations` maps each Rust file (relative to the project root) to the `mod` items it
+/// declares. Crate roots are `lib.rs`/`main.rs` files, binaries under `src/bin/`, integration
+/// tests, examples and benchmarks under `tests/`, `examples/` and `benches/`, and build
+/// scripts.
+pub fn build_module_trees(declarations: &BTreeMap<String, Vec<ModDeclaration>>) -> ModuleTreeIndex {
+    let mut index = ModuleTreeIndex::default();
+    let mut resolver = ModuleResolver {
+        declarations,
+        linked: BTreeSet::new(),
+        missing: Vec::new(),
+    };
+
+    for crate_root in declarations.keys().filter(|path| is_crate_root(path)) {
+        resolver.linked.insert(crate_root.clone());
+        let children = resolver.resolve_children(crate_root, "crate", 0);
+        index.crates.push(CrateModuleTree {
+            crate_root: crate_root.clone(),
+            root: ModuleNode {
+                name: "crate".to_string(),
+                module_path: "crate".to_string(),
+                file: Some(crate_root.clone()),
+                visibility: "pub".to_string(),
+                inline: false,
+                children,
+            },
+        });
+    }
+
+    index.unlinked_files = declarations
+        .keys()
+        .filter(|path| !resolver.linked.contains(*path))
+        .cloned()
+        .collect();
+    index.missing_modules = resolver.missing;
+
+    index
+}
+
+struct ModuleResolver<'a> {
+    declarations: &'a BTreeMap<String, Vec<ModDeclaration>>,
+    /// Files already placed in a tree.
+    linked: BTreeSet<String>,
+    missing: Vec<String>,
+}
+
+impl ModuleResolver<'_> {
+    fn resolve_children(&mut self, file: &str, module_path: &str, depth: usize) -> Vec<ModuleNode> {
+        let declarations = self.declarations;
+        let Some(decls) = declarations.get(file) else {
+            return Vec::new();
+        };
+
+        // Crate roots resolve child modules next to themselves, whatever their file name.
+        let children_dir = if depth == 0 {
+            Path::new(file)
+                .parent()
+                .unwrap_or_else(|| Path::new(""))
+                .to_path_buf()
+        } else {
+            module_children_dir(Path::new(file))
+        };
+        self.resolve_declarations(file, decls, &children_dir, module_path, depth)
+    }
+
+    /// Resolve `decls`, declared in `file`, whose file modules live in `children_dir`.
+    fn resolve_declarations(
+        &mut self,
+        file: &str,
+        decls: &[ModDeclaration],
+        children_dir: &Path,
+        module_path: &str,
+        depth: usize,
+    ) -> Vec<ModuleNode> {
+        if depth >= MAX_MODULE_DEPTH {
+            return Vec::new();
+        }
+        let mut children = Vec::with_capacity(decls.len());
+
+        for decl in decls {
+            let child_path = format!("{module_path}::{}", decl.name);
+
+            if decl.inline {
+                // File modules declared inside `mod name { ... }` live in `name/`, as they
+                // would for a `name.rs`.
+                let grandchildren = self.resolve_declarations(
+                    file,
+                    &decl.children,
+                    &children_dir.join(&decl.name),
+                    &child_path,
+                    depth + 1,
+                );
+                children.push(ModuleNode {
+                    name: decl.name.clone(),
+                    module_path: child_path,
+                    file: None,
+                    visibility: decl.visibility.clone(),
+                    inline: true,
+                    children: grandchildren,
+                });
+                continue;
+            }
+
+            let candidates = [
+                children_dir.join(format!("{}.rs", decl.name)),
+                children_dir.join(&decl.name).join("mod.rs"),
+            ];
+            let child_file = candidates
+                .iter()
+                .map(|candidate| normalize(candidate))
+                .find(|candidate| self.declarations.contains_key(candidate));
+
+            let grandchildren = match &child_file {
+                Some(child_file) if self.linked.insert(child_file.clone()) => {
+                    self.resolve_children(child_file, &child_path, depth + 1)
+                }
+                Some(_) => Vec::new(),
+                None => {
+                    self.missing
+                        .push(format!("{child_path} (declared in {file})"));
+                    Vec::new()
+                }
+            };
+
+            children.push(ModuleNode {
+                name: decl.name.clone(),
+                module_path: child_path,
+                file: child_file,
+                visibility: decl.visibility.clone(),
+                inline: false,
+                children: grandchildren,
+            });
+        }
+
+        children
+    }
+}
+
+fn is_crate_root(path: &str) -> bool {
+    let path = Path::new(path);
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default();
+    let parent = path.parent().unwrap_or_else(|| Path::new(""));
+
+    if matches!(file_name, "lib.rs" | "main.rs")
+        && parent.file_name().is_none_or(|dir| dir == "src")
+    {
+        return true;
+    }
+    // `src/bin/foo.rs` and `src/bin/foo/main.rs`.
+    if (parent.ends_with("src/bin") && file_name.ends_with(".rs"))
+        || (file_name == "main.rs" && parent.parent().is_some_and(|dir| dir.ends_with("src/bin")))
+    {
+        return true;
+    }
+
+    // Targets Cargo discovers outside `src/`: build scripts, `tests/foo.rs` and
+    // `tests/foo/main.rs`, and likewise under `examples/` and `benches/`.
+    if parent
+        .components()
+        .any(|component| component.as_os_str() == "src")
+    {
+        return false;
+    }
+    let is_target_dir = |dir: &Path| {
+        dir.file_name()
+            .is_some_and(|name| matches!(name.to_str(), Some("tests" | "examples" | "benches")))
+    };
+    file_name == "build.rs"
+        || (file_name.ends_with(".rs") && is_target_dir(parent))
+        || (file_name == "main.rs" && parent.parent().is_some_and(is_target_dir))
+}
+
+fn module_children_dir(file: &Path) -> PathBuf {
+    let parent = file.parent().unwrap_or_else(|| Path::new(""));
+    let file_name = file
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default();
+
+    if file_name == "mod.rs" {
+        return parent.to_path_buf();
+    }
+
+    match file.file_stem() {
+        Some(stem) => parent.join(stem),
+        None => parent.to_path_buf(),
+    }
+}
+
+fn normalize(path: &Path) -> String {
+    path.display().to_string()
+}
+
+fn split_visibility(line: &str) -> (&str, &str) {
+    if let Some(rest) = line.strip_prefix("pub(")
+        && let Some(end) = rest.find(')')
+    {
+        let split = "pub(".len() + end + 1;
+        return (&line[..split], line[split..].trim_start());
+    }
+    if let Some(rest) = line.strip_prefix("pub ") {
+        return ("pub", rest.trim_start());
+    }
+    ("", line)
+}
@@ -0,0 +1,659 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use ollama_rs::{
+    Ollama,
+    generation::{
+        chat::ChatMessage,
+        chat::ChatMessageResponse,
+        chat::request::ChatMessageRequest,
+        completion::request::GenerationRequest,
+        embeddings::request::GenerateEmbeddingsRequest,
+        parameters::{FormatType, JsonStructure, KeepAlive, ThinkType, TimeUnit},
+        tools::{Tool, ToolInfo},
+    },
+    headers::{AUTHORIZATION, HeaderMap, HeaderValue},
+    models::ModelOptions,
+};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use serde::Deserialize;
+use tokio::time;
+use tracing::{debug, info};
+
+use crate::error::{PlainSightError, Result};
+
+use super::{
+    ModelMemory, OllamaConfig, OllamaError, Prompt, RunningModel, Task, Thinking, TokenUsage,
+    telemetry, tools::*,
+};
+
+/// Byte progress of a pull is logged each time it crosses another step of this many percent.
+const PULL_PROGRESS_STEP: u64 = 10;
+
+/// One line of the `/api/pull` stream.
+#[derive(Debug, Deserialize)]
+struct PullStatus {
+    #[serde(default)]
+    status: String,
+    total: Option<u64>,
+    completed: Option<u64>,
+    error: Option<String>,
+}
+
+/// The `/api/ps` reply.
+#[derive(Debug, Deserialize)]
+struct RunningModels {
+    #[serde(default)]
+    models: Vec<RunningModelEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RunningModelEntry {
+    name: String,
+    #[serde(default)]
+    size: u64,
+    #[serde(default)]
+    size_vram: u64,
+}
+
+/// A server that runs the model configured for each task.
+///
+/// Prompt building and output cleanup happen in `OllamaWrapper`; a backend only sends the
+/// finished prompt and returns the raw reply.
+#[async_trait]
+pub trait LlmBackend: Send + Sync {
+    /// Run `prompt` against the model configured for `task`, sending `prompt.system` as the
+    /// system message when it is not empty.
+    async fn generate(&self, task: Task, prompt: &Prompt) -> Result<String>;
+
+    /// Like `generate`, but the model may call the `file_source` and `project_memory` tools.
+    /// Backends without tool calling answer from the prompt alone.
+    async fn generate_with_tools(&self, task: Task, prompt: &Prompt) -> Result<String> {
+        self.generate(task, prompt).await
+    }
+
+    /// Like `generate`, or `generate_with_tools` when `tools` is set, but the reply must be a
+    /// JSON object matching `schema`. Backends without constrained decoding send the prompt
+    /// unchanged and rely on its instructions.
+    async fn generate_structured(
+        &self,
+        task: Task,
+        prompt: &Prompt,
+        _schema: &serde_json::Value,
+        tools: bool,
+    ) -> Result<String> {
+        if tools {
+            self.generate_with_tools(task, prompt).await
+        } else {
+            self.generate(task, prompt).await
+        }
+    }
+
+    /// One vector per text of `texts`, in order, from the embedding `model`.
+    async fn embed(&self, model: &str, _texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        Err(OllamaError::Other(format!("this backend cannot embed text ({model})")).into())
+    }
+
+    /// Release `model` from server memory.
+    async fn unload(&self, model: &str) -> Result<()>;
+
+    /// Succeed when the server answers. The default asks for its models.
+    async fn health_check(&self) -> Result<()> {
+        self.available_models().await.map(|_| ())
+    }
+
+    /// Models the server can run, or `None` when the backend has no way to list them.
+    async fn available_models(&self) -> Result<Option<Vec<String>>> {
+        Ok(None)
+    }
+
+    /// Download `model` onto the server, logging progress as it goes.
+    async fn pull_model(&self, model: &str) -> Result<()> {
+        Err(OllamaError::Other(format!(
+            "this backend cannot pull models; install '{model}' on the server"
+        ))
+        .into())
+    }
+
+    /// Sizes of the server's models and of those it has loaded, or `None` when the backend
+    /// cannot tell.
+    async fn model_memory(&self) -> Result<Option<ModelMemory>> {
+        Ok(None)
+    }
+}
+
+/// Run one tool call. Bad arguments and tool failures are returned to the model as an
+/// `error` object so it can correct itself.
+async fn run_tool<T: Tool>(mut tool: T, arguments: serde_json::Value) -> String {
+    let params = match serde_json::from_value::<T::Params>(arguments) {
+        Ok(params) => params,
+        Err(err) => {
+            return serde_json::json!({
+                "error": format!("invalid arguments for {}: {err}", T::name())
+            })
+            .to_string();
+        }
+    };
+    tool.call(params)
+        .await
+        .unwrap_or_else(|err| serde_json::json!({ "error": err.to_string() }).to_string())
+}
+
+/// Pass the token counts Ollama sends with a finished reply to the request log.
+fn record_reply_usage(response: &ChatMessageResponse) {
+    if let Some(data) = &response.final_data {
+        telemetry::record_usage(TokenUsage {
+            prompt_tokens: data.prompt_eval_count,
+            completion_tokens: data.eval_count,
+            eval_duration_ms: data.eval_duration / 1_000_000,
+        });
+    }
+}
+
+fn chat_messages(prompt: &Prompt) -> Vec<ChatMessage> {
+    let mut messages = Vec::with_capacity(2);
+    if !prompt.system.is_empty() {
+        messages.push(ChatMessage::system(prompt.system.clone()));
+    }
+    messages.push(ChatMessage::user(prompt.user.clone()));
+    messages
+}
+
+/// Log a pull status when its message changes, or when its byte progress crosses another
+/// `PULL_PROGRESS_STEP`.
+fn report_pull_progress(model: &str, status: &PullStatus, last: &mut (String, Option<u64>)) {
+    let percent = match (status.completed, status.total) {
+        (Some(completed), Some(total)) if total > 0 => {
+            Some((completed.min(total) * 100 / total) / PULL_PROGRESS_STEP * PULL_PROGRESS_STEP)
+        }
+        _ => None,
+    };
+    if last.0 == status.status && last.1 == percent {
+        return;
+    }
+    match percent {
+        Some(percent) => info!(model, status = %status.status, percent, "model_pull_progress"),
+        None => info!(model, status = %status.status, "model_pull_progress"),
+    }
+    *last = (status.status.clone(), percent);
+}
+
+/// Request slots kept per model, so requests to one model never queue behind another's.
+///
+/// A model gets the largest `OllamaConfig::max_concurrency_for` among the tasks using it;
+/// models no task names (e.g. ones being unloaded) get `max_concurrency`.
+pub(super) struct ModelSlots {
+    limits: BTreeMap<String, usize>,
+    default_limit: usize,
+    slots: Mutex<BTreeMap<String, Arc<Semaphore>>>,
+}
+
+impl ModelSlots {
+    pub(super) fn new(config: &OllamaConfig) -> Self {
+        let mut limits = BTreeMap::new();
+        for task in Task::ALL {
+            let limit = config.max_concurrency_for(task);
+            limits
+                .entry(config.tasks.for_task(task).model.clone())
+                .and_modify(|current: &mut usize| *current = (*current).max(limit))
+                .or_insert(limit);
+        }
+        let limit = config.max_embedding_concurrency();
+        limits
+            .entry(config.tasks.embedding.model.clone())
+            .and_modify(|current: &mut usize| *current = (*current).max(limit))
+            .or_insert(limit);
+        Self {
+            limits,
+            default_limit: config.max_concurrency.max(1),
+            slots: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Wait up to `timeout` for one of `model`'s request slots.
+    pub(super) async fn acquire(
+        &self,
+        model: &str,
+        timeout: Duration,
+    ) -> std::result::Result<OwnedSemaphorePermit, OllamaError> {
+        let slots = {
+            let mut slots = self.slots.lock().unwrap_or_else(PoisonError::into_inner);
+            let limit = self
+                .limits
+                .get(model)
+                .copied()
+                .unwrap_or(self.default_limit);
+            Arc::clone(
+                slots
+                    .entry(model.to_string())
+                    .or_insert_with(|| Arc::new(Semaphore::new(limit))),
+            )
+        };
+        match time::timeout(timeout, slots.acquire_owned()).await {
+            Ok(Ok(permit)) => Ok(permit),
+            Ok(Err(e)) => Err(OllamaError::Other(format!("failed to acquire lock: {e}"))),
+            Err(_) => Err(OllamaError::Other(format!(
+                "timeout acquiring lock for model {model}"
+            ))),
+        }
+    }
+}
+
+pub struct OllamaBackend {
+    client: Ollama,
+    config: OllamaConfig,
+    slots: ModelSlots,
+}
+
+impl Default for OllamaBackend {
+    fn default() -> Self {
+        Self {
+            client: Ollama::default(),
+            slots: ModelSlots::new(&OllamaConfig::default()),
+            config: OllamaConfig::default(),
+        }
+    }
+}
+
+impl OllamaBackend {
+    /// Connect to the server configured by `config.host`/`config.port`, attaching
+    /// `config.authorization` to every request when set.
+    pub fn with_config(config: OllamaConfig) -> Result<Self> {
+        let url = config.base_url();
+        let mut client = Ollama::try_new(url.as_str()).map_err(|e| {
+            PlainSightError::InvalidState(format!("invalid Ollama URL '{url}': {e}"))
+        })?;
+
+        if let Some(authorization) = &config.authorization {
+            let mut value = HeaderValue::from_str(authorization).map_err(|_| {
+                PlainSightError::InvalidState(
+                    "Ollama authorization contains characters not allowed in a header".to_string(),
+                )
+            })?;
+            value.set_sensitive(true);
+            let mut headers = HeaderMap::new();
+            headers.insert(AUTHORIZATION, value);
+            client.set_headers(Some(headers));
+        }
+
+        debug!(ollama_url = %url, authorization = config.authorization.is_some(), "ollama_client_configured");
+        Ok(Self {
+            client,
+            slots: ModelSlots::new(&config),
+            config,
+        })
+    }
+
+    pub async fn list_models(&self) -> Result<Vec<String>> {
+        self.client
+            .list_local_models()
+            .await
+            .map(|models| models.into_iter().map(|model| model.name).collect())
+            .map_err(|e| OllamaError::from_ollama_rs("list models", e).into())
+    }
+
+    /// Stream `/api/pull` ourselves: ollama-rs only streams pulls behind its `stream` feature.
+    async fn pull(&self, model: &str) -> std::result::Result<(), OllamaError> {
+        let url = format!("{}/api/pull", self.config.base_url());
+        let mut request = reqwest::Client::new()
+            .post(&url)
+            .json(&serde_json::json!({ "model": model, "stream": true }));
+        if let Some(authorization) = &self.config.authorization {
+            request = request.header(AUTHORIZATION.as_str(), authorization);
+        }
+        let mut response = request
+            .send()
+            .await
+            .map_err(|e| OllamaError::from_reqwest(model, e))?;
+        let status = response.status();
+        if !status.is_success() {
+            let detail = response.text().await.unwrap_or_default();
+            return Err(OllamaError::Http {
+                model: model.to_string(),
+                status: status.as_u16(),
+                detail: detail.trim().to_string(),
+            });
+        }
+
+        let mut buffer = Vec::new();
+        let mut last_reported = (String::new(), None);
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| OllamaError::from_reqwest(model, e))?
+        {
+            buffer.extend_from_slice(&chunk);
+            while let Some(end) = buffer.iter().position(|byte| *byte == b'\n') {
+                let line = buffer.drain(..=end).collect::<Vec<_>>();
+                if line.iter().all(u8::is_ascii_whitespace) {
+                    continue;
+                }
+                let status: PullStatus = serde_json::from_slice(&line).map_err(|e| {
+                    OllamaError::Other(format!("invalid pull status ({model}): {e}"))
+                })?;
+                if let Some(error) = status.error {
+                    return Err(OllamaError::Other(format!("pull failed ({model}): {error}")));
+                }
+                report_pull_progress(model, &status, &mut last_reported);
+            }
+        }
+        Ok(())
+    }
+
+    /// The models `/api/ps` lists as loaded; ollama-rs has no call for it.
+    async fn running_models(&self) -> std::result::Result<Vec<RunningModel>, OllamaError> {
+        let url = format!("{}/api/ps", self.config.base_url());
+        let mut request = reqwest::Client::new().get(&url);
+        if let Some(authorization) = &self.config.authorization {
+            request = request.header(AUTHORIZATION.as_str(), authorization);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| OllamaError::from_reqwest("list running models", e))?;
+        let status = response.status();
+        if !status.is_success() {
+            let detail = response.text().await.unwrap_or_default();
+            return Err(OllamaError::Http {
+                model: "list running models".to_string(),
+                status: status.as_u16(),
+                detail: detail.trim().to_string(),
+            });
+        }
+        let running: RunningModels = response
+            .json()
+            .await
+            .map_err(|e| OllamaError::Other(format!("invalid running models reply: {e}")))?;
+        Ok(running
+            .models
+            .into_iter()
+            .map(|model| RunningModel {
+                name: model.name,
+                size: model.size,
+                size_vram: model.size_vram,
+            })
+            .collect())
+    }
+
+    /// Send `prompt` with the tool schemas, answer every tool call the model makes and send
+    /// the results back, until it replies with text. After `max_tool_rounds` rounds the tools
+    /// are withdrawn so the model has to answer. Without `tools` this is a single request.
+    /// `format` constrains every reply, tool rounds included.
+    async fn chat_with_tools(
+        &self,
+        task: Task,
+        prompt: &Prompt,
+        tools: bool,
+        format: Option<FormatType>,
+    ) -> std::result::Result<String, OllamaError> {
+        let model_cfg = self.config.tasks.for_request(task, &prompt.options);
+        let mut messages = chat_messages(prompt);
+        let mut round = 0;
+        loop {
+            let tools = if tools && round < self.config.max_tool_rounds {
+                vec![
+                    ToolInfo::new::<_, file_source_tool>(),
+                    ToolInfo::new::<_, project_memory_tool>(),
+                ]
+            } else {
+                Vec::new()
+            };
+            let request = ChatMessageRequest::new(model_cfg.model.clone(), messages.clone())
+                .keep_alive(keep_alive(model_cfg.keep_alive))
+                .options(model_cfg.options())
+                .tools(tools);
+            let request = match think(model_cfg.thinking) {
+                Some(think) => request.think(think),
+                None => request,
+            };
+            let request = match &format {
+                Some(format) => request.format(format.clone()),
+                None => request,
+            };
+            let response = self
+                .client
+                .send_chat_messages(request)
+                .await
+                .map_err(|err| OllamaError::from_ollama_rs(&model_cfg.model, err))?;
+            record_reply_usage(&response);
+            if response.message.tool_calls.is_empty() {
+                return Ok(response.message.content);
+            }
+
+            round += 1;
+            let calls = response.message.tool_calls.clone();
+            messages.push(response.message);
+            for call in calls {
+                debug!(
+                    model = %model_cfg.model,
+                    tool = %call.function.name,
+                    round,
+                    "tool_call"
+                );
+                let output = match call.function.name.as_str() {
+                    name if name == file_source_tool::name() => {
+                        run_tool(file_source_tool, call.function.arguments).await
+                    }
+                    name if name == project_memory_tool::name() => {
+                        run_tool(project_memory_tool, call.function.arguments).await
+                    }
+                    name => serde_json::json!({ "error": format!("unknown tool '{name}'") })
+                        .to_string(),
+                };
+                messages.push(ChatMessage::tool(output));
+            }
+        }
+    }
+
+    /// Fail `request` with `OllamaError::Timeout` after the task's `generate_timeout`, if any.
+    async fn with_generate_timeout(
+        &self,
+        task: Task,
+        request: impl Future<Output = std::result::Result<String, OllamaError>>,
+    ) -> Result<String> {
+        let model_cfg = self.config.tasks.for_task(task);
+        match model_cfg.generate_timeout {
+            Some(generate_timeout) => time::timeout(generate_timeout, request)
+                .await
+                .unwrap_or_else(|_| {
+                    Err(OllamaError::Timeout {
+                        model: model_cfg.model.clone(),
+                        after: generate_timeout,
+                    })
+                })
+                .map_err(Into::into),
+            None => request.await.map_err(Into::into),
+        }
+    }
+}
+
+/// `KeepAlive` for a task's `keep_alive`; zero unloads the model after the request.
+fn keep_alive(duration: Duration) -> KeepAlive {
+    if duration.is_zero() {
+        return KeepAlive::UnloadOnCompletion;
+    }
+    KeepAlive::Until {
+        time: duration.as_secs().max(1),
+        unit: TimeUnit::Seconds,
+    }
+}
+
+/// Ollama's `think` for a task's `thinking`; `None` sends none.
+fn think(thinking: Thinking) -> Option<ThinkType> {
+    match thinking {
+        Thinking::Model => None,
+        Thinking::Off => Some(ThinkType::False),
+        Thinking::On => Some(ThinkType::True),
+        Thinking::Low => Some(ThinkType::Low),
+        Thinking::Medium => Some(ThinkType::Medium),
+        Thinking::High => Some(ThinkType::High),
+    }
+}
+
+#[async_trait]
+impl LlmBackend for OllamaBackend {
+    async fn generate(&self, task: Task, prompt: &Prompt) -> Result<String> {
+        let model_cfg = self.config.tasks.for_request(task, &prompt.options);
+
+        let _permit = self
+            .slots
+            .acquire(&model_cfg.model, self.config.lock_timeout)
+            .await?;
+
+        let request = ChatMessageRequest::new(model_cfg.model.clone(), chat_messages(prompt))
+            .keep_alive(keep_alive(model_cfg.keep_alive))
+            .options(model_cfg.options());
+        let request = match think(model_cfg.thinking) {
+            Some(think) => request.think(think),
+            None => request,
+        };
+        let request = self.client.send_chat_messages(request);
+
+        if let Some(generate_timeout) = model_cfg.generate_timeout {
+            return match time::timeout(generate_timeout, request).await {
+                Ok(Ok(response)) => {
+                    record_reply_usage(&response);
+                    Ok(response.message.content)
+                }
+                Ok(Err(err)) => Err(OllamaError::from_ollama_rs(&model_cfg.model, err).into()),
+                Err(_) => Err(OllamaError::Timeout {
+                    model: model_cfg.model.clone(),
+                    after: generate_timeout,
+                }
+                .into()),
+            };
+        }
+
+        request
+            .await
+            .map(|response| {
+                record_reply_usage(&response);
+                response.message.content
+            })
+            .map_err(|err| OllamaError::from_ollama_rs(&model_cfg.model, err).into())
+    }
+
+    async fn generate_with_tools(&self, task: Task, prompt: &Prompt) -> Result<String> {
+        let model_cfg = self.config.tasks.for_request(task, &prompt.options);
+
+        let _permit = self
+            .slots
+            .acquire(&model_cfg.model, self.config.lock_timeout)
+            .await?;
+
+        self.with_generate_timeout(task, self.chat_with_tools(task, prompt, true, None))
+            .await
+    }
+
+    async fn generate_structured(
+        &self,
+        task: Task,
+        prompt: &Prompt,
+        schema: &serde_json::Value,
+        tools: bool,
+    ) -> Result<String> {
+        let format = serde_json::from_value::<JsonStructure>(schema.clone())
+            .map_err(|e| OllamaError::Other(format!("invalid output schema: {e}")))?;
+        let model_cfg = self.config.tasks.for_request(task, &prompt.options);
+
+        let _permit = self
+            .slots
+            .acquire(&model_cfg.model, self.config.lock_timeout)
+            .await?;
+
+        let request = self.chat_with_tools(
+            task,
+            prompt,
+            tools,
+            Some(FormatType::StructuredJson(Box::new(format))),
+        );
+        self.with_generate_timeout(task, request).await
+    }
+
+    async fn embed(&self, model: &str, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let embedding_cfg = &self.config.tasks.embedding;
+
+        let _permit = self.slots.acquire(model, self.config.lock_timeout).await?;
+
+        let request = GenerateEmbeddingsRequest::new(model.to_string(), texts.to_vec().into())
+            .keep_alive(keep_alive(embedding_cfg.keep_alive))
+            .options(ModelOptions::default().num_ctx(embedding_cfg.num_ctx))
+            .truncate(true);
+        let request = self.client.generate_embeddings(request);
+        let response = match embedding_cfg.generate_timeout {
+            Some(generate_timeout) => match time::timeout(generate_timeout, request).await {
+                Ok(response) => response,
+                Err(_) => {
+                    return Err(OllamaError::Timeout {
+                        model: model.to_string(),
+                        after: generate_timeout,
+                    }
+                    .into());
+                }
+            },
+            None => request.await,
+        }
+        .map_err(|err| OllamaError::from_ollama_rs(model, err))?;
+
+        if response.embeddings.len() != texts.len() {
+            return Err(OllamaError::Other(format!(
+                "expected {} embeddings, got {} ({model})",
+                texts.len(),
+                response.embeddings.len()
+            ))
+            .into());
+        }
+        Ok(response.embeddings)
+    }
+
+    async fn unload(&self, model_name: &str) -> Result<()> {
+        let _permit = self
+            .slots
+            .acquire(model_name, self.config.lock_timeout)
+            .await?;
+
+        let request = GenerationRequest::new(model_name.to_string(), "")
+            .keep_alive(KeepAlive::UnloadOnCompletion);
+
+        match time::timeout(self.config.unload_timeout, self.client.generate(request)).await {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err(err)) => Err(OllamaError::Other(format!(
+                "failed to unload model ({model_name}): {err}"
+            ))
+            .into()),
+            Err(_) => {
+                debug!(
+                    model = model_name,
+                    unload_timeout_secs = self.config.unload_timeout.as_secs(),
+                    "unload timeout - connection may have been closed by Ollama or model is in 'Stopping...' state"
+                );
+                Ok(())
+            }
+        }
+    }
+
+    async fn available_models(&self) -> Result<Option<Vec<String>>> {
+        self.list_models().await.map(Some)
+    }
+
+    async fn pull_model(&self, model: &str) -> Result<()> {
+        Ok(self.pull(model).await?)
+    }
+
+    async fn model_memory(&self) -> Result<Option<ModelMemory>> {
+        let weights = self
+            .client
+            .list_local_models()
+            .await
+            .map_err(|e| OllamaError::from_ollama_rs("list models", e))?
+            .into_iter()
+            .map(|model| (model.name, model.size))
+            .collect();
+        let running = self.running_models().await?;
+        Ok(Some(ModelMemory { weights, running }))
+    }
+}
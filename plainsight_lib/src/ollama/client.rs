@@ -1,279 +1,876 @@
-use std::sync::Arc;
-
-use ollama_rs::{
-    Ollama,
-    coordinator::Coordinator,
-    generation::{
-        chat::ChatMessage,
-        completion::request::GenerationRequest,
-        parameters::{KeepAlive, TimeUnit},
-    },
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
-use tokio::sync::Semaphore;
-use tokio::time;
-use tracing::debug;
 
-use crate::error::{PlainSightError, Result};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
 
-use super::{OllamaConfig, Task, prompts, tools::*, utils};
+use crate::{
+    error::{PlainSightError, Result},
+    prompt_context::PromptContext,
+};
+
+use super::{
+    BackendKind, GenerationResult, LlmBackend, ModelResidency, ModelUnloading, OllamaBackend,
+    OllamaConfig, OllamaError, OpenAiBackend, OptionOverrides, PromptFit, RequestLog,
+    RequestOutcome, RequestRecord, SummaryBatching, Task, TaskOutcome, TokenBudget, TokenUsage,
+    prompts::{self, Prompt},
+    rate_limit::RateLimiter,
+    structured, telemetry,
+    transcripts::{Transcript, TranscriptDump},
+    utils,
+};
+
+/// `RequestRecord::task` of embedding requests.
+const EMBEDDING_TASK_NAME: &str = "embedding";
 
+/// Builds the prompt for each task and cleans up the reply; the model itself runs on an
+/// `LlmBackend`.
 pub struct OllamaWrapper {
-    client: Ollama,
+    backend: Arc<dyn LlmBackend>,
     config: OllamaConfig,
-    lock: Arc<Semaphore>,
+    requests: RequestLog,
+    transcripts: Option<TranscriptDump>,
+    rate_limiter: Option<RateLimiter>,
+    cancel: CancellationToken,
+}
+
+impl Default for OllamaWrapper {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl OllamaWrapper {
     pub fn new() -> Self {
-        Self::with_config(OllamaConfig::default())
+        Self {
+            backend: Arc::new(OllamaBackend::default()),
+            config: OllamaConfig::default(),
+            requests: RequestLog::default(),
+            transcripts: None,
+            rate_limiter: None,
+            cancel: CancellationToken::new(),
+        }
+    }
+
+    /// Connect to the server selected by `config.backend`.
+    pub fn with_config(config: OllamaConfig) -> Result<Self> {
+        let backend: Arc<dyn LlmBackend> = match config.backend {
+            BackendKind::Ollama => Arc::new(OllamaBackend::with_config(config.clone())?),
+            BackendKind::OpenAiCompatible => Arc::new(OpenAiBackend::with_config(config.clone())?),
+        };
+        Ok(Self::with_backend(config, backend))
     }
 
-    pub fn with_config(config: OllamaConfig) -> Self {
+    /// Run generation on a custom backend; `config.tasks` still names the model per task.
+    pub fn with_backend(config: OllamaConfig, backend: Arc<dyn LlmBackend>) -> Self {
         Self {
-            client: Ollama::default(),
+            backend,
+            transcripts: config.dump_prompts_dir.clone().map(TranscriptDump::new),
+            rate_limiter: config
+                .requests_per_minute
+                .and_then(|limit| RateLimiter::new(limit, config.rate_limit_burst)),
             config,
-            lock: Arc::new(Semaphore::new(1)),
+            requests: RequestLog::default(),
+            cancel: CancellationToken::new(),
         }
     }
 
+    /// Fail requests with `PlainSightError::Cancelled` once `cancel` is cancelled. Requests in
+    /// flight are dropped, which closes their connections; unloading models still works.
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
     pub fn model_name(&self, task: Task) -> &str {
         &self.config.tasks.for_task(task).model
     }
 
-    pub async fn list_models(&self) -> Result<Vec<String>> {
-        self.client
-            .list_local_models()
-            .await
-            .map(|models| models.into_iter().map(|model| model.name).collect())
-            .map_err(|e| PlainSightError::Ollama(format!("failed to list models: {e}")))
+    /// The instructions `task`'s prompts use in place of the built-in ones, if overridden.
+    pub fn prompt_override(&self, task: Task) -> Option<&str> {
+        self.config
+            .prompt_overrides
+            .get(task.name())
+            .map(String::as_str)
     }
 
-    pub async fn generate_for_task(&self, task: Task, prompt: &str) -> Result<String> {
-        self.generate(task, prompt).await
+    /// Whether requests ask for JSON output.
+    pub fn structured_output(&self) -> bool {
+        self.config.structured_output
     }
 
-    pub async fn unload_task_model(&self, task: Task) -> Result<()> {
-        self.unload_model(self.model_name(task)).await
+    /// Every request attempt made so far.
+    pub fn request_log(&self) -> &RequestLog {
+        &self.requests
     }
 
-    pub async fn unload_model(&self, model_name: &str) -> Result<()> {
-        let _permit = match time::timeout(self.config.lock_timeout, self.lock.acquire()).await {
-            Ok(Ok(permit)) => permit,
-            Ok(Err(e)) => {
-                return Err(PlainSightError::Ollama(format!(
-                    "failed to acquire lock for unload: {e}"
-                )));
-            }
-            Err(_) => {
-                return Err(PlainSightError::Ollama(format!(
-                    "timeout acquiring lock to unload model {}",
-                    model_name
-                )));
-            }
-        };
+    /// Number of file requests the workflow keeps in flight at once.
+    pub fn max_concurrency(&self) -> usize {
+        self.config.max_concurrency.max(1)
+    }
 
-        let request = GenerationRequest::new(model_name.to_string(), "")
-            .keep_alive(KeepAlive::UnloadOnCompletion);
+    /// Number of `task` requests the workflow keeps in flight at once.
+    pub fn max_concurrency_for(&self, task: Task) -> usize {
+        self.config.max_concurrency_for(task)
+    }
 
-        match time::timeout(self.config.unload_timeout, self.client.generate(request)).await {
-            Ok(Ok(_)) => Ok(()),
-            Ok(Err(err)) => Err(PlainSightError::Ollama(format!(
-                "failed to unload model ({}): {err}",
-                model_name
-            ))),
-            Err(_) => {
+    /// Whether each file's docs may be requested while other files are still summarized.
+    pub fn pipelines_file_tasks(&self) -> bool {
+        self.config.pipeline_file_tasks
+            && self.model_name(Task::Summarize) != self.model_name(Task::Documentation)
+    }
+
+    /// Whether the workflow unloads models as its phases finish; see `ModelUnloading`.
+    pub fn unloads_after_phase(&self) -> bool {
+        self.config.model_unloading == ModelUnloading::AfterPhase
+    }
+
+    /// How small files are batched for summaries; `None` when every file gets its own
+    /// request, because batching is off or structured output or a `summarize` prompt override
+    /// needs the per-file prompt.
+    pub fn summary_batching(&self) -> Option<SummaryBatching> {
+        let batching = self.config.summary_batching;
+        let usable = batching.enabled
+            && batching.max_files > 1
+            && !self.config.structured_output
+            && !self
+                .config
+                .prompt_overrides
+                .contains_key(Task::Summarize.name());
+        usable.then_some(batching)
+    }
+
+    /// Whether generated file docs get a second pass by `Task::Review`.
+    pub fn reviews_docs(&self) -> bool {
+        self.config.enable_review_pass
+    }
+
+    /// Fail fast with `OllamaError::Unreachable` when the server cannot be reached within
+    /// `health_check_timeout`. A server that answers with an error fails with that error.
+    pub async fn health_check(&self) -> Result<()> {
+        let url = self.config.base_url();
+        let started = Instant::now();
+        let unreachable = |message: String| OllamaError::Unreachable {
+            url: url.clone(),
+            message,
+        };
+        let timeout = self.config.health_check_timeout;
+        match tokio::time::timeout(timeout, self.backend.health_check()).await {
+            Ok(Ok(())) => {
                 debug!(
-                    model = model_name,
-                    unload_timeout_secs = self.config.unload_timeout.as_secs(),
-                    "unload timeout - connection may have been closed by Ollama or model is in 'Stopping...' state"
+                    url = %url,
+                    elapsed_ms = telemetry::millis(started.elapsed()),
+                    "llm_server_healthy"
                 );
                 Ok(())
             }
+            Ok(Err(PlainSightError::Ollama(err))) => match err {
+                OllamaError::ConnectionRefused { message, .. } => Err(unreachable(message).into()),
+                err => Err(err.into()),
+            },
+            Ok(Err(e)) => Err(e),
+            Err(_) => {
+                Err(unreachable(format!("no answer within {} seconds", timeout.as_secs())).into())
+            }
         }
     }
 
-    pub async fn summarize(&self, context_payload: &str) -> Result<String> {
-        let context =
-            utils::prepare_file_summary_input(context_payload).map_err(PlainSightError::Ollama)?;
-        debug!(
-            payload_bytes = context.len(),
-            "ollama_summarize_payload_prepared"
-        );
+    /// Make sure the server has the models of `tasks` before any of them runs, pulling the
+    /// missing ones when `pull_missing_models` is set. Backends that cannot list their models
+    /// are not checked.
+    pub async fn ensure_models(&self, tasks: &[Task]) -> Result<()> {
+        let models = tasks.iter().map(|task| self.model_name(*task));
+        self.ensure_model_names(models.collect()).await
+    }
+
+    /// `ensure_models` for the model of `tasks.embedding`.
+    pub async fn ensure_embedding_model(&self) -> Result<()> {
+        self.ensure_model_names(BTreeSet::from([self.embedding_model()]))
+            .await
+    }
+
+    async fn ensure_model_names(&self, models: BTreeSet<&str>) -> Result<()> {
+        let Some(available) = self.backend.available_models().await? else {
+            debug!("model_preflight_skipped");
+            return Ok(());
+        };
+        let missing = models
+            .into_iter()
+            .filter(|model| {
+                !available
+                    .iter()
+                    .any(|name| with_default_tag(name) == with_default_tag(model))
+            })
+            .collect::<BTreeSet<_>>();
+
+        for model in missing {
+            if !self.config.pull_missing_models {
+                return Err(OllamaError::ModelNotFound {
+                    model: model.to_string(),
+                }
+                .into());
+            }
+            info!(model, "model missing on server; pulling");
+            self.backend.pull_model(model).await?;
+            info!(model, "model_pulled");
+        }
+        Ok(())
+    }
+
+    pub fn embedding_model(&self) -> &str {
+        &self.config.tasks.embedding.model
+    }
+
+    /// One vector per text of `texts`, in order, from `model`, usually [`Self::embedding_model`].
+    ///
+    /// Texts are sent `tasks.embedding.batch_size` at a time; a batch that fails transiently is
+    /// retried like any other request.
+    pub async fn embed(&self, model: &str, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(self.config.tasks.embedding.batch_size.max(1)) {
+            let prompt_bytes = batch.iter().map(String::len).sum();
+            let (vectors, _) = self
+                .retrying(EMBEDDING_TASK_NAME, model, prompt_bytes, || {
+                    self.backend.embed(model, batch)
+                })
+                .await?;
+            embeddings.extend(vectors);
+        }
+        debug!(model, texts = texts.len(), "texts_embedded");
+        Ok(embeddings)
+    }
+
+    /// Send `prompt` as the only message, without a system prompt.
+    pub async fn generate_for_task(&self, task: Task, prompt: &str) -> Result<GenerationResult> {
+        self.generate(task, &Prompt::user(prompt)).await
+    }
+
+    pub async fn unload_task_model(&self, task: Task) -> Result<()> {
+        self.unload_model(self.model_name(task)).await
+    }
+
+    /// Which task models fit in GPU memory together, from the model sizes the server reports
+    /// and `OllamaConfig::gpu_memory`. When the server cannot report them, everything is taken
+    /// to fit.
+    pub async fn model_residency(&self) -> ModelResidency {
+        let memory = match self.backend.model_memory().await {
+            Ok(memory) => memory,
+            Err(err) => {
+                warn!(error = %err, "model memory query failed; keeping the usual phase order");
+                None
+            }
+        };
+        ModelResidency::new(memory, self.config.gpu_memory)
+    }
+
+    pub async fn unload_model(&self, model_name: &str) -> Result<()> {
+        self.backend.unload(model_name).await
+    }
+
+    pub async fn summarize(&self, context_payload: &str) -> Result<GenerationResult> {
         let task = Task::Summarize;
-        let prompt = prompts::build_summary_prompt(&context);
+        let prompt = self.file_prompt(task, context_payload)?;
         debug!(
-            prompt_bytes = prompt.len(),
+            prompt_bytes = prompt.user.len(),
             model = self.model_name(task),
             "ollama_summarize_prompt"
         );
-        let out = self.generate_with_memory_tool(task, &prompt).await?;
-        self.postprocess_output(task, out)
+        let started = Instant::now();
+        let out = self
+            .generate_with_memory_tool(task, &prompt)
+            .await
+            .and_then(|out| out.try_map_text(|text| self.postprocess_output(task, text)))
+            .and_then(|out| out.try_map_text(|text| self.reject_refusal(task, text)));
+        self.observe(task, started, out)
     }
 
-    pub async fn document(&self, context_payload: &str) -> Result<String> {
-        let context =
-            utils::prepare_file_docs_input(context_payload).map_err(PlainSightError::Ollama)?;
+    /// Summaries of several small files from one request, by path; `files` holds each file's
+    /// path and context payload. Files whose section is missing, or fails the checks
+    /// `summarize` applies, are left out so callers can summarize them on their own.
+    pub async fn summarize_batch(
+        &self,
+        files: &[(String, String)],
+    ) -> Result<BTreeMap<String, String>> {
+        let task = Task::Summarize;
+        let prompt = self.summary_batch_prompt(files)?;
         debug!(
-            payload_bytes = context.len(),
-            "ollama_docs_payload_prepared"
+            prompt_bytes = prompt.user.len(),
+            files = files.len(),
+            model = self.model_name(task),
+            "ollama_summarize_batch_prompt"
         );
+        let started = Instant::now();
+        let reply = self.generate(task, &prompt).await;
+        let reply = self.observe(task, started, reply)?;
+
+        let mut summaries = BTreeMap::new();
+        for (path, section) in utils::split_batch_reply(&reply.text) {
+            if !files.iter().any(|(file, _)| *file == path) {
+                continue;
+            }
+            match self
+                .postprocess_output(task, section)
+                .and_then(|out| self.reject_refusal(task, out))
+            {
+                Ok(summary) => {
+                    summaries.insert(path, summary);
+                }
+                Err(err) => debug!(target_file = %path, error = %err, "batched_summary_rejected"),
+            }
+        }
+        Ok(summaries)
+    }
+
+    /// Estimated size of the prompt `summarize_batch` would send for `files`.
+    pub fn summary_batch_fit(&self, files: &[(String, String)]) -> Result<PromptFit> {
+        let prompt = self.summary_batch_prompt(files)?;
+        Ok(self
+            .prompt_budget(Task::Summarize, &prompt)
+            .measure(&prompt))
+    }
+
+    pub async fn document(&self, context_payload: &str) -> Result<GenerationResult> {
         let task = Task::Documentation;
-        let prompt = prompts::build_doc_prompt(&context);
+        let prompt = self.file_prompt(task, context_payload)?;
         debug!(
-            prompt_bytes = prompt.len(),
+            prompt_bytes = prompt.user.len(),
             model = self.model_name(task),
             "ollama_docs_prompt"
         );
-        let out = self.generate_with_memory_tool(task, &prompt).await?;
-        self.postprocess_output(task, out)
+        let started = Instant::now();
+        let out = self
+            .generate_with_memory_tool(task, &prompt)
+            .await
+            .and_then(|out| out.try_map_text(|text| self.postprocess_output(task, text)))
+            .and_then(|out| out.try_map_text(|text| self.reject_refusal(task, text)));
+        self.observe(task, started, out)
+    }
+
+    /// Estimated size of the prompt `summarize` (`Task::Summarize`) or `document` (any other
+    /// task) would send for `context_payload`, against the task's context window.
+    pub fn file_prompt_fit(&self, task: Task, context_payload: &str) -> Result<PromptFit> {
+        let prompt = self.file_prompt(task, context_payload)?;
+        Ok(self.prompt_budget(task, &prompt).measure(&prompt))
+    }
+
+    pub fn token_budget(&self, task: Task) -> TokenBudget {
+        TokenBudget::for_task(self.config.tasks.for_task(task), self.offers_tools(task))
+    }
+
+    /// `token_budget`, with the reply room of `prompt`'s `num_predict` override.
+    fn prompt_budget(&self, task: Task, prompt: &Prompt) -> TokenBudget {
+        let config = self.config.tasks.for_request(task, &prompt.options);
+        TokenBudget::for_task(&config, self.offers_tools(task))
+    }
+
+    /// Options `TaskProfiles::rules` set for the file `context_payload` describes. Payloads
+    /// that are not a `PromptContext` get none.
+    fn option_overrides(&self, task: Task, context_payload: &str) -> OptionOverrides {
+        if self.config.tasks.rules.is_empty() {
+            return OptionOverrides::default();
+        }
+        match serde_json::from_str::<PromptContext>(context_payload) {
+            Ok(context) => {
+                let overrides = self.config.tasks.overrides_for(task, &context);
+                if !overrides.is_empty() {
+                    debug!(
+                        task = task.name(),
+                        target_file = %context.path,
+                        temperature = ?overrides.temperature,
+                        num_predict = ?overrides.num_predict,
+                        "option_overrides"
+                    );
+                }
+                overrides
+            }
+            Err(err) => {
+                debug!(error = %err, "payload is not a prompt context; option rules skipped");
+                OptionOverrides::default()
+            }
+        }
+    }
+
+    fn offers_tools(&self, task: Task) -> bool {
+        self.config.use_tools && matches!(task, Task::Summarize | Task::Documentation)
+    }
+
+    /// `prompt` with the task's instructions replaced by `config.prompt_overrides`, if any,
+    /// and asking for JSON when `structured_output` is set.
+    fn with_override(&self, task: Task, prompt: Prompt) -> Prompt {
+        let prompt = match self.config.prompt_overrides.get(task.name()) {
+            Some(template) => prompts::apply_override(prompt, template, self.offers_tools(task)),
+            None => prompt,
+        };
+        if self.config.structured_output {
+            structured::structured_prompt(task, &prompt)
+        } else {
+            prompt
+        }
     }
 
     pub async fn project_summary(
         &self,
         project_name: &str,
         file_summaries_context: &str,
-    ) -> Result<String> {
+    ) -> Result<GenerationResult> {
         let task = Task::ProjectSummary;
-        let prompt = prompts::build_project_summary_prompt(project_name, file_summaries_context);
+        let prompt = self.with_override(
+            task,
+            prompts::build_project_summary_prompt(project_name, file_summaries_context),
+        );
         debug!(
-            prompt_bytes = prompt.len(),
+            prompt_bytes = prompt.user.len(),
             model = self.model_name(task),
             "ollama_project_summary_prompt"
         );
-        let out = self.generate(task, &prompt).await?;
-        self.postprocess_output(task, out)
+        let started = Instant::now();
+        let out = self
+            .generate(task, &prompt)
+            .await
+            .and_then(|out| out.try_map_text(|text| self.postprocess_output(task, text)));
+        self.observe(task, started, out)
     }
 
-    pub async fn architecture(&self, project_name: &str, context_payload: &str) -> Result<String> {
-        let context =
-            utils::prepare_architecture_input(context_payload).map_err(PlainSightError::Ollama)?;
+    /// Summary of `directory` from those of its files and subdirectories, each `(path,
+    /// summary)`.
+    pub async fn directory_summary(
+        &self,
+        project_name: &str,
+        directory: &str,
+        files: &[(String, String)],
+        subdirectories: &[(String, String)],
+    ) -> Result<GenerationResult> {
+        let task = Task::DirectorySummary;
+        let prompt = self.with_override(
+            task,
+            prompts::build_directory_summary_prompt(project_name, directory, files, subdirectories),
+        );
+        debug!(
+            prompt_bytes = prompt.user.len(),
+            model = self.model_name(task),
+            directory,
+            "ollama_directory_summary_prompt"
+        );
+        let started = Instant::now();
+        let out = self
+            .generate(task, &prompt)
+            .await
+            .and_then(|out| out.try_map_text(|text| self.postprocess_output(task, text)));
+        self.observe(task, started, out)
+    }
+
+    pub async fn change_summary(
+        &self,
+        project_name: &str,
+        changes: &serde_json::Value,
+    ) -> Result<GenerationResult> {
+        let task = Task::ChangeSummary;
+        let prompt = self.with_override(
+            task,
+            prompts::build_change_summary_prompt(project_name, changes),
+        );
+        debug!(
+            prompt_bytes = prompt.user.len(),
+            model = self.model_name(task),
+            "ollama_change_summary_prompt"
+        );
+        let started = Instant::now();
+        let out = self
+            .generate(task, &prompt)
+            .await
+            .and_then(|out| out.try_map_text(|text| self.postprocess_output(task, text)));
+        self.observe(task, started, out)
+    }
+
+    pub async fn architecture(
+        &self,
+        project_name: &str,
+        context_payload: &str,
+    ) -> Result<GenerationResult> {
+        let context = utils::prepare_architecture_input(context_payload)?;
         debug!(
             payload_bytes = context.len(),
             "ollama_arch_payload_prepared"
         );
         let task = Task::Architecture;
-        let prompt = prompts::build_architecture_prompt(project_name, &context);
+        let prompt = self.with_override(
+            task,
+            prompts::build_architecture_prompt(project_name, &context),
+        );
         debug!(
-            prompt_bytes = prompt.len(),
+            prompt_bytes = prompt.user.len(),
             model = self.model_name(task),
             "ollama_arch_prompt"
         );
-        let out = self.generate(task, &prompt).await?;
-        self.postprocess_output(task, out)
+        let started = Instant::now();
+        let out = self
+            .generate(task, &prompt)
+            .await
+            .and_then(|out| out.try_map_text(|text| self.postprocess_output(task, text)));
+        self.observe(task, started, out)
     }
 
-    async fn generate(&self, task: Task, prompt: &str) -> Result<String> {
-        let model_cfg = self.config.tasks.for_task(task);
+    /// Explain a selected line range; `context` comes from `explain::build_explain_context`.
+    pub async fn explain(&self, context: &str) -> Result<GenerationResult> {
+        let task = Task::Explain;
+        let prompt = self.with_override(task, prompts::build_explain_prompt(context));
+        debug!(
+            prompt_bytes = prompt.user.len(),
+            model = self.model_name(task),
+            "ollama_explain_prompt"
+        );
+        let started = Instant::now();
+        let out = self
+            .generate(task, &prompt)
+            .await
+            .and_then(|out| out.try_map_text(|text| self.postprocess_output(task, text)));
+        self.observe(task, started, out)
+    }
 
-        let _permit = match time::timeout(self.config.lock_timeout, self.lock.acquire()).await {
-            Ok(Ok(permit)) => permit,
-            Ok(Err(e)) => {
-                return Err(PlainSightError::Ollama(format!(
-                    "failed to acquire lock: {e}"
-                )));
-            }
-            Err(_) => {
-                return Err(PlainSightError::Ollama(format!(
-                    "timeout acquiring lock for model {}",
-                    model_cfg.model
-                )));
-            }
-        };
+    /// Docs for one file, checked against the file's symbols by `Task::Review`'s model.
+    pub async fn review(&self, context: &str) -> Result<GenerationResult> {
+        let task = Task::Review;
+        let prompt = self.with_override(task, prompts::build_review_prompt(context));
+        debug!(
+            prompt_bytes = prompt.user.len(),
+            model = self.model_name(task),
+            "ollama_review_prompt"
+        );
+        let started = Instant::now();
+        let out = self
+            .generate(task, &prompt)
+            .await
+            .and_then(|out| out.try_map_text(|text| self.postprocess_output(task, text)))
+            .and_then(|out| out.try_map_text(|text| self.reject_refusal(task, text)));
+        self.observe(task, started, out)
+    }
 
-        let request = GenerationRequest::new(model_cfg.model.clone(), prompt.to_string())
-            .keep_alive(KeepAlive::Until {
-                time: self.config.keep_alive_minutes,
-                unit: TimeUnit::Minutes,
-            })
-            .options(model_cfg.options());
-
-        if let Some(generate_timeout) = model_cfg.generate_timeout {
-            return match time::timeout(generate_timeout, self.client.generate(request)).await {
-                Ok(Ok(response)) => Ok(response.response),
-                Ok(Err(err)) => Err(PlainSightError::Ollama(format!(
-                    "ollama error ({}): {err}",
-                    model_cfg.model
-                ))),
-                Err(_) => Err(PlainSightError::Ollama(format!(
-                    "ollama error ({}): request timeout after {} seconds - model may have been killed or is in 'Stopping...' state",
-                    model_cfg.model,
-                    generate_timeout.as_secs()
-                ))),
-            };
+    /// The batched summary prompt, with room in `num_predict` for every file's reply.
+    fn summary_batch_prompt(&self, files: &[(String, String)]) -> Result<Prompt> {
+        let files = files
+            .iter()
+            .map(|(path, payload)| Ok((path.clone(), utils::prepare_file_summary_input(payload)?)))
+            .collect::<Result<Vec<_>>>()?;
+        let mut prompt = prompts::build_summary_batch_prompt(&files);
+        let num_predict = self.config.tasks.summarize.num_predict;
+        if num_predict > 0 {
+            prompt.options.num_predict =
+                Some(num_predict.saturating_mul(i32::try_from(files.len()).unwrap_or(i32::MAX)));
         }
+        Ok(prompt)
+    }
 
-        self.client
-            .generate(request)
+    fn file_prompt(&self, task: Task, context_payload: &str) -> Result<Prompt> {
+        if task == Task::Summarize {
+            let context = utils::prepare_file_summary_input(context_payload)?;
+            debug!(
+                payload_bytes = context.len(),
+                "ollama_summarize_payload_prepared"
+            );
+            let prompt = prompts::build_summary_prompt(&context, self.config.use_tools);
+            return Ok(Prompt {
+                options: self.option_overrides(task, context_payload),
+                ..self.with_override(task, prompt)
+            });
+        }
+        let context = utils::prepare_file_docs_input(context_payload)?;
+        debug!(
+            payload_bytes = context.len(),
+            "ollama_docs_payload_prepared"
+        );
+        let prompt = prompts::build_doc_prompt(&context, self.config.use_tools);
+        Ok(Prompt {
+            options: self.option_overrides(task, context_payload),
+            ..self.with_override(task, prompt)
+        })
+    }
+
+    /// Log the prompt's estimated size; a prompt over budget is still sent, since Ollama
+    /// truncates it rather than failing, but callers should have shrunk it first.
+    fn check_budget(&self, task: Task, prompt: &Prompt) {
+        let fit = self.prompt_budget(task, prompt).measure(prompt);
+        if fit.fits() {
+            debug!(
+                model = self.model_name(task),
+                prompt_tokens = fit.tokens,
+                limit = fit.limit,
+                "prompt_budget"
+            );
+        } else {
+            warn!(
+                model = self.model_name(task),
+                prompt_tokens = fit.tokens,
+                limit = fit.limit,
+                num_ctx = self.config.tasks.for_task(task).num_ctx,
+                "prompt exceeds the task's context budget; the model will see a truncated prompt"
+            );
+        }
+    }
+
+    async fn generate(&self, task: Task, prompt: &Prompt) -> Result<GenerationResult> {
+        if self.config.structured_output {
+            return self.generate_structured(task, prompt, false).await;
+        }
+        self.check_budget(task, prompt);
+        self.with_retries(task, prompt, || self.backend.generate(task, prompt))
             .await
-            .map(|response| response.response)
-            .map_err(|err| {
-                PlainSightError::Ollama(format!("ollama error ({}): {err}", model_cfg.model))
-            })
     }
 
-    async fn generate_with_memory_tool(&self, task: Task, prompt: &str) -> Result<String> {
-        let model_cfg = self.config.tasks.for_task(task);
+    /// Offer the source and memory tools when `use_tools` is set; otherwise the model works
+    /// from the prompt alone.
+    async fn generate_with_memory_tool(
+        &self,
+        task: Task,
+        prompt: &Prompt,
+    ) -> Result<GenerationResult> {
+        if !self.config.use_tools {
+            return self.generate(task, prompt).await;
+        }
+        if self.config.structured_output {
+            return self.generate_structured(task, prompt, true).await;
+        }
+        self.check_budget(task, prompt);
+        self.with_retries(task, prompt, || {
+            self.backend.generate_with_tools(task, prompt)
+        })
+        .await
+    }
+
+    /// Request the JSON object of the task's output schema and render its sections as
+    /// Markdown.
+    async fn generate_structured(
+        &self,
+        task: Task,
+        prompt: &Prompt,
+        tools: bool,
+    ) -> Result<GenerationResult> {
+        let schema = structured::output_schema(task);
+        self.check_budget(task, prompt);
+        let reply = self
+            .with_retries(task, prompt, || {
+                self.backend
+                    .generate_structured(task, prompt, &schema, tools)
+            })
+            .await?;
+        reply.try_map_text(|json| {
+            Ok(structured::render_markdown(
+                task,
+                self.model_name(task),
+                &json,
+            )?)
+        })
+    }
 
-        let _permit = match time::timeout(self.config.lock_timeout, self.lock.acquire()).await {
-            Ok(Ok(permit)) => permit,
-            Ok(Err(e)) => {
-                return Err(PlainSightError::Ollama(format!(
-                    "failed to acquire lock: {e}"
-                )));
-            }
-            Err(_) => {
-                return Err(PlainSightError::Ollama(format!(
-                    "timeout acquiring lock for model {}",
-                    model_cfg.model
-                )));
-            }
+    /// Repeat `request` after transient failures, up to `config.max_retries` times. Every
+    /// attempt goes to the request log, and to `config.dump_prompts_dir` when set, thinking
+    /// included; the reply is returned without it.
+    async fn with_retries<F, Fut>(
+        &self,
+        task: Task,
+        prompt: &Prompt,
+        mut request: F,
+    ) -> Result<GenerationResult>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<String>>,
+    {
+        let prompt_bytes = prompt.system.len() + prompt.user.len();
+        let model = self.model_name(task);
+        let requested = Instant::now();
+        let result = |(reply, usage): (String, TokenUsage)| {
+            let text = utils::strip_thinking(reply);
+            GenerationResult::new(text, model, usage, requested.elapsed())
+        };
+        let Some(transcripts) = &self.transcripts else {
+            return self
+                .retrying(task.name(), model, prompt_bytes, request)
+                .await
+                .map(result);
         };
 
-        let keep_alive = KeepAlive::Until {
-            time: self.config.keep_alive_minutes,
-            unit: TimeUnit::Minutes,
+        let task_config = &self.config.tasks.for_request(task, &prompt.options);
+        let mut attempt = 0;
+        let dumped = || {
+            attempt += 1;
+            let attempt = attempt;
+            let started = Instant::now();
+            let reply = request();
+            async move {
+                let reply = reply.await;
+                transcripts.write(&Transcript {
+                    task: task.name(),
+                    task_config,
+                    target: telemetry::current_target(),
+                    attempt,
+                    duration_ms: telemetry::millis(started.elapsed()),
+                    prompt,
+                    reply: &reply,
+                });
+                reply
+            }
         };
+        self.retrying(task.name(), model, prompt_bytes, dumped)
+            .await
+            .map(result)
+    }
 
-        let mut coordinator =
-            Coordinator::new(self.client.clone(), model_cfg.model.clone(), vec![])
-                .options(model_cfg.options())
-                .keep_alive(keep_alive)
-                .add_tool(file_source_tool)
-                .add_tool(project_memory_tool);
-
-        let request = coordinator.chat(vec![ChatMessage::user(prompt.to_string())]);
-
-        if let Some(generate_timeout) = model_cfg.generate_timeout {
-            return match time::timeout(generate_timeout, request).await {
-                Ok(Ok(response)) => Ok(response.message.content),
-                Ok(Err(err)) => Err(PlainSightError::Ollama(format!(
-                    "ollama error ({}): {err}",
-                    model_cfg.model
-                ))),
-                Err(_) => Err(PlainSightError::Ollama(format!(
-                    "ollama error ({}): request timeout after {} seconds - model may have been killed or is in 'Stopping...' state",
-                    model_cfg.model,
-                    generate_timeout.as_secs()
-                ))),
+    /// Send `request` until it succeeds, fails for good or runs out of `max_retries`,
+    /// recording every attempt as `task_name` against `model`. Each attempt waits its turn
+    /// under `requests_per_minute`. Returns the reply with the usage of the attempt that
+    /// succeeded.
+    async fn retrying<T, F, Fut>(
+        &self,
+        task_name: &str,
+        model: &str,
+        prompt_bytes: usize,
+        mut request: F,
+    ) -> Result<(T, TokenUsage)>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 0u32;
+        loop {
+            if let Some(rate_limiter) = &self.rate_limiter {
+                tokio::select! {
+                    () = rate_limiter.acquire() => {}
+                    () = self.cancel.cancelled() => return Err(PlainSightError::Cancelled),
+                }
+            }
+            let started = Instant::now();
+            let (result, usage) = tokio::select! {
+                measured = telemetry::measure(request()) => measured,
+                () = self.cancel.cancelled() => return Err(PlainSightError::Cancelled),
             };
+            self.requests.push(RequestRecord {
+                task: task_name.to_string(),
+                model: model.to_string(),
+                target: telemetry::current_target(),
+                prompt_bytes,
+                duration_ms: telemetry::millis(started.elapsed()),
+                usage,
+                ok: result.is_ok(),
+            });
+            match result {
+                Err(err) if err.is_transient() && attempt < self.config.max_retries => {
+                    let delay = backoff_delay(&self.config, attempt);
+                    attempt += 1;
+                    warn!(
+                        model,
+                        attempt,
+                        max_retries = self.config.max_retries,
+                        delay_ms = delay.as_millis() as u64,
+                        error = %err,
+                        "transient LLM error; retrying"
+                    );
+                    tokio::select! {
+                        () = tokio::time::sleep(delay) => {}
+                        () = self.cancel.cancelled() => return Err(PlainSightError::Cancelled),
+                    }
+                }
+                result => return result.map(|reply| (reply, usage)),
+            }
         }
+    }
 
-        request
-            .await
-            .map(|response| response.message.content)
-            .map_err(|err| {
-                PlainSightError::Ollama(format!("ollama error ({}): {err}", model_cfg.model))
-            })
+    /// Wait for `duration`, or fail with `PlainSightError::Cancelled` once the run is cancelled.
+    pub async fn pause(&self, duration: Duration) -> Result<()> {
+        tokio::select! {
+            () = tokio::time::sleep(duration) => Ok(()),
+            () = self.cancel.cancelled() => Err(PlainSightError::Cancelled),
+        }
+    }
+
+    /// Record in the request log how the `task` request started at `started` ended. Cancelled
+    /// requests are left out.
+    fn observe(
+        &self,
+        task: Task,
+        started: Instant,
+        result: Result<GenerationResult>,
+    ) -> Result<GenerationResult> {
+        let outcome = match &result {
+            Ok(_) => RequestOutcome::Succeeded,
+            Err(PlainSightError::Cancelled) => return result,
+            Err(PlainSightError::Ollama(OllamaError::RefusalDetected { .. })) => {
+                RequestOutcome::Refused
+            }
+            Err(PlainSightError::Ollama(
+                OllamaError::JsonPayload | OllamaError::InvalidStructuredOutput { .. },
+            )) => RequestOutcome::InvalidOutput,
+            Err(_) => RequestOutcome::Failed,
+        };
+        self.requests.push_outcome(TaskOutcome {
+            task: task.name().to_string(),
+            model: self.model_name(task).to_string(),
+            outcome,
+            duration_ms: telemetry::millis(started.elapsed()),
+        });
+        result
+    }
+
+    /// Per-file outputs that decline the task fail with `OllamaError::RefusalDetected`, so
+    /// callers can retry with a smaller prompt.
+    fn reject_refusal(&self, task: Task, out: String) -> Result<String> {
+        if utils::is_refusal_output(
+            &out,
+            self.config.refusal_detection,
+            &self.config.refusal_phrases,
+        ) {
+            return Err(OllamaError::RefusalDetected {
+                task,
+                model: self.model_name(task).to_string(),
+                output: out,
+            }
+            .into());
+        }
+        Ok(out)
     }
 
     fn postprocess_output(&self, task: Task, out: String) -> Result<String> {
-        let out = utils::strip_wrapping_code_fence(out);
-        let out = utils::unwrap_json_markdown(task, out);
-        let out = utils::strip_wrapping_code_fence(out);
-        let out = utils::trim_to_expected_heading(task, out);
-        let out = utils::strip_wrapping_code_fence(out);
-        let out = utils::reject_json_payload(out).map_err(PlainSightError::Ollama)?;
+        // Structured replies were rendered from their fields; there is nothing to unwrap.
+        let out = if self.config.structured_output {
+            out
+        } else {
+            let out = utils::strip_wrapping_code_fence(out);
+            let out = utils::unwrap_json_markdown(task, out);
+            let out = utils::strip_wrapping_code_fence(out);
+            let out = utils::trim_to_expected_heading(task, out);
+            let out = utils::strip_wrapping_code_fence(out);
+            utils::reject_json_payload(out)?
+        };
         let out = utils::ensure_ai_disclaimer(out);
-        utils::ensure_non_empty(task, self.model_name(task), out).map_err(PlainSightError::Ollama)
+        Ok(utils::ensure_non_empty(task, self.model_name(task), out)?)
+    }
+}
+
+/// Ollama reads `llama3` as `llama3:latest`.
+pub(super) fn with_default_tag(model: &str) -> String {
+    let name = model.rsplit('/').next().unwrap_or(model);
+    if name.contains(':') {
+        model.to_string()
+    } else {
+        format!("{model}:latest")
+    }
+}
+
+/// `retry_backoff * 2^attempt`, capped at `retry_max_backoff`, plus up to half of that again
+/// as jitter so concurrent requests don't retry in lockstep.
+fn backoff_delay(config: &OllamaConfig, attempt: u32) -> Duration {
+    let base = config
+        .retry_backoff
+        .saturating_mul(1 << attempt.min(16))
+        .min(config.retry_max_backoff);
+    let jitter_range = base.as_millis() as u64 / 2;
+    if jitter_range == 0 {
+        return base;
     }
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() as u64)
+        .unwrap_or_default();
+    base + Duration::from_millis(seed % (jitter_range + 1))
 }
@@ -1,18 +1,43 @@
-use std::time::Duration;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 use ollama_rs::models::ModelOptions;
+use serde::{Serialize, Serializer};
 
-use super::Task;
+use crate::{
+    error::{PlainSightError, Result},
+    prompt_context::PromptContext,
+};
+
+use super::{Task, prompts, utils};
 
 const DEFAULT_MODEL: &str = "phi4-mini-reasoning:lastest";
+const DEFAULT_EMBEDDING_MODEL: &str = "nomic-embed-text";
+const DEFAULT_HOST: &str = "http://127.0.0.1";
+const DEFAULT_PORT: u16 = 11434;
+const DEFAULT_KEEP_ALIVE: Duration = Duration::from_secs(30 * 60);
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct TaskConfig {
     pub model: String,
     pub temperature: f32,
     pub num_ctx: u64,
     pub num_predict: i32,
+    #[serde(serialize_with = "optional_seconds")]
     pub generate_timeout: Option<Duration>,
+    /// Requests this task's model may run at once, overriding `OllamaConfig::max_concurrency`.
+    /// Useful when tasks use models served with different parallelism.
+    pub max_concurrency: Option<usize>,
+    /// How long the server keeps this task's model loaded after a request; zero unloads it as
+    /// soon as the request is done.
+    #[serde(serialize_with = "seconds")]
+    pub keep_alive: Duration,
+    /// Whether a reasoning model thinks before replying. Thinking left in a reply is always
+    /// stripped before the output is used.
+    pub thinking: Thinking,
 }
 
 impl TaskConfig {
@@ -22,14 +47,118 @@ impl TaskConfig {
             .num_ctx(self.num_ctx)
             .num_predict(self.num_predict)
     }
+
+    /// This config with the options `overrides` sets replaced.
+    pub fn with_overrides(&self, overrides: &OptionOverrides) -> TaskConfig {
+        TaskConfig {
+            temperature: overrides.temperature.unwrap_or(self.temperature),
+            num_predict: overrides.num_predict.unwrap_or(self.num_predict),
+            ..self.clone()
+        }
+    }
+}
+
+/// Model and limits of `OllamaWrapper::embed`.
+#[derive(Debug, Clone, Serialize)]
+pub struct EmbeddingConfig {
+    pub model: String,
+    /// Context size per text; the server truncates longer texts to fit.
+    pub num_ctx: u64,
+    /// Texts sent in one request.
+    pub batch_size: usize,
+    #[serde(serialize_with = "optional_seconds")]
+    pub generate_timeout: Option<Duration>,
+    /// Requests the embedding model may run at once, overriding `OllamaConfig::max_concurrency`.
+    pub max_concurrency: Option<usize>,
+    #[serde(serialize_with = "seconds")]
+    pub keep_alive: Duration,
+}
+
+impl Default for EmbeddingConfig {
+    fn default() -> Self {
+        Self {
+            model: DEFAULT_EMBEDDING_MODEL.to_string(),
+            num_ctx: 2048,
+            batch_size: 32,
+            generate_timeout: None,
+            max_concurrency: None,
+            keep_alive: DEFAULT_KEEP_ALIVE,
+        }
+    }
+}
+
+/// Options replacing a task's own for one request.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct OptionOverrides {
+    pub temperature: Option<f32>,
+    pub num_predict: Option<i32>,
+}
+
+impl OptionOverrides {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// `other`'s options where it sets them, these otherwise.
+    fn then(self, other: Self) -> Self {
+        Self {
+            temperature: other.temperature.or(self.temperature),
+            num_predict: other.num_predict.or(self.num_predict),
+        }
+    }
+}
+
+/// Options for the file summaries and docs whose file meets every condition the rule sets.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OptionRule {
+    /// `summarize` and/or `documentation`; empty for both.
+    pub tasks: Vec<String>,
+    /// Detected languages (`rust`, `python`, ...); empty for any.
+    pub languages: Vec<String>,
+    pub min_lines: Option<usize>,
+    pub max_lines: Option<usize>,
+    /// Files with at least this many detected HTTP endpoints.
+    pub min_endpoints: Option<usize>,
+    #[serde(flatten)]
+    pub options: OptionOverrides,
+}
+
+impl OptionRule {
+    fn matches(&self, task: Task, context: &PromptContext) -> bool {
+        (self.tasks.is_empty() || self.tasks.iter().any(|name| name == task.name()))
+            && (self.languages.is_empty()
+                || self
+                    .languages
+                    .iter()
+                    .any(|language| language.eq_ignore_ascii_case(&context.language)))
+            && self.min_lines.is_none_or(|min| context.line_count >= min)
+            && self.max_lines.is_none_or(|max| context.line_count <= max)
+            && self
+                .min_endpoints
+                .is_none_or(|min| context.endpoints.len() >= min)
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct TaskProfiles {
     pub documentation: TaskConfig,
     pub project_summary: TaskConfig,
     pub architecture: TaskConfig,
     pub summarize: TaskConfig,
+    /// Directory summaries, when `PlainSightConfig::directory_summaries` is set.
+    pub directory_summary: TaskConfig,
+    /// The impact paragraph of `changes.md`, written when a run documents changed files.
+    pub change_summary: TaskConfig,
+    pub explain: TaskConfig,
+    /// The second look at file docs enabled by `OllamaConfig::enable_review_pass`; may use a
+    /// different model than `documentation`.
+    pub review: TaskConfig,
+    /// Vectors for semantic search; `set_model_for_all` leaves it alone, as chat models do not
+    /// embed.
+    pub embedding: EmbeddingConfig,
+    /// Per-file option overrides for summaries and docs, checked in order; when several
+    /// rules match, the later one wins for each option it sets.
+    pub rules: Vec<OptionRule>,
 }
 
 impl TaskProfiles {
@@ -39,15 +168,66 @@ impl TaskProfiles {
             Task::ProjectSummary => &self.project_summary,
             Task::Architecture => &self.architecture,
             Task::Summarize => &self.summarize,
+            Task::DirectorySummary => &self.directory_summary,
+            Task::ChangeSummary => &self.change_summary,
+            Task::Explain => &self.explain,
+            Task::Review => &self.review,
         }
     }
 
+    /// Model name per task, keyed by a stable task name.
+    ///
+    /// `explain` is left out: it produces no stored docs, so switching its model must not
+    /// trigger a regeneration. Neither are `review` and `directory_summary`, which only run
+    /// when enabled, nor `change_summary`, which only describes a run's changes.
+    pub fn models(&self) -> BTreeMap<String, String> {
+        [
+            ("documentation", &self.documentation),
+            ("project_summary", &self.project_summary),
+            ("architecture", &self.architecture),
+            ("summarize", &self.summarize),
+        ]
+        .into_iter()
+        .map(|(task, config)| (task.to_string(), config.model.clone()))
+        .collect()
+    }
+
+    /// `task`'s config with the options a request overrides.
+    pub fn for_request(&self, task: Task, overrides: &OptionOverrides) -> TaskConfig {
+        self.for_task(task).with_overrides(overrides)
+    }
+
+    /// The options `rules` set for `task` on the file described by `context`.
+    pub fn overrides_for(&self, task: Task, context: &PromptContext) -> OptionOverrides {
+        self.rules
+            .iter()
+            .filter(|rule| rule.matches(task, context))
+            .fold(OptionOverrides::default(), |overrides, rule| {
+                overrides.then(rule.options)
+            })
+    }
+
     pub fn set_model_for_all(&mut self, model: impl Into<String>) {
         let model = model.into();
         self.documentation.model = model.clone();
         self.project_summary.model = model.clone();
         self.architecture.model = model.clone();
-        self.summarize.model = model;
+        self.summarize.model = model.clone();
+        self.directory_summary.model = model.clone();
+        self.change_summary.model = model.clone();
+        self.explain.model = model.clone();
+        self.review.model = model;
+    }
+
+    pub fn set_thinking_for_all(&mut self, thinking: Thinking) {
+        self.documentation.thinking = thinking;
+        self.project_summary.thinking = thinking;
+        self.architecture.thinking = thinking;
+        self.summarize.thinking = thinking;
+        self.directory_summary.thinking = thinking;
+        self.change_summary.thinking = thinking;
+        self.explain.thinking = thinking;
+        self.review.thinking = thinking;
     }
 }
 
@@ -60,6 +240,9 @@ impl Default for TaskProfiles {
                 num_ctx: 4096,
                 num_predict: 900,
                 generate_timeout: None,
+                max_concurrency: None,
+                keep_alive: DEFAULT_KEEP_ALIVE,
+                thinking: Thinking::default(),
             },
             project_summary: TaskConfig {
                 model: DEFAULT_MODEL.to_string(),
@@ -67,6 +250,9 @@ impl Default for TaskProfiles {
                 num_ctx: 4096,
                 num_predict: 700,
                 generate_timeout: None,
+                max_concurrency: None,
+                keep_alive: DEFAULT_KEEP_ALIVE,
+                thinking: Thinking::default(),
             },
             architecture: TaskConfig {
                 model: DEFAULT_MODEL.to_string(),
@@ -74,6 +260,9 @@ impl Default for TaskProfiles {
                 num_ctx: 6144,
                 num_predict: 1000,
                 generate_timeout: None,
+                max_concurrency: None,
+                keep_alive: DEFAULT_KEEP_ALIVE,
+                thinking: Thinking::default(),
             },
             summarize: TaskConfig {
                 model: DEFAULT_MODEL.to_string(),
@@ -81,16 +270,227 @@ impl Default for TaskProfiles {
                 num_ctx: 4096,
                 num_predict: 300,
                 generate_timeout: None,
+                max_concurrency: None,
+                keep_alive: DEFAULT_KEEP_ALIVE,
+                thinking: Thinking::default(),
             },
+            directory_summary: TaskConfig {
+                model: DEFAULT_MODEL.to_string(),
+                temperature: 0.1,
+                num_ctx: 4096,
+                num_predict: 400,
+                generate_timeout: None,
+                max_concurrency: None,
+                keep_alive: DEFAULT_KEEP_ALIVE,
+                thinking: Thinking::default(),
+            },
+            change_summary: TaskConfig {
+                model: DEFAULT_MODEL.to_string(),
+                temperature: 0.1,
+                num_ctx: 4096,
+                num_predict: 300,
+                generate_timeout: None,
+                max_concurrency: None,
+                keep_alive: DEFAULT_KEEP_ALIVE,
+                thinking: Thinking::default(),
+            },
+            explain: TaskConfig {
+                model: DEFAULT_MODEL.to_string(),
+                temperature: 0.2,
+                num_ctx: 4096,
+                num_predict: 600,
+                generate_timeout: None,
+                max_concurrency: None,
+                keep_alive: DEFAULT_KEEP_ALIVE,
+                thinking: Thinking::default(),
+            },
+            review: TaskConfig {
+                model: DEFAULT_MODEL.to_string(),
+                temperature: 0.0,
+                num_ctx: 6144,
+                num_predict: 1000,
+                generate_timeout: None,
+                max_concurrency: None,
+                keep_alive: DEFAULT_KEEP_ALIVE,
+                thinking: Thinking::default(),
+            },
+            embedding: EmbeddingConfig::default(),
+            rules: Vec::new(),
         }
     }
 }
 
-#[derive(Debug, Clone)]
+/// Small files summarized together, one section per file. Not used with structured output, a
+/// `summarize` prompt override, or `pipeline_file_tasks` in effect.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct SummaryBatching {
+    pub enabled: bool,
+    /// Files with more lines are summarized on their own.
+    pub max_lines: usize,
+    /// Files per request at most; fewer when the prompt would not fit `tasks.summarize`'s
+    /// context window.
+    pub max_files: usize,
+}
+
+impl Default for SummaryBatching {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_lines: 30,
+            max_files: 8,
+        }
+    }
+}
+
+/// How a run backs off when its requests keep failing, instead of grinding through one slow
+/// timeout after another. Once `max_failures` request attempts have failed, retries included,
+/// or files have run over `OllamaConfig::file_timeout`, the remaining file requests start
+/// from the compact context, or, with a `cooldown`, nothing is sent for that long and the
+/// count starts again.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct RetryBudget {
+    /// `None` never backs off.
+    pub max_failures: Option<usize>,
+    #[serde(serialize_with = "optional_seconds")]
+    pub cooldown: Option<Duration>,
+}
+
+/// Reasoning toggle sent with a task's requests: Ollama's `think`, or `reasoning_effort` and
+/// the chat template's `enable_thinking` on OpenAI-compatible servers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Thinking {
+    /// Send no toggle and leave it to the model. Models without thinking support reject the
+    /// toggle, so this is the only safe choice for them.
+    #[default]
+    Model,
+    Off,
+    On,
+    /// Thinking effort, for models that take one (e.g. `gpt-oss`).
+    Low,
+    Medium,
+    High,
+}
+
+/// API spoken by the server at `OllamaConfig::host`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackendKind {
+    #[default]
+    Ollama,
+    /// `/v1/chat/completions`, as served by llama.cpp, vLLM or LM Studio.
+    OpenAiCompatible,
+}
+
+/// How replies are checked for the model declining the task; see `OllamaConfig::refusal_phrases`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RefusalDetection {
+    /// Never treat a reply as a refusal.
+    Off,
+    /// A phrase in the reply's first line, or two different phrases in its first paragraph
+    /// unless the reply opens with a heading.
+    #[default]
+    Lenient,
+    /// Any phrase anywhere in the reply.
+    Strict,
+}
+
+/// When a run unloads the models it used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelUnloading {
+    /// Unload a phase's models once it is done, and the file task models before the project
+    /// summary or architecture request when those use another model, so that only one model
+    /// is resident at a time on machines with little VRAM.
+    #[default]
+    AfterPhase,
+    /// Leave models loaded; each expires after its task's `keep_alive`.
+    KeepAlive,
+}
+
+/// Serializes with durations in seconds and `authorization` redacted.
+#[derive(Debug, Clone, Serialize)]
 pub struct OllamaConfig {
+    pub backend: BackendKind,
+    /// Scheme and host name of the server; use `https://` for TLS.
+    pub host: String,
+    pub port: u16,
+    /// Value of the `Authorization` header sent with every request, for servers behind an
+    /// authenticating reverse proxy (e.g. `Bearer <token>`).
+    #[serde(serialize_with = "redacted")]
+    pub authorization: Option<String>,
+    #[serde(serialize_with = "seconds")]
     pub lock_timeout: Duration,
+    /// Requests sent to the server at once. Raise it for multi-GPU or remote servers that
+    /// handle requests in parallel; `1` serializes every request.
+    pub max_concurrency: usize,
+    /// Requests sent per minute at most, retries and embedding batches included, so that a run
+    /// leaves room for other users of a shared server. `None` sends them as fast as
+    /// `max_concurrency` allows.
+    pub requests_per_minute: Option<u32>,
+    /// Requests that may be sent back to back after an idle spell before `requests_per_minute`
+    /// spaces them out.
+    pub rate_limit_burst: u32,
+    /// Run each file's docs request as soon as its summary is done instead of after every
+    /// summary, when the two tasks use different models. Both models stay loaded meanwhile.
+    pub pipeline_file_tasks: bool,
+    /// Summarize small files several to a request instead of one round trip each.
+    pub summary_batching: SummaryBatching,
+    /// Extra attempts after a transient failure: a timeout, a dropped connection, or an HTTP
+    /// 429/5xx reply.
+    pub max_retries: u32,
+    /// Delay before the first retry; it doubles on every further attempt up to
+    /// `retry_max_backoff`, and up to half of it is added as jitter.
+    #[serde(serialize_with = "seconds")]
+    pub retry_backoff: Duration,
+    #[serde(serialize_with = "seconds")]
+    pub retry_max_backoff: Duration,
+    #[serde(serialize_with = "seconds")]
     pub unload_timeout: Duration,
-    pub keep_alive_minutes: u64,
+    /// How long the health check at the start of a run waits for the server.
+    #[serde(serialize_with = "seconds")]
+    pub health_check_timeout: Duration,
+    /// Wall-clock budget for a file's summary, and again for its docs, retries and review
+    /// included. A file that runs over is skipped, listed in the run report and added to the
+    /// project's `.skip.json` so later runs leave it out too. `None` waits for every file.
+    #[serde(serialize_with = "optional_seconds")]
+    pub file_timeout: Option<Duration>,
+    pub retry_budget: RetryBudget,
+    /// When models are unloaded during a run; `TaskConfig::keep_alive` covers the rest.
+    pub model_unloading: ModelUnloading,
+    /// GPU memory the task models may fill together, in bytes. Task models that do not fit
+    /// together are not kept loaded side by side, and work is reordered so each is loaded once.
+    /// `None` estimates it from the running models when one of them spilled to the CPU.
+    pub gpu_memory: Option<u64>,
+    /// Pull task models the server does not have before generation starts, instead of failing
+    /// with `OllamaError::ModelNotFound`. Only the Ollama backend can pull.
+    pub pull_missing_models: bool,
+    /// Let file summaries and docs call the `query_file_source` and `query_project_memory`
+    /// tools. Turn it off for models without tool support.
+    pub use_tools: bool,
+    /// Rounds of tool calls answered before the model must reply without tools.
+    pub max_tool_rounds: usize,
+    /// Ask for a JSON object with one field per section (Ollama's `format`, OpenAI's
+    /// `response_format`) and render the Markdown from it, instead of cleaning up free-form
+    /// replies. Needs Ollama 0.5 or a server with JSON schema support.
+    pub structured_output: bool,
+    /// Send each file's generated docs back with the file's symbols (`tasks.review`), so the
+    /// model can remove or correct names that the code does not define. Doubles the docs
+    /// requests; the first draft is kept when the review fails.
+    pub enable_review_pass: bool,
+    /// File summaries and docs judged a refusal are retried with a smaller prompt.
+    pub refusal_detection: RefusalDetection,
+    /// Case-insensitive phrases that signal a refusal; defaults to `DEFAULT_REFUSAL_PHRASES`.
+    pub refusal_phrases: Vec<String>,
+    /// Instructions replacing the built-in ones, keyed by `Task::name`. `{{project_name}}` and
+    /// `{{task}}` are filled in; with `{{context}}` the template is sent as the user message
+    /// with the task's input substituted. Templates in `.plainsight/prompts/` under the project
+    /// root are added by `with_prompt_overrides_from` for tasks not set here.
+    pub prompt_overrides: BTreeMap<String, String>,
+    /// Save every prompt and raw reply of the text tasks to its own file in this directory,
+    /// with the model, options and attempt number. Embedding requests are not saved.
+    pub dump_prompts_dir: Option<PathBuf>,
     pub tasks: TaskProfiles,
 }
 
@@ -99,15 +499,143 @@ impl OllamaConfig {
         self.tasks.set_model_for_all(model);
         self
     }
+
+    /// Point at the server at `url` (`http[s]://host[:port]`). The port defaults to 443 for
+    /// `https` and 80 for `http` when omitted.
+    pub fn with_url(mut self, url: &str) -> Result<Self> {
+        let invalid = |reason: &str| {
+            PlainSightError::InvalidState(format!("invalid Ollama URL '{url}': {reason}"))
+        };
+        let (scheme, rest) = url
+            .split_once("://")
+            .ok_or_else(|| invalid("missing scheme"))?;
+        let default_port = match scheme {
+            "http" => 80,
+            "https" => 443,
+            _ => return Err(invalid("scheme must be http or https")),
+        };
+        let authority = rest.trim_end_matches('/');
+        if authority.is_empty() {
+            return Err(invalid("missing host"));
+        }
+        if authority.contains('/') {
+            return Err(invalid("paths are not supported"));
+        }
+
+        // `[::1]:11434` keeps its brackets; a bare `[::1]` has no port.
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) if !authority.ends_with(']') => {
+                let port = port.parse().map_err(|_| invalid("port is not a number"))?;
+                (host, port)
+            }
+            _ => (authority, default_port),
+        };
+        self.host = format!("{scheme}://{host}");
+        self.port = port;
+        Ok(self)
+    }
+
+    /// Add the templates under `<project_root>/.plainsight/prompts/` for tasks without an
+    /// override, and fill in `{{project_name}}` and `{{task}}` in every override.
+    pub fn with_prompt_overrides_from(
+        mut self,
+        project_root: &Path,
+        project_name: &str,
+    ) -> Result<Self> {
+        for (task, template) in prompts::read_prompt_overrides(project_root)? {
+            self.prompt_overrides.entry(task).or_insert(template);
+        }
+        for (task, template) in &mut self.prompt_overrides {
+            *template = prompts::render_prompt_override(template, task, project_name);
+        }
+        Ok(self)
+    }
+
+    /// Requests `task`'s model may run at once.
+    pub fn max_concurrency_for(&self, task: Task) -> usize {
+        self.tasks
+            .for_task(task)
+            .max_concurrency
+            .unwrap_or(self.max_concurrency)
+            .max(1)
+    }
+
+    /// Requests the embedding model may run at once.
+    pub fn max_embedding_concurrency(&self) -> usize {
+        self.tasks
+            .embedding
+            .max_concurrency
+            .unwrap_or(self.max_concurrency)
+            .max(1)
+    }
+
+    pub(crate) fn base_url(&self) -> String {
+        format!("{}:{}", self.host.trim_end_matches('/'), self.port)
+    }
 }
 
 impl Default for OllamaConfig {
     fn default() -> Self {
         Self {
+            backend: BackendKind::default(),
+            host: DEFAULT_HOST.to_string(),
+            port: DEFAULT_PORT,
+            authorization: None,
             lock_timeout: Duration::from_secs(30),
+            max_concurrency: 1,
+            requests_per_minute: None,
+            rate_limit_burst: 1,
+            pipeline_file_tasks: false,
+            summary_batching: SummaryBatching::default(),
+            max_retries: 2,
+            retry_backoff: Duration::from_secs(2),
+            retry_max_backoff: Duration::from_secs(30),
             unload_timeout: Duration::from_secs(30),
-            keep_alive_minutes: 30,
+            health_check_timeout: Duration::from_secs(10),
+            file_timeout: None,
+            retry_budget: RetryBudget::default(),
+            model_unloading: ModelUnloading::default(),
+            gpu_memory: None,
+            pull_missing_models: false,
+            use_tools: true,
+            max_tool_rounds: 4,
+            structured_output: false,
+            enable_review_pass: false,
+            refusal_detection: RefusalDetection::default(),
+            refusal_phrases: utils::DEFAULT_REFUSAL_PHRASES
+                .iter()
+                .map(|phrase| phrase.to_string())
+                .collect(),
+            prompt_overrides: BTreeMap::new(),
+            dump_prompts_dir: None,
             tasks: TaskProfiles::default(),
         }
     }
 }
+
+fn seconds<S: Serializer>(
+    duration: &Duration,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64())
+}
+
+fn optional_seconds<S: Serializer>(
+    duration: &Option<Duration>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    match duration {
+        Some(duration) => seconds(duration, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+fn redacted<S: Serializer>(
+    secret: &Option<String>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    match secret {
+        Some(_) => serializer.serialize_str("<redacted>"),
+        None => serializer.serialize_none(),
+    }
+}
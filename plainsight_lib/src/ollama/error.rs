@@ -0,0 +1,115 @@
+use std::time::Duration;
+
+use thiserror::Error;
+
+use super::Task;
+
+/// Failure of a request to the LLM server, from either backend, or of its reply.
+#[derive(Debug, Error)]
+pub enum OllamaError {
+    /// No reply within the task's `generate_timeout`.
+    #[error(
+        "request timeout after {} seconds ({model}) - model may have been killed or is in 'Stopping...' state",
+        .after.as_secs()
+    )]
+    Timeout { model: String, after: Duration },
+
+    /// The server could not be reached, or dropped the connection mid-request.
+    #[error("connection refused ({model}): {message}")]
+    ConnectionRefused { model: String, message: String },
+
+    /// The health check at the start of a run got no answer from the server.
+    #[error("LLM server at {url} is unreachable: {message}")]
+    Unreachable { url: String, message: String },
+
+    /// The server does not have the model; for Ollama, `ollama pull` it first.
+    #[error("model '{model}' not found on the server")]
+    ModelNotFound { model: String },
+
+    /// The server answered with a non-success HTTP status.
+    #[error("server returned {status} ({model}): {detail}")]
+    Http {
+        model: String,
+        status: u16,
+        detail: String,
+    },
+
+    #[error("empty output for task {task:?} ({model})")]
+    EmptyOutput { task: Task, model: String },
+
+    /// The model declined the task. `output` is the reply, for callers that want to inspect it.
+    #[error("model refused task {task:?} ({model})")]
+    RefusalDetected {
+        task: Task,
+        model: String,
+        output: String,
+    },
+
+    #[error("model returned a JSON payload instead of markdown")]
+    JsonPayload,
+
+    /// A `structured_output` reply that is not a JSON object holding the task's sections.
+    #[error("invalid structured output for task {task:?} ({model}): {detail}")]
+    InvalidStructuredOutput {
+        task: Task,
+        model: String,
+        detail: String,
+    },
+
+    /// The prompt context handed to the wrapper was not valid JSON.
+    #[error("invalid prompt payload: {0}")]
+    InvalidPayload(String),
+
+    #[error("{0}")]
+    Other(String),
+}
+
+impl OllamaError {
+    /// Whether sending the same request again may succeed: timeouts, dropped connections,
+    /// rate limiting and server-side errors.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Timeout { .. } | Self::ConnectionRefused { .. } => true,
+            Self::Http { status, .. } => *status == 429 || *status >= 500,
+            _ => false,
+        }
+    }
+
+    pub(super) fn from_ollama_rs(model: &str, err: ollama_rs::error::OllamaError) -> Self {
+        match err {
+            ollama_rs::error::OllamaError::ReqwestError(err) => Self::from_reqwest(model, err),
+            // Ollama answers `model "<name>" not found, try pulling it first`.
+            ollama_rs::error::OllamaError::InternalError(err)
+                if err.message.contains("not found") =>
+            {
+                Self::ModelNotFound {
+                    model: model.to_string(),
+                }
+            }
+            // Embedding requests pass the server's error body through as is.
+            ollama_rs::error::OllamaError::Other(message) if message.contains("not found") => {
+                Self::ModelNotFound {
+                    model: model.to_string(),
+                }
+            }
+            err => Self::Other(format!("{err} ({model})")),
+        }
+    }
+
+    pub(super) fn from_reqwest(model: &str, err: reqwest::Error) -> Self {
+        if err.is_connect() || err.is_timeout() || err.is_request() || err.is_body() {
+            return Self::ConnectionRefused {
+                model: model.to_string(),
+                message: err.to_string(),
+            };
+        }
+        match err.status() {
+            Some(status) => Self::Http {
+                model: model.to_string(),
+                status: status.as_u16(),
+                detail: err.to_string(),
+            },
+            None => Self::Other(format!("{err} ({model})")),
+        }
+    }
+}
@@ -1,14 +1,53 @@
+mod backend;
 mod client;
 mod config;
+mod error;
+mod openai;
 mod prompts;
+mod rate_limit;
+mod residency;
+mod structured;
 mod task;
+mod telemetry;
+mod token_budget;
 mod tools;
+mod transcripts;
 mod utils;
 
+pub use backend::{LlmBackend, OllamaBackend};
 pub use client::OllamaWrapper;
-pub use config::{OllamaConfig, TaskConfig, TaskProfiles};
+pub use config::{
+    BackendKind, EmbeddingConfig, ModelUnloading, OllamaConfig, OptionOverrides, OptionRule,
+    RefusalDetection, RetryBudget, SummaryBatching, TaskConfig, TaskProfiles, Thinking,
+};
+pub use error::OllamaError;
+pub use openai::OpenAiBackend;
+pub use prompts::{PROMPT_OVERRIDES_DIR, Prompt, read_prompt_overrides};
+pub use residency::{ModelMemory, ModelResidency, RunningModel};
 pub use task::Task;
+pub use telemetry::{
+    GenerationResult, RequestLog, RequestOutcome, RequestRecord, TaskOutcome, TokenUsage,
+    record_usage, with_target,
+};
+pub use token_budget::{PromptFit, TokenBudget, estimate_tokens};
+pub use utils::DEFAULT_REFUSAL_PHRASES;
 
-pub fn is_refusal_output(output: &str) -> bool {
-    utils::is_refusal_output(output)
+/// Whether `output` reads as a refusal under `config`'s `refusal_detection` and phrases.
+pub fn is_refusal_output(output: &str, config: &OllamaConfig) -> bool {
+    utils::is_refusal_output(output, config.refusal_detection, &config.refusal_phrases)
+}
+
+/// Whether `text` is a document PlainSight generated, recognised by its leading disclaimer.
+pub fn is_generated_output(text: &str) -> bool {
+    utils::has_ai_disclaimer(text)
+}
+
+/// Whether `line` is a heading the prompts ask the model to write: a `## ` section such as
+/// `## Purpose`, or the `# File: <path>` of a batched summary.
+pub fn is_output_heading(line: &str) -> bool {
+    let line = line.trim();
+    line.starts_with(utils::BATCH_FILE_HEADING)
+        || line.strip_prefix("## ").is_some_and(|heading| {
+            structured::is_section_heading(heading.trim_end_matches([' ', '#']))
+        })
 }
@@ -0,0 +1,336 @@
+use async_trait::async_trait;
+use reqwest::header::{AUTHORIZATION, HeaderMap, HeaderValue};
+use serde::Deserialize;
+use tokio::time;
+use tracing::debug;
+
+use crate::error::{PlainSightError, Result};
+
+use super::{
+    LlmBackend, OllamaConfig, OllamaError, Prompt, Task, Thinking, TokenUsage, backend::ModelSlots,
+    telemetry,
+};
+
+const CHAT_COMPLETIONS_PATH: &str = "/v1/chat/completions";
+const MODELS_PATH: &str = "/v1/models";
+const EMBEDDINGS_PATH: &str = "/v1/embeddings";
+
+#[derive(Debug, Deserialize)]
+struct ModelList {
+    data: Vec<ModelEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModelEntry {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatCompletion {
+    choices: Vec<Choice>,
+    #[serde(default)]
+    usage: Option<Usage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Usage {
+    #[serde(default)]
+    prompt_tokens: u64,
+    #[serde(default)]
+    completion_tokens: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingList {
+    data: Vec<EmbeddingEntry>,
+    #[serde(default)]
+    usage: Option<Usage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingEntry {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Choice {
+    message: ChoiceMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChoiceMessage {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+/// Any server exposing the OpenAI `/v1/chat/completions` API, such as llama.cpp's
+/// `llama-server`, vLLM or LM Studio.
+///
+/// Tool calling is not used, and models can be neither unloaded nor pulled over this API:
+/// `unload` only logs.
+pub struct OpenAiBackend {
+    client: reqwest::Client,
+    endpoint: String,
+    config: OllamaConfig,
+    slots: ModelSlots,
+}
+
+impl OpenAiBackend {
+    /// Send requests to `config.host`/`config.port`, with `config.authorization` (usually
+    /// `Bearer <api key>`) attached when set.
+    pub fn with_config(config: OllamaConfig) -> Result<Self> {
+        let mut headers = HeaderMap::new();
+        if let Some(authorization) = &config.authorization {
+            let mut value = HeaderValue::from_str(authorization).map_err(|_| {
+                PlainSightError::InvalidState(
+                    "LLM authorization contains characters not allowed in a header".to_string(),
+                )
+            })?;
+            value.set_sensitive(true);
+            headers.insert(AUTHORIZATION, value);
+        }
+        let client = reqwest::Client::builder()
+            .default_headers(headers)
+            .build()
+            .map_err(|e| {
+                PlainSightError::InvalidState(format!("failed to build HTTP client: {e}"))
+            })?;
+
+        let endpoint = format!("{}{CHAT_COMPLETIONS_PATH}", config.base_url());
+        debug!(endpoint = %endpoint, authorization = config.authorization.is_some(), "openai_client_configured");
+        Ok(Self {
+            client,
+            endpoint,
+            slots: ModelSlots::new(&config),
+            config,
+        })
+    }
+
+    /// POST `body` to `url`, turning failed replies into errors for `model`.
+    async fn post(
+        &self,
+        url: &str,
+        model: &str,
+        body: serde_json::Value,
+    ) -> Result<reqwest::Response> {
+        let response = self
+            .client
+            .post(url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| OllamaError::from_reqwest(model, e))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let detail = response.text().await.unwrap_or_default();
+            if status.as_u16() == 404 && detail.contains("model") {
+                return Err(OllamaError::ModelNotFound {
+                    model: model.to_string(),
+                }
+                .into());
+            }
+            return Err(OllamaError::Http {
+                model: model.to_string(),
+                status: status.as_u16(),
+                detail: detail.trim().to_string(),
+            }
+            .into());
+        }
+        Ok(response)
+    }
+
+    async fn complete(&self, model: &str, body: serde_json::Value) -> Result<String> {
+        let completion = self
+            .post(&self.endpoint, model, body)
+            .await?
+            .json::<ChatCompletion>()
+            .await
+            .map_err(|e| OllamaError::Other(format!("invalid response ({model}): {e}")))?;
+        if let Some(usage) = &completion.usage {
+            telemetry::record_usage(TokenUsage {
+                prompt_tokens: usage.prompt_tokens,
+                completion_tokens: usage.completion_tokens,
+                eval_duration_ms: 0,
+            });
+        }
+        completion
+            .choices
+            .into_iter()
+            .next()
+            .and_then(|choice| choice.message.content)
+            .ok_or_else(|| OllamaError::Other(format!("response without content ({model})")).into())
+    }
+
+    /// Send `prompt` to the task's model; `response_format` is passed through when set.
+    async fn chat(
+        &self,
+        task: Task,
+        prompt: &Prompt,
+        response_format: Option<serde_json::Value>,
+    ) -> Result<String> {
+        let model_cfg = self.config.tasks.for_request(task, &prompt.options);
+
+        let _permit = self
+            .slots
+            .acquire(&model_cfg.model, self.config.lock_timeout)
+            .await?;
+
+        let mut messages = Vec::with_capacity(2);
+        if !prompt.system.is_empty() {
+            messages.push(serde_json::json!({ "role": "system", "content": prompt.system }));
+        }
+        messages.push(serde_json::json!({ "role": "user", "content": prompt.user }));
+
+        // `num_ctx` has no counterpart here; the context size is fixed when the server starts.
+        let mut body = serde_json::json!({
+            "model": model_cfg.model,
+            "messages": messages,
+            "temperature": model_cfg.temperature,
+            "stream": false,
+        });
+        if model_cfg.num_predict > 0 {
+            body["max_tokens"] = serde_json::json!(model_cfg.num_predict);
+        }
+        if let Some(response_format) = response_format {
+            body["response_format"] = response_format;
+        }
+        match model_cfg.thinking {
+            Thinking::Model => {}
+            Thinking::Off | Thinking::On => {
+                let enable_thinking = model_cfg.thinking == Thinking::On;
+                body["chat_template_kwargs"] =
+                    serde_json::json!({ "enable_thinking": enable_thinking });
+            }
+            Thinking::Low => body["reasoning_effort"] = serde_json::json!("low"),
+            Thinking::Medium => body["reasoning_effort"] = serde_json::json!("medium"),
+            Thinking::High => body["reasoning_effort"] = serde_json::json!("high"),
+        }
+
+        let request = self.complete(&model_cfg.model, body);
+        match model_cfg.generate_timeout {
+            Some(generate_timeout) => time::timeout(generate_timeout, request)
+                .await
+                .unwrap_or_else(|_| {
+                    Err(OllamaError::Timeout {
+                        model: model_cfg.model.clone(),
+                        after: generate_timeout,
+                    }
+                    .into())
+                }),
+            None => request.await,
+        }
+    }
+}
+
+#[async_trait]
+impl LlmBackend for OpenAiBackend {
+    async fn generate(&self, task: Task, prompt: &Prompt) -> Result<String> {
+        self.chat(task, prompt, None).await
+    }
+
+    /// Sent as a `json_schema` response format; `tools` is ignored like everywhere else here.
+    async fn generate_structured(
+        &self,
+        task: Task,
+        prompt: &Prompt,
+        schema: &serde_json::Value,
+        _tools: bool,
+    ) -> Result<String> {
+        let response_format = serde_json::json!({
+            "type": "json_schema",
+            "json_schema": { "name": task.name(), "schema": schema },
+        });
+        self.chat(task, prompt, Some(response_format)).await
+    }
+
+    /// `OllamaConfig::tasks.embedding.num_ctx` has no counterpart here and is not sent.
+    async fn embed(&self, model: &str, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let embedding_cfg = &self.config.tasks.embedding;
+
+        let _permit = self.slots.acquire(model, self.config.lock_timeout).await?;
+
+        let url = format!("{}{EMBEDDINGS_PATH}", self.config.base_url());
+        let body = serde_json::json!({ "model": model, "input": texts });
+        let request = async {
+            self.post(&url, model, body)
+                .await?
+                .json::<EmbeddingList>()
+                .await
+                .map_err(|e| {
+                    PlainSightError::from(OllamaError::Other(format!(
+                        "invalid response ({model}): {e}"
+                    )))
+                })
+        };
+        let mut embeddings = match embedding_cfg.generate_timeout {
+            Some(generate_timeout) => time::timeout(generate_timeout, request)
+                .await
+                .unwrap_or_else(|_| {
+                    Err(OllamaError::Timeout {
+                        model: model.to_string(),
+                        after: generate_timeout,
+                    }
+                    .into())
+                })?,
+            None => request.await?,
+        };
+        if let Some(usage) = &embeddings.usage {
+            telemetry::record_usage(TokenUsage {
+                prompt_tokens: usage.prompt_tokens,
+                completion_tokens: 0,
+                eval_duration_ms: 0,
+            });
+        }
+
+        embeddings.data.sort_by_key(|entry| entry.index);
+        if embeddings.data.len() != texts.len() {
+            return Err(OllamaError::Other(format!(
+                "expected {} embeddings, got {} ({model})",
+                texts.len(),
+                embeddings.data.len()
+            ))
+            .into());
+        }
+        Ok(embeddings
+            .data
+            .into_iter()
+            .map(|entry| entry.embedding)
+            .collect())
+    }
+
+    async fn unload(&self, model: &str) -> Result<()> {
+        debug!(model, "unload_not_supported_by_openai_backend");
+        Ok(())
+    }
+
+    /// Servers without `/v1/models` are treated as unable to list their models.
+    async fn available_models(&self) -> Result<Option<Vec<String>>> {
+        let url = format!("{}{MODELS_PATH}", self.config.base_url());
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| OllamaError::from_reqwest("list models", e))?;
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            let detail = response.text().await.unwrap_or_default();
+            return Err(OllamaError::Http {
+                model: "list models".to_string(),
+                status: status.as_u16(),
+                detail: detail.trim().to_string(),
+            }
+            .into());
+        }
+        let models = response
+            .json::<ModelList>()
+            .await
+            .map_err(|e| OllamaError::Other(format!("invalid model list: {e}")))?;
+        Ok(Some(models.data.into_iter().map(|model| model.id).collect()))
+    }
+}
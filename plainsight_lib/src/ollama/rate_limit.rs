@@ -0,0 +1,63 @@
+use std::{
+    sync::{Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use tracing::debug;
+
+/// Token bucket holding back the requests of one `OllamaWrapper` to
+/// `OllamaConfig::requests_per_minute`.
+///
+/// The bucket holds up to `burst` tokens and gains one every `60 / requests_per_minute`
+/// seconds; each request attempt takes one, waiting for it when the bucket is empty.
+#[derive(Debug)]
+pub(super) struct RateLimiter {
+    interval: Duration,
+    burst: f64,
+    bucket: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl RateLimiter {
+    /// `None` when `requests_per_minute` is zero.
+    pub(super) fn new(requests_per_minute: u32, burst: u32) -> Option<Self> {
+        if requests_per_minute == 0 {
+            return None;
+        }
+        let burst = f64::from(burst.max(1));
+        Some(Self {
+            interval: Duration::from_secs(60) / requests_per_minute,
+            burst,
+            bucket: Mutex::new(Bucket {
+                tokens: burst,
+                refilled_at: Instant::now(),
+            }),
+        })
+    }
+
+    /// Wait until a request may be sent and take its token.
+    pub(super) async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().unwrap_or_else(PoisonError::into_inner);
+                let now = Instant::now();
+                let gained = now.duration_since(bucket.refilled_at).as_secs_f64()
+                    / self.interval.as_secs_f64();
+                bucket.tokens = (bucket.tokens + gained).min(self.burst);
+                bucket.refilled_at = now;
+                if bucket.tokens >= 1.0 {
+                    bucket.tokens -= 1.0;
+                    return;
+                }
+                self.interval.mul_f64(1.0 - bucket.tokens)
+            };
+            debug!(wait_ms = wait.as_millis() as u64, "rate_limited");
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
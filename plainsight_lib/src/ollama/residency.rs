@@ -0,0 +1,95 @@
+use std::collections::BTreeMap;
+
+use super::client::with_default_tag;
+
+/// Memory a model takes once loaded beyond its weights (context cache, compute buffers), as a
+/// share of the weights. Only used for models that are not running, whose loaded size the
+/// server cannot report yet.
+const LOAD_OVERHEAD: f64 = 0.2;
+
+/// Model sizes as the server reports them; `/api/tags` and `/api/ps` on Ollama.
+#[derive(Debug, Clone, Default)]
+pub struct ModelMemory {
+    /// Size of each model's weights, by model name.
+    pub weights: BTreeMap<String, u64>,
+    pub running: Vec<RunningModel>,
+}
+
+/// A model the server has loaded.
+#[derive(Debug, Clone)]
+pub struct RunningModel {
+    pub name: String,
+    /// Bytes the loaded model takes, context cache included.
+    pub size: u64,
+    /// The part of `size` in GPU memory; less than `size` when the model spilled to the CPU.
+    pub size_vram: u64,
+}
+
+/// Which task models fit in GPU memory at the same time.
+#[derive(Debug, Clone, Default)]
+pub struct ModelResidency {
+    /// Loaded size of each model the server has, by name with its tag.
+    loaded_bytes: BTreeMap<String, u64>,
+    capacity: Option<u64>,
+}
+
+impl ModelResidency {
+    /// The capacity is `gpu_memory` when set. Otherwise, when a running model spilled to the
+    /// CPU, the GPU is taken to be full at what the running models fill of it; with nothing
+    /// spilled it is unknown.
+    pub(super) fn new(memory: Option<ModelMemory>, gpu_memory: Option<u64>) -> Self {
+        let Some(memory) = memory else {
+            return Self {
+                loaded_bytes: BTreeMap::new(),
+                capacity: gpu_memory,
+            };
+        };
+        let mut loaded_bytes = memory
+            .weights
+            .iter()
+            .map(|(name, weights)| {
+                let loaded = *weights as f64 * (1.0 + LOAD_OVERHEAD);
+                (with_default_tag(name), loaded as u64)
+            })
+            .collect::<BTreeMap<_, _>>();
+        for model in &memory.running {
+            loaded_bytes.insert(with_default_tag(&model.name), model.size);
+        }
+        let spilled = memory
+            .running
+            .iter()
+            .any(|model| model.size_vram < model.size);
+        let filled = memory.running.iter().map(|model| model.size_vram).sum();
+        Self {
+            loaded_bytes,
+            capacity: gpu_memory.or(spilled.then_some(filled)),
+        }
+    }
+
+    /// GPU memory the models may fill together, when known.
+    pub fn capacity(&self) -> Option<u64> {
+        self.capacity
+    }
+
+    /// Whether `models` can all be loaded at once. True when the capacity or the size of one
+    /// of them is unknown, so that the run keeps its usual order.
+    pub fn fit_together(&self, models: &[&str]) -> bool {
+        let Some(capacity) = self.capacity else {
+            return true;
+        };
+        let mut names = models
+            .iter()
+            .map(|model| with_default_tag(model))
+            .collect::<Vec<_>>();
+        names.sort();
+        names.dedup();
+        let mut total = 0u64;
+        for name in &names {
+            let Some(bytes) = self.loaded_bytes.get(name) else {
+                return true;
+            };
+            total = total.saturating_add(*bytes);
+        }
+        total <= capacity
+    }
+}
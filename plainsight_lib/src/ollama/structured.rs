@@ -0,0 +1,192 @@
+use serde_json::{Map, Value, json};
+
+use super::{OllamaError, Prompt, Task};
+
+/// Instruction lines that only make sense for Markdown replies; dropped in structured mode.
+const MARKDOWN_ONLY_LINES: [&str; 2] =
+    ["Return Markdown only.", "Start the first non-comment line"];
+
+/// One `## ` section of a task's output and whether the reply must fill it.
+struct Section {
+    heading: &'static str,
+    required: bool,
+}
+
+const fn required(heading: &'static str) -> Section {
+    Section {
+        heading,
+        required: true,
+    }
+}
+
+const fn optional(heading: &'static str) -> Section {
+    Section {
+        heading,
+        required: false,
+    }
+}
+
+const SUMMARY_SECTIONS: &[Section] = &[required("Purpose"), required("Key Elements")];
+
+const DOCS_SECTIONS: &[Section] = &[
+    required("Overview"),
+    required("Public API"),
+    required("Behavior and Errors"),
+    required("Example"),
+    optional("Performance Characteristics"),
+    optional("Safety"),
+    optional("Testing"),
+    optional("Open Questions"),
+];
+
+const PROJECT_SUMMARY_SECTIONS: &[Section] = &[
+    required("Overview"),
+    required("Core Components"),
+    required("How It Fits Together"),
+    required("Dependencies and Integrations"),
+    optional("Persistence Layer"),
+    required("Notable Design Choices"),
+    optional("Maintainers"),
+];
+
+const DIRECTORY_SUMMARY_SECTIONS: &[Section] = &[required("Overview"), required("Contents")];
+
+const CHANGE_SUMMARY_SECTIONS: &[Section] = &[required("Impact")];
+
+const ARCHITECTURE_SECTIONS: &[Section] = &[
+    required("System Context"),
+    required("Component Topology"),
+    required("Data and Control Flow"),
+    required("Interfaces and Contracts"),
+    required("Operational Concerns"),
+    required("Extension Points"),
+];
+
+const EXPLAIN_SECTIONS: &[Section] = &[
+    required("Explanation"),
+    required("Context"),
+    required("Notes"),
+];
+
+/// Sections in the order the task's instructions list them.
+fn sections(task: Task) -> &'static [Section] {
+    match task {
+        Task::Summarize => SUMMARY_SECTIONS,
+        Task::Documentation | Task::Review => DOCS_SECTIONS,
+        Task::ProjectSummary => PROJECT_SUMMARY_SECTIONS,
+        Task::DirectorySummary => DIRECTORY_SUMMARY_SECTIONS,
+        Task::ChangeSummary => CHANGE_SUMMARY_SECTIONS,
+        Task::Architecture => ARCHITECTURE_SECTIONS,
+        Task::Explain => EXPLAIN_SECTIONS,
+    }
+}
+
+/// Whether `heading` names a `## ` section of any task's output.
+pub(super) fn is_section_heading(heading: &str) -> bool {
+    Task::ALL
+        .into_iter()
+        .flat_map(sections)
+        .any(|section| section.heading.eq_ignore_ascii_case(heading))
+}
+
+/// `Key Elements` becomes `key_elements`.
+fn field_name(heading: &str) -> String {
+    heading
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// JSON schema of a structured reply for `task`: one string field per section.
+pub fn output_schema(task: Task) -> Value {
+    let mut properties = Map::new();
+    let mut required_fields = Vec::new();
+    for section in sections(task) {
+        let field = field_name(section.heading);
+        properties.insert(field.clone(), json!({ "type": "string" }));
+        if section.required {
+            required_fields.push(field);
+        }
+    }
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required_fields,
+    })
+}
+
+/// `prompt` asking for the JSON object of `output_schema` instead of Markdown.
+pub fn structured_prompt(task: Task, prompt: &Prompt) -> Prompt {
+    let mut system = prompt
+        .system
+        .lines()
+        .filter(|line| {
+            !MARKDOWN_ONLY_LINES
+                .iter()
+                .any(|prefix| line.starts_with(prefix))
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    system.push_str(
+        "\nReply with one JSON object instead of Markdown. Each field holds the body of one section, as Markdown without its `## ` heading:\n",
+    );
+    for section in sections(task) {
+        let presence = if section.required {
+            "required"
+        } else {
+            "optional; omit it when the section does not apply"
+        };
+        system.push_str(&format!(
+            "- `{}`: `## {}` ({presence})\n",
+            field_name(section.heading),
+            section.heading
+        ));
+    }
+    Prompt {
+        system,
+        user: prompt.user.clone(),
+        options: prompt.options,
+    }
+}
+
+/// Markdown for a structured `reply`: each filled field under its heading, in section order.
+/// Fields outside the schema are ignored.
+pub fn render_markdown(task: Task, model: &str, reply: &str) -> Result<String, OllamaError> {
+    let invalid = |detail: String| OllamaError::InvalidStructuredOutput {
+        task,
+        model: model.to_string(),
+        detail,
+    };
+    let fields = match serde_json::from_str::<Value>(reply.trim()) {
+        Ok(Value::Object(fields)) => fields,
+        Ok(_) => return Err(invalid("reply is not a JSON object".to_string())),
+        Err(e) => return Err(invalid(e.to_string())),
+    };
+
+    let mut out = String::new();
+    for section in sections(task) {
+        let field = field_name(section.heading);
+        let body = match fields.get(&field) {
+            Some(Value::String(body)) => body.trim(),
+            Some(Value::Null) | None => "",
+            Some(_) => return Err(invalid(format!("field `{field}` is not a string"))),
+        };
+        let body = body
+            .strip_prefix(&format!("## {}", section.heading))
+            .unwrap_or(body)
+            .trim();
+        if body.is_empty() {
+            if section.required {
+                return Err(invalid(format!("missing required field `{field}`")));
+            }
+            continue;
+        }
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&format!("## {}\n{body}\n", section.heading));
+    }
+    Ok(out)
+}
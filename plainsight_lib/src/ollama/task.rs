@@ -4,4 +4,41 @@ pub enum Task {
     ProjectSummary,
     Architecture,
     Summarize,
+    /// Roll a directory's file and subdirectory summaries up into one; runs when
+    /// `PlainSightConfig::directory_summaries` is set.
+    DirectorySummary,
+    /// Describe the impact of the source changes a run documented, for `changes.md`.
+    ChangeSummary,
+    /// Explain a selected line range on request; nothing is written to the docs root.
+    Explain,
+    /// Check generated file docs against the file's symbols and fix invented ones; runs when
+    /// `OllamaConfig::enable_review_pass` is set.
+    Review,
+}
+
+impl Task {
+    pub const ALL: [Task; 8] = [
+        Task::Documentation,
+        Task::ProjectSummary,
+        Task::Architecture,
+        Task::Summarize,
+        Task::DirectorySummary,
+        Task::ChangeSummary,
+        Task::Explain,
+        Task::Review,
+    ];
+
+    /// Stable name used in config keys and prompt override file names.
+    pub fn name(self) -> &'static str {
+        match self {
+            Task::Documentation => "documentation",
+            Task::ProjectSummary => "project_summary",
+            Task::Architecture => "architecture",
+            Task::Summarize => "summarize",
+            Task::DirectorySummary => "directory_summary",
+            Task::ChangeSummary => "change_summary",
+            Task::Explain => "explain",
+            Task::Review => "review",
+        }
+    }
 }
@@ -0,0 +1,189 @@
+use std::{
+    cell::Cell,
+    sync::{Mutex, PoisonError},
+    time::Duration,
+};
+
+use serde::Serialize;
+
+tokio::task_local! {
+    static REQUEST_USAGE: Cell<TokenUsage>;
+    static REQUEST_TARGET: String;
+}
+
+/// Tokens the server reported for a request; zero when it reports none.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// Time the server spent generating the reply (Ollama's `eval_duration`).
+    pub eval_duration_ms: u64,
+}
+
+impl TokenUsage {
+    fn add(self, other: Self) -> Self {
+        Self {
+            prompt_tokens: self.prompt_tokens + other.prompt_tokens,
+            completion_tokens: self.completion_tokens + other.completion_tokens,
+            eval_duration_ms: self.eval_duration_ms + other.eval_duration_ms,
+        }
+    }
+}
+
+/// A task reply with the model that wrote it and what the server reported for it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenerationResult {
+    pub text: String,
+    pub model: String,
+    /// Reply tokens of the attempt that succeeded, every tool round included (Ollama's
+    /// `eval_count`); zero when the server reports none.
+    pub eval_count: u64,
+    /// Prompt tokens of the attempt that succeeded, every tool round included (Ollama's
+    /// `prompt_eval_count`); zero when the server reports none.
+    pub prompt_eval_count: u64,
+    /// Time from the first attempt to the reply, failed attempts and their backoff included.
+    pub total_duration: Duration,
+}
+
+impl GenerationResult {
+    pub(super) fn new(
+        text: String,
+        model: &str,
+        usage: TokenUsage,
+        total_duration: Duration,
+    ) -> Self {
+        Self {
+            text,
+            model: model.to_string(),
+            eval_count: usage.completion_tokens,
+            prompt_eval_count: usage.prompt_tokens,
+            total_duration,
+        }
+    }
+
+    /// This result with its text replaced by what `f` makes of it.
+    pub fn try_map_text<E>(
+        self,
+        f: impl FnOnce(String) -> std::result::Result<String, E>,
+    ) -> std::result::Result<Self, E> {
+        let text = f(self.text)?;
+        Ok(Self { text, ..self })
+    }
+}
+
+/// One attempt at a request, as sent by `OllamaWrapper`.
+#[derive(Debug, Clone, Serialize)]
+pub struct RequestRecord {
+    /// `Task::name` of the request.
+    pub task: String,
+    pub model: String,
+    /// File the request was made for, when the caller set one with `with_target`.
+    pub target: Option<String>,
+    /// Bytes of the system and user messages.
+    pub prompt_bytes: usize,
+    pub duration_ms: u64,
+    #[serde(flatten)]
+    pub usage: TokenUsage,
+    pub ok: bool,
+}
+
+/// How a task request ended, once its reply was cleaned up and checked for a refusal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestOutcome {
+    Succeeded,
+    /// The reply declined the task (`OllamaError::RefusalDetected`).
+    Refused,
+    /// The reply was JSON instead of Markdown, or structured output missing its sections.
+    InvalidOutput,
+    /// Any other error, after retries.
+    Failed,
+}
+
+/// One task request as the caller saw it: every attempt, then the checks of the reply.
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskOutcome {
+    /// `Task::name` of the request.
+    pub task: String,
+    pub model: String,
+    pub outcome: RequestOutcome,
+    pub duration_ms: u64,
+}
+
+/// Every request attempt an `OllamaWrapper` made, retries included, and how each task request
+/// ended.
+#[derive(Debug, Default)]
+pub struct RequestLog {
+    records: Mutex<Vec<RequestRecord>>,
+    outcomes: Mutex<Vec<TaskOutcome>>,
+}
+
+impl RequestLog {
+    pub fn records(&self) -> Vec<RequestRecord> {
+        self.records
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// How many request attempts failed so far, retried ones included.
+    pub fn failed_attempts(&self) -> usize {
+        self.records
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .filter(|record| !record.ok)
+            .count()
+    }
+
+    pub fn outcomes(&self) -> Vec<TaskOutcome> {
+        self.outcomes
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    pub(super) fn push(&self, record: RequestRecord) {
+        self.records
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(record);
+    }
+
+    pub(super) fn push_outcome(&self, outcome: TaskOutcome) {
+        self.outcomes
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(outcome);
+    }
+}
+
+/// Add `usage` to the request being sent; a tool-calling request reports every round.
+/// Backends call this after each server reply. Outside a request it does nothing.
+pub fn record_usage(usage: TokenUsage) {
+    let _ = REQUEST_USAGE.try_with(|total| total.set(total.get().add(usage)));
+}
+
+/// Attribute the requests `future` makes to `target`, e.g. a file's relative path.
+pub async fn with_target<F: Future>(target: &str, future: F) -> F::Output {
+    REQUEST_TARGET.scope(target.to_string(), future).await
+}
+
+/// Run one request, collecting the usage its backend reports.
+pub(super) async fn measure<F: Future>(future: F) -> (F::Output, TokenUsage) {
+    let usage = Cell::new(TokenUsage::default());
+    REQUEST_USAGE
+        .scope(usage, async {
+            let output = future.await;
+            (output, REQUEST_USAGE.with(Cell::get))
+        })
+        .await
+}
+
+pub(super) fn current_target() -> Option<String> {
+    REQUEST_TARGET.try_with(Clone::clone).ok()
+}
+
+pub(super) fn millis(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
@@ -0,0 +1,89 @@
+use super::{Prompt, TaskConfig};
+
+/// Chat template tokens Ollama wraps around each message.
+const MESSAGE_OVERHEAD_TOKENS: usize = 8;
+/// Room for the tool schemas and one `query_file_source` result (`max_chars` up to 3500).
+const TOOL_OVERHEAD_TOKENS: usize = 1200;
+
+/// How much of a task's `num_ctx` a prompt may take: the window minus the tokens reserved for
+/// the reply (`num_predict`) and, when tools are offered, for their schemas and results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenBudget {
+    pub num_ctx: usize,
+    pub reserved: usize,
+}
+
+/// A prompt's estimated size against its budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PromptFit {
+    pub tokens: usize,
+    pub limit: usize,
+}
+
+impl PromptFit {
+    pub fn fits(&self) -> bool {
+        self.tokens <= self.limit
+    }
+}
+
+impl TokenBudget {
+    pub fn for_task(config: &TaskConfig, tools: bool) -> Self {
+        let num_ctx = config.num_ctx as usize;
+        let reply = match usize::try_from(config.num_predict) {
+            Ok(num_predict) if num_predict > 0 => num_predict,
+            // Unbounded replies (`-1`, `-2`): keep a quarter of the window free.
+            _ => num_ctx / 4,
+        };
+        let tools = if tools { TOOL_OVERHEAD_TOKENS } else { 0 };
+        Self {
+            num_ctx,
+            reserved: reply + tools,
+        }
+    }
+
+    /// Tokens left for the system and user messages.
+    pub fn prompt_limit(&self) -> usize {
+        self.num_ctx.saturating_sub(self.reserved)
+    }
+
+    pub fn measure(&self, prompt: &Prompt) -> PromptFit {
+        let tokens = [prompt.system.as_str(), prompt.user.as_str()]
+            .into_iter()
+            .filter(|message| !message.is_empty())
+            .map(|message| estimate_tokens(message) + MESSAGE_OVERHEAD_TOKENS)
+            .sum();
+        PromptFit {
+            tokens,
+            limit: self.prompt_limit(),
+        }
+    }
+}
+
+/// Token count of `text` as BPE tokenizers tend to split it, erring on the high side.
+///
+/// Words cost one token per four characters, digits one per three, runs of ASCII punctuation
+/// one per two (`\"`, `":"`, `::` are usually single tokens) and each non-ASCII character
+/// one. Whitespace is free except for line breaks. Source code and escaped JSON payloads are
+/// punctuation-heavy, which a flat chars/4 estimate undercounts.
+pub fn estimate_tokens(text: &str) -> usize {
+    let mut tokens = 0usize;
+    let (mut word, mut digits, mut punctuation) = (0usize, 0usize, 0usize);
+    for ch in text.chars() {
+        let class = if ch.is_ascii_alphabetic() || ch == '_' {
+            &mut word
+        } else if ch.is_ascii_digit() {
+            &mut digits
+        } else if ch.is_ascii_punctuation() {
+            &mut punctuation
+        } else {
+            tokens += word.div_ceil(4) + digits.div_ceil(3) + punctuation.div_ceil(2);
+            (word, digits, punctuation) = (0, 0, 0);
+            if ch == '\n' || !ch.is_whitespace() {
+                tokens += 1;
+            }
+            continue;
+        };
+        *class += 1;
+    }
+    tokens + word.div_ceil(4) + digits.div_ceil(3) + punctuation.div_ceil(2)
+}
@@ -0,0 +1,103 @@
+use std::{
+    fs,
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use tracing::warn;
+
+use crate::error::PlainSightError;
+
+use super::{Prompt, TaskConfig};
+
+/// Longest part of a target path kept in a transcript file name.
+const MAX_TARGET_CHARS: usize = 80;
+
+/// One request attempt as it went to the server and came back.
+pub(super) struct Transcript<'a> {
+    pub task: &'a str,
+    pub task_config: &'a TaskConfig,
+    pub target: Option<String>,
+    /// 1-based; retries of the same request count up.
+    pub attempt: u32,
+    pub duration_ms: u64,
+    pub prompt: &'a Prompt,
+    pub reply: &'a Result<String, PlainSightError>,
+}
+
+/// Writes every transcript to its own file under `OllamaConfig::dump_prompts_dir`.
+#[derive(Debug)]
+pub(super) struct TranscriptDump {
+    dir: PathBuf,
+    next: AtomicUsize,
+}
+
+impl TranscriptDump {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            next: AtomicUsize::new(1),
+        }
+    }
+
+    /// Write `transcript` to `<unix millis>-<sequence>-<task>[-<target>].txt`. Failures are
+    /// logged and otherwise ignored, so debugging output never fails a run.
+    pub fn write(&self, transcript: &Transcript<'_>) {
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or_default();
+        let sequence = self.next.fetch_add(1, Ordering::Relaxed);
+        let mut name = format!("{millis}-{sequence:04}-{}", transcript.task);
+        if let Some(target) = &transcript.target {
+            name.push('-');
+            name.extend(
+                target
+                    .chars()
+                    .map(|c| {
+                        if c.is_ascii_alphanumeric() || c == '.' {
+                            c
+                        } else {
+                            '_'
+                        }
+                    })
+                    .take(MAX_TARGET_CHARS),
+            );
+        }
+        let path = self.dir.join(format!("{name}.txt"));
+
+        let written =
+            fs::create_dir_all(&self.dir).and_then(|()| fs::write(&path, render(transcript)));
+        if let Err(e) = written {
+            warn!(path = %path.display(), error = %e, "failed writing prompt transcript");
+        }
+    }
+}
+
+fn render(transcript: &Transcript<'_>) -> String {
+    let config = transcript.task_config;
+    let mut out = format!(
+        "task: {}\nmodel: {}\ntarget: {}\nattempt: {}\nduration_ms: {}\ntemperature: {}\nnum_ctx: {}\nnum_predict: {}\n",
+        transcript.task,
+        config.model,
+        transcript.target.as_deref().unwrap_or("-"),
+        transcript.attempt,
+        transcript.duration_ms,
+        config.temperature,
+        config.num_ctx,
+        config.num_predict,
+    );
+    if !transcript.prompt.system.is_empty() {
+        out.push_str(&format!(
+            "\n===== system =====\n{}\n",
+            transcript.prompt.system
+        ));
+    }
+    out.push_str(&format!("\n===== user =====\n{}\n", transcript.prompt.user));
+    match transcript.reply {
+        Ok(reply) => out.push_str(&format!("\n===== response =====\n{reply}\n")),
+        Err(e) => out.push_str(&format!("\n===== error =====\n{e}\n")),
+    }
+    out
+}
@@ -1,35 +1,136 @@
+use std::collections::BTreeMap;
+
 use serde_json::{Value, json};
 
-use super::Task;
+use crate::ownership;
+
+use super::{OllamaError, RefusalDetection, Task};
 
-pub fn ensure_non_empty(task: Task, model_name: &str, output: String) -> Result<String, String> {
+pub fn ensure_non_empty(
+    task: Task,
+    model_name: &str,
+    output: String,
+) -> Result<String, OllamaError> {
     if output.trim().is_empty() {
-        return Err(format!(
-            "ollama returned empty output for task {:?} ({})",
-            task, model_name
-        ));
+        return Err(OllamaError::EmptyOutput {
+            task,
+            model: model_name.to_string(),
+        });
     }
     Ok(output)
 }
 
-pub fn is_refusal_output(output: &str) -> bool {
+/// Phrases `OllamaConfig::refusal_phrases` starts with.
+pub const DEFAULT_REFUSAL_PHRASES: [&str; 16] = [
+    "i cannot",
+    "i can't",
+    "i'm unable",
+    "as an ai",
+    "i don't have",
+    "i do not have",
+    "i am not able",
+    "unable to",
+    "cannot help",
+    "can't help",
+    "not allowed",
+    "not permitted",
+    "against my",
+    "ethical",
+    "policy",
+    "guidelines",
+];
+
+/// Whether `output` reads as the model declining the task.
+///
+/// `Strict` flags any phrase anywhere. `Lenient` needs a phrase in the first non-empty line, or
+/// two different phrases in the first paragraph of a reply that does not open with a heading,
+/// so docs that merely mention a policy pass.
+pub fn is_refusal_output(output: &str, detection: RefusalDetection, phrases: &[String]) -> bool {
     let lower = output.to_lowercase();
-    lower.contains("i cannot")
-        || lower.contains("i can't")
-        || lower.contains("i'm unable")
-        || lower.contains("as an ai")
-        || lower.contains("i don't have")
-        || lower.contains("i do not have")
-        || lower.contains("i am not able")
-        || lower.contains("unable to")
-        || lower.contains("cannot help")
-        || lower.contains("can't help")
-        || lower.contains("not allowed")
-        || lower.contains("not permitted")
-        || lower.contains("against my")
-        || lower.contains("ethical")
-        || lower.contains("policy")
-        || lower.contains("guidelines")
+    let mut phrases = phrases
+        .iter()
+        .map(|phrase| phrase.trim().to_lowercase())
+        .filter(|phrase| !phrase.is_empty());
+    match detection {
+        RefusalDetection::Off => false,
+        RefusalDetection::Strict => phrases.any(|phrase| lower.contains(&phrase)),
+        RefusalDetection::Lenient => {
+            let text = lower.trim_start();
+            let first_line = text.lines().next().unwrap_or_default();
+            // A reply opening with a heading follows the requested format; only its first line
+            // counts.
+            let first_paragraph = if text.starts_with('#') {
+                ""
+            } else {
+                text.split("\n\n").next().unwrap_or_default()
+            };
+            let mut signals = 0;
+            for phrase in phrases {
+                if first_line.contains(&phrase) {
+                    return true;
+                }
+                if first_paragraph.contains(&phrase) {
+                    signals += 1;
+                }
+            }
+            signals >= 2
+        }
+    }
+}
+
+/// Heading that opens each file's section of a batched summary reply.
+pub const BATCH_FILE_HEADING: &str = "# File:";
+
+/// The sections of a batched summary reply, by the path after each `# File:` heading. Text
+/// before the first heading is dropped.
+pub fn split_batch_reply(reply: &str) -> Vec<(String, String)> {
+    let mut sections: Vec<(String, String)> = Vec::new();
+    for line in reply.lines() {
+        if let Some(path) = line.trim().strip_prefix(BATCH_FILE_HEADING) {
+            let path = path.trim().trim_matches('`').to_string();
+            sections.push((path, String::new()));
+        } else if let Some((_, section)) = sections.last_mut() {
+            section.push_str(line);
+            section.push('\n');
+        }
+    }
+    sections
+}
+
+/// Opening and closing tags of the reasoning some models write before their reply.
+const THINKING_TAGS: [(&str, &str); 2] = [("<think>", "</think>"), ("<thinking>", "</thinking>")];
+
+/// `output` without the thinking blocks it opens with. A reply whose opening tag was eaten by
+/// the chat template, leaving only a closing tag on its own line, loses everything up to that
+/// tag; an unclosed block, cut off by `num_predict`, leaves nothing.
+pub fn strip_thinking(output: String) -> String {
+    let mut rest = output.trim_start();
+    while let Some((open, close)) = THINKING_TAGS
+        .iter()
+        .find(|(open, _)| rest.starts_with(open))
+    {
+        rest = match rest[open.len()..].find(close) {
+            Some(end) => rest[open.len() + end + close.len()..].trim_start(),
+            None => "",
+        };
+    }
+    for (open, close) in THINKING_TAGS {
+        if rest.contains(open) {
+            continue;
+        }
+        let mut offset = 0;
+        for line in rest.split_inclusive('\n') {
+            offset += line.len();
+            if line.trim() == close {
+                rest = rest[offset..].trim_start();
+                break;
+            }
+        }
+    }
+    if rest.len() == output.len() {
+        return output;
+    }
+    rest.to_string()
 }
 
 pub fn strip_wrapping_code_fence(output: String) -> String {
@@ -106,10 +207,10 @@ pub fn trim_to_expected_heading(task: Task, output: String) -> String {
     output.trim().to_string()
 }
 
-pub fn reject_json_payload(output: String) -> Result<String, String> {
+pub fn reject_json_payload(output: String) -> Result<String, OllamaError> {
     let trimmed = output.trim_start();
     if trimmed.starts_with('{') || trimmed.starts_with('[') {
-        return Err("ollama returned JSON payload instead of markdown".to_string());
+        return Err(OllamaError::JsonPayload);
     }
     Ok(output)
 }
@@ -117,9 +218,13 @@ pub fn reject_json_payload(output: String) -> Result<String, String> {
 fn expected_headings(task: Task) -> &'static [&'static str] {
     match task {
         Task::Summarize => &["## Purpose"],
+        Task::DirectorySummary => &["## Overview"],
+        Task::ChangeSummary => &["## Impact"],
         Task::Documentation => &["## Overview"],
         Task::ProjectSummary => &["## Overview"],
         Task::Architecture => &["## System Context"],
+        Task::Explain => &["## Explanation"],
+        Task::Review => &["## Overview"],
     }
 }
 
@@ -152,15 +257,22 @@ fn find_markdown_string(value: &Value, expected_headings: &[&str]) -> Option<Str
     }
 }
 
+/// Whether `text` opens with the disclaimer every generated document starts with, after any
+/// ownership front-matter.
+pub fn has_ai_disclaimer(text: &str) -> bool {
+    let lower = ownership::strip_front_matter(text.trim_start())
+        .trim_start()
+        .to_lowercase();
+    lower.starts_with("> **ai-generated content:**")
+        || lower.starts_with("**ai-generated content:**")
+        || lower.starts_with("<!-- generated by ai")
+}
+
 pub fn ensure_ai_disclaimer(output: String) -> String {
     let disclaimer = "> **AI-generated content:** May contain inaccuracies. Verify against source code.";
     let trimmed = output.trim();
 
-    let lower = trimmed.to_lowercase();
-    if lower.starts_with("> **ai-generated content:**")
-        || lower.starts_with("**ai-generated content:**")
-        || lower.starts_with("<!-- generated by ai")
-    {
+    if has_ai_disclaimer(trimmed) {
         return output;
     }
 
@@ -175,37 +287,43 @@ pub fn ensure_ai_disclaimer(output: String) -> String {
     }
 }
 
-pub fn prepare_file_summary_input(context_payload: &str) -> Result<String, String> {
-    let mut v: Value = serde_json::from_str(context_payload).map_err(|e| e.to_string())?;
+pub fn prepare_file_summary_input(context_payload: &str) -> Result<String, OllamaError> {
+    let mut v: Value = serde_json::from_str(context_payload).map_err(invalid_payload)?;
     clamp_chunks_in_payload(&mut v, 4, 900);
     clamp_global_symbols(&mut v, 60);
     clamp_open_items(&mut v, 24);
     clamp_links(&mut v, 40);
-    serde_json::to_string(&v).map_err(|e| e.to_string())
+    serde_json::to_string(&v).map_err(invalid_payload)
 }
 
-pub fn prepare_file_docs_input(context_payload: &str) -> Result<String, String> {
-    let mut v: Value = serde_json::from_str(context_payload).map_err(|e| e.to_string())?;
+pub fn prepare_file_docs_input(context_payload: &str) -> Result<String, OllamaError> {
+    let mut v: Value = serde_json::from_str(context_payload).map_err(invalid_payload)?;
     clamp_chunks_in_payload(&mut v, 6, 1200);
     clamp_global_symbols(&mut v, 80);
     clamp_open_items(&mut v, 30);
     clamp_links(&mut v, 70);
-    serde_json::to_string(&v).map_err(|e| e.to_string())
+    serde_json::to_string(&v).map_err(invalid_payload)
 }
 
-pub fn prepare_architecture_input(context_payload: &str) -> Result<String, String> {
+pub fn prepare_architecture_input(context_payload: &str) -> Result<String, OllamaError> {
     build_project_digest(context_payload, true)
 }
 
+fn invalid_payload(err: serde_json::Error) -> OllamaError {
+    OllamaError::InvalidPayload(err.to_string())
+}
+
 fn build_project_digest(
     context_payload: &str,
     include_chunk_preview: bool,
-) -> Result<String, String> {
-    let v: Value = serde_json::from_str(context_payload).map_err(|e| e.to_string())?;
+) -> Result<String, OllamaError> {
+    let v: Value = serde_json::from_str(context_payload).map_err(invalid_payload)?;
     let files = v
         .get("files")
         .and_then(Value::as_array)
-        .ok_or_else(|| "project index input missing 'files' array".to_string())?;
+        .ok_or_else(|| {
+            OllamaError::InvalidPayload("project index input missing 'files' array".to_string())
+        })?;
 
     let mut file_entries = Vec::with_capacity(files.len());
     for file in files {
@@ -232,20 +350,463 @@ fn build_project_digest(
             "chunk_count": chunk_count,
         });
 
-        if include_chunk_preview {
+        if let Some(file_summary) = file.get("summary").and_then(Value::as_str) {
+            entry["summary"] = json!(summary_digest(file_summary, 600));
+        } else if include_chunk_preview {
             entry["preview"] = json!(preview);
         }
 
         file_entries.push(entry);
     }
 
-    let summary = json!({
+    let mut summary = json!({
         "project": v.get("project").cloned().unwrap_or(json!("unknown")),
         "file_count": v.get("file_count").cloned().unwrap_or(json!(file_entries.len())),
         "files": file_entries
     });
 
-    serde_json::to_string(&summary).map_err(|e| e.to_string())
+    let module_outline = module_tree_outline(v.get("module_tree").unwrap_or(&Value::Null));
+    if !module_outline.is_empty() {
+        summary["module_tree"] = json!(module_outline);
+    }
+
+    let directory_outline = directory_outline(v.get("directories").unwrap_or(&Value::Null), 40);
+    if !directory_outline.is_empty() {
+        summary["directories"] = json!(directory_outline);
+    }
+
+    let api_outline = openapi_outline(v.get("openapi").unwrap_or(&Value::Null), 40, 20);
+    if !api_outline.is_empty() {
+        summary["http_api"] = json!(api_outline);
+    }
+
+    let rpc_outline = rpc_outline(v.get("rpc").unwrap_or(&Value::Null), 40);
+    if !rpc_outline.is_empty() {
+        summary["rpc_services"] = json!(rpc_outline);
+    }
+
+    let env_outline = environment_outline(v.get("environment").unwrap_or(&Value::Null), 60);
+    if !env_outline.is_empty() {
+        summary["environment"] = json!(env_outline);
+    }
+
+    let concurrency_outline =
+        concurrency_outline(v.get("concurrency").unwrap_or(&Value::Null), 40, 6);
+    if !concurrency_outline.is_empty() {
+        summary["concurrency"] = json!(concurrency_outline);
+    }
+
+    let benchmark_outline = benchmark_outline(v.get("benchmarks").unwrap_or(&Value::Null), 30);
+    if !benchmark_outline.is_empty() {
+        summary["benchmarks"] = json!(benchmark_outline);
+    }
+
+    let reexport_outline = reexport_outline(v.get("reexports").unwrap_or(&Value::Null), 40);
+    if !reexport_outline.is_empty() {
+        summary["reexports"] = json!(reexport_outline);
+    }
+
+    let memory = v.get("project_memory").unwrap_or(&Value::Null);
+    let link_outline = link_outline(memory.get("links").unwrap_or(&Value::Null), 80);
+    if !link_outline.is_empty() {
+        summary["links"] = json!(link_outline);
+    }
+    let symbol_outline =
+        global_symbol_outline(memory.get("global_symbols").unwrap_or(&Value::Null), 80);
+    if !symbol_outline.is_empty() {
+        summary["global_symbols"] = json!(symbol_outline);
+    }
+
+    serde_json::to_string(&summary).map_err(invalid_payload)
+}
+
+/// A file summary on one line, without the disclaimer and blank lines, cut at `max_chars`.
+fn summary_digest(file_summary: &str, max_chars: usize) -> String {
+    let text = file_summary
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('>'))
+        .collect::<Vec<_>>()
+        .join(" ");
+    if text.chars().count() <= max_chars {
+        return text;
+    }
+    let truncated: String = text.chars().take(max_chars).collect();
+    format!("{truncated}...")
+}
+
+/// One `from -> to: symbol` line per cross-file link.
+fn link_outline(links: &Value, max_links: usize) -> Vec<String> {
+    let Some(links) = links.as_array() else {
+        return Vec::new();
+    };
+    links
+        .iter()
+        .take(max_links)
+        .filter_map(|link| {
+            let from = link.get("from_file").and_then(Value::as_str)?;
+            let to = link.get("to_file").and_then(Value::as_str)?;
+            let symbol = link.get("symbol").and_then(Value::as_str)?;
+            Some(format!("{from} -> {to}: {symbol}"))
+        })
+        .collect()
+}
+
+/// One `kind name (files)` line per symbol defined across the project.
+fn global_symbol_outline(symbols: &Value, max_symbols: usize) -> Vec<String> {
+    let Some(symbols) = symbols.as_array() else {
+        return Vec::new();
+    };
+    symbols
+        .iter()
+        .take(max_symbols)
+        .filter_map(|symbol| {
+            let name = symbol.get("name").and_then(Value::as_str)?;
+            let kind = symbol.get("kind").and_then(Value::as_str).unwrap_or("item");
+            let defined_in = symbol
+                .get("defined_in")
+                .and_then(Value::as_array)
+                .map(|files| {
+                    files
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                })
+                .unwrap_or_default();
+            Some(format!("{kind} {name} ({defined_in})"))
+        })
+        .collect()
+}
+
+/// One `path: overview` line per directory rollup, keeping the first paragraph of each.
+fn directory_outline(directories: &Value, max_directories: usize) -> Vec<String> {
+    let Some(directories) = directories.as_array() else {
+        return Vec::new();
+    };
+    directories
+        .iter()
+        .take(max_directories)
+        .filter_map(|directory| {
+            let path = directory.get("path").and_then(Value::as_str)?;
+            let summary = directory.get("summary").and_then(Value::as_str)?;
+            let overview = summary
+                .lines()
+                .map(str::trim)
+                .skip_while(|line| line.is_empty() || line.starts_with(['#', '>']))
+                .take_while(|line| !line.is_empty() && !line.starts_with(['#', '>']))
+                .collect::<Vec<_>>()
+                .join(" ");
+            Some(format!("{path}: {overview}").trim_end().to_string())
+        })
+        .collect()
+}
+
+fn openapi_outline(specs: &Value, max_endpoints: usize, max_schemas: usize) -> Vec<String> {
+    let Some(specs) = specs.as_array() else {
+        return Vec::new();
+    };
+
+    let mut lines = Vec::new();
+    for spec in specs {
+        let path = spec.get("path").and_then(Value::as_str).unwrap_or("unknown");
+        let title = spec.get("title").and_then(Value::as_str).unwrap_or_default();
+        lines.push(format!("spec {path} {title}").trim_end().to_string());
+
+        let endpoints = spec.get("endpoints").and_then(Value::as_array);
+        for endpoint in endpoints.into_iter().flatten().take(max_endpoints) {
+            let method = endpoint.get("method").and_then(Value::as_str).unwrap_or("?");
+            let route = endpoint.get("path").and_then(Value::as_str).unwrap_or("?");
+            let summary = endpoint
+                .get("summary")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            lines.push(format!("  {method} {route} {summary}").trim_end().to_string());
+        }
+
+        let schemas = spec.get("schemas").and_then(Value::as_array);
+        for schema in schemas.into_iter().flatten().take(max_schemas) {
+            let name = schema.get("name").and_then(Value::as_str).unwrap_or("?");
+            let properties = schema
+                .get("properties")
+                .and_then(Value::as_array)
+                .map(|props| {
+                    props
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                })
+                .unwrap_or_default();
+            lines.push(format!("  schema {name} {{{properties}}}"));
+        }
+    }
+    lines
+}
+
+fn rpc_outline(protos: &Value, max_rpcs: usize) -> Vec<String> {
+    let Some(protos) = protos.as_array() else {
+        return Vec::new();
+    };
+
+    let mut lines = Vec::new();
+    for proto in protos {
+        let path = proto.get("path").and_then(Value::as_str).unwrap_or("unknown");
+        let package = proto.get("package").and_then(Value::as_str).unwrap_or_default();
+        let services = proto.get("services").and_then(Value::as_array);
+        for service in services.into_iter().flatten() {
+            let name = service.get("name").and_then(Value::as_str).unwrap_or("?");
+            let qualified = if package.is_empty() {
+                name.to_string()
+            } else {
+                format!("{package}.{name}")
+            };
+            let used_in = service
+                .get("used_in")
+                .and_then(Value::as_array)
+                .map(|files| {
+                    files
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                })
+                .unwrap_or_default();
+            lines.push(format!("service {qualified} ({path}) used_in: [{used_in}]"));
+
+            let rpcs = service.get("rpcs").and_then(Value::as_array);
+            for rpc in rpcs.into_iter().flatten().take(max_rpcs) {
+                let stream = |key: &str| {
+                    if rpc.get(key).and_then(Value::as_bool).unwrap_or(false) {
+                        "stream "
+                    } else {
+                        ""
+                    }
+                };
+                lines.push(format!(
+                    "  rpc {}({}{}) returns ({}{})",
+                    rpc.get("name").and_then(Value::as_str).unwrap_or("?"),
+                    stream("client_streaming"),
+                    rpc.get("request").and_then(Value::as_str).unwrap_or("?"),
+                    stream("server_streaming"),
+                    rpc.get("response").and_then(Value::as_str).unwrap_or("?"),
+                ));
+            }
+        }
+    }
+    lines
+}
+
+fn concurrency_outline(
+    inventory: &Value,
+    max_primitives: usize,
+    max_locations: usize,
+) -> Vec<String> {
+    let Some(inventory) = inventory.as_array() else {
+        return Vec::new();
+    };
+
+    inventory
+        .iter()
+        .take(max_primitives)
+        .map(|entry| {
+            let kind = entry.get("kind").and_then(Value::as_str).unwrap_or("?");
+            let primitive = entry.get("primitive").and_then(Value::as_str).unwrap_or("?");
+            let locations = entry
+                .get("locations")
+                .and_then(Value::as_array)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            let mut used_in = locations
+                .iter()
+                .take(max_locations)
+                .filter_map(Value::as_str)
+                .collect::<Vec<_>>()
+                .join(", ");
+            if locations.len() > max_locations {
+                used_in.push_str(&format!(" (+{} more)", locations.len() - max_locations));
+            }
+            format!("{kind} {primitive} used in: {used_in}")
+        })
+        .collect()
+}
+
+fn benchmark_outline(benchmarks: &Value, max_benchmarks: usize) -> Vec<String> {
+    let Some(benchmarks) = benchmarks.as_array() else {
+        return Vec::new();
+    };
+
+    let text = |value: &Value, key: &str| {
+        value.get(key).and_then(Value::as_str).unwrap_or("?").to_string()
+    };
+    benchmarks
+        .iter()
+        .take(max_benchmarks)
+        .map(|benchmark| {
+            let exercises = benchmark
+                .get("exercises")
+                .and_then(Value::as_array)
+                .map(|symbols| {
+                    symbols.iter().filter_map(Value::as_str).collect::<Vec<_>>().join(", ")
+                })
+                .unwrap_or_default();
+            let results = benchmark
+                .get("results")
+                .and_then(Value::as_array)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            let measured = if results.is_empty() {
+                "no recorded results".to_string()
+            } else {
+                results
+                    .iter()
+                    .map(|result| {
+                        format!(
+                            "{} mean {} [{}] from {} on {}",
+                            text(result, "id"),
+                            text(result, "mean"),
+                            text(result, "range"),
+                            text(result, "source"),
+                            text(result, "measured_at")
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            };
+            format!(
+                "benchmark {} ({}) exercises {exercises}: {measured}",
+                text(benchmark, "benchmark"),
+                text(benchmark, "defined_in")
+            )
+        })
+        .collect()
+}
+
+fn environment_outline(inventory: &Value, max_variables: usize) -> Vec<String> {
+    let Some(inventory) = inventory.as_array() else {
+        return Vec::new();
+    };
+
+    let joined = |entry: &Value, key: &str| {
+        entry
+            .get(key)
+            .and_then(Value::as_array)
+            .map(|values| {
+                values
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .unwrap_or_default()
+    };
+
+    inventory
+        .iter()
+        .take(max_variables)
+        .map(|entry| {
+            let name = entry.get("name").and_then(Value::as_str).unwrap_or("?");
+            let defaults = joined(entry, "defaults");
+            let defaults = if defaults.is_empty() {
+                "none".to_string()
+            } else {
+                defaults
+            };
+            format!(
+                "{name} default: {defaults} read in: {}",
+                joined(entry, "locations")
+            )
+        })
+        .collect()
+}
+
+fn reexport_outline(reexports: &Value, max_lines: usize) -> Vec<String> {
+    let Some(entries) = reexports.as_array() else {
+        return Vec::new();
+    };
+
+    let mut grouped: BTreeMap<(String, String), Vec<String>> = BTreeMap::new();
+    for entry in entries {
+        let from = entry
+            .get("exported_from")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let symbol = entry
+            .get("symbol")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let defined_in = entry
+            .get("defined_in")
+            .and_then(Value::as_array)
+            .map(|files| {
+                files
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .unwrap_or_default();
+        if from.is_empty() || symbol.is_empty() || defined_in.is_empty() {
+            continue;
+        }
+        grouped
+            .entry((from.to_string(), defined_in))
+            .or_default()
+            .push(symbol.to_string());
+    }
+
+    grouped
+        .into_iter()
+        .take(max_lines)
+        .map(|((from, defined_in), symbols)| {
+            format!("{from} re-exports {} from {defined_in}", symbols.join(", "))
+        })
+        .collect()
+}
+
+fn module_tree_outline(module_tree: &Value) -> Vec<String> {
+    let mut lines = Vec::new();
+    let Some(crates) = module_tree.get("crates").and_then(Value::as_array) else {
+        return lines;
+    };
+
+    for krate in crates {
+        if let Some(root) = krate.get("root") {
+            push_module_lines(root, 0, &mut lines);
+        }
+    }
+    lines
+}
+
+fn push_module_lines(node: &Value, depth: usize, lines: &mut Vec<String>) {
+    let module_path = node
+        .get("module_path")
+        .and_then(Value::as_str)
+        .unwrap_or("unknown");
+    let visibility = node
+        .get("visibility")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let location = match node.get("file").and_then(Value::as_str) {
+        Some(file) => file.to_string(),
+        None => "inline".to_string(),
+    };
+
+    let indent = "  ".repeat(depth);
+    if visibility.is_empty() {
+        lines.push(format!("{indent}{module_path} ({location})"));
+    } else {
+        lines.push(format!("{indent}{visibility} {module_path} ({location})"));
+    }
+
+    for child in node
+        .get("children")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        push_module_lines(child, depth + 1, lines);
+    }
 }
 
 fn chunk_preview(root: &Value, max_chars: usize) -> String {
@@ -287,11 +848,11 @@ fn clamp_chunks_in_payload(root: &mut Value, max_chunks: usize, max_chars_per_ch
     }
 
     for chunk in chunks {
-        if let Some(Value::String(content)) = chunk.get_mut("content") {
-            if content.chars().count() > max_chars_per_chunk {
-                let truncated: String = content.chars().take(max_chars_per_chunk).collect();
-                *content = format!("{truncated}...");
-            }
+        if let Some(Value::String(content)) = chunk.get_mut("content")
+            && content.chars().count() > max_chars_per_chunk
+        {
+            let truncated: String = content.chars().take(max_chars_per_chunk).collect();
+            *content = format!("{truncated}...");
         }
     }
 }
@@ -0,0 +1,475 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::memory::OpenItem;
+
+const HTTP_METHODS: &[&str] = &[
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+const SPEC_FILE_STEMS: &[&str] = &["openapi", "swagger"];
+const MAX_SCHEMA_PROPERTIES: usize = 20;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenApiSpec {
+    pub path: String,
+    pub title: String,
+    pub version: String,
+    pub endpoints: Vec<SpecEndpoint>,
+    pub schemas: Vec<SpecSchema>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpecEndpoint {
+    pub method: String,
+    pub path: String,
+    #[serde(default)]
+    pub operation_id: String,
+    #[serde(default)]
+    pub summary: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpecSchema {
+    pub name: String,
+    pub properties: Vec<String>,
+    #[serde(default)]
+    pub required: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DetectedRoute {
+    pub method: String,
+    pub path: String,
+    pub line: usize,
+}
+
+/// Whether `file_name` looks like an OpenAPI/Swagger document (`openapi.yaml`,
+/// `swagger.json`, `billing.openapi.yml`, ...).
+pub fn is_spec_file_name(file_name: &str) -> bool {
+    let lower = file_name.to_ascii_lowercase();
+    let Some((stem, ext)) = lower.rsplit_once('.') else {
+        return false;
+    };
+    if !matches!(ext, "json" | "yaml" | "yml") {
+        return false;
+    }
+    SPEC_FILE_STEMS
+        .iter()
+        .any(|name| stem == *name || stem.ends_with(&format!(".{name}")))
+}
+
+/// Parse an OpenAPI 3 or Swagger 2 document from JSON or YAML.
+///
+/// Returns `None` when the content does not parse or lacks the `openapi`/`swagger` marker.
+pub fn parse_spec(relative_path: &str, content: &str) -> Option<OpenApiSpec> {
+    let is_json = relative_path.to_ascii_lowercase().ends_with(".json");
+    let document: Value = if is_json {
+        serde_json::from_str(content).ok()?
+    } else {
+        serde_yaml::from_str(content).ok()?
+    };
+
+    let version = document
+        .get("openapi")
+        .or_else(|| document.get("swagger"))
+        .and_then(value_to_string)?;
+    let title = document
+        .pointer("/info/title")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+
+    let mut endpoints = Vec::new();
+    if let Some(paths) = document.get("paths").and_then(Value::as_object) {
+        for (path, item) in paths {
+            let Some(item) = item.as_object() else {
+                continue;
+            };
+            for (method, operation) in item {
+                if !HTTP_METHODS.contains(&method.as_str()) {
+                    continue;
+                }
+                endpoints.push(SpecEndpoint {
+                    method: method.to_ascii_uppercase(),
+                    path: path.clone(),
+                    operation_id: string_field(operation, "operationId"),
+                    summary: string_field(operation, "summary"),
+                });
+            }
+        }
+    }
+
+    // OpenAPI 3 keeps schemas under components, Swagger 2 under definitions.
+    let schema_root = document
+        .pointer("/components/schemas")
+        .or_else(|| document.get("definitions"))
+        .and_then(Value::as_object);
+    let schemas = schema_root
+        .map(|schemas| {
+            schemas
+                .iter()
+                .map(|(name, schema)| SpecSchema {
+                    name: name.clone(),
+                    properties: schema
+                        .get("properties")
+                        .and_then(Value::as_object)
+                        .map(|props| {
+                            props
+                                .iter()
+                                .take(MAX_SCHEMA_PROPERTIES)
+                                .map(|(prop, def)| match schema_type(def) {
+                                    Some(ty) => format!("{prop}: {ty}"),
+                                    None => prop.clone(),
+                                })
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                    required: schema
+                        .get("required")
+                        .and_then(Value::as_array)
+                        .map(|req| {
+                            req.iter()
+                                .filter_map(Value::as_str)
+                                .map(str::to_string)
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Some(OpenApiSpec {
+        path: relative_path.to_string(),
+        title,
+        version,
+        endpoints,
+        schemas,
+    })
+}
+
+/// Detect HTTP route registrations in a source file.
+///
+/// Covers the common registration styles of axum/actix/rocket, Flask/FastAPI, Express,
+/// net/http/gorilla/gin and Spring. Routes registered without an explicit method are
+/// reported as `ANY`.
+pub fn detect_routes(language: &str, source: &str) -> Vec<DetectedRoute> {
+    let mut routes = Vec::new();
+
+    for (idx, raw_line) in source.lines().enumerate() {
+        let line = raw_line.trim();
+        let line_no = idx + 1;
+        let found = match language {
+            "rust" => rust_routes(line),
+            "python" => python_routes(line),
+            "javascript" | "typescript" => js_routes(line),
+            "go" => go_routes(line),
+            "java" | "kotlin" => spring_routes(line),
+            _ => Vec::new(),
+        };
+        routes.extend(found.into_iter().map(|(method, path)| DetectedRoute {
+            method,
+            path,
+            line: line_no,
+        }));
+    }
+
+    routes
+}
+
+/// Cross-check spec endpoints against detected routes and report mismatches as open items.
+pub fn check_sync(specs: &[OpenApiSpec], routes: &[(String, DetectedRoute)]) -> Vec<OpenItem> {
+    let implemented: BTreeSet<(String, String)> = routes
+        .iter()
+        .map(|(_, route)| (route.method.clone(), normalize_path(&route.path)))
+        .collect();
+    let documented: BTreeSet<(String, String)> = specs
+        .iter()
+        .flat_map(|spec| spec.endpoints.iter())
+        .map(|endpoint| (endpoint.method.clone(), normalize_path(&endpoint.path)))
+        .collect();
+
+    let mut items = Vec::new();
+    for spec in specs {
+        for endpoint in &spec.endpoints {
+            let key = (endpoint.method.clone(), normalize_path(&endpoint.path));
+            if !matches_any(&key, &implemented) {
+                items.push(OpenItem {
+                    kind: "openapi_missing_handler".to_string(),
+                    symbol: format!("{} {}", endpoint.method, endpoint.path),
+                    message: format!(
+                        "'{} {}' is declared in the spec but no route handler was detected",
+                        endpoint.method, endpoint.path
+                    ),
+                    files: vec![spec.path.clone()],
+                });
+            }
+        }
+    }
+
+    let mut reported = BTreeSet::new();
+    for (file, route) in routes {
+        let key = (route.method.clone(), normalize_path(&route.path));
+        if matches_any(&key, &documented) || !reported.insert(key) {
+            continue;
+        }
+        items.push(OpenItem {
+            kind: "openapi_undocumented_route".to_string(),
+            symbol: format!("{} {}", route.method, route.path),
+            message: format!(
+                "route '{} {}' (line {}) is not declared in the OpenAPI spec",
+                route.method, route.path, route.line
+            ),
+            files: vec![file.clone()],
+        });
+    }
+
+    items
+}
+
+/// Find the spec operation documenting a detected route.
+pub fn find_endpoint<'a>(
+    specs: &'a [OpenApiSpec],
+    route: &DetectedRoute,
+) -> Option<&'a SpecEndpoint> {
+    let path = normalize_path(&route.path);
+    specs
+        .iter()
+        .flat_map(|spec| spec.endpoints.iter())
+        .find(|endpoint| {
+            normalize_path(&endpoint.path) == path
+                && (route.method == "ANY" || endpoint.method == route.method)
+        })
+}
+
+fn matches_any(key: &(String, String), set: &BTreeSet<(String, String)>) -> bool {
+    set.contains(key)
+        || set.contains(&("ANY".to_string(), key.1.clone()))
+        || (key.0 == "ANY" && set.iter().any(|(_, path)| path == &key.1))
+}
+
+/// Normalize path parameters (`{id}`, `:id`, `<int:id>`) and trailing slashes so specs and
+/// framework routes compare equal.
+fn normalize_path(path: &str) -> String {
+    let segments = path
+        .trim()
+        .trim_end_matches('/')
+        .split('/')
+        .map(|segment| {
+            let is_param = (segment.starts_with('{') && segment.ends_with('}'))
+                || (segment.starts_with('<') && segment.ends_with('>'))
+                || segment.starts_with(':');
+            if is_param { "{}" } else { segment }
+        })
+        .collect::<Vec<_>>();
+    let joined = segments.join("/");
+    if joined.is_empty() {
+        "/".to_string()
+    } else {
+        joined
+    }
+}
+
+fn value_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+fn string_field(value: &Value, key: &str) -> String {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn schema_type(def: &Value) -> Option<String> {
+    if let Some(reference) = def.get("$ref").and_then(Value::as_str) {
+        return reference.rsplit('/').next().map(str::to_string);
+    }
+    let ty = def.get("type").and_then(Value::as_str)?;
+    if ty == "array"
+        && let Some(items) = def.get("items")
+        && let Some(inner) = schema_type(items)
+    {
+        return Some(format!("{inner}[]"));
+    }
+    Some(ty.to_string())
+}
+
+fn first_string_literal(text: &str) -> Option<&str> {
+    let start = text.find(['"', '\''])?;
+    let quote = text[start..].chars().next()?;
+    let rest = &text[start + 1..];
+    let end = rest.find(quote)?;
+    let literal = &rest[..end];
+    literal.starts_with('/').then_some(literal)
+}
+
+fn rust_routes(line: &str) -> Vec<(String, String)> {
+    // actix-web / rocket attribute macros: #[get("/users/{id}")]
+    if let Some(rest) = line.strip_prefix("#[") {
+        for method in HTTP_METHODS {
+            if let Some(args) = rest.strip_prefix(&format!("{method}("))
+                && let Some(path) = first_string_literal(args)
+            {
+                return vec![(method.to_ascii_uppercase(), path.to_string())];
+            }
+        }
+        return Vec::new();
+    }
+
+    // axum: .route("/users", get(list).post(create))
+    let Some(idx) = line.find(".route(") else {
+        return Vec::new();
+    };
+    let args = &line[idx + ".route(".len()..];
+    let Some(path) = first_string_literal(args) else {
+        return Vec::new();
+    };
+    let methods = HTTP_METHODS
+        .iter()
+        .filter(|method| {
+            args.contains(&format!("{method}(")) || args.contains(&format!("::{method}("))
+        })
+        .map(|method| (method.to_ascii_uppercase(), path.to_string()))
+        .collect::<Vec<_>>();
+    if methods.is_empty() {
+        vec![("ANY".to_string(), path.to_string())]
+    } else {
+        methods
+    }
+}
+
+fn python_routes(line: &str) -> Vec<(String, String)> {
+    let Some(decorator) = line.strip_prefix('@') else {
+        return Vec::new();
+    };
+    let Some((target, args)) = decorator.split_once('(') else {
+        return Vec::new();
+    };
+    let Some(method) = target.rsplit('.').next() else {
+        return Vec::new();
+    };
+    let Some(path) = first_string_literal(args) else {
+        return Vec::new();
+    };
+
+    if HTTP_METHODS.contains(&method) {
+        return vec![(method.to_ascii_uppercase(), path.to_string())];
+    }
+    if method != "route" {
+        return Vec::new();
+    }
+
+    // Flask: @app.route("/x", methods=["GET", "POST"])
+    let Some(methods) = args
+        .split_once("methods")
+        .and_then(|(_, rest)| rest.split_once('['))
+        .and_then(|(_, rest)| rest.split_once(']'))
+        .map(|(list, _)| list)
+    else {
+        return vec![("GET".to_string(), path.to_string())];
+    };
+    methods
+        .split(',')
+        .map(|m| m.trim().trim_matches(['"', '\'']).to_ascii_uppercase())
+        .filter(|m| !m.is_empty())
+        .map(|m| (m, path.to_string()))
+        .collect()
+}
+
+fn js_routes(line: &str) -> Vec<(String, String)> {
+    // Express: app.get('/x', ...), router.post("/x", ...)
+    for method in HTTP_METHODS.iter().chain(std::iter::once(&"all")) {
+        let marker = format!(".{method}(");
+        if let Some(idx) = line.find(&marker)
+            && let Some(path) = first_string_literal(&line[idx + marker.len()..])
+        {
+            let method = if *method == "all" {
+                "ANY".to_string()
+            } else {
+                method.to_ascii_uppercase()
+            };
+            return vec![(method, path.to_string())];
+        }
+    }
+    Vec::new()
+}
+
+fn go_routes(line: &str) -> Vec<(String, String)> {
+    // gin/echo/chi: r.GET("/x", h), e.Post("/x", h)
+    for method in HTTP_METHODS {
+        let upper = method.to_ascii_uppercase();
+        let mut title = method.to_string();
+        title[..1].make_ascii_uppercase();
+        for marker in [format!(".{upper}("), format!(".{title}(")] {
+            if let Some(idx) = line.find(&marker)
+                && let Some(path) = first_string_literal(&line[idx + marker.len()..])
+            {
+                return vec![(upper, path.to_string())];
+            }
+        }
+    }
+
+    // net/http and gorilla/mux: HandleFunc("/x", h).Methods("GET")
+    for marker in ["HandleFunc(", "Handle("] {
+        if let Some(idx) = line.find(marker)
+            && let Some(path) = first_string_literal(&line[idx + marker.len()..])
+        {
+            let methods = line
+                .split_once(".Methods(")
+                .map(|(_, rest)| {
+                    rest.split(')')
+                        .next()
+                        .unwrap_or_default()
+                        .split(',')
+                        .map(|m| m.trim().trim_matches('"').to_ascii_uppercase())
+                        .filter(|m| !m.is_empty())
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+            if methods.is_empty() {
+                return vec![("ANY".to_string(), path.to_string())];
+            }
+            return methods.into_iter().map(|m| (m, path.to_string())).collect();
+        }
+    }
+    Vec::new()
+}
+
+fn spring_routes(line: &str) -> Vec<(String, String)> {
+    // Spring: @GetMapping("/x"), @PostMapping(value = "/x"), @RequestMapping(...)
+    let Some(annotation) = line.strip_prefix('@') else {
+        return Vec::new();
+    };
+    let Some((name, args)) = annotation.split_once('(') else {
+        return Vec::new();
+    };
+    let Some(path) = first_string_literal(args) else {
+        return Vec::new();
+    };
+
+    let method = match name {
+        "GetMapping" => "GET",
+        "PostMapping" => "POST",
+        "PutMapping" => "PUT",
+        "DeleteMapping" => "DELETE",
+        "PatchMapping" => "PATCH",
+        "RequestMapping" => args
+            .split_once("RequestMethod.")
+            .map(|(_, rest)| {
+                rest.split(|c: char| !c.is_ascii_alphabetic())
+                    .next()
+                    .unwrap_or("ANY")
+            })
+            .unwrap_or("ANY"),
+        _ => return Vec::new(),
+    };
+    vec![(method.to_string(), path.to_string())]
+}
@@ -0,0 +1,162 @@
+//! Symbols of every project documented under one docs root.
+//!
+//! Projects that share a docs root can be searched together, e.g. to find which repository
+//! defines a type. The index is rebuilt from each project's `.memory.json` after every full run
+//! when `PlainSightConfig::org_index` is set.
+
+use std::{
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::{
+    error::{PlainSightError, Result},
+    project_manager::ProjectManager,
+    symbol_search::{self, SymbolMatch, SymbolPattern},
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrgIndex {
+    pub generated_at: u64,
+    pub projects: Vec<OrgProject>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrgProject {
+    pub name: String,
+    /// Source tree the project was last documented from, when recorded.
+    pub source_root: Option<PathBuf>,
+    pub symbols: Vec<SymbolMatch>,
+}
+
+/// A symbol found by [`search_org_symbols`], with the project defining it.
+#[derive(Debug, Clone, Serialize)]
+pub struct OrgSymbolMatch {
+    pub project: String,
+    pub source_root: Option<PathBuf>,
+    #[serde(flatten)]
+    pub symbol: SymbolMatch,
+}
+
+/// Collect the global symbols and links of every project with a project memory.
+///
+/// Projects whose memory cannot be read are left out with a warning.
+pub fn build_org_index(manager: &ProjectManager) -> Result<OrgIndex> {
+    let mut projects = Vec::new();
+    for name in manager.project_names()? {
+        let project = manager.new_project(name.as_str(), PathBuf::new());
+        if !project.store().exists(&project.memory_path()) {
+            continue;
+        }
+        let memory = match project.load_memory() {
+            Ok(memory) => memory,
+            Err(e) => {
+                warn!(project = %name, error = %e, "org_index_project_skipped");
+                continue;
+            }
+        };
+        let source_root = project
+            .load_source_root()
+            .ok()
+            .flatten()
+            .map(|record| record.source_root);
+        projects.push(OrgProject {
+            name,
+            source_root,
+            symbols: symbol_search::all_symbols(&memory),
+        });
+    }
+    Ok(OrgIndex {
+        generated_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default(),
+        projects,
+    })
+}
+
+/// Rebuild `.org_index.json` at the docs root.
+pub fn write_org_index(manager: &ProjectManager) -> Result<()> {
+    let index = build_org_index(manager)?;
+    let index_path = manager.org_index_path();
+    let content = serde_json::to_string_pretty(&index)
+        .map_err(|e| PlainSightError::InvalidState(format!("serializing org index: {e}")))?;
+    manager
+        .store()
+        .write(&index_path, content.as_bytes())
+        .map_err(|e| {
+            PlainSightError::io(format!("writing org index '{}'", index_path.display()), e)
+        })?;
+
+    info!(
+        project_count = index.projects.len(),
+        symbol_count = index
+            .projects
+            .iter()
+            .map(|project| project.symbols.len())
+            .sum::<usize>(),
+        org_index_path = %index_path.display(),
+        "org_index_written"
+    );
+    Ok(())
+}
+
+/// Search the symbols of every project under the docs root; patterns and ranking are those of
+/// [`symbol_search::search_symbols`].
+///
+/// Reads `.org_index.json`, or builds the index from the project memories when there is none.
+pub fn search_org_symbols(
+    manager: &ProjectManager,
+    pattern: &str,
+    limit: usize,
+) -> Result<Vec<OrgSymbolMatch>> {
+    let index_path = manager.org_index_path();
+    let index = if manager.store().exists(&index_path) {
+        let content = manager.store().read_to_string(&index_path).map_err(|e| {
+            PlainSightError::io(format!("reading org index '{}'", index_path.display()), e)
+        })?;
+        serde_json::from_str(&content).map_err(|e| {
+            PlainSightError::InvalidState(format!(
+                "failed to parse org index '{}': {e}",
+                index_path.display()
+            ))
+        })?
+    } else {
+        build_org_index(manager)?
+    };
+    Ok(match_org_symbols(index, pattern, limit))
+}
+
+/// `search_org_symbols` over an already loaded `index`.
+pub fn match_org_symbols(index: OrgIndex, pattern: &str, limit: usize) -> Vec<OrgSymbolMatch> {
+    let pattern = SymbolPattern::new(pattern);
+    let mut ranked = index
+        .projects
+        .into_iter()
+        .flat_map(|project| {
+            let OrgProject {
+                name,
+                source_root,
+                symbols,
+            } = project;
+            symbols.into_iter().map(move |symbol| OrgSymbolMatch {
+                project: name.clone(),
+                source_root: source_root.clone(),
+                symbol,
+            })
+        })
+        .filter_map(|found| Some((pattern.rank(&found.symbol.name)?, found)))
+        .collect::<Vec<_>>();
+    ranked.sort_by(|(a_rank, a), (b_rank, b)| {
+        symbol_search::compare_ranked(
+            (*a_rank, &a.symbol.name, &a.symbol.kind),
+            (*b_rank, &b.symbol.name, &b.symbol.kind),
+        )
+        .then_with(|| a.project.cmp(&b.project))
+    });
+    ranked.truncate(limit);
+    ranked.into_iter().map(|(_, found)| found).collect()
+}
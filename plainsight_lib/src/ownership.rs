@@ -0,0 +1,254 @@
+use std::{collections::BTreeMap, path::Path};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Only the comment block at the top of a file is searched for owners.
+const HEADER_SCAN_LINES: usize = 40;
+const HEADER_MARKERS: &[&str] = &[
+    "@author",
+    "@maintainer",
+    "authors:",
+    "author:",
+    "maintainers:",
+    "maintainer:",
+    "owners:",
+    "owner:",
+    "maintained by",
+];
+const COMMENT_PREFIXES: &[&str] = &["///", "//!", "//", "/**", "/*", "*", "#", "--", ";"];
+
+/// Authors and maintainers declared by one package manifest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestOwners {
+    pub manifest: String,
+    pub owners: Vec<String>,
+}
+
+/// Who to contact about a file, and where that came from.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FileOwners {
+    pub owners: Vec<String>,
+    /// `file header`, or the manifest the owners were taken from.
+    pub source: String,
+}
+
+pub const HEADER_SOURCE: &str = "file header";
+
+/// Read `authors` from a `Cargo.toml` (`[package]` or `[workspace.package]`), or `author`,
+/// `maintainers` and `contributors` from a `package.json`.
+///
+/// Returns `None` when the manifest names nobody.
+pub fn parse_manifest_owners(relative_path: &str, content: &str) -> Option<ManifestOwners> {
+    let file_name = Path::new(relative_path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+    let owners = match file_name {
+        "Cargo.toml" => cargo_authors(content),
+        "package.json" => package_json_people(content),
+        _ => Vec::new(),
+    };
+    if owners.is_empty() {
+        return None;
+    }
+    Some(ManifestOwners {
+        manifest: relative_path.to_string(),
+        owners,
+    })
+}
+
+/// Owners named in the leading comment block of a source file, e.g. `// Author: Jane Doe`,
+/// `@author jdoe` or `# Maintained by the storage team`.
+pub fn detect_header_owners(source: &str) -> Vec<String> {
+    let mut owners = Vec::new();
+    for raw_line in source.lines().take(HEADER_SCAN_LINES) {
+        let line = raw_line.trim();
+        if line.is_empty() || line.starts_with("#!") {
+            continue;
+        }
+        let Some(comment) = COMMENT_PREFIXES
+            .iter()
+            .find_map(|prefix| line.strip_prefix(prefix))
+        else {
+            break;
+        };
+        let comment = comment.trim().trim_end_matches("*/").trim();
+        let lower = comment.to_lowercase();
+        let Some(marker) = HEADER_MARKERS
+            .iter()
+            .find(|marker| lower.starts_with(*marker))
+        else {
+            continue;
+        };
+        let value = comment
+            .get(marker.len()..)
+            .unwrap_or_default()
+            .trim_start_matches(':')
+            .trim();
+        if !value.is_empty() && !owners.iter().any(|owner| owner == value) {
+            owners.push(value.to_string());
+        }
+    }
+    owners
+}
+
+/// Owners of `relative_path`: its own header when it names anyone, otherwise the manifest
+/// closest to it.
+pub fn resolve_file_owners(
+    relative_path: &str,
+    header_owners: &[String],
+    manifests: &[ManifestOwners],
+) -> Option<FileOwners> {
+    if !header_owners.is_empty() {
+        return Some(FileOwners {
+            owners: header_owners.to_vec(),
+            source: HEADER_SOURCE.to_string(),
+        });
+    }
+    manifests
+        .iter()
+        .filter(|manifest| {
+            let dir = Path::new(&manifest.manifest)
+                .parent()
+                .unwrap_or(Path::new(""));
+            Path::new(relative_path).starts_with(dir)
+        })
+        .max_by_key(|manifest| Path::new(&manifest.manifest).components().count())
+        .map(|manifest| FileOwners {
+            owners: manifest.owners.clone(),
+            source: manifest.manifest.clone(),
+        })
+}
+
+/// `markdown` without a leading `---` front-matter block.
+pub fn strip_front_matter(markdown: &str) -> &str {
+    let Some(rest) = markdown.strip_prefix("---\n") else {
+        return markdown;
+    };
+    match rest.find("\n---\n") {
+        Some(end) => rest[end + "\n---\n".len()..].trim_start_matches('\n'),
+        None => markdown,
+    }
+}
+
+/// Markdown list of who owns what, for the project summary's metadata block: manifest owners
+/// with the number of files they cover, then the files whose headers name their own.
+pub fn render_owners_outline<'a>(
+    files: impl IntoIterator<Item = (&'a str, Option<&'a FileOwners>)>,
+) -> String {
+    let mut by_manifest: BTreeMap<&str, (&[String], usize)> = BTreeMap::new();
+    let mut by_header: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+    for (path, owners) in files {
+        let Some(owners) = owners else {
+            continue;
+        };
+        if owners.source == HEADER_SOURCE {
+            by_header
+                .entry(owners.owners.join(", "))
+                .or_default()
+                .push(path);
+        } else {
+            by_manifest
+                .entry(owners.source.as_str())
+                .or_insert((&owners.owners, 0))
+                .1 += 1;
+        }
+    }
+
+    let mut out = String::new();
+    for (manifest, (owners, count)) in by_manifest {
+        out.push_str(&format!(
+            "- {} (`{manifest}`, {count} files)\n",
+            owners.join(", ")
+        ));
+    }
+    for (owners, paths) in by_header {
+        let paths = paths
+            .iter()
+            .map(|path| format!("`{path}`"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        out.push_str(&format!("- {owners} (file headers: {paths})\n"));
+    }
+    out
+}
+
+fn cargo_authors(content: &str) -> Vec<String> {
+    let mut section = String::new();
+    let mut pending: Option<String> = None;
+    for raw_line in content.lines() {
+        let line = raw_line.trim();
+        if let Some(buffer) = pending.as_mut() {
+            buffer.push_str(line);
+            if line.contains(']') {
+                return quoted_items(buffer);
+            }
+            continue;
+        }
+        if line.starts_with('[') {
+            section = line.trim_matches(['[', ']']).trim().to_string();
+            continue;
+        }
+        if !matches!(section.as_str(), "package" | "workspace.package") {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim();
+        if key.trim() != "authors" || !value.starts_with('[') {
+            continue;
+        }
+        if value.contains(']') {
+            return quoted_items(value);
+        }
+        pending = Some(value.to_string());
+    }
+    Vec::new()
+}
+
+fn package_json_people(content: &str) -> Vec<String> {
+    let Ok(manifest) = serde_json::from_str::<Value>(content) else {
+        return Vec::new();
+    };
+    let mut people = Vec::new();
+    if let Some(author) = manifest.get("author").and_then(person) {
+        people.push(author);
+    }
+    for key in ["maintainers", "contributors"] {
+        if let Some(list) = manifest.get(key).and_then(Value::as_array) {
+            for entry in list.iter().filter_map(person) {
+                if !people.contains(&entry) {
+                    people.push(entry);
+                }
+            }
+        }
+    }
+    people
+}
+
+/// `"Jane <jane@example.com>"` or `{ "name": "Jane", "email": "jane@example.com" }`.
+fn person(value: &Value) -> Option<String> {
+    match value {
+        Value::String(name) if !name.trim().is_empty() => Some(name.trim().to_string()),
+        Value::Object(fields) => {
+            let name = fields.get("name").and_then(Value::as_str)?.trim();
+            match fields.get("email").and_then(Value::as_str) {
+                Some(email) => Some(format!("{name} <{email}>")),
+                None => Some(name.to_string()),
+            }
+        }
+        _ => None,
+    }
+}
+
+fn quoted_items(array: &str) -> Vec<String> {
+    array
+        .split('"')
+        .skip(1)
+        .step_by(2)
+        .map(str::to_string)
+        .filter(|item| !item.trim().is_empty())
+        .collect()
+}
@@ -0,0 +1,339 @@
+//! Oversized generated pages, split into linked sub-pages by section.
+//!
+//! A page longer than `PageSplitConfig::max_lines` keeps its front-matter and whatever comes
+//! before its first section, followed by a contents list linking one sub-page per section
+//! under `<stem>.pages/`, e.g. `architecture.pages/02-components.md`. Links to headings that
+//! moved to another page are pointed at that page. [`read_page`] joins the sub-pages back, so
+//! exports, retrieval and later runs see the whole document.
+
+use std::{
+    collections::BTreeMap,
+    io,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use tracing::debug;
+
+use crate::{config::PageSplitConfig, docs_store::DocsStore};
+
+const CONTENTS_START: &str = "<!-- plainsight:pages -->";
+const CONTENTS_END: &str = "<!-- /plainsight:pages -->";
+const SUB_PAGE_SUFFIX: &str = ".pages";
+
+/// Write `markdown` to `path`, split into sub-pages when it is longer than
+/// `config.max_lines`. Returns the number of sub-pages written; 0 when the page was written
+/// whole.
+///
+/// Sub-pages of an earlier, longer version are left in place but no longer linked.
+pub fn write_page(
+    store: &dyn DocsStore,
+    path: &Path,
+    markdown: &str,
+    config: &PageSplitConfig,
+) -> io::Result<usize> {
+    let split = config
+        .max_lines
+        .filter(|max_lines| markdown.lines().count() > *max_lines)
+        .and_then(|_| split_page(path, markdown));
+    let Some(split) = split else {
+        store.write(path, markdown.as_bytes())?;
+        return Ok(0);
+    };
+
+    let dir = sub_page_dir(path);
+    store.create_dir_all(&dir)?;
+    for (file_name, content) in &split.pages {
+        store.write(&dir.join(file_name), content.as_bytes())?;
+    }
+    store.write(path, split.index.as_bytes())?;
+    debug!(
+        page = %path.display(),
+        sub_pages = split.pages.len(),
+        lines = markdown.lines().count(),
+        "page_split"
+    );
+    Ok(split.pages.len())
+}
+
+/// The page at `path` with its sub-pages joined back in; the page itself when it was written
+/// whole.
+pub fn read_page(store: &dyn DocsStore, path: &Path) -> io::Result<String> {
+    let page = store.read_to_string(path)?;
+    let Some((preamble, rest)) = page.split_once(CONTENTS_START) else {
+        return Ok(page);
+    };
+    let Some((contents, _)) = rest.split_once(CONTENTS_END) else {
+        return Ok(page);
+    };
+
+    let parent = page_file_name(path);
+    let dir_name = sub_page_dir_name(path);
+    let nav = back_link(&parent);
+    let mut joined = String::with_capacity(page.len());
+    joined.push_str(&rewrite_links(preamble, |dest| {
+        local_anchor(dest.strip_prefix(&format!("{dir_name}/"))?)
+    }));
+    for file_name in contents_links(contents, &dir_name) {
+        let sub_page = store.read_to_string(&sub_page_dir(path).join(&file_name))?;
+        let section = sub_page.strip_prefix(&nav).unwrap_or(&sub_page);
+        joined.push_str(&rewrite_links(section, |dest| {
+            match dest.strip_prefix(&format!("../{parent}")) {
+                Some(anchor) => anchor.starts_with('#').then(|| anchor.to_string()),
+                None => local_anchor(dest),
+            }
+        }));
+    }
+    Ok(joined)
+}
+
+/// The parent page and its sub-pages, by file name.
+struct SplitPage {
+    index: String,
+    pages: Vec<(String, String)>,
+}
+
+struct Heading {
+    level: HeadingLevel,
+    range: Range<usize>,
+    text: String,
+    anchor: String,
+}
+
+/// `markdown` split at its outermost headings that occur more than once; `None` when it has
+/// no such level.
+fn split_page(path: &Path, markdown: &str) -> Option<SplitPage> {
+    let headings = headings(markdown);
+    let split_level = [
+        HeadingLevel::H1,
+        HeadingLevel::H2,
+        HeadingLevel::H3,
+        HeadingLevel::H4,
+    ]
+    .into_iter()
+    .find(|level| headings.iter().filter(|h| h.level == *level).count() > 1)?;
+    let first = headings.iter().position(|h| h.level == split_level)?;
+    let sections = headings[first..]
+        .iter()
+        .filter(|heading| heading.level <= split_level)
+        .collect::<Vec<_>>();
+
+    let parent = page_file_name(path);
+    let dir_name = sub_page_dir_name(path);
+    let preamble_end = sections[0].range.start;
+    let file_names = sections
+        .iter()
+        .enumerate()
+        .map(|(i, heading)| format!("{:02}-{}.md", i + 1, file_slug(&heading.text)))
+        .collect::<Vec<_>>();
+    let section_ranges = sections
+        .iter()
+        .enumerate()
+        .map(|(i, heading)| {
+            let end = sections
+                .get(i + 1)
+                .map(|next| next.range.start)
+                .unwrap_or(markdown.len());
+            heading.range.start..end
+        })
+        .collect::<Vec<_>>();
+
+    // Where each heading ended up: `None` for the parent page, the sub-page index otherwise.
+    let anchor_pages = headings
+        .iter()
+        .map(|heading| {
+            let page = section_ranges
+                .iter()
+                .position(|range| range.contains(&heading.range.start));
+            (heading.anchor.clone(), page)
+        })
+        .collect::<BTreeMap<_, _>>();
+    let target = |dest: &str, from: Option<usize>| -> Option<String> {
+        let anchor = dest.strip_prefix('#')?;
+        let to = *anchor_pages.get(anchor)?;
+        if to == from {
+            return None;
+        }
+        Some(match (from, to) {
+            (None, Some(page)) => format!("{dir_name}/{}#{anchor}", file_names[page]),
+            (Some(_), Some(page)) => format!("{}#{anchor}", file_names[page]),
+            (_, None) => format!("../{parent}#{anchor}"),
+        })
+    };
+
+    let preamble = rewrite_links(&markdown[..preamble_end], |dest| target(dest, None));
+    let mut index = String::new();
+    if !preamble.trim().is_empty() {
+        index.push_str(preamble.trim_end());
+        index.push_str("\n\n");
+    }
+    index.push_str(CONTENTS_START);
+    index.push('\n');
+    for (heading, file_name) in sections.iter().zip(&file_names) {
+        index.push_str(&format!(
+            "- [{}]({dir_name}/{file_name})\n",
+            heading.text.replace(['[', ']'], "")
+        ));
+    }
+    index.push_str(CONTENTS_END);
+    index.push('\n');
+
+    let nav = back_link(&parent);
+    let pages = section_ranges
+        .into_iter()
+        .enumerate()
+        .map(|(i, range)| {
+            let section = rewrite_links(&markdown[range], |dest| target(dest, Some(i)));
+            (file_names[i].clone(), format!("{nav}{section}"))
+        })
+        .collect();
+
+    Some(SplitPage { index, pages })
+}
+
+fn markdown_options() -> Options {
+    Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS
+}
+
+/// Every heading of `markdown` with its GitHub-style anchor.
+fn headings(markdown: &str) -> Vec<Heading> {
+    let mut headings = Vec::new();
+    let mut current: Option<(HeadingLevel, Range<usize>, String)> = None;
+    for (event, range) in Parser::new_ext(markdown, markdown_options()).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                current = Some((level, range, String::new()));
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, _, heading_text)) = &mut current {
+                    heading_text.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some((level, range, text)) = current.take() {
+                    headings.push(Heading {
+                        level,
+                        range,
+                        text: text.trim().to_string(),
+                        anchor: String::new(),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut seen = BTreeMap::<String, usize>::new();
+    for heading in &mut headings {
+        let base = anchor_slug(&heading.text);
+        let count = seen.entry(base.clone()).or_default();
+        heading.anchor = match *count {
+            0 => base,
+            n => format!("{base}-{n}"),
+        };
+        *count += 1;
+    }
+    headings
+}
+
+/// `markdown` with the destinations of its inline links replaced where `map` returns one.
+fn rewrite_links(markdown: &str, map: impl Fn(&str) -> Option<String>) -> String {
+    let mut out = String::with_capacity(markdown.len());
+    let mut copied = 0;
+    for (event, range) in Parser::new_ext(markdown, markdown_options()).into_offset_iter() {
+        let Event::Start(Tag::Link { dest_url, .. }) = event else {
+            continue;
+        };
+        let Some(new_dest) = map(&dest_url) else {
+            continue;
+        };
+        let Some(at) = markdown[range.clone()].rfind(&format!("]({dest_url}")) else {
+            continue;
+        };
+        let start = range.start + at + 2;
+        out.push_str(&markdown[copied..start]);
+        out.push_str(&new_dest);
+        copied = start + dest_url.len();
+    }
+    out.push_str(&markdown[copied..]);
+    out
+}
+
+/// The sub-page file names listed between the contents markers, in order.
+fn contents_links(contents: &str, dir_name: &str) -> Vec<String> {
+    let prefix = format!("]({dir_name}/");
+    contents
+        .lines()
+        .filter_map(|line| {
+            let (_, rest) = line.split_once(&prefix)?;
+            Some(rest.strip_suffix(')')?.to_string())
+        })
+        .collect()
+}
+
+/// `#anchor` for a link to another sub-page of the same page, e.g. `03-errors.md#anchor`.
+fn local_anchor(dest: &str) -> Option<String> {
+    let (file_name, anchor) = dest.split_once('#')?;
+    let is_sub_page = !file_name.contains('/')
+        && file_name.ends_with(".md")
+        && file_name.split_once('-').is_some_and(|(number, _)| {
+            number.len() == 2 && number.chars().all(|c| c.is_ascii_digit())
+        });
+    is_sub_page.then(|| format!("#{anchor}"))
+}
+
+fn back_link(parent: &str) -> String {
+    format!("[↑ Contents](../{parent})\n\n")
+}
+
+fn page_file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn sub_page_dir_name(path: &Path) -> String {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    format!("{stem}{SUB_PAGE_SUFFIX}")
+}
+
+fn sub_page_dir(path: &Path) -> PathBuf {
+    path.with_file_name(sub_page_dir_name(path))
+}
+
+/// The anchor GitHub and most Markdown renderers give a heading with `text`.
+pub(crate) fn anchor_slug(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_'))
+        .map(|c| if c == ' ' { '-' } else { c })
+        .collect()
+}
+
+fn file_slug(text: &str) -> String {
+    let mut slug = String::new();
+    for c in text.to_lowercase().chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        "section".to_string()
+    } else {
+        slug.chars()
+            .take(48)
+            .collect::<String>()
+            .trim_end_matches('-')
+            .to_string()
+    }
+}
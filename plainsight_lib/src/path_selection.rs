@@ -0,0 +1,74 @@
+//! Files picked by path, directory or glob, for runs limited with `PlainSightConfig::paths`.
+
+use std::path::{Component, Path, PathBuf};
+
+use crate::{
+    error::{PlainSightError, Result},
+    symbol_search::glob_matches,
+};
+
+/// Paths relative to the project root, each selecting a file, or every file under a
+/// directory. Segments may hold `*` and `?` globs, and a `**` segment stands for any number of
+/// directories, so `src/parser/**`, `src/parser` and `src/*/mod.rs` all work.
+#[derive(Debug, Clone)]
+pub struct PathSelection {
+    patterns: Vec<Vec<String>>,
+}
+
+impl PathSelection {
+    /// `paths` are relative to `project_root`, or absolute paths inside it.
+    pub fn new(project_root: &Path, paths: &[PathBuf]) -> Result<Self> {
+        let patterns = paths
+            .iter()
+            .map(|path| pattern_segments(project_root, path))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { patterns })
+    }
+
+    /// Whether a pattern matches `relative_path` (`/`-separated) or one of its directories.
+    pub fn matches(&self, relative_path: &str) -> bool {
+        let segments = relative_path.split('/').collect::<Vec<_>>();
+        self.patterns.iter().any(|pattern| {
+            (1..=segments.len()).any(|len| segments_match(pattern, &segments[..len]))
+        })
+    }
+}
+
+fn pattern_segments(project_root: &Path, path: &Path) -> Result<Vec<String>> {
+    let outside = || PlainSightError::PathOutsideProject {
+        path: path.to_path_buf(),
+        project_root: project_root.to_path_buf(),
+    };
+    let relative = if path.is_absolute() {
+        path.strip_prefix(project_root).map_err(|_| outside())?
+    } else {
+        path
+    };
+    let mut segments = Vec::new();
+    for component in relative.components() {
+        match component {
+            Component::Normal(part) => segments.push(part.to_string_lossy().into_owned()),
+            Component::CurDir => {}
+            _ => return Err(outside()),
+        }
+    }
+    if segments.is_empty() {
+        return Err(PlainSightError::InvalidState(format!(
+            "path '{}' selects the whole project; run without paths instead",
+            path.display()
+        )));
+    }
+    Ok(segments)
+}
+
+fn segments_match(pattern: &[String], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((segment, rest)) if segment == "**" => {
+            (0..=path.len()).any(|skip| segments_match(rest, &path[skip..]))
+        }
+        Some((segment, rest)) => path.split_first().is_some_and(|(name, path_rest)| {
+            glob_matches(segment, name) && segments_match(rest, path_rest)
+        }),
+    }
+}
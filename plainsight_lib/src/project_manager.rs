@@ -1,17 +1,48 @@
 use std::{
     collections::{BTreeMap, hash_map::DefaultHasher},
     fs,
-    hash::{Hash, Hasher},
+    hash::Hasher,
+    io::{self, BufRead, IsTerminal, Read, Write},
     path::{Path, PathBuf},
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
 };
 
 use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{
+    config::NameCollisionPolicy,
+    docs_store::{DocsStore, DryRunDocsStore, LocalDocsStore},
+    error::{PlainSightError, Result},
+    memory::{ProjectMemory, SymbolFingerprint},
+    state_files,
+};
+
+const HASH_BUFFER_BYTES: usize = 64 * 1024;
+
+/// Written into every project docs directory; source discovery never descends into a
+/// directory holding it, whatever the directory is called.
+pub const OUTPUT_MARKER_FILE: &str = ".plainsight-output";
+
+/// Records which source tree a project docs directory was generated from.
+pub const SOURCE_ROOT_FILE: &str = ".source-root.json";
+
+/// Symbols of every project under a docs root, written when `PlainSightConfig::org_index` is
+/// set.
+pub const ORG_INDEX_FILE: &str = ".org_index.json";
 
-use crate::error::{PlainSightError, Result};
+/// Outcomes of task requests per model, written when `ModelStatsConfig::enabled` is set.
+pub const MODEL_STATS_FILE: &str = ".model_stats.json";
+
+/// Page linking the projects of a workspace, written by `PlainSight::run_workspace`.
+pub const WORKSPACE_FILE: &str = "workspace.md";
 
 #[derive(Debug)]
 pub struct ProjectManager {
     docs_root: PathBuf,
+    store: Arc<dyn DocsStore>,
+    repair_state_files: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -19,22 +50,387 @@ pub struct ProjectContext {
     docs_root: PathBuf,
     project_name: String,
     project_root: PathBuf,
+    scope: Option<PathBuf>,
+    store: Arc<dyn DocsStore>,
+    repair_state_files: bool,
+}
+
+/// The source tree a project docs directory belongs to.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SourceRootRecord {
+    /// Canonical path of the source root. Records written before the path alone was compared
+    /// also carry a `fingerprint`, which is ignored.
+    pub source_root: PathBuf,
+}
+
+impl SourceRootRecord {
+    pub fn for_root(project_root: &Path) -> Self {
+        let source_root =
+            fs::canonicalize(project_root).unwrap_or_else(|_| project_root.to_path_buf());
+        Self { source_root }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct MetaCache {
     pub files: BTreeMap<String, FileMeta>,
+    #[serde(default)]
+    pub models: BTreeMap<String, String>,
+    /// Set by a cancelled run that finished some changed files, so the next full run rewrites
+    /// the project summary and architecture even when no file is left to regenerate.
+    #[serde(default)]
+    pub project_docs_stale: bool,
+    /// Hash of the models, prompts and file summaries the project summary and architecture were
+    /// last generated from; see `workflow::project_hash`.
+    #[serde(default)]
+    pub project_docs_hash: Option<String>,
+}
+
+/// The file outputs of a run that has not finished, saved after each one so that a run that
+/// stops can be continued with `PlainSightConfig::resume`. Cleared when a run finishes.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct RunCheckpoint {
+    /// The run's task models, as in [`MetaCache::models`].
+    #[serde(default)]
+    pub models: BTreeMap<String, String>,
+    /// Content hash each written file summary is for, by relative path.
+    #[serde(default)]
+    pub summaries: BTreeMap<String, String>,
+    /// Written file docs, by relative path.
+    #[serde(default)]
+    pub docs: BTreeMap<String, CheckpointedDocs>,
+}
+
+impl RunCheckpoint {
+    pub fn is_empty(&self) -> bool {
+        self.summaries.is_empty() && self.docs.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CheckpointedDocs {
+    /// Content hash the docs are for.
+    pub hash: String,
+    /// Open questions split from the docs, for `questions.md`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub questions: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct FileMeta {
     pub hash: String,
+    /// Body fingerprints of the file's symbols at the time of `hash`.
+    #[serde(default)]
+    pub symbols: Vec<SymbolFingerprint>,
+    /// The smallest prompt profile each `<task>/<model>` needed for this content, when it was
+    /// smaller than [`PromptProfile::Standard`].
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub prompt_profiles: BTreeMap<String, PromptProfile>,
+}
+
+/// How much context a per-file prompt carries, from most to least.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PromptProfile {
+    #[default]
+    Standard,
+    Compact,
+    /// Last resort for inputs that failed even with the compact context.
+    Minimal,
+}
+
+impl PromptProfile {
+    /// The next smaller profile, if any.
+    pub fn smaller(self) -> Option<Self> {
+        match self {
+            Self::Standard => Some(Self::Compact),
+            Self::Compact => Some(Self::Minimal),
+            Self::Minimal => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct AnnotationStore {
+    pub files: BTreeMap<String, Vec<Annotation>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Annotation {
+    pub question: String,
+    pub answer: String,
+}
+
+/// Files left out of generation, in `.skip.json`. Files whose summary or docs ran over
+/// `OllamaConfig::file_timeout` are added by the run; users can add or remove entries.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SkipList {
+    pub files: Vec<SkipEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SkipEntry {
+    /// Relative to the project root.
+    pub path: String,
+    #[serde(default)]
+    pub reason: String,
+    /// Unix seconds; 0 for entries added by hand without one.
+    #[serde(default)]
+    pub skipped_at: u64,
+}
+
+impl SkipEntry {
+    /// An entry for `path` skipped now.
+    pub fn new(path: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            reason: reason.into(),
+            skipped_at: unix_now(),
+        }
+    }
+}
+
+impl SkipList {
+    pub fn contains(&self, relative_path: &str) -> bool {
+        self.files.iter().any(|entry| entry.path == relative_path)
+    }
+}
+
+/// A lock older than this is taken as left behind even when its holder cannot be checked,
+/// e.g. because it ran on another host.
+const STALE_LOCK_SECS: u64 = 24 * 60 * 60;
+
+/// The run holding a project's `.lock`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockHolder {
+    pub pid: u32,
+    pub host: String,
+    /// Unix seconds.
+    pub started_at: u64,
+}
+
+impl LockHolder {
+    fn current() -> Self {
+        Self {
+            pid: std::process::id(),
+            host: host_name(),
+            started_at: unix_now(),
+        }
+    }
+
+    /// Whether the run that took the lock is gone: its process no longer exists on this host,
+    /// or the lock is older than `STALE_LOCK_SECS`.
+    fn is_stale(&self) -> bool {
+        if unix_now().saturating_sub(self.started_at) > STALE_LOCK_SECS {
+            return true;
+        }
+        // Without `/proc` the process cannot be looked up, so it is taken as running.
+        let proc = Path::new("/proc");
+        self.host == host_name() && proc.is_dir() && !proc.join(self.pid.to_string()).exists()
+    }
+
+    fn describe(&self) -> String {
+        let minutes = unix_now().saturating_sub(self.started_at) / 60;
+        format!(
+            "pid {} on '{}', started {minutes} min ago",
+            self.pid, self.host
+        )
+    }
+}
+
+/// Held while a run writes to a project's docs directory; removes `.lock` when dropped.
+#[derive(Debug)]
+pub struct RunLock {
+    store: Arc<dyn DocsStore>,
+    path: PathBuf,
+}
+
+impl Drop for RunLock {
+    fn drop(&mut self) {
+        if let Err(e) = self.store.remove_file(&self.path) {
+            warn!(lock_path = %self.path.display(), error = %e, "failed removing run lock");
+        }
+    }
+}
+
+fn host_name() -> String {
+    std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .ok()
+        .or_else(|| fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .unwrap_or_default()
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ProjectTimeline {
+    pub runs: Vec<RunSnapshot>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunSnapshot {
+    pub timestamp: u64,
+    pub file_count: usize,
+    pub symbol_count: usize,
+    pub link_count: usize,
+    pub documented_file_count: usize,
+}
+
+impl RunSnapshot {
+    pub fn coverage(&self) -> f64 {
+        if self.file_count == 0 {
+            return 0.0;
+        }
+        self.documented_file_count as f64 / self.file_count as f64
+    }
 }
 
 impl ProjectManager {
     pub fn new(docs_root: impl Into<PathBuf>) -> Self {
+        Self::with_store(docs_root, Arc::new(LocalDocsStore))
+    }
+
+    pub fn with_store(docs_root: impl Into<PathBuf>, store: Arc<dyn DocsStore>) -> Self {
         Self {
             docs_root: docs_root.into(),
+            store,
+            repair_state_files: false,
+        }
+    }
+
+    /// Drop the invalid entries of hand-edited state files when loading them, instead of
+    /// failing; see `PlainSightConfig::repair_state_files`.
+    pub fn with_state_repair(mut self, repair: bool) -> Self {
+        self.repair_state_files = repair;
+        self
+    }
+
+    /// The same docs root with every write kept in memory; see [`DryRunDocsStore`].
+    pub fn dry_run(&self) -> Self {
+        Self {
+            docs_root: self.docs_root.clone(),
+            store: Arc::new(DryRunDocsStore::new(Arc::clone(&self.store))),
+            repair_state_files: self.repair_state_files,
+        }
+    }
+
+    pub fn docs_root(&self) -> &Path {
+        &self.docs_root
+    }
+
+    pub fn store(&self) -> &dyn DocsStore {
+        self.store.as_ref()
+    }
+
+    pub fn org_index_path(&self) -> PathBuf {
+        self.docs_root.join(ORG_INDEX_FILE)
+    }
+
+    pub fn model_stats_path(&self) -> PathBuf {
+        self.docs_root.join(MODEL_STATS_FILE)
+    }
+
+    pub fn workspace_path(&self) -> PathBuf {
+        self.docs_root.join(WORKSPACE_FILE)
+    }
+
+    /// Names of the projects documented under this docs root, sorted.
+    ///
+    /// A project is a directory holding the output marker. The local docs root is listed, as it
+    /// is the working copy of every store.
+    pub fn project_names(&self) -> Result<Vec<String>> {
+        let entries = match fs::read_dir(&self.docs_root) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(PlainSightError::io(
+                    format!("listing docs root '{}'", self.docs_root.display()),
+                    e,
+                ));
+            }
+        };
+        let mut names = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().join(OUTPUT_MARKER_FILE).is_file())
+            .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+            .collect::<Vec<_>>();
+        names.sort();
+        Ok(names)
+    }
+
+    /// The project name `project_root` should use under this docs root.
+    ///
+    /// A docs directory claimed by another source tree is never reused: depending on `policy`
+    /// the run is refused, moved to the first free `<name>_<n>`, or renamed on the terminal.
+    /// Directories without a source-root record (fresh, or from older runs) are free.
+    pub fn bind_project_name(
+        &self,
+        project_name: &str,
+        project_root: &Path,
+        policy: NameCollisionPolicy,
+    ) -> Result<String> {
+        let record = SourceRootRecord::for_root(project_root);
+        let mut name = project_name.to_string();
+        loop {
+            let Some(existing_root) = self.foreign_source_root(&name, &record)? else {
+                return Ok(name);
+            };
+            let collision = PlainSightError::ProjectNameCollision {
+                project_name: name.clone(),
+                existing_root: existing_root.clone(),
+                project_root: record.source_root.clone(),
+            };
+            let suggestion = self.free_suffixed_name(&name, &record)?;
+            match policy {
+                NameCollisionPolicy::Refuse => return Err(collision),
+                NameCollisionPolicy::Suffix => {
+                    warn!(
+                        project = %name,
+                        existing_root = %existing_root.display(),
+                        renamed_to = %suggestion,
+                        "project_name_collision"
+                    );
+                    return Ok(suggestion);
+                }
+                NameCollisionPolicy::Prompt => {
+                    if !io::stdin().is_terminal() {
+                        return Err(collision);
+                    }
+                    name = prompt_project_name(&name, &existing_root, &suggestion)?;
+                }
+            }
+        }
+    }
+
+    /// The source root recorded for `project_name` when it differs from `record`.
+    fn foreign_source_root(
+        &self,
+        project_name: &str,
+        record: &SourceRootRecord,
+    ) -> Result<Option<PathBuf>> {
+        let existing = self
+            .new_project(project_name, record.source_root.clone())
+            .load_source_root()?;
+        Ok(existing
+            .filter(|existing| existing.source_root != record.source_root)
+            .map(|existing| existing.source_root))
+    }
+
+    fn free_suffixed_name(&self, project_name: &str, record: &SourceRootRecord) -> Result<String> {
+        let mut suffix = 2;
+        loop {
+            let candidate = format!("{project_name}_{suffix}");
+            if self.foreign_source_root(&candidate, record)?.is_none() {
+                return Ok(candidate);
+            }
+            suffix += 1;
         }
     }
 
@@ -47,11 +443,18 @@ impl ProjectManager {
             docs_root: self.docs_root.clone(),
             project_name: project_name.into(),
             project_root: project_root.into(),
+            scope: None,
+            store: Arc::clone(&self.store),
+            repair_state_files: self.repair_state_files,
         }
     }
 }
 
 impl ProjectContext {
+    pub fn store(&self) -> &dyn DocsStore {
+        self.store.as_ref()
+    }
+
     pub fn project_docs_path(&self) -> PathBuf {
         self.docs_root.join(&self.project_name)
     }
@@ -60,18 +463,147 @@ impl ProjectContext {
         self.project_docs_path().join("files")
     }
 
+    /// Restrict project-level outputs to a subtree of the project root.
+    ///
+    /// Per-file docs keep their usual location; the project summary and architecture are
+    /// written under `scopes/<subtree>/` so the project-wide ones are left untouched.
+    pub fn with_scope(mut self, scope: impl Into<PathBuf>) -> Self {
+        self.scope = Some(scope.into());
+        self
+    }
+
+    pub fn scope(&self) -> Option<&Path> {
+        self.scope.as_deref()
+    }
+
+    pub fn scoped_docs_path(&self) -> PathBuf {
+        match &self.scope {
+            Some(scope) => self.project_docs_path().join("scopes").join(scope),
+            None => self.project_docs_path(),
+        }
+    }
+
     pub fn summary_path(&self) -> PathBuf {
-        self.project_docs_path().join("summary.md")
+        self.scoped_docs_path().join("summary.md")
     }
 
     pub fn architecture_path(&self) -> PathBuf {
-        self.project_docs_path().join("architecture.md")
+        self.scoped_docs_path().join("architecture.md")
+    }
+
+    /// Links to every file's pages; see `workflow::doc_index`.
+    pub fn index_path(&self) -> PathBuf {
+        self.project_docs_path().join("index.md")
     }
 
     pub fn meta_path(&self) -> PathBuf {
         self.project_docs_path().join(".meta.json")
     }
 
+    pub fn checkpoint_path(&self) -> PathBuf {
+        self.project_docs_path().join(".checkpoint.json")
+    }
+
+    pub fn memory_path(&self) -> PathBuf {
+        self.project_docs_path().join(".memory.json")
+    }
+
+    pub fn source_index_path(&self) -> PathBuf {
+        self.project_docs_path().join(".source_index.json")
+    }
+
+    pub fn ingest_snapshot_path(&self) -> PathBuf {
+        self.project_docs_path().join(".ingest.json")
+    }
+
+    pub fn module_tree_path(&self) -> PathBuf {
+        self.project_docs_path().join(".module_tree.json")
+    }
+
+    pub fn features_path(&self) -> PathBuf {
+        self.project_docs_path().join("features.md")
+    }
+
+    pub fn errors_path(&self) -> PathBuf {
+        self.project_docs_path().join("errors.md")
+    }
+
+    pub fn unsafe_path(&self) -> PathBuf {
+        self.project_docs_path().join("unsafe.md")
+    }
+
+    pub fn validation_path(&self) -> PathBuf {
+        self.project_docs_path().join("validation.md")
+    }
+
+    pub fn redactions_path(&self) -> PathBuf {
+        self.project_docs_path().join("redactions.md")
+    }
+
+    pub fn api_parity_path(&self) -> PathBuf {
+        self.project_docs_path().join("api_parity.md")
+    }
+
+    pub fn configuration_path(&self) -> PathBuf {
+        self.project_docs_path().join("configuration.md")
+    }
+
+    pub fn rpc_path(&self) -> PathBuf {
+        self.project_docs_path().join("rpc.md")
+    }
+
+    pub fn run_report_path(&self) -> PathBuf {
+        self.project_docs_path().join("run_report.json")
+    }
+
+    pub fn questions_path(&self) -> PathBuf {
+        self.project_docs_path().join("questions.md")
+    }
+
+    pub fn annotations_path(&self) -> PathBuf {
+        self.project_docs_path().join(".annotations.json")
+    }
+
+    pub fn skip_list_path(&self) -> PathBuf {
+        self.project_docs_path().join(".skip.json")
+    }
+
+    pub fn lock_path(&self) -> PathBuf {
+        self.project_docs_path().join(".lock")
+    }
+
+    pub fn model_upgrade_dir(&self) -> PathBuf {
+        self.project_docs_path().join("model_upgrade")
+    }
+
+    pub fn model_upgrade_report_path(&self) -> PathBuf {
+        self.project_docs_path().join("model_upgrade.md")
+    }
+
+    pub fn timeline_path(&self) -> PathBuf {
+        self.project_docs_path().join(".timeline.json")
+    }
+
+    pub fn trends_path(&self) -> PathBuf {
+        self.project_docs_path().join("trends.md")
+    }
+
+    pub fn digest_path(&self) -> PathBuf {
+        self.project_docs_path().join("digest.txt")
+    }
+
+    pub fn changes_path(&self) -> PathBuf {
+        self.scoped_docs_path().join("changes.md")
+    }
+
+    pub fn output_marker_path(&self) -> PathBuf {
+        self.project_docs_path().join(OUTPUT_MARKER_FILE)
+    }
+
+    pub fn source_root_path(&self) -> PathBuf {
+        self.project_docs_path().join(SOURCE_ROOT_FILE)
+    }
+
     pub fn file_docs_dir(&self, file_path: impl AsRef<Path>) -> Result<PathBuf> {
         let relative = self.relative_file_path(file_path)?;
         Ok(self.files_root_path().join(relative))
@@ -88,20 +620,80 @@ impl ProjectContext {
         Ok(self.file_docs_dir(file_path)?.join("docs.md"))
     }
 
+    /// Rollup summary of a source directory, given relative to the project root; see
+    /// `PlainSightConfig::directory_summaries`.
+    pub fn directory_summary_path(&self, dir: impl AsRef<Path>) -> PathBuf {
+        self.files_root_path().join(dir).join("summary.md")
+    }
+
+    /// Links to the pages of a source directory's subdirectories and files, given relative to
+    /// the project root.
+    pub fn directory_index_path(&self, dir: impl AsRef<Path>) -> PathBuf {
+        self.files_root_path().join(dir).join("_index.md")
+    }
+
     pub fn ensure_project_structure(&self) -> Result<()> {
-        fs::create_dir_all(self.files_root_path())
+        self.store
+            .create_dir_all(&self.files_root_path())
             .map_err(|e| PlainSightError::io("creating project docs structure", e))?;
+        self.store
+            .create_dir_all(&self.scoped_docs_path())
+            .map_err(|e| PlainSightError::io("creating scoped docs structure", e))?;
         self.ensure_markdown_file(self.summary_path())?;
         self.ensure_markdown_file(self.architecture_path())?;
+
+        let marker = self.output_marker_path();
+        if !self.store.exists(&marker) {
+            self.store
+                .write(
+                    &marker,
+                    b"Generated by PlainSight. Source discovery skips this directory.\n",
+                )
+                .map_err(|e| {
+                    PlainSightError::io(format!("writing output marker '{}'", marker.display()), e)
+                })?;
+        }
+
+        if !self.project_root.as_os_str().is_empty() && !self.store.exists(&self.source_root_path())
+        {
+            self.save_source_root(&SourceRootRecord::for_root(&self.project_root))?;
+        }
         Ok(())
     }
 
+    pub fn load_source_root(&self) -> Result<Option<SourceRootRecord>> {
+        let path = self.source_root_path();
+        if !self.store.exists(&path) {
+            return Ok(None);
+        }
+
+        let content = self.store.read_to_string(&path).map_err(|e| {
+            PlainSightError::io(format!("reading source root '{}'", path.display()), e)
+        })?;
+
+        serde_json::from_str(&content).map(Some).map_err(|e| {
+            PlainSightError::InvalidState(format!(
+                "failed to parse source root '{}': {e}",
+                path.display()
+            ))
+        })
+    }
+
+    fn save_source_root(&self, record: &SourceRootRecord) -> Result<()> {
+        let content = serde_json::to_string_pretty(record)
+            .map_err(|e| PlainSightError::InvalidState(format!("serializing source root: {e}")))?;
+        let path = self.source_root_path();
+        self.store.write(&path, content.as_bytes()).map_err(|e| {
+            PlainSightError::io(format!("writing source root '{}'", path.display()), e)
+        })
+    }
+
     pub fn ensure_file_structure(
         &self,
         file_path: impl AsRef<Path>,
     ) -> Result<()> {
         let file_dir = self.file_docs_dir(file_path)?;
-        fs::create_dir_all(&file_dir).map_err(|e| {
+        self.store.create_dir_all(&file_dir).map_err(|e| {
             PlainSightError::io(
                 format!("creating file docs directory '{}'", file_dir.display()),
                 e,
@@ -114,7 +706,7 @@ impl ProjectContext {
 
     pub fn load_meta(&self) -> Result<MetaCache> {
         let path = self.meta_path();
-        if path.exists() {
+        if self.store.exists(&path) {
             return self.read_meta_file(&path);
         }
 
@@ -122,11 +714,11 @@ impl ProjectContext {
     }
 
     fn read_meta_file(&self, path: &Path) -> Result<MetaCache> {
-        if !path.exists() {
+        if !self.store.exists(path) {
             return Ok(MetaCache::default());
         }
 
-        let content = fs::read_to_string(path).map_err(|e| {
+        let content = self.store.read_to_string(path).map_err(|e| {
             PlainSightError::io(format!("reading meta cache '{}'", path.display()), e)
         })?;
 
@@ -142,26 +734,196 @@ impl ProjectContext {
         let content = serde_json::to_string_pretty(meta)
             .map_err(|e| PlainSightError::InvalidState(format!("serializing meta cache: {e}")))?;
         let path = self.meta_path();
-        fs::write(&path, content).map_err(|e| {
+        self.store.write(&path, content.as_bytes()).map_err(|e| {
             PlainSightError::io(format!("writing meta cache '{}'", path.display()), e)
         })?;
         Ok(())
     }
 
+    pub fn load_checkpoint(&self) -> Result<RunCheckpoint> {
+        let path = self.checkpoint_path();
+        if !self.store.exists(&path) {
+            return Ok(RunCheckpoint::default());
+        }
+
+        let content = self.store.read_to_string(&path).map_err(|e| {
+            PlainSightError::io(format!("reading checkpoint '{}'", path.display()), e)
+        })?;
+
+        serde_json::from_str(&content).map_err(|e| {
+            PlainSightError::InvalidState(format!(
+                "failed to parse checkpoint '{}': {e}",
+                path.display()
+            ))
+        })
+    }
+
+    pub fn save_checkpoint(&self, checkpoint: &RunCheckpoint) -> Result<()> {
+        let content = serde_json::to_string_pretty(checkpoint)
+            .map_err(|e| PlainSightError::InvalidState(format!("serializing checkpoint: {e}")))?;
+        let path = self.checkpoint_path();
+        self.store.write(&path, content.as_bytes()).map_err(|e| {
+            PlainSightError::io(format!("writing checkpoint '{}'", path.display()), e)
+        })?;
+        Ok(())
+    }
+
     pub fn ensure_meta_exists(&self) -> Result<MetaCache> {
         let meta = self.load_meta()?;
-        if !self.meta_path().exists() {
+        if !self.store.exists(&self.meta_path()) {
             self.save_meta(&meta)?;
         }
         Ok(meta)
     }
 
+    pub fn load_annotations(&self) -> Result<AnnotationStore> {
+        let path = self.annotations_path();
+        state_files::load_state_file(self.store(), &path, self.repair_state_files)
+            .map(Option::unwrap_or_default)
+    }
+
+    pub fn load_skip_list(&self) -> Result<SkipList> {
+        let path = self.skip_list_path();
+        state_files::load_state_file(self.store(), &path, self.repair_state_files)
+            .map(Option::unwrap_or_default)
+    }
+
+    pub fn save_skip_list(&self, skip_list: &SkipList) -> Result<()> {
+        let content = serde_json::to_string_pretty(skip_list)
+            .map_err(|e| PlainSightError::InvalidState(format!("serializing skip list: {e}")))?;
+        let path = self.skip_list_path();
+        self.store.write(&path, content.as_bytes()).map_err(|e| {
+            PlainSightError::io(format!("writing skip list '{}'", path.display()), e)
+        })?;
+        Ok(())
+    }
+
+    /// Take the project's `.lock` for a run, so a second run on the same docs fails instead of
+    /// interleaving its writes with this one's. A lock left behind by a run that is gone is
+    /// replaced. The lock is advisory: it only keeps out other runs that take it.
+    pub fn lock_run(&self) -> Result<RunLock> {
+        let path = self.lock_path();
+        let holder = serde_json::to_vec_pretty(&LockHolder::current())
+            .map_err(|e| PlainSightError::InvalidState(format!("serializing run lock: {e}")))?;
+        let create = || match self.store.create_new(&path, &holder) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => Ok(false),
+            Err(e) => Err(PlainSightError::io(
+                format!("creating run lock '{}'", path.display()),
+                e,
+            )),
+        };
+        if create()? {
+            return Ok(self.run_lock(path));
+        }
+
+        let existing = self
+            .store
+            .read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<LockHolder>(&content).ok());
+        let locked = |existing: Option<&LockHolder>| PlainSightError::ProjectLocked {
+            lock_path: path.clone(),
+            holder: existing.map_or_else(|| "unknown".to_string(), LockHolder::describe),
+        };
+        match &existing {
+            Some(stale) if stale.is_stale() => {
+                warn!(
+                    lock_path = %path.display(),
+                    pid = stale.pid,
+                    host = %stale.host,
+                    "replacing stale run lock"
+                );
+                self.store.remove_file(&path).map_err(|e| {
+                    PlainSightError::io(format!("removing stale run lock '{}'", path.display()), e)
+                })?;
+                if create()? {
+                    Ok(self.run_lock(path))
+                } else {
+                    Err(locked(None))
+                }
+            }
+            _ => Err(locked(existing.as_ref())),
+        }
+    }
+
+    fn run_lock(&self, path: PathBuf) -> RunLock {
+        RunLock {
+            store: Arc::clone(&self.store),
+            path,
+        }
+    }
+
+    /// The project memory persisted by the last run.
+    pub fn load_memory(&self) -> Result<ProjectMemory> {
+        let path = self.memory_path();
+        state_files::load_state_file(self.store(), &path, self.repair_state_files)?.ok_or_else(
+            || {
+                PlainSightError::io(
+                    format!("reading project memory '{}'", path.display()),
+                    io::ErrorKind::NotFound.into(),
+                )
+            },
+        )
+    }
+
+    pub fn save_annotations(&self, annotations: &AnnotationStore) -> Result<()> {
+        let content = serde_json::to_string_pretty(annotations)
+            .map_err(|e| PlainSightError::InvalidState(format!("serializing annotations: {e}")))?;
+        let path = self.annotations_path();
+        self.store.write(&path, content.as_bytes()).map_err(|e| {
+            PlainSightError::io(format!("writing annotations '{}'", path.display()), e)
+        })?;
+        Ok(())
+    }
+
+    pub fn load_timeline(&self) -> Result<ProjectTimeline> {
+        let path = self.timeline_path();
+        if !self.store.exists(&path) {
+            return Ok(ProjectTimeline::default());
+        }
+
+        let content = self.store.read_to_string(&path).map_err(|e| {
+            PlainSightError::io(format!("reading timeline '{}'", path.display()), e)
+        })?;
+
+        serde_json::from_str(&content).map_err(|e| {
+            PlainSightError::InvalidState(format!(
+                "failed to parse timeline '{}': {e}",
+                path.display()
+            ))
+        })
+    }
+
+    pub fn save_timeline(&self, timeline: &ProjectTimeline) -> Result<()> {
+        let content = serde_json::to_string_pretty(timeline)
+            .map_err(|e| PlainSightError::InvalidState(format!("serializing timeline: {e}")))?;
+        let path = self.timeline_path();
+        self.store.write(&path, content.as_bytes()).map_err(|e| {
+            PlainSightError::io(format!("writing timeline '{}'", path.display()), e)
+        })?;
+        Ok(())
+    }
+
     pub fn hash_file(&self, file_path: impl AsRef<Path>) -> Result<String> {
         let path = file_path.as_ref();
-        let content = fs::read(path)
-            .map_err(|e| PlainSightError::io(format!("hashing file '{}'", path.display()), e))?;
+        let hash_error =
+            |e| PlainSightError::io(format!("hashing file '{}'", path.display()), e);
+        let mut file = fs::File::open(path).map_err(hash_error)?;
+        let len = file.metadata().map_err(hash_error)?.len();
+
+        // Feed the same bytes `<[u8]>::hash` would (length prefix, then content) so hashes
+        // match those cached from whole-file reads.
         let mut hasher = DefaultHasher::new();
-        content.hash(&mut hasher);
+        hasher.write_usize(len as usize);
+        let mut buf = vec![0u8; HASH_BUFFER_BYTES];
+        loop {
+            let read = file.read(&mut buf).map_err(hash_error)?;
+            if read == 0 {
+                break;
+            }
+            hasher.write(&buf[..read]);
+        }
         Ok(format!("{:x}", hasher.finish()))
     }
 
@@ -169,16 +931,29 @@ impl ProjectContext {
         &self,
         file_path: impl AsRef<Path>,
         meta: &MetaCache,
+    ) -> Result<bool> {
+        let hash = self.hash_file(file_path.as_ref())?;
+        self.needs_generation_with_hash(file_path, &hash, meta)
+    }
+
+    /// [`Self::needs_generation`] for a file whose content hash is already known, which need
+    /// not be on disk.
+    pub fn needs_generation_with_hash(
+        &self,
+        file_path: impl AsRef<Path>,
+        hash: &str,
+        meta: &MetaCache,
     ) -> Result<bool> {
         let relative = self.relative_file_path(file_path.as_ref())?;
         let key = relative.to_string_lossy().to_string();
-        let hash = self.hash_file(file_path.as_ref())?;
 
         let cached_hash = meta.files.get(&key).map(|f| f.hash.as_str());
-        let summary_exists = self.file_summary_path(file_path.as_ref())?.exists();
-        let docs_exists = self.file_docs_path(file_path.as_ref())?.exists();
+        let summary_exists = self
+            .store
+            .exists(&self.file_summary_path(file_path.as_ref())?);
+        let docs_exists = self.store.exists(&self.file_docs_path(file_path.as_ref())?);
 
-        Ok(cached_hash != Some(hash.as_str()) || !summary_exists || !docs_exists)
+        Ok(cached_hash != Some(hash) || !summary_exists || !docs_exists)
     }
 
     fn relative_file_path(&self, file_path: impl AsRef<Path>) -> Result<PathBuf> {
@@ -199,8 +974,8 @@ impl ProjectContext {
     }
 
     fn ensure_markdown_file(&self, file_path: PathBuf) -> Result<()> {
-        if !file_path.exists() {
-            fs::write(&file_path, "").map_err(|e| {
+        if !self.store.exists(&file_path) {
+            self.store.write(&file_path, b"").map_err(|e| {
                 PlainSightError::io(
                     format!("creating markdown file '{}'", file_path.display()),
                     e,
@@ -210,3 +985,24 @@ impl ProjectContext {
         Ok(())
     }
 }
+
+fn prompt_project_name(project_name: &str, existing_root: &Path, suggestion: &str) -> Result<String> {
+    eprint!(
+        "Docs for project '{project_name}' were generated from '{}'. Project name [{suggestion}]: ",
+        existing_root.display()
+    );
+    io::stderr()
+        .flush()
+        .map_err(|e| PlainSightError::io("prompting for a project name", e))?;
+    let mut answer = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut answer)
+        .map_err(|e| PlainSightError::io("reading the project name", e))?;
+    let answer = answer.trim();
+    Ok(if answer.is_empty() {
+        suggestion.to_string()
+    } else {
+        answer.to_string()
+    })
+}
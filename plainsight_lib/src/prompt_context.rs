@@ -0,0 +1,500 @@
+//! The facts sent with file summary and docs requests.
+//!
+//! The `context` field of a `summarize` or `documentation` request, and so the `{{context}}` of a
+//! prompt template for those tasks, holds one [`PromptContext`] serialized as JSON. Fields are
+//! only ever added within a [`PROMPT_CONTEXT_VERSION`]; renaming or removing a field, or changing
+//! what it holds, bumps the version. Lists with nothing to report are left out.
+
+use serde::{Deserialize, Serialize};
+
+use crate::project_manager::Annotation;
+
+/// Version written to [`PromptContext::version`].
+pub const PROMPT_CONTEXT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptContext {
+    pub version: u32,
+    /// Project-relative path of the file.
+    pub path: String,
+    pub language: String,
+    /// Lines in the whole file, also when it is documented in parts.
+    #[serde(default)]
+    pub line_count: usize,
+    /// Start of the first source chunk.
+    pub source_preview: String,
+    pub file_memory_hint: FileMemoryHint,
+    pub memory_file_path: String,
+    pub source_index_file_path: String,
+    /// Suggested arguments for the `query_file_source` tool.
+    pub source_query: SourceQuery,
+    /// Suggested arguments for the `query_project_memory` tool.
+    pub memory_query: MemoryQuery,
+    pub project_memory_stats: ProjectMemoryStats,
+    /// Functions past the complexity, length or parameter thresholds, most complex first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hot_spots: Vec<HotSpot>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tables: Vec<Table>,
+    /// Present when tests are documented separately and the file has some.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub test_code: Option<TestCode>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub endpoints: Vec<Endpoint>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rustdoc: Vec<RustdocEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub performance: Vec<BenchmarkContext>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unsafe_code: Vec<UnsafeCode>,
+    /// Answers maintainers gave to earlier open questions about the file.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<Annotation>,
+    /// Files whose symbols moved into this one since the last run.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub moved_from: Vec<MovedFrom>,
+    /// The line range covered when the file is documented in parts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub part: Option<PartRange>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileMemoryHint {
+    pub symbol_count: usize,
+    pub import_count: usize,
+    pub top_symbols: Vec<SymbolHint>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolHint {
+    pub name: String,
+    pub kind: String,
+    pub line: usize,
+    pub attributes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceQuery {
+    pub file_path: String,
+    pub chunk_ids: Vec<usize>,
+    pub max_chars: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryQuery {
+    pub file_path: String,
+    pub max_global_symbols: usize,
+    pub max_open_items: usize,
+    pub max_links: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectMemoryStats {
+    pub file_count: usize,
+    pub unique_symbol_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HotSpot {
+    pub name: String,
+    pub line: usize,
+    pub line_count: usize,
+    pub parameter_count: usize,
+    pub complexity: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Table {
+    pub name: String,
+    pub line: usize,
+    /// `name type` per column.
+    pub columns: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestCode {
+    pub is_test_file: bool,
+    pub test_symbol_count: usize,
+    pub test_symbols: Vec<TestSymbol>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestSymbol {
+    pub name: String,
+    pub kind: String,
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Endpoint {
+    pub method: String,
+    pub path: String,
+    pub line: usize,
+    /// Whether an OpenAPI document in the project describes the route.
+    pub in_spec: bool,
+    /// Empty when the route is not in a spec.
+    pub spec_summary: String,
+    pub operation_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RustdocEntry {
+    pub path: String,
+    pub kind: String,
+    pub line: usize,
+    pub signature: String,
+}
+
+/// A benchmark with its results formatted for reading.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkContext {
+    pub benchmark: String,
+    /// `path:line` of the benchmark definition.
+    pub defined_in: String,
+    pub exercises: Vec<String>,
+    pub results: Vec<BenchmarkTiming>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkTiming {
+    pub id: String,
+    /// e.g. `1.20 µs`.
+    pub mean: String,
+    /// `lower - upper` of the confidence interval.
+    pub range: String,
+    /// Project-relative path of the file the numbers come from.
+    pub source: String,
+    /// `YYYY-MM-DD`.
+    pub measured_at: String,
+}
+
+/// `unsafe` code, an FFI declaration or a raw pointer type.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnsafeCode {
+    /// e.g. `unsafe block`, `extern fn` or `raw pointer`.
+    pub kind: String,
+    pub line: usize,
+    /// The declared item, the ABI of an extern block, or the matching code.
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MovedFrom {
+    pub path: String,
+    /// The old file no longer exists and most of this one came from it.
+    pub file_renamed: bool,
+    pub symbols: Vec<String>,
+    /// Start of the old file's summary; empty when there is none.
+    pub previous_summary: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartRange {
+    /// 1-based.
+    pub index: usize,
+    pub count: usize,
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A context with every field set, as sent for the second part of a Rust file.
+    fn representative() -> PromptContext {
+        PromptContext {
+            version: PROMPT_CONTEXT_VERSION,
+            path: "src/store.rs".to_string(),
+            language: "rust".to_string(),
+            line_count: 240,
+            source_preview: "pub struct Store {".to_string(),
+            file_memory_hint: FileMemoryHint {
+                symbol_count: 1,
+                import_count: 2,
+                top_symbols: vec![SymbolHint {
+                    name: "Store".to_string(),
+                    kind: "struct".to_string(),
+                    line: 12,
+                    attributes: vec!["derive(Debug)".to_string()],
+                }],
+            },
+            memory_file_path: "docs/.memory/src/store.rs.json".to_string(),
+            source_index_file_path: "docs/.source_index/src/store.rs.json".to_string(),
+            source_query: SourceQuery {
+                file_path: "src/store.rs".to_string(),
+                chunk_ids: vec![2, 3],
+                max_chars: 3500,
+            },
+            memory_query: MemoryQuery {
+                file_path: "src/store.rs".to_string(),
+                max_global_symbols: 8,
+                max_open_items: 4,
+                max_links: 4,
+            },
+            project_memory_stats: ProjectMemoryStats {
+                file_count: 7,
+                unique_symbol_count: 31,
+            },
+            hot_spots: vec![HotSpot {
+                name: "Store::compact".to_string(),
+                line: 150,
+                line_count: 64,
+                parameter_count: 2,
+                complexity: 14,
+            }],
+            tables: vec![Table {
+                name: "entries".to_string(),
+                line: 20,
+                columns: vec!["id INTEGER".to_string(), "body TEXT".to_string()],
+            }],
+            test_code: Some(TestCode {
+                is_test_file: false,
+                test_symbol_count: 1,
+                test_symbols: vec![TestSymbol {
+                    name: "compacts_empty_store".to_string(),
+                    kind: "function".to_string(),
+                    line: 220,
+                }],
+            }),
+            endpoints: vec![Endpoint {
+                method: "GET".to_string(),
+                path: "/entries/{id}".to_string(),
+                line: 180,
+                in_spec: true,
+                spec_summary: "Read one entry".to_string(),
+                operation_id: "getEntry".to_string(),
+            }],
+            rustdoc: vec![RustdocEntry {
+                path: "store::Store::compact".to_string(),
+                kind: "method".to_string(),
+                line: 150,
+                signature: "pub fn compact(&mut self, limit: usize) -> usize".to_string(),
+            }],
+            performance: vec![BenchmarkContext {
+                benchmark: "compact".to_string(),
+                defined_in: "benches/store.rs:9".to_string(),
+                exercises: vec!["Store::compact".to_string()],
+                results: vec![BenchmarkTiming {
+                    id: "compact/1000".to_string(),
+                    mean: "1.20 µs".to_string(),
+                    range: "1.18 µs - 1.23 µs".to_string(),
+                    source: "target/criterion/compact/1000/new/estimates.json".to_string(),
+                    measured_at: "2026-10-01".to_string(),
+                }],
+            }],
+            unsafe_code: vec![UnsafeCode {
+                kind: "unsafe block".to_string(),
+                line: 171,
+                detail: "unsafe { self.map.get_unchecked(idx) }".to_string(),
+            }],
+            annotations: vec![Annotation {
+                question: "Why is compaction not incremental?".to_string(),
+                answer: "Entries are rewritten in one pass to keep ids dense.".to_string(),
+            }],
+            moved_from: vec![MovedFrom {
+                path: "src/cache.rs".to_string(),
+                file_renamed: false,
+                symbols: vec!["Store::compact".to_string()],
+                previous_summary: "An in-memory cache.".to_string(),
+            }],
+            part: Some(PartRange {
+                index: 2,
+                count: 2,
+                start_line: 121,
+                end_line: 240,
+            }),
+        }
+    }
+
+    /// `representative()` as JSON. A change here changes what prompts receive: bump
+    /// `PROMPT_CONTEXT_VERSION` unless fields were only added.
+    const GOLDEN: &str = r#"{
+  "version": 1,
+  "path": "src/store.rs",
+  "language": "rust",
+  "line_count": 240,
+  "source_preview": "pub struct Store {",
+  "file_memory_hint": {
+    "symbol_count": 1,
+    "import_count": 2,
+    "top_symbols": [
+      {
+        "name": "Store",
+        "kind": "struct",
+        "line": 12,
+        "attributes": [
+          "derive(Debug)"
+        ]
+      }
+    ]
+  },
+  "memory_file_path": "docs/.memory/src/store.rs.json",
+  "source_index_file_path": "docs/.source_index/src/store.rs.json",
+  "source_query": {
+    "file_path": "src/store.rs",
+    "chunk_ids": [
+      2,
+      3
+    ],
+    "max_chars": 3500
+  },
+  "memory_query": {
+    "file_path": "src/store.rs",
+    "max_global_symbols": 8,
+    "max_open_items": 4,
+    "max_links": 4
+  },
+  "project_memory_stats": {
+    "file_count": 7,
+    "unique_symbol_count": 31
+  },
+  "hot_spots": [
+    {
+      "name": "Store::compact",
+      "line": 150,
+      "line_count": 64,
+      "parameter_count": 2,
+      "complexity": 14
+    }
+  ],
+  "tables": [
+    {
+      "name": "entries",
+      "line": 20,
+      "columns": [
+        "id INTEGER",
+        "body TEXT"
+      ]
+    }
+  ],
+  "test_code": {
+    "is_test_file": false,
+    "test_symbol_count": 1,
+    "test_symbols": [
+      {
+        "name": "compacts_empty_store",
+        "kind": "function",
+        "line": 220
+      }
+    ]
+  },
+  "endpoints": [
+    {
+      "method": "GET",
+      "path": "/entries/{id}",
+      "line": 180,
+      "in_spec": true,
+      "spec_summary": "Read one entry",
+      "operation_id": "getEntry"
+    }
+  ],
+  "rustdoc": [
+    {
+      "path": "store::Store::compact",
+      "kind": "method",
+      "line": 150,
+      "signature": "pub fn compact(&mut self, limit: usize) -> usize"
+    }
+  ],
+  "performance": [
+    {
+      "benchmark": "compact",
+      "defined_in": "benches/store.rs:9",
+      "exercises": [
+        "Store::compact"
+      ],
+      "results": [
+        {
+          "id": "compact/1000",
+          "mean": "1.20 µs",
+          "range": "1.18 µs - 1.23 µs",
+          "source": "target/criterion/compact/1000/new/estimates.json",
+          "measured_at": "2026-10-01"
+        }
+      ]
+    }
+  ],
+  "unsafe_code": [
+    {
+      "kind": "unsafe block",
+      "line": 171,
+      "detail": "unsafe { self.map.get_unchecked(idx) }"
+    }
+  ],
+  "annotations": [
+    {
+      "question": "Why is compaction not incremental?",
+      "answer": "Entries are rewritten in one pass to keep ids dense."
+    }
+  ],
+  "moved_from": [
+    {
+      "path": "src/cache.rs",
+      "file_renamed": false,
+      "symbols": [
+        "Store::compact"
+      ],
+      "previous_summary": "An in-memory cache."
+    }
+  ],
+  "part": {
+    "index": 2,
+    "count": 2,
+    "start_line": 121,
+    "end_line": 240
+  }
+}"#;
+
+    #[test]
+    fn serializes_representative_context() {
+        let json = serde_json::to_string_pretty(&representative()).unwrap();
+        assert_eq!(json, GOLDEN);
+    }
+
+    #[test]
+    fn golden_context_round_trips() {
+        let context: PromptContext = serde_json::from_str(GOLDEN).unwrap();
+        assert_eq!(serde_json::to_string_pretty(&context).unwrap(), GOLDEN);
+    }
+
+    #[test]
+    fn leaves_out_empty_lists_and_absent_parts() {
+        let context = PromptContext {
+            hot_spots: Vec::new(),
+            tables: Vec::new(),
+            test_code: None,
+            endpoints: Vec::new(),
+            rustdoc: Vec::new(),
+            performance: Vec::new(),
+            unsafe_code: Vec::new(),
+            annotations: Vec::new(),
+            moved_from: Vec::new(),
+            part: None,
+            ..representative()
+        };
+        let json = serde_json::to_value(&context).unwrap();
+        let keys = json
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            [
+                "version",
+                "path",
+                "language",
+                "line_count",
+                "source_preview",
+                "file_memory_hint",
+                "memory_file_path",
+                "source_index_file_path",
+                "source_query",
+                "memory_query",
+                "project_memory_stats",
+            ]
+        );
+    }
+}
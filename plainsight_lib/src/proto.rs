@@ -0,0 +1,432 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+/// Suffixes protoc plugins append to a service name for generated servers and clients
+/// (tonic, grpc-go, grpcio, grpc-java, grpc-node).
+const GENERATED_SUFFIXES: &[&str] = &["Server", "Client", "Servicer", "Stub", "Grpc"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtoFile {
+    pub path: String,
+    pub package: String,
+    pub services: Vec<ProtoService>,
+    pub messages: Vec<ProtoMessage>,
+    pub enums: Vec<ProtoEnum>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtoService {
+    pub name: String,
+    pub line: usize,
+    pub rpcs: Vec<ProtoRpc>,
+    /// Project files that reference the generated server or client for this service.
+    #[serde(default)]
+    pub used_in: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtoRpc {
+    pub name: String,
+    pub line: usize,
+    pub request: String,
+    pub response: String,
+    pub client_streaming: bool,
+    pub server_streaming: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtoMessage {
+    /// Nested messages are qualified with their parent, e.g. `Outer.Inner`.
+    pub name: String,
+    pub line: usize,
+    /// `type name` pairs; map fields keep their `map<K, V>` type.
+    pub fields: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtoEnum {
+    pub name: String,
+    pub line: usize,
+    pub values: Vec<String>,
+}
+
+impl ProtoFile {
+    /// Fully qualified name of a definition in this file.
+    pub fn qualified(&self, name: &str) -> String {
+        if self.package.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}.{name}", self.package)
+        }
+    }
+}
+
+enum Block {
+    Message(usize),
+    Service(usize),
+    Enum(usize),
+    /// `oneof` fields belong to the enclosing message.
+    Oneof,
+    Other,
+}
+
+/// Extract services, RPCs, messages and enums from a `.proto` file.
+///
+/// Returns `None` when the file defines none of them.
+pub fn parse_proto(relative_path: &str, source: &str) -> Option<ProtoFile> {
+    let mut file = ProtoFile {
+        path: relative_path.to_string(),
+        package: String::new(),
+        services: Vec::new(),
+        messages: Vec::new(),
+        enums: Vec::new(),
+    };
+    let mut stack: Vec<Block> = Vec::new();
+
+    for (line, statement, delimiter) in statements(&strip_comments(source)) {
+        let words = statement.split_whitespace().collect::<Vec<_>>();
+        match delimiter {
+            '}' => {
+                stack.pop();
+            }
+            '{' => {
+                let block = match words.as_slice() {
+                    ["message", name, ..] => {
+                        let name = match message_path(&stack, &file) {
+                            Some(parent) => format!("{parent}.{name}"),
+                            None => name.to_string(),
+                        };
+                        file.messages.push(ProtoMessage {
+                            name,
+                            line,
+                            fields: Vec::new(),
+                        });
+                        Block::Message(file.messages.len() - 1)
+                    }
+                    ["service", name, ..] => {
+                        file.services.push(ProtoService {
+                            name: name.to_string(),
+                            line,
+                            rpcs: Vec::new(),
+                            used_in: Vec::new(),
+                        });
+                        Block::Service(file.services.len() - 1)
+                    }
+                    ["enum", name, ..] => {
+                        file.enums.push(ProtoEnum {
+                            name: name.to_string(),
+                            line,
+                            values: Vec::new(),
+                        });
+                        Block::Enum(file.enums.len() - 1)
+                    }
+                    ["oneof", ..] => Block::Oneof,
+                    ["rpc", ..] => {
+                        add_rpc(&mut file, &stack, statement, line);
+                        Block::Other
+                    }
+                    _ => Block::Other,
+                };
+                stack.push(block);
+            }
+            _ => match (stack.last(), words.as_slice()) {
+                (None, ["package", package]) => file.package = package.to_string(),
+                (Some(Block::Service(_)), ["rpc", ..]) => {
+                    add_rpc(&mut file, &stack, statement, line);
+                }
+                (Some(Block::Enum(_)), ["option" | "reserved", ..]) => {}
+                (Some(Block::Enum(idx)), _) => {
+                    if let Some((value, _)) = statement.split_once('=') {
+                        file.enums[*idx].values.push(value.trim().to_string());
+                    }
+                }
+                (Some(Block::Message(_) | Block::Oneof), _) => {
+                    if let Some(field) = parse_field(statement)
+                        && let Some(idx) = message_index(&stack)
+                    {
+                        file.messages[idx].fields.push(field);
+                    }
+                }
+                _ => {}
+            },
+        }
+    }
+
+    if file.services.is_empty() && file.messages.is_empty() && file.enums.is_empty() {
+        return None;
+    }
+    Some(file)
+}
+
+/// Record every project file that references a service's generated server or client.
+///
+/// `sources` yields `(relative_path, text)` pairs; a file may appear several times (one entry
+/// per chunk).
+pub fn link_usages<'a>(
+    protos: &mut [ProtoFile],
+    sources: impl IntoIterator<Item = (&'a str, &'a str)> + Clone,
+) {
+    for proto in protos.iter_mut() {
+        for service in &mut proto.services {
+            let identifiers = generated_identifiers(&service.name);
+            let used_in = sources
+                .clone()
+                .into_iter()
+                .filter(|(_, text)| {
+                    identifiers
+                        .iter()
+                        .any(|ident| text.contains(ident.as_str()))
+                })
+                .map(|(path, _)| path.to_string())
+                .collect::<BTreeSet<_>>();
+            service.used_in = used_in.into_iter().collect();
+        }
+    }
+}
+
+/// Render `rpc.md`: every service with its RPCs and usages, followed by messages and enums.
+pub fn render_reference(project_name: &str, protos: &[ProtoFile]) -> String {
+    let mut out = format!("# {project_name} RPC Reference\n\n");
+    out.push_str(&format!(
+        "Generated from {} `.proto` file(s).\n",
+        protos.len()
+    ));
+
+    for proto in protos {
+        for service in &proto.services {
+            out.push_str(&format!("\n## {}\n\n", proto.qualified(&service.name)));
+            out.push_str(&format!("Defined in `{}:{}`.", proto.path, service.line));
+            if service.used_in.is_empty() {
+                out.push_str(" No generated-code usages found in the project.\n\n");
+            } else {
+                let used_in = service
+                    .used_in
+                    .iter()
+                    .map(|path| format!("`{path}`"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                out.push_str(&format!(" Used in {used_in}.\n\n"));
+            }
+
+            out.push_str("| RPC | Request | Response | Streaming |\n");
+            out.push_str("|---|---|---|---|\n");
+            for rpc in &service.rpcs {
+                let streaming = match (rpc.client_streaming, rpc.server_streaming) {
+                    (true, true) => "bidirectional",
+                    (true, false) => "client",
+                    (false, true) => "server",
+                    (false, false) => "-",
+                };
+                out.push_str(&format!(
+                    "| {} | {} | {} | {streaming} |\n",
+                    rpc.name, rpc.request, rpc.response
+                ));
+            }
+        }
+    }
+
+    let has_messages = protos.iter().any(|proto| !proto.messages.is_empty());
+    if has_messages {
+        out.push_str("\n## Messages\n");
+        for proto in protos {
+            for message in &proto.messages {
+                out.push_str(&format!(
+                    "\n### {}\n\nDefined in `{}:{}`.\n\n",
+                    proto.qualified(&message.name),
+                    proto.path,
+                    message.line
+                ));
+                if message.fields.is_empty() {
+                    out.push_str("No fields.\n");
+                }
+                for field in &message.fields {
+                    out.push_str(&format!("- `{field}`\n"));
+                }
+            }
+        }
+    }
+
+    let has_enums = protos.iter().any(|proto| !proto.enums.is_empty());
+    if has_enums {
+        out.push_str("\n## Enums\n\n");
+        for proto in protos {
+            for proto_enum in &proto.enums {
+                out.push_str(&format!(
+                    "- `{}` (`{}:{}`): {}\n",
+                    proto.qualified(&proto_enum.name),
+                    proto.path,
+                    proto_enum.line,
+                    proto_enum.values.join(", ")
+                ));
+            }
+        }
+    }
+
+    out
+}
+
+fn generated_identifiers(service: &str) -> Vec<String> {
+    let snake = to_snake_case(service);
+    GENERATED_SUFFIXES
+        .iter()
+        .map(|suffix| format!("{service}{suffix}"))
+        .chain([format!("{snake}_server"), format!("{snake}_client")])
+        .collect()
+}
+
+fn to_snake_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len() + 4);
+    for (idx, ch) in name.chars().enumerate() {
+        if ch.is_ascii_uppercase() {
+            if idx > 0 {
+                out.push('_');
+            }
+            out.push(ch.to_ascii_lowercase());
+        } else {
+            out.push(ch);
+        }
+    }
+    out
+}
+
+fn add_rpc(file: &mut ProtoFile, stack: &[Block], statement: &str, line: usize) {
+    let Some(Block::Service(idx)) = stack.last() else {
+        return;
+    };
+    let Some(rest) = statement.trim().strip_prefix("rpc") else {
+        return;
+    };
+    let Some((name, rest)) = rest.split_once('(') else {
+        return;
+    };
+    let Some((request, rest)) = rest.split_once(')') else {
+        return;
+    };
+    let Some((_, rest)) = rest.split_once("returns") else {
+        return;
+    };
+    let response = rest
+        .trim()
+        .trim_start_matches('(')
+        .split(')')
+        .next()
+        .unwrap_or_default();
+
+    let (client_streaming, request) = strip_stream(request);
+    let (server_streaming, response) = strip_stream(response);
+    file.services[*idx].rpcs.push(ProtoRpc {
+        name: name.trim().to_string(),
+        line,
+        request,
+        response,
+        client_streaming,
+        server_streaming,
+    });
+}
+
+fn strip_stream(message: &str) -> (bool, String) {
+    let message = message.trim();
+    match message.strip_prefix("stream ") {
+        Some(rest) => (true, rest.trim().to_string()),
+        None => (false, message.to_string()),
+    }
+}
+
+fn parse_field(statement: &str) -> Option<String> {
+    let statement = statement.trim();
+    let (declaration, _) = statement.split_once('=')?;
+    let words = declaration.split_whitespace().collect::<Vec<_>>();
+    match words.as_slice() {
+        ["option" | "reserved" | "extensions", ..] => None,
+        ["repeated", ty, name] => Some(format!("repeated {ty} {name}")),
+        ["optional" | "required", ty, name] | [ty, name] => Some(format!("{ty} {name}")),
+        _ if declaration.starts_with("map") => {
+            let close = declaration.find('>')?;
+            let name = declaration[close + 1..].trim();
+            let ty = declaration[..=close].split_whitespace().collect::<String>();
+            Some(format!("{ty} {name}"))
+        }
+        _ => None,
+    }
+}
+
+fn message_index(stack: &[Block]) -> Option<usize> {
+    stack.iter().rev().find_map(|block| match block {
+        Block::Message(idx) => Some(*idx),
+        _ => None,
+    })
+}
+
+fn message_path<'a>(stack: &[Block], file: &'a ProtoFile) -> Option<&'a str> {
+    message_index(stack).map(|idx| file.messages[idx].name.as_str())
+}
+
+/// Drop `//` and `/* */` comments, keeping line breaks so line numbers stay stable.
+fn strip_comments(source: &str) -> String {
+    let mut out = String::with_capacity(source.len());
+    let mut chars = source.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '"' => {
+                in_string = !in_string;
+                out.push(ch);
+            }
+            '/' if !in_string && chars.peek() == Some(&'/') => {
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        out.push('\n');
+                        break;
+                    }
+                }
+            }
+            '/' if !in_string && chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = '\0';
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        out.push('\n');
+                    }
+                    if prev == '*' && next == '/' {
+                        break;
+                    }
+                    prev = next;
+                }
+            }
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+/// Split into `(line, text, delimiter)` statements on `;`, `{` and `}` outside strings.
+///
+/// `line` is where the statement text starts.
+fn statements(text: &str) -> Vec<(usize, &str, char)> {
+    let mut out = Vec::new();
+    let mut start = 0usize;
+    let mut line = 1usize;
+    let mut start_line: Option<usize> = None;
+    let mut in_string = false;
+
+    for (idx, ch) in text.char_indices() {
+        match ch {
+            '"' => in_string = !in_string,
+            ';' | '{' | '}' if !in_string => {
+                out.push((start_line.unwrap_or(line), &text[start..idx], ch));
+                start = idx + 1;
+                start_line = None;
+                continue;
+            }
+            _ => {}
+        }
+        if ch == '\n' {
+            line += 1;
+        } else if start_line.is_none() && !ch.is_whitespace() {
+            start_line = Some(line);
+        }
+    }
+    out
+}
@@ -0,0 +1,253 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{PlainSightError, Result},
+    memory::FileMemory,
+    page_split,
+    project_manager::ProjectContext,
+    source_indexer::SourceChunk,
+};
+
+const MIN_TERM_LEN: usize = 3;
+const STOP_WORDS: &[&str] = &[
+    "the", "and", "for", "how", "what", "where", "which", "who", "why", "does", "this", "that",
+    "with", "from", "into", "are", "is", "can", "should", "when", "there", "about", "code", "file",
+    "files", "function", "use", "used", "work", "works",
+];
+
+#[derive(Debug, Clone)]
+pub struct RetrievalOptions {
+    pub max_files: usize,
+    pub max_chunks_per_file: usize,
+    pub max_docs_chars: usize,
+}
+
+impl Default for RetrievalOptions {
+    fn default() -> Self {
+        Self {
+            max_files: 5,
+            max_chunks_per_file: 2,
+            max_docs_chars: 2500,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ContextBundle {
+    pub question: String,
+    pub terms: Vec<String>,
+    pub files: Vec<ContextFile>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ContextFile {
+    pub path: String,
+    pub score: f32,
+    pub matched_symbols: Vec<String>,
+    pub summary: Option<String>,
+    pub docs_excerpt: Option<String>,
+    pub source_chunks: Vec<SourceChunk>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StoredSourceIndex {
+    files: Vec<StoredSourceFile>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StoredSourceFile {
+    path: String,
+    chunks: Vec<SourceChunk>,
+}
+
+/// Rank documented files by relevance to `question` and bundle their generated summaries,
+/// docs excerpts and matching source chunks.
+///
+/// Only persisted artifacts are read, so this works without Ollama once a run has completed.
+pub fn get_context_for_question(
+    project: &ProjectContext,
+    question: &str,
+    options: &RetrievalOptions,
+) -> Result<ContextBundle> {
+    let project_memory = project.load_memory()?;
+    let source_index: Option<StoredSourceIndex> =
+        read_json(project, &project.source_index_path(), "source index").ok();
+
+    let terms = question_terms(question);
+    let mut scored = project_memory
+        .files
+        .iter()
+        .map(|file| {
+            let summary = read_artifact(
+                project,
+                &project.files_root_path().join(&file.path),
+                "summary.md",
+            );
+            let (score, matched_symbols) = score_file(file, summary.as_deref(), &terms);
+            (file, score, matched_symbols, summary)
+        })
+        .filter(|(_, score, _, _)| *score > 0.0)
+        .collect::<Vec<_>>();
+
+    scored.sort_by(|a, b| {
+        b.1.partial_cmp(&a.1)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.0.path.cmp(&b.0.path))
+    });
+    scored.truncate(options.max_files);
+
+    let files = scored
+        .into_iter()
+        .map(|(file, score, matched_symbols, summary)| {
+            let docs_excerpt = read_artifact(
+                project,
+                &project.files_root_path().join(&file.path),
+                "docs.md",
+            )
+            .map(|docs| truncate_chars(&docs, options.max_docs_chars));
+            let source_chunks = source_index
+                .as_ref()
+                .and_then(|index| index.files.iter().find(|f| f.path == file.path))
+                .map(|stored| select_chunks(&stored.chunks, &terms, options.max_chunks_per_file))
+                .unwrap_or_default();
+
+            ContextFile {
+                path: file.path.clone(),
+                score,
+                matched_symbols,
+                summary,
+                docs_excerpt,
+                source_chunks,
+            }
+        })
+        .collect();
+
+    Ok(ContextBundle {
+        question: question.to_string(),
+        terms,
+        files,
+    })
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(
+    project: &ProjectContext,
+    path: &std::path::Path,
+    label: &str,
+) -> Result<T> {
+    let content = project
+        .store()
+        .read_to_string(path)
+        .map_err(|e| PlainSightError::io(format!("reading {label} '{}'", path.display()), e))?;
+    serde_json::from_str(&content).map_err(|e| {
+        PlainSightError::InvalidState(format!("failed to parse {label} '{}': {e}", path.display()))
+    })
+}
+
+fn read_artifact(project: &ProjectContext, dir: &std::path::Path, name: &str) -> Option<String> {
+    page_split::read_page(project.store(), &dir.join(name))
+        .ok()
+        .filter(|content| !content.trim().is_empty())
+}
+
+fn question_terms(question: &str) -> Vec<String> {
+    let mut seen = BTreeSet::new();
+    let mut terms = Vec::new();
+
+    for word in question.split(|c: char| !(c.is_ascii_alphanumeric() || c == '_')) {
+        for term in std::iter::once(word.to_ascii_lowercase()).chain(split_identifier(word)) {
+            if term.len() < MIN_TERM_LEN || STOP_WORDS.contains(&term.as_str()) {
+                continue;
+            }
+            if seen.insert(term.clone()) {
+                terms.push(term);
+            }
+        }
+    }
+
+    terms
+}
+
+fn split_identifier(identifier: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+
+    for ch in identifier.chars() {
+        if ch == '_' {
+            if !current.is_empty() {
+                parts.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+        if ch.is_ascii_uppercase() && !current.is_empty() {
+            parts.push(std::mem::take(&mut current));
+        }
+        current.push(ch.to_ascii_lowercase());
+    }
+    if !current.is_empty() {
+        parts.push(current);
+    }
+
+    if parts.len() > 1 { parts } else { Vec::new() }
+}
+
+fn score_file(file: &FileMemory, summary: Option<&str>, terms: &[String]) -> (f32, Vec<String>) {
+    let mut score = 0.0;
+    let mut matched_symbols = BTreeSet::new();
+    let path = file.path.to_ascii_lowercase();
+
+    for term in terms {
+        if path.contains(term.as_str()) {
+            score += 2.0;
+        }
+
+        for symbol in &file.symbols {
+            let name = symbol.name.to_ascii_lowercase();
+            if &name == term {
+                score += 3.0;
+                matched_symbols.insert(symbol.name.clone());
+            } else if split_identifier(&symbol.name).contains(term) {
+                score += 1.0;
+                matched_symbols.insert(symbol.name.clone());
+            }
+        }
+
+        if let Some(summary) = summary {
+            let hits = summary.to_ascii_lowercase().matches(term.as_str()).count();
+            score += (hits as f32 * 0.5).min(2.0);
+        }
+    }
+
+    (score, matched_symbols.into_iter().collect())
+}
+
+fn select_chunks(chunks: &[SourceChunk], terms: &[String], max_chunks: usize) -> Vec<SourceChunk> {
+    let mut scored = chunks
+        .iter()
+        .map(|chunk| {
+            let content = chunk.content.to_ascii_lowercase();
+            let hits: usize = terms
+                .iter()
+                .map(|t| content.matches(t.as_str()).count())
+                .sum();
+            (chunk, hits)
+        })
+        .filter(|(_, hits)| *hits > 0)
+        .collect::<Vec<_>>();
+
+    scored.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.chunk_id.cmp(&b.0.chunk_id)));
+    scored
+        .into_iter()
+        .take(max_chunks)
+        .map(|(chunk, _)| chunk.clone())
+        .collect()
+}
+
+fn truncate_chars(content: &str, max_chars: usize) -> String {
+    if content.chars().count() <= max_chars {
+        return content.to_string();
+    }
+    let truncated: String = content.chars().take(max_chars).collect();
+    format!("{truncated}...")
+}
@@ -0,0 +1,62 @@
+//! What a run would do, worked out without contacting the LLM server or writing to the docs
+//! root; see `PlainSight::plan_project`.
+
+use serde::Serialize;
+
+/// The files a run would regenerate and reuse, and the requests it would send.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RunPlan {
+    pub project_name: String,
+    /// In the order they would be generated.
+    pub regenerate: Vec<PlannedFile>,
+    /// Files whose summary and docs from an earlier run would be kept.
+    pub reused: Vec<String>,
+    /// The project summary and architecture would be rewritten.
+    pub project_docs: bool,
+    /// Each file's docs would get a review request (`OllamaConfig::enable_review_pass`).
+    pub reviews: bool,
+    /// The task models changed since the last run, so every file would be regenerated.
+    pub models_changed: bool,
+}
+
+/// A file a run would regenerate.
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedFile {
+    pub path: String,
+    pub summary: PlannedTask,
+    pub docs: PlannedTask,
+}
+
+/// The requests of one file task, as the run would first send them; none when a resumed run
+/// keeps the output it already wrote.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct PlannedTask {
+    /// One, or one per line range when the prompt does not fit the context window whole.
+    pub requests: usize,
+    /// Estimated prompt tokens over every request.
+    pub prompt_tokens: usize,
+    /// Prompt tokens each request may take: the context window less the reply room.
+    pub token_limit: usize,
+}
+
+impl RunPlan {
+    /// Requests the run would send at least: the file tasks, a review per file when docs are
+    /// reviewed, and the project summary and architecture. Retries, tool rounds and smaller
+    /// retries of failed requests add to it; summary batching takes from it.
+    pub fn request_count(&self) -> usize {
+        let per_file = self
+            .regenerate
+            .iter()
+            .map(|file| file.summary.requests + file.docs.requests + usize::from(self.reviews))
+            .sum::<usize>();
+        per_file + if self.project_docs { 2 } else { 0 }
+    }
+
+    /// Estimated prompt tokens of the file summary and docs requests.
+    pub fn file_prompt_tokens(&self) -> usize {
+        self.regenerate
+            .iter()
+            .map(|file| file.summary.prompt_tokens + file.docs.prompt_tokens)
+            .sum()
+    }
+}
@@ -0,0 +1,145 @@
+//! Reading Rust source without a parser: blanking comments and literals so that braces,
+//! keywords and paths inside them are not taken for code.
+
+/// Where a point of Rust source is, carried from one character to the next.
+#[derive(Clone, Copy)]
+enum Lexical {
+    Code,
+    LineComment,
+    String,
+    /// A raw string closed by `"` and this many `#`.
+    RawString(usize),
+    /// A block comment nested this deep.
+    BlockComment(usize),
+}
+
+/// `source` with comments, and the contents of string and character literals, replaced by
+/// spaces. Line breaks and byte offsets are kept, so lines and positions found in the result
+/// hold in `source`.
+pub(crate) fn mask_comments_and_literals(source: &str) -> String {
+    let chars = source.chars().collect::<Vec<_>>();
+    let is_ident = |ch: char| ch.is_ascii_alphanumeric() || ch == '_';
+    let mut out = String::with_capacity(source.len());
+    let mut state = Lexical::Code;
+    let mut idx = 0;
+
+    while idx < chars.len() {
+        let ch = chars[idx];
+        let next = chars.get(idx + 1).copied();
+        match state {
+            Lexical::LineComment => {
+                if ch == '\n' {
+                    state = Lexical::Code;
+                }
+                push_blank(&mut out, ch);
+            }
+            Lexical::String => match ch {
+                '\\' => {
+                    push_blank(&mut out, ch);
+                    if let Some(escaped) = next {
+                        push_blank(&mut out, escaped);
+                        idx += 1;
+                    }
+                }
+                '"' => {
+                    out.push('"');
+                    state = Lexical::Code;
+                }
+                _ => push_blank(&mut out, ch),
+            },
+            Lexical::RawString(hashes) => {
+                let closes = chars
+                    .get(idx + 1..idx + 1 + hashes)
+                    .is_some_and(|tail| tail.iter().all(|c| *c == '#'));
+                if ch == '"' && closes {
+                    out.push('"');
+                    out.extend(std::iter::repeat_n('#', hashes));
+                    idx += hashes;
+                    state = Lexical::Code;
+                } else {
+                    push_blank(&mut out, ch);
+                }
+            }
+            Lexical::BlockComment(nesting) => match (ch, next) {
+                ('*', Some('/')) => {
+                    out.push_str("  ");
+                    idx += 1;
+                    state = match nesting {
+                        1 => Lexical::Code,
+                        _ => Lexical::BlockComment(nesting - 1),
+                    };
+                }
+                ('/', Some('*')) => {
+                    out.push_str("  ");
+                    idx += 1;
+                    state = Lexical::BlockComment(nesting + 1);
+                }
+                _ => push_blank(&mut out, ch),
+            },
+            Lexical::Code => match (ch, next) {
+                ('/', Some('/')) => {
+                    push_blank(&mut out, ch);
+                    state = Lexical::LineComment;
+                }
+                ('/', Some('*')) => {
+                    out.push_str("  ");
+                    idx += 1;
+                    state = Lexical::BlockComment(1);
+                }
+                ('"', _) => {
+                    out.push('"');
+                    state = Lexical::String;
+                }
+                ('r', _) => {
+                    // `r"`, `r#"`, ... not preceded by an identifier other than `b`.
+                    let raw_prefix = match idx.checked_sub(1).map(|prev| chars[prev]) {
+                        Some('b') => idx < 2 || !is_ident(chars[idx - 2]),
+                        Some(prev) => !is_ident(prev),
+                        None => true,
+                    };
+                    let hashes = chars[idx + 1..].iter().take_while(|c| **c == '#').count();
+                    out.push('r');
+                    if raw_prefix && chars.get(idx + 1 + hashes) == Some(&'"') {
+                        out.extend(std::iter::repeat_n('#', hashes));
+                        out.push('"');
+                        idx += hashes + 1;
+                        state = Lexical::RawString(hashes);
+                    }
+                }
+                // Character literals such as `'{'` or `'\''`; lifetimes have no closing quote.
+                ('\'', Some('\\')) => {
+                    let close = chars
+                        .get(idx + 3..)
+                        .and_then(|rest| rest.iter().position(|c| *c == '\''))
+                        .map(|end| idx + 3 + end);
+                    out.push('\'');
+                    if let Some(close) = close {
+                        for inner in &chars[idx + 1..close] {
+                            push_blank(&mut out, *inner);
+                        }
+                        out.push('\'');
+                        idx = close;
+                    }
+                }
+                ('\'', Some(inner)) if chars.get(idx + 2) == Some(&'\'') => {
+                    out.push('\'');
+                    push_blank(&mut out, inner);
+                    out.push('\'');
+                    idx += 2;
+                }
+                _ => out.push(ch),
+            },
+        }
+        idx += 1;
+    }
+    out
+}
+
+/// Spaces as wide in bytes as `ch`; line breaks are kept.
+fn push_blank(out: &mut String, ch: char) {
+    if ch == '\n' {
+        out.push('\n');
+    } else {
+        out.extend(std::iter::repeat_n(' ', ch.len_utf8()));
+    }
+}
@@ -0,0 +1,477 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::Path,
+    process::Command,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    error::{PlainSightError, Result},
+    memory::SymbolFact,
+};
+
+/// Lines read past the start of an item when cutting out its signature.
+const MAX_SIGNATURE_LINES: usize = 8;
+
+/// Symbol kinds compared against rustdoc; modules are left out because rustdoc places a
+/// `mod foo;` item in `foo.rs` rather than where it is declared.
+const COMPARED_KINDS: &[&str] = &[
+    "function",
+    "struct",
+    "enum",
+    "trait",
+    "const",
+    "static",
+    "type_alias",
+];
+
+/// The public items of one crate as rustdoc sees them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RustdocCrate {
+    pub name: String,
+    /// Every project file rustdoc compiled as part of the crate.
+    pub files: BTreeSet<String>,
+    pub items: Vec<RustdocItem>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RustdocItem {
+    /// Path the item is exported under, e.g. `plainsight::ollama::OllamaWrapper::new`; the
+    /// shortest `pub use` wins over a definition inside a private module.
+    pub path: String,
+    pub name: String,
+    /// PlainSight symbol kind (`function`, `struct`, `const`, `type_alias`, …).
+    pub kind: String,
+    pub file: String,
+    pub line: usize,
+    /// The declaration as written in the source, up to its body.
+    pub signature: String,
+    pub documented: bool,
+}
+
+/// A public item found by only one of rustdoc and PlainSight's extractor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiMismatch {
+    pub file: String,
+    pub line: usize,
+    pub kind: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiParity {
+    pub crates: Vec<String>,
+    pub rustdoc_item_count: usize,
+    pub extracted_item_count: usize,
+    /// Exported according to rustdoc but not extracted as a `pub` symbol.
+    pub missing_from_extraction: Vec<ApiMismatch>,
+    /// Declared `pub` in the source but not exported: unreachable from the crate root, or
+    /// compiled out by `cfg`.
+    pub missing_from_rustdoc: Vec<ApiMismatch>,
+}
+
+/// Run `cargo +nightly doc` with JSON output for every crate of the workspace at
+/// `project_root` and read back the public items.
+pub fn run_rustdoc(project_root: &Path, target_dir: &Path) -> Result<Vec<RustdocCrate>> {
+    let doc_dir = target_dir.join("doc");
+    // Output of crates that have since been removed would otherwise be read back.
+    if let Ok(entries) = fs::read_dir(&doc_dir) {
+        for entry in entries.flatten() {
+            if entry.path().extension().is_some_and(|ext| ext == "json") {
+                let _ = fs::remove_file(entry.path());
+            }
+        }
+    }
+
+    let output = Command::new("cargo")
+        .args([
+            "+nightly",
+            "doc",
+            "--no-deps",
+            "--workspace",
+            "--target-dir",
+        ])
+        .arg(target_dir)
+        .current_dir(project_root)
+        .env("RUSTDOCFLAGS", "-Z unstable-options --output-format json")
+        .output()
+        .map_err(|e| PlainSightError::io("running `cargo +nightly doc`", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = stderr
+            .lines()
+            .rev()
+            .find(|line| !line.trim().is_empty())
+            .unwrap_or("no output");
+        return Err(PlainSightError::InvalidState(format!(
+            "`cargo +nightly doc` failed: {}",
+            reason.trim()
+        )));
+    }
+
+    let mut crates = Vec::new();
+    let entries = fs::read_dir(&doc_dir).map_err(|e| {
+        PlainSightError::io(format!("reading rustdoc output '{}'", doc_dir.display()), e)
+    })?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        let json = fs::read_to_string(&path).map_err(|e| {
+            PlainSightError::io(format!("reading rustdoc JSON '{}'", path.display()), e)
+        })?;
+        crates.push(parse_rustdoc_json(&json, project_root)?);
+    }
+    crates.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(crates)
+}
+
+/// Read the public items of the crate described by one rustdoc JSON document. Span file names
+/// are taken relative to `project_root`, which is also where signatures are read from.
+pub fn parse_rustdoc_json(json: &str, project_root: &Path) -> Result<RustdocCrate> {
+    let doc: Value = serde_json::from_str(json)
+        .map_err(|e| PlainSightError::InvalidState(format!("parsing rustdoc JSON: {e}")))?;
+    let index = doc
+        .get("index")
+        .and_then(Value::as_object)
+        .ok_or_else(|| PlainSightError::InvalidState("rustdoc JSON has no index".to_string()))?;
+    let paths = doc.get("paths").and_then(Value::as_object);
+    let definition_path = |id: u64| {
+        let segments = paths?.get(&id.to_string())?.get("path")?.as_array()?;
+        Some(
+            segments
+                .iter()
+                .filter_map(Value::as_str)
+                .collect::<Vec<_>>()
+                .join("::"),
+        )
+    };
+
+    let local = index
+        .values()
+        .filter(|item| item.get("crate_id").and_then(Value::as_u64) == Some(0))
+        .collect::<Vec<_>>();
+
+    // `paths` holds where an item is defined, which may be a private module; a `pub use` of it
+    // in a module with a public path gives the path users actually write.
+    let mut parent_module = BTreeMap::new();
+    for item in &local {
+        let (Some(module_id), Some(children)) = (
+            item.get("id").and_then(Value::as_u64),
+            item.get("inner")
+                .and_then(|inner| inner.get("module"))
+                .and_then(|module| module.get("items"))
+                .and_then(Value::as_array),
+        ) else {
+            continue;
+        };
+        for child in children.iter().filter_map(Value::as_u64) {
+            parent_module.insert(child, module_id);
+        }
+    }
+    let mut reexport_path: BTreeMap<u64, String> = BTreeMap::new();
+    for item in &local {
+        let Some(reexport) = item
+            .get("inner")
+            .and_then(|inner| inner.get("use"))
+            .filter(|_| item.get("visibility").and_then(Value::as_str) == Some("public"))
+        else {
+            continue;
+        };
+        let (Some(target), Some(name), Some(module_path)) = (
+            reexport.get("id").and_then(Value::as_u64),
+            reexport.get("name").and_then(Value::as_str),
+            item.get("id")
+                .and_then(Value::as_u64)
+                .and_then(|id| parent_module.get(&id))
+                .and_then(|module| definition_path(*module)),
+        ) else {
+            continue;
+        };
+        let path = format!("{module_path}::{name}");
+        let shorter = reexport_path
+            .get(&target)
+            .is_none_or(|existing| path.len() < existing.len());
+        if shorter {
+            reexport_path.insert(target, path);
+        }
+    }
+    let path_of = |id: &Value| {
+        let id = id.as_u64()?;
+        match (definition_path(id), reexport_path.get(&id)) {
+            (Some(defined), Some(reexported)) if reexported.len() < defined.len() => {
+                Some(reexported.clone())
+            }
+            (None, Some(reexported)) => Some(reexported.clone()),
+            (defined, _) => defined,
+        }
+    };
+    let name = doc
+        .get("root")
+        .and_then(|root| index.get(&root.as_u64()?.to_string()))
+        .and_then(|root| root.get("name")?.as_str())
+        .unwrap_or_default()
+        .to_string();
+
+    // Inherent methods have no entry in `paths`; they are exported under their type's path.
+    let mut method_owner = BTreeMap::new();
+    for item in &local {
+        let Some(inherent) = item
+            .get("inner")
+            .and_then(|inner| inner.get("impl"))
+            .filter(|imp| imp.get("trait").is_none_or(Value::is_null))
+        else {
+            continue;
+        };
+        let Some(owner) = inherent
+            .get("for")
+            .and_then(|ty| ty.get("resolved_path"))
+            .and_then(|ty| {
+                path_of(ty.get("id")?).or_else(|| Some(ty.get("path")?.as_str()?.to_string()))
+            })
+        else {
+            continue;
+        };
+        for id in inherent
+            .get("items")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            if let Some(id) = id.as_u64() {
+                method_owner.insert(id, owner.clone());
+            }
+        }
+    }
+
+    let mut files = BTreeSet::new();
+    let mut items = Vec::new();
+    let mut sources: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for item in local {
+        let Some((file, line)) = span_location(item, project_root) else {
+            continue;
+        };
+        files.insert(file.clone());
+
+        if item.get("visibility").and_then(Value::as_str) != Some("public") {
+            continue;
+        }
+        let (Some(item_name), Some(kind)) = (
+            item.get("name").and_then(Value::as_str),
+            item.get("inner")
+                .and_then(Value::as_object)
+                .and_then(|inner| inner.keys().next())
+                .and_then(|kind| symbol_kind(kind)),
+        ) else {
+            continue;
+        };
+        let id = item.get("id").cloned().unwrap_or_default();
+        let path = path_of(&id)
+            .or_else(|| {
+                let owner = method_owner.get(&id.as_u64()?)?;
+                Some(format!("{owner}::{item_name}"))
+            })
+            .unwrap_or_else(|| item_name.to_string());
+
+        let lines = sources.entry(file.clone()).or_insert_with(|| {
+            fs::read_to_string(project_root.join(&file))
+                .map(|source| source.lines().map(str::to_string).collect())
+                .unwrap_or_default()
+        });
+        items.push(RustdocItem {
+            path,
+            name: item_name.to_string(),
+            kind: kind.to_string(),
+            signature: signature_at(lines, line, kind),
+            file,
+            line,
+            documented: item.get("docs").and_then(Value::as_str).is_some(),
+        });
+    }
+
+    items.sort_by(|a, b| (&a.file, a.line).cmp(&(&b.file, b.line)));
+    Ok(RustdocCrate { name, files, items })
+}
+
+/// Compare rustdoc's public items with the `pub` symbols extracted from the same files.
+pub fn reconcile(crates: &[RustdocCrate], files: &[(&str, &[SymbolFact])]) -> ApiParity {
+    let covered = crates
+        .iter()
+        .flat_map(|krate| krate.files.iter().map(String::as_str))
+        .collect::<BTreeSet<_>>();
+    let documented = crates
+        .iter()
+        .flat_map(|krate| krate.items.iter())
+        .filter(|item| COMPARED_KINDS.contains(&item.kind.as_str()))
+        .collect::<Vec<_>>();
+    let extracted = files
+        .iter()
+        .filter(|(path, _)| covered.contains(path))
+        .flat_map(|(path, symbols)| symbols.iter().map(move |sym| (*path, sym)))
+        .filter(|(_, sym)| {
+            !sym.is_test
+                && sym.details.visibility == "pub"
+                && COMPARED_KINDS.contains(&sym.kind.as_str())
+        })
+        .collect::<Vec<_>>();
+
+    let mut unmatched: BTreeMap<(&str, &str, &str), usize> = BTreeMap::new();
+    for (path, sym) in &extracted {
+        *unmatched
+            .entry((path, sym.kind.as_str(), sym.name.as_str()))
+            .or_default() += 1;
+    }
+    let mut missing_from_extraction = Vec::new();
+    for item in &documented {
+        let key = (item.file.as_str(), item.kind.as_str(), item.name.as_str());
+        match unmatched.get_mut(&key) {
+            Some(count) if *count > 0 => *count -= 1,
+            _ => missing_from_extraction.push(ApiMismatch {
+                file: item.file.clone(),
+                line: item.line,
+                kind: item.kind.clone(),
+                name: item.path.clone(),
+            }),
+        }
+    }
+
+    let mut missing_from_rustdoc = Vec::new();
+    for (path, sym) in extracted.iter().rev() {
+        let key = (*path, sym.kind.as_str(), sym.name.as_str());
+        if let Some(count) = unmatched.get_mut(&key)
+            && *count > 0
+        {
+            *count -= 1;
+            missing_from_rustdoc.push(ApiMismatch {
+                file: path.to_string(),
+                line: sym.line,
+                kind: sym.kind.clone(),
+                name: sym.name.clone(),
+            });
+        }
+    }
+    missing_from_rustdoc.reverse();
+
+    ApiParity {
+        crates: crates.iter().map(|krate| krate.name.clone()).collect(),
+        rustdoc_item_count: documented.len(),
+        extracted_item_count: extracted.len(),
+        missing_from_extraction,
+        missing_from_rustdoc,
+    }
+}
+
+pub fn render_report(project_name: &str, parity: &ApiParity) -> String {
+    let mut out = format!("# {project_name} Public API Parity\n\n");
+    out.push_str(&format!(
+        "Compared with rustdoc JSON for {}: rustdoc exports {} item(s), PlainSight extracted {} `pub` item(s) from the same files.\n",
+        parity
+            .crates
+            .iter()
+            .map(|name| format!("`{name}`"))
+            .collect::<Vec<_>>()
+            .join(", "),
+        parity.rustdoc_item_count,
+        parity.extracted_item_count
+    ));
+
+    if parity.missing_from_extraction.is_empty() && parity.missing_from_rustdoc.is_empty() {
+        out.push_str("\nThe extracted public API matches rustdoc.\n");
+        return out;
+    }
+
+    if !parity.missing_from_extraction.is_empty() {
+        out.push_str("\n## Missing from Extraction\n\n");
+        out.push_str(
+            "Exported according to rustdoc, but not extracted as a `pub` symbol (macro-generated items, declarations split across lines).\n\n",
+        );
+        render_mismatches(&mut out, &parity.missing_from_extraction, "Exported as");
+    }
+    if !parity.missing_from_rustdoc.is_empty() {
+        out.push_str("\n## Not Exported\n\n");
+        out.push_str(
+            "Declared `pub` but absent from rustdoc: not reachable from the crate root (a private module without a re-export) or disabled by `cfg` under the default features.\n\n",
+        );
+        render_mismatches(&mut out, &parity.missing_from_rustdoc, "Name");
+    }
+    out
+}
+
+fn render_mismatches(out: &mut String, mismatches: &[ApiMismatch], name_header: &str) {
+    out.push_str(&format!(
+        "| File | Line | Kind | {name_header} |\n|---|---|---|---|\n"
+    ));
+    for mismatch in mismatches {
+        out.push_str(&format!(
+            "| `{}` | {} | {} | `{}` |\n",
+            mismatch.file, mismatch.line, mismatch.kind, mismatch.name
+        ));
+    }
+}
+
+/// Project-relative file and 1-based line of an item's span; `None` for spans outside the
+/// project (macro expansions from dependencies, the standard library).
+fn span_location(item: &Value, project_root: &Path) -> Option<(String, usize)> {
+    let span = item.get("span")?;
+    let filename = Path::new(span.get("filename")?.as_str()?);
+    let relative = if filename.is_absolute() {
+        filename.strip_prefix(project_root).ok()?
+    } else {
+        filename
+    };
+    let line = span.get("begin")?.get(0)?.as_u64()? as usize;
+    Some((relative.to_string_lossy().replace('\\', "/"), line))
+}
+
+fn symbol_kind(rustdoc_kind: &str) -> Option<&'static str> {
+    Some(match rustdoc_kind {
+        "function" => "function",
+        "struct" => "struct",
+        "enum" => "enum",
+        "union" => "union",
+        "trait" => "trait",
+        "trait_alias" => "trait_alias",
+        "type_alias" => "type_alias",
+        "constant" => "const",
+        "static" => "static",
+        "macro" => "macro",
+        _ => return None,
+    })
+}
+
+/// The declaration starting at `line`, from its first non-attribute line up to the body (or
+/// the initializer, for constants and statics), with whitespace collapsed.
+fn signature_at(lines: &[String], line: usize, kind: &str) -> String {
+    let mut signature = String::new();
+    let declaration = lines
+        .iter()
+        .skip(line.saturating_sub(1))
+        .map(|line| line.trim())
+        .skip_while(|line| line.starts_with("#[") || line.starts_with("//"))
+        .take(MAX_SIGNATURE_LINES);
+    for text in declaration {
+        let initializer = matches!(kind, "const" | "static")
+            .then(|| text.find(" = "))
+            .flatten();
+        let end = initializer.or_else(|| text.find(['{', ';']));
+        let text = end.map_or(text, |end| &text[..end]);
+        if !signature.is_empty() {
+            signature.push(' ');
+        }
+        signature.push_str(text.trim());
+        if end.is_some() {
+            break;
+        }
+    }
+    signature
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace("( ", "(")
+        .replace(", )", ")")
+        .replace(" )", ")")
+}
@@ -1,4 +1,4 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 const DEFAULT_MAX_CHUNK_LINES: usize = 120;
 const DEFAULT_CHUNK_OVERLAP_LINES: usize = 20;
@@ -13,7 +13,7 @@ struct ChunkConfig {
     max_tokens: usize,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SourceChunk {
     pub chunk_id: usize,
     pub start_line: usize,
@@ -21,7 +21,7 @@ pub struct SourceChunk {
     pub content: String,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SourceIndex {
     pub language: String,
     pub line_count: usize,
@@ -30,61 +30,100 @@ pub struct SourceIndex {
 }
 
 pub fn build_source_index(source: &str, language: &str) -> SourceIndex {
-    let config = chunk_config(language);
-    let lines: Vec<&str> = source.lines().collect();
-    let line_count = lines.len();
+    let mut builder = SourceIndexBuilder::new(language);
+    for line in source.lines() {
+        builder.push_line(line);
+    }
+    builder.finish()
+}
 
-    if lines.is_empty() {
-        return SourceIndex {
+/// Builds a [`SourceIndex`] from lines fed one at a time, holding only the lines of the chunk
+/// being assembled so large files can be indexed while they are streamed.
+#[derive(Debug)]
+pub struct SourceIndexBuilder {
+    language: String,
+    config: ChunkConfig,
+    /// Lines not yet emitted, starting at line `pending_start`.
+    pending: Vec<String>,
+    pending_start: usize,
+    line_count: usize,
+    chunks: Vec<SourceChunk>,
+}
+
+impl SourceIndexBuilder {
+    pub fn new(language: &str) -> Self {
+        Self {
             language: language.to_string(),
+            config: chunk_config(language),
+            pending: Vec::new(),
+            pending_start: 1,
             line_count: 0,
-            chunk_count: 0,
             chunks: Vec::new(),
-        };
+        }
     }
 
-    let mut chunks = Vec::new();
-    let mut start = 0usize;
+    pub fn push_line(&mut self, line: &str) {
+        self.pending.push(line.to_string());
+        self.line_count += 1;
+        // A chunk is only cut once a line past its maximum has arrived, so the last chunk
+        // of the file is never followed by an overlap-only chunk.
+        if self.pending.len() > self.config.max_lines {
+            let end = self.emit_chunk();
+            self.advance(end);
+        }
+    }
 
-    while start < lines.len() {
-        let mut end = usize::min(start + config.max_lines, lines.len());
+    pub fn finish(mut self) -> SourceIndex {
+        while !self.pending.is_empty() {
+            let end = self.emit_chunk();
+            if end >= self.pending.len() {
+                break;
+            }
+            self.advance(end);
+        }
+
+        SourceIndex {
+            language: self.language,
+            line_count: self.line_count,
+            chunk_count: self.chunks.len(),
+            chunks: self.chunks,
+        }
+    }
+
+    /// Emit a chunk from the head of `pending` and return how many lines it covers.
+    fn emit_chunk(&mut self) -> usize {
+        let mut end = usize::min(self.config.max_lines, self.pending.len());
 
         // Bound long chunks by characters and estimated tokens so prompts stay predictable.
-        while end > start {
-            let segment = &lines[start..end];
+        while end > 0 {
+            let segment = &self.pending[..end];
             let char_len: usize = segment.iter().map(|l| l.len() + 1).sum();
             let token_estimate = estimate_tokens(segment);
-            if char_len <= config.max_chars && token_estimate <= config.max_tokens {
+            if char_len <= self.config.max_chars && token_estimate <= self.config.max_tokens {
                 break;
             }
             end -= 1;
         }
 
-        if end == start {
-            end = usize::min(start + 1, lines.len());
+        if end == 0 {
+            end = 1;
         }
 
-        let content = lines[start..end].join("\n");
-        chunks.push(SourceChunk {
-            chunk_id: chunks.len(),
-            start_line: start + 1,
-            end_line: end,
-            content,
+        self.chunks.push(SourceChunk {
+            chunk_id: self.chunks.len(),
+            start_line: self.pending_start,
+            end_line: self.pending_start + end - 1,
+            content: self.pending[..end].join("\n"),
         });
-
-        if end >= lines.len() {
-            break;
-        }
-
-        let overlap = config.overlap_lines.min(end - start);
-        start = end - overlap;
+        end
     }
 
-    SourceIndex {
-        language: language.to_string(),
-        line_count,
-        chunk_count: chunks.len(),
-        chunks,
+    /// Drop the emitted lines except the overlap, always moving forward by at least one line.
+    fn advance(&mut self, end: usize) {
+        let overlap = self.config.overlap_lines.min(end);
+        let consumed = (end - overlap).max(1);
+        self.pending.drain(..consumed);
+        self.pending_start += consumed;
     }
 }
 
@@ -123,7 +162,7 @@ fn chunk_config(language: &str) -> ChunkConfig {
     }
 }
 
-fn estimate_tokens(lines: &[&str]) -> usize {
+fn estimate_tokens(lines: &[String]) -> usize {
     let mut total = 0usize;
     for line in lines {
         // Practical, cheap approximation: ~4 chars/token with a floor from whitespace splits.
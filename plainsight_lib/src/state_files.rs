@@ -0,0 +1,279 @@
+//! Loading state files that users edit by hand: `.annotations.json`, `.skip.json` and
+//! `.memory.json`.
+//!
+//! A file that does not load is checked again entry by entry, so every invalid entry is
+//! reported with its line, column and key path instead of only the first serde error. With
+//! repair turned on, invalid entries are dropped, the original is kept next to the file as
+//! `<file>.bak` and the valid entries are loaded and saved.
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::value::RawValue;
+use tracing::warn;
+
+use crate::{
+    docs_store::DocsStore,
+    error::{PlainSightError, Result},
+    memory::ProjectMemory,
+    project_manager::{AnnotationStore, SkipList},
+};
+
+/// An entry of a state file that does not match its schema.
+#[derive(Debug, Clone)]
+pub(crate) struct StateFileIssue {
+    pub line: usize,
+    pub column: usize,
+    /// Path of the offending value, e.g. `files["src/lib.rs"][1].answer`.
+    pub key: String,
+    pub message: String,
+}
+
+impl fmt::Display for StateFileIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}: {}",
+            self.line, self.column, self.key, self.message
+        )
+    }
+}
+
+/// A state file whose top-level collections can be loaded one entry at a time.
+pub(crate) trait StateFile: Serialize + DeserializeOwned {
+    /// What the file is called in messages.
+    const LABEL: &'static str;
+
+    /// The file built from the entries of `document` that load; the rest become its issues.
+    fn from_entries(document: &mut Document<'_>) -> Self;
+}
+
+impl StateFile for AnnotationStore {
+    const LABEL: &'static str = "annotations";
+
+    fn from_entries(document: &mut Document<'_>) -> Self {
+        Self {
+            files: document.keyed_entries("files"),
+        }
+    }
+}
+
+impl StateFile for SkipList {
+    const LABEL: &'static str = "skip list";
+
+    fn from_entries(document: &mut Document<'_>) -> Self {
+        Self {
+            files: document.entries("files"),
+        }
+    }
+}
+
+impl StateFile for ProjectMemory {
+    const LABEL: &'static str = "project memory";
+
+    fn from_entries(document: &mut Document<'_>) -> Self {
+        let files: Vec<_> = document.entries("files");
+        let global_symbols: Vec<_> = document.entries("global_symbols");
+        Self {
+            file_count: files.len(),
+            unique_symbol_count: global_symbols.len(),
+            files,
+            global_symbols,
+            open_items: document.entries("open_items"),
+            links: document.entries("links"),
+            reexports: document.entries("reexports"),
+        }
+    }
+}
+
+/// The top-level fields of a state file, and the issues found loading their entries.
+pub(crate) struct Document<'a> {
+    content: &'a str,
+    fields: BTreeMap<String, &'a RawValue>,
+    issues: Vec<StateFileIssue>,
+}
+
+impl<'a> Document<'a> {
+    /// The entries of the array `field` that load as `T`; a missing field has none.
+    pub fn entries<T: DeserializeOwned>(&mut self, field: &str) -> Vec<T> {
+        let Some(raw) = self.fields.get(field).copied() else {
+            return Vec::new();
+        };
+        match self.parse::<Vec<&'a RawValue>>(raw, field) {
+            Some(items) => self.items(items, field),
+            None => Vec::new(),
+        }
+    }
+
+    /// The entries of each array in the object `field` that load as `T`, by key. Keys whose
+    /// value is not an array are dropped.
+    pub fn keyed_entries<T: DeserializeOwned>(&mut self, field: &str) -> BTreeMap<String, Vec<T>> {
+        let Some(raw) = self.fields.get(field).copied() else {
+            return BTreeMap::new();
+        };
+        let Some(lists) = self.parse::<BTreeMap<String, &'a RawValue>>(raw, field) else {
+            return BTreeMap::new();
+        };
+        lists
+            .into_iter()
+            .filter_map(|(key, list)| {
+                let prefix = format!("{field}[{key:?}]");
+                let items = self.parse::<Vec<&'a RawValue>>(list, &prefix)?;
+                Some((key, self.items(items, &prefix)))
+            })
+            .collect()
+    }
+
+    fn items<T: DeserializeOwned>(&mut self, items: Vec<&'a RawValue>, key: &str) -> Vec<T> {
+        items
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, item)| self.parse(item, &format!("{key}[{index}]")))
+            .collect()
+    }
+
+    /// `raw` as `T`, or `None` with an issue located in the whole file.
+    fn parse<T: Deserialize<'a>>(&mut self, raw: &'a RawValue, key: &str) -> Option<T> {
+        let text = raw.get();
+        let mut deserializer = serde_json::Deserializer::from_str(text);
+        match serde_path_to_error::deserialize(&mut deserializer) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                let offset = text.as_ptr() as usize - self.content.as_ptr() as usize;
+                let inner = e.path().to_string();
+                let key = if inner == "." {
+                    key.to_string()
+                } else if inner.starts_with('[') {
+                    format!("{key}{inner}")
+                } else {
+                    format!("{key}.{inner}")
+                };
+                self.issues
+                    .push(locate(self.content, offset, key, e.inner()));
+                None
+            }
+        }
+    }
+}
+
+/// Load the state file at `path`, or `None` when there is none.
+///
+/// A file with invalid entries fails with all of them listed, unless `repair` is set: then
+/// they are dropped with a warning, the original is kept as `<file>.bak` and the rest is saved
+/// in its place. Invalid JSON cannot be repaired.
+pub(crate) fn load_state_file<T: StateFile>(
+    store: &dyn DocsStore,
+    path: &Path,
+    repair: bool,
+) -> Result<Option<T>> {
+    if !store.exists(path) {
+        return Ok(None);
+    }
+    let label = T::LABEL;
+    let content = store
+        .read_to_string(path)
+        .map_err(|e| PlainSightError::io(format!("reading {label} '{}'", path.display()), e))?;
+
+    let mut deserializer = serde_json::Deserializer::from_str(&content);
+    let strict_error = match serde_path_to_error::deserialize(&mut deserializer) {
+        Ok(value) if deserializer.end().is_ok() => return Ok(Some(value)),
+        // Trailing characters; the syntax check below reports them.
+        Ok(_) => None,
+        Err(e) => Some(e),
+    };
+    let fields = serde_json::from_str(&content).map_err(|e| {
+        PlainSightError::InvalidState(format!(
+            "failed to parse {label} '{}': {e}; fix the JSON by hand, --repair only drops entries that do not match the schema",
+            path.display()
+        ))
+    })?;
+    let mut document = Document {
+        content: &content,
+        fields,
+        issues: Vec::new(),
+    };
+    let value = T::from_entries(&mut document);
+    let mut issues = document.issues;
+    // A required field missing altogether: the entries that exist all load.
+    if issues.is_empty()
+        && let Some(e) = strict_error
+    {
+        issues.push(locate(&content, 0, "top level".to_string(), e.inner()));
+    }
+
+    if !repair {
+        let listed = issues
+            .iter()
+            .map(|issue| format!("\n  {issue}"))
+            .collect::<String>();
+        let entries = if issues.len() == 1 {
+            "entry"
+        } else {
+            "entries"
+        };
+        return Err(PlainSightError::InvalidState(format!(
+            "{label} '{}' has {} invalid {entries}; rerun with --repair to drop them:{listed}",
+            path.display(),
+            issues.len()
+        )));
+    }
+
+    let backup = backup_path(path);
+    store.write(&backup, content.as_bytes()).map_err(|e| {
+        PlainSightError::io(format!("writing {label} backup '{}'", backup.display()), e)
+    })?;
+    let repaired = serde_json::to_string_pretty(&value)
+        .map_err(|e| PlainSightError::InvalidState(format!("serializing {label}: {e}")))?;
+    store
+        .write(path, repaired.as_bytes())
+        .map_err(|e| PlainSightError::io(format!("writing {label} '{}'", path.display()), e))?;
+    for issue in &issues {
+        warn!(
+            path = %path.display(),
+            line = issue.line,
+            column = issue.column,
+            key = %issue.key,
+            error = %issue.message,
+            "dropped invalid {label} entry"
+        );
+    }
+    warn!(
+        path = %path.display(),
+        backup = %backup.display(),
+        dropped = issues.len(),
+        "{label} repaired"
+    );
+    Ok(Some(value))
+}
+
+fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".bak");
+    path.with_file_name(name)
+}
+
+/// An issue for `error`, raised parsing the text that starts at byte `offset` of `content`.
+fn locate(content: &str, offset: usize, key: String, error: &serde_json::Error) -> StateFileIssue {
+    let before = &content[..offset];
+    let start_line = before.matches('\n').count() + 1;
+    let start_column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+    let (line, column) = match error.line() {
+        0 | 1 => (start_line, start_column + error.column().saturating_sub(1)),
+        line => (start_line + line - 1, error.column()),
+    };
+    let message = error.to_string();
+    let suffix = format!(" at line {} column {}", error.line(), error.column());
+    StateFileIssue {
+        line,
+        column,
+        key,
+        message: message
+            .strip_suffix(&suffix)
+            .unwrap_or(&message)
+            .to_string(),
+    }
+}
@@ -0,0 +1,167 @@
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::Result,
+    memory::{CrossFileLink, GlobalSymbol, ProjectMemory},
+    project_manager::ProjectContext,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolMatch {
+    pub name: String,
+    pub kind: String,
+    pub locations: Vec<SymbolLocation>,
+    /// Cross-file links through this symbol: imports of it and re-exports.
+    pub links: Vec<CrossFileLink>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolLocation {
+    pub file: String,
+    pub line: usize,
+}
+
+/// Search the global symbols of the project memory persisted by the last run.
+///
+/// A pattern with `*` or `?` is a case-insensitive glob over the whole name. Any other pattern
+/// matches names that contain it, then names that contain its characters in order (`prmtctx`
+/// finds `PromptContext`). Exact matches come first, then prefixes, then shorter names.
+pub fn search_symbols(
+    project: &ProjectContext,
+    pattern: &str,
+    limit: usize,
+) -> Result<Vec<SymbolMatch>> {
+    Ok(match_symbols(&project.load_memory()?, pattern, limit))
+}
+
+/// `search_symbols` over an already loaded `memory`.
+pub fn match_symbols(memory: &ProjectMemory, pattern: &str, limit: usize) -> Vec<SymbolMatch> {
+    let pattern = SymbolPattern::new(pattern);
+    let mut ranked = memory
+        .global_symbols
+        .iter()
+        .filter_map(|symbol| Some((pattern.rank(&symbol.name)?, symbol)))
+        .collect::<Vec<_>>();
+    ranked.sort_by(|(a_rank, a), (b_rank, b)| {
+        compare_ranked((*a_rank, &a.name, &a.kind), (*b_rank, &b.name, &b.kind))
+    });
+    ranked.truncate(limit);
+
+    ranked
+        .into_iter()
+        .map(|(_, symbol)| resolve_symbol(memory, symbol))
+        .collect()
+}
+
+/// Every global symbol of `memory` with its locations and links.
+pub(crate) fn all_symbols(memory: &ProjectMemory) -> Vec<SymbolMatch> {
+    memory
+        .global_symbols
+        .iter()
+        .map(|symbol| resolve_symbol(memory, symbol))
+        .collect()
+}
+
+fn resolve_symbol(memory: &ProjectMemory, symbol: &GlobalSymbol) -> SymbolMatch {
+    let locations = memory
+        .files
+        .iter()
+        .filter(|file| symbol.defined_in.contains(&file.path))
+        .flat_map(|file| {
+            file.symbols
+                .iter()
+                .filter(|fact| fact.name == symbol.name && fact.kind == symbol.kind)
+                .map(|fact| SymbolLocation {
+                    file: file.path.clone(),
+                    line: fact.line,
+                })
+        })
+        .collect();
+    let links = memory
+        .links
+        .iter()
+        .filter(|link| link.symbol == symbol.name)
+        .cloned()
+        .collect();
+    SymbolMatch {
+        name: symbol.name.clone(),
+        kind: symbol.kind.clone(),
+        locations,
+        links,
+    }
+}
+
+/// A search pattern as described on [`search_symbols`].
+pub(crate) struct SymbolPattern {
+    pattern: String,
+    is_glob: bool,
+}
+
+impl SymbolPattern {
+    pub fn new(pattern: &str) -> Self {
+        let pattern = pattern.trim().to_lowercase();
+        let is_glob = pattern.contains(['*', '?']);
+        Self { pattern, is_glob }
+    }
+
+    /// How well `name` matches, lower is better; `None` when it does not match.
+    pub fn rank(&self, name: &str) -> Option<u8> {
+        let name = name.to_lowercase();
+        if self.is_glob {
+            glob_matches(&self.pattern, &name).then_some(0)
+        } else if name == self.pattern {
+            Some(0)
+        } else if name.starts_with(&self.pattern) {
+            Some(1)
+        } else if name.contains(&self.pattern) {
+            Some(2)
+        } else {
+            is_subsequence(&self.pattern, &name).then_some(3)
+        }
+    }
+}
+
+/// Order of ranked `(rank, name, kind)` matches: better rank, then shorter name.
+pub(crate) fn compare_ranked(a: (u8, &str, &str), b: (u8, &str, &str)) -> Ordering {
+    a.0.cmp(&b.0)
+        .then_with(|| a.1.len().cmp(&b.1.len()))
+        .then_with(|| a.1.cmp(b.1))
+        .then_with(|| a.2.cmp(b.2))
+}
+
+pub(crate) fn glob_matches(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+    // Position after the last `*` and the name position it was tried against, for
+    // backtracking.
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p + 1, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((after_star, tried)) => {
+                    p = after_star;
+                    n = tried + 1;
+                    star = Some((after_star, tried + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+fn is_subsequence(pattern: &str, name: &str) -> bool {
+    let mut name = name.chars();
+    pattern.chars().all(|c| name.any(|n| n == c))
+}
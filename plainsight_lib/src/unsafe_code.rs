@@ -0,0 +1,257 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::memory::SymbolFact;
+
+/// Kinds in the order they are listed in the report.
+const KINDS: &[&str] = &[
+    "unsafe block",
+    "unsafe fn",
+    "unsafe impl",
+    "unsafe trait",
+    "extern block",
+    "extern fn",
+    "no_mangle",
+    "static mut",
+    "raw pointer",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct UnsafeSite {
+    /// One of `unsafe block`, `unsafe fn`, `unsafe impl`, `unsafe trait`, `extern block`,
+    /// `extern fn`, `no_mangle`, `static mut` or `raw pointer`.
+    pub kind: String,
+    pub line: usize,
+    /// The declared item for declarations (`fn read_raw`, `Send for Handle`), the ABI for extern
+    /// blocks, and the matching code otherwise.
+    pub detail: String,
+}
+
+/// Find `unsafe` code, FFI declarations and raw pointer types in one Rust source file.
+pub fn detect_unsafe(source: &str) -> Vec<UnsafeSite> {
+    let mut sites = Vec::new();
+
+    for (idx, raw_line) in source.lines().enumerate() {
+        let code = code_only(raw_line);
+        let line = code.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let mut push = |kind: &str, detail: String| {
+            sites.push(UnsafeSite {
+                kind: kind.to_string(),
+                line: idx + 1,
+                detail,
+            });
+        };
+
+        let item = strip_visibility(line);
+        if let Some(rest) = item.strip_prefix("unsafe fn ") {
+            push("unsafe fn", format!("fn {}", identifier(rest)));
+        } else if let Some(rest) = item.strip_prefix("unsafe impl") {
+            push("unsafe impl", impl_header(rest));
+        } else if let Some(rest) = item.strip_prefix("unsafe trait ") {
+            push("unsafe trait", format!("trait {}", identifier(rest)));
+        }
+
+        // Matched on the raw line: blanking would erase the ABI string, and the prefix match
+        // cannot hit a literal.
+        let raw_item = strip_visibility(raw_line.trim());
+        let extern_item = raw_item.strip_prefix("unsafe ").unwrap_or(raw_item);
+        if let Some(rest) = extern_item.strip_prefix("extern")
+            && !rest.trim_start().starts_with("crate")
+            && (rest.starts_with(' ') || rest.starts_with('{'))
+        {
+            let (abi, rest) = extern_abi(rest.trim_start());
+            let rest = rest.trim_start();
+            if rest.starts_with('{') {
+                push("extern block", abi);
+            } else if let Some(rest) = rest.strip_prefix("fn ") {
+                push("extern fn", format!("fn {} ({abi})", identifier(rest)));
+            }
+        }
+
+        if line.contains("#[no_mangle]") || line.contains("#[unsafe(no_mangle)]") {
+            push("no_mangle", raw_line.trim().to_string());
+        }
+        if let Some(rest) = item.strip_prefix("static mut ") {
+            push("static mut", identifier(rest));
+        }
+        if contains_unsafe_block(line) {
+            push("unsafe block", truncate(raw_line.trim()));
+        }
+        if line.contains("*const ") || line.contains("*mut ") {
+            push("raw pointer", truncate(raw_line.trim()));
+        }
+    }
+
+    sites
+}
+
+/// Render `unsafe.md` from per-file sites; `symbols` gives each file's symbols so sites can be
+/// attributed to the function that contains them.
+pub fn render_report(
+    project_name: &str,
+    sites: &BTreeMap<String, Vec<UnsafeSite>>,
+    symbols: &BTreeMap<String, Vec<SymbolFact>>,
+) -> String {
+    let mut out = format!("# {project_name} Unsafe Code and FFI Boundaries\n\n");
+    let files = sites
+        .iter()
+        .filter(|(_, sites)| !sites.is_empty())
+        .collect::<Vec<_>>();
+    if files.is_empty() {
+        out.push_str("No `unsafe` code, FFI declarations or raw pointers were found.\n");
+        return out;
+    }
+
+    out.push_str(&format!(
+        "{} file(s) contain unsafe code or FFI boundaries.\n\n",
+        files.len()
+    ));
+    out.push_str("| Kind | Count |\n|---|---|\n");
+    for kind in KINDS {
+        let count = files
+            .iter()
+            .flat_map(|(_, sites)| sites.iter())
+            .filter(|site| site.kind == *kind)
+            .count();
+        if count > 0 {
+            out.push_str(&format!("| {kind} | {count} |\n"));
+        }
+    }
+
+    for (path, file_sites) in files {
+        out.push_str(&format!("\n## `{path}`\n\n"));
+        out.push_str("| Line | Kind | Within | Detail |\n|---|---|---|---|\n");
+        let file_symbols = symbols.get(path).map(Vec::as_slice).unwrap_or_default();
+        for site in file_sites {
+            let within = enclosing_function(file_symbols, site.line)
+                .map(|name| format!("`{name}`"))
+                .unwrap_or_else(|| "-".to_string());
+            out.push_str(&format!(
+                "| {} | {} | {within} | `{}` |\n",
+                site.line,
+                site.kind,
+                site.detail.replace('|', "\\|")
+            ));
+        }
+    }
+
+    out
+}
+
+/// The last function declared at or before `line`.
+fn enclosing_function(symbols: &[SymbolFact], line: usize) -> Option<&str> {
+    symbols
+        .iter()
+        .filter(|sym| sym.kind == "function" && sym.line <= line)
+        .max_by_key(|sym| sym.line)
+        .map(|sym| sym.name.as_str())
+}
+
+fn contains_unsafe_block(line: &str) -> bool {
+    let mut search = 0usize;
+    while let Some(pos) = line[search..].find("unsafe") {
+        let start = search + pos;
+        search = start + "unsafe".len();
+        let before = line[..start].chars().next_back();
+        if before.is_some_and(|c| c.is_ascii_alphanumeric() || c == '_') {
+            continue;
+        }
+        if line[search..].trim_start().starts_with('{') {
+            return true;
+        }
+    }
+    false
+}
+
+/// `"C"` from `"C" fn x()` / `"C" {`; a bare `extern` is the C ABI.
+fn extern_abi(rest: &str) -> (String, &str) {
+    if let Some(quoted) = rest.strip_prefix('"')
+        && let Some(end) = quoted.find('"')
+    {
+        return (format!("extern \"{}\"", &quoted[..end]), &quoted[end + 1..]);
+    }
+    ("extern \"C\"".to_string(), rest)
+}
+
+/// `<T> Send for Handle<T> {` -> `Send for Handle<T>`.
+fn impl_header(rest: &str) -> String {
+    let rest = rest.trim_start();
+    let rest = if rest.starts_with('<') {
+        let mut depth = 0i32;
+        let end = rest
+            .char_indices()
+            .find_map(|(idx, c)| {
+                match c {
+                    '<' => depth += 1,
+                    '>' => depth -= 1,
+                    _ => {}
+                }
+                (depth == 0).then_some(idx + 1)
+            })
+            .unwrap_or(rest.len());
+        &rest[end..]
+    } else {
+        rest
+    };
+    rest.trim()
+        .trim_end_matches(['{', '}'])
+        .split(" where ")
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_string()
+}
+
+fn strip_visibility(line: &str) -> &str {
+    if let Some(rest) = line.strip_prefix("pub(")
+        && let Some(end) = rest.find(')')
+    {
+        return rest[end + 1..].trim_start();
+    }
+    line.strip_prefix("pub ").unwrap_or(line)
+}
+
+/// The line without its `//` comment and with string literal contents blanked, so neither is
+/// mistaken for code.
+fn code_only(line: &str) -> String {
+    let mut code = String::with_capacity(line.len());
+    let mut in_string = false;
+    let mut prev = '\0';
+    for c in line.chars() {
+        match c {
+            '"' if prev != '\\' => {
+                in_string = !in_string;
+                code.push(c);
+            }
+            '/' if !in_string && prev == '/' => {
+                code.pop();
+                break;
+            }
+            _ if in_string => code.push(' '),
+            _ => code.push(c),
+        }
+        prev = if prev == '\\' && c == '\\' { '\0' } else { c };
+    }
+    code
+}
+
+fn identifier(text: &str) -> String {
+    text.split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn truncate(line: &str) -> String {
+    const MAX_DETAIL_CHARS: usize = 100;
+    if line.chars().count() <= MAX_DETAIL_CHARS {
+        return line.to_string();
+    }
+    let mut detail = line.chars().take(MAX_DETAIL_CHARS).collect::<String>();
+    detail.push('…');
+    detail
+}
@@ -0,0 +1,84 @@
+use std::{
+    sync::{Mutex, MutexGuard, PoisonError},
+    time::Instant,
+};
+
+use tracing::warn;
+
+use crate::{error::Result, ollama::RetryBudget};
+
+use super::types::{GenerationContext, PromptProfile};
+
+/// The run's failed requests and file timeouts against `OllamaConfig::retry_budget`, and the
+/// backoff they led to.
+#[derive(Default)]
+pub(crate) struct Backpressure {
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    /// Failures counted before the last cooldown; only those since count against the budget.
+    counted: usize,
+    /// Set once the budget ran out without a cooldown.
+    compact: bool,
+    paused_until: Option<Instant>,
+}
+
+impl Backpressure {
+    /// The profile a file request starts from instead of `start`: no larger than the compact
+    /// context once the budget ran out without a cooldown.
+    pub fn start_profile(&self, start: PromptProfile) -> PromptProfile {
+        if self.lock().compact {
+            start.max(PromptProfile::Compact)
+        } else {
+            start
+        }
+    }
+
+    /// Back off when the run's failures ran over its budget, and wait out a cooldown in
+    /// progress. Called before each file request.
+    pub async fn check(&self, ctx: &GenerationContext<'_>) -> Result<()> {
+        let RetryBudget {
+            max_failures,
+            cooldown,
+        } = ctx.config.ollama.retry_budget;
+        let Some(max_failures) = max_failures else {
+            return Ok(());
+        };
+        let failures = ctx.wrapper.request_log().failed_attempts() + ctx.skipped_files.len();
+        let paused_until = {
+            let mut state = self.lock();
+            if failures.saturating_sub(state.counted) >= max_failures {
+                match cooldown {
+                    Some(cooldown) => {
+                        warn!(
+                            failures,
+                            cooldown_secs = cooldown.as_secs(),
+                            "retry budget spent; pausing requests"
+                        );
+                        state.counted = failures;
+                        state.paused_until = Some(Instant::now() + cooldown);
+                    }
+                    None if !state.compact => {
+                        warn!(
+                            failures,
+                            "retry budget spent; the remaining files start from the compact context"
+                        );
+                        state.compact = true;
+                    }
+                    None => {}
+                }
+            }
+            state.paused_until
+        };
+        match paused_until.map(|until| until.saturating_duration_since(Instant::now())) {
+            Some(wait) if !wait.is_zero() => ctx.wrapper.pause(wait).await,
+            _ => Ok(()),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
@@ -0,0 +1,274 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write as _,
+};
+
+use serde_json::{Value, json};
+use tracing::{info, warn};
+
+use crate::{
+    error::{PlainSightError, Result},
+    memory::{FileMemory, ProjectMemory},
+    ollama::Task,
+    project_manager::MetaCache,
+};
+
+use super::{
+    generate::{self, format_duration},
+    types::{GenerationContext, ParsedFile},
+};
+
+/// Files, and symbols per list, passed to the impact request; the page lists them all.
+const MAX_PROMPT_FILES: usize = 40;
+const MAX_PROMPT_SYMBOLS: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChangeStatus {
+    Added,
+    Modified,
+    Removed,
+}
+
+impl ChangeStatus {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Added => "added",
+            Self::Modified => "modified",
+            Self::Removed => "removed",
+        }
+    }
+}
+
+/// A source file whose content changed since the last run, and how its symbols did.
+struct FileChange<'a> {
+    path: &'a str,
+    status: ChangeStatus,
+    added: Vec<String>,
+    removed: Vec<String>,
+    /// Symbols kept under the same name whose body changed.
+    changed: Vec<String>,
+    summary: Option<String>,
+}
+
+/// Write `changes.md` for the files whose content changed since the last run: the files
+/// added, modified and `removed_files`, the symbols each gained, lost or changed according to
+/// `memory_before` and the body fingerprints in `meta`, and a short impact paragraph from the
+/// model. `meta` still holds the last run's entries. Nothing is written when no file changed
+/// or on a project's first run; a failed impact request leaves the paragraph out.
+pub(super) async fn write_changes(
+    ctx: &GenerationContext<'_>,
+    meta: &MetaCache,
+    memory_before: Option<&ProjectMemory>,
+    removed_files: &[String],
+) -> Result<()> {
+    let GenerationContext {
+        wrapper,
+        project,
+        project_name,
+        parsed_files,
+        stale_left_out,
+        ..
+    } = *ctx;
+    if meta.files.is_empty() && removed_files.is_empty() {
+        return Ok(());
+    }
+    let memory_before = memory_before
+        .map(|memory| {
+            memory
+                .files
+                .iter()
+                .map(|file| (file.path.as_str(), file))
+                .collect::<BTreeMap<_, _>>()
+        })
+        .unwrap_or_default();
+
+    let mut changes = Vec::new();
+    for parsed in parsed_files {
+        if stale_left_out.contains(&parsed.relative_path) {
+            continue;
+        }
+        let status = match meta.files.get(&parsed.relative_path) {
+            None => ChangeStatus::Added,
+            Some(previous) if previous.hash != parsed.hash => ChangeStatus::Modified,
+            Some(_) => continue,
+        };
+        let before = memory_before.get(parsed.relative_path.as_str()).copied();
+        let (added, removed) = symbol_diff(before, Some(&parsed.memory));
+        let summary = project
+            .file_summary_path(&parsed.path)
+            .ok()
+            .and_then(|path| project.store().read_to_string(&path).ok())
+            .filter(|summary| !summary.trim().is_empty());
+        changes.push(FileChange {
+            path: &parsed.relative_path,
+            status,
+            added,
+            removed,
+            changed: changed_symbols(meta, parsed),
+            summary,
+        });
+    }
+    for path in removed_files {
+        let (_, removed) = symbol_diff(memory_before.get(path.as_str()).copied(), None);
+        changes.push(FileChange {
+            path,
+            status: ChangeStatus::Removed,
+            added: Vec::new(),
+            removed,
+            changed: Vec::new(),
+            summary: None,
+        });
+    }
+    if changes.is_empty() {
+        return Ok(());
+    }
+    changes.sort_by(|a, b| a.path.cmp(b.path));
+
+    let impact = match wrapper
+        .change_summary(project_name, &prompt_changes(&changes))
+        .await
+    {
+        Ok(impact) => {
+            info!(
+                model_name = %impact.model,
+                elapsed = %format_duration(impact.total_duration),
+                eval_count = impact.eval_count,
+                "change summary generated"
+            );
+            Some(impact.text)
+        }
+        Err(e) => {
+            warn!(error = %e, "change summary failed; writing changes without it");
+            None
+        }
+    };
+    generate::unload_tasks(wrapper, &[Task::ChangeSummary]).await;
+
+    let page = render_changes(project_name, &changes, impact.as_deref());
+    let changes_path = project.changes_path();
+    project
+        .store()
+        .write(&changes_path, page.as_bytes())
+        .map_err(|e| {
+            PlainSightError::io(format!("writing changes '{}'", changes_path.display()), e)
+        })?;
+    info!(
+        changed_files = changes.len(),
+        changes_path = %changes_path.display(),
+        "changes_written"
+    );
+    Ok(())
+}
+
+/// Symbols only `after` has and symbols only `before` has, as `name (kind)`.
+fn symbol_diff(
+    before: Option<&FileMemory>,
+    after: Option<&FileMemory>,
+) -> (Vec<String>, Vec<String>) {
+    let symbols = |memory: Option<&FileMemory>| {
+        memory
+            .into_iter()
+            .flat_map(|memory| memory.symbols.iter())
+            .map(|symbol| format!("{} ({})", symbol.name, symbol.kind))
+            .collect::<BTreeSet<_>>()
+    };
+    let (before, after) = (symbols(before), symbols(after));
+    (
+        after.difference(&before).cloned().collect(),
+        before.difference(&after).cloned().collect(),
+    )
+}
+
+/// Symbols of `parsed` whose body fingerprint differs from the one recorded in `meta`.
+fn changed_symbols(meta: &MetaCache, parsed: &ParsedFile) -> Vec<String> {
+    let Some(previous) = meta.files.get(&parsed.relative_path) else {
+        return Vec::new();
+    };
+    let before = previous
+        .symbols
+        .iter()
+        .map(|symbol| ((symbol.name.as_str(), symbol.kind.as_str()), symbol))
+        .collect::<BTreeMap<_, _>>();
+    parsed
+        .fingerprints
+        .iter()
+        .filter(|symbol| {
+            before
+                .get(&(symbol.name.as_str(), symbol.kind.as_str()))
+                .is_some_and(|old| old.fingerprint != symbol.fingerprint)
+        })
+        .map(|symbol| format!("{} ({})", symbol.name, symbol.kind))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+fn capped(symbols: &[String]) -> &[String] {
+    &symbols[..symbols.len().min(MAX_PROMPT_SYMBOLS)]
+}
+
+fn prompt_changes(changes: &[FileChange<'_>]) -> Value {
+    let files = changes
+        .iter()
+        .take(MAX_PROMPT_FILES)
+        .map(|change| {
+            json!({
+                "path": change.path,
+                "status": change.status.name(),
+                "symbols_added": capped(&change.added),
+                "symbols_removed": capped(&change.removed),
+                "symbols_changed": capped(&change.changed),
+                "summary": change.summary.as_deref().map(str::trim),
+            })
+        })
+        .collect::<Vec<_>>();
+    Value::Array(files)
+}
+
+fn render_changes(project_name: &str, changes: &[FileChange<'_>], impact: Option<&str>) -> String {
+    let count = |status| {
+        changes
+            .iter()
+            .filter(|change| change.status == status)
+            .count()
+    };
+    let mut page = format!("# {project_name} Changes\n\n");
+    let _ = writeln!(
+        page,
+        "{} files changed since the previous run: {} added, {} modified, {} removed.\n",
+        changes.len(),
+        count(ChangeStatus::Added),
+        count(ChangeStatus::Modified),
+        count(ChangeStatus::Removed)
+    );
+    if let Some(impact) = impact {
+        page.push_str(impact.trim());
+        page.push_str("\n\n");
+    }
+    page.push_str("## Files\n");
+    for change in changes {
+        let _ = writeln!(page, "\n### `{}` ({})\n", change.path, change.status.name());
+        let lists = [
+            ("Added", &change.added),
+            ("Removed", &change.removed),
+            ("Changed", &change.changed),
+        ];
+        let mut listed = false;
+        for (label, symbols) in lists {
+            if symbols.is_empty() {
+                continue;
+            }
+            let symbols = symbols
+                .iter()
+                .map(|symbol| format!("`{symbol}`"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let _ = writeln!(page, "- {label}: {symbols}");
+            listed = true;
+        }
+        if !listed {
+            page.push_str("- No symbol changes.\n");
+        }
+    }
+    page
+}
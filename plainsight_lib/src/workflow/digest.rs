@@ -0,0 +1,247 @@
+use std::collections::BTreeSet;
+
+use tracing::{info, warn};
+
+use crate::{
+    config::{DigestConfig, DigestFormat},
+    error::{PlainSightError, Result},
+    page_split,
+    project_manager::{ProjectContext, RunSnapshot},
+};
+
+use super::{trends, types::ParsedFile};
+
+/// Room kept for the "…and N more" line that closes a truncated list.
+const MORE_LINE_RESERVE: usize = 32;
+const MAX_ARCHITECTURE_HEADINGS: usize = 8;
+
+/// What a run changed, as far as the digest reports it.
+pub(crate) struct RunChanges<'a> {
+    pub parsed_files: &'a [ParsedFile],
+    /// Files recorded in `.meta.json` before this run.
+    pub previous_files: &'a BTreeSet<String>,
+    pub regenerated: &'a BTreeSet<String>,
+    /// `architecture.md` as it was before the docs phase.
+    pub architecture_before: Option<&'a str>,
+}
+
+/// Render the digest for the run that just finished, write it to `digest.txt` and post it to
+/// `config.webhook_url` when set. A failed post is logged and does not fail the run.
+pub(crate) async fn publish_digest(
+    project: &ProjectContext,
+    project_name: &str,
+    config: &DigestConfig,
+    changes: &RunChanges<'_>,
+) -> Result<()> {
+    let mut new_files = Vec::new();
+    let mut updated_files = Vec::new();
+    for parsed in changes.parsed_files {
+        if !changes.regenerated.contains(&parsed.relative_path)
+            || !trends::has_content(project, &project.file_docs_path(&parsed.path)?)
+        {
+            continue;
+        }
+        if changes.previous_files.contains(&parsed.relative_path) {
+            updated_files.push(parsed.relative_path.as_str());
+        } else {
+            new_files.push(parsed.relative_path.as_str());
+        }
+    }
+
+    let architecture_after =
+        page_split::read_page(project.store(), &project.architecture_path()).ok();
+    let (added_headings, removed_headings) = heading_changes(
+        changes.architecture_before.unwrap_or_default(),
+        architecture_after.as_deref().unwrap_or_default(),
+    );
+
+    let timeline = project.load_timeline()?;
+    let latest = timeline.runs.last();
+    let previous = timeline
+        .runs
+        .len()
+        .checked_sub(2)
+        .and_then(|idx| timeline.runs.get(idx));
+
+    let digest = render_digest(
+        config,
+        &DigestContent {
+            project_name,
+            latest,
+            previous,
+            new_files: &new_files,
+            updated_files: &updated_files,
+            added_headings: &added_headings,
+            removed_headings: &removed_headings,
+        },
+    );
+
+    let digest_path = project.digest_path();
+    project
+        .store()
+        .write(&digest_path, digest.as_bytes())
+        .map_err(|e| {
+            PlainSightError::io(format!("writing digest '{}'", digest_path.display()), e)
+        })?;
+    info!(
+        new_files = new_files.len(),
+        updated_files = updated_files.len(),
+        digest_chars = digest.chars().count(),
+        digest_path = %digest_path.display(),
+        "digest_written"
+    );
+
+    if let Some(url) = &config.webhook_url {
+        match post_to_webhook(url, &digest).await {
+            Ok(()) => info!("digest_posted"),
+            Err(err) => warn!(error = %err, "failed posting digest to webhook; continuing"),
+        }
+    }
+    Ok(())
+}
+
+struct DigestContent<'a> {
+    project_name: &'a str,
+    latest: Option<&'a RunSnapshot>,
+    previous: Option<&'a RunSnapshot>,
+    new_files: &'a [&'a str],
+    updated_files: &'a [&'a str],
+    added_headings: &'a [String],
+    removed_headings: &'a [String],
+}
+
+fn render_digest(config: &DigestConfig, content: &DigestContent<'_>) -> String {
+    let bold = |text: &str| match config.format {
+        DigestFormat::Slack => format!("*{text}*"),
+        DigestFormat::Teams => format!("**{text}**"),
+    };
+    // Teams collapses single newlines in webhook text.
+    let separator = match config.format {
+        DigestFormat::Slack => "\n",
+        DigestFormat::Teams => "\n\n",
+    };
+
+    let mut lines = vec![bold(&format!("{} documentation updated", content.project_name))];
+    if let Some(latest) = content.latest {
+        let delta = match content.previous {
+            Some(previous) => format!(
+                " ({:+.1} pts)",
+                (latest.coverage() - previous.coverage()) * 100.0
+            ),
+            None => String::new(),
+        };
+        lines.push(format!(
+            "Coverage: {:.1}%{delta} · {} of {} files documented",
+            latest.coverage() * 100.0,
+            latest.documented_file_count,
+            latest.file_count
+        ));
+    }
+    if content.new_files.is_empty()
+        && content.updated_files.is_empty()
+        && content.added_headings.is_empty()
+        && content.removed_headings.is_empty()
+    {
+        lines.push("No documentation changes in this run.".to_string());
+        return lines.join(separator);
+    }
+
+    let mut sections: Vec<(String, Vec<String>)> = Vec::new();
+    if !content.new_files.is_empty() {
+        sections.push((
+            format!("Newly documented ({})", content.new_files.len()),
+            content.new_files.iter().map(|path| format!("`{path}`")).collect(),
+        ));
+    }
+    if !content.updated_files.is_empty() {
+        sections.push((
+            format!("Updated ({})", content.updated_files.len()),
+            content
+                .updated_files
+                .iter()
+                .map(|path| format!("`{path}`"))
+                .collect(),
+        ));
+    }
+    let architecture = content
+        .added_headings
+        .iter()
+        .map(|heading| format!("added: {heading}"))
+        .chain(
+            content
+                .removed_headings
+                .iter()
+                .map(|heading| format!("removed: {heading}")),
+        )
+        .take(MAX_ARCHITECTURE_HEADINGS)
+        .collect::<Vec<_>>();
+    if !architecture.is_empty() {
+        sections.push(("Architecture changes".to_string(), architecture));
+    }
+
+    let mut used = lines
+        .iter()
+        .map(|line| line.chars().count() + separator.len())
+        .sum::<usize>();
+    for (title, items) in sections {
+        let heading = bold(&title);
+        if used + heading.chars().count() + MORE_LINE_RESERVE > config.max_chars {
+            break;
+        }
+        used += heading.chars().count() + separator.len();
+        lines.push(heading);
+
+        for (idx, item) in items.iter().enumerate() {
+            let line = format!("• {item}");
+            let cost = line.chars().count() + separator.len();
+            if used + cost + MORE_LINE_RESERVE > config.max_chars {
+                let more = format!("…and {} more", items.len() - idx);
+                used += more.chars().count() + separator.len();
+                lines.push(more);
+                break;
+            }
+            used += cost;
+            lines.push(line);
+        }
+    }
+
+    lines.join(separator)
+}
+
+/// Second-level and third-level headings present only in `after`, and only in `before`.
+fn heading_changes(before: &str, after: &str) -> (Vec<String>, Vec<String>) {
+    let headings = |doc: &str| -> BTreeSet<String> {
+        doc.lines()
+            .filter_map(|line| {
+                line.strip_prefix("### ")
+                    .or_else(|| line.strip_prefix("## "))
+            })
+            .map(|heading| heading.trim().to_string())
+            .filter(|heading| !heading.is_empty())
+            .collect()
+    };
+    if before.trim().is_empty() {
+        return (Vec::new(), Vec::new());
+    }
+    let (before, after) = (headings(before), headings(after));
+    (
+        after.difference(&before).cloned().collect(),
+        before.difference(&after).cloned().collect(),
+    )
+}
+
+/// Slack and Teams incoming webhooks both accept a JSON body with a `text` field.
+async fn post_to_webhook(url: &str, text: &str) -> std::result::Result<(), String> {
+    let response = reqwest::Client::new()
+        .post(url)
+        .json(&serde_json::json!({ "text": text }))
+        .send()
+        .await
+        .map_err(|e| format!("request failed: {e}"))?;
+    let status = response.status();
+    if !status.is_success() {
+        let detail = response.text().await.unwrap_or_default();
+        return Err(format!("webhook returned {status}: {}", detail.trim()));
+    }
+    Ok(())
+}
@@ -0,0 +1,226 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use tracing::info;
+
+use crate::{
+    error::{PlainSightError, Result},
+    freshness,
+    project_manager::ProjectContext,
+};
+
+use super::types::ParsedFile;
+
+/// One source file's pages, as the indexes link them.
+struct IndexedFile<'a> {
+    path: &'a str,
+    name: &'a str,
+    has_summary: bool,
+    has_docs: bool,
+    symbols: usize,
+    /// `generated_on` of the docs page's front-matter.
+    generated_on: Option<String>,
+}
+
+/// The subdirectories and files directly inside one source directory; `""` is the project
+/// root.
+#[derive(Default)]
+struct IndexedDir<'a> {
+    dirs: BTreeSet<&'a str>,
+    files: Vec<IndexedFile<'a>>,
+}
+
+/// Write `index.md`, a tree of links to every file's `summary.md` and `docs.md` with its
+/// symbol count and when its docs were generated, and an `_index.md` listing the directories
+/// and files inside each source directory.
+pub(super) fn write_doc_indexes(
+    project: &ProjectContext,
+    project_name: &str,
+    parsed_files: &[ParsedFile],
+) -> Result<()> {
+    let mut dirs: BTreeMap<&str, IndexedDir<'_>> = BTreeMap::new();
+    dirs.entry("").or_default();
+    for parsed in parsed_files {
+        let path = parsed.relative_path.as_str();
+        let (parent, name) = path.rsplit_once('/').unwrap_or(("", path));
+        let summary_path = project.file_summary_path(&parsed.path)?;
+        let docs_path = project.file_docs_path(&parsed.path)?;
+        let docs = project.store().read_to_string(&docs_path).ok();
+        dirs.entry(parent).or_default().files.push(IndexedFile {
+            path,
+            name,
+            has_summary: project.store().exists(&summary_path),
+            has_docs: docs.is_some(),
+            symbols: parsed
+                .memory
+                .symbols
+                .iter()
+                .filter(|symbol| !symbol.is_test)
+                .count(),
+            generated_on: docs
+                .as_deref()
+                .and_then(freshness::parse_front_matter)
+                .and_then(|front_matter| front_matter.generated_on),
+        });
+        let mut child = parent;
+        while !child.is_empty() {
+            let grandparent = child.rsplit_once('/').map_or("", |(dir, _)| dir);
+            dirs.entry(grandparent).or_default().dirs.insert(child);
+            child = grandparent;
+        }
+    }
+    for dir in dirs.values_mut() {
+        dir.files.sort_by_key(|file| file.path);
+    }
+
+    let index_path = project.index_path();
+    project
+        .store()
+        .write(
+            &index_path,
+            render_project_index(project, project_name, &dirs).as_bytes(),
+        )
+        .map_err(|e| PlainSightError::io(format!("writing index '{}'", index_path.display()), e))?;
+    for dir in dirs.keys().filter(|dir| !dir.is_empty()) {
+        let path = project.directory_index_path(dir);
+        if let Some(parent) = path.parent() {
+            project.store().create_dir_all(parent).map_err(|e| {
+                PlainSightError::io(format!("creating docs directory '{}'", parent.display()), e)
+            })?;
+        }
+        project
+            .store()
+            .write(
+                &path,
+                render_directory_index(project, dir, &dirs).as_bytes(),
+            )
+            .map_err(|e| PlainSightError::io(format!("writing index '{}'", path.display()), e))?;
+    }
+
+    info!(
+        files = parsed_files.len(),
+        directories = dirs.len() - 1,
+        index_path = %index_path.display(),
+        "doc_indexes_written"
+    );
+    Ok(())
+}
+
+fn render_project_index(
+    project: &ProjectContext,
+    project_name: &str,
+    dirs: &BTreeMap<&str, IndexedDir<'_>>,
+) -> String {
+    let file_count = dirs.values().map(|dir| dir.files.len()).sum::<usize>();
+    let mut out = format!("# {project_name} Docs\n\n");
+    out.push_str("- [Project summary](summary.md)\n");
+    out.push_str("- [Architecture](architecture.md)\n\n");
+    out.push_str(&format!("## Files\n\n{file_count} files.\n\n"));
+    render_tree(project, "", "files/", 0, dirs, &mut out);
+    out
+}
+
+/// List `dir`'s subdirectories, each followed by its own tree, then its files, indented by
+/// `depth`, with links relative to `prefix`.
+fn render_tree(
+    project: &ProjectContext,
+    dir: &str,
+    prefix: &str,
+    depth: usize,
+    dirs: &BTreeMap<&str, IndexedDir<'_>>,
+    out: &mut String,
+) {
+    let Some(entry) = dirs.get(dir) else {
+        return;
+    };
+    let indent = "  ".repeat(depth);
+    for child in &entry.dirs {
+        let name = child.rsplit_once('/').map_or(*child, |(_, name)| name);
+        out.push_str(&indent);
+        out.push_str(&directory_line(
+            project,
+            child,
+            name,
+            &format!("{prefix}{child}"),
+        ));
+        render_tree(project, child, prefix, depth + 1, dirs, out);
+    }
+    for file in &entry.files {
+        out.push_str(&indent);
+        out.push_str(&file_line(file, &format!("{prefix}{}", file.path)));
+    }
+}
+
+fn render_directory_index(
+    project: &ProjectContext,
+    dir: &str,
+    dirs: &BTreeMap<&str, IndexedDir<'_>>,
+) -> String {
+    let parent_index = if dir.contains('/') {
+        "../_index.md"
+    } else {
+        "../../index.md"
+    };
+    let mut out = format!("# {dir}/\n\n[Up]({parent_index})\n\n");
+    let Some(entry) = dirs.get(dir) else {
+        return out;
+    };
+    for child in &entry.dirs {
+        let name = child.rsplit_once('/').map_or(*child, |(_, name)| name);
+        out.push_str(&directory_line(project, child, name, name));
+    }
+    for file in &entry.files {
+        out.push_str(&file_line(file, file.name));
+    }
+    out
+}
+
+/// `- [label/](pages_dir/_index.md)`, with a link to the directory summary of `dir` when
+/// there is one.
+fn directory_line(project: &ProjectContext, dir: &str, label: &str, pages_dir: &str) -> String {
+    let mut line = format!("- [{label}/]({})", link(&format!("{pages_dir}/_index.md")));
+    if project.store().exists(&project.directory_summary_path(dir)) {
+        line.push_str(&format!(
+            " · [summary]({})",
+            link(&format!("{pages_dir}/summary.md"))
+        ));
+    }
+    line.push('\n');
+    line
+}
+
+/// `- name · [summary](…) · [docs](…) · N symbols · generated on YYYY-MM-DD`, linking the
+/// pages under `pages_dir`.
+fn file_line(file: &IndexedFile<'_>, pages_dir: &str) -> String {
+    let mut line = format!("- `{}`", file.name);
+    if file.has_summary {
+        line.push_str(&format!(
+            " · [summary]({})",
+            link(&format!("{pages_dir}/summary.md"))
+        ));
+    }
+    if file.has_docs {
+        line.push_str(&format!(
+            " · [docs]({})",
+            link(&format!("{pages_dir}/docs.md"))
+        ));
+    }
+    if !file.has_summary && !file.has_docs {
+        line.push_str(" · not generated");
+    }
+    let noun = if file.symbols == 1 {
+        "symbol"
+    } else {
+        "symbols"
+    };
+    line.push_str(&format!(" · {} {noun}", file.symbols));
+    if let Some(date) = &file.generated_on {
+        line.push_str(&format!(" · generated on {date}"));
+    }
+    line.push('\n');
+    line
+}
+
+/// `path` usable as a Markdown link destination.
+fn link(path: &str) -> String {
+    path.replace(' ', "%20")
+}
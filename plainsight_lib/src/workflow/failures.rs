@@ -0,0 +1,78 @@
+use crate::{
+    error::{PlainSightError, Result},
+    project_manager::SkipEntry,
+};
+
+use super::{quality::QualityReport, types::GenerationContext};
+
+/// Record a file whose summary or docs failed, and stop the run when `ErrorPolicy` allows no
+/// more failed files.
+pub(super) fn record_failure(ctx: &GenerationContext<'_>, entry: SkipEntry) -> Result<()> {
+    let policy = &ctx.config.error_policy;
+    let reason = format!("'{}': {}", entry.path, entry.reason);
+    let failed = ctx.failed_files.insert(entry);
+    if policy.fail_fast {
+        return Err(too_many_failures(
+            ctx,
+            format!("fail_fast stops at the first failed file, {reason}"),
+        ));
+    }
+    match policy.max_file_failures {
+        Some(max) if failed > max => Err(too_many_failures(
+            ctx,
+            format!("more than {max} files failed, the last {reason}"),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Fail a finished run when fewer of the files it regenerated succeeded than
+/// `ErrorPolicy::min_success_ratio` requires.
+pub(super) fn check_success_ratio(ctx: &GenerationContext<'_>) -> Result<()> {
+    let Some(min) = ctx.config.error_policy.min_success_ratio else {
+        return Ok(());
+    };
+    let attempted = ctx.files_to_regenerate.len();
+    if attempted == 0 {
+        return Ok(());
+    }
+    let succeeded = attempted.saturating_sub(ctx.failed_files.len());
+    if (succeeded as f64 / attempted as f64) < min {
+        return Err(too_many_failures(
+            ctx,
+            format!(
+                "{succeeded} of {attempted} regenerated files succeeded, below the minimum success ratio of {min}"
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Fail a finished run whose regenerated docs scored below `ErrorPolicy::min_quality` on
+/// average; the error lists the files that scored below it.
+pub(super) fn check_quality(ctx: &GenerationContext<'_>, quality: &QualityReport) -> Result<()> {
+    let (Some(min), Some(average)) = (ctx.config.error_policy.min_quality, quality.average) else {
+        return Ok(());
+    };
+    if average >= min {
+        return Ok(());
+    }
+    Err(PlainSightError::TooManyFailures {
+        failed_files: quality
+            .files
+            .iter()
+            .filter(|file| file.score < min)
+            .map(|file| file.path.clone())
+            .collect(),
+        reason: format!(
+            "the regenerated docs scored {average} on average, below the minimum quality of {min}"
+        ),
+    })
+}
+
+fn too_many_failures(ctx: &GenerationContext<'_>, reason: String) -> PlainSightError {
+    PlainSightError::TooManyFailures {
+        failed_files: ctx.failed_files.paths(),
+        reason,
+    }
+}
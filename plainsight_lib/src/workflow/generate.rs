@@ -1,201 +1,310 @@
 use std::{
-    collections::BTreeSet,
+    collections::{BTreeMap, BTreeSet},
     fs,
-    path::Path,
+    path::{Path, PathBuf},
     time::{Duration, Instant},
 };
 
+use futures::stream::{FuturesUnordered, StreamExt};
+use tokio::sync::{Semaphore, SemaphorePermit};
 use tracing::{debug, info, warn};
 
 use crate::{
+    benchmarks::{self, PerformanceNote},
+    config::TestCodeMode,
     error::{PlainSightError, Result as PlainResult},
+    freshness::{self, PageFreshness},
     memory::{self, ProjectMemory},
-    ollama::{self, OllamaWrapper, Task},
-    project_manager::ProjectContext,
+    ollama::{OllamaError, OllamaWrapper, Task, with_target},
+    openapi, ownership, page_split,
+    project_manager::{ProjectContext, SkipEntry},
+    prompt_context::{
+        self, BenchmarkContext, BenchmarkTiming, PROMPT_CONTEXT_VERSION, PromptContext,
+    },
 };
 
-use super::types::{ParsedFile, PromptProfile};
+use super::{
+    failures,
+    hooks::{self, PipelineStage, StageContext},
+    progress::ProgressEvent,
+    questions::{self, FileQuestions},
+    renames, review, rollup,
+    types::{FilePart, FileRequests, GenerationContext, MetadataFile, ParsedFile, PromptProfile},
+};
 
-pub(crate) async fn generate_summaries(
-    wrapper: &OllamaWrapper,
-    manager: &ProjectContext,
-    project_name: &str,
-    parsed_files: &[ParsedFile],
-    project_memory: &ProjectMemory,
-    memory_file_path: &Path,
-    source_index_file_path: &Path,
-    files_to_regenerate: &BTreeSet<String>,
-) -> PlainResult<()> {
-    info!(file_count = parsed_files.len(), "summary_phase_start");
-    let mut file_summaries: Vec<(String, String)> = Vec::with_capacity(parsed_files.len());
-    let mut summary_reused = 0usize;
-    let mut summary_generated = 0usize;
-    let mut summary_skipped = 0usize;
+const HOT_SPOT_COMPLEXITY: usize = 10;
+const HOT_SPOT_LINES: usize = 60;
+const HOT_SPOT_PARAMETERS: usize = 6;
+const MAX_HOT_SPOTS: usize = 5;
+const MAX_DATA_MODEL_TABLES: usize = 60;
 
-    for parsed in parsed_files {
-        if !files_to_regenerate.contains(&parsed.relative_path) {
-            let summary_path = manager.file_summary_path(&parsed.path)?;
-            if let Ok(existing_summary) = fs::read_to_string(&summary_path) {
-                if !existing_summary.trim().is_empty() {
-                    file_summaries.push((parsed.relative_path.clone(), existing_summary));
-                    summary_reused += 1;
-                    debug!(
-                        target_file = %parsed.relative_path,
-                        summary_path = %summary_path.display(),
-                        "reuse_file_summary"
-                    );
-                    continue;
+/// What a summary or docs pass did with one file.
+enum FileOutcome<T> {
+    Reused(T),
+    Generated(T),
+    Skipped,
+}
+
+/// A file's docs pass result: its open questions, and its docs as drafted while their review
+/// waits for every file's docs to be written.
+#[derive(Default)]
+struct FileDocs {
+    questions: Vec<String>,
+    unreviewed: Option<String>,
+}
+
+/// Summarize every file and write the project summary, unless `ModelSchedule` defers it.
+/// Returns each summarized file's summary by path.
+pub(crate) async fn generate_summaries(
+    ctx: &GenerationContext<'_>,
+) -> PlainResult<Vec<(String, String)>> {
+    let GenerationContext {
+        wrapper,
+        parsed_files,
+        ..
+    } = *ctx;
+    hooks::before(ctx.hooks, PipelineStage::Summaries, &StageContext::of(ctx)).await?;
+    let max_concurrency = wrapper.max_concurrency_for(Task::Summarize);
+    info!(
+        file_count = parsed_files.len(),
+        max_concurrency, "summary_phase_start"
+    );
+    let batched = summarize_small_files(ctx).await?;
+    let order = ctx.generation_order.iter().copied();
+    let outcomes = run_ordered(parsed_files, order, max_concurrency, |parsed| {
+        let batched = batched.get(&parsed.relative_path).cloned();
+        async move {
+            match batched {
+                Some(summary) => {
+                    ctx.progress.event(ProgressEvent::FileFinished {
+                        path: &parsed.relative_path,
+                        task: Task::Summarize,
+                        bytes: summary.len(),
+                    });
+                    Ok(FileOutcome::Generated(summary))
+                }
+                None => {
+                    within_file_budget(ctx, parsed, Task::Summarize, summarize_file(ctx, parsed))
+                        .await
                 }
             }
         }
+    })
+    .await?;
+    finish_summaries(ctx, outcomes).await
+}
 
-        debug!(
-            target_file = %parsed.relative_path,
-            model_name = wrapper.model_name(Task::Summarize),
-            "generate_file_summary"
-        );
-
-        debug_current_memory(memory_file_path, &parsed.relative_path);
-
-        let input = build_file_prompt_input(
-            parsed,
-            project_memory,
-            PromptProfile::Standard,
-            memory_file_path,
-            source_index_file_path,
-        )?;
-        debug!(
-            target_file = %parsed.relative_path,
-            profile = "standard",
-            payload_bytes = input.len(),
-            "file_summary_payload"
-        );
+/// Document every file and write the architecture docs, and the project summary from
+/// `deferred_summaries` when `ModelSchedule` deferred it.
+pub(crate) async fn generate_docs(
+    ctx: &GenerationContext<'_>,
+    project_index: &str,
+    deferred_summaries: Option<&[(String, String)]>,
+) -> PlainResult<FileQuestions> {
+    let GenerationContext {
+        wrapper,
+        parsed_files,
+        ..
+    } = *ctx;
+    hooks::before(ctx.hooks, PipelineStage::Docs, &StageContext::of(ctx)).await?;
+    let max_concurrency = wrapper.max_concurrency_for(Task::Documentation);
+    info!(
+        file_count = parsed_files.len(),
+        max_concurrency, "documentation_phase_start"
+    );
+    let order = ctx.generation_order.iter().copied();
+    let outcomes = run_ordered(parsed_files, order, max_concurrency, |parsed| {
+        within_file_budget(ctx, parsed, Task::Documentation, document_file(ctx, parsed))
+    })
+    .await?;
+    finish_docs(ctx, project_index, outcomes, deferred_summaries).await
+}
 
-        let start = Instant::now();
-        let mut used_compact = false;
-        let mut summary = match wrapper.summarize(&input).await {
-            Ok(summary) => summary,
-            Err(err) if should_retry_compact_ollama_error(&err) => {
-                warn!(
-                    target_file = %parsed.relative_path,
-                    error = %err,
-                    "summary request failed with transient Ollama error; retrying with compact context"
-                );
-                used_compact = true;
-                let fallback = build_file_prompt_input(
-                    parsed,
-                    project_memory,
-                    PromptProfile::Compact,
-                    memory_file_path,
-                    source_index_file_path,
-                )?;
-                debug!(
-                    target_file = %parsed.relative_path,
-                    profile = "compact",
-                    payload_bytes = fallback.len(),
-                    "file_summary_payload"
-                );
-                wrapper.summarize(&fallback).await.or_else(|fallback_err| {
-                    if should_retry_compact_ollama_error(&fallback_err) {
-                        warn!(
-                            target_file = %parsed.relative_path,
-                            error = %fallback_err,
-                            "summary compact retry also failed with transient Ollama error; skipping file"
-                        );
-                        Ok(String::new())
-                    } else {
-                        Err(fallback_err)
-                    }
-                })?
+/// Review the docs the docs pass left unreviewed, unloading the models of `loaded_tasks` first,
+/// and write them again with the reviewed text. Returns whether any were reviewed.
+async fn review_deferred(
+    ctx: &GenerationContext<'_>,
+    outcomes: &mut [FileOutcome<FileDocs>],
+    loaded_tasks: &[Task],
+) -> PlainResult<bool> {
+    let pending = ctx
+        .parsed_files
+        .iter()
+        .zip(outcomes.iter_mut())
+        .enumerate()
+        .filter_map(|(index, (parsed, outcome))| match outcome {
+            FileOutcome::Generated(docs) => {
+                docs.unreviewed.take().map(|draft| (index, parsed, draft))
             }
-            Err(err) => return Err(err),
-        };
+            FileOutcome::Reused(_) | FileOutcome::Skipped => None,
+        })
+        .collect::<Vec<_>>();
+    if pending.is_empty() {
+        return Ok(false);
+    }
 
-        if summary.is_empty() {
-            summary_skipped += 1;
-            continue;
+    unload_before(ctx.wrapper, Task::Review, loaded_tasks).await;
+    let max_concurrency = ctx.wrapper.max_concurrency_for(Task::Review);
+    info!(
+        file_count = pending.len(),
+        max_concurrency, "deferred_review_phase_start"
+    );
+    let reviewed = run_bounded(&pending, max_concurrency, |(_, parsed, draft)| async move {
+        let docs = review::review_docs(ctx, parsed, draft.clone()).await?;
+        let (questions, _) = write_file_docs(ctx, parsed, &docs)?;
+        ctx.checkpoint
+            .docs_written(ctx.project, parsed, &questions)?;
+        Ok(questions)
+    })
+    .await?;
+    for ((index, _, _), questions) in pending.iter().zip(reviewed) {
+        if let FileOutcome::Generated(docs) = &mut outcomes[*index] {
+            docs.questions = questions;
         }
+    }
+    Ok(true)
+}
 
-        if !used_compact && ollama::is_refusal_output(&summary) {
-            warn!(
-                target_file = %parsed.relative_path,
-                "summary refusal detected; retrying with compact context"
-            );
-            let fallback = build_file_prompt_input(
-                parsed,
-                project_memory,
-                PromptProfile::Compact,
-                memory_file_path,
-                source_index_file_path,
-            )?;
-            debug!(
-                target_file = %parsed.relative_path,
-                profile = "compact",
-                payload_bytes = fallback.len(),
-                "file_summary_payload"
-            );
-            summary = wrapper.summarize(&fallback).await.or_else(|fallback_err| {
-                if should_retry_compact_ollama_error(&fallback_err) {
-                    warn!(
-                        target_file = %parsed.relative_path,
-                        error = %fallback_err,
-                        "summary refusal fallback failed with transient Ollama error; skipping file"
-                    );
-                    Ok(String::new())
-                } else {
-                    Err(fallback_err)
-                }
-            })?;
-            if summary.is_empty() {
-                summary_skipped += 1;
-                continue;
+/// Summaries and docs in one pass over the files: each file's docs request goes out as soon
+/// as its summary is done, while other files are still being summarized. Each task keeps to
+/// its own `max_concurrency_for` limit. The project summary and architecture follow once
+/// every file is through.
+pub(crate) async fn generate_pipelined(
+    ctx: &GenerationContext<'_>,
+    project_index: &str,
+) -> PlainResult<FileQuestions> {
+    let GenerationContext {
+        wrapper,
+        parsed_files,
+        ..
+    } = *ctx;
+    let stage = StageContext::of(ctx);
+    hooks::before(ctx.hooks, PipelineStage::Summaries, &stage).await?;
+    hooks::before(ctx.hooks, PipelineStage::Docs, &stage).await?;
+    let summary_concurrency = wrapper.max_concurrency_for(Task::Summarize);
+    let docs_concurrency = wrapper.max_concurrency_for(Task::Documentation);
+    info!(
+        file_count = parsed_files.len(),
+        summary_concurrency, docs_concurrency, "pipelined_phase_start"
+    );
+    let summary_slots = Semaphore::new(summary_concurrency);
+    let docs_slots = Semaphore::new(docs_concurrency);
+    let outcomes = run_ordered(
+        parsed_files,
+        ctx.generation_order.iter().copied(),
+        summary_concurrency + docs_concurrency,
+        |parsed| {
+            let (summary_slots, docs_slots) = (&summary_slots, &docs_slots);
+            async move {
+                let summary = {
+                    let _slot = acquire_slot(summary_slots).await?;
+                    within_file_budget(ctx, parsed, Task::Summarize, summarize_file(ctx, parsed))
+                        .await?
+                };
+                let _slot = acquire_slot(docs_slots).await?;
+                let docs = within_file_budget(
+                    ctx,
+                    parsed,
+                    Task::Documentation,
+                    document_file(ctx, parsed),
+                )
+                .await?;
+                Ok((summary, docs))
             }
-        }
-
-        if ollama::is_refusal_output(&summary) {
-            warn!(
-                target_file = %parsed.relative_path,
-                "summary refusal persisted; skipping file"
-            );
-            summary_skipped += 1;
-            continue;
-        }
-
-        let elapsed = format_duration(start.elapsed());
-        let summary_path = manager.file_summary_path(&parsed.path)?;
-        fs::write(&summary_path, &summary).map_err(|e| {
-            PlainSightError::io(
-                format!("writing summary output '{}'", summary_path.display()),
-                e,
-            )
-        })?;
+        },
+    )
+    .await?;
+    let (summaries, docs): (Vec<_>, Vec<_>) = outcomes.into_iter().unzip();
+    finish_summaries(ctx, summaries).await?;
+    finish_docs(ctx, project_index, docs, None).await
+}
 
-        // Keep memory snapshot fresh for each generated artifact.
-        sync_memory_snapshot(memory_file_path, project_memory, "after_file_summary")?;
+async fn acquire_slot(slots: &Semaphore) -> PlainResult<SemaphorePermit<'_>> {
+    slots
+        .acquire()
+        .await
+        .map_err(|e| PlainSightError::InvalidState(format!("request slots closed: {e}")))
+}
 
-        file_summaries.push((parsed.relative_path.clone(), summary.clone()));
-        summary_generated += 1;
+/// Count the summary pass's outcomes and write the project summary from the file summaries,
+/// unless `ModelSchedule` defers it. Returns the file summaries.
+async fn finish_summaries(
+    ctx: &GenerationContext<'_>,
+    outcomes: Vec<FileOutcome<String>>,
+) -> PlainResult<Vec<(String, String)>> {
+    let GenerationContext {
+        wrapper,
+        parsed_files,
+        regenerate_project_docs,
+        ..
+    } = *ctx;
+    let mut file_summaries: Vec<(String, String)> = Vec::with_capacity(parsed_files.len());
+    let mut summary_reused = 0usize;
+    let mut summary_generated = 0usize;
+    let mut summary_skipped = 0usize;
 
-        debug!(
-            target_file = %parsed.relative_path,
-            model_name = wrapper.model_name(Task::Summarize),
-            elapsed = %elapsed,
-            summary_len = summary.len(),
-            summary_path = %summary_path.display(),
-            "file summary generated"
-        );
+    for (parsed, outcome) in parsed_files.iter().zip(outcomes) {
+        match outcome {
+            FileOutcome::Reused(summary) => {
+                file_summaries.push((parsed.relative_path.clone(), summary));
+                summary_reused += 1;
+            }
+            FileOutcome::Generated(summary) => {
+                file_summaries.push((parsed.relative_path.clone(), summary));
+                summary_generated += 1;
+            }
+            FileOutcome::Skipped => summary_skipped += 1,
+        }
     }
 
-    if files_to_regenerate.is_empty() {
+    if !regenerate_project_docs {
         info!("project_summary_unchanged_skip");
-        info!(
-            reused = summary_reused,
-            generated = summary_generated,
-            skipped = summary_skipped,
-            "summary_phase_complete"
-        );
-        return Ok(());
+    } else if ctx.schedule.defer_project_summary {
+        info!("project summary deferred until the file docs are done");
+    } else {
+        let file_tasks = if ctx.schedule.pipeline_file_tasks {
+            super::with_review(wrapper, &[Task::Summarize, Task::Documentation])
+        } else {
+            vec![Task::Summarize]
+        };
+        write_project_summary(ctx, &file_summaries, &file_tasks).await?;
     }
+    info!(
+        reused = summary_reused,
+        generated = summary_generated,
+        skipped = summary_skipped,
+        "summary_phase_complete"
+    );
+    hooks::after(ctx.hooks, PipelineStage::Summaries, &StageContext::of(ctx)).await?;
+    Ok(file_summaries)
+}
+
+/// Write the project summary from `file_summaries`, unloading the models of `loaded_tasks`
+/// that it does not use first.
+async fn write_project_summary(
+    ctx: &GenerationContext<'_>,
+    file_summaries: &[(String, String)],
+    loaded_tasks: &[Task],
+) -> PlainResult<()> {
+    let GenerationContext {
+        wrapper,
+        project: manager,
+        project_name,
+        parsed_files,
+        project_memory,
+        memory_file_path,
+        project_metadata,
+        ..
+    } = *ctx;
+    let directory_summaries = if ctx.config.directory_summaries {
+        let summaries =
+            rollup::write_directory_summaries(ctx, file_summaries, loaded_tasks).await?;
+        unload_before(wrapper, Task::ProjectSummary, &[Task::DirectorySummary]).await;
+        summaries
+    } else {
+        unload_before(wrapper, Task::ProjectSummary, loaded_tasks).await;
+        BTreeMap::new()
+    };
 
     info!(
         model_name = wrapper.model_name(Task::ProjectSummary),
@@ -203,15 +312,24 @@ pub(crate) async fn generate_summaries(
         "generate_project_summary"
     );
 
-    let start = Instant::now();
-    let summary_context = build_project_summary_context(&file_summaries);
+    let summary_context = build_project_summary_context(
+        file_summaries,
+        &directory_summaries,
+        parsed_files,
+        project_metadata,
+    );
     let project_summary = wrapper
         .project_summary(project_name, &summary_context)
         .await?;
-    let elapsed = format_duration(start.elapsed());
 
     let project_summary_path = manager.summary_path();
-    fs::write(&project_summary_path, &project_summary).map_err(|e| {
+    page_split::write_page(
+        manager.store(),
+        &project_summary_path,
+        &project_summary.text,
+        &ctx.config.page_split,
+    )
+    .map_err(|e| {
         PlainSightError::io(
             format!(
                 "writing project summary output '{}'",
@@ -220,228 +338,910 @@ pub(crate) async fn generate_summaries(
             e,
         )
     })?;
-    sync_memory_snapshot(memory_file_path, project_memory, "after_project_summary")?;
+    sync_memory_snapshot(
+        manager,
+        memory_file_path,
+        project_memory,
+        "after_project_summary",
+    )?;
 
     info!(
-        model_name = wrapper.model_name(Task::ProjectSummary),
-        elapsed = %elapsed,
-        summary_len = project_summary.len(),
+        model_name = %project_summary.model,
+        elapsed = %format_duration(project_summary.total_duration),
+        prompt_eval_count = project_summary.prompt_eval_count,
+        eval_count = project_summary.eval_count,
+        summary_len = project_summary.text.len(),
         summary_path = %project_summary_path.display(),
         "project summary generated"
     );
-    info!(
-        reused = summary_reused,
-        generated = summary_generated,
-        skipped = summary_skipped,
-        "summary_phase_complete"
-    );
-
     Ok(())
 }
 
-pub(crate) async fn generate_docs(
-    wrapper: &OllamaWrapper,
-    manager: &ProjectContext,
-    project_name: &str,
-    parsed_files: &[ParsedFile],
-    project_memory: &ProjectMemory,
-    memory_file_path: &Path,
-    source_index_file_path: &Path,
+/// Run the reviews deferred by `ModelSchedule`, count the docs pass's outcomes, collect open
+/// questions and write the architecture docs, and the project summary from
+/// `deferred_summaries`, in the order that loads each model once.
+async fn finish_docs(
+    ctx: &GenerationContext<'_>,
     project_index: &str,
-    files_to_regenerate: &BTreeSet<String>,
-) -> PlainResult<()> {
-    info!(file_count = parsed_files.len(), "documentation_phase_start");
+    mut outcomes: Vec<FileOutcome<FileDocs>>,
+    deferred_summaries: Option<&[(String, String)]>,
+) -> PlainResult<FileQuestions> {
+    let GenerationContext {
+        wrapper,
+        parsed_files,
+        regenerate_project_docs,
+        ..
+    } = *ctx;
+    let mut loaded_tasks = super::with_review(wrapper, &[Task::Documentation]);
+    if ctx.schedule.pipeline_file_tasks {
+        loaded_tasks.push(Task::ProjectSummary);
+    }
+    // The architecture only needs the project index, so it can go ahead of the reviews.
+    let architecture_first = regenerate_project_docs && ctx.schedule.architecture_first;
+    if architecture_first {
+        write_architecture(ctx, project_index, &loaded_tasks).await?;
+        loaded_tasks = vec![Task::Architecture];
+    }
+    if ctx.schedule.defer_reviews && review_deferred(ctx, &mut outcomes, &loaded_tasks).await? {
+        loaded_tasks = vec![Task::Review];
+    }
+    hooks::after(ctx.hooks, PipelineStage::Docs, &StageContext::of(ctx)).await?;
+
     let mut docs_reused = 0usize;
     let mut docs_generated = 0usize;
     let mut docs_skipped = 0usize;
+    let mut open_questions = FileQuestions::new();
 
-    for parsed in parsed_files {
-        if !files_to_regenerate.contains(&parsed.relative_path) {
-            docs_reused += 1;
-            debug!(target_file = %parsed.relative_path, "reuse_file_docs");
-            continue;
+    for (parsed, outcome) in parsed_files.iter().zip(outcomes) {
+        match outcome {
+            // Docs resumed from the checkpoint bring the questions split from them.
+            FileOutcome::Reused(FileDocs { questions, .. }) => {
+                if !questions.is_empty() {
+                    open_questions.insert(parsed.relative_path.clone(), questions);
+                }
+                docs_reused += 1;
+            }
+            FileOutcome::Generated(FileDocs { questions, .. }) => {
+                if !questions.is_empty() {
+                    open_questions.insert(parsed.relative_path.clone(), questions);
+                }
+                docs_generated += 1;
+            }
+            FileOutcome::Skipped => docs_skipped += 1,
         }
+    }
 
-        debug!(
-            target_file = %parsed.relative_path,
-            model_name = wrapper.model_name(Task::Documentation),
-            "generate_file_docs"
-        );
+    if !regenerate_project_docs {
+        info!("architecture_unchanged_skip");
+    } else {
+        if let Some(file_summaries) = deferred_summaries {
+            write_project_summary(ctx, file_summaries, &loaded_tasks).await?;
+            loaded_tasks = vec![Task::ProjectSummary];
+        }
+        if !architecture_first {
+            write_architecture(ctx, project_index, &loaded_tasks).await?;
+        }
+    }
+    info!(
+        reused = docs_reused,
+        generated = docs_generated,
+        skipped = docs_skipped,
+        "documentation_phase_complete"
+    );
 
-        debug_current_memory(memory_file_path, &parsed.relative_path);
+    Ok(open_questions)
+}
 
-        let input = build_file_prompt_input(
-            parsed,
-            project_memory,
-            PromptProfile::Standard,
-            memory_file_path,
-            source_index_file_path,
-        )?;
-        debug!(
-            target_file = %parsed.relative_path,
-            profile = "standard",
-            payload_bytes = input.len(),
-            "file_docs_payload"
-        );
+/// Write the architecture docs, unloading the models of `loaded_tasks` that they do not use
+/// first.
+async fn write_architecture(
+    ctx: &GenerationContext<'_>,
+    project_index: &str,
+    loaded_tasks: &[Task],
+) -> PlainResult<()> {
+    let GenerationContext {
+        wrapper,
+        project: manager,
+        project_name,
+        project_memory,
+        memory_file_path,
+        ..
+    } = *ctx;
+    unload_before(wrapper, Task::Architecture, loaded_tasks).await;
+    let stage = StageContext::of(ctx);
+    hooks::before(ctx.hooks, PipelineStage::Architecture, &stage).await?;
+
+    info!(
+        model_name = wrapper.model_name(Task::Architecture),
+        architecture_path = %manager.architecture_path().display(),
+        "generate_architecture_docs"
+    );
+
+    let architecture_input = rollup::architecture_input(ctx, project_index)?;
+    let architecture = wrapper
+        .architecture(project_name, &architecture_input)
+        .await?;
+
+    let architecture_path = manager.architecture_path();
+    page_split::write_page(
+        manager.store(),
+        &architecture_path,
+        &architecture.text,
+        &ctx.config.page_split,
+    )
+    .map_err(|e| {
+        PlainSightError::io(
+            format!(
+                "writing architecture output '{}'",
+                architecture_path.display()
+            ),
+            e,
+        )
+    })?;
+    sync_memory_snapshot(
+        manager,
+        memory_file_path,
+        project_memory,
+        "after_architecture",
+    )?;
+
+    info!(
+        model_name = %architecture.model,
+        elapsed = %format_duration(architecture.total_duration),
+        prompt_eval_count = architecture.prompt_eval_count,
+        eval_count = architecture.eval_count,
+        architecture_len = architecture.text.len(),
+        architecture_path = %architecture_path.display(),
+        "architecture docs generated"
+    );
+    hooks::after(ctx.hooks, PipelineStage::Architecture, &stage).await
+}
 
-        let start = Instant::now();
-        let mut used_compact = false;
-        let mut docs = match wrapper.document(&input).await {
-            Ok(docs) => docs,
-            Err(err) if should_retry_compact_ollama_error(&err) => {
+/// Run `generate` for `parsed` within `OllamaConfig::file_timeout`, skipping the file and
+/// recording it in `GenerationContext::skipped_files` when it runs over. Files on the skip list,
+/// and those skipped for their summary, are not generated at all. Waits out a cooldown of
+/// `OllamaConfig::retry_budget` first.
+async fn within_file_budget<T>(
+    ctx: &GenerationContext<'_>,
+    parsed: &ParsedFile,
+    task: Task,
+    generate: impl Future<Output = PlainResult<FileOutcome<T>>>,
+) -> PlainResult<FileOutcome<T>> {
+    let path = &parsed.relative_path;
+    let skipped = ProgressEvent::FileSkipped { path, task };
+    if ctx.skip_listed.iter().any(|entry| &entry.path == path) || ctx.skipped_files.contains(path) {
+        ctx.progress.event(skipped);
+        return Ok(FileOutcome::Skipped);
+    }
+    // Outside the file's budget, which a cooldown would otherwise use up.
+    if ctx.regenerates(path, task) {
+        ctx.backpressure.check(ctx).await?;
+    }
+    let outcome = match ctx.config.ollama.file_timeout {
+        None => generate.await?,
+        Some(budget) => match tokio::time::timeout(budget, generate).await {
+            Ok(outcome) => outcome?,
+            Err(_) => {
                 warn!(
-                    target_file = %parsed.relative_path,
-                    error = %err,
-                    "docs request failed with transient Ollama error; retrying with compact context"
+                    target_file = %path,
+                    task = task.name(),
+                    budget_secs = budget.as_secs(),
+                    "file ran over its time budget; skipping it"
                 );
-                used_compact = true;
-                let fallback = build_file_prompt_input(
-                    parsed,
-                    project_memory,
-                    PromptProfile::Compact,
-                    memory_file_path,
-                    source_index_file_path,
-                )?;
-                debug!(
-                    target_file = %parsed.relative_path,
-                    profile = "compact",
-                    payload_bytes = fallback.len(),
-                    "file_docs_payload"
+                let entry = SkipEntry::new(
+                    path.clone(),
+                    format!(
+                        "{} ran over the {}s file budget",
+                        task.name(),
+                        budget.as_secs()
+                    ),
                 );
-                wrapper.document(&fallback).await.or_else(|fallback_err| {
-                    if should_retry_compact_ollama_error(&fallback_err) {
-                        warn!(
-                            target_file = %parsed.relative_path,
-                            error = %fallback_err,
-                            "docs compact retry also failed with transient Ollama error; skipping file"
-                        );
-                        Ok(String::new())
-                    } else {
-                        Err(fallback_err)
-                    }
-                })?
+                ctx.skipped_files.insert(entry.clone());
+                failures::record_failure(ctx, entry)?;
+                FileOutcome::Skipped
             }
-            Err(err) => return Err(err),
-        };
+        },
+    };
+    match &outcome {
+        FileOutcome::Reused(_) => ctx.progress.event(ProgressEvent::FileReused { path, task }),
+        FileOutcome::Skipped => ctx.progress.event(skipped),
+        // Reported with its size where it is written.
+        FileOutcome::Generated(_) => {}
+    }
+    Ok(outcome)
+}
 
-        if docs.is_empty() {
-            docs_skipped += 1;
-            continue;
-        }
+async fn summarize_file(
+    ctx: &GenerationContext<'_>,
+    parsed: &ParsedFile,
+) -> PlainResult<FileOutcome<String>> {
+    let GenerationContext {
+        wrapper,
+        project: manager,
+        project_memory,
+        memory_file_path,
+        ..
+    } = *ctx;
 
-        if !used_compact && ollama::is_refusal_output(&docs) {
-            warn!(
-                target_file = %parsed.relative_path,
-                "docs refusal detected; retrying with compact context"
-            );
-            let fallback = build_file_prompt_input(
-                parsed,
-                project_memory,
-                PromptProfile::Compact,
-                memory_file_path,
-                source_index_file_path,
-            )?;
+    if !ctx.regenerates(&parsed.relative_path, Task::Summarize)
+        || ctx.checkpoint.has_summary(parsed)
+    {
+        let summary_path = manager.file_summary_path(&parsed.path)?;
+        if let Ok(existing_summary) = manager.store().read_to_string(&summary_path)
+            && !existing_summary.trim().is_empty()
+        {
             debug!(
                 target_file = %parsed.relative_path,
-                profile = "compact",
-                payload_bytes = fallback.len(),
-                "file_docs_payload"
+                summary_path = %summary_path.display(),
+                "reuse_file_summary"
+            );
+            return Ok(FileOutcome::Reused(existing_summary));
+        }
+    }
+
+    debug!(
+        target_file = %parsed.relative_path,
+        model_name = wrapper.model_name(Task::Summarize),
+        "generate_file_summary"
+    );
+    ctx.progress.event(ProgressEvent::FileStarted {
+        path: &parsed.relative_path,
+        task: Task::Summarize,
+    });
+
+    debug_current_memory(memory_file_path, &parsed.relative_path);
+
+    let start = Instant::now();
+    let (summary, ()) = tokio::join!(
+        with_target(
+            &parsed.relative_path,
+            request_file_output(ctx, parsed, Task::Summarize),
+        ),
+        async { prefetch_next(ctx, parsed, Task::Summarize) },
+    );
+    let summary = summary?;
+
+    if summary.is_empty() {
+        return Ok(FileOutcome::Skipped);
+    }
+
+    let elapsed = format_duration(start.elapsed());
+    let summary_path = write_file_summary(ctx, parsed, &summary)?;
+
+    // Keep memory snapshot fresh for each generated artifact.
+    sync_memory_snapshot(
+        manager,
+        memory_file_path,
+        project_memory,
+        "after_file_summary",
+    )?;
+
+    debug!(
+        target_file = %parsed.relative_path,
+        model_name = wrapper.model_name(Task::Summarize),
+        elapsed = %elapsed,
+        summary_len = summary.len(),
+        summary_path = %summary_path.display(),
+        "file summary generated"
+    );
+    ctx.progress.event(ProgressEvent::FileFinished {
+        path: &parsed.relative_path,
+        task: Task::Summarize,
+        bytes: summary.len(),
+    });
+    Ok(FileOutcome::Generated(summary))
+}
+
+fn write_file_summary(
+    ctx: &GenerationContext<'_>,
+    parsed: &ParsedFile,
+    summary: &str,
+) -> PlainResult<PathBuf> {
+    let manager = ctx.project;
+    let summary_path = manager.file_summary_path(&parsed.path)?;
+    manager
+        .store()
+        .write(&summary_path, summary.as_bytes())
+        .map_err(|e| {
+            PlainSightError::io(
+                format!("writing summary output '{}'", summary_path.display()),
+                e,
+            )
+        })?;
+    ctx.checkpoint.summary_written(manager, parsed)?;
+    Ok(summary_path)
+}
+
+/// Summaries of the small files waiting for one, several files to a request, by relative path.
+/// Files a reply left out, or whose batch failed, are not in the result and get their own
+/// request afterwards.
+async fn summarize_small_files(
+    ctx: &GenerationContext<'_>,
+) -> PlainResult<BTreeMap<String, String>> {
+    let Some(batching) = ctx.wrapper.summary_batching() else {
+        return Ok(BTreeMap::new());
+    };
+    let history_key = profile_history_key(ctx, Task::Summarize);
+    let mut batches: Vec<Vec<(String, String)>> = Vec::new();
+    let mut batch: Vec<(String, String)> = Vec::new();
+    for parsed in ctx.parsed_files.iter().filter(|parsed| {
+        ctx.regenerates(&parsed.relative_path, Task::Summarize)
+            && !ctx.checkpoint.has_summary(parsed)
+            && parsed.source_index.line_count <= batching.max_lines
+            && ctx.profile_history.get(&parsed.relative_path, &history_key)
+                == PromptProfile::Standard
+    }) {
+        let input = build_file_prompt_input(ctx, parsed, PromptProfile::Standard)?;
+        batch.push((parsed.relative_path.clone(), input));
+        if batch.len() > 1 && !ctx.wrapper.summary_batch_fit(&batch)?.fits() {
+            let last = batch.split_off(batch.len() - 1);
+            batches.push(std::mem::replace(&mut batch, last));
+        } else if batch.len() >= batching.max_files {
+            batches.push(std::mem::take(&mut batch));
+        }
+    }
+    batches.push(batch);
+    // A file alone in its batch is better off with the regular prompt.
+    batches.retain(|batch| batch.len() > 1);
+    if batches.is_empty() {
+        return Ok(BTreeMap::new());
+    }
+
+    let max_concurrency = ctx.wrapper.max_concurrency_for(Task::Summarize);
+    info!(
+        batch_count = batches.len(),
+        file_count = batches.iter().map(Vec::len).sum::<usize>(),
+        max_concurrency,
+        "summary_batches_start"
+    );
+    let summaries = run_bounded(&batches, max_concurrency, |batch| {
+        summarize_batch(ctx, batch)
+    })
+    .await?;
+    Ok(summaries.into_iter().flatten().collect())
+}
+
+async fn summarize_batch(
+    ctx: &GenerationContext<'_>,
+    batch: &[(String, String)],
+) -> PlainResult<BTreeMap<String, String>> {
+    let GenerationContext {
+        wrapper,
+        project: manager,
+        project_memory,
+        memory_file_path,
+        parsed_files,
+        ..
+    } = *ctx;
+
+    let start = Instant::now();
+    let target = format!("{} (+{} more)", batch[0].0, batch.len() - 1);
+    let summaries = match with_target(&target, wrapper.summarize_batch(batch)).await {
+        Ok(summaries) => summaries,
+        Err(PlainSightError::Cancelled) => return Err(PlainSightError::Cancelled),
+        Err(err) => {
+            warn!(
+                files = batch.len(),
+                first_file = %batch[0].0,
+                error = %err,
+                "summary batch failed; summarizing its files one by one"
             );
-            docs = wrapper.document(&fallback).await.or_else(|fallback_err| {
-                if should_retry_compact_ollama_error(&fallback_err) {
-                    warn!(
-                        target_file = %parsed.relative_path,
-                        error = %fallback_err,
-                        "docs refusal fallback failed with transient Ollama error; skipping file"
-                    );
-                    Ok(String::new())
-                } else {
-                    Err(fallback_err)
+            return Ok(BTreeMap::new());
+        }
+    };
+
+    for (path, summary) in &summaries {
+        if let Some(parsed) = parsed_files
+            .iter()
+            .find(|parsed| parsed.relative_path == *path)
+        {
+            write_file_summary(ctx, parsed, summary)?;
+        }
+    }
+    sync_memory_snapshot(
+        manager,
+        memory_file_path,
+        project_memory,
+        "after_summary_batch",
+    )?;
+
+    debug!(
+        files = batch.len(),
+        summarized = summaries.len(),
+        model_name = wrapper.model_name(Task::Summarize),
+        elapsed = %format_duration(start.elapsed()),
+        "summary batch generated"
+    );
+    Ok(summaries)
+}
+
+async fn document_file(
+    ctx: &GenerationContext<'_>,
+    parsed: &ParsedFile,
+) -> PlainResult<FileOutcome<FileDocs>> {
+    let GenerationContext {
+        wrapper,
+        memory_file_path,
+        ..
+    } = *ctx;
+
+    if !ctx.regenerates(&parsed.relative_path, Task::Documentation) {
+        debug!(target_file = %parsed.relative_path, "reuse_file_docs");
+        return Ok(FileOutcome::Reused(FileDocs::default()));
+    }
+    if let Some(questions) = ctx.checkpoint.docs(parsed) {
+        debug!(target_file = %parsed.relative_path, "resume_file_docs");
+        ctx.finished_files.insert(&parsed.relative_path);
+        return Ok(FileOutcome::Reused(FileDocs {
+            questions,
+            unreviewed: None,
+        }));
+    }
+
+    debug!(
+        target_file = %parsed.relative_path,
+        model_name = wrapper.model_name(Task::Documentation),
+        "generate_file_docs"
+    );
+    ctx.progress.event(ProgressEvent::FileStarted {
+        path: &parsed.relative_path,
+        task: Task::Documentation,
+    });
+
+    debug_current_memory(memory_file_path, &parsed.relative_path);
+
+    let start = Instant::now();
+    let (docs, ()) = tokio::join!(
+        with_target(
+            &parsed.relative_path,
+            request_file_output(ctx, parsed, Task::Documentation),
+        ),
+        async { prefetch_next(ctx, parsed, Task::Documentation) },
+    );
+    let docs = docs?;
+
+    if docs.is_empty() {
+        ctx.finished_files.insert(&parsed.relative_path);
+        return Ok(FileOutcome::Skipped);
+    }
+    // Written unreviewed first, so the draft is kept if the run stops before the review pass.
+    let (docs, unreviewed) = if ctx.schedule.defer_reviews && wrapper.reviews_docs() {
+        (docs.clone(), Some(docs))
+    } else {
+        (review::review_docs(ctx, parsed, docs).await?, None)
+    };
+    let (questions, docs_path) = write_file_docs(ctx, parsed, &docs)?;
+    // Drafts are checkpointed once reviewed, so a resumed run still reviews them.
+    if unreviewed.is_none() {
+        ctx.checkpoint
+            .docs_written(ctx.project, parsed, &questions)?;
+    }
+
+    debug!(
+        target_file = %parsed.relative_path,
+        model_name = wrapper.model_name(Task::Documentation),
+        elapsed = %format_duration(start.elapsed()),
+        docs_path = %docs_path.display(),
+        review_deferred = unreviewed.is_some(),
+        "file docs generated"
+    );
+    ctx.progress.event(ProgressEvent::FileFinished {
+        path: &parsed.relative_path,
+        task: Task::Documentation,
+        bytes: docs.len(),
+    });
+    Ok(FileOutcome::Generated(FileDocs {
+        questions,
+        unreviewed,
+    }))
+}
+
+/// Write a file's docs with their front-matter, and return their open questions and path.
+fn write_file_docs(
+    ctx: &GenerationContext<'_>,
+    parsed: &ParsedFile,
+    docs: &str,
+) -> PlainResult<(Vec<String>, PathBuf)> {
+    let GenerationContext {
+        project: manager,
+        project_memory,
+        memory_file_path,
+        ..
+    } = *ctx;
+    let (docs, file_questions) = questions::split_open_questions(docs);
+    let freshness = PageFreshness::now(parsed.hash.as_str());
+    let docs =
+        freshness::render_front_matter(&parsed.relative_path, &freshness, parsed.owners.as_ref())
+            + docs.as_str();
+
+    let docs_path = manager.file_docs_path(&parsed.path)?;
+    page_split::write_page(manager.store(), &docs_path, &docs, &ctx.config.page_split).map_err(
+        |e| PlainSightError::io(format!("writing docs output '{}'", docs_path.display()), e),
+    )?;
+    sync_memory_snapshot(manager, memory_file_path, project_memory, "after_file_docs")?;
+    ctx.finished_files.insert(&parsed.relative_path);
+    Ok((file_questions, docs_path))
+}
+
+/// Ask `task` (`Summarize` or `Documentation`) about one file, sized to the task's context
+/// window: the standard context when it fits, the compact one otherwise, and when even that is
+/// too large, one request per line range with the replies merged section by section. Files
+/// that needed a smaller profile on an earlier run start at that profile.
+///
+/// Failures a smaller prompt may avoid are retried with the compact, then the minimal
+/// context. When those fail too, or any part of a split file fails that way, the file is
+/// skipped and an empty string returned. The profile that worked, or the minimal one when
+/// none did, is remembered for the next run.
+async fn request_file_output(
+    ctx: &GenerationContext<'_>,
+    parsed: &ParsedFile,
+    task: Task,
+) -> PlainResult<String> {
+    let label = if task == Task::Summarize {
+        "summary"
+    } else {
+        "docs"
+    };
+    let history_key = profile_history_key(ctx, task);
+    let remembered = ctx.profile_history.get(&parsed.relative_path, &history_key);
+    if remembered != PromptProfile::Standard {
+        info!(
+            target_file = %parsed.relative_path,
+            task = ?task,
+            profile = ?remembered,
+            "{label} needed a smaller context before; starting with it"
+        );
+    }
+    let start = ctx.backpressure.start_profile(remembered);
+    let requests = match ctx.prompt_prefetch.take(&parsed.relative_path, task, start) {
+        Some(requests) => requests,
+        None => plan_file_requests(ctx, parsed, task, start)?,
+    };
+    let (mut profile, mut input) = match requests {
+        FileRequests::Single(profile, input) => (profile, input),
+        FileRequests::Split(inputs) => {
+            let mut outputs = Vec::with_capacity(inputs.len());
+            for input in &inputs {
+                let output = skip_if_compact_failed(
+                    ctx,
+                    parsed,
+                    label,
+                    request_file(ctx.wrapper, task, input).await,
+                )?;
+                if output.is_empty() {
+                    return Ok(output);
                 }
-            })?;
-            if docs.is_empty() {
-                docs_skipped += 1;
-                continue;
+                outputs.push(output);
+            }
+            return Ok(merge_part_outputs(&outputs));
+        }
+    };
+    loop {
+        match request_file(ctx.wrapper, task, &input).await {
+            Err(err) if compact_retry_may_help(&err) => {
+                let Some(smaller) = profile.smaller() else {
+                    ctx.profile_history
+                        .record(&parsed.relative_path, &history_key, profile);
+                    return skip_if_compact_failed(ctx, parsed, label, Err(err));
+                };
+                warn!(
+                    target_file = %parsed.relative_path,
+                    error = %err,
+                    profile = ?smaller,
+                    "{label} request failed; retrying with smaller context"
+                );
+                profile = smaller;
+                input = build_file_prompt_input(ctx, parsed, profile)?;
+            }
+            result => {
+                // A start the retry budget made smaller says nothing about the file.
+                if result.is_ok() && (start == remembered || profile > start) {
+                    ctx.profile_history
+                        .record(&parsed.relative_path, &history_key, profile);
+                }
+                return result;
             }
         }
+    }
+}
+
+pub(super) fn profile_history_key(ctx: &GenerationContext<'_>, task: Task) -> String {
+    format!("{}/{}", task.name(), ctx.wrapper.model_name(task))
+}
+
+/// Build the `task` requests of the next file after `parsed` that is still waiting for its
+/// turn, so they are ready when it comes. Runs while `parsed`'s request is in flight.
+fn prefetch_next(ctx: &GenerationContext<'_>, parsed: &ParsedFile, task: Task) {
+    let Some(next) = ctx
+        .parsed_files
+        .iter()
+        .skip_while(|other| !std::ptr::eq(*other, parsed))
+        .skip(1)
+        .find(|other| {
+            ctx.regenerates(&other.relative_path, task)
+                && ctx.prompt_prefetch.wants(&other.relative_path, task)
+        })
+    else {
+        return;
+    };
+    let start = ctx.backpressure.start_profile(
+        ctx.profile_history
+            .get(&next.relative_path, &profile_history_key(ctx, task)),
+    );
+    let build_start = Instant::now();
+    match plan_file_requests(ctx, next, task, start) {
+        Ok(requests) => ctx.prompt_prefetch.insert(
+            &next.relative_path,
+            task,
+            start,
+            requests,
+            build_start.elapsed(),
+        ),
+        // Its own turn builds the requests again and reports the error.
+        Err(err) => debug!(
+            target_file = %next.relative_path,
+            error = %err,
+            "prompt_prefetch_failed"
+        ),
+    }
+}
+
+/// The summary or docs for `input`, logging the token counts the server reported.
+async fn request_file(wrapper: &OllamaWrapper, task: Task, input: &str) -> PlainResult<String> {
+    let reply = if task == Task::Summarize {
+        wrapper.summarize(input).await?
+    } else {
+        wrapper.document(input).await?
+    };
+    debug!(
+        task = task.name(),
+        model_name = %reply.model,
+        elapsed = %format_duration(reply.total_duration),
+        prompt_eval_count = reply.prompt_eval_count,
+        eval_count = reply.eval_count,
+        "file_request_complete"
+    );
+    Ok(reply.text)
+}
 
-        if ollama::is_refusal_output(&docs) {
+/// An empty output for a request that failed even with the smallest context, recording the
+/// file as failed; see [`crate::config::ErrorPolicy`].
+fn skip_if_compact_failed(
+    ctx: &GenerationContext<'_>,
+    parsed: &ParsedFile,
+    label: &str,
+    result: PlainResult<String>,
+) -> PlainResult<String> {
+    result.or_else(|err| {
+        if compact_retry_may_help(&err) {
             warn!(
                 target_file = %parsed.relative_path,
-                "docs refusal persisted; skipping file"
+                error = %err,
+                "{label} compact request failed; skipping file"
             );
-            docs_skipped += 1;
-            continue;
+            failures::record_failure(
+                ctx,
+                SkipEntry::new(
+                    parsed.relative_path.clone(),
+                    format!("{label} request failed: {err}"),
+                ),
+            )?;
+            Ok(String::new())
+        } else {
+            Err(err)
         }
+    })
+}
 
-        let elapsed = format_duration(start.elapsed());
-        let docs_path = manager.file_docs_path(&parsed.path)?;
-        fs::write(&docs_path, docs).map_err(|e| {
-            PlainSightError::io(format!("writing docs output '{}'", docs_path.display()), e)
-        })?;
-        sync_memory_snapshot(memory_file_path, project_memory, "after_file_docs")?;
-
-        docs_generated += 1;
+/// Pick the largest context no larger than `start` that fits the task's token budget:
+/// standard, compact, or the compact context split into 2, 4, 8, ... line ranges (at most one
+/// per source chunk). A `Minimal` start is sent as is or split likewise. When nothing fits,
+/// the finest split is sent with a warning.
+pub(super) fn plan_file_requests(
+    ctx: &GenerationContext<'_>,
+    parsed: &ParsedFile,
+    task: Task,
+    start: PromptProfile,
+) -> PlainResult<FileRequests> {
+    let wrapper = ctx.wrapper;
+    let split_profile = start.max(PromptProfile::Compact);
+    let mut compact = String::new();
+    let mut profiles = vec![start];
+    if split_profile != start {
+        profiles.push(split_profile);
+    }
+    for profile in profiles {
+        let input = build_file_prompt_input(ctx, parsed, profile)?;
+        let fit = wrapper.file_prompt_fit(task, &input)?;
         debug!(
             target_file = %parsed.relative_path,
-            model_name = wrapper.model_name(Task::Documentation),
-            elapsed = %elapsed,
-            docs_path = %docs_path.display(),
-            "file docs generated"
+            task = ?task,
+            profile = ?profile,
+            payload_bytes = input.len(),
+            prompt_tokens = fit.tokens,
+            limit = fit.limit,
+            "file_prompt_payload"
         );
+        if fit.fits() {
+            if profile == PromptProfile::Compact && start == PromptProfile::Standard {
+                info!(
+                    target_file = %parsed.relative_path,
+                    task = ?task,
+                    "standard context exceeds the token budget; using compact context"
+                );
+            }
+            return Ok(FileRequests::Single(profile, input));
+        }
+        compact = input;
     }
 
-    if files_to_regenerate.is_empty() {
-        info!("architecture_unchanged_skip");
-        info!(
-            reused = docs_reused,
-            generated = docs_generated,
-            skipped = docs_skipped,
-            "documentation_phase_complete"
-        );
-        return Ok(());
+    let chunk_count = parsed.source_index.chunks.len();
+    let mut finest = None;
+    let mut count = 2;
+    while count <= chunk_count {
+        let inputs = file_parts(parsed, count)
+            .iter()
+            .map(|part| build_file_part_prompt_input(ctx, parsed, split_profile, Some(part)))
+            .collect::<PlainResult<Vec<_>>>()?;
+        let mut fits = true;
+        for input in &inputs {
+            fits &= wrapper.file_prompt_fit(task, input)?.fits();
+        }
+        if fits {
+            info!(
+                target_file = %parsed.relative_path,
+                task = ?task,
+                parts = inputs.len(),
+                "compact context exceeds the token budget; splitting file"
+            );
+            return Ok(FileRequests::Split(inputs));
+        }
+        finest = Some(inputs);
+        count = if count == chunk_count {
+            count + 1
+        } else {
+            (count * 2).min(chunk_count)
+        };
     }
 
-    info!(
-        model_name = wrapper.model_name(Task::Architecture),
-        architecture_path = %manager.architecture_path().display(),
-        "generate_architecture_docs"
+    warn!(
+        target_file = %parsed.relative_path,
+        task = ?task,
+        "file context exceeds the token budget even when split; sending it anyway"
     );
+    Ok(match finest {
+        Some(inputs) => FileRequests::Split(inputs),
+        None => FileRequests::Single(split_profile, compact),
+    })
+}
 
-    let start = Instant::now();
-    let architecture = wrapper.architecture(project_name, project_index).await?;
-    let elapsed = format_duration(start.elapsed());
+/// `parsed`'s source chunks in `count` contiguous groups of about the same size.
+fn file_parts(parsed: &ParsedFile, count: usize) -> Vec<FilePart> {
+    let chunks = &parsed.source_index.chunks;
+    let per_part = chunks.len().div_ceil(count.max(1)).max(1);
+    let groups = chunks.chunks(per_part).collect::<Vec<_>>();
+    groups
+        .iter()
+        .enumerate()
+        .map(|(index, group)| FilePart {
+            index: index + 1,
+            count: groups.len(),
+            start_line: group.first().map_or(1, |chunk| chunk.start_line),
+            end_line: group.last().map_or(1, |chunk| chunk.end_line),
+            chunk_ids: group.iter().map(|chunk| chunk.chunk_id).collect(),
+        })
+        .collect()
+}
 
-    let architecture_path = manager.architecture_path();
-    fs::write(&architecture_path, &architecture).map_err(|e| {
-        PlainSightError::io(
-            format!(
-                "writing architecture output '{}'",
-                architecture_path.display()
-            ),
-            e,
-        )
-    })?;
-    sync_memory_snapshot(memory_file_path, project_memory, "after_architecture")?;
+/// Join the replies for the parts of a split file: the first reply's preamble, then each
+/// `## ` section once, in order of first appearance, with the parts' distinct bodies in file
+/// order.
+fn merge_part_outputs(outputs: &[String]) -> String {
+    let mut preamble = String::new();
+    let mut sections: Vec<(String, Vec<String>)> = Vec::new();
+    for (part, output) in outputs.iter().enumerate() {
+        let mut current: Option<usize> = None;
+        for line in output.lines() {
+            if let Some(heading) = line.strip_prefix("## ") {
+                let heading = heading.trim().to_string();
+                let index = match sections.iter().position(|(name, _)| *name == heading) {
+                    Some(index) => index,
+                    None => {
+                        sections.push((heading, Vec::new()));
+                        sections.len() - 1
+                    }
+                };
+                sections[index].1.push(String::new());
+                current = Some(index);
+                continue;
+            }
+            match current {
+                Some(index) => {
+                    if let Some(body) = sections[index].1.last_mut() {
+                        body.push_str(line);
+                        body.push('\n');
+                    }
+                }
+                None if part == 0 => {
+                    preamble.push_str(line);
+                    preamble.push('\n');
+                }
+                None => {}
+            }
+        }
+    }
 
-    info!(
-        model_name = wrapper.model_name(Task::Architecture),
-        elapsed = %elapsed,
-        architecture_len = architecture.len(),
-        architecture_path = %architecture_path.display(),
-        "architecture docs generated"
-    );
-    info!(
-        reused = docs_reused,
-        generated = docs_generated,
-        skipped = docs_skipped,
-        "documentation_phase_complete"
-    );
+    let mut merged = preamble.trim_end().to_string();
+    for (heading, bodies) in sections {
+        let mut kept: Vec<&str> = Vec::new();
+        for body in bodies.iter().map(|body| body.trim()) {
+            if !body.is_empty() && !kept.contains(&body) {
+                kept.push(body);
+            }
+        }
+        let body = kept.join("\n\n");
+        if !merged.is_empty() {
+            merged.push_str("\n\n");
+        }
+        merged.push_str(&format!("## {heading}\n{body}"));
+    }
+    merged.push('\n');
+    merged
+}
 
-    Ok(())
+/// Runs `run` for every file (or batch of files) with at most `limit` requests in flight and
+/// returns the results in order. The first error ends the phase and drops the requests still
+/// running.
+pub(super) async fn run_bounded<'a, I, T, F, Fut>(
+    items: &'a [I],
+    limit: usize,
+    run: F,
+) -> PlainResult<Vec<T>>
+where
+    F: FnMut(&'a I) -> Fut,
+    Fut: Future<Output = PlainResult<T>>,
+{
+    run_ordered(items, 0..items.len(), limit, run).await
 }
 
+/// [`run_bounded`], starting the items in `order` (indices into `items`, each once). Results
+/// are still returned in `items` order.
+async fn run_ordered<'a, I, T, F, Fut>(
+    items: &'a [I],
+    order: impl IntoIterator<Item = usize>,
+    limit: usize,
+    mut run: F,
+) -> PlainResult<Vec<T>>
+where
+    F: FnMut(&'a I) -> Fut,
+    Fut: Future<Output = PlainResult<T>>,
+{
+    let mut pending = order.into_iter().map(|index| (index, &items[index]));
+    let mut in_flight = FuturesUnordered::new();
+    let mut results: Vec<Option<T>> = items.iter().map(|_| None).collect();
+
+    loop {
+        while in_flight.len() < limit.max(1) {
+            let Some((index, item)) = pending.next() else {
+                break;
+            };
+            let request = run(item);
+            in_flight.push(async move { (index, request.await) });
+        }
+        let Some((index, result)) = in_flight.next().await else {
+            break;
+        };
+        results[index] = Some(result?);
+    }
+
+    Ok(results.into_iter().flatten().collect())
+}
+
+/// Unload the models of `tasks`, unless `OllamaConfig::model_unloading` keeps them loaded.
 pub(crate) async fn unload_tasks(wrapper: &OllamaWrapper, tasks: &[Task]) {
+    if !wrapper.unloads_after_phase() {
+        return;
+    }
     let mut seen_models: BTreeSet<String> = BTreeSet::new();
     let mut unload_ok = 0usize;
     let mut unload_failed = 0usize;
@@ -473,16 +1273,49 @@ pub(crate) async fn unload_tasks(wrapper: &OllamaWrapper, tasks: &[Task]) {
     );
 }
 
-fn build_file_prompt_input(
+/// Unload the models of `tasks` that `next` does not use, so that they are not resident while
+/// `next`'s model is loaded.
+pub(crate) async fn unload_before(wrapper: &OllamaWrapper, next: Task, tasks: &[Task]) {
+    let next_model = wrapper.model_name(next);
+    let other_tasks = tasks
+        .iter()
+        .copied()
+        .filter(|task| wrapper.model_name(*task) != next_model)
+        .collect::<Vec<_>>();
+    if !other_tasks.is_empty() {
+        unload_tasks(wrapper, &other_tasks).await;
+    }
+}
+
+pub(super) fn build_file_prompt_input(
+    ctx: &GenerationContext<'_>,
     parsed: &ParsedFile,
-    project_memory: &ProjectMemory,
     profile: PromptProfile,
-    memory_file_path: &Path,
-    source_index_file_path: &Path,
 ) -> PlainResult<String> {
+    build_file_part_prompt_input(ctx, parsed, profile, None)
+}
+
+/// The file prompt input, limited to the symbols, source chunks and findings within `part`
+/// when the file is split.
+fn build_file_part_prompt_input(
+    ctx: &GenerationContext<'_>,
+    parsed: &ParsedFile,
+    profile: PromptProfile,
+    part: Option<&FilePart>,
+) -> PlainResult<String> {
+    let in_part = |line: usize| part.is_none_or(|part| part.contains_line(line));
+    let GenerationContext {
+        project_memory,
+        memory_file_path,
+        source_index_file_path,
+        annotations,
+        api_specs,
+        ..
+    } = *ctx;
     let (mut max_chunks, mut max_chunk_chars, max_file_symbols, max_file_imports) = match profile {
         PromptProfile::Standard => (8usize, 1600usize, 70usize, 50usize),
         PromptProfile::Compact => (4usize, 900usize, 30usize, 20usize),
+        PromptProfile::Minimal => (2usize, 600usize, 15usize, 10usize),
     };
 
     let relevant_memory =
@@ -494,16 +1327,21 @@ fn build_file_prompt_input(
         + relevant_memory.open_items.len()
         + relevant_memory.links.len();
 
-    if memory_pressure > 200 {
+    if memory_pressure > 200 && profile != PromptProfile::Minimal {
         max_chunks = max_chunks.saturating_sub(2).max(3);
         max_chunk_chars = max_chunk_chars.saturating_sub(250).max(800);
     }
-    if memory_pressure > 350 {
+    if memory_pressure > 350 && profile != PromptProfile::Minimal {
         max_chunks = max_chunks.saturating_sub(1).max(2);
         max_chunk_chars = max_chunk_chars.saturating_sub(150).max(650);
     }
 
     let mut source_index = parsed.source_index.clone();
+    if let Some(part) = part {
+        source_index
+            .chunks
+            .retain(|chunk| part.chunk_ids.contains(&chunk.chunk_id));
+    }
     if source_index.chunks.len() > max_chunks {
         source_index.chunks.truncate(max_chunks);
     }
@@ -528,6 +1366,18 @@ fn build_file_prompt_input(
         .unwrap_or_default();
 
     let mut file_memory = parsed.memory.clone();
+    file_memory.symbols.retain(|symbol| in_part(symbol.line));
+    let separate_tests = ctx.config.test_code == TestCodeMode::Separate;
+    let test_symbols = if separate_tests {
+        let (tests, rest) = file_memory
+            .symbols
+            .drain(..)
+            .partition::<Vec<_>, _>(|symbol| symbol.is_test);
+        file_memory.symbols = rest;
+        tests
+    } else {
+        Vec::new()
+    };
     if file_memory.symbols.len() > max_file_symbols {
         file_memory.symbols.truncate(max_file_symbols);
     }
@@ -537,6 +1387,23 @@ fn build_file_prompt_input(
     file_memory.symbol_count = file_memory.symbols.len();
     file_memory.import_count = file_memory.imports.len();
 
+    let mut hot_spots = file_memory
+        .symbols
+        .iter()
+        .filter_map(|s| s.metrics.as_ref().map(|m| (s, m)))
+        .filter(|(_, m)| {
+            m.complexity >= HOT_SPOT_COMPLEXITY
+                || m.line_count >= HOT_SPOT_LINES
+                || m.parameter_count >= HOT_SPOT_PARAMETERS
+        })
+        .collect::<Vec<_>>();
+    hot_spots.sort_by(|a, b| {
+        b.1.complexity
+            .cmp(&a.1.complexity)
+            .then_with(|| b.1.line_count.cmp(&a.1.line_count))
+    });
+    hot_spots.truncate(MAX_HOT_SPOTS);
+
     let source_chars: usize = source_preview.chars().count();
 
     debug!(
@@ -549,53 +1416,208 @@ fn build_file_prompt_input(
         "file_prompt_context_breakdown"
     );
 
-    serde_json::to_string(&serde_json::json!({
-        "path": parsed.relative_path,
-        "language": parsed.language,
-        "source_preview": source_preview,
-        "file_memory_hint": {
-            "symbol_count": file_memory.symbol_count,
-            "import_count": file_memory.import_count,
-            "top_symbols": file_memory.symbols.iter().take(8).map(|s| serde_json::json!({
-                "name": s.name,
-                "kind": s.kind,
-                "line": s.line,
-            })).collect::<Vec<_>>(),
+    let tables = file_memory
+        .symbols
+        .iter()
+        .filter(|s| s.kind == "table")
+        .map(|s| prompt_context::Table {
+            name: s.name.clone(),
+            line: s.line,
+            columns: s
+                .details
+                .fields
+                .iter()
+                .map(|f| format!("{} {}", f.name, f.type_name).trim_end().to_string())
+                .collect(),
+        })
+        .collect();
+
+    let test_code = (separate_tests && (parsed.memory.is_test_file || !test_symbols.is_empty()))
+        .then(|| prompt_context::TestCode {
+            is_test_file: parsed.memory.is_test_file,
+            test_symbol_count: test_symbols.len(),
+            test_symbols: test_symbols
+                .iter()
+                .take(max_file_symbols)
+                .map(|s| prompt_context::TestSymbol {
+                    name: s.name.clone(),
+                    kind: s.kind.clone(),
+                    line: s.line,
+                })
+                .collect(),
+        });
+
+    let endpoints = parsed
+        .routes
+        .iter()
+        .filter(|route| in_part(route.line))
+        .map(|route| {
+            let documented = openapi::find_endpoint(api_specs, route);
+            prompt_context::Endpoint {
+                method: route.method.clone(),
+                path: route.path.clone(),
+                line: route.line,
+                in_spec: documented.is_some(),
+                spec_summary: documented.map(|e| e.summary.clone()).unwrap_or_default(),
+                operation_id: documented
+                    .map(|e| e.operation_id.clone())
+                    .unwrap_or_default(),
+            }
+        })
+        .collect();
+
+    let rustdoc = ctx
+        .rustdoc_items
+        .get(&parsed.relative_path)
+        .into_iter()
+        .flatten()
+        .filter(|item| in_part(item.line))
+        .take(max_file_symbols)
+        .map(|item| prompt_context::RustdocEntry {
+            path: item.path.clone(),
+            kind: item.kind.clone(),
+            line: item.line,
+            signature: item.signature.clone(),
+        })
+        .collect();
+
+    let unsafe_code = parsed
+        .unsafe_sites
+        .iter()
+        .filter(|site| in_part(site.line))
+        .map(|site| prompt_context::UnsafeCode {
+            kind: site.kind.clone(),
+            line: site.line,
+            detail: site.detail.clone(),
+        })
+        .collect();
+
+    let context = PromptContext {
+        version: PROMPT_CONTEXT_VERSION,
+        path: parsed.relative_path.clone(),
+        language: parsed.language.clone(),
+        line_count: parsed.source_index.line_count,
+        source_preview,
+        file_memory_hint: prompt_context::FileMemoryHint {
+            symbol_count: file_memory.symbol_count,
+            import_count: file_memory.import_count,
+            top_symbols: file_memory
+                .symbols
+                .iter()
+                .take(8)
+                .map(|s| prompt_context::SymbolHint {
+                    name: s.name.clone(),
+                    kind: s.kind.clone(),
+                    line: s.line,
+                    attributes: s.details.attributes.clone(),
+                })
+                .collect(),
         },
-        "memory_file_path": memory_file_path.display().to_string(),
-        "source_index_file_path": source_index_file_path.display().to_string(),
-        "source_query": {
-            "file_path": parsed.relative_path,
-            "chunk_ids": [0, 1],
-            "max_chars": if matches!(profile, PromptProfile::Standard) { 3500 } else { 1800 }
+        memory_file_path: memory_file_path.display().to_string(),
+        source_index_file_path: source_index_file_path.display().to_string(),
+        source_query: prompt_context::SourceQuery {
+            file_path: parsed.relative_path.clone(),
+            chunk_ids: part.map_or(vec![0, 1], |part| {
+                part.chunk_ids.iter().take(2).copied().collect()
+            }),
+            max_chars: match profile {
+                PromptProfile::Standard => 3500,
+                PromptProfile::Compact => 1800,
+                PromptProfile::Minimal => 1000,
+            },
         },
-        "memory_query": {
-            "file_path": parsed.relative_path,
-            "max_global_symbols": relevant_memory.global_symbols.len().clamp(8, 20),
-            "max_open_items": relevant_memory.open_items.len().clamp(4, 10),
-            "max_links": relevant_memory.links.len().clamp(4, 14)
+        memory_query: prompt_context::MemoryQuery {
+            file_path: parsed.relative_path.clone(),
+            max_global_symbols: relevant_memory.global_symbols.len().clamp(8, 20),
+            max_open_items: relevant_memory.open_items.len().clamp(4, 10),
+            max_links: relevant_memory.links.len().clamp(4, 14),
         },
-        "project_memory_stats": {
-            "file_count": relevant_memory.file_count,
-            "unique_symbol_count": relevant_memory.unique_symbol_count
-        }
-    }))
-    .map_err(|e| PlainSightError::InvalidState(format!("serializing file prompt input: {e}")))
+        project_memory_stats: prompt_context::ProjectMemoryStats {
+            file_count: relevant_memory.file_count,
+            unique_symbol_count: relevant_memory.unique_symbol_count,
+        },
+        hot_spots: hot_spots
+            .iter()
+            .map(|(s, m)| prompt_context::HotSpot {
+                name: s.name.clone(),
+                line: s.line,
+                line_count: m.line_count,
+                parameter_count: m.parameter_count,
+                complexity: m.complexity,
+            })
+            .collect(),
+        tables,
+        test_code,
+        endpoints,
+        rustdoc,
+        performance: performance_context(&parsed.performance),
+        unsafe_code,
+        annotations: annotations
+            .files
+            .get(&parsed.relative_path)
+            .cloned()
+            .unwrap_or_default(),
+        moved_from: renames::moved_from_context(
+            ctx.project,
+            ctx.symbol_moves,
+            &parsed.relative_path,
+        )?,
+        part: part.map(|part| prompt_context::PartRange {
+            index: part.index,
+            count: part.count,
+            start_line: part.start_line,
+            end_line: part.end_line,
+        }),
+    };
+
+    serde_json::to_string(&context)
+        .map_err(|e| PlainSightError::InvalidState(format!("serializing file prompt input: {e}")))
+}
+
+/// Benchmarks with their results formatted for prompts, keeping where each number came from.
+pub(super) fn performance_context(notes: &[PerformanceNote]) -> Vec<BenchmarkContext> {
+    notes
+        .iter()
+        .map(|note| BenchmarkContext {
+            benchmark: note.benchmark.clone(),
+            defined_in: note.defined_in.clone(),
+            exercises: note.exercises.clone(),
+            results: note
+                .results
+                .iter()
+                .map(|result| BenchmarkTiming {
+                    id: result.id.clone(),
+                    mean: benchmarks::format_nanos(result.mean_ns),
+                    range: format!(
+                        "{} - {}",
+                        benchmarks::format_nanos(result.lower_ns),
+                        benchmarks::format_nanos(result.upper_ns)
+                    ),
+                    source: result.source.clone(),
+                    measured_at: freshness::format_date(result.measured_at),
+                })
+                .collect(),
+        })
+        .collect()
 }
 
 fn sync_memory_snapshot(
+    manager: &ProjectContext,
     memory_file_path: &Path,
     project_memory: &ProjectMemory,
     reason: &str,
 ) -> PlainResult<()> {
     let serialized = serde_json::to_string_pretty(project_memory)
         .map_err(|e| PlainSightError::InvalidState(format!("serializing project memory: {e}")))?;
-    fs::write(memory_file_path, &serialized).map_err(|e| {
-        PlainSightError::io(
-            format!("writing project memory '{}'", memory_file_path.display()),
-            e,
-        )
-    })?;
+    manager
+        .store()
+        .write(memory_file_path, serialized.as_bytes())
+        .map_err(|e| {
+            PlainSightError::io(
+                format!("writing project memory '{}'", memory_file_path.display()),
+                e,
+            )
+        })?;
 
     debug!(
         reason,
@@ -623,29 +1645,112 @@ fn debug_current_memory(memory_file_path: &Path, target_file: &str) {
     }
 }
 
-fn build_project_summary_context(file_summaries: &[(String, String)]) -> String {
-    let mut out = String::from("# File Summaries\n\n");
-    for (path, summary) in file_summaries {
+/// With directory summaries, the top-level directories stand in for the files they hold.
+fn build_project_summary_context(
+    file_summaries: &[(String, String)],
+    directory_summaries: &BTreeMap<String, String>,
+    parsed_files: &[ParsedFile],
+    project_metadata: &[MetadataFile],
+) -> String {
+    let mut out = String::new();
+    let owners = ownership::render_owners_outline(
+        parsed_files
+            .iter()
+            .map(|parsed| (parsed.relative_path.as_str(), parsed.owners.as_ref())),
+    );
+    if !project_metadata.is_empty() || !owners.is_empty() {
+        out.push_str("# Project Metadata\n\n");
+        if !owners.is_empty() {
+            out.push_str("## Owners\n");
+            out.push_str(&owners);
+            out.push('\n');
+        }
+        for file in project_metadata {
+            out.push_str("## ");
+            out.push_str(&file.relative_path);
+            if file.truncated {
+                out.push_str(" (truncated)");
+            }
+            out.push_str("\n```\n");
+            out.push_str(file.content.trim_end());
+            out.push_str("\n```\n\n");
+        }
+    }
+
+    let (directories, files) = rollup::top_level(file_summaries, directory_summaries);
+    if !directories.is_empty() {
+        out.push_str("# Directory Summaries\n\n");
+        push_summaries(&mut out, &directories);
+    }
+    if !files.is_empty() || directories.is_empty() {
+        out.push_str("# File Summaries\n\n");
+        push_summaries(&mut out, &files);
+    }
+
+    let data_model = build_data_model_outline(parsed_files);
+    if !data_model.is_empty() {
+        out.push_str("# Data Model\n\n");
+        out.push_str(&data_model);
+    }
+    out
+}
+
+fn push_summaries(out: &mut String, summaries: &[(&str, &str)]) {
+    for (path, summary) in summaries {
         out.push_str("## ");
         out.push_str(path);
         out.push('\n');
         out.push_str(summary.trim());
         out.push_str("\n\n");
     }
+}
+
+/// Tables from schema files, merged across migrations so each table is listed once with every
+/// column it gained.
+fn build_data_model_outline(parsed_files: &[ParsedFile]) -> String {
+    let mut tables: BTreeMap<&str, (BTreeSet<&str>, Vec<String>)> = BTreeMap::new();
+    for parsed in parsed_files {
+        for symbol in parsed.memory.symbols.iter().filter(|s| s.kind == "table") {
+            let (files, columns) = tables.entry(symbol.name.as_str()).or_default();
+            files.insert(parsed.relative_path.as_str());
+            for field in &symbol.details.fields {
+                let column = format!("{} {}", field.name, field.type_name)
+                    .trim_end()
+                    .to_string();
+                if !columns.contains(&column) {
+                    columns.push(column);
+                }
+            }
+        }
+    }
+
+    let mut out = String::new();
+    for (name, (files, columns)) in tables.into_iter().take(MAX_DATA_MODEL_TABLES) {
+        out.push_str(&format!(
+            "- {name} ({}): {}\n",
+            files.into_iter().collect::<Vec<_>>().join(", "),
+            columns.join(", ")
+        ));
+    }
     out
 }
 
-fn should_retry_compact_ollama_error(err: &PlainSightError) -> bool {
-    let lower = err.to_string().to_ascii_lowercase();
-    lower.contains("request timeout")
-        || lower.contains("timed out")
-        || lower.contains("stopping")
-        || lower.contains("killed")
-        || lower.contains("connection")
-        || lower.contains("json payload instead of markdown")
+/// `OllamaWrapper` has already retried transient failures by the time they get here; a smaller
+/// prompt may still get through, and gives the model another chance after a JSON reply or a
+/// refusal.
+fn compact_retry_may_help(err: &PlainSightError) -> bool {
+    err.is_transient()
+        || matches!(
+            err,
+            PlainSightError::Ollama(
+                OllamaError::JsonPayload
+                    | OllamaError::InvalidStructuredOutput { .. }
+                    | OllamaError::RefusalDetected { .. }
+            )
+        )
 }
 
-fn format_duration(d: Duration) -> String {
+pub(super) fn format_duration(d: Duration) -> String {
     let total_secs = d.as_secs();
     let millis = d.subsec_millis();
     let mins = total_secs / 60;
@@ -0,0 +1,142 @@
+use std::{collections::BTreeSet, error::Error, sync::Arc};
+
+use async_trait::async_trait;
+use tracing::debug;
+
+use crate::{
+    error::{PlainSightError, Result},
+    project_manager::ProjectContext,
+};
+
+use super::types::{GenerationContext, ParsedFile};
+
+/// A stage of a run that [`PipelineHook`]s are called around.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PipelineStage {
+    /// Discovering and parsing the source, and writing the parse artifacts.
+    Ingest,
+    /// The file summaries, and the project summary when it is written with them.
+    Summaries,
+    /// The file docs, up to their reviews.
+    Docs,
+    /// The architecture docs; only called when they are regenerated. When the model schedule
+    /// writes them ahead of the deferred reviews, that is within the docs stage.
+    Architecture,
+}
+
+impl PipelineStage {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Ingest => "ingest",
+            Self::Summaries => "summaries",
+            Self::Docs => "docs",
+            Self::Architecture => "architecture",
+        }
+    }
+}
+
+/// What a [`PipelineHook`] sees of the run.
+pub struct StageContext<'a> {
+    pub project_name: &'a str,
+    /// The project's docs paths, and the store the run writes them to.
+    pub project: &'a ProjectContext,
+    parsed_files: &'a [ParsedFile],
+    regenerated: &'a BTreeSet<String>,
+}
+
+impl<'a> StageContext<'a> {
+    /// The project files the run documents, relative to the project root; none before ingest
+    /// is done.
+    pub fn files(&self) -> impl Iterator<Item = &'a str> {
+        self.parsed_files
+            .iter()
+            .map(|parsed| parsed.relative_path.as_str())
+    }
+
+    /// The files whose summary or docs the run regenerates.
+    pub fn regenerated(&self) -> &'a BTreeSet<String> {
+        self.regenerated
+    }
+
+    pub(super) fn new(
+        project_name: &'a str,
+        project: &'a ProjectContext,
+        parsed_files: &'a [ParsedFile],
+        regenerated: &'a BTreeSet<String>,
+    ) -> Self {
+        Self {
+            project_name,
+            project,
+            parsed_files,
+            regenerated,
+        }
+    }
+
+    pub(super) fn of(ctx: &GenerationContext<'a>) -> Self {
+        Self::new(
+            ctx.project_name,
+            ctx.project,
+            ctx.parsed_files,
+            ctx.files_to_regenerate,
+        )
+    }
+}
+
+/// Custom steps around the stages of a run, e.g. uploading artifacts or validating them. Set
+/// with `PlainSight::with_hook`; hooks are called in the order they were added, and an error
+/// from one stops the run. Dry runs call none.
+#[async_trait]
+pub trait PipelineHook: Send + Sync {
+    async fn before(
+        &self,
+        _stage: PipelineStage,
+        _run: &StageContext<'_>,
+    ) -> std::result::Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+
+    async fn after(
+        &self,
+        _stage: PipelineStage,
+        _run: &StageContext<'_>,
+    ) -> std::result::Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+}
+
+pub(super) async fn before(
+    hooks: &[Arc<dyn PipelineHook>],
+    stage: PipelineStage,
+    run: &StageContext<'_>,
+) -> Result<()> {
+    for hook in hooks {
+        debug!(stage = stage.name(), "pipeline_hook_before");
+        hook.before(stage, run)
+            .await
+            .map_err(|source| PlainSightError::Hook {
+                when: "before",
+                stage: stage.name(),
+                source,
+            })?;
+    }
+    Ok(())
+}
+
+pub(super) async fn after(
+    hooks: &[Arc<dyn PipelineHook>],
+    stage: PipelineStage,
+    run: &StageContext<'_>,
+) -> Result<()> {
+    for hook in hooks {
+        debug!(stage = stage.name(), "pipeline_hook_after");
+        hook.after(stage, run)
+            .await
+            .map_err(|source| PlainSightError::Hook {
+                when: "after",
+                stage: stage.name(),
+                source,
+            })?;
+    }
+    Ok(())
+}
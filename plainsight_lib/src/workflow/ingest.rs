@@ -1,110 +1,785 @@
 use std::{
     fs,
+    io::{self, BufRead, BufReader, Read},
+    num::NonZeroUsize,
+    panic,
     path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::{Instant, UNIX_EPOCH},
 };
 
 use tracing::{debug, info, warn};
 
 use crate::{
-    config::SourceDiscoveryConfig,
+    benchmarks::{self, BenchmarkResult},
+    concurrency,
+    config::{ProjectMetadataConfig, RustdocMode, SourceDiscoveryConfig},
+    env_vars,
     error::Result,
+    error_catalog,
+    features::{self, CrateFeatures},
     file_walker::{FileWalker, FilterOptions},
-    memory,
+    language::LanguageDetector,
+    memory, module_tree, ollama,
+    openapi::{self, OpenApiSpec},
+    ownership::{self, ManifestOwners},
     project_manager::{FileMeta, MetaCache, ProjectContext},
-    source_indexer,
+    proto::{self, ProtoFile},
+    rustdoc::{self, RustdocCrate},
+    source_indexer::{self, SourceIndex, SourceIndexBuilder},
+    unsafe_code,
 };
 
-use super::types::ParsedFile;
+use super::types::{MetadataFile, ParsedFile, ProfileHistory};
+
+const SNIFF_BYTES: usize = 8 * 1024;
+/// Enough to hold the disclaimer that opens every generated document, after its front-matter.
+const PROVENANCE_SNIFF_BYTES: usize = 2048;
+/// Lines per fact-extraction segment when a file is streamed.
+const STREAM_SEGMENT_LINES: usize = 5000;
 
 pub(crate) fn discover_source_files(
     project_root: &Path,
     config: &SourceDiscoveryConfig,
 ) -> Result<Vec<PathBuf>> {
+    let mut extensions = config.extensions.clone();
+    if config.detect_extensionless {
+        // An empty extension makes the walker yield extensionless files; they are kept only
+        // when their content identifies a language.
+        extensions.push(String::new());
+    }
     let walker = FileWalker::with_filter(FilterOptions {
-        extensions: config.extensions.clone(),
+        extensions,
         exclude_directories: config.exclude_directories.clone(),
+        exclude_paths: config.exclude_paths.clone(),
+        limits: config.walk_limits,
     });
 
+    let detector = LanguageDetector::new();
     let mut files: Vec<PathBuf> = walker
         .walk(project_root.to_path_buf())?
         .into_iter()
         .map(|f| f.path)
+        .filter(|path| path.extension().is_some() || is_script(&detector, path))
+        .filter(|path| !is_generated_output(path))
         .collect();
 
-    files.sort();
+    // Plain string order rather than `Path`'s component order (`a-b.rs` before `a/b.rs`),
+    // which is how the source index and project memory list files, so that a scoped run
+    // merging into them keeps the order a full run writes.
+    files.sort_by(|a, b| a.as_os_str().cmp(b.as_os_str()));
     Ok(files)
 }
 
+/// Generated docs copied or moved out of the docs root still carry their disclaimer; they are
+/// never fed back in as sources.
+fn is_generated_output(path: &Path) -> bool {
+    let mut head = Vec::with_capacity(PROVENANCE_SNIFF_BYTES);
+    let read = fs::File::open(path)
+        .and_then(|file| {
+            file.take(PROVENANCE_SNIFF_BYTES as u64)
+                .read_to_end(&mut head)
+        })
+        .is_ok();
+    let generated = read && ollama::is_generated_output(&String::from_utf8_lossy(&head));
+    if generated {
+        debug!(target_file = %path.display(), "skipping_generated_output");
+    }
+    generated
+}
+
+/// Sniff the head of an extensionless file; hidden files and binaries are never scripts.
+fn is_script(detector: &LanguageDetector, path: &Path) -> bool {
+    let hidden = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .is_none_or(|name| name.starts_with('.'));
+    if hidden {
+        return false;
+    }
+
+    let mut head = Vec::with_capacity(SNIFF_BYTES);
+    let read = fs::File::open(path)
+        .and_then(|file| file.take(SNIFF_BYTES as u64).read_to_end(&mut head))
+        .is_ok();
+    if !read || head.contains(&0) {
+        return false;
+    }
+
+    let language = detector.detect(path, &String::from_utf8_lossy(&head));
+    if language == "text" {
+        return false;
+    }
+    debug!(target_file = %path.display(), language, "extensionless_script_detected");
+    true
+}
+
+/// Facts extracted from a file, or from one segment of a streamed file.
+struct SourceFacts {
+    memory: memory::FileMemory,
+    mod_declarations: Vec<module_tree::ModDeclaration>,
+    routes: Vec<openapi::DetectedRoute>,
+    env_reads: Vec<env_vars::EnvVarRead>,
+    feature_gates: Vec<features::FeatureGate>,
+    error_types: Vec<error_catalog::ErrorType>,
+    concurrency: Vec<concurrency::ConcurrencyFact>,
+    fingerprints: Vec<memory::SymbolFingerprint>,
+    unsafe_sites: Vec<unsafe_code::UnsafeSite>,
+    benchmarks: Vec<benchmarks::BenchmarkDef>,
+    header_owners: Vec<String>,
+}
+
+impl SourceFacts {
+    fn extract(relative_path: &str, language: &str, source: &str) -> Self {
+        let (mod_declarations, feature_gates, unsafe_sites) = if language == "rust" {
+            (
+                module_tree::parse_mod_declarations(source),
+                features::detect_feature_gates(source),
+                unsafe_code::detect_unsafe(source),
+            )
+        } else {
+            (Vec::new(), Vec::new(), Vec::new())
+        };
+
+        let file_memory = memory::build_file_memory(relative_path, language, source);
+        Self {
+            fingerprints: memory::symbol_fingerprints(&file_memory, source),
+            memory: file_memory,
+            mod_declarations,
+            routes: openapi::detect_routes(language, source),
+            env_reads: env_vars::detect_env_reads(language, source),
+            feature_gates,
+            error_types: error_catalog::detect_error_types(language, source),
+            concurrency: concurrency::detect_concurrency(language, source),
+            unsafe_sites,
+            benchmarks: benchmarks::detect_benchmarks(language, relative_path, source),
+            header_owners: ownership::detect_header_owners(source),
+        }
+    }
+
+    /// Append the facts of a later segment whose first line is `line_offset + 1`. Header
+    /// owners only come from the first segment.
+    fn extend(&mut self, segment: SourceFacts, line_offset: usize) {
+        memory::merge_file_memory(&mut self.memory, segment.memory, line_offset);
+        self.mod_declarations
+            .extend(segment.mod_declarations.into_iter().map(|mut decl| {
+                decl.shift_lines(line_offset);
+                decl
+            }));
+        self.routes
+            .extend(segment.routes.into_iter().map(|mut route| {
+                route.line += line_offset;
+                route
+            }));
+        self.env_reads
+            .extend(segment.env_reads.into_iter().map(|mut read| {
+                read.line += line_offset;
+                read
+            }));
+        self.feature_gates
+            .extend(segment.feature_gates.into_iter().map(|mut gate| {
+                gate.line += line_offset;
+                gate
+            }));
+        self.error_types
+            .extend(segment.error_types.into_iter().map(|mut error| {
+                error.line += line_offset;
+                error
+            }));
+        self.concurrency
+            .extend(segment.concurrency.into_iter().map(|mut fact| {
+                fact.line += line_offset;
+                fact
+            }));
+        self.fingerprints
+            .extend(segment.fingerprints.into_iter().map(|mut fingerprint| {
+                fingerprint.line += line_offset;
+                fingerprint
+            }));
+        self.unsafe_sites
+            .extend(segment.unsafe_sites.into_iter().map(|mut site| {
+                site.line += line_offset;
+                site
+            }));
+        self.benchmarks
+            .extend(segment.benchmarks.into_iter().map(|mut benchmark| {
+                benchmark.line += line_offset;
+                benchmark
+            }));
+    }
+}
+
+/// Index a file above `stream_threshold_bytes` without holding its whole content: lines feed
+/// the chunk builder as they are read, and facts are extracted from `STREAM_SEGMENT_LINES`
+/// segments at a time. Items that straddle a segment boundary can be missed.
+fn parse_streamed(
+    detector: &LanguageDetector,
+    path: &Path,
+    relative_path: &str,
+) -> io::Result<(&'static str, SourceIndex, SourceFacts)> {
+    let mut reader = BufReader::new(fs::File::open(path)?);
+    let head = reader.fill_buf()?;
+    let language = detector.detect(
+        path,
+        &String::from_utf8_lossy(&head[..head.len().min(SNIFF_BYTES)]),
+    );
+
+    let mut builder = SourceIndexBuilder::new(language);
+    let mut facts: Option<SourceFacts> = None;
+    let mut segment = String::new();
+    let mut segment_lines = 0usize;
+    let mut segment_offset = 0usize;
+
+    for line in reader.lines() {
+        let line = line?;
+        builder.push_line(&line);
+        segment.push_str(&line);
+        segment.push('\n');
+        segment_lines += 1;
+        if segment_lines == STREAM_SEGMENT_LINES {
+            let extracted = SourceFacts::extract(relative_path, language, &segment);
+            match &mut facts {
+                Some(facts) => facts.extend(extracted, segment_offset),
+                None => facts = Some(extracted),
+            }
+            segment.clear();
+            segment_offset += segment_lines;
+            segment_lines = 0;
+        }
+    }
+
+    let extracted = SourceFacts::extract(relative_path, language, &segment);
+    let facts = match facts {
+        Some(mut facts) => {
+            facts.extend(extracted, segment_offset);
+            facts
+        }
+        None => extracted,
+    };
+    let source_index = builder.finish();
+    debug!(
+        target_file = %relative_path,
+        line_count = source_index.line_count,
+        chunk_count = source_index.chunk_count,
+        "source_streamed"
+    );
+    Ok((language, source_index, facts))
+}
+
+/// Hash, read and index `files` on `SourceDiscoveryConfig::ingest_threads` threads. Files
+/// that cannot be read are skipped with a warning; the rest keep their order.
 pub(crate) fn parse_project_files(
     files: &[PathBuf],
     manager: &ProjectContext,
     project_root: &Path,
+    config: &SourceDiscoveryConfig,
 ) -> Result<Vec<ParsedFile>> {
-    let mut parsed_files = Vec::new();
-    let mut skipped_file_count = 0usize;
+    let start = Instant::now();
+    // Docs directories are created up front and one at a time, as a store may upload each
+    // write from the calling thread.
+    let documented = files
+        .iter()
+        .filter(|path| match manager.ensure_file_structure(path) {
+            Ok(()) => true,
+            Err(err) => {
+                warn!(target_file = %relative_path_display(path, project_root), error = %err, "failed to ensure file docs structure; skipping file");
+                false
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let threads = ingest_threads(config, documented.len());
+    let detector = LanguageDetector::new();
+    let next = AtomicUsize::new(0);
+    let mut parsed = thread::scope(|scope| {
+        let workers = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut parsed = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(path) = documented.get(index) else {
+                            return parsed;
+                        };
+                        if let Some(file) =
+                            parse_file(&detector, manager, path, project_root, config)
+                        {
+                            parsed.push((index, file));
+                        }
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        workers
+            .into_iter()
+            .flat_map(|worker| {
+                worker
+                    .join()
+                    .unwrap_or_else(|panic| panic::resume_unwind(panic))
+            })
+            .collect::<Vec<_>>()
+    });
+    parsed.sort_unstable_by_key(|(index, _)| *index);
+    let parsed_files = parsed.into_iter().map(|(_, file)| file).collect::<Vec<_>>();
 
-    for path in files {
-        let relative_path = relative_path_display(path, project_root);
-        debug!(target_file = %relative_path, "index_source");
+    info!(
+        total_files = files.len(),
+        parsed_files = parsed_files.len(),
+        skipped_files = files.len() - parsed_files.len(),
+        threads,
+        elapsed_ms = start.elapsed().as_millis() as u64,
+        "ingest_complete"
+    );
 
-        if let Err(err) = manager.ensure_file_structure(path) {
-            warn!(target_file = %relative_path, error = %err, "failed to ensure file docs structure; skipping file");
-            skipped_file_count += 1;
+    Ok(parsed_files)
+}
+
+/// Threads for ingesting `file_count` files: the configured number, or one per available CPU,
+/// and never more than there are files.
+fn ingest_threads(config: &SourceDiscoveryConfig, file_count: usize) -> usize {
+    let threads = config
+        .ingest_threads
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, NonZeroUsize::get));
+    threads.clamp(1, file_count.max(1))
+}
+
+/// Hash, read and index one file; `None`, after a warning, when it cannot be read.
+fn parse_file(
+    detector: &LanguageDetector,
+    manager: &ProjectContext,
+    path: &Path,
+    project_root: &Path,
+    config: &SourceDiscoveryConfig,
+) -> Option<ParsedFile> {
+    let relative_path = relative_path_display(path, project_root);
+    debug!(target_file = %relative_path, "index_source");
+
+    let hash = match manager.hash_file(path) {
+        Ok(hash) => hash,
+        Err(err) => {
+            warn!(target_file = %relative_path, error = %err, "failed hashing source file; skipping file");
+            return None;
+        }
+    };
+
+    let streamed =
+        fs::metadata(path).is_ok_and(|metadata| metadata.len() > config.stream_threshold_bytes);
+    let parsed = if streamed {
+        parse_streamed(detector, path, &relative_path)
+    } else {
+        fs::read_to_string(path).map(|source| {
+            let language = detector.detect(path, &source);
+            let source_index = source_indexer::build_source_index(&source, language);
+            (
+                language,
+                source_index,
+                SourceFacts::extract(&relative_path, language, &source),
+            )
+        })
+    };
+    let (language, source_index, facts) = match parsed {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            warn!(target_file = %relative_path, error = %err, "failed reading source file; skipping file");
+            return None;
+        }
+    };
+
+    Some(ParsedFile {
+        path: path.to_path_buf(),
+        relative_path,
+        language: language.to_string(),
+        hash,
+        source_index,
+        memory: facts.memory,
+        mod_declarations: facts.mod_declarations,
+        routes: facts.routes,
+        env_reads: facts.env_reads,
+        feature_gates: facts.feature_gates,
+        error_types: facts.error_types,
+        concurrency: facts.concurrency,
+        fingerprints: facts.fingerprints,
+        unsafe_sites: facts.unsafe_sites,
+        benchmarks: facts.benchmarks,
+        performance: Vec::new(),
+        header_owners: facts.header_owners,
+        owners: None,
+    })
+}
+
+/// Find and parse OpenAPI/Swagger documents anywhere under `project_root`.
+pub(crate) fn discover_api_specs(
+    project_root: &Path,
+    config: &SourceDiscoveryConfig,
+) -> Result<Vec<OpenApiSpec>> {
+    let walker = FileWalker::with_filter(FilterOptions {
+        extensions: vec!["json".to_string(), "yaml".to_string(), "yml".to_string()],
+        exclude_directories: config.exclude_directories.clone(),
+        exclude_paths: config.exclude_paths.clone(),
+        limits: config.walk_limits,
+    });
+
+    let mut specs = Vec::new();
+    for file in walker.walk(project_root.to_path_buf())? {
+        let is_spec = file
+            .path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(openapi::is_spec_file_name);
+        if !is_spec {
             continue;
         }
 
-        let hash = match manager.hash_file(path) {
-            Ok(hash) => hash,
+        let relative_path = relative_path_display(&file.path, project_root);
+        let content = match fs::read_to_string(&file.path) {
+            Ok(content) => content,
             Err(err) => {
-                warn!(target_file = %relative_path, error = %err, "failed hashing source file; skipping file");
-                skipped_file_count += 1;
+                warn!(spec_file = %relative_path, error = %err, "failed reading api spec; skipping file");
                 continue;
             }
         };
+        match openapi::parse_spec(&relative_path, &content) {
+            Some(spec) => specs.push(spec),
+            None => {
+                warn!(spec_file = %relative_path, "not a valid OpenAPI/Swagger document; skipping file")
+            }
+        }
+    }
+
+    specs.sort_by(|a, b| a.path.cmp(&b.path));
+    if !specs.is_empty() {
+        info!(
+            spec_count = specs.len(),
+            endpoint_count = specs.iter().map(|s| s.endpoints.len()).sum::<usize>(),
+            schema_count = specs.iter().map(|s| s.schemas.len()).sum::<usize>(),
+            "api_specs_discovered"
+        );
+    }
+    Ok(specs)
+}
 
-        let source = match fs::read_to_string(path) {
-            Ok(source) => source,
+/// Find and parse `.proto` files anywhere under `project_root`.
+pub(crate) fn discover_proto_files(
+    project_root: &Path,
+    config: &SourceDiscoveryConfig,
+) -> Result<Vec<ProtoFile>> {
+    let walker = FileWalker::with_filter(FilterOptions {
+        extensions: vec!["proto".to_string()],
+        exclude_directories: config.exclude_directories.clone(),
+        exclude_paths: config.exclude_paths.clone(),
+        limits: config.walk_limits,
+    });
+
+    let mut protos = Vec::new();
+    for file in walker.walk(project_root.to_path_buf())? {
+        let relative_path = relative_path_display(&file.path, project_root);
+        let content = match fs::read_to_string(&file.path) {
+            Ok(content) => content,
             Err(err) => {
-                warn!(target_file = %relative_path, error = %err, "failed reading source file; skipping file");
-                skipped_file_count += 1;
+                warn!(proto_file = %relative_path, error = %err, "failed reading proto file; skipping file");
                 continue;
             }
         };
+        if let Some(parsed) = proto::parse_proto(&relative_path, &content) {
+            protos.push(parsed);
+        }
+    }
 
-        let language = detect_language(path);
-        let source_index = source_indexer::build_source_index(&source, language);
-        let file_memory = memory::build_file_memory(&relative_path, language, &source);
+    protos.sort_by(|a, b| a.path.cmp(&b.path));
+    if !protos.is_empty() {
+        info!(
+            proto_file_count = protos.len(),
+            service_count = protos.iter().map(|p| p.services.len()).sum::<usize>(),
+            message_count = protos.iter().map(|p| p.messages.len()).sum::<usize>(),
+            "proto_files_discovered"
+        );
+    }
+    Ok(protos)
+}
 
-        parsed_files.push(ParsedFile {
-            path: path.clone(),
+/// The latest criterion results under `target/criterion` (`<bench>/new/estimates.json`).
+pub(crate) fn discover_benchmark_results(project_root: &Path) -> Vec<BenchmarkResult> {
+    let mut results = Vec::new();
+    let mut pending = vec![project_root.join("target").join("criterion")];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !path.is_dir() {
+                continue;
+            }
+            if path.file_name().is_none_or(|name| name != "new") {
+                pending.push(path);
+                continue;
+            }
+
+            let estimates_path = path.join("estimates.json");
+            let (Ok(benchmark), Ok(estimates)) = (
+                fs::read_to_string(path.join("benchmark.json")),
+                fs::read_to_string(&estimates_path),
+            ) else {
+                continue;
+            };
+            let measured_at = fs::metadata(&estimates_path)
+                .and_then(|meta| meta.modified())
+                .ok()
+                .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                .map(|age| age.as_secs())
+                .unwrap_or_default();
+            let source = relative_path_display(&estimates_path, project_root);
+            match benchmarks::parse_criterion_result(&benchmark, &estimates, source, measured_at) {
+                Some(result) => results.push(result),
+                None => {
+                    warn!(path = %estimates_path.display(), "unreadable criterion estimates; skipping")
+                }
+            }
+        }
+    }
+
+    results.sort_by(|a, b| a.id.cmp(&b.id));
+    if !results.is_empty() {
+        info!(
+            benchmark_results = results.len(),
+            "criterion_results_loaded"
+        );
+    }
+    results
+}
+
+/// Public items of every crate in the Rust workspace at `project_root` according to rustdoc;
+/// empty when `mode` is off, the project has no root `Cargo.toml`, or rustdoc fails.
+pub(crate) fn discover_rustdoc_items(project_root: &Path, mode: RustdocMode) -> Vec<RustdocCrate> {
+    if mode == RustdocMode::Off || !project_root.join("Cargo.toml").is_file() {
+        return Vec::new();
+    }
+
+    let target_dir = project_root.join("target").join("plainsight-rustdoc");
+    match rustdoc::run_rustdoc(project_root, &target_dir) {
+        Ok(crates) => {
+            info!(
+                crate_count = crates.len(),
+                public_items = crates.iter().map(|krate| krate.items.len()).sum::<usize>(),
+                "rustdoc_items_loaded"
+            );
+            crates
+        }
+        Err(err) => {
+            warn!(error = %err, "rustdoc JSON unavailable; using extracted symbols only");
+            Vec::new()
+        }
+    }
+}
+
+/// Authors and maintainers of every `Cargo.toml` and `package.json` under `project_root`
+/// that names any.
+pub(crate) fn discover_manifest_owners(
+    project_root: &Path,
+    config: &SourceDiscoveryConfig,
+) -> Result<Vec<ManifestOwners>> {
+    let walker = FileWalker::with_filter(FilterOptions {
+        extensions: vec!["toml".to_string(), "json".to_string()],
+        exclude_directories: config
+            .exclude_directories
+            .iter()
+            .cloned()
+            .chain(std::iter::once("node_modules".to_string()))
+            .collect(),
+        exclude_paths: config.exclude_paths.clone(),
+        limits: config.walk_limits,
+    });
+
+    let mut manifests = Vec::new();
+    for file in walker.walk(project_root.to_path_buf())? {
+        let is_manifest = file
+            .path
+            .file_name()
+            .is_some_and(|name| name == "Cargo.toml" || name == "package.json");
+        if !is_manifest {
+            continue;
+        }
+
+        let relative_path = relative_path_display(&file.path, project_root);
+        let content = match fs::read_to_string(&file.path) {
+            Ok(content) => content,
+            Err(err) => {
+                warn!(manifest = %relative_path, error = %err, "failed reading package manifest; skipping file");
+                continue;
+            }
+        };
+        manifests.extend(ownership::parse_manifest_owners(&relative_path, &content));
+    }
+
+    manifests.sort_by(|a, b| a.manifest.cmp(&b.manifest));
+    Ok(manifests)
+}
+
+/// Find every `Cargo.toml` with a package under `project_root` and read its features.
+pub(crate) fn discover_crate_features(
+    project_root: &Path,
+    config: &SourceDiscoveryConfig,
+) -> Result<Vec<CrateFeatures>> {
+    let walker = FileWalker::with_filter(FilterOptions {
+        extensions: vec!["toml".to_string()],
+        exclude_directories: config.exclude_directories.clone(),
+        exclude_paths: config.exclude_paths.clone(),
+        limits: config.walk_limits,
+    });
+
+    let mut crates = Vec::new();
+    for file in walker.walk(project_root.to_path_buf())? {
+        let is_manifest = file
+            .path
+            .file_name()
+            .is_some_and(|name| name == "Cargo.toml");
+        if !is_manifest {
+            continue;
+        }
+
+        let relative_path = relative_path_display(&file.path, project_root);
+        let content = match fs::read_to_string(&file.path) {
+            Ok(content) => content,
+            Err(err) => {
+                warn!(manifest = %relative_path, error = %err, "failed reading cargo manifest; skipping file");
+                continue;
+            }
+        };
+        crates.extend(features::parse_manifest(&relative_path, &content));
+    }
+
+    crates.sort_by(|a, b| a.manifest.cmp(&b.manifest));
+    Ok(crates)
+}
+
+/// Collect the README, package manifests and CI definitions that describe the project as a
+/// whole. Missing or unreadable files are skipped.
+pub(crate) fn discover_project_metadata(
+    project_root: &Path,
+    discovery: &SourceDiscoveryConfig,
+    config: &ProjectMetadataConfig,
+) -> Result<Vec<MetadataFile>> {
+    if !config.enabled {
+        return Ok(Vec::new());
+    }
+
+    let mut paths = config
+        .files
+        .iter()
+        .map(|file| project_root.join(file))
+        .filter(|path| path.is_file())
+        .collect::<Vec<_>>();
+
+    for dir in &config.ci_directories {
+        let dir = project_root.join(dir);
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut workflows = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.is_file()
+                    && path
+                        .extension()
+                        .and_then(|ext| ext.to_str())
+                        .is_some_and(|ext| matches!(ext, "yml" | "yaml"))
+            })
+            .collect::<Vec<_>>();
+        workflows.sort();
+        paths.extend(workflows);
+    }
+
+    let walker = FileWalker::with_filter(FilterOptions {
+        extensions: vec!["toml".to_string(), "json".to_string()],
+        exclude_directories: discovery
+            .exclude_directories
+            .iter()
+            .cloned()
+            .chain(std::iter::once("node_modules".to_string()))
+            .collect(),
+        exclude_paths: discovery.exclude_paths.clone(),
+        limits: discovery.walk_limits,
+    });
+    let mut manifests = walker
+        .walk(project_root.to_path_buf())?
+        .into_iter()
+        .map(|file| file.path)
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| config.manifests.iter().any(|m| m == name))
+        })
+        .collect::<Vec<_>>();
+    // Shallow manifests first: the workspace root says more about the project than a fixture.
+    manifests.sort_by_key(|path| (path.components().count(), path.clone()));
+    manifests.truncate(config.max_manifests);
+    paths.extend(manifests);
+
+    let mut metadata = Vec::with_capacity(paths.len());
+    for path in paths {
+        let relative_path = relative_path_display(&path, project_root);
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) => {
+                warn!(metadata_file = %relative_path, error = %err, "failed reading project metadata; skipping file");
+                continue;
+            }
+        };
+        let truncated = content.chars().count() > config.max_file_chars;
+        let content = if truncated {
+            content.chars().take(config.max_file_chars).collect()
+        } else {
+            content
+        };
+        metadata.push(MetadataFile {
             relative_path,
-            language: language.to_string(),
-            hash,
-            source_index,
-            memory: file_memory,
+            content,
+            truncated,
         });
     }
 
+    if !metadata.is_empty() {
+        info!(
+            metadata_file_count = metadata.len(),
+            "project_metadata_discovered"
+        );
+    }
+    Ok(metadata)
+}
+
+pub(crate) fn exclude_test_code(parsed_files: &mut Vec<ParsedFile>) {
+    let before = parsed_files.len();
+    parsed_files.retain(|parsed| !parsed.memory.is_test_file);
+
+    let mut dropped_symbols = 0usize;
+    for parsed in parsed_files.iter_mut() {
+        let symbol_count = parsed.memory.symbols.len();
+        parsed.memory.symbols.retain(|symbol| !symbol.is_test);
+        parsed.memory.symbol_count = parsed.memory.symbols.len();
+        dropped_symbols += symbol_count - parsed.memory.symbols.len();
+    }
+
     info!(
-        total_files = files.len(),
-        parsed_files = parsed_files.len(),
-        skipped_files = skipped_file_count,
-        "ingest_complete"
+        excluded_files = before - parsed_files.len(),
+        excluded_symbols = dropped_symbols,
+        "test_code_excluded"
     );
-
-    Ok(parsed_files)
 }
 
-pub(crate) fn update_meta_for_files(
+pub(crate) fn update_meta_for_files<'a>(
     manager: &ProjectContext,
     meta: &mut MetaCache,
-    parsed_files: &[ParsedFile],
+    parsed_files: impl IntoIterator<Item = &'a ParsedFile>,
+    profile_history: &ProfileHistory,
 ) -> Result<()> {
     for parsed in parsed_files {
         meta.files.insert(
             parsed.relative_path.clone(),
             FileMeta {
                 hash: parsed.hash.clone(),
+                symbols: parsed.fingerprints.clone(),
+                prompt_profiles: profile_history.for_file(&parsed.relative_path),
             },
         );
     }
@@ -112,28 +787,6 @@ pub(crate) fn update_meta_for_files(
     manager.save_meta(meta)
 }
 
-fn detect_language(path: &Path) -> &'static str {
-    match path
-        .extension()
-        .and_then(|ext| ext.to_str())
-        .unwrap_or_default()
-        .to_ascii_lowercase()
-        .as_str()
-    {
-        "rs" => "rust",
-        "py" => "python",
-        "js" | "jsx" => "javascript",
-        "ts" | "tsx" => "typescript",
-        "go" => "go",
-        "java" => "java",
-        "kt" => "kotlin",
-        "cs" => "csharp",
-        "c" | "h" => "c",
-        "cc" | "cpp" | "hpp" => "cpp",
-        _ => "text",
-    }
-}
-
 fn relative_path_display(path: &Path, project_root: &Path) -> String {
     path.strip_prefix(project_root)
         .unwrap_or(path)
@@ -1,126 +1,888 @@
+mod backpressure;
+mod changes;
+mod digest;
+mod doc_index;
+mod failures;
 mod generate;
+pub mod hooks;
 mod ingest;
+mod model_upgrade;
+mod plan;
+mod priority;
+pub mod progress;
+mod project_hash;
+mod quality;
+mod questions;
+mod renames;
+mod review;
+mod rollup;
+mod run_report;
+mod schedule;
+mod skip_list;
+mod snapshot;
+mod stale;
+mod trends;
 mod types;
+mod validation;
 
-use std::{collections::BTreeSet, fs, path::PathBuf};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
 
+use tokio_util::sync::CancellationToken;
 use tracing::{info, warn};
 
 use crate::{
-    config::PlainSightConfig,
+    benchmarks::{self, PerformanceNote},
+    concurrency,
+    config::{
+        GenerationMode, InjectionGuard, PlainSightConfig, SourceDiscoveryConfig, TestCodeMode,
+    },
+    env_vars::{self, EnvVarUsage},
     error::{PlainSightError, Result},
+    error_catalog,
+    features::{self, CrateFeatures},
+    git_changes,
+    injection_guard::{self, Redaction},
     memory::{self, ProjectMemory},
-    ollama::{OllamaWrapper, Task},
-    project_manager::ProjectManager,
+    model_stats,
+    module_tree::{self, ModuleTreeIndex},
+    ollama::{LlmBackend, OllamaWrapper, Task},
+    openapi::{self, OpenApiSpec},
+    org_index,
+    ownership,
+    page_split,
+    path_selection::PathSelection,
+    project_manager::{MetaCache, ProjectContext, ProjectManager, RunCheckpoint},
+    proto::{self, ProtoFile},
+    run_plan::RunPlan,
+    rustdoc::{self, RustdocCrate, RustdocItem},
+    unsafe_code,
 };
 
-use types::ParsedFile;
+use backpressure::Backpressure;
+use hooks::{PipelineHook, PipelineStage, StageContext};
+use progress::{NoProgress, ProgressSink};
+use quality::QualityReport;
+use schedule::ModelSchedule;
+use snapshot::SourceSnapshot;
+use types::{
+    Checkpoint, FailedFiles, FinishedFiles, GenerationContext, ParsedFile, PrefetchStats,
+    ProfileHistory, PromptPrefetch, SkippedFiles,
+};
+
+/// How the embedding program stops a run, follows it and adds steps to it.
+#[derive(Clone, Copy)]
+pub(crate) struct RunControl<'a> {
+    pub(crate) cancel: &'a CancellationToken,
+    pub(crate) progress: &'a dyn ProgressSink,
+    pub(crate) hooks: &'a [Arc<dyn PipelineHook>],
+}
+
+/// Whether `run_project` generates, reporting to the progress sink and calling the hooks of
+/// `RunControl`, or only plans.
+enum RunMode<'a> {
+    Generate(RunControl<'a>),
+    Plan,
+}
 
 pub(crate) async fn run_with_manager(
     manager: &ProjectManager,
     config: &PlainSightConfig,
     project_name: &str,
-    project_root: &std::path::Path,
+    project_root: &Path,
+    scope: Option<&Path>,
+    backend: Option<&Arc<dyn LlmBackend>>,
+    control: RunControl<'_>,
 ) -> Result<()> {
-    let project = manager.new_project(project_name, project_root);
+    // The docs directory was claimed when the snapshot was taken, from the indexed tree.
+    let project_name = if config.from_index {
+        project_name.to_string()
+    } else {
+        manager.bind_project_name(project_name, project_root, config.name_collision)?
+    };
+    let project_name = project_name.as_str();
+    let wrapper = task_wrapper(config, project_name, project_root, backend)?
+        .with_cancellation(control.cancel.clone());
+    let result = run_project(
+        manager,
+        config,
+        project_name,
+        project_root,
+        scope,
+        &wrapper,
+        RunMode::Generate(control),
+    )
+    .await
+    .map(|_| ());
+    if config.model_stats.enabled {
+        let outcomes = wrapper.request_log().outcomes();
+        model_stats::record_run(manager, &config.model_stats, &outcomes).await;
+    }
+    result
+}
+
+/// Discover, parse and hash the project as `run_with_manager` would, and plan the generation
+/// from the prompts it would send, without sending them or writing to the docs root.
+pub(crate) async fn plan_with_manager(
+    manager: &ProjectManager,
+    config: &PlainSightConfig,
+    project_name: &str,
+    project_root: &Path,
+    scope: Option<&Path>,
+    backend: Option<&Arc<dyn LlmBackend>>,
+) -> Result<RunPlan> {
+    let manager = manager.dry_run();
+    let project_name = if config.from_index {
+        project_name.to_string()
+    } else {
+        manager.bind_project_name(project_name, project_root, config.name_collision)?
+    };
+    let project_name = project_name.as_str();
+    let wrapper = task_wrapper(config, project_name, project_root, backend)?;
+    let plan = run_project(
+        &manager,
+        config,
+        project_name,
+        project_root,
+        scope,
+        &wrapper,
+        RunMode::Plan,
+    )
+    .await?;
+    Ok(plan.unwrap_or_else(|| RunPlan {
+        project_name: project_name.to_string(),
+        ..RunPlan::default()
+    }))
+}
+
+fn task_wrapper(
+    config: &PlainSightConfig,
+    project_name: &str,
+    project_root: &Path,
+    backend: Option<&Arc<dyn LlmBackend>>,
+) -> Result<OllamaWrapper> {
+    let ollama_config = config
+        .ollama
+        .clone()
+        .with_prompt_overrides_from(project_root, project_name)?;
+    Ok(match backend {
+        Some(backend) => OllamaWrapper::with_backend(ollama_config, Arc::clone(backend)),
+        None => OllamaWrapper::with_config(ollama_config)?,
+    })
+}
+
+/// Run the project's generation, or with `RunMode::Plan` only plan it: the LLM server is not
+/// contacted and `manager` is expected to keep its writes in memory.
+async fn run_project(
+    manager: &ProjectManager,
+    config: &PlainSightConfig,
+    project_name: &str,
+    project_root: &Path,
+    scope: Option<&Path>,
+    wrapper: &OllamaWrapper,
+    mode: RunMode<'_>,
+) -> Result<Option<RunPlan>> {
+    let dry_run = matches!(mode, RunMode::Plan);
+    let (progress, hooks): (&dyn ProgressSink, &[Arc<dyn PipelineHook>]) = match mode {
+        RunMode::Generate(control) => (control.progress, control.hooks),
+        RunMode::Plan => (&NoProgress, &[]),
+    };
+    if dry_run {
+        info!(project = %project_name, "dry run; nothing is sent to the model or written");
+    } else if config.offline {
+        info!(project = %project_name, "offline run; only parse artifacts are refreshed");
+    } else {
+        wrapper.health_check().await?;
+    }
+    let mut phases = run_report::PhaseTimes::start("ingest", progress);
+    let mut project = manager.new_project(project_name, project_root);
+    let discovery_root = match scope {
+        Some(scope) => {
+            let scope = resolve_scope(project_root, scope)?;
+            info!(project = %project_name, scope = %scope.display(), "scoped_run");
+            let discovery_root = project_root.join(&scope);
+            project = project.with_scope(scope);
+            discovery_root
+        }
+        None => project_root.to_path_buf(),
+    };
 
     info!(project = %project_name, "ensure_structure");
     project.ensure_project_structure()?;
+    // Held until the run returns, however it ends.
+    let _lock = if dry_run {
+        None
+    } else {
+        Some(project.lock_run()?)
+    };
+    let nothing_regenerated = BTreeSet::new();
+    hooks::before(
+        hooks,
+        PipelineStage::Ingest,
+        &StageContext::new(project_name, &project, &[], &nothing_regenerated),
+    )
+    .await?;
     let mut meta = project.ensure_meta_exists()?;
 
-    let files = ingest::discover_source_files(project_root, &config.source_discovery)?;
-    if files.is_empty() {
-        warn!(
-            project = %project_name,
-            "no source files found, skipping generation"
-        );
-        return Ok(());
+    let discovery = discovery_config(config, manager.docs_root(), project_root);
+    let snapshot = if config.from_index {
+        if project.scope().is_some() || config.changed_since.is_some() {
+            return Err(PlainSightError::InvalidState(
+                "a run from the ingest snapshot cannot be scoped or use changed_since".to_string(),
+            ));
+        }
+        SourceSnapshot::load(&project, project_root)?
+    } else {
+        let Some(snapshot) =
+            SourceSnapshot::read(&project, project_root, &discovery_root, &discovery, config)?
+        else {
+            warn!(
+                project = %project_name,
+                "no source files found, skipping generation"
+            );
+            return Ok(None);
+        };
+        snapshot
+    };
+    // A scoped snapshot would leave out the rest of the project.
+    if config.offline && !dry_run && project.scope().is_none() {
+        snapshot.save(&project)?;
+    }
+    let SourceSnapshot {
+        mut parsed_files,
+        benchmark_results,
+        manifest_owners,
+        api_specs,
+        mut protos,
+        rustdoc_crates,
+        crate_features,
+        project_metadata,
+        ..
+    } = snapshot;
+    if config.test_code == TestCodeMode::Exclude {
+        ingest::exclude_test_code(&mut parsed_files);
     }
-
-    let parsed_files = ingest::parse_project_files(&files, &project, project_root)?;
     if parsed_files.is_empty() {
         return Err(PlainSightError::InvalidState(
             "no files could be parsed for documentation generation".to_string(),
         ));
     }
-    let files_to_regenerate: BTreeSet<String> = parsed_files
+    let stale_files: BTreeSet<String> = parsed_files
         .iter()
-        .filter_map(
-            |parsed| match project.needs_generation(&parsed.path, &meta) {
+        .filter_map(|parsed| {
+            match project.needs_generation_with_hash(&parsed.path, &parsed.hash, &meta) {
                 Ok(true) => Some(Ok(parsed.relative_path.clone())),
                 Ok(false) => None,
                 Err(err) => Some(Err(err)),
-            },
-        )
+            }
+        })
         .collect::<Result<BTreeSet<_>>>()?;
+    let (mut files_to_regenerate, mut stale_left_out) =
+        select_files_to_regenerate(config, project_root, &parsed_files, stale_files)?;
+    let symbol_moves =
+        renames::detect_moves(project_root, &meta, &parsed_files, &files_to_regenerate);
+    renames::reuse_renamed_docs(
+        &project,
+        &symbol_moves,
+        &config.page_split,
+        &mut files_to_regenerate,
+    )?;
+    let mut forced_only = force_file_outputs(config, &parsed_files, &mut files_to_regenerate);
+    let skip_listed = skip_list::leave_out_skip_listed(
+        &project.load_skip_list()?,
+        &mut files_to_regenerate,
+        &mut forced_only,
+    );
+    stale_left_out.extend(skip_listed.iter().map(|entry| entry.path.clone()));
 
-    let project_memory = build_project_memory(&parsed_files);
+    let mut project_memory = match project.scope() {
+        Some(scope) => build_scoped_project_memory(&project, scope, &parsed_files)?,
+        None => build_project_memory(&parsed_files),
+    };
+    let benchmark_defs = parsed_files
+        .iter()
+        .flat_map(|parsed| {
+            parsed
+                .benchmarks
+                .iter()
+                .map(|benchmark| (parsed.relative_path.clone(), benchmark.clone()))
+        })
+        .collect::<Vec<_>>();
+    if !benchmark_defs.is_empty() {
+        let mut notes =
+            benchmarks::link_benchmarks(&benchmark_defs, &benchmark_results, &project_memory);
+        for parsed in &mut parsed_files {
+            parsed.performance = notes.remove(&parsed.relative_path).unwrap_or_default();
+        }
+    }
+    for parsed in &mut parsed_files {
+        parsed.owners = ownership::resolve_file_owners(
+            &parsed.relative_path,
+            &parsed.header_owners,
+            &manifest_owners,
+        );
+    }
+    // Handlers outside a scoped run are not parsed, so the spec can only be checked in full.
+    if !api_specs.is_empty() && project.scope().is_none() {
+        let routes = parsed_files
+            .iter()
+            .flat_map(|parsed| {
+                parsed
+                    .routes
+                    .iter()
+                    .map(|route| (parsed.relative_path.clone(), route.clone()))
+            })
+            .collect::<Vec<_>>();
+        let sync_items = openapi::check_sync(&api_specs, &routes);
+        info!(
+            detected_routes = routes.len(),
+            mismatches = sync_items.len(),
+            "api_spec_sync_checked"
+        );
+        project_memory.open_items.extend(sync_items);
+    }
+    if !protos.is_empty() {
+        let chunks = parsed_files.iter().flat_map(|parsed| {
+            parsed
+                .source_index
+                .chunks
+                .iter()
+                .map(|chunk| (parsed.relative_path.as_str(), chunk.content.as_str()))
+        });
+        proto::link_usages(&mut protos, chunks);
+        // Usages are only complete when the whole project was parsed.
+        if project.scope().is_none() {
+            persist_rpc_reference(&project, project_name, &protos)?;
+        }
+    }
+    let env_reads = parsed_files
+        .iter()
+        .flat_map(|parsed| {
+            parsed
+                .env_reads
+                .iter()
+                .map(|read| (parsed.relative_path.clone(), read.clone()))
+        })
+        .collect::<Vec<_>>();
+    let env_inventory = env_vars::compile_inventory(&env_reads);
+    let mut rustdoc_items: BTreeMap<String, Vec<RustdocItem>> = BTreeMap::new();
+    for item in rustdoc_crates.iter().flat_map(|krate| krate.items.iter()) {
+        rustdoc_items
+            .entry(item.file.clone())
+            .or_default()
+            .push(item.clone());
+    }
+    if project.scope().is_none() {
+        persist_configuration(&project, project_name, &env_inventory)?;
+        if !crate_features.is_empty() {
+            persist_feature_matrix(&project, project_name, &crate_features, &parsed_files)?;
+        }
+        persist_error_catalog(&project, project_name, &parsed_files)?;
+        if !rustdoc_crates.is_empty() {
+            persist_api_parity(&project, project_name, &rustdoc_crates, &parsed_files)?;
+        }
+        if parsed_files.iter().any(|parsed| parsed.language == "rust") {
+            persist_unsafe_report(&project, project_name, &parsed_files)?;
+        }
+    }
+    // The checks above read the source as it is; prompts and the persisted source index the
+    // model tools read get the neutralized chunks.
+    if config.injection_guard == InjectionGuard::Neutralize {
+        let redactions = neutralize_injections(&mut parsed_files);
+        if project.scope().is_none() {
+            persist_redaction_report(&project, project_name, &redactions)?;
+        }
+    }
+    let memory_before = if project.store().exists(&project.memory_path()) {
+        project.load_memory().ok()
+    } else {
+        None
+    };
     let memory_file_path = persist_project_memory(&project, &project_memory)?;
     let source_index_file_path = persist_source_index(&project, &parsed_files)?;
-    let project_index = build_project_index(project_name, &parsed_files)?;
-    let wrapper = OllamaWrapper::with_config(config.ollama.clone());
-
-    generate::generate_summaries(
-        &wrapper,
-        &project,
+    let module_tree = build_module_tree(&parsed_files);
+    if project.scope().is_none() {
+        persist_module_tree(&project, &module_tree)?;
+    }
+    hooks::after(
+        hooks,
+        PipelineStage::Ingest,
+        &StageContext::new(project_name, &project, &parsed_files, &files_to_regenerate),
+    )
+    .await?;
+    if config.offline && !dry_run {
+        if project.scope().is_none() && config.org_index {
+            org_index::write_org_index(manager)?;
+        }
+        run_report::write_run_report(
+            &project,
+            project_name,
+            phases,
+            &[],
+            PrefetchStats::default(),
+            &[],
+            &QualityReport::default(),
+        )?;
+        info!(
+            project = %project_name,
+            file_count = parsed_files.len(),
+            memory_file_path = %memory_file_path.display(),
+            source_index_file_path = %source_index_file_path.display(),
+            "offline run completed; generation skipped"
+        );
+        return Ok(None);
+    }
+    let project_index = build_project_index(
         project_name,
         &parsed_files,
+        &module_tree,
         &project_memory,
-        &memory_file_path,
-        &source_index_file_path,
-        &files_to_regenerate,
-    )
-    .await?;
-    generate::unload_tasks(&wrapper, &[Task::Summarize, Task::ProjectSummary]).await;
+        &api_specs,
+        &protos,
+        &env_inventory,
+    )?;
+    let annotations = questions::load_annotations(&project)?;
+    let schedule = if dry_run {
+        ModelSchedule::default()
+    } else {
+        wrapper.ensure_models(&run_tasks(config, wrapper)).await?;
+        let mut schedule = schedule::plan(wrapper).await;
+        // The architecture docs read the directory summaries written with the project summary.
+        schedule.architecture_first &= !config.directory_summaries;
+        // A run without summaries or docs has one file phase, which writes its own project page.
+        if config.mode != GenerationMode::Full {
+            schedule.pipeline_file_tasks = false;
+            schedule.defer_project_summary = false;
+        }
+        schedule
+    };
 
-    generate::generate_docs(
-        &wrapper,
+    let profile_history = ProfileHistory::from_meta(&meta, &parsed_files);
+    let prompt_prefetch = PromptPrefetch::default();
+    let finished_files = FinishedFiles::default();
+    let skipped_files = SkippedFiles::default();
+    let failed_files = FailedFiles::default();
+    let backpressure = Backpressure::default();
+    let checkpoint = start_checkpoint(&project, config, &parsed_files)?;
+    let generation_order =
+        priority::generation_order(config.generation_order, &parsed_files, &project_memory);
+    let ctx = GenerationContext {
+        config,
+        wrapper,
+        project: &project,
+        project_name,
+        parsed_files: &parsed_files,
+        generation_order: &generation_order,
+        project_memory: &project_memory,
+        memory_file_path: &memory_file_path,
+        source_index_file_path: &source_index_file_path,
+        files_to_regenerate: &files_to_regenerate,
+        forced_only: &forced_only,
+        regenerate_project_docs: files_to_regenerate.len() > forced_only.len(),
+        annotations: &annotations,
+        api_specs: &api_specs,
+        project_metadata: &project_metadata,
+        symbol_moves: &symbol_moves,
+        rustdoc_items: &rustdoc_items,
+        profile_history: &profile_history,
+        prompt_prefetch: &prompt_prefetch,
+        finished_files: &finished_files,
+        skip_listed: &skip_listed,
+        skipped_files: &skipped_files,
+        failed_files: &failed_files,
+        backpressure: &backpressure,
+        checkpoint: &checkpoint,
+        stale_left_out: &stale_left_out,
+        progress,
+        hooks,
+        schedule,
+    };
+
+    let known_files = parsed_files
+        .iter()
+        .map(|parsed| parsed.relative_path.clone())
+        .collect::<BTreeSet<_>>();
+    // A scoped, `changed_since` or `paths` run only regenerates part of the project, so model
+    // switches are left to the next full run.
+    let full_run =
+        project.scope().is_none() && config.changed_since.is_none() && config.paths.is_empty();
+    let current_models = config.ollama.tasks.models();
+    let models_changed = full_run && !meta.models.is_empty() && meta.models != current_models;
+    // A run that leaves out summaries or docs leaves the comparison to the next full run.
+    if models_changed && !dry_run && config.mode == GenerationMode::Full {
+        phases.next("model_upgrade");
+        info!(
+            previous = ?meta.models,
+            current = ?current_models,
+            "model_change_detected"
+        );
+        let compared = model_upgrade::compare_models(&ctx, &meta.models).await;
+        generate::unload_tasks(wrapper, &[Task::Summarize, Task::Documentation]).await;
+        compared?;
+    }
+    let nothing_forced = BTreeSet::new();
+    let (files_to_regenerate, forced_only) = if models_changed {
+        (&known_files, &nothing_forced)
+    } else {
+        (&files_to_regenerate, &forced_only)
+    };
+    let ctx = GenerationContext {
+        files_to_regenerate,
+        forced_only,
+        // Forced file outputs leave the project-level pages to `force.project_level`.
+        regenerate_project_docs: files_to_regenerate.len() > forced_only.len()
+            || config.force.project_level
+            || (project.scope().is_none()
+                && (meta.project_docs_stale || project_hash::project_docs_outdated(&ctx, &meta))),
+        ..ctx
+    };
+    if dry_run {
+        return plan::build_plan(&ctx, models_changed).map(Some);
+    }
+
+    let previous_files = meta.files.keys().cloned().collect::<BTreeSet<_>>();
+    let architecture_before =
+        page_split::read_page(project.store(), &project.architecture_path()).ok();
+
+    let open_questions = match generate_files(&ctx, &project_index, &mut phases).await {
+        Err(PlainSightError::Cancelled) => {
+            return save_cancelled_run(&ctx, &mut meta, phases)
+                .await
+                .map(|()| None);
+        }
+        result => result?,
+    };
+
+    phases.next("finalize");
+    let documented_files = project_memory
+        .files
+        .iter()
+        .map(|file| file.path.clone())
+        .collect::<BTreeSet<_>>();
+    let docs_regenerated = ctx
+        .files_to_regenerate
+        .iter()
+        .filter(|path| ctx.regenerates(path, Task::Documentation))
+        .cloned()
+        .collect::<BTreeSet<_>>();
+    questions::write_questions(
         &project,
         project_name,
-        &parsed_files,
-        &project_memory,
-        &memory_file_path,
-        &source_index_file_path,
-        &project_index,
-        &files_to_regenerate,
-    )
-    .await?;
-    generate::unload_tasks(&wrapper, &[Task::Documentation, Task::Architecture]).await;
+        &documented_files,
+        &docs_regenerated,
+        open_questions,
+        &annotations,
+    )?;
+    if project.scope().is_none() {
+        validation::write_validation(&ctx)?;
+    }
+    let quality = quality::score_docs(&ctx, &docs_regenerated);
 
-    ingest::update_meta_for_files(&project, &mut meta, &parsed_files)?;
+    // A run that leaves out summaries or docs leaves its files, and model switches, to a full
+    // run; its outputs stay in the checkpoint for one that resumes.
+    let full_mode = config.mode == GenerationMode::Full;
+    if full_run && full_mode {
+        meta.models = current_models;
+    }
+    if project.scope().is_none() && full_mode {
+        meta.project_docs_stale = false;
+        meta.project_docs_hash = Some(project_hash::project_docs_hash(&ctx));
+    }
+    let removed_files = if config.keep_stale {
+        Vec::new()
+    } else {
+        stale::remove_stale_docs(
+            &project,
+            project_root,
+            &mut meta,
+            &parsed_files,
+            config.from_index,
+        )?
+    };
+    changes::write_changes(&ctx, &meta, memory_before.as_ref(), &removed_files).await?;
+    if project.scope().is_none() {
+        doc_index::write_doc_indexes(&project, project_name, &parsed_files)?;
+    }
+    let up_to_date = parsed_files.iter().filter(|parsed| {
+        let path = &parsed.relative_path;
+        !stale_left_out.contains(path)
+            && !failed_files.contains(path)
+            && (full_mode || !ctx.files_to_regenerate.contains(path))
+    });
+    ingest::update_meta_for_files(&project, &mut meta, up_to_date, &profile_history)?;
+    let run_skipped = skip_list::record_skipped_files(&ctx)?;
+    if full_mode {
+        checkpoint.finish(&project)?;
+    }
+    if project.scope().is_none() {
+        trends::record_run(&project, project_name, &parsed_files, &project_memory)?;
+        if config.digest.enabled {
+            let changes = digest::RunChanges {
+                parsed_files: &parsed_files,
+                previous_files: &previous_files,
+                regenerated: ctx.files_to_regenerate,
+                architecture_before: architecture_before.as_deref(),
+            };
+            digest::publish_digest(&project, project_name, &config.digest, &changes).await?;
+        }
+        if config.org_index {
+            org_index::write_org_index(manager)?;
+        }
+    }
+
+    run_report::write_run_report(
+        &project,
+        project_name,
+        phases,
+        &wrapper.request_log().records(),
+        prompt_prefetch.stats(),
+        &run_skipped,
+        &quality,
+    )?;
+    failures::check_success_ratio(&ctx)?;
+    failures::check_quality(&ctx, &quality)?;
 
     info!(
         project = %project_name,
         file_count = parsed_files.len(),
         project_summary_path = %project.summary_path().display(),
         architecture_path = %project.architecture_path().display(),
+        trends_path = %project.trends_path().display(),
         "project documentation generation completed"
     );
 
-    Ok(())
+    Ok(None)
+}
+
+/// File summaries and docs, then the project summary and architecture, unloading each model
+/// once its phase is done. `PlainSightConfig::mode` can leave out either half.
+async fn generate_files(
+    ctx: &GenerationContext<'_>,
+    project_index: &str,
+    phases: &mut run_report::PhaseTimes<'_>,
+) -> Result<questions::FileQuestions> {
+    let GenerationContext {
+        config, wrapper, ..
+    } = *ctx;
+    if !config.mode.docs() {
+        phases.next_over_files("summaries", ctx.parsed_files.len());
+        generate::generate_summaries(ctx).await?;
+        generate::unload_tasks(wrapper, &run_tasks(config, wrapper)).await;
+        return Ok(questions::FileQuestions::new());
+    }
+    if !config.mode.summaries() {
+        phases.next_over_files("docs", ctx.parsed_files.len());
+        let open_questions = generate::generate_docs(ctx, project_index, None).await?;
+        generate::unload_tasks(wrapper, &run_tasks(config, wrapper)).await;
+        return Ok(open_questions);
+    }
+    if ctx.schedule.pipeline_file_tasks {
+        phases.next_over_files("summaries_and_docs", 2 * ctx.parsed_files.len());
+        let open_questions = generate::generate_pipelined(ctx, project_index).await?;
+        generate::unload_tasks(wrapper, &run_tasks(config, wrapper)).await;
+        Ok(open_questions)
+    } else {
+        if config.ollama.pipeline_file_tasks && !wrapper.pipelines_file_tasks() {
+            info!("summaries and docs share a model; running them one after the other");
+        }
+        phases.next_over_files("summaries", ctx.parsed_files.len());
+        let file_summaries = generate::generate_summaries(ctx).await?;
+        generate::unload_before(
+            wrapper,
+            Task::Documentation,
+            &[Task::Summarize, Task::ProjectSummary],
+        )
+        .await;
+
+        phases.next_over_files("docs", ctx.parsed_files.len());
+        let deferred_summaries = ctx
+            .schedule
+            .defer_project_summary
+            .then_some(file_summaries.as_slice());
+        let open_questions =
+            generate::generate_docs(ctx, project_index, deferred_summaries).await?;
+        let mut docs_tasks = with_review(wrapper, &[Task::Documentation, Task::Architecture]);
+        if deferred_summaries.is_some() {
+            docs_tasks.push(Task::ProjectSummary);
+        }
+        generate::unload_tasks(wrapper, &docs_tasks).await;
+        Ok(open_questions)
+    }
+}
+
+/// Every task the run sends requests for.
+fn run_tasks(config: &PlainSightConfig, wrapper: &OllamaWrapper) -> Vec<Task> {
+    let mut tasks = vec![Task::ChangeSummary];
+    if config.mode.summaries() {
+        tasks.extend([Task::Summarize, Task::ProjectSummary]);
+        if config.directory_summaries {
+            tasks.push(Task::DirectorySummary);
+        }
+    }
+    if config.mode.docs() {
+        tasks.extend(with_review(
+            wrapper,
+            &[Task::Documentation, Task::Architecture],
+        ));
+    }
+    tasks
+}
+
+/// `tasks`, and `Task::Review` when file docs are reviewed.
+fn with_review(wrapper: &OllamaWrapper, tasks: &[Task]) -> Vec<Task> {
+    let mut tasks = tasks.to_vec();
+    if wrapper.reviews_docs() {
+        tasks.push(Task::Review);
+    }
+    tasks
+}
+
+/// Unload the models and record the files whose docs were finished before the run was
+/// cancelled in the meta cache, then fail with `PlainSightError::Cancelled`. Files still
+/// waiting keep their old entries and are regenerated by the next run.
+/// The checkpoint this run starts from: with `PlainSightConfig::resume`, the outputs an
+/// unfinished run wrote, less those of files whose content changed since; otherwise none.
+fn start_checkpoint(
+    project: &ProjectContext,
+    config: &PlainSightConfig,
+    parsed_files: &[ParsedFile],
+) -> Result<Checkpoint> {
+    let models = config.ollama.tasks.models();
+    let previous = project.load_checkpoint()?;
+    let fresh = || {
+        Checkpoint::new(RunCheckpoint {
+            models: models.clone(),
+            ..RunCheckpoint::default()
+        })
+    };
+    if previous.is_empty() {
+        return Ok(fresh());
+    }
+    if !config.resume {
+        warn!(
+            summaries = previous.summaries.len(),
+            docs = previous.docs.len(),
+            checkpoint_path = %project.checkpoint_path().display(),
+            "an earlier run did not finish; its checkpoint is discarded, resume to keep its outputs"
+        );
+        return Ok(fresh());
+    }
+    if previous.models != models {
+        warn!("task models changed since the unfinished run; its outputs are generated again");
+        return Ok(fresh());
+    }
+
+    // Files outside this run's scope keep their entries for a later run.
+    let hashes = parsed_files
+        .iter()
+        .map(|parsed| (parsed.relative_path.as_str(), parsed.hash.as_str()))
+        .collect::<BTreeMap<_, _>>();
+    let unchanged = |path: &String, hash: &str| {
+        hashes
+            .get(path.as_str())
+            .is_none_or(|current| *current == hash)
+    };
+    let RunCheckpoint {
+        summaries, docs, ..
+    } = previous;
+    let summaries = summaries
+        .into_iter()
+        .filter(|(path, hash)| unchanged(path, hash))
+        .collect::<BTreeMap<_, _>>();
+    let docs = docs
+        .into_iter()
+        .filter(|(path, docs)| unchanged(path, &docs.hash))
+        .collect::<BTreeMap<_, _>>();
+    info!(
+        summaries = summaries.len(),
+        docs = docs.len(),
+        "resuming_unfinished_run"
+    );
+    Ok(Checkpoint::new(RunCheckpoint {
+        models,
+        summaries,
+        docs,
+    }))
+}
+
+async fn save_cancelled_run(
+    ctx: &GenerationContext<'_>,
+    meta: &mut MetaCache,
+    phases: run_report::PhaseTimes<'_>,
+) -> Result<()> {
+    let GenerationContext {
+        config,
+        wrapper,
+        project,
+        project_name,
+        parsed_files,
+        files_to_regenerate,
+        finished_files,
+        stale_left_out,
+        failed_files,
+        ..
+    } = *ctx;
+    generate::unload_tasks(wrapper, &run_tasks(config, wrapper)).await;
+
+    let finished = files_to_regenerate
+        .iter()
+        .filter(|path| finished_files.contains(path))
+        .count();
+    if project.scope().is_none() && finished > 0 {
+        meta.project_docs_stale = true;
+    }
+    let unchanged_or_finished = parsed_files.iter().filter(|parsed| {
+        let path = &parsed.relative_path;
+        (!files_to_regenerate.contains(path)
+            || (config.mode == GenerationMode::Full && finished_files.contains(path)))
+            && !stale_left_out.contains(path)
+            && !failed_files.contains(path)
+    });
+    ingest::update_meta_for_files(project, meta, unchanged_or_finished, ctx.profile_history)?;
+    let run_skipped = skip_list::record_skipped_files(ctx)?;
+    run_report::write_run_report(
+        project,
+        project_name,
+        phases,
+        &wrapper.request_log().records(),
+        ctx.prompt_prefetch.stats(),
+        &run_skipped,
+        &QualityReport::default(),
+    )?;
+
+    warn!(
+        project = %project_name,
+        finished,
+        remaining = files_to_regenerate.len() - finished,
+        "run cancelled; finished files recorded"
+    );
+    Err(PlainSightError::Cancelled)
 }
 
 fn persist_project_memory(
-    project: &crate::project_manager::ProjectContext,
+    project: &ProjectContext,
     project_memory: &ProjectMemory,
 ) -> Result<PathBuf> {
-    let memory_file = project.project_docs_path().join(".memory.json");
+    let memory_file = project.memory_path();
     let memory_json = serde_json::to_string_pretty(project_memory)
         .map_err(|e| PlainSightError::InvalidState(format!("serializing project memory: {e}")))?;
-    fs::write(&memory_file, memory_json).map_err(|e| {
-        PlainSightError::io(
-            format!("writing project memory '{}'", memory_file.display()),
-            e,
-        )
-    })?;
+    project
+        .store()
+        .write(&memory_file, memory_json.as_bytes())
+        .map_err(|e| {
+            PlainSightError::io(
+                format!("writing project memory '{}'", memory_file.display()),
+                e,
+            )
+        })?;
     Ok(memory_file)
 }
 
-fn persist_source_index(
-    project: &crate::project_manager::ProjectContext,
-    parsed_files: &[ParsedFile],
-) -> Result<PathBuf> {
-    let source_index_file = project.project_docs_path().join(".source_index.json");
+fn persist_source_index(project: &ProjectContext, parsed_files: &[ParsedFile]) -> Result<PathBuf> {
+    let source_index_file = project.source_index_path();
 
     let files = parsed_files
         .iter()
@@ -135,19 +897,310 @@ fn persist_source_index(
         })
         .collect::<Vec<_>>();
 
+    let mut files = files;
+    if let Some(scope) = project.scope()
+        && let Ok(existing) = project.store().read_to_string(&source_index_file)
+        && let Ok(existing) = serde_json::from_str::<serde_json::Value>(&existing)
+        && let Some(existing_files) = existing["files"].as_array()
+    {
+        // Entries outside the scope come from earlier runs and are kept as they are.
+        let outside_scope = existing_files.iter().filter(|file| {
+            file["path"]
+                .as_str()
+                .is_some_and(|path| !Path::new(path).starts_with(scope))
+        });
+        files.extend(outside_scope.cloned());
+    }
+    files.sort_by(|a, b| a["path"].as_str().cmp(&b["path"].as_str()));
+
     let content = serde_json::to_string_pretty(&serde_json::json!({ "files": files }))
         .map_err(|e| PlainSightError::InvalidState(format!("serializing source index: {e}")))?;
 
-    fs::write(&source_index_file, content).map_err(|e| {
-        PlainSightError::io(
-            format!("writing source index '{}'", source_index_file.display()),
-            e,
-        )
-    })?;
+    project
+        .store()
+        .write(&source_index_file, content.as_bytes())
+        .map_err(|e| {
+            PlainSightError::io(
+                format!("writing source index '{}'", source_index_file.display()),
+                e,
+            )
+        })?;
 
     Ok(source_index_file)
 }
 
+fn persist_module_tree(project: &ProjectContext, module_tree: &ModuleTreeIndex) -> Result<PathBuf> {
+    let module_tree_file = project.module_tree_path();
+    let content = serde_json::to_string_pretty(module_tree)
+        .map_err(|e| PlainSightError::InvalidState(format!("serializing module tree: {e}")))?;
+
+    project
+        .store()
+        .write(&module_tree_file, content.as_bytes())
+        .map_err(|e| {
+            PlainSightError::io(
+                format!("writing module tree '{}'", module_tree_file.display()),
+                e,
+            )
+        })?;
+
+    Ok(module_tree_file)
+}
+
+fn persist_rpc_reference(
+    project: &ProjectContext,
+    project_name: &str,
+    protos: &[ProtoFile],
+) -> Result<PathBuf> {
+    let rpc_file = project.rpc_path();
+    let content = proto::render_reference(project_name, protos);
+
+    project
+        .store()
+        .write(&rpc_file, content.as_bytes())
+        .map_err(|e| {
+            PlainSightError::io(format!("writing rpc reference '{}'", rpc_file.display()), e)
+        })?;
+
+    info!(rpc_path = %rpc_file.display(), "rpc_reference_written");
+    Ok(rpc_file)
+}
+
+fn persist_feature_matrix(
+    project: &ProjectContext,
+    project_name: &str,
+    crates: &[CrateFeatures],
+    parsed_files: &[ParsedFile],
+) -> Result<PathBuf> {
+    let gates = parsed_files
+        .iter()
+        .flat_map(|parsed| {
+            parsed
+                .feature_gates
+                .iter()
+                .map(|gate| (parsed.relative_path.clone(), gate.clone()))
+        })
+        .collect::<Vec<_>>();
+    let features_file = project.features_path();
+    let content = features::render_matrix(project_name, crates, &gates);
+
+    project
+        .store()
+        .write(&features_file, content.as_bytes())
+        .map_err(|e| {
+            PlainSightError::io(
+                format!("writing feature matrix '{}'", features_file.display()),
+                e,
+            )
+        })?;
+
+    info!(
+        crate_count = crates.len(),
+        feature_gates = gates.len(),
+        features_path = %features_file.display(),
+        "feature_matrix_written"
+    );
+    Ok(features_file)
+}
+
+fn persist_error_catalog(
+    project: &ProjectContext,
+    project_name: &str,
+    parsed_files: &[ParsedFile],
+) -> Result<PathBuf> {
+    let errors = parsed_files
+        .iter()
+        .flat_map(|parsed| {
+            parsed
+                .error_types
+                .iter()
+                .map(|error| (parsed.relative_path.clone(), error.clone()))
+        })
+        .collect::<Vec<_>>();
+    let chunks = parsed_files.iter().flat_map(|parsed| {
+        parsed.source_index.chunks.iter().map(|chunk| {
+            (
+                parsed.relative_path.as_str(),
+                chunk.start_line,
+                chunk.content.as_str(),
+            )
+        })
+    });
+    let catalog = error_catalog::build_catalog(errors, chunks);
+    let errors_file = project.errors_path();
+    let content = error_catalog::render_catalog(project_name, &catalog);
+
+    project
+        .store()
+        .write(&errors_file, content.as_bytes())
+        .map_err(|e| {
+            PlainSightError::io(
+                format!("writing error catalog '{}'", errors_file.display()),
+                e,
+            )
+        })?;
+
+    info!(
+        error_types = catalog.len(),
+        errors_path = %errors_file.display(),
+        "error_catalog_written"
+    );
+    Ok(errors_file)
+}
+
+fn persist_api_parity(
+    project: &ProjectContext,
+    project_name: &str,
+    crates: &[RustdocCrate],
+    parsed_files: &[ParsedFile],
+) -> Result<PathBuf> {
+    let files = parsed_files
+        .iter()
+        .filter(|parsed| parsed.language == "rust")
+        .map(|parsed| {
+            (
+                parsed.relative_path.as_str(),
+                parsed.memory.symbols.as_slice(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let parity = rustdoc::reconcile(crates, &files);
+    let parity_file = project.api_parity_path();
+    let content = rustdoc::render_report(project_name, &parity);
+
+    project
+        .store()
+        .write(&parity_file, content.as_bytes())
+        .map_err(|e| {
+            PlainSightError::io(
+                format!("writing API parity report '{}'", parity_file.display()),
+                e,
+            )
+        })?;
+
+    info!(
+        rustdoc_items = parity.rustdoc_item_count,
+        extracted_items = parity.extracted_item_count,
+        missing_from_extraction = parity.missing_from_extraction.len(),
+        missing_from_rustdoc = parity.missing_from_rustdoc.len(),
+        api_parity_path = %parity_file.display(),
+        "api_parity_written"
+    );
+    Ok(parity_file)
+}
+
+fn persist_unsafe_report(
+    project: &ProjectContext,
+    project_name: &str,
+    parsed_files: &[ParsedFile],
+) -> Result<PathBuf> {
+    let mut sites = BTreeMap::new();
+    let mut symbols = BTreeMap::new();
+    for parsed in parsed_files
+        .iter()
+        .filter(|parsed| !parsed.unsafe_sites.is_empty())
+    {
+        sites.insert(parsed.relative_path.clone(), parsed.unsafe_sites.clone());
+        symbols.insert(parsed.relative_path.clone(), parsed.memory.symbols.clone());
+    }
+    let unsafe_file = project.unsafe_path();
+    let content = unsafe_code::render_report(project_name, &sites, &symbols);
+
+    project
+        .store()
+        .write(&unsafe_file, content.as_bytes())
+        .map_err(|e| {
+            PlainSightError::io(
+                format!("writing unsafe code report '{}'", unsafe_file.display()),
+                e,
+            )
+        })?;
+
+    info!(
+        files_with_unsafe = sites.len(),
+        unsafe_sites = sites.values().map(Vec::len).sum::<usize>(),
+        unsafe_path = %unsafe_file.display(),
+        "unsafe_report_written"
+    );
+    Ok(unsafe_file)
+}
+
+/// Neutralize prompt-injection attempts in every file's source chunks and return what was
+/// changed, by file.
+fn neutralize_injections(parsed_files: &mut [ParsedFile]) -> BTreeMap<String, Vec<Redaction>> {
+    let mut redactions = BTreeMap::new();
+    for parsed in parsed_files {
+        let found = injection_guard::sanitize_source_index(&mut parsed.source_index);
+        if found.is_empty() {
+            continue;
+        }
+        warn!(
+            target_file = %parsed.relative_path,
+            redacted_lines = found.len(),
+            first_line = found[0].line,
+            "instruction-like text in source neutralized before prompting"
+        );
+        redactions.insert(parsed.relative_path.clone(), found);
+    }
+    redactions
+}
+
+fn persist_redaction_report(
+    project: &ProjectContext,
+    project_name: &str,
+    redactions: &BTreeMap<String, Vec<Redaction>>,
+) -> Result<PathBuf> {
+    let redactions_file = project.redactions_path();
+    let content = injection_guard::render_report(project_name, redactions);
+
+    project
+        .store()
+        .write(&redactions_file, content.as_bytes())
+        .map_err(|e| {
+            PlainSightError::io(
+                format!("writing redaction report '{}'", redactions_file.display()),
+                e,
+            )
+        })?;
+
+    info!(
+        files_with_redactions = redactions.len(),
+        redacted_lines = redactions.values().map(Vec::len).sum::<usize>(),
+        redactions_path = %redactions_file.display(),
+        "redaction_report_written"
+    );
+    Ok(redactions_file)
+}
+
+fn persist_configuration(
+    project: &ProjectContext,
+    project_name: &str,
+    env_inventory: &[EnvVarUsage],
+) -> Result<PathBuf> {
+    let configuration_file = project.configuration_path();
+    let content = env_vars::render_inventory(project_name, env_inventory);
+
+    project
+        .store()
+        .write(&configuration_file, content.as_bytes())
+        .map_err(|e| {
+            PlainSightError::io(
+                format!(
+                    "writing configuration inventory '{}'",
+                    configuration_file.display()
+                ),
+                e,
+            )
+        })?;
+
+    info!(
+        variable_count = env_inventory.len(),
+        configuration_path = %configuration_file.display(),
+        "configuration_inventory_written"
+    );
+    Ok(configuration_file)
+}
+
 fn build_project_memory(parsed_files: &[ParsedFile]) -> ProjectMemory {
     let files = parsed_files
         .iter()
@@ -156,20 +1209,250 @@ fn build_project_memory(parsed_files: &[ParsedFile]) -> ProjectMemory {
     memory::build_project_memory(&files)
 }
 
-fn build_project_index(project_name: &str, parsed_files: &[ParsedFile]) -> Result<String> {
+/// Merge freshly parsed scope files into the memory persisted by earlier runs, so prompts still
+/// see symbols and links from the rest of the project.
+fn build_scoped_project_memory(
+    project: &ProjectContext,
+    scope: &Path,
+    parsed_files: &[ParsedFile],
+) -> Result<ProjectMemory> {
+    let memory_path = project.memory_path();
+    let mut files = if project.store().exists(&memory_path) {
+        project.load_memory()?.files
+    } else {
+        warn!(
+            memory_path = %memory_path.display(),
+            "no project memory from a full run; cross-file context is limited to the scope"
+        );
+        Vec::new()
+    };
+
+    files.retain(|file| !Path::new(&file.path).starts_with(scope));
+    files.extend(parsed_files.iter().map(|parsed| parsed.memory.clone()));
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    info!(
+        scope_files = parsed_files.len(),
+        project_files = files.len(),
+        "scoped_memory_merged"
+    );
+    Ok(memory::build_project_memory(&files))
+}
+
+/// `config.source_discovery`, also excluding the docs root when it lives inside the project,
+/// so generated output is never picked up as source whatever the directory is called.
+fn discovery_config(
+    config: &PlainSightConfig,
+    docs_root: &Path,
+    project_root: &Path,
+) -> SourceDiscoveryConfig {
+    let mut discovery = config.source_discovery.clone();
+    if let (Ok(docs_root), Ok(project_root)) =
+        (docs_root.canonicalize(), project_root.canonicalize())
+        && docs_root.starts_with(&project_root)
+    {
+        info!(docs_root = %docs_root.display(), "docs_root_inside_project_excluded");
+        discovery.exclude_paths.push(docs_root);
+    }
+    discovery
+}
+
+/// The files to regenerate: the stale ones, or those `changed_since` and `paths` select. Also
+/// returns the stale files the selection left out.
+fn select_files_to_regenerate(
+    config: &PlainSightConfig,
+    project_root: &Path,
+    parsed_files: &[ParsedFile],
+    stale_files: BTreeSet<String>,
+) -> Result<(BTreeSet<String>, BTreeSet<String>)> {
+    if config.changed_since.is_none() && config.paths.is_empty() {
+        return Ok((stale_files, BTreeSet::new()));
+    }
+    let mut selected = parsed_files
+        .iter()
+        .map(|parsed| parsed.relative_path.clone())
+        .collect::<BTreeSet<_>>();
+    if let Some(rev) = &config.changed_since {
+        let changed = git_changes::changed_files(project_root, rev)?;
+        selected.retain(|path| changed.contains(path));
+        info!(
+            rev = %rev,
+            changed = changed.len(),
+            selected = selected.len(),
+            "changed_since_selection"
+        );
+    }
+    if !config.paths.is_empty() {
+        let selection = PathSelection::new(project_root, &config.paths)?;
+        let matched = parsed_files
+            .iter()
+            .filter(|parsed| selection.matches(&parsed.relative_path))
+            .count();
+        if matched == 0 {
+            return Err(PlainSightError::InvalidState(format!(
+                "no source files match {}",
+                config
+                    .paths
+                    .iter()
+                    .map(|path| format!("'{}'", path.display()))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )));
+        }
+        selected.retain(|path| selection.matches(path));
+        info!(
+            paths = ?config.paths,
+            matched,
+            selected = selected.len(),
+            "path_selection"
+        );
+    }
+    let stale_left_out = stale_files
+        .difference(&selected)
+        .cloned()
+        .collect::<BTreeSet<_>>();
+    info!(
+        stale_left_out = stale_left_out.len(),
+        "stale_files_left_out"
+    );
+    Ok((selected, stale_left_out))
+}
+
+/// With `PlainSightConfig::force` forcing file summaries or docs, add every parsed file to
+/// `files_to_regenerate`, unless `changed_since` or `paths` already chose the files. Returns
+/// the files added, which keep the output that is not forced.
+fn force_file_outputs(
+    config: &PlainSightConfig,
+    parsed_files: &[ParsedFile],
+    files_to_regenerate: &mut BTreeSet<String>,
+) -> BTreeSet<String> {
+    let force = config.force;
+    let selected = config.changed_since.is_some() || !config.paths.is_empty();
+    if !(force.summaries || force.docs) || selected {
+        return BTreeSet::new();
+    }
+    let forced = parsed_files
+        .iter()
+        .map(|parsed| &parsed.relative_path)
+        .filter(|path| !files_to_regenerate.contains(*path))
+        .cloned()
+        .collect::<BTreeSet<_>>();
+    files_to_regenerate.extend(forced.iter().cloned());
+    info!(
+        summaries = force.summaries,
+        docs = force.docs,
+        forced = forced.len(),
+        "file_outputs_forced"
+    );
+    forced
+}
+
+/// Normalize `scope` to a path relative to `project_root` and make sure it is a directory inside it.
+fn resolve_scope(project_root: &Path, scope: &Path) -> Result<PathBuf> {
+    let relative = if scope.is_absolute() {
+        scope
+            .strip_prefix(project_root)
+            .map(Path::to_path_buf)
+            .map_err(|_| PlainSightError::PathOutsideProject {
+                path: scope.to_path_buf(),
+                project_root: project_root.to_path_buf(),
+            })?
+    } else {
+        scope.to_path_buf()
+    };
+
+    let mut normalized = PathBuf::new();
+    for component in relative.components() {
+        match component {
+            Component::Normal(part) => normalized.push(part),
+            Component::CurDir => {}
+            _ => {
+                return Err(PlainSightError::PathOutsideProject {
+                    path: scope.to_path_buf(),
+                    project_root: project_root.to_path_buf(),
+                });
+            }
+        }
+    }
+
+    if !project_root.join(&normalized).is_dir() {
+        return Err(PlainSightError::InvalidState(format!(
+            "scope '{}' is not a directory under '{}'",
+            scope.display(),
+            project_root.display()
+        )));
+    }
+
+    Ok(normalized)
+}
+
+fn build_module_tree(parsed_files: &[ParsedFile]) -> ModuleTreeIndex {
+    let declarations = parsed_files
+        .iter()
+        .filter(|parsed| parsed.language == "rust")
+        .map(|parsed| {
+            (
+                parsed.relative_path.clone(),
+                parsed.mod_declarations.clone(),
+            )
+        })
+        .collect::<BTreeMap<_, _>>();
+    module_tree::build_module_trees(&declarations)
+}
+
+fn build_project_index(
+    project_name: &str,
+    parsed_files: &[ParsedFile],
+    module_tree: &ModuleTreeIndex,
+    project_memory: &ProjectMemory,
+    api_specs: &[OpenApiSpec],
+    protos: &[ProtoFile],
+    env_inventory: &[EnvVarUsage],
+) -> Result<String> {
     let mut files = Vec::with_capacity(parsed_files.len());
+    let mut concurrency_facts = Vec::new();
+    let mut benchmarks: BTreeMap<&str, PerformanceNote> = BTreeMap::new();
 
     for parsed in parsed_files {
         files.push(serde_json::json!({
             "path": parsed.relative_path,
             "symbols": &parsed.source_index,
         }));
+        concurrency_facts.extend(
+            parsed
+                .concurrency
+                .iter()
+                .map(|fact| (parsed.relative_path.clone(), fact.clone())),
+        );
+        for note in &parsed.performance {
+            benchmarks
+                .entry(&note.benchmark)
+                .or_insert_with(|| PerformanceNote {
+                    exercises: Vec::new(),
+                    ..note.clone()
+                })
+                .exercises
+                .extend(
+                    note.exercises
+                        .iter()
+                        .map(|symbol| format!("{symbol} ({})", parsed.relative_path)),
+                );
+        }
     }
 
+    files.sort_by(|a, b| a["path"].as_str().cmp(&b["path"].as_str()));
+
     serde_json::to_string_pretty(&serde_json::json!({
         "project": project_name,
         "file_count": parsed_files.len(),
         "files": files,
+        "module_tree": module_tree,
+        "reexports": project_memory.reexports,
+        "openapi": api_specs,
+        "rpc": protos,
+        "environment": env_inventory,
+        "concurrency": concurrency::compile_inventory(&concurrency_facts),
+        "benchmarks": generate::performance_context(&benchmarks.into_values().collect::<Vec<_>>()),
     }))
     .map_err(|e| PlainSightError::InvalidState(format!("serializing project index: {e}")))
 }
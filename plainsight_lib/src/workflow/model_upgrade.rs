@@ -0,0 +1,433 @@
+use std::{collections::BTreeMap, path::Path};
+
+use tracing::{info, warn};
+
+use crate::{
+    config::ModelUpgradeMode,
+    error::{PlainSightError, Result},
+    ollama::{self, OllamaConfig, OllamaError},
+    page_split,
+    project_manager::ProjectContext,
+};
+
+use super::{
+    generate::build_file_prompt_input,
+    questions,
+    types::{GenerationContext, ParsedFile, PromptProfile},
+};
+
+const SAMPLE_SIZE: usize = 5;
+const SUMMARY_HEADINGS: &[&str] = &["## Purpose", "## Key Elements"];
+const DOCS_HEADINGS: &[&str] = &[
+    "## Overview",
+    "## Public API",
+    "## Behavior and Errors",
+    "## Example",
+];
+const MIN_LENGTH_RATIO: f64 = 0.5;
+const MAX_SYMBOL_COVERAGE_DROP: f64 = 0.2;
+
+#[derive(Debug, Clone)]
+struct ArtifactMetrics {
+    chars: usize,
+    headings_found: usize,
+    headings_expected: usize,
+    symbol_coverage: f64,
+    refusal: bool,
+}
+
+#[derive(Debug)]
+struct SampleComparison {
+    path: String,
+    artifact: &'static str,
+    previous: ArtifactMetrics,
+    candidate: Option<ArtifactMetrics>,
+    flags: Vec<String>,
+    /// Why the candidate could not be generated; such samples count as neither ok nor a
+    /// regression.
+    not_compared: Option<String>,
+}
+
+/// Generate a sample of files with the new models side by side with the previous outputs and
+/// write a regression report.
+///
+/// Previous artifacts stay in place and are copied to `model_upgrade/previous/`; candidates
+/// land in `model_upgrade/candidate/`. Samples whose candidate fails to generate are reported as
+/// not compared. In [`ModelUpgradeMode::Guard`] any regression or sample not compared stops the
+/// run before the whole project is regenerated.
+pub(crate) async fn compare_models(
+    ctx: &GenerationContext<'_>,
+    previous_models: &BTreeMap<String, String>,
+) -> Result<()> {
+    let GenerationContext {
+        config,
+        wrapper,
+        project,
+        project_name,
+        parsed_files,
+        ..
+    } = *ctx;
+    let current_models = config.ollama.tasks.models();
+
+    if config.model_upgrade == ModelUpgradeMode::Off {
+        info!("model_upgrade_check_disabled");
+        return Ok(());
+    }
+
+    let samples = select_samples(project, parsed_files)?;
+    if samples.is_empty() {
+        info!("model_upgrade_no_baseline");
+        return Ok(());
+    }
+    info!(
+        sample_count = samples.len(),
+        "model_upgrade_comparison_start"
+    );
+
+    let upgrade_dir = project.model_upgrade_dir();
+    let mut comparisons = Vec::with_capacity(samples.len() * 2);
+
+    for (parsed, previous_summary, previous_docs) in samples {
+        let previous_dir = upgrade_dir.join("previous").join(&parsed.relative_path);
+        let candidate_dir = upgrade_dir.join("candidate").join(&parsed.relative_path);
+        write_artifact(project, &previous_dir, "summary.md", &previous_summary)?;
+        write_artifact(project, &previous_dir, "docs.md", &previous_docs)?;
+
+        let input = build_file_prompt_input(ctx, parsed, PromptProfile::Standard)?;
+
+        let candidate_summary = wrapper.summarize(&input).await.map(|reply| reply.text);
+        if let Ok(summary) = &candidate_summary {
+            write_artifact(project, &candidate_dir, "summary.md", summary)?;
+        }
+        comparisons.push(compare(
+            &config.ollama,
+            parsed,
+            "summary",
+            SUMMARY_HEADINGS,
+            &previous_summary,
+            candidate_summary,
+        ));
+
+        let candidate_docs = wrapper
+            .document(&input)
+            .await
+            .map(|docs| questions::split_open_questions(&docs.text).0);
+        if let Ok(docs) = &candidate_docs {
+            write_artifact(project, &candidate_dir, "docs.md", docs)?;
+        }
+        comparisons.push(compare(
+            &config.ollama,
+            parsed,
+            "docs",
+            DOCS_HEADINGS,
+            &previous_docs,
+            candidate_docs,
+        ));
+    }
+
+    let regressions = comparisons.iter().filter(|c| !c.flags.is_empty()).count();
+    let not_compared = comparisons
+        .iter()
+        .filter(|c| c.not_compared.is_some())
+        .count();
+    let report_path = project.model_upgrade_report_path();
+    let report = render_report(
+        project_name,
+        previous_models,
+        &current_models,
+        &comparisons,
+        config.model_upgrade,
+    );
+    project
+        .store()
+        .write(&report_path, report.as_bytes())
+        .map_err(|e| {
+            PlainSightError::io(
+                format!("writing model upgrade report '{}'", report_path.display()),
+                e,
+            )
+        })?;
+
+    info!(
+        compared_artifacts = comparisons.len(),
+        regressions,
+        not_compared,
+        report_path = %report_path.display(),
+        "model_upgrade_comparison_complete"
+    );
+
+    if regressions > 0 || not_compared > 0 {
+        // A sample that could not be compared may hide a regression, so it blocks too.
+        if config.model_upgrade == ModelUpgradeMode::Guard {
+            return Err(PlainSightError::InvalidState(format!(
+                "model upgrade regressed {regressions} and could not compare {not_compared} \
+                 sample artifact(s); see '{}', and run in report-only mode to switch over anyway",
+                report_path.display()
+            )));
+        }
+        warn!(
+            regressions,
+            not_compared, "model upgrade not cleared by the samples; switching over anyway"
+        );
+    }
+
+    Ok(())
+}
+
+/// Pick the files with the most symbols among those that already have both artifacts.
+fn select_samples<'a>(
+    project: &ProjectContext,
+    parsed_files: &'a [ParsedFile],
+) -> Result<Vec<(&'a ParsedFile, String, String)>> {
+    let mut candidates = Vec::new();
+    for parsed in parsed_files {
+        let summary = read_non_empty(project, &project.file_summary_path(&parsed.path)?);
+        let docs = read_non_empty(project, &project.file_docs_path(&parsed.path)?);
+        if let (Some(summary), Some(docs)) = (summary, docs) {
+            candidates.push((parsed, summary, docs));
+        }
+    }
+
+    candidates.sort_by(|a, b| {
+        b.0.memory
+            .symbol_count
+            .cmp(&a.0.memory.symbol_count)
+            .then_with(|| a.0.relative_path.cmp(&b.0.relative_path))
+    });
+    candidates.truncate(SAMPLE_SIZE);
+    Ok(candidates)
+}
+
+fn read_non_empty(project: &ProjectContext, path: &Path) -> Option<String> {
+    page_split::read_page(project.store(), path)
+        .ok()
+        .filter(|content| !content.trim().is_empty())
+}
+
+fn write_artifact(project: &ProjectContext, dir: &Path, name: &str, content: &str) -> Result<()> {
+    project
+        .store()
+        .create_dir_all(dir)
+        .map_err(|e| PlainSightError::io(format!("creating directory '{}'", dir.display()), e))?;
+    let path = dir.join(name);
+    project
+        .store()
+        .write(&path, content.as_bytes())
+        .map_err(|e| PlainSightError::io(format!("writing artifact '{}'", path.display()), e))
+}
+
+fn compare(
+    ollama_config: &OllamaConfig,
+    parsed: &ParsedFile,
+    artifact: &'static str,
+    headings: &[&str],
+    previous: &str,
+    candidate: Result<String>,
+) -> SampleComparison {
+    let previous = measure(ollama_config, parsed, headings, previous);
+    let mut flags = Vec::new();
+    let mut not_compared = None;
+
+    let candidate = match candidate {
+        Ok(output) if output.trim().is_empty() => {
+            flags.push("empty output".to_string());
+            None
+        }
+        Ok(output) => Some(measure(ollama_config, parsed, headings, &output)),
+        Err(PlainSightError::Ollama(OllamaError::EmptyOutput { .. })) => {
+            flags.push("empty output".to_string());
+            None
+        }
+        // The wrapper rejects refusals; measure the reply so the report shows it as one.
+        Err(PlainSightError::Ollama(OllamaError::RefusalDetected { output, .. })) => {
+            Some(measure(ollama_config, parsed, headings, &output))
+        }
+        // A failed request says nothing about the new model's output.
+        Err(err) => {
+            not_compared = Some(format!("generation failed: {err}"));
+            None
+        }
+    };
+
+    if let Some(candidate) = &candidate {
+        if candidate.refusal {
+            flags.push("refusal".to_string());
+        }
+        if candidate.headings_found < previous.headings_found {
+            flags.push(format!(
+                "missing sections ({}/{} vs {}/{})",
+                candidate.headings_found,
+                candidate.headings_expected,
+                previous.headings_found,
+                previous.headings_expected
+            ));
+        }
+        if (candidate.chars as f64) < previous.chars as f64 * MIN_LENGTH_RATIO {
+            flags.push(format!(
+                "output shrank to {} chars from {}",
+                candidate.chars, previous.chars
+            ));
+        }
+        if previous.symbol_coverage - candidate.symbol_coverage > MAX_SYMBOL_COVERAGE_DROP {
+            flags.push(format!(
+                "symbol coverage dropped to {:.0}% from {:.0}%",
+                candidate.symbol_coverage * 100.0,
+                previous.symbol_coverage * 100.0
+            ));
+        }
+    }
+
+    SampleComparison {
+        path: parsed.relative_path.clone(),
+        artifact,
+        previous,
+        candidate,
+        flags,
+        not_compared,
+    }
+}
+
+fn measure(
+    ollama_config: &OllamaConfig,
+    parsed: &ParsedFile,
+    headings: &[&str],
+    output: &str,
+) -> ArtifactMetrics {
+    let symbols = parsed
+        .memory
+        .symbols
+        .iter()
+        .filter(|symbol| !symbol.is_test)
+        .map(|symbol| symbol.name.as_str())
+        .collect::<Vec<_>>();
+    let mentioned = symbols
+        .iter()
+        .filter(|name| output.contains(**name))
+        .count();
+
+    ArtifactMetrics {
+        chars: output.chars().count(),
+        headings_found: headings.iter().filter(|h| output.contains(**h)).count(),
+        headings_expected: headings.len(),
+        symbol_coverage: if symbols.is_empty() {
+            1.0
+        } else {
+            mentioned as f64 / symbols.len() as f64
+        },
+        refusal: ollama::is_refusal_output(output, ollama_config),
+    }
+}
+
+fn render_report(
+    project_name: &str,
+    previous_models: &BTreeMap<String, String>,
+    current_models: &BTreeMap<String, String>,
+    comparisons: &[SampleComparison],
+    mode: ModelUpgradeMode,
+) -> String {
+    let regressions = comparisons.iter().filter(|c| !c.flags.is_empty()).count();
+    let not_compared = comparisons
+        .iter()
+        .filter(|c| c.not_compared.is_some())
+        .count();
+    let mut out = format!("# {project_name} Model Upgrade Report\n\n");
+
+    let mut findings = match regressions {
+        0 => "No regressions detected".to_string(),
+        count => format!("{count} sample artifact(s) regressed"),
+    };
+    if not_compared > 0 {
+        findings.push_str(&format!(
+            " and {not_compared} sample artifact(s) could not be compared"
+        ));
+    }
+    let outcome = match (regressions + not_compared, mode) {
+        (0, _) => "the project was switched to the new models.",
+        (_, ModelUpgradeMode::Guard) => {
+            "the project was not switched to the new models. Run in report-only mode to switch \
+             over anyway."
+        }
+        _ => "the project was switched to the new models anyway.",
+    };
+    out.push_str(&format!("{findings}; {outcome}"));
+    out.push_str("\n\n## Models\n\n");
+    out.push_str("| Task | Previous | Current |\n");
+    out.push_str("|---|---|---|\n");
+    for (task, current) in current_models {
+        let previous = previous_models.get(task).map(String::as_str).unwrap_or("-");
+        let marker = if previous == current {
+            ""
+        } else {
+            " (changed)"
+        };
+        out.push_str(&format!("| {task} | {previous} | {current}{marker} |\n"));
+    }
+
+    out.push_str("\n## Samples\n\n");
+    out.push_str("| File | Artifact | Sections | Length | Symbol coverage | Status |\n");
+    out.push_str("|---|---|---|---|---|---|\n");
+    for comparison in comparisons {
+        let previous = &comparison.previous;
+        let (sections, length, coverage) = match &comparison.candidate {
+            Some(candidate) => (
+                format!(
+                    "{}/{} → {}/{}",
+                    previous.headings_found,
+                    previous.headings_expected,
+                    candidate.headings_found,
+                    candidate.headings_expected
+                ),
+                format!("{} → {}", previous.chars, candidate.chars),
+                format!(
+                    "{:.0}% → {:.0}%",
+                    previous.symbol_coverage * 100.0,
+                    candidate.symbol_coverage * 100.0
+                ),
+            ),
+            None => ("-".to_string(), "-".to_string(), "-".to_string()),
+        };
+        let status = if comparison.not_compared.is_some() {
+            "not compared"
+        } else if comparison.flags.is_empty() {
+            "ok"
+        } else {
+            "regression"
+        };
+        out.push_str(&format!(
+            "| {} | {} | {sections} | {length} | {coverage} | {status} |\n",
+            comparison.path, comparison.artifact
+        ));
+    }
+
+    let not_compared = comparisons
+        .iter()
+        .filter_map(|c| Some((c, c.not_compared.as_ref()?)))
+        .collect::<Vec<_>>();
+    if !not_compared.is_empty() {
+        out.push_str("\n## Not Compared\n\n");
+        for (comparison, reason) in not_compared {
+            out.push_str(&format!(
+                "- `{}` ({}): {reason}\n",
+                comparison.path, comparison.artifact
+            ));
+        }
+    }
+
+    if regressions > 0 {
+        out.push_str("\n## Regressions\n\n");
+        for comparison in comparisons.iter().filter(|c| !c.flags.is_empty()) {
+            out.push_str(&format!(
+                "- `{}` ({}): {}\n",
+                comparison.path,
+                comparison.artifact,
+                comparison.flags.join("; ")
+            ));
+        }
+    }
+
+    out.push_str(
+        "\nPrevious outputs are under `model_upgrade/previous/`, candidates under \
+         `model_upgrade/candidate/`.\n",
+    );
+    out
+}
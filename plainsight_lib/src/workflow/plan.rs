@@ -0,0 +1,75 @@
+use crate::{
+    error::Result,
+    ollama::Task,
+    run_plan::{PlannedFile, PlannedTask, RunPlan},
+};
+
+use super::{
+    generate,
+    types::{FileRequests, GenerationContext, ParsedFile},
+};
+
+/// The plan of the run `ctx` was built for, from the prompts it would send first. Nothing is
+/// sent.
+pub(super) fn build_plan(ctx: &GenerationContext<'_>, models_changed: bool) -> Result<RunPlan> {
+    let mut regenerate = Vec::new();
+    let mut reused = Vec::new();
+    for &index in ctx.generation_order {
+        let parsed = &ctx.parsed_files[index];
+        // Outputs a resumed run already wrote are kept, as are those a forced run does not
+        // force.
+        let summary = ctx.regenerates(&parsed.relative_path, Task::Summarize)
+            && !ctx.checkpoint.has_summary(parsed);
+        let docs = ctx.regenerates(&parsed.relative_path, Task::Documentation)
+            && ctx.checkpoint.docs(parsed).is_none();
+        if !summary && !docs {
+            reused.push(parsed.relative_path.clone());
+            continue;
+        }
+        regenerate.push(PlannedFile {
+            path: parsed.relative_path.clone(),
+            summary: if summary {
+                plan_task(ctx, parsed, Task::Summarize)?
+            } else {
+                PlannedTask::default()
+            },
+            docs: if docs {
+                plan_task(ctx, parsed, Task::Documentation)?
+            } else {
+                PlannedTask::default()
+            },
+        });
+    }
+    reused.sort();
+    Ok(RunPlan {
+        project_name: ctx.project_name.to_string(),
+        regenerate,
+        reused,
+        project_docs: ctx.regenerate_project_docs,
+        reviews: ctx.wrapper.reviews_docs(),
+        models_changed,
+    })
+}
+
+fn plan_task(ctx: &GenerationContext<'_>, parsed: &ParsedFile, task: Task) -> Result<PlannedTask> {
+    let start = ctx.profile_history.get(
+        &parsed.relative_path,
+        &generate::profile_history_key(ctx, task),
+    );
+    let inputs = match generate::plan_file_requests(ctx, parsed, task, start)? {
+        FileRequests::Single(_, input) => vec![input],
+        FileRequests::Split(inputs) => inputs,
+    };
+    let mut prompt_tokens = 0;
+    let mut token_limit = 0;
+    for input in &inputs {
+        let fit = ctx.wrapper.file_prompt_fit(task, input)?;
+        prompt_tokens += fit.tokens;
+        token_limit = fit.limit;
+    }
+    Ok(PlannedTask {
+        requests: inputs.len(),
+        prompt_tokens,
+        token_limit,
+    })
+}
@@ -0,0 +1,99 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
+
+use tracing::info;
+
+use crate::{
+    config::GenerationOrder,
+    memory::{FileMemory, ProjectMemory, SymbolFact},
+};
+
+use super::types::ParsedFile;
+
+/// File names that start a program or define a package's public surface.
+const ENTRY_POINTS: &[&str] = &[
+    "main.rs",
+    "lib.rs",
+    "main.go",
+    "main.py",
+    "__main__.py",
+    "app.py",
+    "index.js",
+    "index.ts",
+    "main.js",
+    "main.ts",
+    "Main.java",
+    "Main.kt",
+    "Program.cs",
+];
+
+/// Indices into `parsed_files` in the order `order` generates them in. With
+/// `GenerationOrder::Priority`, entry points come first, then files by how many other files
+/// link to them in `project_memory`, then by their count of public symbols; ties keep path
+/// order.
+pub(super) fn generation_order(
+    order: GenerationOrder,
+    parsed_files: &[ParsedFile],
+    project_memory: &ProjectMemory,
+) -> Vec<usize> {
+    let mut indices = (0..parsed_files.len()).collect::<Vec<_>>();
+    if order == GenerationOrder::Path {
+        return indices;
+    }
+
+    let mut linked_from: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    for link in &project_memory.links {
+        if link.from_file != link.to_file {
+            linked_from
+                .entry(link.to_file.as_str())
+                .or_default()
+                .insert(link.from_file.as_str());
+        }
+    }
+    indices.sort_by_cached_key(|&index| {
+        let parsed = &parsed_files[index];
+        let inbound = linked_from
+            .get(parsed.relative_path.as_str())
+            .map_or(0, BTreeSet::len);
+        (
+            !is_entry_point(&parsed.relative_path),
+            Reverse(inbound),
+            Reverse(public_symbols(&parsed.memory).count()),
+            index,
+        )
+    });
+    if let Some(&first) = indices.first() {
+        info!(
+            ?order,
+            first = %parsed_files[first].relative_path,
+            "generation_order"
+        );
+    }
+    indices
+}
+
+fn is_entry_point(relative_path: &str) -> bool {
+    Path::new(relative_path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| ENTRY_POINTS.contains(&name))
+}
+
+/// Non-test symbols visible outside their file: `pub` items in Rust, and in languages whose
+/// symbols carry no visibility, those not named with a leading underscore.
+pub(super) fn public_symbols(memory: &FileMemory) -> impl Iterator<Item = &SymbolFact> {
+    memory
+        .symbols
+        .iter()
+        .filter(|symbol| !symbol.is_test)
+        .filter(|symbol| {
+            if memory.language == "rust" {
+                symbol.details.visibility == "pub"
+            } else {
+                !symbol.name.starts_with('_')
+            }
+        })
+}
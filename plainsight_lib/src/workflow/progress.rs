@@ -0,0 +1,40 @@
+use crate::ollama::Task;
+
+/// What a run reports to a [`ProgressSink`] as it goes. Each file goes through each file task
+/// of a phase once and ends in exactly one of `FileFinished`, `FileReused` or `FileSkipped`;
+/// `FileStarted` comes first when a request is sent for it on its own.
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub enum ProgressEvent<'a> {
+    /// A phase of the run started: `ingest`, `model_upgrade`, `summaries`, `docs`,
+    /// `summaries_and_docs` or `finalize`. `file_tasks` is how many file outcomes the phase
+    /// reports, one per file and task; 0 for phases that report none.
+    PhaseStarted { phase: &'a str, file_tasks: usize },
+    /// A request for the file's summary or docs is about to be sent.
+    FileStarted { path: &'a str, task: Task },
+    /// The file's summary or docs was generated and written; `bytes` is its length.
+    FileFinished {
+        path: &'a str,
+        task: Task,
+        bytes: usize,
+    },
+    /// The file's summary or docs from an earlier run is kept.
+    FileReused { path: &'a str, task: Task },
+    /// The file has no summary or docs from this run: it is on the skip list, ran over
+    /// `OllamaConfig::file_timeout`, or the model returned nothing usable.
+    FileSkipped { path: &'a str, task: Task },
+}
+
+/// Receives a run's progress, e.g. to drive a progress bar, instead of parsing its logs. Set
+/// with `PlainSight::with_progress`; events arrive from the tasks generating files, possibly
+/// several at once.
+pub trait ProgressSink: Send + Sync {
+    fn event(&self, event: ProgressEvent<'_>);
+}
+
+/// The sink of runs that nothing watches.
+pub(crate) struct NoProgress;
+
+impl ProgressSink for NoProgress {
+    fn event(&self, _event: ProgressEvent<'_>) {}
+}
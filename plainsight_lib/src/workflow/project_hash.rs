@@ -0,0 +1,63 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+use tracing::info;
+
+use crate::{
+    ollama::Task, page_split, project_manager::MetaCache, prompt_context::PROMPT_CONTEXT_VERSION,
+};
+
+use super::types::GenerationContext;
+
+/// Hash of what the project summary and architecture are generated from besides the project
+/// index: their models and prompt overrides, the prompt context version and every file
+/// summary on disk. Directory summaries count when `PlainSightConfig::directory_summaries` is
+/// set.
+pub(super) fn project_docs_hash(ctx: &GenerationContext<'_>) -> String {
+    let GenerationContext {
+        wrapper,
+        project,
+        parsed_files,
+        ..
+    } = *ctx;
+    let mut hasher = DefaultHasher::new();
+    let mut tasks = vec![Task::ProjectSummary, Task::Architecture];
+    if ctx.config.directory_summaries {
+        tasks.push(Task::DirectorySummary);
+    }
+    for task in tasks {
+        task.name().hash(&mut hasher);
+        wrapper.model_name(task).hash(&mut hasher);
+        wrapper.prompt_override(task).hash(&mut hasher);
+    }
+    wrapper.structured_output().hash(&mut hasher);
+    PROMPT_CONTEXT_VERSION.hash(&mut hasher);
+    for parsed in parsed_files {
+        let summary = project
+            .file_summary_path(&parsed.relative_path)
+            .ok()
+            .and_then(|path| project.store().read_to_string(&path).ok());
+        parsed.relative_path.hash(&mut hasher);
+        summary.hash(&mut hasher);
+    }
+    format!("{:x}", hasher.finish())
+}
+
+/// Whether the project summary or architecture is missing, or was generated from something
+/// other than what `project_docs_hash` now hashes, so an unscoped run rewrites them even with
+/// no file to regenerate.
+pub(super) fn project_docs_outdated(ctx: &GenerationContext<'_>, meta: &MetaCache) -> bool {
+    let project = ctx.project;
+    let missing = [project.summary_path(), project.architecture_path()]
+        .iter()
+        .any(|path| {
+            page_split::read_page(project.store(), path).map_or(true, |page| page.trim().is_empty())
+        });
+    let changed = meta.project_docs_hash.as_deref() != Some(project_docs_hash(ctx).as_str());
+    if missing || changed {
+        info!(missing, changed, "project_docs_outdated");
+    }
+    missing || changed
+}
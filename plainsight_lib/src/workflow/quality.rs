@@ -0,0 +1,126 @@
+use std::collections::BTreeSet;
+
+use serde::Serialize;
+use tracing::info;
+
+use crate::{ollama, ownership, page_split};
+
+use super::{
+    priority,
+    types::{GenerationContext, ParsedFile},
+};
+
+/// Sections the docs prompt requires of every file's docs.
+const REQUIRED_SECTIONS: [&str; 4] = [
+    "## Overview",
+    "## Public API",
+    "## Behavior and Errors",
+    "## Example",
+];
+/// Word counts a file's docs can have without losing length score.
+const MIN_WORDS: usize = 40;
+const MAX_WORDS: usize = 1500;
+
+/// Heuristic quality of one file's generated docs, each part from 0.0 to 1.0.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct DocQuality {
+    pub path: String,
+    /// Mean of `sections`, `length` and `symbol_coverage`; 0.0 for a refusal.
+    pub score: f64,
+    /// Share of the required sections present.
+    pub sections: f64,
+    /// 1.0 within the word bounds, falling off proportionally outside them.
+    pub length: f64,
+    /// Share of the file's public symbols the docs mention; 1.0 when it has none.
+    pub symbol_coverage: f64,
+    pub refused: bool,
+}
+
+/// Quality scores of the docs a run regenerated.
+#[derive(Debug, Default, Serialize)]
+pub(crate) struct QualityReport {
+    /// Mean score over `files`; `None` when the run regenerated no docs.
+    pub average: Option<f64>,
+    /// Lowest score first.
+    pub files: Vec<DocQuality>,
+}
+
+/// Score the docs of `docs_regenerated`, the files whose docs this run wrote. Files whose docs
+/// failed, or cannot be read, are left out.
+pub(super) fn score_docs(
+    ctx: &GenerationContext<'_>,
+    docs_regenerated: &BTreeSet<String>,
+) -> QualityReport {
+    let project = ctx.project;
+    let mut files = ctx
+        .parsed_files
+        .iter()
+        .filter(|parsed| {
+            docs_regenerated.contains(&parsed.relative_path)
+                && !ctx.failed_files.contains(&parsed.relative_path)
+        })
+        .filter_map(|parsed| {
+            let path = project.file_docs_path(&parsed.path).ok()?;
+            let docs = page_split::read_page(project.store(), &path).ok()?;
+            Some(score_file(ctx, parsed, &docs))
+        })
+        .collect::<Vec<_>>();
+    files.sort_by(|a, b| {
+        a.score
+            .total_cmp(&b.score)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    let average = (!files.is_empty())
+        .then(|| round(files.iter().map(|file| file.score).sum::<f64>() / files.len() as f64));
+
+    info!(
+        scored_files = files.len(),
+        average_quality = average.unwrap_or_default(),
+        "docs_quality_scored"
+    );
+    QualityReport { average, files }
+}
+
+fn score_file(ctx: &GenerationContext<'_>, parsed: &ParsedFile, docs: &str) -> DocQuality {
+    let body = ownership::strip_front_matter(docs);
+    let refused = ollama::is_refusal_output(body, &ctx.config.ollama);
+    let present = REQUIRED_SECTIONS
+        .iter()
+        .filter(|section| body.lines().any(|line| line.trim_end() == **section))
+        .count();
+    let sections = present as f64 / REQUIRED_SECTIONS.len() as f64;
+    let words = body.split_whitespace().count();
+    let length = if words < MIN_WORDS {
+        words as f64 / MIN_WORDS as f64
+    } else if words > MAX_WORDS {
+        MAX_WORDS as f64 / words as f64
+    } else {
+        1.0
+    };
+    let symbols = priority::public_symbols(&parsed.memory)
+        .map(|symbol| symbol.name.as_str())
+        .collect::<BTreeSet<_>>();
+    let symbol_coverage = if symbols.is_empty() {
+        1.0
+    } else {
+        let mentioned = symbols.iter().filter(|name| body.contains(**name)).count();
+        mentioned as f64 / symbols.len() as f64
+    };
+    let score = if refused {
+        0.0
+    } else {
+        (sections + length + symbol_coverage) / 3.0
+    };
+    DocQuality {
+        path: parsed.relative_path.clone(),
+        score: round(score),
+        sections: round(sections),
+        length: round(length),
+        symbol_coverage: round(symbol_coverage),
+        refused,
+    }
+}
+
+fn round(value: f64) -> f64 {
+    (value * 1000.0).round() / 1000.0
+}
@@ -0,0 +1,189 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use tracing::info;
+
+use crate::{
+    error::{PlainSightError, Result},
+    project_manager::{Annotation, AnnotationStore, ProjectContext},
+};
+
+const QUESTIONS_HEADING: &str = "## Open Questions";
+const QUESTION_PREFIX: &str = "- Q: ";
+const ANSWER_PREFIX: &str = "Answer:";
+const MAX_QUESTIONS_PER_FILE: usize = 8;
+
+pub(crate) type FileQuestions = BTreeMap<String, Vec<String>>;
+
+/// Load stored annotations and merge in any answers filled into `questions.md` since the last
+/// run, so they reach the prompts of this run.
+pub(crate) fn load_annotations(project: &ProjectContext) -> Result<AnnotationStore> {
+    let mut annotations = project.load_annotations()?;
+    let (_, answered) = read_questions_file(project)?;
+    if answered.is_empty() {
+        return Ok(annotations);
+    }
+
+    let mut added = 0usize;
+    for (file, entries) in answered {
+        let stored = annotations.files.entry(file).or_default();
+        for entry in entries {
+            if let Some(existing) = stored.iter_mut().find(|a| a.question == entry.question) {
+                existing.answer = entry.answer;
+            } else {
+                stored.push(entry);
+                added += 1;
+            }
+        }
+    }
+    project.save_annotations(&annotations)?;
+
+    info!(added_annotations = added, "annotations_merged");
+    Ok(annotations)
+}
+
+/// Remove the model's `## Open Questions` section from generated docs and return its bullets.
+pub(crate) fn split_open_questions(docs: &str) -> (String, Vec<String>) {
+    let Some(start) = docs.find(QUESTIONS_HEADING) else {
+        return (docs.to_string(), Vec::new());
+    };
+
+    let section = &docs[start + QUESTIONS_HEADING.len()..];
+    let end = section
+        .find("\n## ")
+        .map(|idx| idx + 1)
+        .unwrap_or(section.len());
+    let questions = section[..end]
+        .lines()
+        .filter_map(|line| {
+            let item = line
+                .trim()
+                .strip_prefix("- ")
+                .or_else(|| line.trim().strip_prefix("* "))?;
+            let item = item.trim();
+            let lower = item.to_ascii_lowercase();
+            if item.is_empty() || lower == "none" || lower.starts_with("none.") {
+                return None;
+            }
+            Some(item.to_string())
+        })
+        .take(MAX_QUESTIONS_PER_FILE)
+        .collect();
+
+    let mut remaining = docs[..start].trim_end().to_string();
+    let rest = section[end..].trim();
+    if !rest.is_empty() {
+        remaining.push_str("\n\n");
+        remaining.push_str(rest);
+    }
+
+    (remaining, questions)
+}
+
+/// Rewrite `questions.md`: regenerated files get their fresh questions, other files keep their
+/// still-unanswered questions from the previous run.
+pub(crate) fn write_questions(
+    project: &ProjectContext,
+    project_name: &str,
+    known_files: &BTreeSet<String>,
+    regenerated: &BTreeSet<String>,
+    new_questions: FileQuestions,
+    annotations: &AnnotationStore,
+) -> Result<()> {
+    let (previous, _) = read_questions_file(project)?;
+
+    let mut pending: FileQuestions = previous
+        .into_iter()
+        .filter(|(file, _)| known_files.contains(file) && !regenerated.contains(file))
+        .collect();
+    pending.extend(new_questions);
+
+    for (file, questions) in pending.iter_mut() {
+        let answered = annotations.files.get(file);
+        questions.retain(|question| {
+            answered.is_none_or(|entries| entries.iter().all(|a| &a.question != question))
+        });
+    }
+    pending.retain(|_, questions| !questions.is_empty());
+
+    let path = project.questions_path();
+    let content = render_questions(project_name, &pending);
+    project
+        .store()
+        .write(&path, content.as_bytes())
+        .map_err(|e| PlainSightError::io(format!("writing questions '{}'", path.display()), e))?;
+
+    info!(
+        files_with_questions = pending.len(),
+        question_count = pending.values().map(Vec::len).sum::<usize>(),
+        questions_path = %path.display(),
+        "questions_updated"
+    );
+    Ok(())
+}
+
+fn render_questions(project_name: &str, pending: &FileQuestions) -> String {
+    let mut out = format!("# {project_name} Open Questions\n\n");
+    out.push_str(
+        "Fill in an `Answer:` line to resolve a question. \
+         Answers are stored as annotations and passed to the model on the next run.\n\n",
+    );
+
+    if pending.is_empty() {
+        out.push_str("No open questions.\n");
+        return out;
+    }
+
+    for (file, questions) in pending {
+        out.push_str(&format!("## {file}\n\n"));
+        for question in questions {
+            out.push_str(QUESTION_PREFIX);
+            out.push_str(question);
+            out.push_str("\n  ");
+            out.push_str(ANSWER_PREFIX);
+            out.push_str("\n\n");
+        }
+    }
+    out
+}
+
+/// Parse `questions.md` into unanswered questions and answered annotations, keyed by file.
+fn read_questions_file(
+    project: &ProjectContext,
+) -> Result<(FileQuestions, BTreeMap<String, Vec<Annotation>>)> {
+    let path = project.questions_path();
+    if !project.store().exists(&path) {
+        return Ok(Default::default());
+    }
+    let content = project
+        .store()
+        .read_to_string(&path)
+        .map_err(|e| PlainSightError::io(format!("reading questions '{}'", path.display()), e))?;
+
+    let mut unanswered: FileQuestions = BTreeMap::new();
+    let mut answered: BTreeMap<String, Vec<Annotation>> = BTreeMap::new();
+    let mut current_file: Option<String> = None;
+    let mut current_question: Option<String> = None;
+
+    for line in content.lines() {
+        if let Some(file) = line.strip_prefix("## ") {
+            current_file = Some(file.trim().to_string());
+            current_question = None;
+        } else if let Some(question) = line.strip_prefix(QUESTION_PREFIX) {
+            current_question = Some(question.trim().to_string());
+        } else if let Some(answer) = line.trim().strip_prefix(ANSWER_PREFIX)
+            && let (Some(file), Some(question)) = (&current_file, current_question.take())
+        {
+            let answer = answer.trim();
+            if answer.is_empty() {
+                unanswered.entry(file.clone()).or_default().push(question);
+            } else {
+                answered.entry(file.clone()).or_default().push(Annotation {
+                    question,
+                    answer: answer.to_string(),
+                });
+            }
+        }
+    }
+
+    Ok((unanswered, answered))
+}
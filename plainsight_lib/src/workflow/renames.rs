@@ -0,0 +1,213 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
+
+use tracing::{debug, info};
+
+use crate::{
+    config::PageSplitConfig,
+    error::{PlainSightError, Result},
+    page_split,
+    project_manager::{MetaCache, ProjectContext},
+    prompt_context::MovedFrom,
+};
+
+use super::types::ParsedFile;
+
+/// Share of a file's fingerprinted symbols that must come from one vanished file for the file
+/// to count as that file renamed.
+const RENAME_MIN_SHARED_PERCENT: usize = 50;
+const MAX_PREVIOUS_SUMMARY_CHARS: usize = 2000;
+
+/// Symbols of a changed file whose bodies were last seen in another file.
+#[derive(Debug, Clone)]
+pub(crate) struct SymbolMove {
+    pub from: String,
+    pub to: String,
+    pub symbols: Vec<String>,
+    /// `from` no longer exists and most of `to` came from it.
+    pub file_renamed: bool,
+    /// `to` has exactly the content `from` had, so its docs can be copied as they are.
+    pub identical: bool,
+}
+
+/// Match the changed files of this run against symbol fingerprints cached from earlier runs.
+///
+/// A fingerprint counts as moved when it disappeared from the file it was cached for (or that
+/// file no longer exists) and now shows up in a different file.
+pub(crate) fn detect_moves(
+    project_root: &Path,
+    meta: &MetaCache,
+    parsed_files: &[ParsedFile],
+    files_to_regenerate: &BTreeSet<String>,
+) -> Vec<SymbolMove> {
+    let current = parsed_files
+        .iter()
+        .map(|parsed| (parsed.relative_path.as_str(), parsed))
+        .collect::<BTreeMap<_, _>>();
+    let vanished = |path: &str| !current.contains_key(path) && !project_root.join(path).exists();
+
+    // fingerprint -> (previous file, symbol name) for every body that left its file.
+    let mut departed: BTreeMap<&str, (&str, &str)> = BTreeMap::new();
+    for (path, file_meta) in &meta.files {
+        let remaining = match current.get(path.as_str()) {
+            Some(parsed) => parsed
+                .fingerprints
+                .iter()
+                .map(|fp| fp.fingerprint.as_str())
+                .collect::<BTreeSet<_>>(),
+            // Files outside a scoped run were not parsed; nothing is known to have left them.
+            None if !vanished(path) => continue,
+            None => BTreeSet::new(),
+        };
+        for fp in &file_meta.symbols {
+            if !remaining.contains(fp.fingerprint.as_str()) {
+                departed.insert(&fp.fingerprint, (path, &fp.name));
+            }
+        }
+    }
+
+    let mut moves = Vec::new();
+    for path in files_to_regenerate {
+        let Some(parsed) = current.get(path.as_str()) else {
+            continue;
+        };
+        let previous = meta
+            .files
+            .get(path)
+            .map(|file_meta| {
+                file_meta
+                    .symbols
+                    .iter()
+                    .map(|fp| fp.fingerprint.as_str())
+                    .collect::<BTreeSet<_>>()
+            })
+            .unwrap_or_default();
+
+        let mut by_source: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+        for fp in &parsed.fingerprints {
+            if previous.contains(fp.fingerprint.as_str()) {
+                continue;
+            }
+            if let Some((from, _)) = departed.get(fp.fingerprint.as_str())
+                && *from != path
+            {
+                by_source.entry(from).or_default().push(fp.name.clone());
+            }
+        }
+
+        let identical_source = (!meta.files.contains_key(path))
+            .then(|| {
+                meta.files
+                    .iter()
+                    .find(|(from, file_meta)| file_meta.hash == parsed.hash && vanished(from))
+                    .map(|(from, _)| from.as_str())
+            })
+            .flatten();
+        if let Some(from) = identical_source {
+            by_source.entry(from).or_default();
+        }
+
+        for (from, symbols) in by_source {
+            let identical = identical_source == Some(from);
+            let file_renamed = identical
+                || (vanished(from)
+                    && symbols.len() * 100
+                        >= parsed.fingerprints.len() * RENAME_MIN_SHARED_PERCENT);
+            debug!(
+                from,
+                to = %path,
+                moved_symbols = symbols.len(),
+                file_renamed,
+                identical,
+                "symbol_move_detected"
+            );
+            moves.push(SymbolMove {
+                from: from.to_string(),
+                to: path.clone(),
+                symbols,
+                file_renamed,
+                identical,
+            });
+        }
+    }
+
+    if !moves.is_empty() {
+        info!(
+            move_count = moves.len(),
+            renamed_files = moves.iter().filter(|m| m.file_renamed).count(),
+            "symbol_moves_detected"
+        );
+    }
+    moves
+}
+
+/// Copy the docs of files renamed without changes to their new path and drop them from
+/// `files_to_regenerate`.
+pub(crate) fn reuse_renamed_docs(
+    project: &ProjectContext,
+    moves: &[SymbolMove],
+    page_split: &PageSplitConfig,
+    files_to_regenerate: &mut BTreeSet<String>,
+) -> Result<()> {
+    for symbol_move in moves.iter().filter(|m| m.identical) {
+        let (Some(summary), Some(docs)) = (
+            read_docs(project, &project.file_summary_path(&symbol_move.from)?),
+            read_docs(project, &project.file_docs_path(&symbol_move.from)?),
+        ) else {
+            continue;
+        };
+
+        for (path, content) in [
+            (project.file_summary_path(&symbol_move.to)?, summary),
+            (project.file_docs_path(&symbol_move.to)?, docs),
+        ] {
+            let content = content.replace(&symbol_move.from, &symbol_move.to);
+            page_split::write_page(project.store(), &path, &content, page_split).map_err(|e| {
+                PlainSightError::io(format!("writing reused docs '{}'", path.display()), e)
+            })?;
+        }
+
+        files_to_regenerate.remove(&symbol_move.to);
+        info!(
+            from = %symbol_move.from,
+            to = %symbol_move.to,
+            "renamed_file_docs_reused"
+        );
+    }
+    Ok(())
+}
+
+/// Prompt context for the symbols `path` took over from other files: where they came from and
+/// what the previous file's summary said, so wording can be carried over.
+pub(crate) fn moved_from_context(
+    project: &ProjectContext,
+    moves: &[SymbolMove],
+    path: &str,
+) -> Result<Vec<MovedFrom>> {
+    let mut context = Vec::new();
+    for symbol_move in moves.iter().filter(|m| m.to == path) {
+        let previous_summary = read_docs(project, &project.file_summary_path(&symbol_move.from)?)
+            .map(|summary| {
+                summary
+                    .chars()
+                    .take(MAX_PREVIOUS_SUMMARY_CHARS)
+                    .collect::<String>()
+            })
+            .unwrap_or_default();
+        context.push(MovedFrom {
+            path: symbol_move.from.clone(),
+            file_renamed: symbol_move.file_renamed,
+            symbols: symbol_move.symbols.clone(),
+            previous_summary,
+        });
+    }
+    Ok(context)
+}
+
+fn read_docs(project: &ProjectContext, path: &Path) -> Option<String> {
+    page_split::read_page(project.store(), path)
+        .ok()
+        .filter(|content| !content.trim().is_empty())
+}
@@ -0,0 +1,177 @@
+use std::collections::BTreeSet;
+
+use pulldown_cmark::{Event, Parser};
+use serde_json::json;
+use tracing::{debug, warn};
+
+use crate::{
+    error::{PlainSightError, Result as PlainResult},
+    memory,
+    ollama::with_target,
+};
+
+use super::types::{GenerationContext, ParsedFile};
+
+const MAX_REVIEW_SYMBOLS: usize = 80;
+const MAX_REVIEW_PROJECT_SYMBOLS: usize = 60;
+const MAX_REVIEW_IMPORTS: usize = 40;
+const MAX_UNVERIFIED_SYMBOLS: usize = 30;
+const MAX_SIGNATURE_CHARS: usize = 200;
+
+/// `docs` after the review pass when `OllamaConfig::enable_review_pass` is set, `docs` itself
+/// otherwise. A failed review keeps the first draft; only cancellation is passed on.
+pub(super) async fn review_docs(
+    ctx: &GenerationContext<'_>,
+    parsed: &ParsedFile,
+    docs: String,
+) -> PlainResult<String> {
+    if !ctx.wrapper.reviews_docs() {
+        return Ok(docs);
+    }
+    let mut unverified = unverified_symbols(ctx, parsed, &docs);
+    unverified.truncate(MAX_UNVERIFIED_SYMBOLS);
+    let input = build_review_input(ctx, parsed, &docs, &unverified);
+    match with_target(&parsed.relative_path, ctx.wrapper.review(&input)).await {
+        Ok(reviewed) => {
+            debug!(
+                target_file = %parsed.relative_path,
+                unverified = unverified.len(),
+                changed = reviewed.text != docs,
+                prompt_eval_count = reviewed.prompt_eval_count,
+                eval_count = reviewed.eval_count,
+                "file docs reviewed"
+            );
+            Ok(reviewed.text)
+        }
+        Err(PlainSightError::Cancelled) => Err(PlainSightError::Cancelled),
+        Err(err) => {
+            warn!(
+                target_file = %parsed.relative_path,
+                error = %err,
+                "docs review failed; keeping the unreviewed docs"
+            );
+            Ok(docs)
+        }
+    }
+}
+
+fn build_review_input(
+    ctx: &GenerationContext<'_>,
+    parsed: &ParsedFile,
+    docs: &str,
+    unverified: &[String],
+) -> String {
+    let symbols = parsed
+        .memory
+        .symbols
+        .iter()
+        .take(MAX_REVIEW_SYMBOLS)
+        .map(|symbol| {
+            json!({
+                "name": symbol.name,
+                "kind": symbol.kind,
+                "line": symbol.line,
+                "signature": symbol
+                    .details
+                    .signature
+                    .chars()
+                    .take(MAX_SIGNATURE_CHARS)
+                    .collect::<String>(),
+            })
+        })
+        .collect::<Vec<_>>();
+    let relevant = memory::get_relevant_memory_for_file(
+        ctx.project_memory,
+        parsed.path.to_str().unwrap_or(""),
+    );
+    let project_symbols = relevant
+        .global_symbols
+        .iter()
+        .take(MAX_REVIEW_PROJECT_SYMBOLS)
+        .map(|symbol| json!({ "name": symbol.name, "kind": symbol.kind }))
+        .collect::<Vec<_>>();
+    let rustdoc = ctx
+        .rustdoc_items
+        .get(&parsed.relative_path)
+        .into_iter()
+        .flatten()
+        .take(MAX_REVIEW_SYMBOLS)
+        .map(|item| item.path.as_str())
+        .collect::<Vec<_>>();
+
+    let mut input = json!({
+        "target_file": parsed.relative_path,
+        "language": parsed.language,
+        "docs": docs,
+        "symbols": symbols,
+        "imports": parsed.memory.imports.iter().take(MAX_REVIEW_IMPORTS).collect::<Vec<_>>(),
+        "project_symbols": project_symbols,
+        "unverified_symbols": unverified,
+    });
+    if !rustdoc.is_empty() {
+        input["rustdoc"] = json!(rustdoc);
+    }
+    serde_json::to_string_pretty(&input).unwrap_or_else(|_| input.to_string())
+}
+
+/// Inline code spans of `docs` that look like code references and share no identifier with
+/// anything the file defines or imports, or any symbol of the project.
+pub(super) fn unverified_symbols(
+    ctx: &GenerationContext<'_>,
+    parsed: &ParsedFile,
+    docs: &str,
+) -> Vec<String> {
+    let mut known = BTreeSet::new();
+    for symbol in &parsed.memory.symbols {
+        known.insert(symbol.name.as_str());
+        let details = &symbol.details;
+        known.extend(details.fields.iter().map(|field| field.name.as_str()));
+        known.extend(details.variants.iter().map(|variant| variant.name.as_str()));
+        known.extend(details.parameters.iter().map(|param| param.name.as_str()));
+    }
+    for import in &parsed.memory.imports {
+        known.extend(identifiers(import));
+    }
+    known.extend(
+        ctx.project_memory
+            .global_symbols
+            .iter()
+            .map(|symbol| symbol.name.as_str()),
+    );
+    known.extend(
+        ctx.rustdoc_items
+            .get(&parsed.relative_path)
+            .into_iter()
+            .flatten()
+            .map(|item| item.name.as_str()),
+    );
+    if let Some(stem) = parsed.path.file_stem().and_then(|stem| stem.to_str()) {
+        known.insert(stem);
+    }
+
+    let mut unverified = BTreeSet::new();
+    for event in Parser::new(docs) {
+        let Event::Code(span) = event else {
+            continue;
+        };
+        let span = span.trim();
+        let looks_like_reference = !span.is_empty()
+            && span
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '_' | ':' | '.' | '(' | ')'));
+        if !looks_like_reference {
+            continue;
+        }
+        let mut names = identifiers(span).peekable();
+        if names.peek().is_some() && names.all(|name| !known.contains(name)) {
+            unverified.insert(span.to_string());
+        }
+    }
+    unverified.into_iter().collect()
+}
+
+/// The identifiers in `text`, e.g. `Config`, `load` and `path` for `Config::load(path)`.
+fn identifiers(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| word.starts_with(|c: char| c.is_alphabetic() || c == '_'))
+}
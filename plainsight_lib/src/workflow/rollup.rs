@@ -0,0 +1,300 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
+
+use serde_json::Value;
+use tracing::info;
+
+use crate::{
+    config::ArchitectureContext,
+    error::{PlainSightError, Result as PlainResult},
+    ollama::Task,
+};
+
+use super::{
+    generate::{self, format_duration},
+    types::GenerationContext,
+};
+
+/// Paths with their summaries.
+type Summaries<'a> = Vec<(&'a str, &'a str)>;
+
+/// The source directories holding the run's files, deepest first: every ancestor directory of
+/// a parsed file except the project root, and in a scoped run only those inside the scope.
+pub(super) fn directories(ctx: &GenerationContext<'_>) -> Vec<String> {
+    let mut directories = BTreeSet::new();
+    for parsed in ctx.parsed_files {
+        let mut dir = parent(&parsed.relative_path);
+        while let Some(path) = dir {
+            if !directories.insert(path.to_string()) {
+                break;
+            }
+            dir = parent(path);
+        }
+    }
+    let mut directories = directories
+        .into_iter()
+        .filter(|dir| {
+            ctx.project
+                .scope()
+                .is_none_or(|scope| Path::new(dir).starts_with(scope))
+        })
+        .collect::<Vec<_>>();
+    directories.sort_by(|a, b| depth(b).cmp(&depth(a)).then_with(|| a.cmp(b)));
+    directories
+}
+
+/// Write the summary of every directory from those of its files and subdirectories, a level
+/// at a time from the deepest, unloading the models of `loaded_tasks` first. A directory with
+/// no regenerated file summary or subdirectory keeps the summary it has, unless
+/// `ForceConfig::project_level` is set. Returns each directory's
+/// summary by path.
+pub(super) async fn write_directory_summaries(
+    ctx: &GenerationContext<'_>,
+    file_summaries: &[(String, String)],
+    loaded_tasks: &[Task],
+) -> PlainResult<BTreeMap<String, String>> {
+    let GenerationContext {
+        wrapper,
+        project: manager,
+        files_to_regenerate,
+        ..
+    } = *ctx;
+    let forced = ctx.config.force.project_level;
+    let directories = directories(ctx);
+    let max_concurrency = wrapper.max_concurrency_for(Task::DirectorySummary);
+    info!(
+        directory_count = directories.len(),
+        max_concurrency, "directory_summary_phase_start"
+    );
+
+    let mut summaries = BTreeMap::new();
+    let mut regenerated = files_to_regenerate
+        .iter()
+        .filter(|path| ctx.regenerates(path, Task::Summarize))
+        .filter_map(|path| parent(path))
+        .map(str::to_string)
+        .collect::<BTreeSet<_>>();
+    let mut unloaded = false;
+    let mut reused = 0usize;
+    let mut generated = 0usize;
+    for level in directories.chunk_by(|a, b| depth(a) == depth(b)) {
+        let mut pending = Vec::new();
+        for dir in level {
+            let existing = if forced || regenerated.contains(dir) {
+                None
+            } else {
+                read_summary(ctx, dir)
+            };
+            match existing {
+                Some(summary) => {
+                    summaries.insert(dir.clone(), summary);
+                    reused += 1;
+                }
+                None => pending.push(dir),
+            }
+        }
+        if pending.is_empty() {
+            continue;
+        }
+        if !unloaded {
+            generate::unload_before(wrapper, Task::DirectorySummary, loaded_tasks).await;
+            unloaded = true;
+        }
+        let written = generate::run_bounded(&pending, max_concurrency, |dir| {
+            summarize_directory(ctx, dir, file_summaries, &summaries)
+        })
+        .await?;
+        for (dir, summary) in pending.into_iter().zip(written) {
+            if let Some(parent) = parent(dir) {
+                regenerated.insert(parent.to_string());
+            }
+            summaries.insert(dir.clone(), summary);
+            generated += 1;
+        }
+    }
+    info!(
+        reused,
+        generated,
+        summaries_root = %manager.files_root_path().display(),
+        "directory_summary_phase_complete"
+    );
+    Ok(summaries)
+}
+
+/// `project_index` with the directory summaries written so far added as `directories`,
+/// outermost first, when `PlainSightConfig::directory_summaries` is set. With
+/// `ArchitectureContext::Summaries` each file also gets its `summary`, and the project memory's
+/// links and global symbols are added as `project_memory`.
+pub(super) fn architecture_input(
+    ctx: &GenerationContext<'_>,
+    project_index: &str,
+) -> PlainResult<String> {
+    let from_summaries = ctx.config.architecture_context == ArchitectureContext::Summaries;
+    if !ctx.config.directory_summaries && !from_summaries {
+        return Ok(project_index.to_string());
+    }
+    let mut index: Value = serde_json::from_str(project_index)
+        .map_err(|e| PlainSightError::InvalidState(format!("reading project index: {e}")))?;
+    if ctx.config.directory_summaries {
+        let mut directories = directories(ctx);
+        directories.sort_by(|a, b| depth(a).cmp(&depth(b)).then_with(|| a.cmp(b)));
+        let directories = directories
+            .iter()
+            .filter_map(|dir| {
+                let summary = read_summary(ctx, dir)?;
+                Some(serde_json::json!({ "path": dir, "summary": summary }))
+            })
+            .collect::<Vec<_>>();
+        index["directories"] = Value::Array(directories);
+    }
+    if from_summaries {
+        add_file_summaries(ctx, &mut index)?;
+    }
+    serde_json::to_string_pretty(&index)
+        .map_err(|e| PlainSightError::InvalidState(format!("serializing project index: {e}")))
+}
+
+/// Add each file's summary to its entry in `index`, and the project memory's links and global
+/// symbols as `project_memory`.
+fn add_file_summaries(ctx: &GenerationContext<'_>, index: &mut Value) -> PlainResult<()> {
+    let parsed_files = ctx
+        .parsed_files
+        .iter()
+        .map(|parsed| (parsed.relative_path.as_str(), parsed))
+        .collect::<BTreeMap<_, _>>();
+    let files = index
+        .get_mut("files")
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten();
+    for file in files {
+        let Some(parsed) = file
+            .get("path")
+            .and_then(Value::as_str)
+            .and_then(|path| parsed_files.get(path))
+        else {
+            continue;
+        };
+        let summary_path = ctx.project.file_summary_path(&parsed.path)?;
+        if let Ok(summary) = ctx.project.store().read_to_string(&summary_path)
+            && !summary.trim().is_empty()
+        {
+            file["summary"] = Value::String(summary);
+        }
+    }
+    index["project_memory"] = serde_json::json!({
+        "global_symbols": ctx.project_memory.global_symbols,
+        "links": ctx.project_memory.links,
+    });
+    Ok(())
+}
+
+/// The directories of `directory_summaries` no other one contains, and the files of
+/// `file_summaries` outside all of them. A lone directory with nothing beside it, such as
+/// `src`, is replaced by its contents.
+pub(super) fn top_level<'a>(
+    file_summaries: &'a [(String, String)],
+    directory_summaries: &'a BTreeMap<String, String>,
+) -> (Summaries<'a>, Summaries<'a>) {
+    let mut top = contents(None, file_summaries, directory_summaries);
+    while let ([(dir, _)], []) = (top.0.as_slice(), top.1.as_slice()) {
+        let inner = contents(Some(dir), file_summaries, directory_summaries);
+        if inner.0.is_empty() && inner.1.is_empty() {
+            break;
+        }
+        top = inner;
+    }
+    top
+}
+
+/// The directories and files directly in `dir` among those with a summary, or in no
+/// summarized directory when `dir` is `None`.
+fn contents<'a>(
+    dir: Option<&str>,
+    file_summaries: &'a [(String, String)],
+    directory_summaries: &'a BTreeMap<String, String>,
+) -> (Summaries<'a>, Summaries<'a>) {
+    let in_dir =
+        |path: &str| parent(path).filter(|holder| directory_summaries.contains_key(*holder)) == dir;
+    let directories = directory_summaries
+        .iter()
+        .filter(|(path, _)| in_dir(path))
+        .map(|(path, summary)| (path.as_str(), summary.as_str()))
+        .collect();
+    let files = file_summaries
+        .iter()
+        .filter(|(path, _)| in_dir(path))
+        .map(|(path, summary)| (path.as_str(), summary.as_str()))
+        .collect();
+    (directories, files)
+}
+
+async fn summarize_directory(
+    ctx: &GenerationContext<'_>,
+    dir: &str,
+    file_summaries: &[(String, String)],
+    directory_summaries: &BTreeMap<String, String>,
+) -> PlainResult<String> {
+    let GenerationContext {
+        wrapper,
+        project: manager,
+        project_name,
+        ..
+    } = *ctx;
+    let files = file_summaries
+        .iter()
+        .filter(|(path, _)| parent(path) == Some(dir))
+        .cloned()
+        .collect::<Vec<_>>();
+    let subdirectories = directory_summaries
+        .iter()
+        .filter(|(path, _)| parent(path) == Some(dir))
+        .map(|(path, summary)| (path.clone(), summary.clone()))
+        .collect::<Vec<_>>();
+
+    let summary = wrapper
+        .directory_summary(project_name, dir, &files, &subdirectories)
+        .await?;
+    let summary_path = manager.directory_summary_path(dir);
+    manager
+        .store()
+        .write(&summary_path, summary.text.as_bytes())
+        .map_err(|e| {
+            PlainSightError::io(
+                format!(
+                    "writing directory summary output '{}'",
+                    summary_path.display()
+                ),
+                e,
+            )
+        })?;
+    info!(
+        directory = dir,
+        model_name = %summary.model,
+        elapsed = %format_duration(summary.total_duration),
+        eval_count = summary.eval_count,
+        file_count = files.len(),
+        subdirectory_count = subdirectories.len(),
+        "directory summary generated"
+    );
+    Ok(summary.text)
+}
+
+fn read_summary(ctx: &GenerationContext<'_>, dir: &str) -> Option<String> {
+    let summary_path = ctx.project.directory_summary_path(dir);
+    ctx.project
+        .store()
+        .read_to_string(&summary_path)
+        .ok()
+        .filter(|summary| !summary.trim().is_empty())
+}
+
+fn parent(path: &str) -> Option<&str> {
+    path.rsplit_once('/').map(|(dir, _)| dir)
+}
+
+fn depth(dir: &str) -> usize {
+    dir.matches('/').count()
+}
@@ -0,0 +1,224 @@
+use std::{
+    cmp::Reverse,
+    collections::BTreeMap,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+use tracing::info;
+
+use crate::{
+    error::{PlainSightError, Result},
+    ollama::RequestRecord,
+    project_manager::{ProjectContext, SkipEntry},
+};
+
+use super::{
+    progress::{ProgressEvent, ProgressSink},
+    quality::QualityReport,
+    types::PrefetchStats,
+};
+
+const MAX_SLOWEST_FILES: usize = 10;
+
+/// Wall time of each workflow phase, in the order they ran. Each phase is also reported to
+/// the run's [`ProgressSink`] as it starts.
+pub(crate) struct PhaseTimes<'a> {
+    progress: &'a dyn ProgressSink,
+    run_started: Instant,
+    current: (&'static str, Instant),
+    finished: Vec<PhaseTime>,
+}
+
+impl<'a> PhaseTimes<'a> {
+    pub fn start(name: &'static str, progress: &'a dyn ProgressSink) -> Self {
+        progress.event(ProgressEvent::PhaseStarted {
+            phase: name,
+            file_tasks: 0,
+        });
+        let now = Instant::now();
+        Self {
+            progress,
+            run_started: now,
+            current: (name, now),
+            finished: Vec::new(),
+        }
+    }
+
+    /// End the running phase and start `name`.
+    pub fn next(&mut self, name: &'static str) {
+        self.next_over_files(name, 0);
+    }
+
+    /// End the running phase and start `name`, which reports `file_tasks` file outcomes.
+    pub fn next_over_files(&mut self, name: &'static str, file_tasks: usize) {
+        self.progress.event(ProgressEvent::PhaseStarted {
+            phase: name,
+            file_tasks,
+        });
+        let (previous, started) = std::mem::replace(&mut self.current, (name, Instant::now()));
+        self.finished.push(PhaseTime {
+            name: previous,
+            wall_ms: millis(started),
+        });
+    }
+
+    /// End the running phase; returns every phase and the run's total wall time.
+    fn finish(self) -> (Vec<PhaseTime>, u64) {
+        let (name, started) = self.current;
+        let mut phases = self.finished;
+        phases.push(PhaseTime {
+            name,
+            wall_ms: millis(started),
+        });
+        (phases, millis(self.run_started))
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct PhaseTime {
+    name: &'static str,
+    wall_ms: u64,
+}
+
+/// Request counts, tokens and request time, summed over some set of requests.
+#[derive(Debug, Default, Serialize)]
+struct Totals {
+    requests: usize,
+    failed_requests: usize,
+    prompt_bytes: usize,
+    prompt_tokens: u64,
+    completion_tokens: u64,
+    request_ms: u64,
+    /// Part of `request_ms` the server spent generating, when it reports it.
+    eval_ms: u64,
+}
+
+impl Totals {
+    fn add(&mut self, record: &RequestRecord) {
+        self.requests += 1;
+        self.failed_requests += usize::from(!record.ok);
+        self.prompt_bytes += record.prompt_bytes;
+        self.prompt_tokens += record.usage.prompt_tokens;
+        self.completion_tokens += record.usage.completion_tokens;
+        self.request_ms += record.duration_ms;
+        self.eval_ms += record.usage.eval_duration_ms;
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ModelTotals {
+    task: String,
+    model: String,
+    #[serde(flatten)]
+    totals: Totals,
+}
+
+#[derive(Debug, Serialize)]
+struct FileTotals {
+    path: String,
+    #[serde(flatten)]
+    totals: Totals,
+}
+
+#[derive(Debug, Serialize)]
+struct RunReport<'a> {
+    project: String,
+    generated_at: u64,
+    wall_ms: u64,
+    phases: Vec<PhaseTime>,
+    totals: Totals,
+    /// Per task and model.
+    models: Vec<ModelTotals>,
+    /// Files whose summary and docs requests took longest, slowest first.
+    slowest_files: Vec<FileTotals>,
+    /// File prompts built while an earlier request was in flight.
+    prompt_prefetch: PrefetchStats,
+    /// Files left out of generation: on the skip list, or over their time budget this run.
+    skipped_files: Vec<SkipEntry>,
+    /// Heuristic scores of the docs the run regenerated.
+    quality: &'a QualityReport,
+}
+
+/// Write `run_report.json` from the run's phases, request log, prompt prefetching, skipped
+/// files and docs quality.
+pub(crate) fn write_run_report(
+    project: &ProjectContext,
+    project_name: &str,
+    phases: PhaseTimes<'_>,
+    records: &[RequestRecord],
+    prompt_prefetch: PrefetchStats,
+    skipped_files: &[SkipEntry],
+    quality: &QualityReport,
+) -> Result<()> {
+    let (phases, wall_ms) = phases.finish();
+    let mut totals = Totals::default();
+    let mut models: BTreeMap<(String, String), Totals> = BTreeMap::new();
+    let mut files: BTreeMap<String, Totals> = BTreeMap::new();
+    for record in records {
+        totals.add(record);
+        models
+            .entry((record.task.clone(), record.model.clone()))
+            .or_default()
+            .add(record);
+        if let Some(target) = &record.target {
+            files.entry(target.clone()).or_default().add(record);
+        }
+    }
+    let mut slowest_files = files
+        .into_iter()
+        .map(|(path, totals)| FileTotals { path, totals })
+        .collect::<Vec<_>>();
+    slowest_files.sort_by_key(|file| Reverse(file.totals.request_ms));
+    slowest_files.truncate(MAX_SLOWEST_FILES);
+
+    let report = RunReport {
+        project: project_name.to_string(),
+        generated_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default(),
+        wall_ms,
+        phases,
+        totals,
+        models: models
+            .into_iter()
+            .map(|((task, model), totals)| ModelTotals {
+                task,
+                model,
+                totals,
+            })
+            .collect(),
+        slowest_files,
+        prompt_prefetch,
+        skipped_files: skipped_files.to_vec(),
+        quality,
+    };
+
+    let report_path = project.run_report_path();
+    let content = serde_json::to_string_pretty(&report)
+        .map_err(|e| PlainSightError::InvalidState(format!("serializing run report: {e}")))?;
+    project
+        .store()
+        .write(&report_path, content.as_bytes())
+        .map_err(|e| {
+            PlainSightError::io(format!("writing run report '{}'", report_path.display()), e)
+        })?;
+
+    info!(
+        requests = report.totals.requests,
+        prompt_tokens = report.totals.prompt_tokens,
+        completion_tokens = report.totals.completion_tokens,
+        wall_ms = report.wall_ms,
+        prefetch_overlapped_ms = report.prompt_prefetch.overlapped_ms,
+        skipped_files = report.skipped_files.len(),
+        average_quality = report.quality.average.unwrap_or_default(),
+        run_report_path = %report_path.display(),
+        "run_report_written"
+    );
+    Ok(())
+}
+
+fn millis(since: Instant) -> u64 {
+    u64::try_from(since.elapsed().as_millis()).unwrap_or(u64::MAX)
+}
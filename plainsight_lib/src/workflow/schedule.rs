@@ -0,0 +1,74 @@
+use tracing::info;
+
+use crate::ollama::{ModelResidency, OllamaWrapper, Task};
+
+/// Order of a run's model work, chosen so that task models that cannot share GPU memory are
+/// each loaded once instead of taking turns.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct ModelSchedule {
+    /// Summaries and docs in one pass over the files; see `OllamaConfig::pipeline_file_tasks`.
+    pub pipeline_file_tasks: bool,
+    /// Review every file's docs once all of them are written, instead of right after each.
+    pub defer_reviews: bool,
+    /// Write the project summary after the file docs instead of after the file summaries.
+    pub defer_project_summary: bool,
+    /// With the project summary deferred, write the architecture docs right after the file
+    /// docs, while the docs model is still loaded.
+    pub architecture_first: bool,
+}
+
+/// Plan the run's model work from which task models fit in GPU memory together.
+pub(crate) async fn plan(wrapper: &OllamaWrapper) -> ModelSchedule {
+    let residency = wrapper.model_residency().await;
+    let schedule = plan_with(wrapper, &residency);
+    info!(
+        gpu_memory = ?residency.capacity(),
+        pipeline_file_tasks = schedule.pipeline_file_tasks,
+        defer_reviews = schedule.defer_reviews,
+        defer_project_summary = schedule.defer_project_summary,
+        architecture_first = schedule.architecture_first,
+        "model_schedule_planned"
+    );
+    schedule
+}
+
+fn plan_with(wrapper: &OllamaWrapper, residency: &ModelResidency) -> ModelSchedule {
+    let model = |task| wrapper.model_name(task);
+    let summarize = model(Task::Summarize);
+    let documentation = model(Task::Documentation);
+    let project_summary = model(Task::ProjectSummary);
+    let architecture = model(Task::Architecture);
+
+    let mut file_models = vec![summarize, documentation];
+    if wrapper.reviews_docs() {
+        file_models.push(model(Task::Review));
+    }
+    let pipeline_file_tasks =
+        wrapper.pipelines_file_tasks() && residency.fit_together(&file_models);
+    if wrapper.pipelines_file_tasks() && !pipeline_file_tasks {
+        info!("file task models do not fit in GPU memory together; running them one at a time");
+    }
+
+    let review = model(Task::Review);
+    let defer_reviews = wrapper.reviews_docs()
+        && review != documentation
+        && !residency.fit_together(&[documentation, review]);
+
+    // Right after the summaries, the project summary model takes the GPU from a model that is
+    // needed again later: the summaries' model when it also writes the docs, or its own when
+    // it also writes the architecture.
+    let reloads_later = summarize == documentation || project_summary == architecture;
+    let swaps =
+        wrapper.unloads_after_phase() || !residency.fit_together(&[summarize, project_summary]);
+    let defer_project_summary =
+        !pipeline_file_tasks && project_summary != summarize && reloads_later && swaps;
+    let architecture_first =
+        defer_project_summary && architecture == documentation && project_summary != documentation;
+
+    ModelSchedule {
+        pipeline_file_tasks,
+        defer_reviews,
+        defer_project_summary,
+        architecture_first,
+    }
+}
@@ -0,0 +1,59 @@
+use std::collections::BTreeSet;
+
+use tracing::info;
+
+use crate::{
+    error::Result,
+    project_manager::{SkipEntry, SkipList},
+};
+
+use super::types::GenerationContext;
+
+/// Take the files on `skip_list` out of `files_to_regenerate` and `forced_only`. Returns the
+/// entries of those that would otherwise have been regenerated.
+pub(super) fn leave_out_skip_listed(
+    skip_list: &SkipList,
+    files_to_regenerate: &mut BTreeSet<String>,
+    forced_only: &mut BTreeSet<String>,
+) -> Vec<SkipEntry> {
+    let left_out = skip_list
+        .files
+        .iter()
+        .filter(|entry| files_to_regenerate.remove(&entry.path))
+        .cloned()
+        .collect::<Vec<_>>();
+    for entry in &left_out {
+        forced_only.remove(&entry.path);
+    }
+    if !left_out.is_empty() {
+        info!(skipped = left_out.len(), "skip_listed_files_left_out");
+    }
+    left_out
+}
+
+/// Add the files this run skipped for running over their time budget to the skip list. Returns
+/// every file the run skipped or failed, with the reason, for the run report.
+pub(super) fn record_skipped_files(ctx: &GenerationContext<'_>) -> Result<Vec<SkipEntry>> {
+    let project = ctx.project;
+    let timed_out = ctx.skipped_files.entries();
+    if !timed_out.is_empty() {
+        let mut skip_list = project.load_skip_list()?;
+        for entry in &timed_out {
+            if !skip_list.contains(&entry.path) {
+                skip_list.files.push(entry.clone());
+            }
+        }
+        project.save_skip_list(&skip_list)?;
+        info!(
+            added = timed_out.len(),
+            skip_list_path = %project.skip_list_path().display(),
+            "skip_list_updated"
+        );
+    }
+    Ok(ctx
+        .skip_listed
+        .iter()
+        .cloned()
+        .chain(ctx.failed_files.entries())
+        .collect())
+}
@@ -0,0 +1,137 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{
+    benchmarks::BenchmarkResult,
+    config::{PlainSightConfig, SourceDiscoveryConfig},
+    error::{PlainSightError, Result},
+    features::CrateFeatures,
+    openapi::OpenApiSpec,
+    ownership::ManifestOwners,
+    project_manager::ProjectContext,
+    proto::ProtoFile,
+    rustdoc::RustdocCrate,
+};
+
+use super::{
+    ingest,
+    types::{MetadataFile, ParsedFile},
+};
+
+/// Bumped when a field of [`SourceSnapshot`] or of the types it holds changes incompatibly.
+const SNAPSHOT_VERSION: u32 = 1;
+
+/// Everything a run reads from the source tree, as discovered and parsed before any of it is
+/// combined. Offline runs save it to `.ingest.json`, and `PlainSightConfig::from_index` runs
+/// load it in place of reading the tree.
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct SourceSnapshot {
+    pub version: u32,
+    pub parsed_files: Vec<ParsedFile>,
+    pub benchmark_results: Vec<BenchmarkResult>,
+    pub manifest_owners: Vec<ManifestOwners>,
+    pub api_specs: Vec<OpenApiSpec>,
+    pub protos: Vec<ProtoFile>,
+    pub rustdoc_crates: Vec<RustdocCrate>,
+    pub crate_features: Vec<CrateFeatures>,
+    pub project_metadata: Vec<MetadataFile>,
+}
+
+impl SourceSnapshot {
+    /// Discover and parse the source files under `discovery_root` and read the rest of the
+    /// tree's inputs. `None` when there is no source file.
+    pub fn read(
+        project: &ProjectContext,
+        project_root: &Path,
+        discovery_root: &Path,
+        discovery: &SourceDiscoveryConfig,
+        config: &PlainSightConfig,
+    ) -> Result<Option<Self>> {
+        let files = ingest::discover_source_files(discovery_root, discovery)?;
+        if files.is_empty() {
+            return Ok(None);
+        }
+        let parsed_files = ingest::parse_project_files(&files, project, project_root, discovery)?;
+        let benchmark_results = if parsed_files
+            .iter()
+            .any(|parsed| !parsed.benchmarks.is_empty())
+        {
+            ingest::discover_benchmark_results(project_root)
+        } else {
+            Vec::new()
+        };
+        Ok(Some(Self {
+            version: SNAPSHOT_VERSION,
+            parsed_files,
+            benchmark_results,
+            manifest_owners: ingest::discover_manifest_owners(project_root, discovery)?,
+            api_specs: ingest::discover_api_specs(project_root, discovery)?,
+            protos: ingest::discover_proto_files(project_root, discovery)?,
+            rustdoc_crates: ingest::discover_rustdoc_items(project_root, config.rustdoc),
+            crate_features: ingest::discover_crate_features(project_root, discovery)?,
+            project_metadata: ingest::discover_project_metadata(
+                project_root,
+                discovery,
+                &config.project_metadata,
+            )?,
+        }))
+    }
+
+    /// The snapshot the last offline run saved, with file paths resolved against
+    /// `project_root`.
+    pub fn load(project: &ProjectContext, project_root: &Path) -> Result<Self> {
+        let path = project.ingest_snapshot_path();
+        let content = project.store().read_to_string(&path).map_err(|e| {
+            PlainSightError::io(
+                format!(
+                    "reading ingest snapshot '{}' (run `plainsight index` first)",
+                    path.display()
+                ),
+                e,
+            )
+        })?;
+        let mut snapshot: Self = serde_json::from_str(&content).map_err(|e| {
+            PlainSightError::InvalidState(format!(
+                "reading ingest snapshot '{}': {e}",
+                path.display()
+            ))
+        })?;
+        if snapshot.version != SNAPSHOT_VERSION {
+            return Err(PlainSightError::InvalidState(format!(
+                "ingest snapshot '{}' has version {}, expected {SNAPSHOT_VERSION}; run `plainsight index` again",
+                path.display(),
+                snapshot.version
+            )));
+        }
+        for parsed in &mut snapshot.parsed_files {
+            parsed.path = project_root.join(&parsed.relative_path);
+        }
+        info!(
+            file_count = snapshot.parsed_files.len(),
+            snapshot_path = %path.display(),
+            "ingest_snapshot_loaded"
+        );
+        Ok(snapshot)
+    }
+
+    pub fn save(&self, project: &ProjectContext) -> Result<PathBuf> {
+        let path = project.ingest_snapshot_path();
+        let content = serde_json::to_string(self).map_err(|e| {
+            PlainSightError::InvalidState(format!("serializing ingest snapshot: {e}"))
+        })?;
+        project
+            .store()
+            .write(&path, content.as_bytes())
+            .map_err(|e| {
+                PlainSightError::io(format!("writing ingest snapshot '{}'", path.display()), e)
+            })?;
+        info!(
+            file_count = self.parsed_files.len(),
+            snapshot_path = %path.display(),
+            "ingest_snapshot_saved"
+        );
+        Ok(path)
+    }
+}
@@ -0,0 +1,146 @@
+use std::{
+    collections::BTreeSet,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use tracing::{debug, info};
+
+use crate::{
+    error::{PlainSightError, Result},
+    project_manager::{MetaCache, ProjectContext},
+};
+
+use super::types::ParsedFile;
+
+/// Where a run's source paths are looked up: the project root, or for a run from an ingest
+/// snapshot the snapshot's files and their directories, as the tree need not be on disk.
+enum SourceTree<'a> {
+    Disk(&'a Path),
+    Snapshot {
+        files: BTreeSet<&'a str>,
+        directories: BTreeSet<&'a str>,
+    },
+}
+
+impl SourceTree<'_> {
+    fn exists(&self, relative: &Path) -> bool {
+        match self {
+            Self::Disk(project_root) => project_root.join(relative).exists(),
+            Self::Snapshot { files, .. } => {
+                relative.to_str().is_some_and(|path| files.contains(path)) || self.is_dir(relative)
+            }
+        }
+    }
+
+    fn is_dir(&self, relative: &Path) -> bool {
+        match self {
+            Self::Disk(project_root) => project_root.join(relative).is_dir(),
+            Self::Snapshot { directories, .. } => relative
+                .to_str()
+                .is_some_and(|path| directories.contains(path)),
+        }
+    }
+}
+
+/// Remove the docs directories and `.meta.json` entries of source files that no longer exist,
+/// such as deleted or renamed files. A path counts as gone when the run did not parse it and
+/// it is not under `project_root` either, so files a run leaves out keep their docs. A run
+/// `from_index` goes by the snapshot's files instead of `project_root`. The caller saves
+/// `meta`.
+pub(super) fn remove_stale_docs(
+    project: &ProjectContext,
+    project_root: &Path,
+    meta: &mut MetaCache,
+    parsed_files: &[ParsedFile],
+    from_index: bool,
+) -> Result<Vec<String>> {
+    let parsed = parsed_files
+        .iter()
+        .map(|parsed| parsed.relative_path.as_str())
+        .collect::<BTreeSet<_>>();
+    let tree = if from_index {
+        let directories = parsed
+            .iter()
+            .flat_map(|path| {
+                path.match_indices('/')
+                    .map(|(separator, _)| &path[..separator])
+            })
+            .collect();
+        SourceTree::Snapshot {
+            files: parsed.clone(),
+            directories,
+        }
+    } else {
+        SourceTree::Disk(project_root)
+    };
+    let vanished = |path: &str| !parsed.contains(path) && !tree.exists(Path::new(path));
+
+    let removed = meta
+        .files
+        .keys()
+        .filter(|path| vanished(path))
+        .cloned()
+        .collect::<Vec<_>>();
+    for path in &removed {
+        meta.files.remove(path);
+    }
+    let meta_entries = removed.len();
+
+    let files_root = project.files_root_path();
+    let mut stale_dirs = Vec::new();
+    find_stale_dirs(&files_root, &tree, Path::new(""), &mut stale_dirs)?;
+    for relative in &stale_dirs {
+        let docs_dir = files_root.join(relative);
+        debug!(docs_dir = %docs_dir.display(), "remove_stale_docs_dir");
+        project.store().remove_dir_all(&docs_dir).map_err(|e| {
+            PlainSightError::io(format!("removing stale docs '{}'", docs_dir.display()), e)
+        })?;
+    }
+
+    if meta_entries > 0 || !stale_dirs.is_empty() {
+        info!(
+            meta_entries,
+            docs_dirs = stale_dirs.len(),
+            "stale_docs_removed"
+        );
+    }
+    Ok(removed)
+}
+
+/// Directories under `files_root/relative` whose source path is gone from `tree`, outermost
+/// only. Only directories that mirror a source directory are searched, so the
+/// outputs inside a file's docs directory are left alone. The local docs root is listed, as it
+/// is the working copy of every store.
+fn find_stale_dirs(
+    files_root: &Path,
+    tree: &SourceTree<'_>,
+    relative: &Path,
+    stale: &mut Vec<PathBuf>,
+) -> Result<()> {
+    let dir = files_root.join(relative);
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => {
+            return Err(PlainSightError::io(
+                format!("listing docs '{}'", dir.display()),
+                e,
+            ));
+        }
+    };
+    for entry in entries {
+        let entry = entry
+            .map_err(|e| PlainSightError::io(format!("listing docs '{}'", dir.display()), e))?;
+        if !entry.path().is_dir() {
+            continue;
+        }
+        let path = relative.join(entry.file_name());
+        if !tree.exists(&path) {
+            stale.push(path);
+        } else if tree.is_dir(&path) {
+            find_stale_dirs(files_root, tree, &path, stale)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,165 @@
+use std::{
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use tracing::info;
+
+use crate::{
+    error::{PlainSightError, Result},
+    freshness::format_date,
+    memory::ProjectMemory,
+    project_manager::{ProjectContext, RunSnapshot},
+};
+
+use super::types::ParsedFile;
+
+const MAX_TIMELINE_RUNS: usize = 500;
+const MAX_RECENT_RUNS: usize = 20;
+const SPARKLINE_WIDTH: usize = 30;
+const SPARK_CHARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+type MetricFn = fn(&RunSnapshot) -> usize;
+
+pub(crate) fn record_run(
+    project: &ProjectContext,
+    project_name: &str,
+    parsed_files: &[ParsedFile],
+    project_memory: &ProjectMemory,
+) -> Result<()> {
+    let mut documented_file_count = 0usize;
+    for parsed in parsed_files {
+        if has_content(project, &project.file_summary_path(&parsed.path)?)
+            && has_content(project, &project.file_docs_path(&parsed.path)?)
+        {
+            documented_file_count += 1;
+        }
+    }
+
+    let snapshot = RunSnapshot {
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default(),
+        file_count: parsed_files.len(),
+        symbol_count: project_memory.unique_symbol_count,
+        link_count: project_memory.links.len(),
+        documented_file_count,
+    };
+
+    let mut timeline = project.load_timeline()?;
+    timeline.runs.push(snapshot);
+    if timeline.runs.len() > MAX_TIMELINE_RUNS {
+        let overflow = timeline.runs.len() - MAX_TIMELINE_RUNS;
+        timeline.runs.drain(..overflow);
+    }
+    project.save_timeline(&timeline)?;
+
+    let trends_path = project.trends_path();
+    let trends = render_trends(project_name, &timeline.runs);
+    project
+        .store()
+        .write(&trends_path, trends.as_bytes())
+        .map_err(|e| {
+            PlainSightError::io(format!("writing trends '{}'", trends_path.display()), e)
+        })?;
+
+    info!(
+        run_count = timeline.runs.len(),
+        documented_files = documented_file_count,
+        trends_path = %trends_path.display(),
+        "trends_updated"
+    );
+
+    Ok(())
+}
+
+pub(super) fn has_content(project: &ProjectContext, path: &Path) -> bool {
+    project
+        .store()
+        .read_to_string(path)
+        .is_ok_and(|content| !content.trim().is_empty())
+}
+
+fn render_trends(project_name: &str, runs: &[RunSnapshot]) -> String {
+    let mut out = format!("# {project_name} Trends\n\n");
+
+    let (Some(first), Some(latest)) = (runs.first(), runs.last()) else {
+        out.push_str("No runs recorded yet.\n");
+        return out;
+    };
+
+    out.push_str(&format!(
+        "Runs recorded: {} (first {}, latest {})\n\n",
+        runs.len(),
+        format_date(first.timestamp),
+        format_date(latest.timestamp)
+    ));
+
+    let recent = &runs[runs.len().saturating_sub(SPARKLINE_WIDTH)..];
+    out.push_str("## Overview\n\n");
+    out.push_str("| Metric | Trend | First | Latest | Change |\n");
+    out.push_str("|---|---|---|---|---|\n");
+
+    let counters: [(&str, MetricFn); 4] = [
+        ("Files", |r| r.file_count),
+        ("Symbols", |r| r.symbol_count),
+        ("Links", |r| r.link_count),
+        ("Documented files", |r| r.documented_file_count),
+    ];
+    for (label, metric) in counters {
+        let values: Vec<f64> = recent.iter().map(|r| metric(r) as f64).collect();
+        let (from, to) = (metric(first), metric(latest));
+        out.push_str(&format!(
+            "| {label} | {} | {from} | {to} | {:+} |\n",
+            sparkline(&values),
+            to as i64 - from as i64
+        ));
+    }
+
+    let coverage: Vec<f64> = recent.iter().map(RunSnapshot::coverage).collect();
+    out.push_str(&format!(
+        "| Coverage | {} | {} | {} | {:+.1} pts |\n\n",
+        sparkline(&coverage),
+        format_percent(first.coverage()),
+        format_percent(latest.coverage()),
+        (latest.coverage() - first.coverage()) * 100.0
+    ));
+
+    out.push_str("## Recent Runs\n\n");
+    out.push_str("| Date | Files | Symbols | Links | Coverage |\n");
+    out.push_str("|---|---|---|---|---|\n");
+    for run in runs.iter().rev().take(MAX_RECENT_RUNS) {
+        out.push_str(&format!(
+            "| {} | {} | {} | {} | {} |\n",
+            format_date(run.timestamp),
+            run.file_count,
+            run.symbol_count,
+            run.link_count,
+            format_percent(run.coverage())
+        ));
+    }
+
+    out
+}
+
+fn sparkline(values: &[f64]) -> String {
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let span = max - min;
+
+    values
+        .iter()
+        .map(|value| {
+            if span <= f64::EPSILON {
+                return SPARK_CHARS[SPARK_CHARS.len() / 2];
+            }
+            let idx = ((value - min) / span * (SPARK_CHARS.len() - 1) as f64).round() as usize;
+            SPARK_CHARS[idx.min(SPARK_CHARS.len() - 1)]
+        })
+        .collect()
+}
+
+fn format_percent(ratio: f64) -> String {
+    format!("{:.1}%", ratio * 100.0)
+}
@@ -1,19 +1,468 @@
-use std::path::PathBuf;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
 
-use crate::{memory::FileMemory, source_indexer::SourceIndex};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+use crate::{
+    benchmarks::{BenchmarkDef, PerformanceNote},
+    concurrency::ConcurrencyFact,
+    config::PlainSightConfig,
+    env_vars::EnvVarRead,
+    error::Result,
+    error_catalog::ErrorType,
+    features::FeatureGate,
+    memory::{FileMemory, ProjectMemory, SymbolFingerprint},
+    module_tree::ModDeclaration,
+    ollama::{OllamaWrapper, Task},
+    openapi::{DetectedRoute, OpenApiSpec},
+    ownership::FileOwners,
+    project_manager::{
+        AnnotationStore, CheckpointedDocs, MetaCache, ProjectContext, RunCheckpoint, SkipEntry,
+    },
+    rustdoc::RustdocItem,
+    source_indexer::SourceIndex,
+    unsafe_code::UnsafeSite,
+};
+
+use super::{
+    backpressure::Backpressure, hooks::PipelineHook, progress::ProgressSink, renames::SymbolMove,
+    schedule::ModelSchedule,
+};
+
+pub(crate) use crate::project_manager::PromptProfile;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct ParsedFile {
+    /// Absolute; not saved with an ingest snapshot, as it is resolved against the project root
+    /// of the run that loads it.
+    #[serde(skip)]
     pub path: PathBuf,
     pub relative_path: String,
     pub language: String,
     pub hash: String,
     pub source_index: SourceIndex,
     pub memory: FileMemory,
+    pub mod_declarations: Vec<ModDeclaration>,
+    pub routes: Vec<DetectedRoute>,
+    pub env_reads: Vec<EnvVarRead>,
+    pub feature_gates: Vec<FeatureGate>,
+    pub error_types: Vec<ErrorType>,
+    pub concurrency: Vec<ConcurrencyFact>,
+    pub fingerprints: Vec<SymbolFingerprint>,
+    pub unsafe_sites: Vec<UnsafeSite>,
+    pub benchmarks: Vec<BenchmarkDef>,
+    /// Benchmarks elsewhere that exercise this file's symbols; filled once project memory
+    /// is built.
+    pub performance: Vec<PerformanceNote>,
+    /// Owners named in the file's leading comment block.
+    pub header_owners: Vec<String>,
+    /// Who to contact about the file; filled once package manifests are read.
+    pub owners: Option<FileOwners>,
+}
+
+/// A README, manifest or CI file included verbatim (up to a cap) in the project summary input.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct MetadataFile {
+    pub relative_path: String,
+    pub content: String,
+    pub truncated: bool,
+}
+
+/// Prompt profiles files needed on earlier runs, keyed by file then `<task>/<model>`, so
+/// known-problematic inputs start small instead of timing out on the standard context first.
+#[derive(Debug, Default)]
+pub(crate) struct ProfileHistory {
+    profiles: Mutex<BTreeMap<String, BTreeMap<String, PromptProfile>>>,
+}
+
+impl ProfileHistory {
+    /// Entries recorded for the files' current content; an edited file starts over.
+    pub fn from_meta(meta: &MetaCache, parsed_files: &[ParsedFile]) -> Self {
+        let profiles = parsed_files
+            .iter()
+            .filter_map(|parsed| {
+                let file = meta.files.get(&parsed.relative_path)?;
+                (file.hash == parsed.hash && !file.prompt_profiles.is_empty())
+                    .then(|| (parsed.relative_path.clone(), file.prompt_profiles.clone()))
+            })
+            .collect();
+        Self {
+            profiles: Mutex::new(profiles),
+        }
+    }
+
+    pub fn get(&self, relative_path: &str, key: &str) -> PromptProfile {
+        self.lock()
+            .get(relative_path)
+            .and_then(|profiles| profiles.get(key))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Remember that `profile` is what `relative_path` needs for `key`; `Standard` clears it.
+    pub fn record(&self, relative_path: &str, key: &str, profile: PromptProfile) {
+        let mut profiles = self.lock();
+        if profile == PromptProfile::Standard {
+            if let Some(file) = profiles.get_mut(relative_path) {
+                file.remove(key);
+            }
+        } else {
+            profiles
+                .entry(relative_path.to_string())
+                .or_default()
+                .insert(key.to_string(), profile);
+        }
+    }
+
+    pub fn for_file(&self, relative_path: &str) -> BTreeMap<String, PromptProfile> {
+        self.lock().get(relative_path).cloned().unwrap_or_default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<String, BTreeMap<String, PromptProfile>>> {
+        self.profiles.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Files whose docs pass finished during this run, so a cancelled run can record them in the
+/// meta cache and leave the rest for the next run.
+#[derive(Default)]
+pub(crate) struct FinishedFiles {
+    files: Mutex<BTreeSet<String>>,
+}
+
+impl FinishedFiles {
+    pub fn insert(&self, relative_path: &str) {
+        self.lock().insert(relative_path.to_string());
+    }
+
+    pub fn contains(&self, relative_path: &str) -> bool {
+        self.lock().contains(relative_path)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeSet<String>> {
+        self.files.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Files this run skipped for running over `OllamaConfig::file_timeout`, with the reason.
+#[derive(Default)]
+pub(crate) struct SkippedFiles {
+    files: Mutex<Vec<SkipEntry>>,
+}
+
+impl SkippedFiles {
+    pub fn insert(&self, entry: SkipEntry) {
+        self.lock().push(entry);
+    }
+
+    pub fn contains(&self, relative_path: &str) -> bool {
+        self.lock().iter().any(|entry| entry.path == relative_path)
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn entries(&self) -> Vec<SkipEntry> {
+        self.lock().clone()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<SkipEntry>> {
+        self.files.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Files whose summary or docs failed during this run, by path, with the first reason; see
+/// [`crate::config::ErrorPolicy`]. Includes the files in `SkippedFiles`.
+#[derive(Default)]
+pub(crate) struct FailedFiles {
+    files: Mutex<BTreeMap<String, SkipEntry>>,
+}
+
+impl FailedFiles {
+    /// Record `entry` unless its file already failed. Returns how many files failed so far.
+    pub fn insert(&self, entry: SkipEntry) -> usize {
+        let mut files = self.lock();
+        files.entry(entry.path.clone()).or_insert(entry);
+        files.len()
+    }
+
+    pub fn contains(&self, relative_path: &str) -> bool {
+        self.lock().contains_key(relative_path)
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn paths(&self) -> Vec<String> {
+        self.lock().keys().cloned().collect()
+    }
+
+    pub fn entries(&self) -> Vec<SkipEntry> {
+        self.lock().values().cloned().collect()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<String, SkipEntry>> {
+        self.files.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// This run's file outputs, saved to `.checkpoint.json` after each one so that an unfinished
+/// run can be resumed; see [`RunCheckpoint`].
+pub(crate) struct Checkpoint {
+    state: Mutex<RunCheckpoint>,
+}
+
+impl Checkpoint {
+    pub fn new(state: RunCheckpoint) -> Self {
+        Self {
+            state: Mutex::new(state),
+        }
+    }
+
+    /// Whether the file's summary for its current content is already written.
+    pub fn has_summary(&self, parsed: &ParsedFile) -> bool {
+        self.lock().summaries.get(&parsed.relative_path) == Some(&parsed.hash)
+    }
+
+    /// The open questions of the file's docs, when docs for its current content are already
+    /// written.
+    pub fn docs(&self, parsed: &ParsedFile) -> Option<Vec<String>> {
+        self.lock()
+            .docs
+            .get(&parsed.relative_path)
+            .filter(|docs| docs.hash == parsed.hash)
+            .map(|docs| docs.questions.clone())
+    }
+
+    pub fn summary_written(&self, project: &ProjectContext, parsed: &ParsedFile) -> Result<()> {
+        let mut state = self.lock();
+        state
+            .summaries
+            .insert(parsed.relative_path.clone(), parsed.hash.clone());
+        project.save_checkpoint(&state)
+    }
+
+    pub fn docs_written(
+        &self,
+        project: &ProjectContext,
+        parsed: &ParsedFile,
+        questions: &[String],
+    ) -> Result<()> {
+        let mut state = self.lock();
+        state.docs.insert(
+            parsed.relative_path.clone(),
+            CheckpointedDocs {
+                hash: parsed.hash.clone(),
+                questions: questions.to_vec(),
+            },
+        );
+        project.save_checkpoint(&state)
+    }
+
+    /// Clear the saved checkpoint once the run has finished and its outputs are in the meta
+    /// cache.
+    pub fn finish(&self, project: &ProjectContext) -> Result<()> {
+        if !project.store().exists(&project.checkpoint_path()) {
+            return Ok(());
+        }
+        project.save_checkpoint(&RunCheckpoint::default())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, RunCheckpoint> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// The requests one file's summary or docs are generated from.
+pub(crate) enum FileRequests {
+    Single(PromptProfile, String),
+    /// One input per line range, in file order.
+    Split(Vec<String>),
+}
+
+/// File requests built ahead of their turn while an earlier file's request is in flight, so
+/// packing and serializing the next prompt overlaps with waiting on the model.
+#[derive(Default)]
+pub(crate) struct PromptPrefetch {
+    state: Mutex<PrefetchState>,
+}
+
+#[derive(Default)]
+struct PrefetchState {
+    /// Files whose requests went out, by path and `Task::name`.
+    started: BTreeSet<(String, &'static str)>,
+    ready: BTreeMap<(String, &'static str), Prefetched>,
+    stats: PrefetchStats,
+}
+
+struct Prefetched {
+    start: PromptProfile,
+    requests: FileRequests,
+    build_time: Duration,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub(crate) struct PrefetchStats {
+    /// Files whose requests were built ahead of their turn.
+    pub prepared: usize,
+    /// Prepared requests that were sent as built.
+    pub used: usize,
+    /// Time the used requests took to build, spent while other requests were in flight
+    /// instead of before their own.
+    pub overlapped_ms: u64,
+}
+
+impl PromptPrefetch {
+    /// Whether `relative_path`'s `task` requests are neither sent nor prepared yet.
+    pub fn wants(&self, relative_path: &str, task: Task) -> bool {
+        let key = (relative_path.to_string(), task.name());
+        let state = self.lock();
+        !state.started.contains(&key) && !state.ready.contains_key(&key)
+    }
+
+    pub fn insert(
+        &self,
+        relative_path: &str,
+        task: Task,
+        start: PromptProfile,
+        requests: FileRequests,
+        build_time: Duration,
+    ) {
+        let key = (relative_path.to_string(), task.name());
+        let mut state = self.lock();
+        if state.started.contains(&key) {
+            return;
+        }
+        state.stats.prepared += 1;
+        state.ready.insert(
+            key,
+            Prefetched {
+                start,
+                requests,
+                build_time,
+            },
+        );
+    }
+
+    /// Mark `relative_path`'s `task` requests as sent and hand over the prepared ones, when
+    /// they were built starting at `start`.
+    pub fn take(
+        &self,
+        relative_path: &str,
+        task: Task,
+        start: PromptProfile,
+    ) -> Option<FileRequests> {
+        let key = (relative_path.to_string(), task.name());
+        let mut state = self.lock();
+        state.started.insert(key.clone());
+        let prefetched = state.ready.remove(&key)?;
+        if prefetched.start != start {
+            return None;
+        }
+        state.stats.used += 1;
+        state.stats.overlapped_ms +=
+            u64::try_from(prefetched.build_time.as_millis()).unwrap_or(u64::MAX);
+        Some(prefetched.requests)
+    }
+
+    pub fn stats(&self) -> PrefetchStats {
+        self.lock().stats
+    }
+
+    fn lock(&self) -> MutexGuard<'_, PrefetchState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// One line range of a file too large for a single request.
+#[derive(Debug, Clone)]
+pub(crate) struct FilePart {
+    /// 1-based.
+    pub index: usize,
+    pub count: usize,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub chunk_ids: Vec<usize>,
+}
+
+impl GenerationContext<'_> {
+    /// Whether the `task` output (`Task::Summarize` or `Task::Documentation`) of the file at
+    /// `relative_path` is generated this run instead of reused.
+    pub fn regenerates(&self, relative_path: &str, task: Task) -> bool {
+        let mode = self.config.mode;
+        let in_mode = match task {
+            Task::Summarize => mode.summaries(),
+            _ => mode.docs(),
+        };
+        if !in_mode || !self.files_to_regenerate.contains(relative_path) {
+            return false;
+        }
+        if !self.forced_only.contains(relative_path) {
+            return true;
+        }
+        match task {
+            Task::Summarize => self.config.force.summaries,
+            _ => self.config.force.docs,
+        }
+    }
+}
+
+impl FilePart {
+    pub fn contains_line(&self, line: usize) -> bool {
+        (self.start_line..=self.end_line).contains(&line)
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
-pub(crate) enum PromptProfile {
-    Standard,
-    Compact,
+#[derive(Clone, Copy)]
+pub(crate) struct GenerationContext<'a> {
+    pub config: &'a PlainSightConfig,
+    pub wrapper: &'a OllamaWrapper,
+    pub project: &'a ProjectContext,
+    pub project_name: &'a str,
+    pub parsed_files: &'a [ParsedFile],
+    /// Indices into `parsed_files` in the order `PlainSightConfig::generation_order` generates
+    /// them in; outputs are still collected in `parsed_files` order.
+    pub generation_order: &'a [usize],
+    pub project_memory: &'a ProjectMemory,
+    pub memory_file_path: &'a Path,
+    pub source_index_file_path: &'a Path,
+    pub files_to_regenerate: &'a BTreeSet<String>,
+    /// Files in `files_to_regenerate` only because `PlainSightConfig::force` forces their
+    /// summaries or docs; the output that is not forced is reused.
+    pub forced_only: &'a BTreeSet<String>,
+    /// Write the project summary and architecture; false when nothing changed since they were
+    /// and `ForceConfig::project_level` is not set.
+    pub regenerate_project_docs: bool,
+    pub annotations: &'a AnnotationStore,
+    pub api_specs: &'a [OpenApiSpec],
+    pub project_metadata: &'a [MetadataFile],
+    pub symbol_moves: &'a [SymbolMove],
+    /// Rustdoc's public items by file; empty unless `RustdocMode::Auto` succeeded.
+    pub rustdoc_items: &'a BTreeMap<String, Vec<RustdocItem>>,
+    pub profile_history: &'a ProfileHistory,
+    pub prompt_prefetch: &'a PromptPrefetch,
+    pub finished_files: &'a FinishedFiles,
+    /// Entries of the skip list whose files this run would otherwise have regenerated; they
+    /// are also in `stale_left_out`.
+    pub skip_listed: &'a [SkipEntry],
+    /// Files skipped during the run for running over `OllamaConfig::file_timeout`.
+    pub skipped_files: &'a SkippedFiles,
+    /// Files whose summary or docs failed during the run; their `.meta.json` entries are kept
+    /// as they were so that the next run regenerates them.
+    pub failed_files: &'a FailedFiles,
+    pub backpressure: &'a Backpressure,
+    pub checkpoint: &'a Checkpoint,
+    /// Stale files that `PlainSightConfig::changed_since` or `paths` left out, and files on the
+    /// skip list; their `.meta.json` entries are kept as they were so that the next run
+    /// regenerates them.
+    pub stale_left_out: &'a BTreeSet<String>,
+    pub progress: &'a dyn ProgressSink,
+    pub hooks: &'a [Arc<dyn PipelineHook>],
+    pub schedule: ModelSchedule,
 }
@@ -0,0 +1,87 @@
+use std::collections::BTreeMap;
+
+use tracing::info;
+
+use crate::{
+    error::{PlainSightError, Result},
+    page_split,
+};
+
+use super::{review, types::GenerationContext};
+
+/// Check every file's `docs.md` against the symbols the file defines and imports and the
+/// project's symbols, and write the code references that match none of them to
+/// `validation.md`. Files without docs are left out.
+pub(super) fn write_validation(ctx: &GenerationContext<'_>) -> Result<()> {
+    let GenerationContext {
+        project,
+        project_name,
+        parsed_files,
+        ..
+    } = *ctx;
+    let mut checked = 0usize;
+    let mut findings = BTreeMap::new();
+    for parsed in parsed_files {
+        let docs = project
+            .file_docs_path(&parsed.path)
+            .ok()
+            .and_then(|path| page_split::read_page(project.store(), &path).ok());
+        let Some(docs) = docs else {
+            continue;
+        };
+        checked += 1;
+        let unverified = review::unverified_symbols(ctx, parsed, &docs);
+        if !unverified.is_empty() {
+            findings.insert(parsed.relative_path.as_str(), unverified);
+        }
+    }
+
+    let content = render_validation(project_name, checked, &findings);
+    let validation_path = project.validation_path();
+    project
+        .store()
+        .write(&validation_path, content.as_bytes())
+        .map_err(|e| {
+            PlainSightError::io(
+                format!("writing validation '{}'", validation_path.display()),
+                e,
+            )
+        })?;
+    info!(
+        checked_files = checked,
+        flagged_files = findings.len(),
+        unverified = findings.values().map(Vec::len).sum::<usize>(),
+        validation_path = %validation_path.display(),
+        "validation_written"
+    );
+    Ok(())
+}
+
+fn render_validation(
+    project_name: &str,
+    checked: usize,
+    findings: &BTreeMap<&str, Vec<String>>,
+) -> String {
+    let mut out = format!("# {project_name} Docs Validation\n\n");
+    if findings.is_empty() {
+        out.push_str(&format!(
+            "Every code reference in the docs of {checked} file(s) names a symbol the file \
+             defines or imports, or a symbol of the project.\n"
+        ));
+        return out;
+    }
+
+    out.push_str(&format!(
+        "{} of {checked} file(s) have docs that mention code the file does not define or import \
+         and the project has no symbol for. Check these against the source; they may be \
+         invented.\n",
+        findings.len()
+    ));
+    for (path, unverified) in findings {
+        out.push_str(&format!("\n## `{path}`\n\n"));
+        for reference in unverified {
+            out.push_str(&format!("- `{reference}`\n"));
+        }
+    }
+    out
+}
@@ -0,0 +1,613 @@
+//! Sub-projects of a workspace, documented one by one with `PlainSight::run_workspace`.
+//!
+//! Members are read from the workspace root's manifests: `[workspace] members` of a
+//! `Cargo.toml`, `packages` of a `pnpm-workspace.yaml` or `workspaces` of a `package.json`, and
+//! `<modules>` of a Maven `pom.xml`. Each member is documented as its own project under the
+//! docs root, and `workspace.md` at the docs root links them together.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write as _,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use serde_json::Value;
+use tracing::{info, warn};
+
+use crate::{
+    config::PlainSightConfig,
+    error::{PlainSightError, Result},
+    ollama::LlmBackend,
+    page_split,
+    project_manager::ProjectManager,
+    symbol_search::glob_matches,
+    workflow::{self, RunControl},
+};
+
+/// Manifest a member was declared in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkspaceKind {
+    Cargo,
+    Pnpm,
+    /// `workspaces` of a `package.json`, as read by Yarn and npm.
+    Yarn,
+    Maven,
+}
+
+impl WorkspaceKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Cargo => "Cargo",
+            Self::Pnpm => "pnpm",
+            Self::Yarn => "Yarn",
+            Self::Maven => "Maven",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WorkspaceMember {
+    /// Name in the member's manifest: the crate, package or Maven artifact id.
+    pub package_name: String,
+    /// Project name under the docs root, made from the package name as project names are
+    /// inferred from directory names, or from the member's path when two members share a
+    /// package name.
+    pub project_name: String,
+    /// Relative to the workspace root, `/`-separated; empty for the root itself.
+    pub path: String,
+    pub kind: WorkspaceKind,
+    /// Package names of the other members this one depends on.
+    pub depends_on: Vec<String>,
+}
+
+/// The members declared by the manifests at `workspace_root`, sorted by path. Globs in member
+/// lists are expanded; matches without the kind's manifest are left out.
+pub fn detect_members(workspace_root: &Path) -> Result<Vec<WorkspaceMember>> {
+    let mut found = Vec::new();
+    found.extend(cargo_members(workspace_root)?);
+    match pnpm_members(workspace_root)? {
+        Some(members) => found.extend(members),
+        None => found.extend(package_json_members(workspace_root)?),
+    }
+    found.extend(maven_members(workspace_root, Path::new(""))?);
+    // A directory declared by several manifests is documented once, as its first kind.
+    let mut paths = BTreeSet::new();
+    found.retain(|member| paths.insert(member.path.clone()));
+
+    let package_names = found
+        .iter()
+        .map(|member| member.package_name.clone())
+        .collect::<BTreeSet<_>>();
+    let mut name_counts = BTreeMap::new();
+    for member in &found {
+        *name_counts.entry(member.package_name.clone()).or_insert(0) += 1;
+    }
+    let mut members = found
+        .into_iter()
+        .map(|member| {
+            let project_name = match member.path.as_str() {
+                path if !path.is_empty() && name_counts[&member.package_name] > 1 => {
+                    project_name_for(path)
+                }
+                _ => project_name_for(&member.package_name),
+            };
+            let depends_on = member
+                .dependencies
+                .into_iter()
+                .filter(|name| *name != member.package_name && package_names.contains(name))
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect();
+            WorkspaceMember {
+                project_name,
+                package_name: member.package_name,
+                path: member.path,
+                kind: member.kind,
+                depends_on,
+            }
+        })
+        .collect::<Vec<_>>();
+    members.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(members)
+}
+
+/// Document every member of the workspace at `workspace_root` as its own project, then write
+/// `workspace.md`. A member's walk leaves out the members nested inside it, so a root crate
+/// does not document its workspace's other crates a second time.
+pub(crate) async fn run_workspace(
+    manager: &ProjectManager,
+    config: &PlainSightConfig,
+    workspace_root: &Path,
+    backend: Option<&Arc<dyn LlmBackend>>,
+    control: RunControl<'_>,
+) -> Result<()> {
+    let mut members = detect_members(workspace_root)?;
+    if members.is_empty() {
+        return Err(PlainSightError::InvalidState(format!(
+            "no workspace members found in '{}'",
+            workspace_root.display()
+        )));
+    }
+    info!(
+        workspace_root = %workspace_root.display(),
+        member_count = members.len(),
+        "workspace_members_detected"
+    );
+
+    let member_paths = members
+        .iter()
+        .map(|member| member.path.clone())
+        .collect::<Vec<_>>();
+    for member in &mut members {
+        let member_root = workspace_root.join(&member.path);
+        member.project_name =
+            manager.bind_project_name(&member.project_name, &member_root, config.name_collision)?;
+        let mut member_config = config.clone();
+        member_config.source_discovery.exclude_paths.extend(
+            member_paths
+                .iter()
+                .filter(|path| **path != member.path && contains(&member.path, path))
+                .map(|path| workspace_root.join(path)),
+        );
+        info!(
+            project = %member.project_name,
+            path = %member.path,
+            kind = member.kind.name(),
+            "workspace_member_start"
+        );
+        workflow::run_with_manager(
+            manager,
+            &member_config,
+            &member.project_name,
+            &member_root,
+            None,
+            backend,
+            control,
+        )
+        .await?;
+    }
+    write_workspace_page(manager, workspace_root, &members)
+}
+
+/// Write `workspace.md` at the docs root: each member with links to its summary and
+/// architecture, the members it depends on and the opening paragraph of its summary.
+pub fn write_workspace_page(
+    manager: &ProjectManager,
+    workspace_root: &Path,
+    members: &[WorkspaceMember],
+) -> Result<()> {
+    let workspace_name = workspace_root
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| workspace_root.display().to_string());
+    let mut page = format!("# Workspace `{workspace_name}`\n\n");
+    let _ = writeln!(
+        page,
+        "{} projects documented from `{}`.\n",
+        members.len(),
+        workspace_root.display()
+    );
+    page.push_str("| Project | Path | Kind | Depends on |\n|---|---|---|---|\n");
+    for member in members {
+        let _ = writeln!(
+            page,
+            "| [{}](#{}) | `{}` | {} | {} |",
+            member.package_name,
+            page_split::anchor_slug(&member.package_name),
+            display_path(&member.path),
+            member.kind.name(),
+            code_list(&member.depends_on)
+        );
+    }
+
+    for member in members {
+        let project = manager.new_project(member.project_name.as_str(), PathBuf::new());
+        let _ = writeln!(page, "\n## {}\n", member.package_name);
+        let _ = writeln!(
+            page,
+            "[Summary]({name}/summary.md) · [Architecture]({name}/architecture.md) · `{path}` ({kind})\n",
+            name = member.project_name,
+            path = display_path(&member.path),
+            kind = member.kind.name()
+        );
+        if !member.depends_on.is_empty() {
+            let _ = writeln!(page, "Depends on {}.\n", code_list(&member.depends_on));
+        }
+        let lead = page_split::read_page(project.store(), &project.summary_path())
+            .ok()
+            .and_then(|summary| lead_paragraph(&summary));
+        page.push_str(lead.as_deref().unwrap_or("_No summary yet._"));
+        page.push('\n');
+    }
+
+    let page_path = manager.workspace_path();
+    manager
+        .store()
+        .write(&page_path, page.as_bytes())
+        .map_err(|e| {
+            PlainSightError::io(
+                format!("writing workspace page '{}'", page_path.display()),
+                e,
+            )
+        })?;
+    info!(
+        member_count = members.len(),
+        workspace_page = %page_path.display(),
+        "workspace_page_written"
+    );
+    Ok(())
+}
+
+/// A member as read from its manifest, before names are settled.
+struct FoundMember {
+    package_name: String,
+    path: String,
+    kind: WorkspaceKind,
+    dependencies: Vec<String>,
+}
+
+fn cargo_members(workspace_root: &Path) -> Result<Vec<FoundMember>> {
+    let Some(manifest) = read_toml(&workspace_root.join("Cargo.toml"))? else {
+        return Ok(Vec::new());
+    };
+    let Some(workspace) = manifest.get("workspace").and_then(toml::Value::as_table) else {
+        return Ok(Vec::new());
+    };
+    let strings = |key: &str| {
+        workspace
+            .get(key)
+            .and_then(toml::Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(toml::Value::as_str)
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    };
+    let excluded = expand_all(workspace_root, &strings("exclude"));
+    let mut members = Vec::new();
+    for path in expand_all(workspace_root, &strings("members")).difference(&excluded) {
+        let manifest_path = workspace_root.join(path).join("Cargo.toml");
+        let Some(manifest) = read_toml(&manifest_path)? else {
+            warn!(member = %path, "workspace_member_without_manifest");
+            continue;
+        };
+        let Some(package_name) = manifest
+            .get("package")
+            .and_then(|package| package.get("name"))
+            .and_then(toml::Value::as_str)
+        else {
+            continue;
+        };
+        let dependencies = ["dependencies", "dev-dependencies", "build-dependencies"]
+            .into_iter()
+            .filter_map(|section| manifest.get(section).and_then(toml::Value::as_table))
+            .flatten()
+            .map(|(key, dependency)| {
+                dependency
+                    .get("package")
+                    .and_then(toml::Value::as_str)
+                    .unwrap_or(key)
+                    .to_string()
+            })
+            .collect();
+        members.push(FoundMember {
+            package_name: package_name.to_string(),
+            path: path.clone(),
+            kind: WorkspaceKind::Cargo,
+            dependencies,
+        });
+    }
+    Ok(members)
+}
+
+/// Members of a `pnpm-workspace.yaml`, or `None` without one. `!` patterns exclude.
+fn pnpm_members(workspace_root: &Path) -> Result<Option<Vec<FoundMember>>> {
+    let manifest_path = workspace_root.join("pnpm-workspace.yaml");
+    let Some(content) = read_optional(&manifest_path)? else {
+        return Ok(None);
+    };
+    let manifest: serde_yaml::Value = serde_yaml::from_str(&content).map_err(|e| {
+        PlainSightError::InvalidState(format!("parsing '{}': {e}", manifest_path.display()))
+    })?;
+    let patterns = manifest
+        .get("packages")
+        .and_then(serde_yaml::Value::as_sequence)
+        .into_iter()
+        .flatten()
+        .filter_map(serde_yaml::Value::as_str)
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    node_members(workspace_root, &patterns, WorkspaceKind::Pnpm).map(Some)
+}
+
+fn package_json_members(workspace_root: &Path) -> Result<Vec<FoundMember>> {
+    let Some(manifest) = read_json(&workspace_root.join("package.json"))? else {
+        return Ok(Vec::new());
+    };
+    // Yarn also takes `{ "packages": [...] }`.
+    let workspaces = match manifest.get("workspaces") {
+        Some(Value::Object(workspaces)) => workspaces.get("packages"),
+        workspaces => workspaces,
+    };
+    let patterns = workspaces
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    node_members(workspace_root, &patterns, WorkspaceKind::Yarn)
+}
+
+fn node_members(
+    workspace_root: &Path,
+    patterns: &[String],
+    kind: WorkspaceKind,
+) -> Result<Vec<FoundMember>> {
+    let (excludes, includes): (Vec<_>, Vec<_>) = patterns
+        .iter()
+        .cloned()
+        .partition(|pattern| pattern.starts_with('!'));
+    let excludes = excludes
+        .iter()
+        .map(|pattern| pattern.trim_start_matches('!').to_string())
+        .collect::<Vec<_>>();
+    let excluded = expand_all(workspace_root, &excludes);
+    let mut members = Vec::new();
+    for path in expand_all(workspace_root, &includes).difference(&excluded) {
+        let Some(manifest) = read_json(&workspace_root.join(path).join("package.json"))? else {
+            continue;
+        };
+        let Some(package_name) = manifest.get("name").and_then(Value::as_str) else {
+            continue;
+        };
+        let dependencies = ["dependencies", "devDependencies", "peerDependencies"]
+            .into_iter()
+            .filter_map(|section| manifest.get(section).and_then(Value::as_object))
+            .flat_map(|section| section.keys().cloned())
+            .collect();
+        members.push(FoundMember {
+            package_name: package_name.to_string(),
+            path: path.clone(),
+            kind,
+            dependencies,
+        });
+    }
+    Ok(members)
+}
+
+/// The modules of the `pom.xml` in `dir`. A module that aggregates modules of its own is
+/// replaced by them.
+fn maven_members(workspace_root: &Path, dir: &Path) -> Result<Vec<FoundMember>> {
+    let Some(pom) = read_optional(&workspace_root.join(dir).join("pom.xml"))? else {
+        return Ok(Vec::new());
+    };
+    let pom = strip_xml_comments(&pom);
+    let modules = xml_block(&pom, "modules")
+        .map(|modules| xml_texts(modules, "module"))
+        .unwrap_or_default();
+    let mut members = Vec::new();
+    for module in modules {
+        let module_dir = dir.join(module);
+        let Some(module_pom) = read_optional(&workspace_root.join(&module_dir).join("pom.xml"))?
+        else {
+            warn!(member = %module_dir.display(), "workspace_member_without_manifest");
+            continue;
+        };
+        let module_pom = strip_xml_comments(&module_pom);
+        if xml_block(&module_pom, "modules").is_some() {
+            members.extend(maven_members(workspace_root, &module_dir)?);
+            continue;
+        }
+        let own = match xml_block(&module_pom, "parent") {
+            Some(parent) => module_pom.replacen(parent, "", 1),
+            None => module_pom.clone(),
+        };
+        let Some(package_name) = xml_texts(&own, "artifactId")
+            .first()
+            .map(|id| id.to_string())
+        else {
+            continue;
+        };
+        let dependencies = xml_block(&own, "dependencies")
+            .map(|dependencies| xml_texts(dependencies, "artifactId"))
+            .unwrap_or_default()
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        members.push(FoundMember {
+            package_name,
+            path: relative_string(&module_dir),
+            kind: WorkspaceKind::Maven,
+            dependencies,
+        });
+    }
+    Ok(members)
+}
+
+/// Directories under `root` matching any of `patterns`, relative and `/`-separated.
+fn expand_all(root: &Path, patterns: &[String]) -> BTreeSet<String> {
+    let mut found = BTreeSet::new();
+    for pattern in patterns {
+        let segments = pattern
+            .split('/')
+            .filter(|segment| !segment.is_empty() && *segment != ".")
+            .collect::<Vec<_>>();
+        expand(root, Path::new(""), &segments, &mut found);
+    }
+    found
+}
+
+fn expand(root: &Path, relative: &Path, segments: &[&str], found: &mut BTreeSet<String>) {
+    match segments.split_first() {
+        None => {
+            if root.join(relative).is_dir() {
+                found.insert(relative_string(relative));
+            }
+        }
+        Some((&"**", rest)) => {
+            expand(root, relative, rest, found);
+            for name in subdirectories(&root.join(relative)) {
+                expand(root, &relative.join(name), segments, found);
+            }
+        }
+        Some((segment, rest)) if segment.contains(['*', '?']) => {
+            for name in subdirectories(&root.join(relative)) {
+                if glob_matches(segment, &name) {
+                    expand(root, &relative.join(name), rest, found);
+                }
+            }
+        }
+        Some((segment, rest)) => expand(root, &relative.join(segment), rest, found),
+    }
+}
+
+/// Names of the directories in `dir`, without hidden ones and dependency or build outputs.
+fn subdirectories(dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut names = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+        .filter(|name| !name.starts_with('.') && name != "node_modules" && name != "target")
+        .collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+fn read_optional(path: &Path) -> Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(PlainSightError::io(
+            format!("reading manifest '{}'", path.display()),
+            e,
+        )),
+    }
+}
+
+fn read_toml(path: &Path) -> Result<Option<toml::Table>> {
+    read_optional(path)?
+        .map(|content| {
+            toml::from_str(&content).map_err(|e| {
+                PlainSightError::InvalidState(format!("parsing '{}': {e}", path.display()))
+            })
+        })
+        .transpose()
+}
+
+fn read_json(path: &Path) -> Result<Option<Value>> {
+    read_optional(path)?
+        .map(|content| {
+            serde_json::from_str(&content).map_err(|e| {
+                PlainSightError::InvalidState(format!("parsing '{}': {e}", path.display()))
+            })
+        })
+        .transpose()
+}
+
+fn strip_xml_comments(xml: &str) -> String {
+    let mut stripped = String::with_capacity(xml.len());
+    let mut rest = xml;
+    while let Some(start) = rest.find("<!--") {
+        stripped.push_str(&rest[..start]);
+        rest = rest[start..]
+            .find("-->")
+            .map_or("", |end| &rest[start + end + 3..]);
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+/// The first `<tag>` element of `xml`, tags included.
+fn xml_block<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    let start = xml.find(&format!("<{tag}>"))?;
+    let close = format!("</{tag}>");
+    let end = xml[start..].find(&close)? + start + close.len();
+    Some(&xml[start..end])
+}
+
+/// The trimmed text of every `<tag>` element in `xml`.
+fn xml_texts<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let open = format!("<{tag}>");
+    let close = format!("</{tag}>");
+    let mut texts = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        rest = &rest[start + open.len()..];
+        let Some(end) = rest.find(&close) else {
+            break;
+        };
+        texts.push(rest[..end].trim());
+        rest = &rest[end + close.len()..];
+    }
+    texts
+}
+
+/// The first paragraph of a generated page, after its front-matter, headings and notices.
+fn lead_paragraph(page: &str) -> Option<String> {
+    let body = page
+        .strip_prefix("---\n")
+        .and_then(|rest| rest.split_once("\n---\n"))
+        .map_or(page, |(_, body)| body);
+    let mut paragraph = Vec::new();
+    for line in body.lines().map(str::trim) {
+        if line.is_empty() {
+            if !paragraph.is_empty() {
+                break;
+            }
+            continue;
+        }
+        if line.starts_with('#') || line.starts_with('>') {
+            if !paragraph.is_empty() {
+                break;
+            }
+            continue;
+        }
+        paragraph.push(line);
+    }
+    (!paragraph.is_empty()).then(|| paragraph.join(" "))
+}
+
+/// `name` with `-` replaced by `_` as in inferred project names, and `/` and the other
+/// characters a directory name should not hold (as in `@scope/package`) dropped or replaced.
+fn project_name_for(name: &str) -> String {
+    name.trim_start_matches('@')
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Whether the member at `inner` lies inside the one at `outer`.
+fn contains(outer: &str, inner: &str) -> bool {
+    outer.is_empty() || inner.starts_with(&format!("{outer}/"))
+}
+
+fn relative_string(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn display_path(path: &str) -> &str {
+    if path.is_empty() { "." } else { path }
+}
+
+fn code_list(names: &[String]) -> String {
+    names
+        .iter()
+        .map(|name| format!("`{name}`"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}